@@ -20,6 +20,8 @@ public class MainActivity extends GameActivity {
         System.loadLibrary("main");
     }
 
+    private OrientationSensor orientationSensor;
+
     private void hideSystemUI() {
         // This will put the game behind any cutouts and waterfalls on devices which have
         // them, so the corresponding insets will be non-zero.
@@ -42,6 +44,10 @@ public class MainActivity extends GameActivity {
             int fd = -1;
 
             try {
+                // The picker was cancelled.
+                if (resultCode != RESULT_OK || data == null) {
+                    throw new IOException("No file picked");
+                }
                 Uri uri = data.getData();
 
                 if (uri == null) {
@@ -64,6 +70,8 @@ public class MainActivity extends GameActivity {
 
     @Override
     protected void onCreate(Bundle savedInstanceState) {
+        // Create the sensor before the app starts, so it can ask whether there is one.
+        orientationSensor = new OrientationSensor(this);
         super.onCreate(savedInstanceState);
 
         // Keep the screen on
@@ -74,5 +82,18 @@ public class MainActivity extends GameActivity {
         WindowCompat.setDecorFitsSystemWindows(getWindow(), false);
         hideSystemUI();
         FilePicker.Register(this);
+        ShareSheet.Register(this);
+    }
+
+    @Override
+    protected void onResume() {
+        super.onResume();
+        orientationSensor.resume();
+    }
+
+    @Override
+    protected void onPause() {
+        orientationSensor.pause();
+        super.onPause();
     }
 }
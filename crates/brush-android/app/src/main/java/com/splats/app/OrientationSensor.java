@@ -0,0 +1,83 @@
+package com.splats.app;
+
+import android.content.Context;
+import android.hardware.Sensor;
+import android.hardware.SensorEvent;
+import android.hardware.SensorEventListener;
+import android.hardware.SensorManager;
+
+// Passes the orientation of the device on to the app, to look around by moving the phone.
+//
+// Uses the game rotation vector, which doesn't need a magnetometer or ARCore, and doesn't
+// jump around when the compass heading is corrected.
+//
+// The sensor only runs while the app has it enabled and the activity is in the foreground.
+public class OrientationSensor implements SensorEventListener {
+    private static OrientationSensor instance;
+
+    private final SensorManager sensorManager;
+    private final Sensor sensor;
+    private final float[] quaternion = new float[4];
+    private boolean enabled = false;
+    private boolean resumed = false;
+    private boolean running = false;
+
+    private static native void onOrientation(float x, float y, float z, float w);
+
+    public OrientationSensor(Context context) {
+        sensorManager = (SensorManager) context.getSystemService(Context.SENSOR_SERVICE);
+        sensor = sensorManager == null
+                ? null
+                : sensorManager.getDefaultSensor(Sensor.TYPE_GAME_ROTATION_VECTOR);
+        instance = this;
+    }
+
+    // Called from the app to know whether to offer looking around.
+    public static boolean isAvailable() {
+        return instance != null && instance.sensor != null;
+    }
+
+    // Called from the app when looking around is turned on or off.
+    public static void setEnabled(boolean enabled) {
+        if (instance != null) {
+            synchronized (instance) {
+                instance.enabled = enabled;
+                instance.update();
+            }
+        }
+    }
+
+    public synchronized void resume() {
+        resumed = true;
+        update();
+    }
+
+    public synchronized void pause() {
+        resumed = false;
+        update();
+    }
+
+    private void update() {
+        boolean run = sensor != null && enabled && resumed;
+        if (run == running) {
+            return;
+        }
+        running = run;
+        if (run) {
+            sensorManager.registerListener(this, sensor, SensorManager.SENSOR_DELAY_GAME);
+        } else {
+            sensorManager.unregisterListener(this);
+        }
+    }
+
+    @Override
+    public void onSensorChanged(SensorEvent event) {
+        // Ordered as w, x, y, z.
+        SensorManager.getQuaternionFromVector(quaternion, event.values);
+        onOrientation(quaternion[1], quaternion[2], quaternion[3], quaternion[0]);
+    }
+
+    @Override
+    public void onAccuracyChanged(Sensor sensor, int accuracy) {
+    }
+}
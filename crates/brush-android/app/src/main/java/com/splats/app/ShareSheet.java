@@ -0,0 +1,44 @@
+package com.splats.app;
+
+import android.annotation.SuppressLint;
+import android.app.Activity;
+import android.content.Intent;
+import android.net.Uri;
+
+import androidx.core.content.FileProvider;
+
+import java.io.File;
+import java.io.FileOutputStream;
+import java.io.IOException;
+
+// Shares exported files with other apps through the share sheet of the system.
+//
+// The file is written to the cache of the app first, and shared through a FileProvider, so the
+// other app can read it without any storage permissions.
+public class ShareSheet {
+    @SuppressLint("StaticFieldLeak")
+    private static Activity _activity;
+
+    public static void Register(Activity activity) {
+        _activity = activity;
+    }
+
+    public static void share(String name, byte[] data) throws IOException {
+        File dir = new File(_activity.getCacheDir(), "shared");
+        if (!dir.isDirectory() && !dir.mkdirs()) {
+            throw new IOException("Failed to create " + dir);
+        }
+        File file = new File(dir, name);
+        try (FileOutputStream out = new FileOutputStream(file)) {
+            out.write(data);
+        }
+
+        Uri uri = FileProvider.getUriForFile(
+                _activity, _activity.getPackageName() + ".fileprovider", file);
+        Intent intent = new Intent(Intent.ACTION_SEND);
+        intent.setType("application/octet-stream");
+        intent.putExtra(Intent.EXTRA_STREAM, uri);
+        intent.addFlags(Intent.FLAG_GRANT_READ_URI_PERMISSION);
+        _activity.startActivity(Intent.createChooser(intent, null));
+    }
+}
@@ -1,17 +1,76 @@
 #![cfg(target_os = "android")]
 
-use jni::sys::{JNI_VERSION_1_6, jint};
+use brush_app::AppContext;
+use jni::JNIEnv;
+use jni::objects::{GlobalRef, JClass, JValue};
+use jni::sys::{JNI_VERSION_1_6, jfloat, jint};
 use std::os::raw::c_void;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, RwLock};
+
+// Set once the app is created, so sensor updates can be passed on to it.
+static APP_CONTEXT: OnceLock<Arc<RwLock<AppContext>>> = OnceLock::new();
+
+// Classes can only be found from the main thread, so the sensor class is looked up on load.
+static VM: OnceLock<Arc<jni::JavaVM>> = OnceLock::new();
+static SENSOR_CLASS: OnceLock<GlobalRef> = OnceLock::new();
 
 #[allow(non_snake_case)]
 #[no_mangle]
 pub extern "system" fn JNI_OnLoad(vm: jni::JavaVM, _: *mut c_void) -> jint {
     let vm_ref = Arc::new(vm);
+    if let Ok(mut env) = vm_ref.get_env() {
+        if let Ok(class) = env.find_class("com/splats/app/OrientationSensor") {
+            if let Ok(class) = env.new_global_ref(class) {
+                let _ = SENSOR_CLASS.set(class);
+            }
+        }
+    }
+    let _ = VM.set(vm_ref.clone());
     rrfd::android::jni_initialize(vm_ref);
     JNI_VERSION_1_6
 }
 
+/// Call a static method of `OrientationSensor.java`, returning the result of boolean methods.
+fn call_sensor(method: &str, sig: &str, args: &[JValue]) -> jni::errors::Result<bool> {
+    let (Some(vm), Some(class)) = (VM.get(), SENSOR_CLASS.get()) else {
+        return Ok(false);
+    };
+    let mut env = vm.attach_current_thread()?;
+    let result = env.call_static_method(<&JClass>::from(class.as_obj()), method, sig, args);
+    if result.is_err() && env.exception_check()? {
+        env.exception_clear()?;
+    }
+    // Void methods return nothing, which counts as done.
+    Ok(result?.z().unwrap_or(true))
+}
+
+/// Whether the device has a sensor to look around with.
+fn has_orientation_sensor() -> bool {
+    call_sensor("isAvailable", "()Z", &[]).unwrap_or(false)
+}
+
+/// Only run the sensor while looking around, so it doesn't drain the battery otherwise.
+fn set_orientation_sensor(enabled: bool) {
+    if let Err(err) = call_sensor("setEnabled", "(Z)V", &[JValue::from(enabled)]) {
+        log::warn!("Failed to turn the orientation sensor on or off: {err}");
+    }
+}
+
+/// Called by `OrientationSensor.java` with the rotation of the device as a quaternion.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_com_splats_app_OrientationSensor_onOrientation(
+    _env: JNIEnv,
+    _class: JClass,
+    x: jfloat,
+    y: jfloat,
+    z: jfloat,
+    w: jfloat,
+) {
+    if let Some(Ok(mut context)) = APP_CONTEXT.get().map(|c| c.write()) {
+        context.set_device_orientation(glam::Quat::from_xyzw(x, y, z, w).normalize());
+    }
+}
+
 #[no_mangle]
 fn android_main(app: winit::platform::android::activity::AndroidApp) {
     use winit::platform::android::EventLoopBuilderExtAndroid;
@@ -22,14 +81,23 @@ fn android_main(app: winit::platform::android::activity::AndroidApp) {
         .build()
         .unwrap();
 
-    // Unused.
-    #[allow(unused)]
     let (send, rec) = tokio::sync::oneshot::channel();
+    runtime.spawn(async move {
+        if let Ok(created) = rec.await {
+            if has_orientation_sensor() {
+                if let Ok(mut context) = created.context.write() {
+                    context.orientation_sensor = Some(Box::new(set_orientation_sensor));
+                }
+            }
+            let _ = APP_CONTEXT.set(created.context);
+        }
+    });
 
     runtime.block_on(async {
-        android_logger::init_once(
+        let logger = android_logger::AndroidLogger::new(
             android_logger::Config::default().with_max_level(log::LevelFilter::Info),
         );
+        brush_app::debug_bundle::init_logger(Box::new(logger), log::LevelFilter::Info);
 
         eframe::run_native(
             "Brush",
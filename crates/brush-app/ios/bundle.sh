@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+# Build Brush.app for iOS. Pass aarch64-apple-ios-sim to build for the simulator.
+set -euo pipefail
+
+TARGET="${1:-aarch64-apple-ios}"
+ROOT="$(cd "$(dirname "$0")/../../.." && pwd)"
+
+cargo build --release --target "$TARGET" -p brush-app --bin brush_app
+
+OUT="$ROOT/target/$TARGET/release"
+APP="$OUT/Brush.app"
+rm -rf "$APP"
+mkdir -p "$APP"
+cp "$OUT/brush_app" "$APP/"
+cp "$ROOT/crates/brush-app/ios/Info.plist" "$APP/"
+cp "$ROOT/crates/brush-app/assets/icon-256.png" "$APP/AppIcon.png"
+
+echo "Built $APP"
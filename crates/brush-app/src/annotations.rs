@@ -0,0 +1,189 @@
+//! Text notes anchored to points in the scene, eg. to point out damage when reviewing a scan.
+
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use egui::{Align2, Color32, FontId, Rect, Stroke, vec2};
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::i18n::tr;
+use crate::picking::PickTarget;
+use crate::recent::RecentLocation;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Annotation {
+    pub(crate) text: String,
+    /// Where the note is anchored, in the space of the splats.
+    pub(crate) position: Vec3,
+}
+
+/// Annotations of all data that has been opened, saved with the app settings.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct AnnotationStore {
+    entries: Vec<(RecentLocation, Vec<Annotation>)>,
+}
+
+impl AnnotationStore {
+    pub(crate) fn get(&self, location: &RecentLocation) -> Vec<Annotation> {
+        self.entries
+            .iter()
+            .find(|(l, _)| l == location)
+            .map(|(_, annotations)| annotations.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set(&mut self, location: RecentLocation, annotations: Vec<Annotation>) {
+        self.entries.retain(|(l, _)| l != &location);
+        if !annotations.is_empty() {
+            self.entries.push((location, annotations));
+        }
+    }
+}
+
+pub(crate) fn annotations_to_json(annotations: &[Annotation]) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(annotations)?)
+}
+
+pub(crate) fn annotations_from_json(data: &[u8]) -> anyhow::Result<Vec<Annotation>> {
+    Ok(serde_json::from_slice(data)?)
+}
+
+// Color of the marker and text of visible annotations.
+const ANNOTATION_COLOR: Color32 = Color32::from_rgb(255, 200, 60);
+
+// How much closer than an annotation the splats have to be to hide it.
+const OCCLUSION_MARGIN: f32 = 0.03;
+
+/// Places annotations in the view, and checks which are hidden behind splats.
+pub(crate) struct AnnotationView {
+    /// Whether a click in the view adds an annotation.
+    pub(crate) placing: bool,
+    pick_receiver: Option<Receiver<Option<Vec3>>>,
+    occlusion_receiver: Option<Receiver<Vec<bool>>>,
+    // Whether each annotation is hidden behind splats, as of the last check.
+    occluded: Vec<bool>,
+}
+
+impl AnnotationView {
+    pub(crate) fn new() -> Self {
+        Self {
+            placing: false,
+            pick_receiver: None,
+            occlusion_receiver: None,
+            occluded: vec![],
+        }
+    }
+
+    /// Add an annotation at the splats under `pixel` of the render `target`.
+    pub(crate) fn place(&mut self, target: PickTarget, pixel: Vec2) {
+        let (sender, receiver) = channel();
+        self.pick_receiver = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let picked = target.pick(&[pixel]).await;
+            let _ = sender.send(picked.into_iter().next().flatten());
+        });
+    }
+
+    /// Check which `annotations` are hidden behind splats in the render `target`.
+    pub(crate) fn update_occlusion(&mut self, target: PickTarget, annotations: &[Annotation]) {
+        if annotations.is_empty() {
+            self.occluded.clear();
+            return;
+        }
+        let positions: Vec<Vec3> = annotations.iter().map(|a| a.position).collect();
+        let (sender, receiver) = channel();
+        self.occlusion_receiver = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            // Annotations outside of the view are never drawn, so their pixel doesn't matter.
+            let pixels: Vec<Vec2> = positions
+                .iter()
+                .map(|&p| target.project(p).unwrap_or(Vec2::splat(-1.0)))
+                .collect();
+            let picked = target.pick(&pixels).await;
+            let occluded = positions
+                .iter()
+                .zip(picked)
+                .map(|(&p, picked)| {
+                    picked.is_some_and(|picked| {
+                        target.depth(picked) < target.depth(p) * (1.0 - OCCLUSION_MARGIN)
+                    })
+                })
+                .collect();
+            let _ = sender.send(occluded);
+        });
+    }
+
+    /// Whether the annotations changed since occlusion was last checked.
+    pub(crate) fn needs_occlusion_update(&self, annotations: &[Annotation]) -> bool {
+        self.occlusion_receiver.is_none() && self.occluded.len() != annotations.len()
+    }
+
+    /// Receive the results of picking, and add new annotations to `annotations`.
+    pub(crate) fn receive(&mut self, ctx: &egui::Context, annotations: &mut Vec<Annotation>) {
+        if let Some(receiver) = self.pick_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(picked) => {
+                    self.pick_receiver = None;
+                    if let Some(position) = picked {
+                        annotations.push(Annotation {
+                            text: format!("{} {}", tr("Note"), annotations.len() + 1),
+                            position,
+                        });
+                    }
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint(),
+                Err(TryRecvError::Disconnected) => self.pick_receiver = None,
+            }
+        }
+
+        if let Some(receiver) = self.occlusion_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(occluded) => {
+                    self.occlusion_receiver = None;
+                    self.occluded = occluded;
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint(),
+                Err(TryRecvError::Disconnected) => self.occlusion_receiver = None,
+            }
+        }
+    }
+
+    /// Draw `annotations` as labels on top of the render `target` shown in `rect`. Labels hidden
+    /// behind splats are faded out.
+    pub(crate) fn draw(
+        &self,
+        ui: &egui::Ui,
+        rect: Rect,
+        target: &PickTarget,
+        annotations: &[Annotation],
+    ) {
+        let painter = ui.painter_at(rect);
+        for (i, annotation) in annotations.iter().enumerate() {
+            let Some(pixel) = target.project(annotation.position) else {
+                continue;
+            };
+            let pos = rect.min + vec2(pixel.x, pixel.y);
+            if !rect.contains(pos) {
+                continue;
+            }
+
+            let occluded = self.occluded.get(i).copied().unwrap_or(false);
+            let color = if occluded {
+                ANNOTATION_COLOR.gamma_multiply(0.35)
+            } else {
+                ANNOTATION_COLOR
+            };
+
+            painter.circle(pos, 4.0, color, Stroke::new(1.0, Color32::BLACK));
+            let galley =
+                painter.layout_no_wrap(annotation.text.clone(), FontId::proportional(14.0), color);
+            let text_rect = Align2::LEFT_BOTTOM
+                .anchor_size(pos + vec2(6.0, -6.0), galley.size())
+                .expand(3.0);
+            let background = Color32::from_black_alpha(if occluded { 80 } else { 160 });
+            painter.rect_filled(text_rect, 3.0, background);
+            painter.galley(text_rect.min + vec2(3.0, 3.0), galley, color);
+        }
+    }
+}
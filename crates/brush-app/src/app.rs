@@ -1,22 +1,36 @@
 use std::sync::{Arc, RwLock};
 
+use crate::annotations::{Annotation, AnnotationStore};
+use crate::background::{self, BackgroundMode};
 use crate::channel::reactive_receiver;
-use crate::orbit_controls::CameraController;
-use crate::panels::SettingsPanel;
-use crate::panels::{DatasetPanel, PresetsPanel, ScenePanel, StatsPanel, TracingPanel};
+use crate::i18n::{self, Language, tr};
+use crate::keymap::{Action, BOOKMARK_COUNT, Keymap};
+use crate::layout::{Layout, PaneFactory, PaneKind, PaneType};
+use crate::locale;
+use crate::logs::LogSettings;
+use crate::orbit_controls::{CameraController, CameraPose, Transition};
+use crate::recent::{RecentLocation, RecentSources};
+use crate::reference_layers::ReferenceLayer;
+use crate::scene_graph::SceneGraph;
+use crate::sessions::{Session, record_replay, session_name};
+use crate::updates::Updates;
 use brush_dataset::Dataset;
 use brush_process::data_source::DataSource;
 use brush_process::process_loop::{
     ControlMessage, ProcessArgs, ProcessMessage, RunningProcess, start_process,
 };
+use brush_render::bounding_box::BoundingBox;
 use brush_render::camera::Camera;
 use brush_train::scene::SceneView;
 use burn_wgpu::WgpuDevice;
 use eframe::egui;
 use egui_tiles::SimplificationOptions;
-use egui_tiles::{Container, Tile, TileId, Tiles};
-use glam::{Affine3A, Quat, Vec3};
+use egui_tiles::{Container, Tile, TileId, Tree};
+use glam::{Affine3A, Mat3, Quat, Vec3};
 use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio_with_wasm::alias as tokio_wasm;
 
 pub(crate) trait AppPanel {
     fn title(&self) -> String;
@@ -34,6 +48,16 @@ pub(crate) trait AppPanel {
     fn inner_margin(&self) -> f32 {
         12.0
     }
+
+    /// Restore the settings of this panel from a previous session.
+    fn load_settings(&mut self, storage: &dyn eframe::Storage) {
+        let _ = storage;
+    }
+
+    /// Save the settings of this panel, to restore them next session.
+    fn save_settings(&self, storage: &mut dyn eframe::Storage) {
+        let _ = storage;
+    }
 }
 
 struct AppTree {
@@ -41,8 +65,6 @@ struct AppTree {
     context: Arc<RwLock<AppContext>>,
 }
 
-type PaneType = Box<dyn AppPanel>;
-
 impl egui_tiles::Behavior<PaneType> for AppTree {
     fn tab_title_for_pane(&mut self, pane: &PaneType) -> egui::WidgetText {
         pane.title().into()
@@ -94,12 +116,75 @@ fn parse_search(search: &str) -> HashMap<String, String> {
     params
 }
 
+/// Make widgets big enough to hit with a finger.
+fn apply_touch_style(ctx: &egui::Context) {
+    ctx.all_styles_mut(|style| {
+        let spacing = &mut style.spacing;
+        spacing.interact_size = egui::vec2(48.0, 40.0);
+        spacing.button_padding = egui::vec2(12.0, 8.0);
+        spacing.item_spacing = egui::vec2(10.0, 8.0);
+        spacing.icon_width = 24.0;
+        spacing.icon_width_inner = 14.0;
+        spacing.scroll.bar_width = 16.0;
+    });
+}
+
+/// Parse a comma separated list of exactly `N` numbers, eg. "1.0,2.0,3.0".
+fn parse_floats<const N: usize>(value: &str) -> Option<[f32; N]> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse().ok())
+        .collect::<Option<Vec<f32>>>()?;
+    values.try_into().ok()
+}
+
+fn format_floats(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(|v| format!("{v:.4}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+const KEYMAP_KEY: &str = "keymap";
+const LANGUAGE_KEY: &str = "language";
+const RECENT_KEY: &str = "recent_sources";
+const BACKGROUND_KEY: &str = "background_mode";
+const ANNOTATIONS_KEY: &str = "annotations";
+const CHECK_UPDATES_KEY: &str = "check_updates";
+const LOG_FILTER_KEY: &str = "log_filter";
+const FORMATTING_KEY: &str = "formatting";
+
+/// Add the dataset panel next to the other panels.
+fn add_dataset_pane(tree: &mut Tree<PaneType>, pane: PaneType) -> TileId {
+    let pane_id = tree.tiles.insert_pane(pane);
+    if let Some(Tile::Container(Container::Linear(lin))) =
+        tree.root().and_then(|root| tree.tiles.get_mut(root))
+    {
+        lin.add_child(pane_id);
+    }
+    pane_id
+}
+
 pub struct App {
-    tree: egui_tiles::Tree<PaneType>,
+    tree: Tree<PaneType>,
     datasets: Option<TileId>,
+    side_panel: TileId,
     tree_ctx: AppTree,
+    show_help: bool,
+    touch_ui: bool,
+    pane_factory: PaneFactory,
+    // Kind of each panel in the tree, to save the layout.
+    pane_kinds: HashMap<TileId, PaneKind>,
 }
 
+/// A rendered PNG of the current view, or why it couldn't be taken.
+pub type ScreenshotResult = Result<Vec<u8>, String>;
+
+/// An unclamped render of the current view, with premultiplied sRGB colors, or why it couldn't
+/// be rendered.
+pub(crate) type HdrRenderResult = Result<image::Rgba32FImage, String>;
+
 // TODO: Bit too much random shared state here.
 pub struct AppContext {
     pub dataset: Dataset,
@@ -107,14 +192,66 @@ pub struct AppContext {
     pub view_aspect: Option<f32>,
     pub controls: CameraController,
     pub model_local_to_world: Affine3A,
+    pub scene_graph: SceneGraph,
+    pub reference_layers: Vec<ReferenceLayer>,
     pub device: WgpuDevice,
+    /// Orientation of the device from its motion sensors, on platforms that have them.
+    pub device_orientation: Option<Quat>,
+    /// Set in kiosk mode, where the panels stay hidden and nothing can be trained.
+    pub(crate) kiosk: bool,
+    /// Turns the device's orientation sensor on or off, on platforms that have one. It's only
+    /// running while looking around, to not drain the battery.
+    pub orientation_sensor: Option<Box<dyn Fn(bool) + Send + Sync>>,
+    pub keymap: Keymap,
+    /// Set by a panel while it's waiting for a key press, to not trigger any shortcuts.
+    pub(crate) capturing_keys: bool,
+    /// Set to put all panels back where they were by default.
+    pub(crate) reset_layout: bool,
+    pub(crate) recent: RecentSources,
+    /// Where the currently loaded data can be opened from again.
+    pub(crate) current_location: Option<RecentLocation>,
+    /// Processes running in the background, which can be switched to.
+    pub(crate) sessions: Vec<Session>,
+    /// What to do with training while the app is in the background.
+    pub(crate) background_mode: BackgroundMode,
+    /// Notes anchored in the currently loaded data.
+    pub(crate) annotations: Vec<Annotation>,
+    /// Annotations of all data opened before, to show them again when it's opened.
+    pub(crate) annotation_store: AnnotationStore,
+    /// Checks for and installs new releases.
+    pub(crate) updates: Updates,
+    /// Levels of the recorded logs.
+    pub(crate) logs: LogSettings,
+    /// Whether the GPU supports f16 in shaders.
+    pub(crate) supports_f16: bool,
+    /// Whether the camera was restored from a shared link, so the first splats loaded shouldn't
+    /// move it to frame them.
+    pub(crate) camera_restored: bool,
 
     loading: bool,
     training: bool,
+    show_ui: bool,
 
     ctx: egui::Context,
     running_process: Option<RunningProcess>,
+    // Latest messages of the running process, to show it again after switching sessions.
+    replay: Vec<ProcessMessage>,
+    // Messages of a session that was switched to, to restore the panels.
+    pending_replay: Vec<ProcessMessage>,
+    // Training limits sent to all processes, including those of background sessions, see
+    // [`BackgroundMode::training_limits`].
+    training_limits: (bool, Option<Duration>),
     cam_settings: CameraSettings,
+    // Actions triggered by shortcuts this frame.
+    actions: Vec<Action>,
+    camera_bookmarks: [Option<(Vec3, Quat)>; BOOKMARK_COUNT],
+    // Animated move of the camera to a new pose, eg. a bookmark or a view of the dataset.
+    camera_transition: Option<Transition>,
+
+    camera_listeners: Vec<mpsc::UnboundedSender<(Vec3, Quat)>>,
+    last_notified_pose: Option<(Vec3, Quat)>,
+    screenshot_requests: Vec<oneshot::Sender<ScreenshotResult>>,
+    hdr_requests: Vec<oneshot::Sender<HdrRenderResult>>,
 }
 
 #[derive(Clone)]
@@ -142,14 +279,43 @@ impl AppContext {
             camera,
             controls,
             model_local_to_world: model_transform,
+            scene_graph: SceneGraph::default(),
+            reference_layers: vec![],
             device,
+            device_orientation: None,
+            kiosk: false,
+            orientation_sensor: None,
+            keymap: Keymap::default(),
+            capturing_keys: false,
+            reset_layout: false,
+            recent: RecentSources::default(),
+            current_location: None,
+            sessions: vec![],
+            background_mode: BackgroundMode::default(),
+            annotations: vec![],
+            annotation_store: AnnotationStore::default(),
+            updates: Updates::new(),
+            logs: LogSettings::new(),
+            supports_f16: false,
+            camera_restored: false,
             ctx,
             view_aspect: None,
             loading: false,
             training: false,
+            show_ui: true,
             dataset: Dataset::empty(),
             running_process: None,
+            replay: vec![],
+            pending_replay: vec![],
+            training_limits: (false, None),
             cam_settings: cam_settings.clone(),
+            actions: vec![],
+            camera_bookmarks: [None; BOOKMARK_COUNT],
+            camera_transition: None,
+            camera_listeners: vec![],
+            last_notified_pose: None,
+            screenshot_requests: vec![],
+            hdr_requests: vec![],
         }
     }
 
@@ -161,6 +327,52 @@ impl AppContext {
         self.controls.rotation = Quat::from_mat3a(&transform.matrix3);
     }
 
+    fn camera_pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.controls.position,
+            rotation: self.controls.rotation,
+            focus_distance: self.controls.focus_distance,
+            fov: [self.camera.fov_x, self.camera.fov_y],
+        }
+    }
+
+    fn apply_camera_pose(&mut self, pose: CameraPose) {
+        self.controls.position = pose.position;
+        self.controls.rotation = pose.rotation;
+        self.controls.focus_distance = pose.focus_distance;
+        [self.camera.fov_x, self.camera.fov_y] = pose.fov;
+    }
+
+    /// Animate the camera from the pose `from` to where it is now, if the controls are smooth.
+    fn animate_from(&mut self, from: CameraPose) {
+        if !self.controls.smooth {
+            return;
+        }
+        let to = self.camera_pose();
+        self.apply_camera_pose(from);
+        self.camera_transition = Some(Transition::new(from, to));
+        self.ctx.request_repaint();
+    }
+
+    /// Advance the camera transition by `delta_time` seconds. When `interrupted`, eg. by the user
+    /// moving the camera, it stops where it is.
+    pub(crate) fn update_camera_transition(&mut self, delta_time: f32, interrupted: bool) {
+        if interrupted {
+            self.camera_transition = None;
+        }
+        let Some(transition) = self.camera_transition.as_mut() else {
+            return;
+        };
+        let (pose, done) = transition.step(delta_time);
+        self.apply_camera_pose(pose);
+        self.controls.stop_movement();
+        if done {
+            self.camera_transition = None;
+        } else {
+            self.ctx.request_repaint();
+        }
+    }
+
     pub fn set_model_up(&mut self, up_axis: Vec3) {
         self.model_local_to_world = Affine3A::from_rotation_translation(
             Quat::from_rotation_arc(up_axis, Vec3::NEG_Y),
@@ -171,7 +383,51 @@ impl AppContext {
         self.match_controls_to(&cam);
     }
 
+    /// Look at the middle of `bounds` (in the space of the splats) from far enough away to see
+    /// all of it, keeping the direction the camera looks in.
+    pub(crate) fn frame_bounds(&mut self, bounds: &BoundingBox) {
+        let from = self.camera_pose();
+        let radius = bounds.extent.length().max(1e-3);
+        let half_fov = 0.5 * self.camera.fov_x.min(self.camera.fov_y) as f32;
+        let distance = radius / half_fov.sin().max(0.1);
+        self.controls.position = bounds.center - self.controls.rotation * Vec3::Z * distance;
+        self.controls.focus_distance = distance;
+        self.controls.stop_movement();
+        self.animate_from(from);
+    }
+
+    /// Orbit around `pivot` (in the space of the controls) from now on, turning the camera to
+    /// look at it without moving it.
+    pub(crate) fn set_orbit_pivot(&mut self, pivot: Vec3) {
+        let Some(dir) = (pivot - self.controls.position).try_normalize() else {
+            return;
+        };
+        let from = self.camera_pose();
+        // Keep the camera upright, with its down axis in the plane of the old one.
+        let down = self.controls.rotation * Vec3::Y;
+        if let Some(right) = down.cross(dir).try_normalize() {
+            let basis = Mat3::from_cols(right, dir.cross(right), dir);
+            self.controls.rotation = Quat::from_mat3(&basis).normalize();
+        }
+        self.controls.focus_distance = pivot.distance(self.controls.position);
+        self.controls.stop_movement();
+        self.animate_from(from);
+    }
+
+    /// Stand the model upright on a floor with normal `up_axis` through `floor`, putting the
+    /// floor at height zero.
+    pub fn set_model_floor(&mut self, up_axis: Vec3, floor: Vec3) {
+        let rotation = Quat::from_rotation_arc(up_axis, Vec3::NEG_Y);
+        let height = (rotation * floor).y;
+        self.model_local_to_world =
+            Affine3A::from_rotation_translation(rotation, Vec3::new(0.0, -height, 0.0));
+
+        let cam = self.camera.clone();
+        self.match_controls_to(&cam);
+    }
+
     pub fn focus_view(&mut self, view: &SceneView) {
+        let from = self.camera_pose();
         self.camera = view.camera.clone();
         self.match_controls_to(&view.camera);
         self.controls.stop_movement();
@@ -182,17 +438,172 @@ impl AppContext {
             .train
             .estimate_extent()
             .map_or(4.0, |x| x / 3.0);
+        self.animate_from(from);
     }
 
     pub fn connect_to(&mut self, process: RunningProcess) {
-        // reset context & view.
-        *self = Self::new(self.device.clone(), self.ctx.clone(), &self.cam_settings);
+        // Keep training in the background, so it can be switched back to.
+        let background = if self.training {
+            self.take_session()
+        } else {
+            None
+        };
 
         // Convert the receiver to a "reactive" receiver that wakes up the UI.
-        self.running_process = Some(RunningProcess {
+        let process = RunningProcess {
             messages: reactive_receiver(process.messages, self.ctx.clone()),
             ..process
-        });
+        };
+        self.show_process(process, vec![]);
+        self.sessions.extend(background);
+    }
+
+    fn take_session(&mut self) -> Option<Session> {
+        let process = self.running_process.take()?;
+        Some(Session {
+            process,
+            replay: std::mem::take(&mut self.replay),
+        })
+    }
+
+    fn show_process(&mut self, process: RunningProcess, replay: Vec<ProcessMessage>) {
+        // reset context & view, but keep anyone listening to the app.
+        let fly_mode = self.controls.fly_mode;
+        self.store_annotations();
+        *self = Self {
+            show_ui: self.show_ui,
+            device_orientation: self.device_orientation,
+            kiosk: self.kiosk,
+            orientation_sensor: self.orientation_sensor.take(),
+            keymap: std::mem::take(&mut self.keymap),
+            recent: std::mem::take(&mut self.recent),
+            sessions: std::mem::take(&mut self.sessions),
+            background_mode: self.background_mode,
+            annotation_store: std::mem::take(&mut self.annotation_store),
+            camera_listeners: std::mem::take(&mut self.camera_listeners),
+            screenshot_requests: std::mem::take(&mut self.screenshot_requests),
+            hdr_requests: std::mem::take(&mut self.hdr_requests),
+            ..Self::new(self.device.clone(), self.ctx.clone(), &self.cam_settings)
+        };
+        self.controls.fly_mode = fly_mode;
+        self.running_process = Some(process);
+        self.pending_replay = replay;
+        self.ctx.request_repaint();
+    }
+
+    /// Show a background session, moving the current process to the background.
+    pub(crate) fn switch_to_session(&mut self, index: usize) {
+        if index >= self.sessions.len() {
+            return;
+        }
+        let session = self.sessions.remove(index);
+        let current = self.take_session();
+        // Let the panels know to forget the current process first.
+        let replay = std::iter::once(ProcessMessage::NewSource)
+            .chain(session.replay)
+            .collect();
+        self.show_process(session.process, replay);
+        self.sessions.extend(current);
+    }
+
+    /// Keep the annotations of the current data, to show them again when it's opened later.
+    pub(crate) fn store_annotations(&mut self) {
+        if let Some(location) = self.current_location.clone() {
+            self.annotation_store
+                .set(location, std::mem::take(&mut self.annotations));
+        }
+    }
+
+    /// Name of the shown session, see [`Session::name`].
+    pub(crate) fn session_name(&self) -> String {
+        session_name(&self.replay)
+    }
+
+    /// Stop the running process, if any.
+    pub fn disconnect(&mut self) {
+        self.running_process = None;
+        self.loading = false;
+        self.training = false;
+    }
+
+    /// Move the camera to a world space position and rotation.
+    pub fn set_camera_pose(&mut self, position: Vec3, rotation: Quat) {
+        self.camera_transition = None;
+        self.camera.position = position;
+        self.camera.rotation = rotation.normalize();
+        let cam = self.camera.clone();
+        self.match_controls_to(&cam);
+        self.controls.stop_movement();
+        self.ctx.request_repaint();
+    }
+
+    /// Like [`Self::set_camera_pose`], but animated if the controls are smooth.
+    pub(crate) fn move_camera_to(&mut self, position: Vec3, rotation: Quat) {
+        let from = self.camera_pose();
+        self.set_camera_pose(position, rotation);
+        self.animate_from(from);
+    }
+
+    /// Get the world space position and rotation of the camera whenever it changes.
+    pub fn subscribe_camera(&mut self) -> mpsc::UnboundedReceiver<(Vec3, Quat)> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.camera_listeners.push(sender);
+        // Let the new listener know about the current pose.
+        self.last_notified_pose = None;
+        self.ctx.request_repaint();
+        receiver
+    }
+
+    fn notify_camera_listeners(&mut self) {
+        let pose = (self.camera.position, self.camera.rotation);
+        if self.camera_listeners.is_empty() || self.last_notified_pose == Some(pose) {
+            return;
+        }
+        self.last_notified_pose = Some(pose);
+        self.camera_listeners
+            .retain(|listener| listener.send(pose).is_ok());
+    }
+
+    /// Update the orientation reported by the device's motion sensors.
+    pub fn set_device_orientation(&mut self, orientation: Quat) {
+        if self.device_orientation != Some(orientation) {
+            self.device_orientation = Some(orientation);
+            self.ctx.request_repaint();
+        }
+    }
+
+    /// Show or hide everything but the scene view. The panels stay hidden in kiosk mode.
+    pub fn set_ui_visible(&mut self, visible: bool) {
+        if self.kiosk {
+            return;
+        }
+        self.show_ui = visible;
+        self.ctx.request_repaint();
+    }
+
+    /// Render the current view to a PNG, which is sent to `result` once rendered.
+    pub fn request_screenshot(&mut self, result: oneshot::Sender<ScreenshotResult>) {
+        self.screenshot_requests.push(result);
+        self.ctx.request_repaint();
+    }
+
+    pub(crate) fn take_screenshot_requests(&mut self) -> Vec<oneshot::Sender<ScreenshotResult>> {
+        std::mem::take(&mut self.screenshot_requests)
+    }
+
+    /// Render the current view without clamping its colors, see [`HdrRenderResult`].
+    pub(crate) fn request_hdr_render(&mut self, result: oneshot::Sender<HdrRenderResult>) {
+        self.hdr_requests.push(result);
+        self.ctx.request_repaint();
+    }
+
+    pub(crate) fn take_hdr_requests(&mut self) -> Vec<oneshot::Sender<HdrRenderResult>> {
+        std::mem::take(&mut self.hdr_requests)
+    }
+
+    /// Whether a shortcut triggered `action` this frame.
+    pub(crate) fn action_triggered(&self, action: Action) -> bool {
+        self.actions.contains(&action)
     }
 
     pub(crate) fn control_message(&self, msg: ControlMessage) {
@@ -201,10 +612,43 @@ impl AppContext {
         }
     }
 
+    /// Query string that restores the current view when the web viewer is opened with it.
+    ///
+    /// Only data loaded from a url can be shared, otherwise this only contains the camera.
+    pub fn share_query(&self) -> String {
+        let mut query = String::new();
+        if let Some(DataSource::Url(url)) = self.running_process.as_ref().map(|p| &p.source) {
+            query += &format!("url={}&", urlencoding::encode(url));
+        }
+        query += &format!(
+            "pos={}&rot={}&fov={:.2}",
+            format_floats(&self.camera.position.to_array()),
+            format_floats(&self.camera.rotation.to_array()),
+            self.camera.fov_y.to_degrees()
+        );
+        if !self.show_ui {
+            query += "&zen=1";
+        }
+        query
+    }
+
+    /// Arguments of the currently running process, if any.
+    pub fn process_args(&self) -> Option<&ProcessArgs> {
+        self.running_process.as_ref().map(|p| &p.start_args)
+    }
+
     pub fn training(&self) -> bool {
         self.training
     }
 
+    /// Whether there's no process, or the current one has stopped and all its messages have
+    /// been handled.
+    pub(crate) fn process_ended(&self) -> bool {
+        self.running_process
+            .as_ref()
+            .is_none_or(|p| p.messages.is_closed() && p.messages.is_empty())
+    }
+
     pub fn loading(&self) -> bool {
         self.loading
     }
@@ -224,6 +668,7 @@ impl App {
             .wgpu_render_state
             .as_ref()
             .expect("No wgpu renderer enabled in egui");
+        let supports_f16 = state.device.features().contains(wgpu::Features::SHADER_F16);
         let device = brush_render::burn_init_device(
             state.adapter.clone(),
             state.device.clone(),
@@ -246,16 +691,17 @@ impl App {
 
             #[cfg(all(feature = "tracy", not(target_family = "wasm")))]
             {
-                use tracing_subscriber::layer::SubscriberExt;
+                use tracing_subscriber::Layer as _;
 
-                tracing::subscriber::set_global_default(
-                    tracing_subscriber::registry()
-                        .with(tracing_tracy::TracyLayer::default())
-                        .with(sync_span::SyncLayer::<
-                            burn_cubecl::CubeBackend<burn_wgpu::WgpuRuntime, f32, i32, u32>,
-                        >::new(device.clone())),
-                )
-                .expect("Failed to set tracing subscriber");
+                // Logging already set up the subscriber, see brush_cli::logging.
+                brush_cli::logging::add_profiling_layers(vec![
+                    tracing_tracy::TracyLayer::default().boxed(),
+                    sync_span::SyncLayer::<
+                        burn_cubecl::CubeBackend<burn_wgpu::WgpuRuntime, f32, i32, u32>,
+                    >::new(device.clone())
+                    .boxed(),
+                ])
+                .expect("Failed to add profiling layers");
             }
         }
 
@@ -266,10 +712,23 @@ impl App {
 
         let search_params = parse_search(start_uri.as_deref().unwrap_or(""));
 
-        let mut zen = false;
-        if let Some(z) = search_params.get("zen") {
-            zen = z.parse::<bool>().unwrap_or(false);
-        }
+        let flag = |name: &str| {
+            search_params
+                .get(name)
+                .is_some_and(|v| v == "1" || v.parse::<bool>().unwrap_or(false))
+        };
+
+        // Kiosk mode is for unattended displays: only show the scene, and slowly orbit around
+        // it when nobody has interacted with it for a while.
+        let kiosk_idle = flag("kiosk").then(|| {
+            let secs = search_params
+                .get("idle")
+                .and_then(|i| i.parse::<f32>().ok())
+                .filter(|i| i.is_finite())
+                .unwrap_or(30.0);
+            Duration::from_secs_f32(secs.max(0.0))
+        });
+        let zen = flag("zen") || kiosk_idle.is_some();
 
         let focal = search_params
             .get("focal")
@@ -281,51 +740,63 @@ impl App {
             .unwrap_or(4.0);
 
         let settings = CameraSettings { focal, radius };
-        let context = AppContext::new(device.clone(), cc.egui_ctx.clone(), &settings);
+        let mut context = AppContext::new(device.clone(), cc.egui_ctx.clone(), &settings);
+        context.show_ui = !zen;
+        context.kiosk = kiosk_idle.is_some();
+        context.supports_f16 = supports_f16;
 
-        let mut tiles: Tiles<PaneType> = Tiles::default();
-        let scene_pane = ScenePanel::new(
-            state.device.clone(),
-            state.queue.clone(),
-            state.renderer.clone(),
-            zen,
-        );
+        let touch_ui =
+            cfg!(target_os = "android") || search_params.get("touch").is_some_and(|t| t == "1");
+        if touch_ui {
+            apply_touch_style(&cc.egui_ctx);
+        }
 
-        let scene_pane_id = tiles.insert_pane(Box::new(scene_pane));
-
-        let root_container = if !zen {
-            let loading_subs = vec![
-                tiles.insert_pane(Box::new(SettingsPanel::new())),
-                tiles.insert_pane(Box::new(PresetsPanel::new())),
-            ];
-            let loading_pane = tiles.insert_tab_tile(loading_subs);
-
-            #[allow(unused_mut)]
-            let mut sides = vec![
-                loading_pane,
-                tiles.insert_pane(Box::new(StatsPanel::new(
-                    device.clone(),
-                    state.adapter.get_info(),
-                ))),
-            ];
-
-            if cfg!(feature = "tracing") {
-                sides.push(tiles.insert_pane(Box::new(TracingPanel::default())));
+        if let Some(storage) = cc.storage {
+            if let Some(keymap) = eframe::get_value::<Keymap>(storage, KEYMAP_KEY) {
+                context.keymap.restore(&keymap);
             }
+            if let Some(language) = eframe::get_value::<Language>(storage, LANGUAGE_KEY) {
+                i18n::set_language(&cc.egui_ctx, language);
+            }
+            if let Some(formatting) = eframe::get_value(storage, FORMATTING_KEY) {
+                locale::set_formatting(formatting);
+            }
+            if let Some(recent) = eframe::get_value(storage, RECENT_KEY) {
+                context.recent = recent;
+            }
+            if let Some(mode) = eframe::get_value(storage, BACKGROUND_KEY) {
+                context.background_mode = mode;
+            }
+            if let Some(annotations) = eframe::get_value(storage, ANNOTATIONS_KEY) {
+                context.annotation_store = annotations;
+            }
+            if let Some(check) = eframe::get_value(storage, CHECK_UPDATES_KEY) {
+                context.updates.check_on_startup = check;
+            }
+            if let Some(filter) = eframe::get_value(storage, LOG_FILTER_KEY) {
+                context.logs.load(filter);
+            }
+        }
+        if context.updates.check_on_startup {
+            context.updates.check(&cc.egui_ctx);
+        }
 
-            let side_panel = tiles.insert_vertical_tile(sides);
-
-            let mut lin = egui_tiles::Linear::new(
-                egui_tiles::LinearDir::Horizontal,
-                vec![side_panel, scene_pane_id],
-            );
-            lin.shares.set_share(side_panel, 0.4);
-            tiles.insert_container(lin)
-        } else {
-            scene_pane_id
+        let pane_factory = PaneFactory {
+            device: device.clone(),
+            wgpu_device: state.device.clone(),
+            queue: state.queue.clone(),
+            renderer: state.renderer.clone(),
+            adapter_info: state.adapter.get_info(),
+            zen,
+            kiosk_idle,
         };
 
-        let tree = egui_tiles::Tree::new("brush_tree", root_container, tiles);
+        // Restore the layout of the last session, unless the panels have changed since.
+        let layout = cc
+            .storage
+            .and_then(|s| eframe::get_value::<Layout>(s, Layout::storage_key(touch_ui)))
+            .filter(Layout::is_valid)
+            .unwrap_or_else(|| Layout::new_default(touch_ui));
 
         let context = Arc::new(RwLock::new(context));
         let _ = create_callback.send(AppCreateCb {
@@ -334,24 +805,101 @@ impl App {
 
         let tree_ctx = AppTree { zen, context };
 
-        let url = search_params.get("url");
-        if let Some(url) = url {
-            let running = start_process(
-                DataSource::Url(url.to_owned()),
-                ProcessArgs::default(),
-                device,
-            );
-            tree_ctx
-                .context
-                .write()
-                .expect("Lock poisoned")
-                .connect_to(running);
+        {
+            let mut context = tree_ctx.context.write().expect("Lock poisoned");
+
+            let url = search_params.get("url");
+            if let Some(url) = url {
+                let running = start_process(
+                    DataSource::Url(url.to_owned()),
+                    ProcessArgs::default(),
+                    device,
+                );
+                context.connect_to(running);
+            }
+
+            // Restore a shared view, see [`AppContext::share_query`].
+            let pos = search_params.get("pos").and_then(|p| parse_floats::<3>(p));
+            let rot = search_params.get("rot").and_then(|r| parse_floats::<4>(r));
+            if pos.is_some() || rot.is_some() {
+                let current = context.model_local_to_world * context.controls.local_to_world();
+                context.set_camera_pose(
+                    pos.map_or(current.translation.into(), Vec3::from_array),
+                    rot.map_or(Quat::from_mat3a(&current.matrix3), Quat::from_array),
+                );
+                context.camera_restored = true;
+            }
+            if let Some(fov) = search_params.get("fov").and_then(|f| f.parse::<f64>().ok()) {
+                context.camera.fov_y = fov.clamp(1.0, 170.0).to_radians();
+            }
         }
 
-        Self {
-            tree,
+        let mut app = Self {
+            tree: Tree::empty("brush_tree"),
             tree_ctx,
+            side_panel: layout.side_panel,
             datasets: None,
+            show_help: false,
+            touch_ui,
+            pane_factory,
+            pane_kinds: HashMap::new(),
+        };
+        app.apply_layout(&layout);
+
+        // The dataset panel is only shown once a dataset is loaded.
+        if let Some(datasets) = app.datasets {
+            app.tree.tiles.set_visible(datasets, false);
+        }
+        // In zen mode only the scene is shown, but the rest of the UI can be shown later on.
+        app.tree.tiles.set_visible(app.side_panel, !zen);
+
+        if let Some(storage) = cc.storage {
+            for (_, tile) in app.tree.tiles.iter_mut() {
+                if let Tile::Pane(pane) = tile {
+                    pane.load_settings(storage);
+                }
+            }
+        }
+
+        app
+    }
+
+    /// Arrange the panels as in `layout`, keeping the panels that are already open.
+    fn apply_layout(&mut self, layout: &Layout) {
+        let mut existing = HashMap::new();
+        for (id, kind) in self.pane_kinds.drain() {
+            if let Some(Tile::Pane(pane)) = self.tree.tiles.remove(id) {
+                existing.insert(kind, pane);
+            }
+        }
+
+        let factory = &self.pane_factory;
+        self.tree = layout.instantiate(|kind| {
+            existing
+                .remove(&kind)
+                .unwrap_or_else(|| factory.create(kind))
+        });
+        self.side_panel = layout.side_panel;
+        self.pane_kinds = layout
+            .tree
+            .tiles
+            .iter()
+            .filter_map(|(&id, tile)| match tile {
+                Tile::Pane(kind) => Some((id, *kind)),
+                Tile::Container(_) => None,
+            })
+            .collect();
+        self.datasets = self
+            .pane_kinds
+            .iter()
+            .find(|&(_, &kind)| kind == PaneKind::Dataset)
+            .map(|(&id, _)| id);
+
+        // Keep showing a loaded dataset, even if it's not part of the layout.
+        if let Some(pane) = existing.remove(&PaneKind::Dataset) {
+            let pane_id = add_dataset_pane(&mut self.tree, pane);
+            self.pane_kinds.insert(pane_id, PaneKind::Dataset);
+            self.datasets = Some(pane_id);
         }
     }
 }
@@ -361,29 +909,72 @@ impl App {
     fn receive_messages(&mut self) {
         let mut context = self.tree_ctx.context.write().expect("Lock poisoned");
 
+        for session in &mut context.sessions {
+            session.receive_messages();
+        }
+
+        let mut messages = std::mem::take(&mut context.pending_replay);
         let Some(process) = context.running_process.as_mut() else {
             return;
         };
 
-        let mut messages = vec![];
         while let Ok(message) = process.messages.try_recv() {
             messages.push(message);
         }
+        let control = process.control.clone();
+
+        for mut message in messages {
+            // A panel might have started another process, don't mix in messages of the old one.
+            let current = context.running_process.as_ref();
+            if !current.is_some_and(|p| p.control.same_channel(&control)) {
+                break;
+            }
+
+            // Kiosk displays only show splats, they shouldn't start training on a dataset.
+            if context.kiosk && matches!(message, ProcessMessage::StartLoading { training: true }) {
+                context.disconnect();
+                message = ProcessMessage::Error(Arc::new(anyhow::anyhow!(
+                    "Can't train in kiosk mode, only splat files can be shown"
+                )));
+            }
+            record_replay(&mut context.replay, &message);
+
+            if let ProcessMessage::SourceOpened { name, reopen } = &message {
+                context.store_annotations();
+                context.current_location = reopen.as_ref().and_then(RecentLocation::from_source);
+                context.annotations = context
+                    .current_location
+                    .as_ref()
+                    .map(|location| context.annotation_store.get(location))
+                    .unwrap_or_default();
+                if let Some(location) = context.current_location.clone() {
+                    let args = context
+                        .running_process
+                        .as_ref()
+                        .map(|p| p.start_args.clone())
+                        .unwrap_or_default();
+                    let evicted = context.recent.add(name.clone(), location, args);
+                    for entry in evicted {
+                        let id = entry.location.thumbnail_id();
+                        tokio_wasm::task::spawn(async move {
+                            if let Err(e) = brush_process::thumbnails::remove(&id).await {
+                                log::warn!("Failed to remove thumbnail: {e}");
+                            }
+                        });
+                    }
+                }
+            }
 
-        for message in messages {
             match message {
                 ProcessMessage::Dataset { data: _ } => {
                     // Show the dataset panel if we've loaded one.
-                    if self.datasets.is_none() {
-                        let pane_id = self.tree.tiles.insert_pane(Box::new(DatasetPanel::new()));
+                    if let Some(datasets) = self.datasets {
+                        self.tree.tiles.set_visible(datasets, true);
+                    } else {
+                        let pane = self.pane_factory.create(PaneKind::Dataset);
+                        let pane_id = add_dataset_pane(&mut self.tree, pane);
+                        self.pane_kinds.insert(pane_id, PaneKind::Dataset);
                         self.datasets = Some(pane_id);
-                        if let Some(Tile::Container(Container::Linear(lin))) = self
-                            .tree
-                            .tiles
-                            .get_mut(self.tree.root().expect("UI must have a root"))
-                        {
-                            lin.add_child(pane_id);
-                        }
                     }
                 }
                 ProcessMessage::StartLoading { training } => {
@@ -408,9 +999,185 @@ impl App {
     }
 }
 
+/// Save a screenshot of the current view to a file.
+fn save_screenshot(context: &mut AppContext) {
+    let (sender, receiver) = oneshot::channel();
+    context.request_screenshot(sender);
+
+    tokio_wasm::task::spawn(async move {
+        let png = match receiver.await {
+            Ok(Ok(png)) => png,
+            Ok(Err(e)) => {
+                log::warn!("Failed to take screenshot: {e}");
+                return;
+            }
+            Err(_) => return,
+        };
+
+        match rrfd::save_file("screenshot.png").await {
+            Ok(file) => {
+                if let Err(e) = file.write(&png).await {
+                    log::error!("Failed to write file: {e}");
+                }
+            }
+            Err(e) => log::error!("Failed to save file: {e}"),
+        }
+    });
+}
+
+impl App {
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        let mut context = self.tree_ctx.context.write().expect("Lock poisoned");
+
+        // Panels capturing keys set this again every frame.
+        let capturing = std::mem::take(&mut context.capturing_keys);
+        context.actions = if capturing {
+            vec![]
+        } else {
+            context.keymap.triggered(ctx)
+        };
+
+        for action in context.actions.clone() {
+            match action {
+                Action::TogglePanels => {
+                    let visible = !context.show_ui;
+                    context.set_ui_visible(visible);
+                }
+                Action::ShowHelp => self.show_help = !self.show_help,
+                Action::ToggleFlyMode => context.controls.fly_mode = !context.controls.fly_mode,
+                Action::Screenshot => save_screenshot(&mut context),
+                Action::SaveBookmark(i) => {
+                    let pose = (context.camera.position, context.camera.rotation);
+                    if let Some(bookmark) = context.camera_bookmarks.get_mut(i) {
+                        *bookmark = Some(pose);
+                    }
+                }
+                Action::GoToBookmark(i) => {
+                    if let Some(&Some((position, rotation))) = context.camera_bookmarks.get(i) {
+                        context.move_camera_to(position, rotation);
+                    }
+                }
+                Action::ResetLayout => context.reset_layout = true,
+                // Handled by the scene panel.
+                Action::TogglePause
+                | Action::Export
+                | Action::FlipComparison
+                | Action::FrameScene => {}
+            }
+        }
+    }
+
+    /// Throttle or pause training of all sessions while the app is in the background, and resume
+    /// it after.
+    fn limit_background_training(&mut self, ctx: &egui::Context) {
+        let mut context = self.tree_ctx.context.write().expect("Lock poisoned");
+        let limits = context
+            .background_mode
+            .training_limits(background::in_background(ctx));
+        if limits != context.training_limits {
+            context.training_limits = limits;
+            let processes = context
+                .running_process
+                .iter()
+                .chain(context.sessions.iter().map(|s| &s.process));
+            for process in processes {
+                let _ = process.control.send(ControlMessage::Suspend(limits.0));
+                let _ = process.control.send(ControlMessage::Throttle(limits.1));
+            }
+        }
+    }
+
+    /// Tabs to switch between the shown process and those running in the background.
+    fn sessions_ui(&mut self, ctx: &egui::Context) {
+        let mut context = self.tree_ctx.context.write().expect("Lock poisoned");
+        if context.sessions.is_empty() || !context.show_ui {
+            return;
+        }
+
+        let mut switch_to = None;
+        let mut close = None;
+        egui::TopBottomPanel::top("sessions").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                let _ = ui.selectable_label(true, context.session_name());
+                for (i, session) in context.sessions.iter().enumerate() {
+                    if ui.selectable_label(false, session.name()).clicked() {
+                        switch_to = Some(i);
+                    }
+                    if ui
+                        .small_button("✖")
+                        .on_hover_text(tr("Stop this session"))
+                        .clicked()
+                    {
+                        close = Some(i);
+                    }
+                }
+            });
+        });
+
+        if let Some(i) = close {
+            context.sessions.remove(i);
+        } else if let Some(i) = switch_to {
+            context.switch_to_session(i);
+        }
+    }
+
+    fn help_ui(&mut self, ctx: &egui::Context) {
+        let context = self.tree_ctx.context.read().expect("Lock poisoned");
+
+        egui::Window::new(tr("Keyboard shortcuts"))
+            .open(&mut self.show_help)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Grid::new("help_shortcuts")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for &(action, _) in context.keymap.bindings() {
+                            ui.label(action.description());
+                            ui.monospace(context.keymap.describe(ctx, action));
+                            ui.end_row();
+                        }
+                    });
+
+                ui.add_space(6.0);
+                ui.label(tr("Shortcuts can be changed in the Shortcuts panel."));
+            });
+    }
+}
+
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
         self.receive_messages();
+        self.handle_shortcuts(ctx);
+        self.limit_background_training(ctx);
+
+        let reset_layout = std::mem::take(
+            &mut self
+                .tree_ctx
+                .context
+                .write()
+                .expect("Lock poisoned")
+                .reset_layout,
+        );
+        if reset_layout {
+            self.apply_layout(&Layout::new_default(self.touch_ui));
+        }
+
+        let show_ui = self.tree_ctx.context.read().expect("Lock poisoned").show_ui;
+        if self.tree.tiles.is_visible(self.side_panel) != show_ui {
+            self.tree.tiles.set_visible(self.side_panel, show_ui);
+            self.tree_ctx.zen = !show_ui;
+        }
+
+        self.tree_ctx
+            .context
+            .write()
+            .expect("Lock poisoned")
+            .updates
+            .notification_ui(ctx);
+        self.sessions_ui(ctx);
 
         let main_panel_frame = egui::Frame::central_panel(ctx.style().as_ref()).inner_margin(0.0);
 
@@ -419,5 +1186,49 @@ impl eframe::App for App {
             .show(ctx, |ui| {
                 self.tree.ui(&mut self.tree_ctx, ui);
             });
+
+        self.help_ui(ctx);
+
+        self.tree_ctx
+            .context
+            .write()
+            .expect("Lock poisoned")
+            .notify_camera_listeners();
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let layout = Layout::from_tree(&self.tree, self.side_panel, |id| {
+            self.pane_kinds.get(&id).copied()
+        });
+        if let Some(layout) = layout {
+            eframe::set_value(storage, Layout::storage_key(self.touch_ui), &layout);
+        }
+
+        {
+            let context = self.tree_ctx.context.read().expect("Lock poisoned");
+            eframe::set_value(storage, KEYMAP_KEY, &context.keymap);
+            eframe::set_value(storage, RECENT_KEY, &context.recent);
+            eframe::set_value(storage, BACKGROUND_KEY, &context.background_mode);
+            eframe::set_value(
+                storage,
+                CHECK_UPDATES_KEY,
+                &context.updates.check_on_startup,
+            );
+            eframe::set_value(storage, LOG_FILTER_KEY, &context.logs.filter);
+
+            let mut annotations = context.annotation_store.clone();
+            if let Some(location) = context.current_location.clone() {
+                annotations.set(location, context.annotations.clone());
+            }
+            eframe::set_value(storage, ANNOTATIONS_KEY, &annotations);
+        }
+        eframe::set_value(storage, LANGUAGE_KEY, &i18n::language());
+        eframe::set_value(storage, FORMATTING_KEY, &locale::formatting());
+
+        for (_, tile) in self.tree.tiles.iter() {
+            if let Tile::Pane(pane) = tile {
+                pane.save_settings(storage);
+            }
+        }
     }
 }
@@ -0,0 +1,54 @@
+//! What to do while the app is in the background, so it doesn't keep a laptop busy.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+// How long to wait after every training step while throttled.
+const THROTTLE_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum BackgroundMode {
+    /// Keep training at full speed.
+    #[default]
+    Continue,
+    /// Wait a bit after every training step.
+    Throttle,
+    /// Pause training until the app is in the foreground again.
+    Pause,
+}
+
+impl BackgroundMode {
+    pub(crate) const ALL: [Self; 3] = [Self::Continue, Self::Throttle, Self::Pause];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Continue => "Keep training",
+            Self::Throttle => "Train slower",
+            Self::Pause => "Pause training",
+        }
+    }
+
+    /// Whether to suspend training, and how long to wait after every step.
+    pub(crate) fn training_limits(self, in_background: bool) -> (bool, Option<Duration>) {
+        match self {
+            Self::Pause if in_background => (true, None),
+            Self::Throttle if in_background => (false, Some(THROTTLE_DELAY)),
+            _ => (false, None),
+        }
+    }
+}
+
+/// Whether the app is in the background: unfocused, minimized, or in a hidden browser tab.
+pub(crate) fn in_background(ctx: &egui::Context) -> bool {
+    let (focused, minimized) = ctx.input(|i| (i.viewport().focused, i.viewport().minimized));
+
+    #[cfg(target_family = "wasm")]
+    let hidden = web_sys::window()
+        .and_then(|w| w.document())
+        .is_some_and(|d| d.hidden());
+    #[cfg(not(target_family = "wasm"))]
+    let hidden = false;
+
+    focused == Some(false) || minimized == Some(true) || hidden
+}
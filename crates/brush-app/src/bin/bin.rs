@@ -22,18 +22,31 @@ fn main() -> MainResult {
     #[cfg(not(target_family = "wasm"))]
     {
         use brush_cli::Cli;
-        use clap::Parser;
+        use clap::{Parser, error::ErrorKind};
+        use tracing_subscriber::Layer as _;
 
         let args = Cli::parse().validate()?;
 
+        // Keep logs next to the saved settings by default, so they're there for issue reports.
+        let log_dir = eframe::storage_dir("brush").map(|dir| dir.join("logs"));
+        let capture = brush_app::debug_bundle::capture_layer().boxed();
+        brush_cli::logging::init(&args.logging, log_dir, vec![capture]).map_err(|e| {
+            clap::Error::raw(
+                ErrorKind::InvalidValue,
+                format!("Failed to set up logging: {e:#}\n"),
+            )
+        })?;
+
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
             .expect("Failed to initialize tokio runtime");
 
-        runtime.block_on(async {
-            env_logger::init();
+        if let Some(command) = args.command {
+            return runtime.block_on(brush_cli::run_command(command));
+        }
 
+        runtime.block_on(async {
             if args.with_viewer {
                 let icon = eframe::icon_data::from_png_bytes(
                     &include_bytes!("../../assets/icon-256.png")[..],
@@ -44,6 +57,8 @@ fn main() -> MainResult {
                     // Build app display.
                     viewport: egui::ViewportBuilder::default()
                         .with_inner_size(egui::Vec2::new(1450.0, 1200.0))
+                        // Keeps the saved layout in the same place for debug & release builds.
+                        .with_app_id("brush")
                         .with_active(true)
                         .with_icon(std::sync::Arc::new(icon)),
                     wgpu_options,
@@ -80,8 +95,8 @@ fn main() -> MainResult {
                 };
 
                 let device = brush_render::burn_init_setup().await;
-                let process = start_process(source, args.process, device);
-                brush_cli::ui::process_ui(process).await;
+                let process = start_process(source, args.process, device.clone());
+                brush_cli::run_headless(process, device, args.log_format).await;
             }
         });
     }
@@ -91,9 +106,12 @@ fn main() -> MainResult {
         use tokio_with_wasm::alias as tokio_wasm;
         use wasm_bindgen::JsCast;
 
-        if cfg!(debug_assertions) {
-            eframe::WebLogger::init(log::LevelFilter::Debug).ok();
-        }
+        let level = if cfg!(debug_assertions) {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Off
+        };
+        brush_app::debug_bundle::init_logger(Box::new(eframe::WebLogger::new(level)), level);
 
         let document = web_sys::window()
             .expect("Failed to find web window (not running in a browser?")
@@ -105,20 +123,34 @@ fn main() -> MainResult {
             .and_then(|x| x.dyn_into::<web_sys::HtmlCanvasElement>().ok())
         {
             // On wasm, run as a local task.
-            tokio_wasm::task::spawn(async {
+            tokio_wasm::task::spawn(async move {
+                let report = brush_ui::gpu_check::check_gpu().await;
+                log::info!("Using GPU: {}", report.adapter_summary());
+                for warning in &report.warnings {
+                    log::warn!("{warning}");
+                }
+                if !report.is_supported() {
+                    gpu_diagnostics::show(&canvas, &report, None);
+                    return;
+                }
+
                 let web_options = eframe::WebOptions {
                     wgpu_options,
                     ..Default::default()
                 };
 
-                eframe::WebRunner::new()
+                let result = eframe::WebRunner::new()
                     .start(
-                        canvas,
+                        canvas.clone(),
                         web_options,
                         Box::new(|cc| Ok(Box::new(App::new(cc, send)))),
                     )
-                    .await
-                    .expect("failed to start eframe");
+                    .await;
+
+                if let Err(e) = result {
+                    log::error!("Failed to start: {e:?}");
+                    gpu_diagnostics::show(&canvas, &report, Some(&format!("{e:?}")));
+                }
             });
         }
     }
@@ -126,19 +158,89 @@ fn main() -> MainResult {
     Ok(())
 }
 
+#[cfg(target_family = "wasm")]
+mod gpu_diagnostics {
+    use brush_ui::gpu_check::GpuReport;
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    fn list(items: &[String]) -> String {
+        let items: String = items
+            .iter()
+            .map(|item| format!("<li>{}</li>", escape(item)))
+            .collect();
+        format!("<ul>{items}</ul>")
+    }
+
+    /// Replace the canvas with a page explaining why the GPU can't be used, instead of leaving
+    /// a blank canvas.
+    pub fn show(canvas: &web_sys::HtmlCanvasElement, report: &GpuReport, error: Option<&str>) {
+        let Some(document) = canvas.owner_document() else {
+            return;
+        };
+        let Ok(page) = document.create_element("div") else {
+            return;
+        };
+
+        let mut html = "<h2>Brush can't run on this device</h2>".to_owned();
+        html += &format!("<p><b>GPU:</b> {}</p>", escape(&report.adapter_summary()));
+        if !report.errors.is_empty() {
+            html += &list(&report.errors);
+        }
+        if let Some(error) = error {
+            html += &format!("<p><b>Failed to start:</b> {}</p>", escape(error));
+        }
+        if !report.warnings.is_empty() {
+            html += "<p>Other missing features:</p>";
+            html += &list(&report.warnings);
+        }
+        html += "<p>See <a href=\"https://caniuse.com/webgpu\" style=\"color: inherit\">caniuse.com/webgpu</a> \
+                 for browsers that support WebGPU.</p>";
+
+        page.set_class_name("centered");
+        let _ = page.set_attribute(
+            "style",
+            "font-size: 16px; max-width: 640px; text-align: left;",
+        );
+        page.set_inner_html(&html);
+
+        if let Some(parent) = canvas.parent_node() {
+            let _ = parent.replace_child(&page, canvas);
+        }
+    }
+}
+
 #[cfg(target_family = "wasm")]
 mod embedded {
     use super::*;
-    use brush_app::App;
-    use brush_process::{data_source::DataSource, process_loop::ProcessArgs};
+    use brush_app::{App, ScreenshotResult};
+    use brush_process::{
+        data_source::{DataBytes, DataSource},
+        process_loop::ProcessArgs,
+    };
+    use glam::{Quat, Vec3};
     use std::future::IntoFuture;
-    use tokio::sync::mpsc::UnboundedSender;
+    use tokio::sync::{mpsc::UnboundedSender, oneshot};
     use tokio_with_wasm::alias as tokio_wasm;
     use wasm_bindgen::prelude::*;
 
+    enum Command {
+        Load(DataSource),
+        SetCamera(Vec3, Quat),
+        WatchCamera(js_sys::Function),
+        ShowUi(bool),
+        Screenshot(oneshot::Sender<ScreenshotResult>),
+        Dispose,
+    }
+
     #[wasm_bindgen]
     pub struct EmbeddedApp {
-        command_channel: UnboundedSender<DataSource>,
+        command_channel: UnboundedSender<Command>,
+        runner: eframe::WebRunner,
     }
 
     #[wasm_bindgen]
@@ -153,47 +255,168 @@ mod embedded {
                 .dyn_into::<web_sys::HtmlCanvasElement>()
                 .unwrap();
 
-            let url = url.to_owned();
             let (send, rec) = tokio::sync::oneshot::channel();
 
             let (cmd_send, mut cmd_rec) = tokio::sync::mpsc::unbounded_channel();
 
+            let runner = eframe::WebRunner::new();
+            let app_runner = runner.clone();
+
             // On wasm, run as a local task.
-            tokio_wasm::spawn(async {
-                eframe::WebRunner::new()
+            tokio_wasm::spawn(async move {
+                let report = brush_ui::gpu_check::check_gpu().await;
+                if !report.is_supported() {
+                    crate::gpu_diagnostics::show(&canvas, &report, None);
+                    return;
+                }
+
+                let result = app_runner
                     .start(
-                        canvas,
+                        canvas.clone(),
                         eframe::WebOptions {
                             wgpu_options,
                             ..Default::default()
                         },
                         Box::new(|cc| Ok(Box::new(App::new(cc, send)))),
                     )
-                    .await
-                    .expect("failed to start eframe");
+                    .await;
+
+                if let Err(e) = result {
+                    crate::gpu_diagnostics::show(&canvas, &report, Some(&format!("{e:?}")));
+                }
             });
 
             tokio_wasm::spawn(async move {
-                let context = rec.into_future().await.unwrap().context;
+                let Ok(created) = rec.into_future().await else {
+                    return;
+                };
+                let context = created.context;
 
-                while let Some(source) = cmd_rec.recv().await {
-                    let mut ctx = context.write().unwrap();
-                    let process = start_process(source, ProcessArgs::default(), ctx.device.clone());
-                    ctx.connect_to(process);
+                while let Some(command) = cmd_rec.recv().await {
+                    let mut ctx = context.write().expect("Lock poisoned");
+                    match command {
+                        Command::Load(source) => {
+                            let process =
+                                start_process(source, ProcessArgs::default(), ctx.device.clone());
+                            ctx.connect_to(process);
+                        }
+                        Command::SetCamera(position, rotation) => {
+                            ctx.set_camera_pose(position, rotation);
+                        }
+                        Command::WatchCamera(callback) => {
+                            let mut poses = ctx.subscribe_camera();
+                            tokio_wasm::spawn(async move {
+                                while let Some((position, rotation)) = poses.recv().await {
+                                    let position =
+                                        js_sys::Float32Array::from(&position.to_array()[..]);
+                                    let rotation =
+                                        js_sys::Float32Array::from(&rotation.to_array()[..]);
+                                    if let Err(e) =
+                                        callback.call2(&JsValue::NULL, &position, &rotation)
+                                    {
+                                        log::error!("Camera callback failed: {e:?}");
+                                    }
+                                }
+                            });
+                        }
+                        Command::ShowUi(visible) => ctx.set_ui_visible(visible),
+                        Command::Screenshot(result) => ctx.request_screenshot(result),
+                        Command::Dispose => {
+                            ctx.disconnect();
+                            break;
+                        }
+                    }
                 }
             });
-            // Load initial url.
-            let _ = cmd_send.send(DataSource::Url(url.to_owned()));
+
+            if !url.is_empty() {
+                let _ = cmd_send.send(Command::Load(DataSource::Url(url.to_owned())));
+            }
+
             Self {
                 command_channel: cmd_send,
+                runner,
             }
         }
 
-        #[wasm_bindgen]
-        pub fn load_url(&self, url: &str) {
+        fn send(&self, command: Command) -> Result<(), JsError> {
             self.command_channel
-                .send(DataSource::Url(url.to_owned()))
-                .expect("Viewer was closed?");
+                .send(command)
+                .map_err(|_e| JsError::new("The viewer was disposed"))
+        }
+
+        #[wasm_bindgen]
+        pub fn load_url(&self, url: &str) -> Result<(), JsError> {
+            self.send(Command::Load(DataSource::Url(url.to_owned())))
+        }
+
+        /// Load a ply or zip file from memory. The name is only used to describe the data.
+        #[wasm_bindgen]
+        pub fn load_buffer(&self, name: &str, data: &js_sys::ArrayBuffer) -> Result<(), JsError> {
+            let data = js_sys::Uint8Array::new(data).to_vec();
+            self.send(Command::Load(DataSource::Bytes(DataBytes {
+                name: name.to_owned(),
+                data: data.into(),
+            })))
+        }
+
+        /// Move the camera to a world space `position` (x, y, z) with a `rotation`
+        /// quaternion (x, y, z, w).
+        #[wasm_bindgen]
+        pub fn set_camera(&self, position: &[f32], rotation: &[f32]) -> Result<(), JsError> {
+            let (Ok(position), Ok(rotation)) = (
+                <[f32; 3]>::try_from(position),
+                <[f32; 4]>::try_from(rotation),
+            ) else {
+                return Err(JsError::new(
+                    "Expected a position with 3 values and a rotation with 4 values",
+                ));
+            };
+            self.send(Command::SetCamera(
+                Vec3::from_array(position),
+                Quat::from_array(rotation),
+            ))
+        }
+
+        /// Call `callback(position, rotation)` whenever the camera moves, with the same
+        /// layout as [`Self::set_camera`].
+        #[wasm_bindgen]
+        pub fn on_camera_changed(&self, callback: js_sys::Function) -> Result<(), JsError> {
+            self.send(Command::WatchCamera(callback))
+        }
+
+        /// Show or hide the panels around the scene view.
+        #[wasm_bindgen]
+        pub fn set_ui_visible(&self, visible: bool) -> Result<(), JsError> {
+            self.send(Command::ShowUi(visible))
+        }
+
+        /// Render the current view to a PNG. Returns a promise resolving to a Blob.
+        #[wasm_bindgen]
+        pub fn screenshot(&self) -> js_sys::Promise {
+            let (sender, receiver) = oneshot::channel();
+            let sent = self.send(Command::Screenshot(sender));
+
+            wasm_bindgen_futures::future_to_promise(async move {
+                sent.map_err(JsValue::from)?;
+                let png = receiver
+                    .await
+                    .map_err(|_e| JsValue::from_str("The viewer was disposed"))?
+                    .map_err(|e| JsValue::from_str(&e))?;
+
+                let options = web_sys::BlobPropertyBag::new();
+                options.set_type("image/png");
+                let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(png.as_slice()));
+                let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+                Ok(blob.into())
+            })
+        }
+
+        /// Stop the viewer and release its resources. The instance can't be used afterwards.
+        #[wasm_bindgen]
+        pub fn dispose(&self) {
+            let _ = self.command_channel.send(Command::Dispose);
+            self.runner.destroy();
         }
     }
 }
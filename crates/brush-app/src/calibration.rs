@@ -0,0 +1,239 @@
+//! Entering camera intrinsics by hand in the dataset panel, for when structure from motion got
+//! them wrong or the dataset doesn't have them. Where the sparse points of the dataset project
+//! to shows how well the intrinsics fit, as they're being edited.
+
+use std::collections::BTreeMap;
+
+use brush_dataset::intrinsics::CameraIntrinsics;
+use brush_process::process_loop::start_process;
+use brush_render::camera::{focal_to_fov, fov_to_focal};
+use brush_train::scene::SceneView;
+
+use crate::app::AppContext;
+use crate::i18n::tr;
+use crate::locale;
+use crate::recent::RecentLocation;
+
+// Number of views of a group to measure the reprojection error on, to keep editing smooth.
+const MAX_ERROR_VIEWS: usize = 50;
+
+/// Mean reprojection error in pixels over the views, with their intrinsics replaced.
+fn reprojection_error(views: &[&SceneView], intrinsics: &CameraIntrinsics) -> Option<f32> {
+    let errors: Vec<f32> = views
+        .iter()
+        .take(MAX_ERROR_VIEWS)
+        .filter_map(|view| {
+            let mut camera = view.camera.clone();
+            intrinsics.apply(&mut camera);
+            view.reprojection_error(&camera)
+        })
+        .collect();
+    (!errors.is_empty()).then(|| errors.iter().sum::<f32>() / errors.len() as f32)
+}
+
+struct GroupEdit {
+    /// Intrinsics the data was loaded with, before any were entered.
+    loaded: CameraIntrinsics,
+    /// Intrinsics the views use now.
+    current: CameraIntrinsics,
+    edited: CameraIntrinsics,
+    loaded_error: Option<f32>,
+    edited_error: Option<f32>,
+}
+
+pub(crate) struct IntrinsicsEditor {
+    groups: BTreeMap<u32, GroupEdit>,
+}
+
+impl IntrinsicsEditor {
+    pub(crate) fn new() -> Self {
+        Self {
+            groups: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        let mut views: BTreeMap<u32, Vec<&SceneView>> = BTreeMap::new();
+        let eval_views = context.dataset.eval.iter().flat_map(|e| e.views.iter());
+        for view in context.dataset.train.views.iter().chain(eval_views) {
+            views.entry(view.camera_group).or_default().push(view);
+        }
+        if views.is_empty() {
+            return;
+        }
+
+        // Start over when the data changed, eg. after reloading it.
+        let report = &context.dataset.preprocess;
+        for (&group, group_views) in &views {
+            let current = CameraIntrinsics::of_camera(group, &group_views[0].camera);
+            if self.groups.get(&group).is_none_or(|g| g.current != current) {
+                let loaded = report
+                    .loaded_intrinsics
+                    .iter()
+                    .find(|i| i.camera_group == group)
+                    .copied()
+                    .unwrap_or(current);
+                let error = reprojection_error(group_views, &current);
+                self.groups.insert(
+                    group,
+                    GroupEdit {
+                        loaded,
+                        current,
+                        edited: current,
+                        loaded_error: reprojection_error(group_views, &loaded),
+                        edited_error: error,
+                    },
+                );
+            }
+        }
+        self.groups.retain(|group, _| views.contains_key(group));
+
+        for (group, edit) in &mut self.groups {
+            let group_views = &views[group];
+            let image = &group_views[0].image;
+            let (w, h) = (image.width(), image.height());
+
+            let title = format!(
+                "{} {group} ({} {})",
+                tr("Camera"),
+                locale::count(group_views.len() as u64),
+                tr("views")
+            );
+            egui::CollapsingHeader::new(title)
+                .id_salt(("camera_group", *group))
+                .show(ui, |ui| {
+                    let before = edit.edited;
+                    let intrinsics = &mut edit.edited;
+
+                    egui::Grid::new(("intrinsics", *group))
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            ui.label(tr("Focal length (px)"));
+                            for (fov, size) in
+                                [(&mut intrinsics.fov_x, w), (&mut intrinsics.fov_y, h)]
+                            {
+                                let mut focal = fov_to_focal(fov.to_radians(), size);
+                                if ui
+                                    .add(egui::DragValue::new(&mut focal).range(1.0..=1e6))
+                                    .changed()
+                                {
+                                    *fov = focal_to_fov(focal, size).to_degrees();
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("Principal point (px)"));
+                            for (uv, size) in intrinsics.center_uv.iter_mut().zip([w, h]) {
+                                let mut center = *uv * size as f32;
+                                if ui.add(egui::DragValue::new(&mut center)).changed() {
+                                    *uv = center / size as f32;
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label(tr("Radial distortion"));
+                            ui.horizontal(|ui| {
+                                for k in
+                                    [&mut intrinsics.k1, &mut intrinsics.k2, &mut intrinsics.k3]
+                                {
+                                    ui.add(egui::DragValue::new(k).speed(0.001));
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label(tr("Tangential distortion"));
+                            ui.horizontal(|ui| {
+                                for p in [&mut intrinsics.p1, &mut intrinsics.p2] {
+                                    ui.add(egui::DragValue::new(p).speed(0.0001));
+                                }
+                            });
+                            ui.end_row();
+                        });
+
+                    if ui
+                        .add_enabled(edit.edited != edit.loaded, egui::Button::new(tr("Reset")))
+                        .clicked()
+                    {
+                        edit.edited = edit.loaded;
+                    }
+                    if edit.edited != before {
+                        edit.edited_error = reprojection_error(group_views, &edit.edited);
+                    }
+
+                    match (edit.loaded_error, edit.edited_error) {
+                        (Some(loaded), Some(edited)) => {
+                            ui.label(format!(
+                                "{}: {} px ({} {} px)",
+                                tr("Reprojection error"),
+                                locale::number(edited.into(), 2),
+                                tr("was"),
+                                locale::number(loaded.into(), 2),
+                            ));
+                        }
+                        _ => {
+                            ui.label(tr(
+                                "No sparse points to measure the reprojection error with.",
+                            ));
+                        }
+                    }
+                });
+        }
+
+        self.reload_ui(ui, context);
+    }
+
+    // Intrinsics are applied while loading the views, so reload the data to apply them.
+    fn reload_ui(&self, ui: &mut egui::Ui, context: &mut AppContext) {
+        let changed: Vec<_> = self
+            .groups
+            .values()
+            .filter(|g| g.edited != g.current)
+            .collect();
+        let source = context
+            .current_location
+            .as_ref()
+            .map(RecentLocation::to_source);
+
+        let reload = ui
+            .add_enabled(
+                source.is_some() && !changed.is_empty(),
+                egui::Button::new(tr("Reload with these intrinsics")),
+            )
+            .on_disabled_hover_text(if source.is_some() {
+                tr("The intrinsics haven't changed.")
+            } else {
+                tr("This data can't be opened again, open it from the settings panel instead.")
+            })
+            .clicked();
+
+        if let (true, Some(source), Some(args)) = (reload, source, context.process_args()) {
+            let mut args = args.clone();
+            // Keep the intrinsics entered before, of groups that weren't changed now.
+            let mut intrinsics = args
+                .load_config
+                .intrinsics
+                .as_deref()
+                .and_then(|json| CameraIntrinsics::list_from_json(json).ok())
+                .unwrap_or_default();
+            intrinsics.retain(|i| {
+                changed
+                    .iter()
+                    .all(|c| c.edited.camera_group != i.camera_group)
+            });
+            // Groups reset to the loaded intrinsics don't need an override.
+            intrinsics.extend(
+                changed
+                    .iter()
+                    .filter(|c| c.edited != c.loaded)
+                    .map(|c| c.edited),
+            );
+            match CameraIntrinsics::list_to_json(&intrinsics) {
+                Ok(json) => {
+                    args.load_config.intrinsics = Some(json);
+                    context.connect_to(start_process(source, args, context.device.clone()));
+                }
+                Err(e) => log::error!("Failed to save the intrinsics: {e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Compare the splats with another version of them, eg. an earlier step of the same run or a run
+//! with different settings, seen from the same camera.
+
+use std::io::Cursor;
+
+use anyhow::Context;
+use brush_dataset::splat_import::load_splat_from_ply;
+use brush_render::{camera::Camera, gaussian_splats::Splats};
+use brush_train::diagnostics::{error_diagnostics, error_heatmap};
+use brush_train::scene::Scene;
+use brush_train::train::TrainBack;
+use burn::tensor::backend::AutodiffBackend;
+use burn_wgpu::WgpuDevice;
+use egui::{Align2, Color32, FontId, Rect, Stroke, TextureId, pos2, vec2};
+use tokio_stream::StreamExt;
+
+use crate::i18n::tr;
+
+pub(crate) type ViewSplats = Splats<<TrainBack as AutodiffBackend>::InnerBackend>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CompareMode {
+    /// Show either the current or the other splats, and flip between them.
+    Flip,
+    /// Show the current splats on the left, and the other splats on the right.
+    Split,
+}
+
+impl CompareMode {
+    pub(crate) const ALL: [Self; 2] = [Self::Flip, Self::Split];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Flip => "Flip",
+            Self::Split => "Side by side",
+        }
+    }
+}
+
+pub(crate) struct Comparison {
+    /// What the other splats are, eg. "Step 7000".
+    pub(crate) name: String,
+    pub(crate) splats: ViewSplats,
+    pub(crate) mode: CompareMode,
+    /// In flip mode, whether the other splats are shown instead of the current ones.
+    pub(crate) show_other: bool,
+    /// In split mode, where the view is split, from 0 (left) to 1 (right).
+    pub(crate) split: f32,
+}
+
+impl Comparison {
+    pub(crate) fn new(name: String, splats: ViewSplats) -> Self {
+        Self {
+            name,
+            splats,
+            mode: CompareMode::Split,
+            show_other: false,
+            split: 0.5,
+        }
+    }
+}
+
+/// Ask for a .ply file, and load the splats in it to compare with.
+pub(crate) async fn pick_comparison(device: WgpuDevice) -> anyhow::Result<(String, ViewSplats)> {
+    let file = rrfd::pick_file().await?;
+    let name = file.file_name();
+    let data = file.read().await?;
+
+    let stream = load_splat_from_ply(Cursor::new(data), None, device);
+    let mut stream = std::pin::pin!(stream);
+    // Files with multiple frames are compared at their last frame.
+    let mut splats = None;
+    while let Some(message) = stream.next().await {
+        splats = Some(message?.splats);
+    }
+    let splats = splats.with_context(|| format!("There are no splats in {name}"))?;
+    Ok((name, splats))
+}
+
+/// One of the views the splats explain worst.
+pub(crate) struct WorstView {
+    pub(crate) name: String,
+    /// Mean absolute error of the view.
+    pub(crate) error: f32,
+    pub(crate) camera: Camera,
+}
+
+// Number of worst views to list.
+const WORST_VIEW_COUNT: usize = 5;
+
+/// Render the views of `scene`, and color the splats by how badly they explain the views they're
+/// seen in. Returns the colored splats, and the views with the largest error.
+pub(crate) async fn error_comparison(
+    splats: ViewSplats,
+    scene: Scene,
+    device: WgpuDevice,
+) -> (ViewSplats, Vec<WorstView>) {
+    let diagnostics = error_diagnostics(&splats, &scene, &device).await;
+    let heatmap = error_heatmap(&splats, &diagnostics);
+    let worst = diagnostics
+        .views
+        .iter()
+        .take(WORST_VIEW_COUNT)
+        .map(|v| {
+            let view = &scene.views[v.index];
+            let name = std::path::Path::new(&view.path)
+                .file_name()
+                .map_or(view.path.clone(), |n| n.to_string_lossy().into_owned());
+            WorstView {
+                name,
+                error: v.error,
+                camera: view.camera.clone(),
+            }
+        })
+        .collect();
+    (heatmap, worst)
+}
+
+/// Draw the rendered current splats (`current`) and other splats (`other`) into `rect`.
+pub(crate) fn draw_comparison(
+    ui: &egui::Ui,
+    rect: Rect,
+    current: TextureId,
+    other: TextureId,
+    comparison: &Comparison,
+) {
+    let painter = ui.painter_at(rect);
+    let label = |pos, align, text: &str| {
+        painter.text(pos, align, text, FontId::proportional(14.0), Color32::WHITE);
+    };
+    let left_label = rect.left_top() + vec2(8.0, 8.0);
+    let right_label = rect.right_top() + vec2(-8.0, 8.0);
+    let full_uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+
+    match comparison.mode {
+        CompareMode::Flip => {
+            let (texture, name) = if comparison.show_other {
+                (other, comparison.name.as_str())
+            } else {
+                (current, tr("Current"))
+            };
+            painter.image(texture, rect, full_uv, Color32::WHITE);
+            label(left_label, Align2::LEFT_TOP, name);
+        }
+        CompareMode::Split => {
+            let split = comparison.split.clamp(0.0, 1.0);
+            let x = rect.min.x + rect.width() * split;
+            let left = Rect::from_min_max(rect.min, pos2(x, rect.max.y));
+            let right = Rect::from_min_max(pos2(x, rect.min.y), rect.max);
+            painter.image(
+                current,
+                left,
+                Rect::from_min_max(pos2(0.0, 0.0), pos2(split, 1.0)),
+                Color32::WHITE,
+            );
+            painter.image(
+                other,
+                right,
+                Rect::from_min_max(pos2(split, 0.0), pos2(1.0, 1.0)),
+                Color32::WHITE,
+            );
+            painter.vline(x, rect.y_range(), Stroke::new(2.0, Color32::WHITE));
+            label(left_label, Align2::LEFT_TOP, tr("Current"));
+            label(right_label, Align2::RIGHT_TOP, &comparison.name);
+        }
+    }
+}
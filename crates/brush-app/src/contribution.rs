@@ -0,0 +1,253 @@
+//! Preview hiding the splats that contribute least to the renders, to see how aggressive pruning
+//! would look before exporting. The splats themselves are left as they are.
+
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use brush_render::{camera::Camera, render::rgb_to_sh};
+use brush_train::burn_glue::SplatForwardDiff;
+use brush_train::scene::Scene;
+use brush_train::train::TrainBack;
+use burn::module::ParamId;
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{Int, Tensor, TensorPrimitive};
+use glam::{Quat, UVec2};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::compare::ViewSplats;
+use crate::i18n::tr;
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+
+// Views to measure the contribution from, and the size of their longest side in pixels. Low
+// resolutions are plenty to tell the splats apart, and keep the pass short.
+const MAX_VIEWS: usize = 16;
+const ORBIT_VIEWS: usize = 8;
+const MAX_SIZE: u32 = 512;
+
+/// Up to [`MAX_VIEWS`] of the training views of `scene`, or without any, views orbiting the
+/// point `focus_distance` in front of `camera`.
+pub(crate) fn measure_views(
+    scene: &Scene,
+    camera: &Camera,
+    size: UVec2,
+    focus_distance: f32,
+) -> Vec<(Camera, UVec2)> {
+    let fit = |size: UVec2| {
+        let scale = (MAX_SIZE as f32 / size.max_element() as f32).min(1.0);
+        (size.as_vec2() * scale).round().as_uvec2().max(UVec2::ONE)
+    };
+
+    if !scene.views.is_empty() {
+        let step = scene.views.len().div_ceil(MAX_VIEWS);
+        return scene
+            .views
+            .iter()
+            .step_by(step)
+            .map(|view| {
+                let size = glam::uvec2(view.image.width(), view.image.height());
+                (view.camera.clone(), fit(size))
+            })
+            .collect();
+    }
+
+    let focus = camera.position + camera.rotation * glam::Vec3::Z * focus_distance;
+    let up = camera.rotation * glam::Vec3::Y;
+    (0..ORBIT_VIEWS)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / ORBIT_VIEWS as f32;
+            let orbit = Quat::from_axis_angle(up, angle);
+            let mut view = camera.clone();
+            view.position = focus + orbit * (camera.position - focus);
+            view.rotation = (orbit * camera.rotation).normalize();
+            (view, fit(size))
+        })
+        .collect()
+}
+
+/// How much each splat contributes to renders from `views`, as the sum of the weights it's
+/// blended into the pixels with.
+async fn measure(splats: ViewSplats, views: Vec<(Camera, UVec2)>) -> Vec<f32> {
+    let device = splats.device();
+    let n = splats.num_splats() as usize;
+
+    // Give every splat the same color. The gradient of the rendered color with respect to the
+    // color of a splat is then the weight it was blended with, summed over the pixels.
+    let sh_coeffs =
+        Tensor::<TrainBack, 3>::from_inner(Tensor::full([n, 1, 3], rgb_to_sh(1.0), &device))
+            .require_grad();
+    let means = Tensor::<TrainBack, 2>::from_inner(splats.means.val());
+    let log_scales = Tensor::<TrainBack, 2>::from_inner(splats.log_scales.val());
+    let rotation = Tensor::<TrainBack, 2>::from_inner(splats.rotation.val());
+    let raw_opacity = Tensor::<TrainBack, 1>::from_inner(splats.raw_opacity.val());
+
+    let mut total = Tensor::<InnerBack, 1>::zeros([n], &device);
+    for (camera, size) in &views {
+        let out = <TrainBack as SplatForwardDiff<TrainBack>>::render_splats(
+            camera,
+            *size,
+            means.clone().into_primitive().tensor(),
+            log_scales.clone().into_primitive().tensor(),
+            rotation.clone().into_primitive().tensor(),
+            sh_coeffs.clone().into_primitive().tensor(),
+            raw_opacity.clone().into_primitive().tensor(),
+        );
+        let img: Tensor<TrainBack, 3> = Tensor::from_primitive(TensorPrimitive::Float(out.img));
+        let [h, w, _] = img.dims();
+        let grads = img.slice([0..h, 0..w, 0..1]).sum().backward();
+        if let Some(grad) = sh_coeffs.grad(&grads) {
+            total = total + grad.slice([0..n, 0..1, 0..1]).reshape([n]);
+        }
+    }
+    total
+        .into_data_async()
+        .await
+        .to_vec()
+        .unwrap_or_else(|_| vec![0.0; n])
+}
+
+/// The splats of `splats` that contribute more than the `hide_percent` that contribute least.
+fn keep_contributing(scores: &[f32], hide_percent: u32, splats: &ViewSplats) -> ViewSplats {
+    let hidden = scores.len() * hide_percent as usize / 100;
+    if hidden == 0 {
+        return splats.clone();
+    }
+    let mut sorted = scores.to_vec();
+    let (_, threshold, _) = sorted.select_nth_unstable_by(hidden - 1, f32::total_cmp);
+    let threshold = *threshold;
+    let kept: Vec<i32> = scores
+        .iter()
+        .enumerate()
+        .filter(|(_, score)| **score > threshold)
+        .map(|(i, _)| i as i32)
+        .collect();
+    let indices = Tensor::<InnerBack, 1, Int>::from_ints(kept.as_slice(), &splats.device());
+    ViewSplats::from_tensor_data(
+        splats.means.val().select(0, indices.clone()),
+        splats.rotation.val().select(0, indices.clone()),
+        splats.log_scales.val().select(0, indices.clone()),
+        splats.sh_coeffs.val().select(0, indices.clone()),
+        splats.raw_opacity.val().select(0, indices),
+    )
+}
+
+/// Hides the splats that contribute least to the renders, without changing the splats.
+pub(crate) struct ContributionFilter {
+    /// Percentage of the splats to hide.
+    pub(crate) hide_percent: u32,
+    /// Whether to leave the hidden splats out of exports too.
+    pub(crate) prune_exports: bool,
+    // Contribution of each splat, and the splats it was measured for.
+    scores: Option<(ParamId, Vec<f32>)>,
+    pending: Option<(ParamId, Receiver<Vec<f32>>)>,
+    // The splats left shown, and the splats and percentage they were filtered with.
+    shown: Option<(ParamId, u32, ViewSplats)>,
+}
+
+impl ContributionFilter {
+    pub(crate) fn new() -> Self {
+        Self {
+            hide_percent: 10,
+            prune_exports: false,
+            scores: None,
+            pending: None,
+            shown: None,
+        }
+    }
+
+    /// Whether the contribution of the splats is still being measured.
+    pub(crate) fn measuring(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.hide_percent, 0..=95)
+                .suffix("%")
+                .text(tr("Hidden")),
+        )
+        .on_hover_text(tr(
+            "Part of the splats to hide, starting with the ones that add the least to the views",
+        ));
+        ui.checkbox(&mut self.prune_exports, tr("Prune exports too"))
+            .on_hover_text(tr(
+                "Leave the hidden splats out of exports and shared files",
+            ));
+        if self.measuring() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(tr("Measuring contribution…"));
+            });
+        }
+    }
+
+    /// The splats to show for `splats`, once the contribution of each was measured from
+    /// `views`. Returns `None` while measuring, and whether the shown splats changed since the
+    /// last call.
+    pub(crate) fn filter(
+        &mut self,
+        ctx: &egui::Context,
+        splats: &ViewSplats,
+        views: impl FnOnce() -> Vec<(Camera, UVec2)>,
+    ) -> (Option<ViewSplats>, bool) {
+        let id = splats.means.id;
+
+        if let Some((pending_id, receiver)) = self.pending.as_ref() {
+            match receiver.try_recv() {
+                Ok(scores) => {
+                    self.scores = Some((*pending_id, scores));
+                    self.pending = None;
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint(),
+                Err(TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        let measured = self
+            .scores
+            .as_ref()
+            .filter(|(scores_id, _)| *scores_id == id);
+        let Some((_, scores)) = measured else {
+            if self
+                .pending
+                .as_ref()
+                .is_none_or(|(pending_id, _)| *pending_id != id)
+            {
+                let (sender, receiver) = channel();
+                let job = measure(splats.clone(), views());
+                tokio_wasm::task::spawn(async move {
+                    let _ = sender.send(job.await);
+                });
+                self.pending = Some((id, receiver));
+                ctx.request_repaint();
+            }
+            return (None, self.shown.take().is_some());
+        };
+
+        if let Some((shown_id, percent, shown)) = &self.shown {
+            if *shown_id == id && *percent == self.hide_percent {
+                return (Some(shown.clone()), false);
+            }
+        }
+
+        let shown = keep_contributing(scores, self.hide_percent, splats);
+        self.shown = Some((id, self.hide_percent, shown.clone()));
+        (Some(shown), true)
+    }
+
+    /// The splats to export of `splats`: those shown when exports are pruned too, otherwise all
+    /// of them. Fails while their contribution is still being measured.
+    pub(crate) fn for_export(&self, splats: &ViewSplats) -> anyhow::Result<ViewSplats> {
+        if !self.prune_exports {
+            return Ok(splats.clone());
+        }
+        match &self.scores {
+            Some((id, scores)) if *id == splats.means.id => {
+                Ok(keep_contributing(scores, self.hide_percent, splats))
+            }
+            _ => anyhow::bail!(
+                "{}",
+                tr("The contribution of the splats is still being measured")
+            ),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! A zip with what's needed to look into a problem, to attach to a GitHub issue. Nothing is sent
+//! anywhere, the user saves the zip and can check what's in it before sharing it.
+
+use std::collections::VecDeque;
+use std::io::Write as _;
+use std::sync::Mutex;
+
+use brush_dataset::Dataset;
+use brush_process::process_loop::ProcessArgs;
+use serde_json::json;
+use web_time::Instant;
+use wgpu::AdapterInfo;
+
+// Number of log lines to keep for the bundle.
+const MAX_LOG_LINES: usize = 2000;
+// Number of views to describe in the bundle, enough to spot odd cameras or images.
+const MAX_VIEWS: usize = 10;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+fn push_line(line: String) {
+    let mut logs = RECENT_LOGS.lock().expect("Lock poisoned");
+    if logs.len() == MAX_LOG_LINES {
+        logs.pop_front();
+    }
+    logs.push_back(line);
+}
+
+struct CaptureLogger {
+    inner: Box<dyn log::Log>,
+    start: Instant,
+}
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= log::Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if record.level() <= log::Level::Info {
+            let line = format!(
+                "[{:>9.3}s {} {}] {}",
+                self.start.elapsed().as_secs_f32(),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+            push_line(line);
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Log to `inner` up to `inner_level`, and keep the recent info, warning and error messages for
+/// debug bundles. For the web and Android, where logs don't go through a tracing subscriber.
+pub fn init_logger(inner: Box<dyn log::Log>, inner_level: log::LevelFilter) {
+    let logger = CaptureLogger {
+        inner,
+        start: Instant::now(),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(inner_level.max(log::LevelFilter::Info));
+    }
+}
+
+// Receives the formatted events of `capture_layer`, one write per event.
+#[cfg(not(target_family = "wasm"))]
+struct RecentLogsWriter;
+
+#[cfg(not(target_family = "wasm"))]
+impl std::io::Write for RecentLogsWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for line in String::from_utf8_lossy(buf).lines() {
+            push_line(line.to_owned());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Layer keeping the recent messages of a tracing subscriber for debug bundles.
+#[cfg(not(target_family = "wasm"))]
+pub fn capture_layer<S>() -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_timer(brush_cli::logging::LogTime)
+        .with_writer(|| RecentLogsWriter)
+}
+
+/// Cameras and images of the first views, without the images themselves.
+fn dataset_summary(dataset: &Dataset) -> serde_json::Value {
+    let views: Vec<_> = dataset
+        .train
+        .views
+        .iter()
+        .take(MAX_VIEWS)
+        .map(|view| {
+            let camera = &view.camera;
+            json!({
+                "path": view.path,
+                "width": view.image.width(),
+                "height": view.image.height(),
+                "color": format!("{:?}", view.image.color()),
+                "fov_x": camera.fov_x,
+                "fov_y": camera.fov_y,
+                "center_uv": camera.center_uv.to_array(),
+                "position": camera.position.to_array(),
+                "rotation": camera.rotation.to_array(),
+            })
+        })
+        .collect();
+
+    json!({
+        "train_views": dataset.train.views.len(),
+        "eval_views": dataset.eval.as_ref().map_or(0, |e| e.views.len()),
+        "skipped": dataset.skipped_summary(),
+        "first_views": views,
+    })
+}
+
+/// What goes into a debug bundle, gathered from the app.
+pub(crate) struct DebugInfo<'a> {
+    pub(crate) adapter: &'a AdapterInfo,
+    pub(crate) process_args: Option<&'a ProcessArgs>,
+    /// The last error of the process, and the errors that caused it.
+    pub(crate) error: Option<&'a [String]>,
+    pub(crate) dataset: Option<&'a Dataset>,
+}
+
+impl DebugInfo<'_> {
+    /// Write the bundle as a zip file.
+    pub(crate) fn to_zip(&self) -> anyhow::Result<Vec<u8>> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+
+        zip.start_file("system.txt", options)?;
+        writeln!(zip, "Brush {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(
+            zip,
+            "{} {}, {} build",
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+        )?;
+        writeln!(zip, "{:#?}", self.adapter)?;
+
+        if let Some(args) = self.process_args {
+            zip.start_file("config.json", options)?;
+            zip.write_all(serde_json::to_string_pretty(args)?.as_bytes())?;
+        }
+
+        if let Some(error) = self.error {
+            zip.start_file("error.txt", options)?;
+            for (i, cause) in error.iter().enumerate() {
+                writeln!(zip, "{}{cause}", "  ".repeat(i))?;
+            }
+        }
+
+        if let Some(dataset) = self.dataset {
+            zip.start_file("dataset.json", options)?;
+            zip.write_all(serde_json::to_string_pretty(&dataset_summary(dataset))?.as_bytes())?;
+        }
+
+        zip.start_file("log.txt", options)?;
+        for line in RECENT_LOGS.lock().expect("Lock poisoned").iter() {
+            writeln!(zip, "{line}")?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+}
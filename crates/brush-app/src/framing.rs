@@ -0,0 +1,44 @@
+//! Where to put the camera for splats loaded without a dataset, so it doesn't start inside of
+//! them or looking at empty space, and to frame the model or a selection on request.
+
+use brush_render::bounding_box::BoundingBox;
+use brush_train::train::TrainBack;
+use burn::tensor::Tensor;
+use burn::tensor::backend::AutodiffBackend;
+use glam::Vec3;
+
+use crate::compare::ViewSplats;
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+
+// Most splats to find the bounds of, the rest are skipped over.
+const MAX_POINTS: usize = 100_000;
+// Fraction of the splats on either side of each axis to ignore, eg. floaters far away from the
+// rest of the scene.
+const OUTLIERS: f32 = 0.02;
+
+/// Bounds of `splats`, ignoring splats far out from the rest. With a `selection` (1 for selected
+/// splats, 0 for others), only the selected splats are included.
+pub(crate) async fn robust_bounds(
+    splats: ViewSplats,
+    selection: Option<Tensor<InnerBack, 1>>,
+) -> Option<BoundingBox> {
+    let means: Vec<f32> = splats.means.val().into_data_async().await.to_vec().ok()?;
+    let selected = match selection {
+        Some(selection) => Some(selection.into_data_async().await.to_vec::<f32>().ok()?),
+        None => None,
+    };
+    let points: Vec<Vec3> = means
+        .chunks_exact(3)
+        .enumerate()
+        .filter(|(i, _)| {
+            selected
+                .as_ref()
+                .is_none_or(|s| s.get(*i).is_some_and(|&v| v > 0.5))
+        })
+        .map(|(_, mean)| Vec3::from_slice(mean))
+        .collect();
+    let step = points.len().div_ceil(MAX_POINTS).max(1);
+    let points: Vec<Vec3> = points.into_iter().step_by(step).collect();
+    BoundingBox::from_points_robust(&points, OUTLIERS)
+}
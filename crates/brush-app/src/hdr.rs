@@ -0,0 +1,110 @@
+//! Offline renders as linear EXR files, to composite them with other renders, and a tone mapped
+//! PNG preview of them.
+//!
+//! Renders hold sRGB encoded colors, like the splats. They aren't clamped, so bright splats can
+//! be brighter than white.
+
+use brush_train::image::linear_to_srgb;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+
+/// How colors brighter than white are brought into range for the preview.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Tonemap {
+    /// Clip colors at white, as the viewer shows them.
+    #[default]
+    Clamp,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// John Hable's filmic curve, as used in Uncharted 2.
+    Filmic,
+}
+
+impl Tonemap {
+    pub(crate) const ALL: [Self; 3] = [Self::Clamp, Self::Aces, Self::Filmic];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Clamp => "clamp",
+            Self::Aces => "aces",
+            Self::Filmic => "filmic",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.name().eq_ignore_ascii_case(name))
+    }
+
+    fn apply(self, x: f32) -> f32 {
+        let x = x.max(0.0);
+        match self {
+            Self::Clamp => x.min(1.0),
+            Self::Aces => {
+                ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+            }
+            Self::Filmic => {
+                fn hable(x: f32) -> f32 {
+                    let (a, b, c, d, e, f) = (0.15, 0.5, 0.1, 0.2, 0.02, 0.3);
+                    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+                }
+                // Exposure bias and white point of the original curve.
+                (hable(2.0 * x) / hable(11.2)).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+fn srgb_to_linear(srgb: f32) -> f32 {
+    let srgb = srgb.max(0.0);
+    if srgb <= 0.04045 {
+        srgb / 12.92
+    } else {
+        ((srgb + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear colors of a render. Colors stay premultiplied by alpha, as EXR files expect.
+pub(crate) fn render_to_linear(render: &Rgba32FImage) -> Rgba32FImage {
+    let mut linear = render.clone();
+    for pixel in linear.pixels_mut() {
+        let alpha = pixel[3];
+        if alpha > 0.0 {
+            for value in pixel.0.iter_mut().take(3) {
+                *value = srgb_to_linear(*value / alpha) * alpha;
+            }
+        }
+    }
+    linear
+}
+
+pub(crate) fn encode_exr(linear: Rgba32FImage) -> anyhow::Result<Vec<u8>> {
+    let mut exr = vec![];
+    DynamicImage::ImageRgba32F(linear).write_to(
+        &mut std::io::Cursor::new(&mut exr),
+        image::ImageFormat::OpenExr,
+    )?;
+    Ok(exr)
+}
+
+/// A PNG of linear colors, tone mapped and encoded to sRGB.
+pub(crate) fn encode_preview(linear: &Rgba32FImage, tonemap: Tonemap) -> anyhow::Result<Vec<u8>> {
+    let preview = RgbaImage::from_fn(linear.width(), linear.height(), |x, y| {
+        let pixel = linear.get_pixel(x, y);
+        let alpha = pixel[3];
+        // PNG colors aren't premultiplied.
+        let color = |c: f32| {
+            let straight = if alpha > 0.0 { c / alpha } else { 0.0 };
+            (linear_to_srgb(tonemap.apply(straight)) * 255.0).round() as u8
+        };
+        image::Rgba([
+            color(pixel[0]),
+            color(pixel[1]),
+            color(pixel[2]),
+            (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ])
+    });
+    let mut png = vec![];
+    preview.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
@@ -0,0 +1,1802 @@
+//! Translations of the UI.
+//!
+//! Text is looked up by its English version, so anything without a translation just shows up
+//! in English.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    ChineseSimplified,
+    Japanese,
+}
+
+impl Language {
+    pub const ALL: [Self; 3] = [Self::English, Self::ChineseSimplified, Self::Japanese];
+
+    /// Name of the language, in the language itself.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::ChineseSimplified => "简体中文",
+            Self::Japanese => "日本語",
+        }
+    }
+
+    fn needs_cjk_font(self) -> bool {
+        matches!(self, Self::ChineseSimplified | Self::Japanese)
+    }
+
+    fn translations(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::English => &[],
+            Self::ChineseSimplified => ZH_CN,
+            Self::Japanese => JA,
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn language() -> Language {
+    Language::ALL
+        .get(LANGUAGE.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Switch the language of the UI, loading a font that can show it if needed.
+pub fn set_language(ctx: &egui::Context, language: Language) {
+    let index = Language::ALL
+        .iter()
+        .position(|&l| l == language)
+        .unwrap_or(0);
+    LANGUAGE.store(index as u8, Ordering::Relaxed);
+
+    if language.needs_cjk_font() {
+        load_cjk_font(ctx);
+    }
+    ctx.request_repaint();
+}
+
+/// Translate `text` to the current language.
+pub fn tr(text: &'static str) -> &'static str {
+    language()
+        .translations()
+        .iter()
+        .find(|&&(english, _)| english == text)
+        .map_or(text, |&(_, translated)| translated)
+}
+
+// The default egui fonts don't include CJK glyphs, use a font from the system as a fallback.
+#[cfg(not(target_family = "wasm"))]
+const CJK_FONT_PATHS: &[&str] = &[
+    // Windows
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\YuGothM.ttc",
+    "C:\\Windows\\Fonts\\msgothic.ttc",
+    // macOS & iOS
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    // Linux
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    // Android
+    "/system/fonts/NotoSansCJK-Regular.ttc",
+];
+
+// There's no access to system fonts on the web, fetch the Noto CJK subset of the language instead
+// of bundling a font of several megabytes with the app. Pinned to a release, so the font can't
+// change under us.
+#[cfg(target_family = "wasm")]
+fn cjk_font_url(region: &str) -> String {
+    const NOTO_CJK: &str =
+        "https://cdn.jsdelivr.net/gh/notofonts/noto-cjk@Sans2.004/Sans/SubsetOTF";
+    format!("{NOTO_CJK}/{region}/NotoSans{region}-Regular.otf")
+}
+
+#[cfg(target_family = "wasm")]
+async fn fetch_font(url: &str) -> anyhow::Result<Vec<u8>> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+fn load_cjk_font(ctx: &egui::Context) {
+    // The system font covers both languages, but on the web each language has its own subset.
+    #[cfg(not(target_family = "wasm"))]
+    let font_name = "cjk_fallback";
+    #[cfg(target_family = "wasm")]
+    let (font_name, region) = if language() == Language::Japanese {
+        ("cjk_fallback_jp", "JP")
+    } else {
+        ("cjk_fallback_sc", "SC")
+    };
+
+    let loaded = egui::Id::new(font_name);
+    if ctx.data(|d| d.get_temp::<bool>(loaded)).is_some() {
+        return;
+    }
+    ctx.data_mut(|d| d.insert_temp(loaded, true));
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let font = CJK_FONT_PATHS
+            .iter()
+            .find_map(|path| std::fs::read(path).ok());
+        let Some(font) = font else {
+            log::warn!("No font with CJK characters found, text might not show up correctly.");
+            return;
+        };
+        add_fallback_font(ctx, font_name, font);
+    }
+
+    #[cfg(target_family = "wasm")]
+    {
+        let ctx = ctx.clone();
+        let url = cjk_font_url(region);
+        tokio_with_wasm::alias::task::spawn(async move {
+            match fetch_font(&url).await {
+                Ok(font) => add_fallback_font(&ctx, font_name, font),
+                Err(error) => {
+                    log::warn!("Failed to fetch a font with CJK characters: {error:?}");
+                    // Try again the next time the language is picked.
+                    ctx.data_mut(|d| d.remove::<bool>(loaded));
+                }
+            }
+        });
+    }
+}
+
+/// Add a font after the fonts that are already there, to use for any characters they're missing.
+fn add_fallback_font(ctx: &egui::Context, name: &str, font: Vec<u8>) {
+    use egui::epaint::text::{FontInsert, FontPriority, InsertFontFamily};
+
+    let families = [egui::FontFamily::Proportional, egui::FontFamily::Monospace]
+        .into_iter()
+        .map(|family| InsertFontFamily {
+            family,
+            priority: FontPriority::Lowest,
+        })
+        .collect();
+    ctx.add_font(FontInsert::new(
+        name,
+        egui::FontData::from_owned(font),
+        families,
+    ));
+    ctx.request_repaint();
+}
+
+const ZH_CN: &[(&str, &str)] = &[
+    // Panels
+    ("Scene", "场景"),
+    ("Settings", "设置"),
+    ("Presets", "预设"),
+    ("Stats", "统计"),
+    ("Plots", "图表"),
+    ("Histograms", "直方图"),
+    ("Scene graph", "场景图"),
+    ("Dataset", "数据集"),
+    // Settings
+    ("Language", "语言"),
+    ("Model Settings", "模型设置"),
+    ("Spherical Harmonics Degree:", "球谐函数阶数："),
+    ("Max image resolution", "最大图像分辨率"),
+    ("Limit max frames", "限制最大帧数"),
+    ("Initialize from dense point cloud", "从稠密点云初始化"),
+    ("Split dataset for evaluation", "划分数据集用于评估"),
+    ("Training Settings", "训练设置"),
+    ("Current Training", "当前训练"),
+    (
+        "Changes apply to the current run right away.",
+        "更改会立即应用到当前训练。",
+    ),
+    ("Mean learning rate", "位置学习率"),
+    ("Final mean learning rate", "最终位置学习率"),
+    ("Color learning rate", "颜色学习率"),
+    ("Opacity learning rate", "不透明度学习率"),
+    ("Scale learning rate", "缩放学习率"),
+    ("Rotation learning rate", "旋转学习率"),
+    ("SSIM weight", "SSIM 权重"),
+    ("Refine", "细化"),
+    ("Limit number of splats", "限制 splat 数量"),
+    ("Train", "训练"),
+    ("Process Settings", "处理设置"),
+    ("Evaluate", "评估"),
+    ("Export", "导出"),
+    (
+        "Select a .ply to visualize, or a .zip with training data.",
+        "选择要查看的 .ply 文件，或包含训练数据的 .zip 文件。",
+    ),
+    ("Load file", "加载文件"),
+    ("Load directory", "加载文件夹"),
+    ("Load URL", "加载网址"),
+    // Scene
+    (
+        "Load a ply file or dataset to get started.",
+        "加载 ply 文件或数据集以开始。",
+    ),
+    ("Loading... Please wait.", "加载中……请稍候。"),
+    ("⏸ paused", "⏸ 已暂停"),
+    ("⏵ playing", "⏵ 播放中"),
+    ("⏵ training", "⏵ 训练中"),
+    ("🔴 Live update splats", "🔴 实时更新"),
+    ("⬆ Export", "⬆ 导出"),
+    ("📤 Share", "📤 分享"),
+    (
+        "Export the splats and send them to another app",
+        "导出 splat 并发送到其他应用",
+    ),
+    ("📂 Show export", "📂 显示导出文件"),
+    ("🗺 Minimap", "🗺 小地图"),
+    ("⚖ Compare", "⚖ 对比"),
+    ("Pin current splats", "固定当前 splat"),
+    ("Pinned", "已固定"),
+    ("Open .ply file…", "打开 .ply 文件…"),
+    ("Stop comparing", "停止对比"),
+    ("Flip", "切换"),
+    ("Side by side", "并排"),
+    ("Current", "当前"),
+    ("Flip between compared splats", "在对比的 splat 之间切换"),
+    ("Error heatmap", "误差热图"),
+    (
+        "Color the splats by how badly they match the eval views, to find areas that need more photos",
+        "按 splat 与评估视图的差异程度为其着色，以找出需要更多照片的区域",
+    ),
+    ("Views with the largest error", "误差最大的视图"),
+    ("✂ Clipping", "✂ 裁剪"),
+    ("Near plane", "近裁剪面"),
+    (
+        "Hide splats closer to the camera than this",
+        "隐藏比此距离更靠近相机的 splat",
+    ),
+    ("Max distance", "最大距离"),
+    (
+        "Hide splats further from the camera than this, which also makes huge scenes faster to render",
+        "隐藏比此距离更远离相机的 splat，这也能加快超大场景的渲染",
+    ),
+    ("Reset", "重置"),
+    ("🔪 Section", "🔪 剖切"),
+    (
+        "Slice through the splats with a plane, to look inside",
+        "用平面剖切 splat，以查看内部",
+    ),
+    ("View", "视图"),
+    ("Offset", "偏移"),
+    ("Flip side", "翻转方向"),
+    ("Highlight cut", "高亮剖切面"),
+    ("📌 Annotate", "📌 标注"),
+    (
+        "Click on the splats to add a note there",
+        "点击 splat 以在该处添加注释",
+    ),
+    ("Note", "注释"),
+    ("Annotations", "标注"),
+    ("Import JSON…", "导入 JSON…"),
+    ("Export JSON…", "导出 JSON…"),
+    (
+        "Turn on 📌 Annotate in the scene, and click on the splats to add a note.",
+        "在场景中打开 📌 标注，然后点击 splat 以添加注释。",
+    ),
+    ("Look at this note", "查看此注释"),
+    ("🖌 Paint", "🖌 绘制"),
+    (
+        "Paint the color or opacity of the splats under the brush. Use the right or middle mouse button to move the camera while painting.",
+        "绘制画笔下 splat 的颜色或不透明度。绘制时使用鼠标右键或中键移动相机。",
+    ),
+    ("↶ Undo", "↶ 撤销"),
+    ("Color", "颜色"),
+    ("Opacity", "不透明度"),
+    ("Scale", "缩放"),
+    ("Radius", "半径"),
+    ("Hardness", "硬度"),
+    ("Strength", "强度"),
+    ("Select", "选择"),
+    ("Deselect", "取消选择"),
+    ("selected", "已选择"),
+    ("Select all", "全选"),
+    (
+        "Select all splats that aren't cut away by the section cut",
+        "选择所有未被剖切切除的 splat",
+    ),
+    ("Clear selection", "清除选择"),
+    ("⇋ Mirror", "⇋ 镜像"),
+    (
+        "Add a mirrored copy of the selection on the other side of the cut",
+        "在剖切面的另一侧添加所选内容的镜像副本",
+    ),
+    (
+        "Cut along X, Y or Z with 🔪 Section to place the plane to mirror through",
+        "使用 🔪 剖切沿 X、Y 或 Z 轴切割以放置镜像平面",
+    ),
+    ("⧉ Duplicate", "⧉ 复制"),
+    ("Copies", "副本数"),
+    ("Duplicate", "复制"),
+    ("💡 Shading", "💡 着色"),
+    (
+        "Light the splats or color them by their normals, to judge the shape of the splats without their colors",
+        "为 splat 打光或按法线着色，以便在不看颜色的情况下判断 splat 的形状",
+    ),
+    ("Lit", "光照"),
+    ("Normals", "法线"),
+    ("Light direction", "光照方向"),
+    ("Light height", "光照高度"),
+    ("Ambient light", "环境光"),
+    ("⚡ Fast blending", "⚡ 快速混合"),
+    ("📱 Look around", "📱 环顾四周"),
+    ("🔗 Copy share link", "🔗 复制分享链接"),
+    ("Controls", "操作"),
+    // Shortcuts
+    ("Shortcuts", "快捷键"),
+    ("Keyboard shortcuts", "键盘快捷键"),
+    (
+        "Shortcuts can be changed in the Shortcuts panel.",
+        "可以在快捷键面板中修改快捷键。",
+    ),
+    (
+        "Click a shortcut and press the new keys. Escape cancels, backspace removes the shortcut.",
+        "点击快捷键后按下新的按键。Esc 取消，退格键删除快捷键。",
+    ),
+    ("Press a key…", "请按键…"),
+    ("Reset to defaults", "恢复默认"),
+    ("Pause / resume", "暂停 / 继续"),
+    ("Export splats", "导出 splats"),
+    ("Save screenshot", "保存截图"),
+    ("Show / hide panels", "显示 / 隐藏面板"),
+    ("Switch between orbit and fly", "切换环绕 / 飞行模式"),
+    ("Show shortcuts", "显示快捷键"),
+    ("Save camera bookmark", "保存相机书签"),
+    ("Go to camera bookmark", "前往相机书签"),
+    ("Reset layout", "重置布局"),
+    // Preprocessing
+    ("Preprocess", "预处理"),
+    ("Preprocessing", "预处理"),
+    (
+        "Load a dataset to preview how it is preprocessed.",
+        "加载数据集以预览预处理效果。",
+    ),
+    ("Show mask", "显示遮罩"),
+    (
+        "This image has no mask or transparency.",
+        "此图像没有遮罩或透明度。",
+    ),
+    ("Crop around the center", "围绕中心裁剪"),
+    ("Skip blurry frames", "跳过模糊帧"),
+    (
+        "Sharpness is the variance of the Laplacian of the image. Blurry images have few edges and score low.",
+        "清晰度为图像拉普拉斯算子的方差。模糊图像边缘较少，得分较低。",
+    ),
+    ("Min sharpness", "最低清晰度"),
+    ("Skip underexposed frames", "跳过曝光不足的帧"),
+    ("Skip overexposed frames", "跳过曝光过度的帧"),
+    ("Max black pixels", "最大黑色像素比例"),
+    ("Max blown out pixels", "最大过曝像素比例"),
+    ("This frame", "此帧"),
+    ("black", "黑色"),
+    ("blown out", "过曝"),
+    ("frames would be skipped", "帧将被跳过"),
+    ("Measuring frame quality…", "正在测量帧质量…"),
+    ("Skip duplicate frames", "跳过重复帧"),
+    (
+        "Skip frames that look nearly the same as the frame before them, while the camera barely moved.",
+        "跳过与前一帧几乎相同且相机几乎没有移动的帧。",
+    ),
+    ("Min similarity", "最低相似度"),
+    ("Similarity to the previous frame", "与前一帧的相似度"),
+    ("Match exposure and white balance", "统一曝光和白平衡"),
+    ("Known color corrections", "已知的颜色校正"),
+    (
+        "A JSON file in the dataset, as exported with the splats.",
+        "数据集中的 JSON 文件，与 splats 一起导出。",
+    ),
+    ("⬆ Export color corrections", "⬆ 导出颜色校正"),
+    (
+        "Adjust the colors of all frames to match the first frame, for cameras that used automatic exposure or white balance.",
+        "调整所有帧的颜色以匹配第一帧，适用于使用自动曝光或自动白平衡的相机。",
+    ),
+    ("Reload with these settings", "使用这些设置重新加载"),
+    ("The settings haven't changed.", "设置没有变化。"),
+    (
+        "This data can't be opened again, open it from the settings panel instead.",
+        "无法重新打开此数据，请在设置面板中打开。",
+    ),
+    // Recent data
+    ("Recently opened", "最近打开"),
+    ("Remove", "移除"),
+    // Refine log
+    ("Refine log", "细化日志"),
+    ("Nothing has been refined yet.", "尚未进行细化。"),
+    ("Step", "步骤"),
+    ("Split", "分裂"),
+    ("Cloned", "克隆"),
+    ("Transparent", "透明"),
+    ("Too big", "过大"),
+    ("Splats", "Splat 数"),
+    ("Opacity reset", "不透明度重置"),
+    // Scripts
+    ("Script", "脚本"),
+    ("▶ Run", "▶ 运行"),
+    ("⏹ Stop", "⏹ 停止"),
+    ("commands left", "条命令待执行"),
+    ("Stopped the script", "已停止脚本"),
+    ("Functions", "函数"),
+    // Sessions
+    ("Stop this session", "停止此会话"),
+    // Background
+    ("In the background", "在后台时"),
+    ("Keep training", "继续训练"),
+    ("Train slower", "降低训练速度"),
+    ("Pause training", "暂停训练"),
+    // Rate limit
+    ("Rate limit", "速率限制"),
+    ("Limit steps per second", "限制每秒步数"),
+    ("Only train part of the time", "仅在部分时间训练"),
+    (
+        "Sleep between steps, so other applications get to use the GPU as well.",
+        "在步骤之间休眠，让其他应用程序也能使用 GPU。",
+    ),
+    (
+        "When the window isn't focused, is minimized, or is a hidden browser tab",
+        "当窗口未聚焦、已最小化或为隐藏的浏览器标签页时",
+    ), // Warnings
+    ("Dismiss", "关闭"),
+    // Updates
+    ("Check for updates on startup", "启动时检查更新"),
+    ("Check now", "立即检查"),
+    ("Brush is up to date", "Brush 已是最新版本"),
+    ("A new version is available:", "有新版本可用："),
+    ("Update", "更新"),
+    ("Downloading Brush", "正在下载 Brush"),
+    ("Updated to Brush", "已更新到 Brush"),
+    ("restart Brush to use it.", "重新启动 Brush 以使用新版本。"),
+    ("Release notes", "发行说明"),
+    // Debug bundle
+    ("🐞 Save debug bundle…", "🐞 保存调试包…"),
+    (
+        "Save the GPU, settings, last error and recent logs to a zip, to attach to a GitHub issue. Nothing is sent anywhere.",
+        "将 GPU、设置、最近的错误和日志保存到 zip 文件中，以便附加到 GitHub issue。不会发送任何数据。",
+    ),
+    ("Include dataset info", "包含数据集信息"),
+    (
+        "The number of views, and the cameras and image sizes of the first views",
+        "视图数量，以及前几个视图的相机和图像尺寸",
+    ),
+    // Logging
+    ("Log levels", "日志级别"),
+    ("set on the command line", "已在命令行中设置"),
+    (
+        "A level, or levels per module, eg. info,brush_train=debug",
+        "一个级别，或按模块设置的级别，例如 info,brush_train=debug",
+    ),
+    ("📂 Show logs", "📂 显示日志"),
+    // Formatting
+    ("Units", "单位"),
+    ("Metric", "公制"),
+    ("Imperial", "英制"),
+    ("Numbers", "数字"),
+    ("24 hour clock", "24 小时制"),
+    // Color
+    ("☀ Exposure", "☀ 曝光"),
+    (
+        "Only changes how the splats are shown, not the splats or exports",
+        "只改变高斯点的显示方式，不改变高斯点本身或导出结果",
+    ),
+    ("Exposure", "曝光"),
+    ("Gamma", "伽马"),
+    ("Compare colors in", "颜色比较空间"),
+    ("Linear light", "线性光"),
+    (
+        "sRGB weighs errors like they're seen, linear light weighs bright areas more",
+        "sRGB 按人眼感知衡量误差，线性光更看重明亮区域",
+    ),
+    // Intrinsics
+    ("Camera intrinsics", "相机内参"),
+    ("Camera", "相机"),
+    ("views", "个视图"),
+    ("Focal length (px)", "焦距（像素）"),
+    ("Principal point (px)", "主点（像素）"),
+    ("Radial distortion", "径向畸变"),
+    ("Tangential distortion", "切向畸变"),
+    ("Reprojection error", "重投影误差"),
+    ("was", "原为"),
+    (
+        "No sparse points to measure the reprojection error with.",
+        "没有稀疏点，无法计算重投影误差。",
+    ),
+    ("Reload with these intrinsics", "使用这些内参重新加载"),
+    ("The intrinsics haven't changed.", "内参没有改变。"),
+    // Motion blur
+    ("Model motion blur", "模拟运动模糊"),
+    (
+        "For blurry frames. Renders each step several times, so training is slower",
+        "适用于模糊的帧。每一步会渲染多次，因此训练更慢",
+    ),
+    ("up to ", "最多 "),
+    // Defocus
+    ("Model defocus", "模拟散焦"),
+    (
+        "For shallow depth of field, like macro or portrait shots",
+        "用于浅景深，例如微距或人像照片",
+    ),
+    // Crop training
+    ("Train on crops", "在裁剪区域上训练"),
+    (
+        "For very large images that don't fit in memory",
+        "用于无法放入内存的超大图像",
+    ),
+    // Half precision colors
+    ("Half precision colors", "半精度颜色"),
+    (
+        "Store the colors of viewed splats at half precision, to use less memory for big scenes",
+        "以半精度存储所查看 splat 的颜色，使大场景占用更少内存",
+    ),
+    // Prune preview
+    ("🍂 Prune preview", "🍂 剪枝预览"),
+    (
+        "Hide the splats that contribute least to the views, to see how pruning them would look. The splats are left as they are.",
+        "隐藏对视图贡献最小的 splat，以预览剪枝后的效果。splat 本身保持不变。",
+    ),
+    ("Hidden", "隐藏"),
+    (
+        "Part of the splats to hide, starting with the ones that add the least to the views",
+        "要隐藏的 splat 比例，从对视图贡献最小的开始",
+    ),
+    ("Measuring contribution…", "正在测量贡献…"),
+    // Density presets
+    ("Density", "密度"),
+    ("Custom", "自定义"),
+    ("Quality", "质量"),
+    ("Balanced", "均衡"),
+    ("Compact", "紧凑"),
+    (
+        "How many splats to add while training. Compact trains fewer splats, for small exports that render fast.",
+        "训练时添加多少 splat。紧凑模式训练更少的 splat，导出文件更小、渲染更快。",
+    ),
+    // Scene scale
+    (
+        "Scales the learning rate of the splat positions and the refine thresholds, so small and large scenes train with the same settings",
+        "缩放 splat 位置的学习率和细化阈值，使小场景和大场景都能用相同的设置训练",
+    ),
+    ("estimated", "估计"),
+    ("set", "设定"),
+    // Eval
+    ("Evaluate in the background", "在后台评估"),
+    (
+        "Keep training while evaluating, instead of pausing",
+        "评估时继续训练，而不是暂停",
+    ),
+    (
+        "Averaged over all eval views, ± the 95% confidence interval",
+        "所有评估视图的平均值，± 95% 置信区间",
+    ),
+    // Background GPU
+    ("Background GPU", "后台 GPU"),
+    ("eg. discrete:1", "例如 discrete:1"),
+    (
+        "Run evals and exports on another GPU, so they don't slow down training. The GPU that trains can be picked too, which gives them their own queue on it.",
+        "在另一个 GPU 上运行评估和导出，以免拖慢训练。也可以选择正在训练的 GPU，这样它们会在该 GPU 上使用独立的队列。",
+    ),
+    // Dataset stats
+    ("Dataset stats", "数据集统计"),
+    ("No dataset loaded.", "未加载数据集。"),
+    ("Views", "视图"),
+    ("train", "训练"),
+    ("eval", "评估"),
+    ("Resolutions", "分辨率"),
+    ("Other", "其他"),
+    ("Camera models", "相机模型"),
+    ("Sparse points", "稀疏点"),
+    (
+        "Mean distance between where the sparse points were seen, and where the cameras project them. A few pixels or more means the poses are off.",
+        "稀疏点被观测到的位置与相机投影位置之间的平均距离。达到几个像素或更多说明位姿不准确。",
+    ),
+    ("Extent", "范围"),
+    ("Size of the box around the cameras", "包围相机的盒子大小"),
+    ("Capture density", "拍摄密度"),
+    (
+        "Median distance from a view to the closest other view. Views far apart compared to the scene see too little overlap to train well.",
+        "每个视图到最近的其他视图的距离中位数。相对于场景而言相距太远的视图重叠太少，难以训练好。",
+    ),
+    ("a view every", "每隔"),
+    // Feature matches
+    ("Feature matches", "特征匹配"),
+    (
+        "This view has no sparse points in common with other views.",
+        "此视图与其他视图没有共同的稀疏点。",
+    ),
+    ("Matched with", "匹配视图"),
+    ("shared points", "个共同点"),
+    (
+        "Hover a match to see its epipolar line in the other image. Matches should lie on the line, if not, the poses or intrinsics are off.",
+        "将鼠标悬停在匹配上可查看其在另一张图像中的极线。匹配点应位于线上，否则位姿或内参有误。",
+    ),
+    // Export volumes
+    ("📦 Export volumes", "📦 导出范围"),
+    (
+        "Exports only keep the splats inside these volumes. Without any, all splats are kept.",
+        "导出时只保留这些范围内的 splat。没有范围时保留所有 splat。",
+    ),
+    ("Box", "长方体"),
+    ("Sphere", "球体"),
+    ("Center", "中心"),
+    ("Size", "大小"),
+    ("Rotation", "旋转"),
+    ("➕ Box", "➕ 长方体"),
+    ("➕ Sphere", "➕ 球体"),
+    ("Soft edge", "柔和边缘"),
+    (
+        "Fade out the splats up to this far outside the volumes",
+        "在范围外这个距离内逐渐淡出 splat",
+    ),
+    ("Show outlines", "显示轮廓"),
+    ("Load…", "加载…"),
+    ("Save…", "保存…"),
+    (
+        "Save the volumes to use with --export-volumes",
+        "保存范围，以便与 --export-volumes 一起使用",
+    ),
+    // Auto-orient
+    ("🧭 Auto-orient", "🧭 自动定向"),
+    (
+        "Find the floor, then turn the splats upright and move the floor to height zero",
+        "找到地面，然后将 splat 摆正并把地面移到高度零",
+    ),
+    (
+        "Couldn't find a floor to stand the splats on.",
+        "找不到可放置 splat 的地面。",
+    ),
+    // Orbit pivot
+    ("Frame the selection or model", "将选区或模型置于视野中"),
+    // Smooth camera
+    ("🎞 Smooth camera", "🎞 平滑相机"),
+    (
+        "Ease the camera into bookmarks and views, and let it glide on after orbiting or panning",
+        "跳转到书签和视图时平滑过渡相机，并在环绕或平移后让其继续滑行",
+    ),
+    // Camera panel
+    ("Field of view", "视场角"),
+    ("Focal length", "焦距"),
+    (
+        "The focal length giving this field of view on a full frame (35mm) camera",
+        "在全画幅（35mm）相机上得到此视场角的焦距",
+    ),
+    ("Roll", "滚转"),
+    ("Nearest training view:", "最近的训练视图："),
+    ("Match its intrinsics", "匹配其内参"),
+    (
+        "Use the field of view, principal point and aspect ratio of this view, keeping the camera where it is",
+        "使用此视图的视场角、主点和宽高比，相机位置保持不变",
+    ),
+    (
+        "Load a dataset to match the intrinsics of its cameras.",
+        "加载数据集以匹配其相机内参。",
+    ),
+    ("Fill the viewport", "填满视口"),
+    (
+        "Stop matching the aspect ratio of a view, and center the principal point",
+        "不再匹配视图的宽高比，并将主点居中",
+    ),
+    // Training view
+    ("Show the last training view", "显示最近的训练视图"),
+    (
+        "The render and ground truth of the view trained on last, the error between them, and the gradient of the loss for each pixel",
+        "最近训练的视图的渲染与真值、两者之间的误差，以及每个像素的损失梯度",
+    ),
+    (
+        "Waiting for the next training step…",
+        "正在等待下一个训练步骤…",
+    ),
+    ("Render", "渲染"),
+    ("Ground truth", "真值"),
+    ("Error", "误差"),
+    ("Loss gradient", "损失梯度"),
+    (
+        "Brighter is larger, scaled to the largest values of this step",
+        "越亮越大，按此步骤的最大值缩放",
+    ),
+    // Refine highlight
+    ("🌱 Refine highlight", "🌱 细化高亮"),
+    (
+        "After each refine, tint the splats it added green and mark the splats it pruned red for a few seconds, to see where the densify settings grow and prune splats",
+        "每次细化后，在几秒内将新增的 splat 染成绿色，并用红色标出被剪除的 splat，以便查看致密化设置在哪里增加和剪除 splat",
+    ),
+    // Snapshot diff
+    ("Snapshot diff", "快照对比"),
+    ("Snapshot", "快照"),
+    (
+        "Load or train splats to compare snapshots of them.",
+        "加载或训练 splat 后即可对比它们的快照。",
+    ),
+    ("📸 Take snapshot", "📸 拍摄快照"),
+    (
+        "Keep the current splats, to compare them with later ones, eg. after changing a setting",
+        "保留当前的 splat，以便与之后的进行对比，例如在更改设置之后",
+    ),
+    ("Before", "之前"),
+    ("After", "之后"),
+    ("Compare", "对比"),
+    ("Opacity mass", "不透明度总量"),
+    (
+        "The opacity of all splats summed",
+        "所有 splat 的不透明度之和",
+    ),
+    ("Moved mass", "移动的总量"),
+    (
+        "How much of the opacity is in other parts of the scene, from 0% (spread the same way) to 100% (nothing in common)",
+        "有多少不透明度位于场景的其他部分，从 0%（分布相同）到 100%（没有共同之处）",
+    ),
+    ("Center of mass moved", "质心移动距离"),
+    ("Seen along", "沿此轴查看"),
+    (
+        "Red where splats gained opacity, blue where they lost it, summed along the axis",
+        "沿该轴累加，红色表示 splat 增加了不透明度，蓝色表示减少了不透明度",
+    ),
+    // Splat tracking
+    ("Track splats", "跟踪高斯点"),
+    (
+        "Track when each splat was created, how often it was seen and how hard training moved it, to color the splats by in the compare menu and export with them",
+        "跟踪每个高斯点的创建时间、被看到的次数以及训练移动它的幅度，可在对比菜单中按此着色，并随高斯点一起导出",
+    ),
+    ("Color by age", "按年龄着色"),
+    (
+        "Blue for the oldest splats, red for the splats refining added last",
+        "最旧的高斯点为蓝色，最近一次细化添加的为红色",
+    ),
+    ("Color by times seen", "按被看到次数着色"),
+    (
+        "Red for the splats visible in the most training steps",
+        "在最多训练步中可见的高斯点为红色",
+    ),
+    ("Color by gradient", "按梯度着色"),
+    (
+        "Red for the splats training moved the hardest, summed over all steps",
+        "训练在所有步骤中移动幅度最大的高斯点为红色",
+    ),
+    (
+        "Turn on Track splats in the settings, or train with --track-splats",
+        "在设置中开启“跟踪高斯点”，或使用 --track-splats 训练",
+    ),
+    // Dataset hash
+    (
+        "Hash of the image paths, image sizes and camera poses. Runs with the same hash trained on the same data",
+        "图像路径、图像尺寸和相机位姿的哈希。哈希相同的运行使用了相同的数据进行训练",
+    ),
+    ("Click to copy", "点击复制"),
+    // Download cache
+    ("Cached data", "缓存的数据"),
+    (
+        "Uploaded and downloaded data is kept in the browser, to load it again without uploading or downloading it.",
+        "上传和下载的数据保存在浏览器中，无需再次上传或下载即可重新加载。",
+    ),
+    (
+        "Downloaded data is kept on disk, to load it again without downloading it. Interrupted downloads continue where they stopped.",
+        "下载的数据保存在磁盘上，无需再次下载即可重新加载。中断的下载会从停止处继续。",
+    ),
+    ("Copy the path of the download cache", "复制下载缓存的路径"),
+    ("entries", "项"),
+    ("⟳ Refresh", "⟳ 刷新"),
+    ("🗑 Clear all", "🗑 全部清除"),
+    ("Resume", "继续"),
+    ("Open", "打开"),
+    ("Remove from cache", "从缓存中移除"),
+    ("(partial)", "（不完整）"),
+    // Minimap
+    ("Click to move the camera here", "点击将相机移到此处"),
+    // Scene graph nesting
+    ("Parent", "父节点"),
+    ("None", "无"),
+    // Reference origin
+    ("Moved to the origin from", "已从此处移到原点"),
+    (
+        "Coordinates this far out don't fit in 32 bit floats, so the points were moved closer to the origin, the same way as point clouds splats start from.",
+        "如此远的坐标无法用 32 位浮点数表示，因此这些点已移近原点，与作为高斯初始化的点云相同。",
+    ),
+    // Panels, stats and plots
+    (" frames", " 帧"),
+    (" settings", " 设置"),
+    (" steps", " 步"),
+    (" steps/s", " 步/秒"),
+    ("% of the time", "% 的时间"),
+    ("1 out of ", "每 "),
+    ("Active allocations", "活动分配"),
+    ("Bins", "分箱数"),
+    (
+        "Blend splats without sorting them by depth, weighing closer splats more. Much faster for huge scenes, but less accurate where splats overlap.",
+        "不按深度排序混合高斯点，离得越近权重越大。对于超大场景快得多，但在高斯点重叠处不够准确。",
+    ),
+    ("Bytes in use", "已用字节"),
+    ("Bytes reserved", "已保留字节"),
+    ("Clear", "清除"),
+    ("Compute deviation", "计算偏差"),
+    ("Constant redraw", "持续重绘"),
+    (
+        "Copy .ply or .zip files to Brush in the Files app to open them here.",
+        "在“文件”应用中将 .ply 或 .zip 文件复制到 Brush，即可在此打开。",
+    ),
+    (
+        "Copy a link that opens the viewer at this view",
+        "复制在此视角打开查看器的链接",
+    ),
+    ("Dataset hash", "数据集哈希"),
+    ("Deviation analysis", "偏差分析"),
+    (
+        "Distance from each reference point to the surface of the splats",
+        "每个参考点到高斯点表面的距离",
+    ),
+    ("Driver", "驱动"),
+    ("Enable rerun", "启用 rerun"),
+    ("Eval PSNR", "评估 PSNR"),
+    ("Eval SSIM", "评估 SSIM"),
+    ("Export format", "导出格式"),
+    ("Export name", "导出名称"),
+    ("Frames", "帧"),
+    ("GPU memory", "GPU 内存"),
+    (
+        "Heatmap: white is on the splat surface, red is far from it.",
+        "热力图：白色表示在高斯点表面上，红色表示远离表面。",
+    ),
+    ("Import reference...", "导入参考..."),
+    ("Last eval:", "上次评估："),
+    ("Learning rates (log10)", "学习率 (log10)"),
+    ("Link steps", "联动步数"),
+    (
+        "Load a PLY, LAS/LAZ or OBJ point cloud or mesh to compare against. It's drawn over the splats, except where it's behind them.",
+        "加载 PLY、LAS/LAZ 或 OBJ 点云或网格进行比较。它会绘制在高斯点之上，被遮挡的部分除外。",
+    ),
+    (
+        "Load or train splats to see histograms of their parameters.",
+        "加载或训练高斯点以查看其参数的直方图。",
+    ),
+    ("Loading...", "加载中..."),
+    ("Log counts", "对数计数"),
+    ("Log train stats", "记录训练统计"),
+    ("Look around by moving your device", "移动设备来环顾四周"),
+    ("Loss", "损失"),
+    ("Mipnerf scenes", "Mipnerf 场景"),
+    ("Name", "名称"),
+    (
+        "Note: Running in browser is still experimental",
+        "注意：在浏览器中运行仍处于实验阶段",
+    ),
+    (
+        "Note: running in debug mode, compile with --release for best performance",
+        "注意：正在以调试模式运行，使用 --release 编译以获得最佳性能",
+    ),
+    ("Nothing loaded yet.", "尚未加载任何内容。"),
+    (
+        "Open the brush_blueprint.rbl in the rerun viewer for a good default layout.",
+        "在 rerun 查看器中打开 brush_blueprint.rbl 以获得良好的默认布局。",
+    ),
+    ("Points", "点数"),
+    ("Projected splats", "投影的高斯点"),
+    ("Rerun Settings", "Rerun 设置"),
+    ("Reset transform", "重置变换"),
+    ("SH Degree", "SH 阶数"),
+    ("Scene scale", "场景尺度"),
+    (
+        "Scroll to zoom, drag to pan and double click to reset.",
+        "滚动缩放，拖动平移，双击重置。",
+    ),
+    ("Shared files", "共享文件"),
+    ("Splat count", "高斯点数量"),
+    (
+        "Start training to see plots of the training progress.",
+        "开始训练以查看训练进度图表。",
+    ),
+    ("Steps/s", "步/秒"),
+    ("Sync scopes", "同步范围"),
+    ("Synthetic blender scenes", "合成 Blender 场景"),
+    ("Time remaining", "剩余时间"),
+    ("Train step", "训练步数"),
+    ("Training time", "训练时间"),
+    ("Type", "类型"),
+    (
+        "Use a COLMAP fused.ply instead of the sparse points, if available",
+        "如果有，使用 COLMAP 的 fused.ply 代替稀疏点",
+    ),
+    ("Visualize splats", "可视化高斯点"),
+    ("Visualize splats every", "可视化高斯点的间隔"),
+    ("Zoom and pan all plots together", "同时缩放和平移所有图表"),
+    ("every ", "每 "),
+    ("step", "步数"),
+    (
+        "{iter} is replaced by the step, {timestamp} by the current time.",
+        "{iter} 会被替换为步数，{timestamp} 会被替换为当前时间。",
+    ),
+    ("• Left click and drag to orbit", "• 左键拖动以环绕"),
+    (
+        "• Middle click, or left click + control, and drag to pan",
+        "• 中键，或左键 + Control，拖动以平移",
+    ),
+    (
+        "• Right click, or left click + spacebar, and drag to look around.",
+        "• 右键，或左键 + 空格键，拖动以环顾四周。",
+    ),
+    ("• Scroll to zoom", "• 滚动以缩放"),
+    ("• Shift to move faster", "• 按住 Shift 移动更快"),
+    (
+        "• WASD to fly, Q&E to move up & down.",
+        "• WASD 飞行，Q 和 E 上下移动。",
+    ),
+    (
+        "• Z&C to roll, X to reset roll",
+        "• Z 和 C 滚转，X 重置滚转",
+    ),
+    ("⬆ Export CSV", "⬆ 导出 CSV"),
+    ("⬆ Export report", "⬆ 导出报告"),
+    ("Mean", "平均值"),
+    ("RMS", "均方根"),
+    ("Median", "中位数"),
+    ("95th pct", "第 95 百分位"),
+    ("Max", "最大值"),
+    // Panels, stats and plots
+    (
+        "The export is ready, send it to another app",
+        "导出已就绪，发送到其他应用",
+    ),
+    ("📤 Preparing export…", "📤 正在准备导出…"),
+    // Panels, stats and plots
+    ("Prune exports too", "导出时也剪枝"),
+    (
+        "Leave the hidden splats out of exports and shared files",
+        "导出和分享的文件中不包含隐藏的 splat",
+    ),
+    (
+        "The contribution of the splats is still being measured",
+        "仍在测量 splat 的贡献",
+    ),
+    // Panels, stats and plots
+    (
+        "Stats show once the dataset is loaded.",
+        "数据集加载完成后显示统计信息。",
+    ),
+    ("Computing dataset stats…", "正在计算数据集统计信息…"),
+    // Panels, stats and plots
+    ("⬆ Waiting for the eval…", "⬆ 等待评估完成…"),
+    (
+        "The export includes the results of the eval that's running",
+        "导出将包含正在运行的评估结果",
+    ),
+];
+
+const JA: &[(&str, &str)] = &[
+    // Panels
+    ("Scene", "シーン"),
+    ("Settings", "設定"),
+    ("Presets", "プリセット"),
+    ("Stats", "統計"),
+    ("Plots", "グラフ"),
+    ("Histograms", "ヒストグラム"),
+    ("Scene graph", "シーングラフ"),
+    ("Dataset", "データセット"),
+    // Settings
+    ("Language", "言語"),
+    ("Model Settings", "モデル設定"),
+    ("Spherical Harmonics Degree:", "球面調和関数の次数："),
+    ("Max image resolution", "最大画像解像度"),
+    ("Limit max frames", "最大フレーム数を制限"),
+    ("Initialize from dense point cloud", "高密度点群から初期化"),
+    ("Split dataset for evaluation", "評価用にデータセットを分割"),
+    ("Training Settings", "学習設定"),
+    ("Current Training", "現在の学習"),
+    (
+        "Changes apply to the current run right away.",
+        "変更は現在の学習にすぐ反映されます。",
+    ),
+    ("Mean learning rate", "位置の学習率"),
+    ("Final mean learning rate", "位置の最終学習率"),
+    ("Color learning rate", "色の学習率"),
+    ("Opacity learning rate", "不透明度の学習率"),
+    ("Scale learning rate", "スケールの学習率"),
+    ("Rotation learning rate", "回転の学習率"),
+    ("SSIM weight", "SSIM の重み"),
+    ("Refine", "リファイン"),
+    ("Limit number of splats", "スプラット数を制限"),
+    ("Train", "学習"),
+    ("Process Settings", "処理設定"),
+    ("Evaluate", "評価"),
+    ("Export", "エクスポート"),
+    (
+        "Select a .ply to visualize, or a .zip with training data.",
+        "表示する .ply、または学習データの .zip を選択してください。",
+    ),
+    ("Load file", "ファイルを開く"),
+    ("Load directory", "フォルダを開く"),
+    ("Load URL", "URL を開く"),
+    // Scene
+    (
+        "Load a ply file or dataset to get started.",
+        "ply ファイルかデータセットを読み込んで始めましょう。",
+    ),
+    ("Loading... Please wait.", "読み込み中…お待ちください。"),
+    ("⏸ paused", "⏸ 一時停止中"),
+    ("⏵ playing", "⏵ 再生中"),
+    ("⏵ training", "⏵ 学習中"),
+    ("🔴 Live update splats", "🔴 ライブ更新"),
+    ("⬆ Export", "⬆ エクスポート"),
+    ("📤 Share", "📤 共有"),
+    (
+        "Export the splats and send them to another app",
+        "スプラットをエクスポートして他のアプリに送る",
+    ),
+    ("📂 Show export", "📂 エクスポートを表示"),
+    ("🗺 Minimap", "🗺 ミニマップ"),
+    ("⚖ Compare", "⚖ 比較"),
+    ("Pin current splats", "現在のスプラットを固定"),
+    ("Pinned", "固定済み"),
+    ("Open .ply file…", ".ply ファイルを開く…"),
+    ("Stop comparing", "比較を終了"),
+    ("Flip", "切り替え"),
+    ("Side by side", "左右に並べる"),
+    ("Current", "現在"),
+    (
+        "Flip between compared splats",
+        "比較するスプラットを切り替え",
+    ),
+    ("Error heatmap", "誤差ヒートマップ"),
+    (
+        "Color the splats by how badly they match the eval views, to find areas that need more photos",
+        "評価ビューとの誤差でスプラットを色分けし、追加の写真が必要な場所を見つけます",
+    ),
+    ("Views with the largest error", "誤差が最も大きいビュー"),
+    ("✂ Clipping", "✂ クリッピング"),
+    ("Near plane", "ニアクリップ面"),
+    (
+        "Hide splats closer to the camera than this",
+        "カメラからこの距離より近いスプラットを隠す",
+    ),
+    ("Max distance", "最大距離"),
+    (
+        "Hide splats further from the camera than this, which also makes huge scenes faster to render",
+        "カメラからこの距離より遠いスプラットを隠します。巨大なシーンの描画も速くなります",
+    ),
+    ("Reset", "リセット"),
+    ("🔪 Section", "🔪 断面"),
+    (
+        "Slice through the splats with a plane, to look inside",
+        "平面でスプラットを切断し、内部を見ます",
+    ),
+    ("View", "ビュー"),
+    ("Offset", "オフセット"),
+    ("Flip side", "反対側を切る"),
+    ("Highlight cut", "断面を強調表示"),
+    ("📌 Annotate", "📌 注釈"),
+    (
+        "Click on the splats to add a note there",
+        "スプラットをクリックしてメモを追加",
+    ),
+    ("Note", "メモ"),
+    ("Annotations", "注釈"),
+    ("Import JSON…", "JSON をインポート…"),
+    ("Export JSON…", "JSON をエクスポート…"),
+    (
+        "Turn on 📌 Annotate in the scene, and click on the splats to add a note.",
+        "シーンで 📌 注釈 をオンにし、スプラットをクリックしてメモを追加します。",
+    ),
+    ("Look at this note", "このメモを見る"),
+    ("🖌 Paint", "🖌 ペイント"),
+    (
+        "Paint the color or opacity of the splats under the brush. Use the right or middle mouse button to move the camera while painting.",
+        "ブラシの下のスプラットの色や不透明度を塗ります。塗っている間は右ボタンか中ボタンでカメラを動かせます。",
+    ),
+    ("↶ Undo", "↶ 元に戻す"),
+    ("Color", "色"),
+    ("Opacity", "不透明度"),
+    ("Scale", "倍率"),
+    ("Radius", "半径"),
+    ("Hardness", "硬さ"),
+    ("Strength", "強さ"),
+    ("Select", "選択"),
+    ("Deselect", "選択解除"),
+    ("selected", "個選択中"),
+    ("Select all", "すべて選択"),
+    (
+        "Select all splats that aren't cut away by the section cut",
+        "断面で切り取られていないすべてのスプラットを選択",
+    ),
+    ("Clear selection", "選択をクリア"),
+    ("⇋ Mirror", "⇋ ミラー"),
+    (
+        "Add a mirrored copy of the selection on the other side of the cut",
+        "選択範囲を反転したコピーを断面の反対側に追加",
+    ),
+    (
+        "Cut along X, Y or Z with 🔪 Section to place the plane to mirror through",
+        "🔪 断面で X、Y、Z 軸に沿って切ると、ミラーの平面を配置できます",
+    ),
+    ("⧉ Duplicate", "⧉ 複製"),
+    ("Copies", "コピー数"),
+    ("Duplicate", "複製"),
+    ("💡 Shading", "💡 シェーディング"),
+    (
+        "Light the splats or color them by their normals, to judge the shape of the splats without their colors",
+        "スプラットをライティングするか法線で色付けして、色なしでスプラットの形状を確認します",
+    ),
+    ("Lit", "ライティング"),
+    ("Normals", "法線"),
+    ("Light direction", "光の方向"),
+    ("Light height", "光の高さ"),
+    ("Ambient light", "環境光"),
+    ("⚡ Fast blending", "⚡ 高速ブレンド"),
+    ("📱 Look around", "📱 見回す"),
+    ("🔗 Copy share link", "🔗 共有リンクをコピー"),
+    ("Controls", "操作方法"),
+    // Shortcuts
+    ("Shortcuts", "ショートカット"),
+    ("Keyboard shortcuts", "キーボードショートカット"),
+    (
+        "Shortcuts can be changed in the Shortcuts panel.",
+        "ショートカットはショートカットパネルで変更できます。",
+    ),
+    (
+        "Click a shortcut and press the new keys. Escape cancels, backspace removes the shortcut.",
+        "ショートカットをクリックして新しいキーを押してください。Esc でキャンセル、Backspace で削除します。",
+    ),
+    ("Press a key…", "キーを押してください…"),
+    ("Reset to defaults", "初期設定に戻す"),
+    ("Pause / resume", "一時停止 / 再開"),
+    ("Export splats", "スプラットをエクスポート"),
+    ("Save screenshot", "スクリーンショットを保存"),
+    ("Show / hide panels", "パネルの表示 / 非表示"),
+    (
+        "Switch between orbit and fly",
+        "オービット / フライ切り替え",
+    ),
+    ("Show shortcuts", "ショートカットを表示"),
+    ("Save camera bookmark", "カメラのブックマークを保存"),
+    ("Go to camera bookmark", "カメラのブックマークへ移動"),
+    ("Reset layout", "レイアウトをリセット"),
+    // Preprocessing
+    ("Preprocess", "前処理"),
+    ("Preprocessing", "前処理"),
+    (
+        "Load a dataset to preview how it is preprocessed.",
+        "データセットを読み込むと前処理をプレビューできます。",
+    ),
+    ("Show mask", "マスクを表示"),
+    (
+        "This image has no mask or transparency.",
+        "この画像にはマスクや透明度がありません。",
+    ),
+    ("Crop around the center", "中央を切り抜く"),
+    ("Skip blurry frames", "ぼやけたフレームを除外"),
+    (
+        "Sharpness is the variance of the Laplacian of the image. Blurry images have few edges and score low.",
+        "シャープネスは画像のラプラシアンの分散です。ぼやけた画像はエッジが少なく、スコアが低くなります。",
+    ),
+    ("Min sharpness", "最小シャープネス"),
+    ("Skip underexposed frames", "露出不足のフレームを除外"),
+    ("Skip overexposed frames", "露出オーバーのフレームを除外"),
+    ("Max black pixels", "黒いピクセルの上限"),
+    ("Max blown out pixels", "白飛びピクセルの上限"),
+    ("This frame", "このフレーム"),
+    ("black", "黒"),
+    ("blown out", "白飛び"),
+    ("frames would be skipped", "フレームが除外されます"),
+    ("Measuring frame quality…", "フレームの品質を測定中…"),
+    ("Skip duplicate frames", "重複フレームを除外"),
+    (
+        "Skip frames that look nearly the same as the frame before them, while the camera barely moved.",
+        "カメラがほとんど動かず、前のフレームとほぼ同じに見えるフレームを除外します。",
+    ),
+    ("Min similarity", "最小類似度"),
+    ("Similarity to the previous frame", "前のフレームとの類似度"),
+    (
+        "Match exposure and white balance",
+        "露出とホワイトバランスを揃える",
+    ),
+    ("Known color corrections", "既知の色補正"),
+    (
+        "A JSON file in the dataset, as exported with the splats.",
+        "データセット内の JSON ファイル（スプラットと一緒にエクスポートされたもの）。",
+    ),
+    ("⬆ Export color corrections", "⬆ 色補正をエクスポート"),
+    (
+        "Adjust the colors of all frames to match the first frame, for cameras that used automatic exposure or white balance.",
+        "自動露出や自動ホワイトバランスを使ったカメラ向けに、すべてのフレームの色を最初のフレームに合わせます。",
+    ),
+    ("Reload with these settings", "この設定で再読み込み"),
+    (
+        "The settings haven't changed.",
+        "設定は変更されていません。",
+    ),
+    (
+        "This data can't be opened again, open it from the settings panel instead.",
+        "このデータは再度開けません。設定パネルから開いてください。",
+    ),
+    // Recent data
+    ("Recently opened", "最近開いたもの"),
+    ("Remove", "削除"),
+    // Refine log
+    ("Refine log", "リファインログ"),
+    (
+        "Nothing has been refined yet.",
+        "まだリファインされていません。",
+    ),
+    ("Step", "ステップ"),
+    ("Split", "分割"),
+    ("Cloned", "複製"),
+    ("Transparent", "透明"),
+    ("Too big", "大きすぎる"),
+    ("Splats", "スプラット数"),
+    ("Opacity reset", "不透明度リセット"),
+    // Scripts
+    ("Script", "スクリプト"),
+    ("▶ Run", "▶ 実行"),
+    ("⏹ Stop", "⏹ 停止"),
+    ("commands left", "個のコマンドが残っています"),
+    ("Stopped the script", "スクリプトを停止しました"),
+    ("Functions", "関数"),
+    // Sessions
+    ("Stop this session", "このセッションを停止"),
+    // Background
+    ("In the background", "バックグラウンド時"),
+    ("Keep training", "学習を続ける"),
+    ("Train slower", "学習を遅くする"),
+    ("Pause training", "学習を一時停止"),
+    // Rate limit
+    ("Rate limit", "速度制限"),
+    ("Limit steps per second", "1秒あたりのステップ数を制限"),
+    ("Only train part of the time", "一部の時間だけ学習する"),
+    (
+        "Sleep between steps, so other applications get to use the GPU as well.",
+        "ステップの間に休止し、他のアプリケーションも GPU を使えるようにします。",
+    ),
+    (
+        "When the window isn't focused, is minimized, or is a hidden browser tab",
+        "ウィンドウにフォーカスがない、最小化されている、またはブラウザのタブが非表示のとき",
+    ), // Warnings
+    ("Dismiss", "閉じる"),
+    // Updates
+    ("Check for updates on startup", "起動時にアップデートを確認"),
+    ("Check now", "今すぐ確認"),
+    ("Brush is up to date", "Brush は最新です"),
+    (
+        "A new version is available:",
+        "新しいバージョンがあります：",
+    ),
+    ("Update", "アップデート"),
+    ("Downloading Brush", "Brush をダウンロード中"),
+    ("Updated to Brush", "Brush をアップデートしました："),
+    ("restart Brush to use it.", "再起動すると使えます。"),
+    ("Release notes", "リリースノート"),
+    // Debug bundle
+    ("🐞 Save debug bundle…", "🐞 デバッグバンドルを保存…"),
+    (
+        "Save the GPU, settings, last error and recent logs to a zip, to attach to a GitHub issue. Nothing is sent anywhere.",
+        "GPU、設定、最後のエラー、最近のログを zip に保存し、GitHub の issue に添付できるようにします。どこにも送信されません。",
+    ),
+    ("Include dataset info", "データセットの情報を含める"),
+    (
+        "The number of views, and the cameras and image sizes of the first views",
+        "ビューの数と、最初のいくつかのビューのカメラと画像サイズ",
+    ),
+    // Logging
+    ("Log levels", "ログレベル"),
+    ("set on the command line", "コマンドラインで設定済み"),
+    (
+        "A level, or levels per module, eg. info,brush_train=debug",
+        "レベル、またはモジュールごとのレベル。例: info,brush_train=debug",
+    ),
+    ("📂 Show logs", "📂 ログを表示"),
+    // Formatting
+    ("Units", "単位"),
+    ("Metric", "メートル法"),
+    ("Imperial", "ヤード・ポンド法"),
+    ("Numbers", "数値"),
+    ("24 hour clock", "24 時間表示"),
+    // Color
+    ("☀ Exposure", "☀ 露出"),
+    (
+        "Only changes how the splats are shown, not the splats or exports",
+        "スプラットの表示だけを変更し、スプラット自体やエクスポートは変更しません",
+    ),
+    ("Exposure", "露出"),
+    ("Gamma", "ガンマ"),
+    ("Compare colors in", "色の比較空間"),
+    ("Linear light", "リニア光"),
+    (
+        "sRGB weighs errors like they're seen, linear light weighs bright areas more",
+        "sRGB は見た目どおりに誤差を評価し、リニア光は明るい領域をより重視します",
+    ),
+    // Intrinsics
+    ("Camera intrinsics", "カメラの内部パラメータ"),
+    ("Camera", "カメラ"),
+    ("views", "ビュー"),
+    ("Focal length (px)", "焦点距離（ピクセル）"),
+    ("Principal point (px)", "主点（ピクセル）"),
+    ("Radial distortion", "放射方向の歪み"),
+    ("Tangential distortion", "接線方向の歪み"),
+    ("Reprojection error", "再投影誤差"),
+    ("was", "変更前"),
+    (
+        "No sparse points to measure the reprojection error with.",
+        "再投影誤差を測るための疎な点がありません。",
+    ),
+    (
+        "Reload with these intrinsics",
+        "この内部パラメータで再読み込み",
+    ),
+    (
+        "The intrinsics haven't changed.",
+        "内部パラメータは変更されていません。",
+    ),
+    // Motion blur
+    ("Model motion blur", "モーションブラーをモデル化"),
+    (
+        "For blurry frames. Renders each step several times, so training is slower",
+        "ブレたフレーム向けです。各ステップで複数回レンダリングするため、学習が遅くなります",
+    ),
+    ("up to ", "最大 "),
+    // Defocus
+    ("Model defocus", "ピンぼけをモデル化"),
+    (
+        "For shallow depth of field, like macro or portrait shots",
+        "マクロやポートレートなど、被写界深度が浅い撮影向け",
+    ),
+    // Crop training
+    ("Train on crops", "切り抜きで学習"),
+    (
+        "For very large images that don't fit in memory",
+        "メモリに収まらない非常に大きな画像向け",
+    ),
+    // Half precision colors
+    ("Half precision colors", "半精度の色"),
+    (
+        "Store the colors of viewed splats at half precision, to use less memory for big scenes",
+        "表示中のスプラットの色を半精度で保存し、大きなシーンのメモリ使用量を減らします",
+    ),
+    // Prune preview
+    ("🍂 Prune preview", "🍂 剪定プレビュー"),
+    (
+        "Hide the splats that contribute least to the views, to see how pruning them would look. The splats are left as they are.",
+        "ビューへの寄与が最も小さいスプラットを隠し、剪定した場合の見た目を確認します。スプラット自体はそのままです。",
+    ),
+    ("Hidden", "非表示"),
+    (
+        "Part of the splats to hide, starting with the ones that add the least to the views",
+        "隠すスプラットの割合。ビューへの寄与が最も小さいものから隠します",
+    ),
+    ("Measuring contribution…", "寄与を計測中…"),
+    // Density presets
+    ("Density", "密度"),
+    ("Custom", "カスタム"),
+    ("Quality", "品質"),
+    ("Balanced", "バランス"),
+    ("Compact", "コンパクト"),
+    (
+        "How many splats to add while training. Compact trains fewer splats, for small exports that render fast.",
+        "学習中に追加するスプラットの量。コンパクトはスプラットを少なくし、小さく高速に描画できるエクスポートにします。",
+    ),
+    // Scene scale
+    (
+        "Scales the learning rate of the splat positions and the refine thresholds, so small and large scenes train with the same settings",
+        "スプラット位置の学習率とリファインのしきい値をスケールし、小さなシーンも大きなシーンも同じ設定で学習できるようにします",
+    ),
+    ("estimated", "推定"),
+    ("set", "指定"),
+    // Eval
+    ("Evaluate in the background", "バックグラウンドで評価"),
+    (
+        "Keep training while evaluating, instead of pausing",
+        "評価中も一時停止せずに学習を続けます",
+    ),
+    (
+        "Averaged over all eval views, ± the 95% confidence interval",
+        "すべての評価ビューの平均。± は 95% 信頼区間です",
+    ),
+    // Background GPU
+    ("Background GPU", "バックグラウンド GPU"),
+    ("eg. discrete:1", "例: discrete:1"),
+    (
+        "Run evals and exports on another GPU, so they don't slow down training. The GPU that trains can be picked too, which gives them their own queue on it.",
+        "評価とエクスポートを別の GPU で実行し、学習を遅くしないようにします。学習中の GPU を選ぶこともでき、その場合は同じ GPU 上の専用キューを使います。",
+    ),
+    // Dataset stats
+    ("Dataset stats", "データセット統計"),
+    ("No dataset loaded.", "データセットが読み込まれていません。"),
+    ("Views", "ビュー"),
+    ("train", "学習"),
+    ("eval", "評価"),
+    ("Resolutions", "解像度"),
+    ("Other", "その他"),
+    ("Camera models", "カメラモデル"),
+    ("Sparse points", "スパース点"),
+    (
+        "Mean distance between where the sparse points were seen, and where the cameras project them. A few pixels or more means the poses are off.",
+        "スパース点が観測された位置と、カメラが投影する位置との平均距離。数ピクセル以上ある場合は姿勢がずれています。",
+    ),
+    ("Extent", "範囲"),
+    (
+        "Size of the box around the cameras",
+        "カメラを囲むボックスの大きさ",
+    ),
+    ("Capture density", "撮影密度"),
+    (
+        "Median distance from a view to the closest other view. Views far apart compared to the scene see too little overlap to train well.",
+        "各ビューから最も近い別のビューまでの距離の中央値。シーンに対して離れすぎたビューは重なりが少なく、うまく学習できません。",
+    ),
+    ("a view every", "ビュー間隔"),
+    // Feature matches
+    ("Feature matches", "特徴点マッチ"),
+    (
+        "This view has no sparse points in common with other views.",
+        "このビューは他のビューと共通のスパース点がありません。",
+    ),
+    ("Matched with", "マッチ先"),
+    ("shared points", "個の共通点"),
+    (
+        "Hover a match to see its epipolar line in the other image. Matches should lie on the line, if not, the poses or intrinsics are off.",
+        "マッチにカーソルを合わせると、もう一方の画像でのエピポーラ線が表示されます。マッチは線上にあるはずで、そうでなければ姿勢か内部パラメータがずれています。",
+    ),
+    // Export volumes
+    ("📦 Export volumes", "📦 エクスポート範囲"),
+    (
+        "Exports only keep the splats inside these volumes. Without any, all splats are kept.",
+        "エクスポートではこれらの範囲内のスプラットのみを残します。範囲がなければすべて残します。",
+    ),
+    ("Box", "ボックス"),
+    ("Sphere", "球"),
+    ("Center", "中心"),
+    ("Size", "サイズ"),
+    ("Rotation", "回転"),
+    ("➕ Box", "➕ ボックス"),
+    ("➕ Sphere", "➕ 球"),
+    ("Soft edge", "ソフトエッジ"),
+    (
+        "Fade out the splats up to this far outside the volumes",
+        "範囲の外側この距離までスプラットをフェードアウトします",
+    ),
+    ("Show outlines", "輪郭を表示"),
+    ("Load…", "読み込み…"),
+    ("Save…", "保存…"),
+    (
+        "Save the volumes to use with --export-volumes",
+        "--export-volumes で使うために範囲を保存します",
+    ),
+    // Auto-orient
+    ("🧭 Auto-orient", "🧭 自動向き調整"),
+    (
+        "Find the floor, then turn the splats upright and move the floor to height zero",
+        "床を見つけ、スプラットをまっすぐに立てて床を高さ 0 に移動します",
+    ),
+    (
+        "Couldn't find a floor to stand the splats on.",
+        "スプラットを置く床が見つかりませんでした。",
+    ),
+    // Orbit pivot
+    (
+        "Frame the selection or model",
+        "選択範囲またはモデルを画面に収める",
+    ),
+    // Smooth camera
+    ("🎞 Smooth camera", "🎞 なめらかなカメラ"),
+    (
+        "Ease the camera into bookmarks and views, and let it glide on after orbiting or panning",
+        "ブックマークやビューへカメラをなめらかに移動し、回転やパンの後も慣性で動かします",
+    ),
+    // Camera panel
+    ("Field of view", "画角"),
+    ("Focal length", "焦点距離"),
+    (
+        "The focal length giving this field of view on a full frame (35mm) camera",
+        "フルサイズ（35mm）カメラでこの画角になる焦点距離",
+    ),
+    ("Roll", "ロール"),
+    ("Nearest training view:", "最も近い学習ビュー："),
+    ("Match its intrinsics", "その内部パラメータに合わせる"),
+    (
+        "Use the field of view, principal point and aspect ratio of this view, keeping the camera where it is",
+        "カメラの位置はそのままで、このビューの画角・主点・アスペクト比を使います",
+    ),
+    (
+        "Load a dataset to match the intrinsics of its cameras.",
+        "データセットを読み込むと、そのカメラの内部パラメータに合わせられます。",
+    ),
+    ("Fill the viewport", "ビューポート全体に表示"),
+    (
+        "Stop matching the aspect ratio of a view, and center the principal point",
+        "ビューのアスペクト比に合わせるのをやめ、主点を中央に戻します",
+    ),
+    // Training view
+    ("Show the last training view", "最新の学習ビューを表示"),
+    (
+        "The render and ground truth of the view trained on last, the error between them, and the gradient of the loss for each pixel",
+        "最後に学習したビューのレンダリングと正解画像、その誤差、各ピクセルの損失の勾配",
+    ),
+    (
+        "Waiting for the next training step…",
+        "次の学習ステップを待っています…",
+    ),
+    ("Render", "レンダリング"),
+    ("Ground truth", "正解画像"),
+    ("Error", "誤差"),
+    ("Loss gradient", "損失の勾配"),
+    (
+        "Brighter is larger, scaled to the largest values of this step",
+        "明るいほど大きく、このステップの最大値に合わせて調整されます",
+    ),
+    // Refine highlight
+    ("🌱 Refine highlight", "🌱 リファイン強調表示"),
+    (
+        "After each refine, tint the splats it added green and mark the splats it pruned red for a few seconds, to see where the densify settings grow and prune splats",
+        "リファインのたびに、追加されたスプラットを数秒間緑に、削除されたスプラットを赤で表示し、高密度化の設定がどこでスプラットを増やし削除するかを確認できます",
+    ),
+    // Snapshot diff
+    ("Snapshot diff", "スナップショット比較"),
+    ("Snapshot", "スナップショット"),
+    (
+        "Load or train splats to compare snapshots of them.",
+        "スプラットを読み込むか学習すると、そのスナップショットを比較できます。",
+    ),
+    ("📸 Take snapshot", "📸 スナップショットを撮る"),
+    (
+        "Keep the current splats, to compare them with later ones, eg. after changing a setting",
+        "現在のスプラットを保持し、設定変更後などの後のものと比較します",
+    ),
+    ("Before", "前"),
+    ("After", "後"),
+    ("Compare", "比較"),
+    ("Opacity mass", "不透明度の総量"),
+    (
+        "The opacity of all splats summed",
+        "全スプラットの不透明度の合計",
+    ),
+    ("Moved mass", "移動した量"),
+    (
+        "How much of the opacity is in other parts of the scene, from 0% (spread the same way) to 100% (nothing in common)",
+        "不透明度のうちシーンの別の部分にある割合。0%（同じ分布）から 100%（共通部分なし）まで",
+    ),
+    ("Center of mass moved", "重心の移動距離"),
+    ("Seen along", "見る方向"),
+    (
+        "Red where splats gained opacity, blue where they lost it, summed along the axis",
+        "軸方向に合計し、スプラットの不透明度が増えた所を赤、減った所を青で示します",
+    ),
+    // Splat tracking
+    ("Track splats", "スプラットを追跡"),
+    (
+        "Track when each splat was created, how often it was seen and how hard training moved it, to color the splats by in the compare menu and export with them",
+        "各スプラットの作成時期、見えた回数、学習で動かされた強さを追跡し、比較メニューでの色分けやエクスポートに使います",
+    ),
+    ("Color by age", "経過で色分け"),
+    (
+        "Blue for the oldest splats, red for the splats refining added last",
+        "最も古いスプラットは青、最後のリファインで追加されたものは赤",
+    ),
+    ("Color by times seen", "見えた回数で色分け"),
+    (
+        "Red for the splats visible in the most training steps",
+        "最も多くの学習ステップで見えたスプラットは赤",
+    ),
+    ("Color by gradient", "勾配で色分け"),
+    (
+        "Red for the splats training moved the hardest, summed over all steps",
+        "全ステップを通じて学習で最も強く動かされたスプラットは赤",
+    ),
+    (
+        "Turn on Track splats in the settings, or train with --track-splats",
+        "設定で「スプラットを追跡」をオンにするか、--track-splats で学習してください",
+    ),
+    // Dataset hash
+    (
+        "Hash of the image paths, image sizes and camera poses. Runs with the same hash trained on the same data",
+        "画像のパス、画像サイズ、カメラ姿勢のハッシュです。ハッシュが同じ実行は同じデータで学習しています",
+    ),
+    ("Click to copy", "クリックしてコピー"),
+    // Download cache
+    ("Cached data", "キャッシュされたデータ"),
+    (
+        "Uploaded and downloaded data is kept in the browser, to load it again without uploading or downloading it.",
+        "アップロードやダウンロードしたデータはブラウザに保存され、再度アップロードやダウンロードせずに読み込めます。",
+    ),
+    (
+        "Downloaded data is kept on disk, to load it again without downloading it. Interrupted downloads continue where they stopped.",
+        "ダウンロードしたデータはディスクに保存され、再度ダウンロードせずに読み込めます。中断されたダウンロードは停止した所から再開します。",
+    ),
+    (
+        "Copy the path of the download cache",
+        "ダウンロードキャッシュのパスをコピー",
+    ),
+    ("entries", "件"),
+    ("⟳ Refresh", "⟳ 更新"),
+    ("🗑 Clear all", "🗑 すべて削除"),
+    ("Resume", "再開"),
+    ("Open", "開く"),
+    ("Remove from cache", "キャッシュから削除"),
+    ("(partial)", "（未完了）"),
+    // Minimap
+    (
+        "Click to move the camera here",
+        "クリックでカメラをここに移動",
+    ),
+    // Scene graph nesting
+    ("Parent", "親ノード"),
+    ("None", "なし"),
+    // Reference origin
+    ("Moved to the origin from", "原点に移動した元の位置"),
+    (
+        "Coordinates this far out don't fit in 32 bit floats, so the points were moved closer to the origin, the same way as point clouds splats start from.",
+        "これほど遠い座標は 32 ビット浮動小数点数に収まらないため、スプラットの初期化に使う点群と同じように、点を原点の近くに移動しました。",
+    ),
+    // Panels, stats and plots
+    (" frames", " フレーム"),
+    (" settings", " 設定"),
+    (" steps", " ステップ"),
+    (" steps/s", " ステップ/秒"),
+    ("% of the time", "% の時間"),
+    ("1 out of ", "間隔 "),
+    ("Active allocations", "アクティブな割り当て"),
+    ("Bins", "ビン数"),
+    (
+        "Blend splats without sorting them by depth, weighing closer splats more. Much faster for huge scenes, but less accurate where splats overlap.",
+        "深度でソートせずにスプラットをブレンドし、近いスプラットほど重く扱います。巨大なシーンではずっと高速ですが、スプラットが重なる部分では精度が落ちます。",
+    ),
+    ("Bytes in use", "使用中のバイト数"),
+    ("Bytes reserved", "予約済みのバイト数"),
+    ("Clear", "クリア"),
+    ("Compute deviation", "偏差を計算"),
+    ("Constant redraw", "常に再描画"),
+    (
+        "Copy .ply or .zip files to Brush in the Files app to open them here.",
+        "「ファイル」アプリで .ply または .zip ファイルを Brush にコピーすると、ここで開けます。",
+    ),
+    (
+        "Copy a link that opens the viewer at this view",
+        "この視点でビューアを開くリンクをコピー",
+    ),
+    ("Dataset hash", "データセットのハッシュ"),
+    ("Deviation analysis", "偏差分析"),
+    (
+        "Distance from each reference point to the surface of the splats",
+        "各参照点からスプラット表面までの距離",
+    ),
+    ("Driver", "ドライバー"),
+    ("Enable rerun", "rerun を有効化"),
+    ("Eval PSNR", "評価 PSNR"),
+    ("Eval SSIM", "評価 SSIM"),
+    ("Export format", "エクスポート形式"),
+    ("Export name", "エクスポート名"),
+    ("Frames", "フレーム"),
+    ("GPU memory", "GPU メモリ"),
+    (
+        "Heatmap: white is on the splat surface, red is far from it.",
+        "ヒートマップ：白はスプラット表面上、赤は表面から遠いことを示します。",
+    ),
+    ("Import reference...", "参照をインポート..."),
+    ("Last eval:", "前回の評価："),
+    ("Learning rates (log10)", "学習率 (log10)"),
+    ("Link steps", "ステップを連動"),
+    (
+        "Load a PLY, LAS/LAZ or OBJ point cloud or mesh to compare against. It's drawn over the splats, except where it's behind them.",
+        "比較する PLY、LAS/LAZ、OBJ の点群またはメッシュを読み込みます。スプラットの背後にある部分を除き、スプラットの上に描画されます。",
+    ),
+    (
+        "Load or train splats to see histograms of their parameters.",
+        "スプラットを読み込むか学習すると、パラメータのヒストグラムが表示されます。",
+    ),
+    ("Loading...", "読み込み中..."),
+    ("Log counts", "対数カウント"),
+    ("Log train stats", "学習統計を記録"),
+    (
+        "Look around by moving your device",
+        "デバイスを動かして見回す",
+    ),
+    ("Loss", "損失"),
+    ("Mipnerf scenes", "Mipnerf シーン"),
+    ("Name", "名前"),
+    (
+        "Note: Running in browser is still experimental",
+        "注意：ブラウザでの実行はまだ実験的です",
+    ),
+    (
+        "Note: running in debug mode, compile with --release for best performance",
+        "注意：デバッグモードで実行中です。最高の性能を得るには --release でコンパイルしてください",
+    ),
+    ("Nothing loaded yet.", "まだ何も読み込まれていません。"),
+    (
+        "Open the brush_blueprint.rbl in the rerun viewer for a good default layout.",
+        "rerun ビューアで brush_blueprint.rbl を開くと、適切な既定のレイアウトになります。",
+    ),
+    ("Points", "点数"),
+    ("Projected splats", "投影されたスプラット"),
+    ("Rerun Settings", "Rerun の設定"),
+    ("Reset transform", "変換をリセット"),
+    ("SH Degree", "SH 次数"),
+    ("Scene scale", "シーンのスケール"),
+    (
+        "Scroll to zoom, drag to pan and double click to reset.",
+        "スクロールでズーム、ドラッグでパン、ダブルクリックでリセットします。",
+    ),
+    ("Shared files", "共有ファイル"),
+    ("Splat count", "スプラット数"),
+    (
+        "Start training to see plots of the training progress.",
+        "学習を開始すると、学習の進行状況のグラフが表示されます。",
+    ),
+    ("Steps/s", "ステップ/秒"),
+    ("Sync scopes", "スコープを同期"),
+    ("Synthetic blender scenes", "合成 Blender シーン"),
+    ("Time remaining", "残り時間"),
+    ("Train step", "学習ステップ"),
+    ("Training time", "学習時間"),
+    ("Type", "種類"),
+    (
+        "Use a COLMAP fused.ply instead of the sparse points, if available",
+        "利用可能な場合、疎な点の代わりに COLMAP の fused.ply を使用",
+    ),
+    ("Visualize splats", "スプラットを可視化"),
+    ("Visualize splats every", "スプラットを可視化する間隔"),
+    (
+        "Zoom and pan all plots together",
+        "すべてのグラフを一緒にズーム・パン",
+    ),
+    ("every ", "間隔 "),
+    ("step", "ステップ"),
+    (
+        "{iter} is replaced by the step, {timestamp} by the current time.",
+        "{iter} はステップに、{timestamp} は現在時刻に置き換えられます。",
+    ),
+    (
+        "• Left click and drag to orbit",
+        "• 左クリックしてドラッグで周回",
+    ),
+    (
+        "• Middle click, or left click + control, and drag to pan",
+        "• 中クリック、または左クリック + Control でドラッグしてパン",
+    ),
+    (
+        "• Right click, or left click + spacebar, and drag to look around.",
+        "• 右クリック、または左クリック + スペースでドラッグして見回す。",
+    ),
+    ("• Scroll to zoom", "• スクロールでズーム"),
+    ("• Shift to move faster", "• Shift で速く移動"),
+    (
+        "• WASD to fly, Q&E to move up & down.",
+        "• WASD で飛行、Q と E で上下に移動。",
+    ),
+    (
+        "• Z&C to roll, X to reset roll",
+        "• Z と C でロール、X でロールをリセット",
+    ),
+    ("⬆ Export CSV", "⬆ CSV をエクスポート"),
+    ("⬆ Export report", "⬆ レポートをエクスポート"),
+    ("Mean", "平均"),
+    ("RMS", "RMS"),
+    ("Median", "中央値"),
+    ("95th pct", "95 パーセンタイル"),
+    ("Max", "最大"),
+    // Panels, stats and plots
+    (
+        "The export is ready, send it to another app",
+        "エクスポートの準備ができました。他のアプリに送信します",
+    ),
+    ("📤 Preparing export…", "📤 エクスポートを準備中…"),
+    // Panels, stats and plots
+    ("Prune exports too", "エクスポートも剪定"),
+    (
+        "Leave the hidden splats out of exports and shared files",
+        "エクスポートと共有するファイルから非表示のスプラットを除きます",
+    ),
+    (
+        "The contribution of the splats is still being measured",
+        "スプラットの寄与をまだ測定中です",
+    ),
+    // Panels, stats and plots
+    (
+        "Stats show once the dataset is loaded.",
+        "データセットの読み込みが完了すると統計が表示されます。",
+    ),
+    ("Computing dataset stats…", "データセットの統計を計算中…"),
+    // Panels, stats and plots
+    ("⬆ Waiting for the eval…", "⬆ 評価の完了を待っています…"),
+    (
+        "The export includes the results of the eval that's running",
+        "エクスポートには実行中の評価の結果が含まれます",
+    ),
+];
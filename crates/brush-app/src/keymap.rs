@@ -0,0 +1,160 @@
+//! Keyboard shortcuts for actions in the app, which can be rebound in the shortcuts panel.
+//!
+//! Flying the camera around (WASD etc.) is handled by the camera controls and isn't part of the
+//! keymap.
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+/// Number of camera bookmarks that can be saved.
+pub const BOOKMARK_COUNT: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    TogglePause,
+    Export,
+    Screenshot,
+    TogglePanels,
+    ToggleFlyMode,
+    FrameScene,
+    ShowHelp,
+    ResetLayout,
+    FlipComparison,
+    SaveBookmark(usize),
+    GoToBookmark(usize),
+}
+
+impl Action {
+    pub fn description(self) -> String {
+        match self {
+            Self::TogglePause => tr("Pause / resume").to_owned(),
+            Self::Export => tr("Export splats").to_owned(),
+            Self::Screenshot => tr("Save screenshot").to_owned(),
+            Self::TogglePanels => tr("Show / hide panels").to_owned(),
+            Self::ToggleFlyMode => tr("Switch between orbit and fly").to_owned(),
+            Self::FrameScene => tr("Frame the selection or model").to_owned(),
+            Self::ShowHelp => tr("Show shortcuts").to_owned(),
+            Self::ResetLayout => tr("Reset layout").to_owned(),
+            Self::FlipComparison => tr("Flip between compared splats").to_owned(),
+            Self::SaveBookmark(i) => format!("{} {}", tr("Save camera bookmark"), i + 1),
+            Self::GoToBookmark(i) => format!("{} {}", tr("Go to camera bookmark"), i + 1),
+        }
+    }
+}
+
+const BOOKMARK_KEYS: [Key; BOOKMARK_COUNT] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keymap {
+    bindings: Vec<(Action, Option<KeyboardShortcut>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let key = |key| Some(KeyboardShortcut::new(Modifiers::NONE, key));
+
+        let mut bindings = vec![
+            (Action::TogglePause, key(Key::P)),
+            (
+                Action::Export,
+                Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::S)),
+            ),
+            (
+                Action::Screenshot,
+                Some(KeyboardShortcut::new(
+                    Modifiers::COMMAND | Modifiers::SHIFT,
+                    Key::S,
+                )),
+            ),
+            (Action::TogglePanels, key(Key::H)),
+            (Action::ToggleFlyMode, key(Key::G)),
+            (Action::FrameScene, key(Key::F)),
+            (Action::ShowHelp, key(Key::Questionmark)),
+            (Action::ResetLayout, None),
+            (Action::FlipComparison, key(Key::B)),
+        ];
+        for (i, &num) in BOOKMARK_KEYS.iter().enumerate() {
+            bindings.push((
+                Action::SaveBookmark(i),
+                Some(KeyboardShortcut::new(Modifiers::COMMAND, num)),
+            ));
+            bindings.push((Action::GoToBookmark(i), key(num)));
+        }
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// All actions with their current shortcut, if they have one.
+    pub fn bindings(&self) -> &[(Action, Option<KeyboardShortcut>)] {
+        &self.bindings
+    }
+
+    pub fn shortcut(&self, action: Action) -> Option<KeyboardShortcut> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, shortcut)| *shortcut)
+    }
+
+    /// Use the shortcuts of a keymap from an earlier session. Actions it doesn't know about keep
+    /// their default shortcut, unless the saved keymap uses it for something else.
+    pub fn restore(&mut self, saved: &Self) {
+        for (action, current) in &mut self.bindings {
+            match saved.bindings.iter().find(|(a, _)| a == action) {
+                Some(&(_, shortcut)) => *current = shortcut,
+                None if saved.bindings.iter().any(|(_, s)| *s == *current) => *current = None,
+                None => {}
+            }
+        }
+    }
+
+    /// Bind `action` to `shortcut`. Any other action using the same shortcut is unbound.
+    pub fn set_shortcut(&mut self, action: Action, shortcut: Option<KeyboardShortcut>) {
+        for (a, current) in &mut self.bindings {
+            if *a == action {
+                *current = shortcut;
+            } else if shortcut.is_some() && *current == shortcut {
+                *current = None;
+            }
+        }
+    }
+
+    /// Describe the shortcut of `action` in the platform's style, eg. "Ctrl+S" or "⌘S".
+    pub fn describe(&self, ctx: &egui::Context, action: Action) -> String {
+        self.shortcut(action)
+            .map_or_else(|| "-".to_owned(), |s| ctx.format_shortcut(&s))
+    }
+
+    /// Consume the key presses of this frame, and return the actions they trigger.
+    pub(crate) fn triggered(&self, ctx: &egui::Context) -> Vec<Action> {
+        // Don't steal keys from text fields.
+        if ctx.wants_keyboard_input() {
+            return vec![];
+        }
+
+        // Shortcuts match when extra shift or alt keys are held, so check the shortcuts with
+        // the most modifiers first. Otherwise Ctrl+S would also trigger on Ctrl+Shift+S.
+        let mut bindings: Vec<_> = self
+            .bindings
+            .iter()
+            .filter_map(|&(action, shortcut)| Some((action, shortcut?)))
+            .collect();
+        bindings.sort_by_key(|(_, shortcut)| {
+            let m = shortcut.modifiers;
+            let held = [m.alt, m.shift, m.ctrl, m.command];
+            std::cmp::Reverse(held.into_iter().filter(|&h| h).count())
+        });
+
+        ctx.input_mut(|input| {
+            bindings
+                .into_iter()
+                .filter(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,228 @@
+//! Layout of the panels, which is saved between sessions.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use burn_wgpu::WgpuDevice;
+use eframe::egui_wgpu::Renderer;
+use egui::epaint::mutex::RwLock as EguiRwLock;
+use egui_tiles::{Tile, TileId, Tiles, Tree};
+use serde::{Deserialize, Serialize};
+use wgpu::AdapterInfo;
+
+use crate::app::AppPanel;
+use crate::panels::{
+    AnnotationsPanel, CameraPanel, DatasetPanel, DatasetStatsPanel, HistogramsPanel, PlotsPanel,
+    PreprocessPanel, PresetsPanel, RefineLogPanel, SceneGraphPanel, ScenePanel, ScriptPanel,
+    SettingsPanel, ShortcutsPanel, SnapshotDiffPanel, StatsPanel, TracingPanel,
+};
+
+pub(crate) type PaneType = Box<dyn AppPanel>;
+
+/// The kinds of panels, to save the layout without the panels themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum PaneKind {
+    Scene,
+    Settings,
+    Presets,
+    Preprocess,
+    Script,
+    Shortcuts,
+    Stats,
+    Plots,
+    RefineLog,
+    Histograms,
+    SnapshotDiff,
+    SceneGraph,
+    Camera,
+    Annotations,
+    Dataset,
+    DatasetStats,
+    Tracing,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Layout {
+    pub(crate) tree: Tree<PaneKind>,
+    /// The container with everything but the scene, which is hidden in zen mode.
+    pub(crate) side_panel: TileId,
+}
+
+impl Layout {
+    pub(crate) fn storage_key(touch_ui: bool) -> &'static str {
+        // Touch screens have their own layout, don't mix them up.
+        if touch_ui { "layout_touch" } else { "layout" }
+    }
+
+    pub(crate) fn new_default(touch_ui: bool) -> Self {
+        let mut tiles: Tiles<PaneKind> = Tiles::default();
+        let scene = tiles.insert_pane(PaneKind::Scene);
+
+        let loading_subs = vec![
+            tiles.insert_pane(PaneKind::Settings),
+            tiles.insert_pane(PaneKind::Presets),
+            tiles.insert_pane(PaneKind::Preprocess),
+            tiles.insert_pane(PaneKind::Script),
+            tiles.insert_pane(PaneKind::Shortcuts),
+        ];
+        let loading_pane = tiles.insert_tab_tile(loading_subs);
+
+        let stats_subs = vec![
+            tiles.insert_pane(PaneKind::Stats),
+            tiles.insert_pane(PaneKind::DatasetStats),
+            tiles.insert_pane(PaneKind::Plots),
+            tiles.insert_pane(PaneKind::RefineLog),
+            tiles.insert_pane(PaneKind::Histograms),
+            tiles.insert_pane(PaneKind::SnapshotDiff),
+            tiles.insert_pane(PaneKind::SceneGraph),
+            tiles.insert_pane(PaneKind::Camera),
+            tiles.insert_pane(PaneKind::Annotations),
+        ];
+        let stats_pane = tiles.insert_tab_tile(stats_subs);
+
+        let mut sides = vec![loading_pane, stats_pane];
+
+        if cfg!(feature = "tracing") {
+            sides.push(tiles.insert_pane(PaneKind::Tracing));
+        }
+
+        // On touch screens, show the panels as tabs in a sheet below the scene, so the scene
+        // gets the full width of the (often portrait) screen.
+        let (side_panel, lin) = if touch_ui {
+            let side_panel = tiles.insert_tab_tile(sides);
+            let mut lin =
+                egui_tiles::Linear::new(egui_tiles::LinearDir::Vertical, vec![scene, side_panel]);
+            lin.shares.set_share(side_panel, 0.35);
+            (side_panel, lin)
+        } else {
+            let side_panel = tiles.insert_vertical_tile(sides);
+            let mut lin =
+                egui_tiles::Linear::new(egui_tiles::LinearDir::Horizontal, vec![side_panel, scene]);
+            lin.shares.set_share(side_panel, 0.4);
+            (side_panel, lin)
+        };
+        let root = tiles.insert_container(lin);
+
+        Self {
+            tree: Tree::new("brush_tree", root, tiles),
+            side_panel,
+        }
+    }
+
+    fn pane_kinds(&self) -> Vec<PaneKind> {
+        self.tree
+            .tiles
+            .iter()
+            .filter_map(|(_, tile)| match tile {
+                Tile::Pane(kind) => Some(*kind),
+                Tile::Container(_) => None,
+            })
+            .collect()
+    }
+
+    /// Check a restored layout still makes sense, eg. after panels have changed.
+    pub(crate) fn is_valid(&self) -> bool {
+        let kinds = self.pane_kinds();
+        let unique = kinds
+            .iter()
+            .enumerate()
+            .all(|(i, kind)| !kinds[..i].contains(kind));
+
+        // Panels added since the layout was saved wouldn't show up at all.
+        let complete = Self::new_default(false)
+            .pane_kinds()
+            .iter()
+            .all(|kind| kinds.contains(kind));
+
+        unique
+            && complete
+            && self.tree.root().is_some()
+            && matches!(
+                self.tree.tiles.get(self.side_panel),
+                Some(Tile::Container(_))
+            )
+    }
+
+    /// Create the panels of this layout.
+    pub(crate) fn instantiate(
+        &self,
+        mut create_pane: impl FnMut(PaneKind) -> PaneType,
+    ) -> Tree<PaneType> {
+        let mut tiles = Tiles::default();
+        for (&id, tile) in self.tree.tiles.iter() {
+            let tile = match tile {
+                Tile::Pane(kind) => Tile::Pane(create_pane(*kind)),
+                Tile::Container(container) => Tile::Container(container.clone()),
+            };
+            tiles.insert(id, tile);
+            tiles.set_visible(id, self.tree.tiles.is_visible(id));
+        }
+        let root = self.tree.root().expect("Layout must have a root");
+        Tree::new("brush_tree", root, tiles)
+    }
+
+    /// The layout of `tree`, where the kind of each panel is looked up with `kind_of`.
+    pub(crate) fn from_tree(
+        tree: &Tree<PaneType>,
+        side_panel: TileId,
+        kind_of: impl Fn(TileId) -> Option<PaneKind>,
+    ) -> Option<Self> {
+        let mut tiles = Tiles::default();
+        for (&id, tile) in tree.tiles.iter() {
+            let tile = match tile {
+                Tile::Pane(_) => Tile::Pane(kind_of(id)?),
+                Tile::Container(container) => Tile::Container(container.clone()),
+            };
+            tiles.insert(id, tile);
+            tiles.set_visible(id, tree.tiles.is_visible(id));
+        }
+        Some(Self {
+            tree: Tree::new("brush_tree", tree.root()?, tiles),
+            side_panel,
+        })
+    }
+}
+
+/// Everything needed to create the panels.
+pub(crate) struct PaneFactory {
+    pub(crate) device: WgpuDevice,
+    pub(crate) wgpu_device: wgpu::Device,
+    pub(crate) queue: wgpu::Queue,
+    pub(crate) renderer: Arc<EguiRwLock<Renderer>>,
+    pub(crate) adapter_info: AdapterInfo,
+    pub(crate) zen: bool,
+    pub(crate) kiosk_idle: Option<Duration>,
+}
+
+impl PaneFactory {
+    pub(crate) fn create(&self, kind: PaneKind) -> PaneType {
+        match kind {
+            PaneKind::Scene => Box::new(ScenePanel::new(
+                self.wgpu_device.clone(),
+                self.queue.clone(),
+                self.renderer.clone(),
+                self.zen,
+                self.kiosk_idle,
+            )),
+            PaneKind::Settings => Box::new(SettingsPanel::new()),
+            PaneKind::Presets => Box::new(PresetsPanel::new()),
+            PaneKind::Preprocess => Box::new(PreprocessPanel::new()),
+            PaneKind::Script => Box::new(ScriptPanel::new()),
+            PaneKind::Shortcuts => Box::new(ShortcutsPanel::new()),
+            PaneKind::Stats => Box::new(StatsPanel::new(
+                self.device.clone(),
+                self.adapter_info.clone(),
+            )),
+            PaneKind::Plots => Box::new(PlotsPanel::new()),
+            PaneKind::RefineLog => Box::new(RefineLogPanel::new()),
+            PaneKind::Histograms => Box::new(HistogramsPanel::new()),
+            PaneKind::SnapshotDiff => Box::new(SnapshotDiffPanel::new()),
+            PaneKind::SceneGraph => Box::new(SceneGraphPanel::new()),
+            PaneKind::Camera => Box::new(CameraPanel::new()),
+            PaneKind::Annotations => Box::new(AnnotationsPanel::new()),
+            PaneKind::Dataset => Box::new(DatasetPanel::new()),
+            PaneKind::DatasetStats => Box::new(DatasetStatsPanel::new()),
+            PaneKind::Tracing => Box::new(TracingPanel::default()),
+        }
+    }
+}
@@ -1,7 +1,37 @@
 #![recursion_limit = "256"]
 
+mod annotations;
+mod background;
+mod calibration;
+mod compare;
+mod contribution;
+pub mod debug_bundle;
+mod framing;
+mod hdr;
+mod i18n;
+mod keymap;
+mod layout;
+mod lighting;
+mod locale;
+mod logs;
+mod matches;
+mod minimap;
 mod orbit_controls;
+mod orient;
+mod paint;
 mod panels;
+mod picking;
+mod recent;
+pub mod reference_layers;
+mod refine_highlight;
+pub mod scene_graph;
+mod script;
+mod section;
+mod sessions;
+mod symmetry;
+mod train_view;
+mod updates;
+mod volumes;
 
 mod app;
 mod channel;
@@ -0,0 +1,93 @@
+//! Preview the splats under a directional light or colored by their normals, to judge the
+//! geometry of the splats without their colors, or for stylized renders.
+
+use brush_render::camera::Shading;
+use glam::{Quat, Vec3};
+
+use crate::i18n::tr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ShadingMode {
+    Color,
+    Lit,
+    Normals,
+}
+
+impl ShadingMode {
+    pub(crate) const ALL: [Self; 3] = [Self::Color, Self::Lit, Self::Normals];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Color => "Color",
+            Self::Lit => "Lit",
+            Self::Normals => "Normals",
+        }
+    }
+}
+
+pub(crate) struct Lighting {
+    pub(crate) mode: ShadingMode,
+    /// Angle of the light around the up axis of the camera in degrees, 0 is behind the camera.
+    pub(crate) azimuth: f32,
+    /// Angle of the light above the camera in degrees.
+    pub(crate) elevation: f32,
+    /// How much light reaches all splats, from 0 to 1.
+    pub(crate) ambient: f32,
+}
+
+impl Lighting {
+    pub(crate) fn new() -> Self {
+        Self {
+            mode: ShadingMode::Color,
+            azimuth: -30.0,
+            elevation: 40.0,
+            ambient: 0.2,
+        }
+    }
+
+    /// Shading to render with. The light moves with the camera, which is rotated by
+    /// `cam_rotation` in the space of the splats.
+    pub(crate) fn shading(&self, cam_rotation: Quat) -> Shading {
+        match self.mode {
+            ShadingMode::Color => Shading::Color,
+            ShadingMode::Normals => Shading::Normals,
+            ShadingMode::Lit => {
+                let (azimuth, elevation) = (self.azimuth.to_radians(), self.elevation.to_radians());
+                // The camera looks along +Z, with +Y pointing down.
+                let towards_light = Vec3::new(
+                    azimuth.sin() * elevation.cos(),
+                    -elevation.sin(),
+                    -azimuth.cos() * elevation.cos(),
+                );
+                Shading::Lit {
+                    light_dir: cam_rotation * towards_light,
+                    ambient: self.ambient,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_id_salt("shading_mode")
+            .selected_text(tr(self.mode.label()))
+            .show_ui(ui, |ui| {
+                for mode in ShadingMode::ALL {
+                    ui.selectable_value(&mut self.mode, mode, tr(mode.label()));
+                }
+            });
+
+        if self.mode == ShadingMode::Lit {
+            ui.add(
+                egui::Slider::new(&mut self.azimuth, -180.0..=180.0)
+                    .text(tr("Light direction"))
+                    .suffix("°"),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.elevation, -90.0..=90.0)
+                    .text(tr("Light height"))
+                    .suffix("°"),
+            );
+            ui.add(egui::Slider::new(&mut self.ambient, 0.0..=1.0).text(tr("Ambient light")));
+        }
+    }
+}
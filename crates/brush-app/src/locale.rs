@@ -0,0 +1,248 @@
+//! How numbers, lengths and times are written, set in the settings panel next to the language.
+//!
+//! Splats have no real world scale, so lengths are shown as if one unit of the scene is a meter.
+
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    pub(crate) const ALL: [Self; 2] = [Self::Metric, Self::Imperial];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Metric => "Metric",
+            Self::Imperial => "Imperial",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum DecimalSeparator {
+    /// 1,234.5
+    #[default]
+    Point,
+    /// 1.234,5
+    Comma,
+}
+
+impl DecimalSeparator {
+    pub(crate) const ALL: [Self; 2] = [Self::Point, Self::Comma];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Point => "1,234.5",
+            Self::Comma => "1.234,5",
+        }
+    }
+
+    fn decimal(self) -> char {
+        match self {
+            Self::Point => '.',
+            Self::Comma => ',',
+        }
+    }
+
+    fn thousands(self) -> char {
+        match self {
+            Self::Point => ',',
+            Self::Comma => '.',
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Formatting {
+    pub(crate) units: Units,
+    pub(crate) separator: DecimalSeparator,
+    /// Show times of day from 0 to 23 hours instead of with AM and PM, eg. in the logs.
+    pub(crate) clock_24h: bool,
+}
+
+impl Formatting {
+    const DEFAULT: Self = Self {
+        units: Units::Metric,
+        separator: DecimalSeparator::Point,
+        clock_24h: true,
+    };
+}
+
+impl Default for Formatting {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static FORMATTING: RwLock<Formatting> = RwLock::new(Formatting::DEFAULT);
+
+pub(crate) fn formatting() -> Formatting {
+    *FORMATTING.read().expect("Lock poisoned")
+}
+
+pub(crate) fn set_formatting(formatting: Formatting) {
+    *FORMATTING.write().expect("Lock poisoned") = formatting;
+    #[cfg(not(target_family = "wasm"))]
+    brush_cli::logging::set_clock_24h(formatting.clock_24h);
+}
+
+fn group_thousands(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// `value` with `decimals` digits after the decimal separator.
+pub(crate) fn number(value: f64, decimals: usize) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let separator = formatting().separator;
+    let text = format!("{:.decimals$}", value.abs());
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+    // Values that round to zero don't get a sign.
+    let mut result = if value < 0.0 && text.chars().any(|c| matches!(c, '1'..='9')) {
+        "-".to_owned()
+    } else {
+        String::new()
+    };
+    result.push_str(&group_thousands(whole, separator.thousands()));
+    if !fraction.is_empty() {
+        result.push(separator.decimal());
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// A count, with its thousands separated.
+pub(crate) fn count(value: impl Into<u64>) -> String {
+    group_thousands(
+        &value.into().to_string(),
+        formatting().separator.thousands(),
+    )
+}
+
+/// Size in bytes, in kB, MB, GB and so on.
+pub(crate) fn bytes(bytes: u64) -> String {
+    const PREFIXES: &[char] = &['k', 'M', 'G', 'T', 'P', 'E'];
+
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+    let size = bytes as f64;
+    let exp = (size.log(1000.0).floor() as usize).clamp(1, PREFIXES.len());
+    format!(
+        "{} {}B",
+        number(size / 1000f64.powi(exp as i32), 2),
+        PREFIXES[exp - 1]
+    )
+}
+
+// Length units, as their symbol and size in meters.
+const METRIC_UNITS: &[(&str, f64)] = &[("mm", 0.001), ("cm", 0.01), ("m", 1.0), ("km", 1000.0)];
+const IMPERIAL_UNITS: &[(&str, f64)] = &[("in", 0.0254), ("ft", 0.3048), ("mi", 1609.344)];
+
+/// A length in units of the scene, in the largest unit it's at least one of.
+pub(crate) fn length(length: f64) -> String {
+    let units = match formatting().units {
+        Units::Metric => METRIC_UNITS,
+        Units::Imperial => IMPERIAL_UNITS,
+    };
+    let (symbol, size) = units
+        .iter()
+        .rev()
+        .find(|(_, size)| length.abs() >= *size)
+        .unwrap_or(&units[0]);
+    let value = length / size;
+    let decimals = if value.abs() < 10.0 { 2 } else { 1 };
+    format!("{} {symbol}", number(value, decimals))
+}
+
+/// Parse a length typed in by the user, eg. "1.5 m" or "3ft", in units of the scene. Without a
+/// unit, the length is in meters or feet.
+pub(crate) fn parse_length(text: &str) -> Option<f64> {
+    let formatting = formatting();
+    let text = text.trim();
+    let split = text.find(|c: char| c.is_alphabetic()).unwrap_or(text.len());
+    let (value, symbol) = text.split_at(split);
+
+    let value: String = value
+        .chars()
+        .filter(|&c| c != formatting.separator.thousands() && !c.is_whitespace())
+        .map(|c| {
+            if c == formatting.separator.decimal() {
+                '.'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let value: f64 = value.parse().ok()?;
+
+    let symbol = symbol.trim();
+    let size = if symbol.is_empty() {
+        match formatting.units {
+            Units::Metric => 1.0,
+            Units::Imperial => 0.3048,
+        }
+    } else {
+        METRIC_UNITS
+            .iter()
+            .chain(IMPERIAL_UNITS)
+            .find(|(s, _)| s.eq_ignore_ascii_case(symbol))?
+            .1
+    };
+    Some(value * size)
+}
+
+/// Edit a length in units of the scene, in the preferred units.
+pub(crate) fn length_drag(value: &mut f32) -> egui::DragValue<'_> {
+    egui::DragValue::new(value)
+        .custom_formatter(|v, _| length(v))
+        .custom_parser(parse_length)
+}
+
+pub(crate) fn settings_ui(ui: &mut egui::Ui) {
+    let mut current = formatting();
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Units"));
+        egui::ComboBox::from_id_salt("units")
+            .selected_text(tr(current.units.label()))
+            .show_ui(ui, |ui| {
+                for units in Units::ALL {
+                    ui.selectable_value(&mut current.units, units, tr(units.label()));
+                }
+            });
+
+        ui.label(tr("Numbers"));
+        egui::ComboBox::from_id_salt("decimal_separator")
+            .selected_text(current.separator.label())
+            .show_ui(ui, |ui| {
+                for separator in DecimalSeparator::ALL {
+                    ui.selectable_value(&mut current.separator, separator, separator.label());
+                }
+            });
+
+        ui.checkbox(&mut current.clock_24h, tr("24 hour clock"));
+    });
+
+    if current != formatting() {
+        set_formatting(current);
+    }
+}
@@ -0,0 +1,110 @@
+//! Levels of the recorded logs, which are written to the log folder and kept for debug bundles.
+
+use crate::i18n::tr;
+
+#[cfg(not(target_family = "wasm"))]
+mod imp {
+    pub(super) use brush_cli::logging::{
+        DEFAULT_FILTER, has_explicit_filter, is_initialized, log_dir, set_filter,
+    };
+}
+
+// The web app logs to the browser console instead, see `debug_bundle::init_logger`.
+#[cfg(target_family = "wasm")]
+mod imp {
+    pub(super) const DEFAULT_FILTER: &str = "";
+
+    pub(super) fn has_explicit_filter() -> bool {
+        false
+    }
+
+    pub(super) fn is_initialized() -> bool {
+        false
+    }
+
+    pub(super) fn log_dir() -> Option<&'static std::path::Path> {
+        None
+    }
+
+    pub(super) fn set_filter(directives: &str) -> anyhow::Result<()> {
+        let _ = directives;
+        anyhow::bail!("Log levels can't be changed on this platform")
+    }
+}
+
+pub(crate) struct LogSettings {
+    /// Levels of the recorded logs, like `--log-filter`. Empty for the default levels.
+    pub(crate) filter: String,
+    // Why the levels couldn't be applied.
+    error: Option<String>,
+}
+
+impl LogSettings {
+    pub(crate) fn new() -> Self {
+        Self {
+            filter: String::new(),
+            error: None,
+        }
+    }
+
+    fn apply(&mut self) {
+        let directives = if self.filter.trim().is_empty() {
+            imp::DEFAULT_FILTER
+        } else {
+            self.filter.as_str()
+        };
+        self.error = imp::set_filter(directives).err().map(|e| e.to_string());
+    }
+
+    /// Use the saved levels, unless levels were given on the command line.
+    pub(crate) fn load(&mut self, filter: String) {
+        if !imp::is_initialized() || imp::has_explicit_filter() {
+            return;
+        }
+        self.filter = filter;
+        self.apply();
+    }
+
+    pub(crate) fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        if !imp::is_initialized() {
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Log levels"));
+            if imp::has_explicit_filter() {
+                ui.label(tr("set on the command line"));
+                return;
+            }
+            let response = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.filter)
+                        .hint_text(imp::DEFAULT_FILTER)
+                        .desired_width(180.0),
+                )
+                .on_hover_text(tr(
+                    "A level, or levels per module, eg. info,brush_train=debug",
+                ));
+            if response.lost_focus() {
+                self.apply();
+            }
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        if let Some(dir) = imp::log_dir() {
+            if rrfd::can_reveal()
+                && ui
+                    .button(tr("📂 Show logs"))
+                    .on_hover_text(dir.display().to_string())
+                    .clicked()
+            {
+                if let Err(e) = rrfd::reveal_file(dir) {
+                    log::error!("Failed to show {}: {e}", dir.display());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Shows which sparse points two views of the dataset have in common, as matches between the
+//! images, with the epipolar line of a match in the other image. Regions without matches, or
+//! matches far from their epipolar line, point at problems with structure from motion rather
+//! than with training.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use brush_train::scene::SceneView;
+use egui::{Color32, Pos2, Rect, Stroke, TextureHandle, TextureOptions};
+use glam::{UVec2, Vec2};
+
+use crate::i18n::tr;
+use crate::locale;
+
+// Most matches to draw, to keep the images readable.
+const MAX_DRAWN: usize = 400;
+// Points along an epipolar line, which is curved with lens distortion.
+const CURVE_SAMPLES: usize = 64;
+// Range of depths the epipolar line covers, relative to the distance between the cameras.
+const CURVE_NEAR: f32 = 0.01;
+const CURVE_FAR: f32 = 1000.0;
+// How close in points the pointer has to be to a match to pick it.
+const HOVER_RADIUS: f32 = 8.0;
+
+fn image_size(view: &SceneView) -> UVec2 {
+    glam::uvec2(view.image.width(), view.image.height())
+}
+
+fn point_key(position: glam::Vec3) -> [u32; 3] {
+    position.to_array().map(f32::to_bits)
+}
+
+/// Where the points along the ray through `uv` in an image of `from` end up in an image of
+/// `to`, in uv coordinates. The distortion of `from` is ignored, it only bends the line a bit.
+fn epipolar_curve(from: &SceneView, uv: Vec2, to: &SceneView) -> Vec<Vec2> {
+    let (from_cam, to_cam) = (&from.camera, &to.camera);
+    let (from_size, to_size) = (image_size(from), image_size(to));
+    let pixel = uv * from_size.as_vec2();
+    let local = ((pixel - from_cam.center(from_size)) / from_cam.focal(from_size)).extend(1.0);
+    let dir = from_cam.rotation * local;
+    let baseline = from_cam.position.distance(to_cam.position).max(1e-4);
+
+    (0..=CURVE_SAMPLES)
+        .filter_map(|i| {
+            let t = i as f32 / CURVE_SAMPLES as f32;
+            let depth = baseline * CURVE_NEAR * (CURVE_FAR / CURVE_NEAR).powf(t);
+            let projected = to_cam.project(from_cam.position + dir * depth, to_size)?;
+            Some(projected / to_size.as_vec2())
+        })
+        .collect()
+}
+
+fn view_texture(ctx: &egui::Context, name: &str, view: &SceneView) -> TextureHandle {
+    let image = &view.image;
+    let size = [image.width() as usize, image.height() as usize];
+    let color_img = if image.color().has_alpha() {
+        egui::ColorImage::from_rgba_unmultiplied(size, &image.to_rgba8().into_vec())
+    } else {
+        egui::ColorImage::from_rgb(size, &image.to_rgb8().into_vec())
+    };
+    ctx.load_texture(name, color_img, TextureOptions::default())
+}
+
+pub(crate) struct MatchViewer {
+    // The views the candidates were found for, and the index of the first view.
+    source: Option<(Arc<Vec<SceneView>>, usize)>,
+    // Views sharing sparse points with the first view, and how many, most first.
+    candidates: Vec<(usize, usize)>,
+    other: Option<usize>,
+    // The pair of views the textures are of.
+    textures: Option<((usize, usize), TextureHandle, TextureHandle)>,
+}
+
+impl MatchViewer {
+    pub(crate) fn new() -> Self {
+        Self {
+            source: None,
+            candidates: vec![],
+            other: None,
+            textures: None,
+        }
+    }
+
+    fn find_candidates(views: &[SceneView], first: usize) -> Vec<(usize, usize)> {
+        let points: HashSet<_> = views[first]
+            .sparse_points
+            .iter()
+            .map(|(_, position)| point_key(*position))
+            .collect();
+        let mut candidates: Vec<_> = views
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != first)
+            .map(|(i, view)| {
+                let shared = view
+                    .sparse_points
+                    .iter()
+                    .filter(|(_, position)| points.contains(&point_key(*position)))
+                    .count();
+                (i, shared)
+            })
+            .filter(|&(_, shared)| shared > 0)
+            .collect();
+        candidates.sort_by_key(|&(_, shared)| std::cmp::Reverse(shared));
+        candidates
+    }
+
+    /// Show the matches between view `first` of `views` and a view picked in the ui.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui, views: &Arc<Vec<SceneView>>, first: usize) {
+        let stale = self
+            .source
+            .as_ref()
+            .is_none_or(|(source, index)| !Arc::ptr_eq(source, views) || *index != first);
+        if stale {
+            self.candidates = Self::find_candidates(views, first);
+            self.other = self.candidates.first().map(|&(i, _)| i);
+            self.source = Some((views.clone(), first));
+        }
+
+        let Some(other) = self.other else {
+            ui.label(tr(
+                "This view has no sparse points in common with other views.",
+            ));
+            return;
+        };
+
+        egui::ComboBox::from_label(tr("Matched with"))
+            .selected_text(&views[other].path)
+            .show_ui(ui, |ui| {
+                for &(i, shared) in &self.candidates {
+                    let label = format!("{} ({shared})", views[i].path);
+                    ui.selectable_value(&mut self.other, Some(i), label);
+                }
+            });
+
+        let pair = (first, other);
+        if self.textures.as_ref().is_none_or(|(p, _, _)| *p != pair) {
+            let ctx = ui.ctx();
+            self.textures = Some((
+                pair,
+                view_texture(ctx, "match_tex_a", &views[first]),
+                view_texture(ctx, "match_tex_b", &views[other]),
+            ));
+        }
+        let Some((_, tex_a, tex_b)) = &self.textures else {
+            return;
+        };
+
+        let (view_a, view_b) = (&views[first], &views[other]);
+        let by_point: HashMap<_, Vec2> = view_a
+            .sparse_points
+            .iter()
+            .map(|(uv, position)| (point_key(*position), *uv))
+            .collect();
+        let matches: Vec<(Vec2, Vec2)> = view_b
+            .sparse_points
+            .iter()
+            .filter_map(|(uv, position)| Some((*by_point.get(&point_key(*position))?, *uv)))
+            .collect();
+
+        // Both images side by side, at the same height.
+        let gap = 8.0;
+        let aspect_a = view_a.image.width() as f32 / view_a.image.height() as f32;
+        let aspect_b = view_b.image.width() as f32 / view_b.image.height() as f32;
+        let height = (ui.available_width() - gap) / (aspect_a + aspect_b);
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), height),
+            egui::Sense::hover(),
+        );
+        let rect_a = Rect::from_min_size(rect.min, egui::vec2(height * aspect_a, height));
+        let rect_b = Rect::from_min_size(
+            rect.min + egui::vec2(rect_a.width() + gap, 0.0),
+            egui::vec2(height * aspect_b, height),
+        );
+        let full_uv = Rect::from_min_max(Pos2::ZERO, egui::pos2(1.0, 1.0));
+        let painter = ui.painter_at(rect);
+        painter.image(tex_a.id(), rect_a, full_uv, Color32::WHITE);
+        painter.image(tex_b.id(), rect_b, full_uv, Color32::WHITE);
+
+        let to_screen = |rect: Rect, uv: Vec2| rect.min + egui::vec2(uv.x, uv.y) * rect.size();
+
+        let step = matches.len().div_ceil(MAX_DRAWN).max(1);
+        let line = Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 255, 128, 96));
+        for &(uv_a, uv_b) in matches.iter().step_by(step) {
+            let (a, b) = (to_screen(rect_a, uv_a), to_screen(rect_b, uv_b));
+            painter.line_segment([a, b], line);
+            painter.circle_filled(a, 2.0, Color32::GREEN);
+            painter.circle_filled(b, 2.0, Color32::GREEN);
+        }
+
+        // Epipolar line of the match under the pointer, in the other image.
+        let hovered = response.hover_pos().and_then(|pos| {
+            matches
+                .iter()
+                .flat_map(|&(uv_a, uv_b)| {
+                    [
+                        (to_screen(rect_a, uv_a), (uv_a, uv_b, true)),
+                        (to_screen(rect_b, uv_b), (uv_a, uv_b, false)),
+                    ]
+                })
+                .filter(|(point, _)| point.distance(pos) < HOVER_RADIUS)
+                .min_by(|(a, _), (b, _)| a.distance(pos).total_cmp(&b.distance(pos)))
+                .map(|(_, hovered)| hovered)
+        });
+        if let Some((uv_a, uv_b, in_a)) = hovered {
+            let (from, uv, to, to_rect) = if in_a {
+                (view_a, uv_a, view_b, rect_b)
+            } else {
+                (view_b, uv_b, view_a, rect_a)
+            };
+            let curve: Vec<Pos2> = epipolar_curve(from, uv, to)
+                .into_iter()
+                .map(|uv| to_screen(to_rect, uv))
+                .collect();
+            ui.painter_at(to_rect)
+                .add(egui::Shape::line(curve, Stroke::new(1.5, Color32::YELLOW)));
+            let (a, b) = (to_screen(rect_a, uv_a), to_screen(rect_b, uv_b));
+            painter.line_segment([a, b], Stroke::new(2.0, Color32::YELLOW));
+            painter.circle_stroke(a, 4.0, Stroke::new(2.0, Color32::YELLOW));
+            painter.circle_stroke(b, 4.0, Stroke::new(2.0, Color32::YELLOW));
+        }
+
+        ui.label(format!(
+            "{} {}",
+            locale::count(matches.len() as u64),
+            tr("shared points")
+        ))
+        .on_hover_text(tr(
+            "Hover a match to see its epipolar line in the other image. Matches should lie on \
+             the line, if not, the poses or intrinsics are off.",
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use brush_render::bounding_box::BoundingBox;
+use egui::{Color32, Pos2, Rect, Sense, Stroke, StrokeKind, vec2};
+use glam::{Vec2, Vec3};
+
+use crate::{app::AppContext, i18n::tr, scene_graph::SceneNodeKind};
+
+/// Size of the minimap in points.
+const MINIMAP_SIZE: f32 = 160.0;
+
+// Maps positions in controls space (where -Y is up) to a top-down view
+// on the xz plane, fitted inside the minimap rect.
+struct TopDownMapping {
+    center: Vec2,
+    scale: f32,
+    rect: Rect,
+}
+
+impl TopDownMapping {
+    fn new(points: &[Vec2], rect: Rect) -> Self {
+        let (min, max) = points.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let center = (min + max) / 2.0;
+        // Leave a bit of a margin around the outermost points.
+        let extent = ((max - min).max_element() * 0.6).max(1e-3);
+        let scale = rect.width().min(rect.height()) / (2.0 * extent);
+        Self {
+            center,
+            scale,
+            rect,
+        }
+    }
+
+    fn to_screen(&self, p: Vec2) -> Pos2 {
+        let rel = (p - self.center) * self.scale;
+        self.rect.center() + vec2(rel.x, rel.y)
+    }
+
+    fn to_world(&self, pos: Pos2) -> Vec2 {
+        let rel = pos - self.rect.center();
+        glam::vec2(rel.x, rel.y) / self.scale + self.center
+    }
+}
+
+fn top_down(p: Vec3) -> Vec2 {
+    glam::vec2(p.x, p.z)
+}
+
+/// Draw a small top-down overview of the scene in the corner of `scene_rect`, showing the
+/// training cameras, the `splat_bounds` if given (in the space of the controls), and the current
+/// viewer camera. Clicking the map moves the viewer there.
+pub(crate) fn draw_minimap(
+    ui: &mut egui::Ui,
+    scene_rect: Rect,
+    context: &mut AppContext,
+    splat_bounds: Option<&BoundingBox>,
+) {
+    if scene_rect.width() < MINIMAP_SIZE * 2.0 || scene_rect.height() < MINIMAP_SIZE * 2.0 {
+        return;
+    }
+
+    let rect = Rect::from_min_size(
+        scene_rect.right_bottom() - vec2(MINIMAP_SIZE + 8.0, MINIMAP_SIZE + 8.0),
+        vec2(MINIMAP_SIZE, MINIMAP_SIZE),
+    );
+
+    // Everything is drawn in the space of the orbit controls, which has the up axis aligned to -Y.
+    let to_controls = context.model_local_to_world.inverse();
+    let view_positions: Vec<Vec3> = context
+        .dataset
+        .train
+        .views
+        .iter()
+        .map(|v| to_controls.transform_point3(v.camera.position))
+        .collect();
+
+    let cam_pos = context.controls.position;
+    let cam_forward = context.controls.rotation * Vec3::Z;
+    let pivot = cam_pos + cam_forward * context.controls.focus_distance;
+
+    let mut points: Vec<Vec2> = view_positions.iter().copied().map(top_down).collect();
+    points.push(top_down(cam_pos));
+    points.push(top_down(pivot));
+    if let Some(bounds) = splat_bounds {
+        points.push(top_down(bounds.min()));
+        points.push(top_down(bounds.max()));
+    }
+
+    let mapping = TopDownMapping::new(&points, rect.shrink(6.0));
+
+    let response = ui.interact(rect, ui.id().with("minimap"), Sense::click());
+    let painter = ui.painter_at(rect);
+
+    painter.rect_filled(rect, 4.0, Color32::from_black_alpha(180));
+    painter.rect_stroke(
+        rect,
+        4.0,
+        Stroke::new(1.0, Color32::GRAY),
+        StrokeKind::Inside,
+    );
+
+    let cameras_visible = context
+        .scene_graph
+        .resolve_kind(SceneNodeKind::CameraPath)
+        .is_none_or(|(visible, _)| visible);
+
+    if let Some(bounds) = splat_bounds {
+        let outline = Rect::from_two_pos(
+            mapping.to_screen(top_down(bounds.min())),
+            mapping.to_screen(top_down(bounds.max())),
+        );
+        painter.rect_filled(outline, 0.0, Color32::from_white_alpha(24));
+        painter.rect_stroke(
+            outline,
+            0.0,
+            Stroke::new(1.0, Color32::from_gray(160)),
+            StrokeKind::Inside,
+        );
+    }
+
+    for pos in view_positions.iter().filter(|_| cameras_visible) {
+        painter.circle_filled(
+            mapping.to_screen(top_down(*pos)),
+            2.0,
+            Color32::from_rgb(120, 170, 255),
+        );
+    }
+
+    let cam_screen = mapping.to_screen(top_down(cam_pos));
+    let forward = top_down(cam_forward).normalize_or_zero() * 14.0;
+    painter.circle_filled(mapping.to_screen(top_down(pivot)), 2.5, Color32::GRAY);
+    painter.arrow(
+        cam_screen,
+        vec2(forward.x, forward.y),
+        Stroke::new(2.0, Color32::from_rgb(255, 200, 60)),
+    );
+    painter.circle_filled(cam_screen, 4.0, Color32::from_rgb(255, 200, 60));
+
+    if let Some(click_pos) = response.interact_pointer_pos() {
+        if response.clicked() && rect.contains(click_pos) {
+            // Teleport, keeping the current height & orientation.
+            let target = mapping.to_world(click_pos);
+            context.controls.position = glam::vec3(target.x, cam_pos.y, target.y);
+            context.controls.stop_movement();
+        }
+    }
+
+    response.on_hover_text(tr("Click to move the camera here"));
+}
@@ -7,9 +7,77 @@ pub struct CameraController {
     pub position: Vec3,
     pub rotation: Quat,
     pub focus_distance: f32,
+    /// Look around when dragging with the left mouse button, instead of orbiting.
+    pub fly_mode: bool,
+    /// Keep orbiting and panning for a bit after letting go, and animate jumps to new poses.
+    pub smooth: bool,
     roll: Quat,
     fly_velocity: Vec3,
     orbit_velocity: Vec2,
+    pan_velocity: Vec2,
+}
+
+// Seconds a transition to a new pose takes.
+const TRANSITION_TIME: f32 = 0.6;
+
+/// Pose of the controls, and the field of view of the camera.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CameraPose {
+    pub(crate) position: Vec3,
+    pub(crate) rotation: Quat,
+    pub(crate) focus_distance: f32,
+    pub(crate) fov: [f64; 2],
+}
+
+impl CameraPose {
+    fn focus(&self) -> Vec3 {
+        self.position + self.rotation * Vec3::Z * self.focus_distance
+    }
+}
+
+/// An eased move from one pose to another. The focus point moves in a straight line while the
+/// camera turns, so moving between views of an object swings around it rather than through it.
+pub(crate) struct Transition {
+    from: CameraPose,
+    to: CameraPose,
+    elapsed: f32,
+}
+
+impl Transition {
+    pub(crate) fn new(from: CameraPose, to: CameraPose) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Advance by `delta_time` seconds, and return the pose to show now, and whether the
+    /// transition is done.
+    pub(crate) fn step(&mut self, delta_time: f32) -> (CameraPose, bool) {
+        self.elapsed += delta_time;
+        let t = (self.elapsed / TRANSITION_TIME).clamp(0.0, 1.0);
+        if t >= 1.0 {
+            return (self.to, true);
+        }
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let rotation = self.from.rotation.slerp(self.to.rotation, t).normalize();
+        // Interpolate the distance in log space, so zooming in and out feels even.
+        let [from_log, to_log] =
+            [self.from.focus_distance, self.to.focus_distance].map(|d| d.max(1e-6).ln());
+        let focus_distance = (from_log + (to_log - from_log) * t).exp();
+        let focus = self.from.focus().lerp(self.to.focus(), t);
+        let fov =
+            [0, 1].map(|i| self.from.fov[i] + (self.to.fov[i] - self.from.fov[i]) * f64::from(t));
+        let pose = CameraPose {
+            position: focus - rotation * Vec3::Z * focus_distance,
+            rotation,
+            focus_distance,
+            fov,
+        };
+        (pose, false)
+    }
 }
 
 pub fn smooth_orbit(
@@ -53,8 +121,11 @@ impl CameraController {
             rotation: Quat::IDENTITY,
             roll: Quat::IDENTITY,
             focus_distance: start_focus_distance,
+            fly_mode: false,
+            smooth: true,
             fly_velocity: Vec3::ZERO,
             orbit_velocity: Vec2::ZERO,
+            pan_velocity: Vec2::ZERO,
         }
     }
 
@@ -66,7 +137,7 @@ impl CameraController {
         let mmb = response.dragged_by(egui::PointerButton::Middle);
 
         let look_pan = mmb || lmb && ui.input(|r| r.modifiers.ctrl);
-        let look_fps = rmb || lmb && ui.input(|r| r.key_down(egui::Key::Space));
+        let look_fps = rmb || lmb && (self.fly_mode || ui.input(|r| r.key_down(egui::Key::Space)));
         let look_orbit = lmb;
 
         let mouselook_speed = 0.002;
@@ -78,7 +149,7 @@ impl CameraController {
         if response.hovered() {
             if ui.input(|r| r.modifiers.ctrl) {
                 ui.ctx().set_cursor_icon(egui::CursorIcon::Move);
-            } else if ui.input(|r| r.key_down(egui::Key::Space)) {
+            } else if self.fly_mode || ui.input(|r| r.key_down(egui::Key::Space)) {
                 ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
             } else {
                 ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
@@ -87,8 +158,8 @@ impl CameraController {
 
         if look_pan {
             let drag_mult = self.focus_distance / response.rect.width().max(response.rect.height());
-            self.position -= right * response.drag_delta().x * drag_mult;
-            self.position += up * response.drag_delta().y * drag_mult;
+            let delta = response.drag_delta();
+            self.pan_velocity = glam::vec2(delta.x, delta.y) * drag_mult;
 
             ui.ctx().set_cursor_icon(egui::CursorIcon::Move);
         } else if look_fps {
@@ -113,6 +184,8 @@ impl CameraController {
             self.orbit_velocity.y,
             self.focus_distance,
         );
+        self.position -= right * self.pan_velocity.x;
+        self.position += up * self.pan_velocity.y;
 
         let fly_moment_lambda = 0.8;
 
@@ -185,8 +258,7 @@ impl CameraController {
             self.roll = roll * self.roll;
         }
         if ui.input(|r| r.key_down(egui::Key::X)) {
-            self.rotation = self.roll.inverse() * self.rotation;
-            self.roll = Quat::IDENTITY;
+            self.reset_roll();
         }
         if ui.input(|r| r.key_down(egui::Key::C)) {
             let roll = Quat::from_axis_angle(forward, -move_speed * 0.025 * delta_time);
@@ -197,8 +269,14 @@ impl CameraController {
         let delta = self.fly_velocity * delta_time;
         self.position += delta.x * right + delta.y * up + delta.z * forward;
 
-        // Damp velocities towards zero.
-        self.orbit_velocity = exp_lerp2(self.orbit_velocity, Vec2::ZERO, delta_time, 8.0);
+        // Damp velocities towards zero, or stop right away without inertia.
+        if self.smooth {
+            self.orbit_velocity = exp_lerp2(self.orbit_velocity, Vec2::ZERO, delta_time, 8.0);
+            self.pan_velocity = exp_lerp2(self.pan_velocity, Vec2::ZERO, delta_time, 8.0);
+        } else {
+            self.orbit_velocity = Vec2::ZERO;
+            self.pan_velocity = Vec2::ZERO;
+        }
         self.fly_velocity = exp_lerp3(self.fly_velocity, Vec3::ZERO, delta_time, 7.0);
 
         // Handle scroll wheel: move back, and adjust focus distance.
@@ -218,8 +296,48 @@ impl CameraController {
         glam::Affine3A::from_rotation_translation(self.rotation, self.position)
     }
 
+    /// Slowly orbit around the focus point, eg. while nobody is interacting with the viewer.
+    pub(crate) fn auto_orbit(&mut self, delta_yaw: f32) {
+        (self.position, self.rotation) = smooth_orbit(
+            self.position,
+            self.rotation,
+            self.roll,
+            delta_yaw,
+            0.0,
+            self.focus_distance,
+        );
+    }
+
+    /// How far the camera is rolled around the direction it looks in, in radians.
+    pub(crate) fn roll_angle(&self) -> f32 {
+        let (axis, angle) = self.roll.to_axis_angle();
+        let angle = if angle > f32::consts::PI {
+            angle - f32::consts::TAU
+        } else {
+            angle
+        };
+        if axis.dot(self.rotation * Vec3::Z) < 0.0 {
+            -angle
+        } else {
+            angle
+        }
+    }
+
+    /// Roll the camera to `angle` radians around the direction it looks in.
+    pub(crate) fn set_roll(&mut self, angle: f32) {
+        let roll = Quat::from_axis_angle(self.rotation * Vec3::Z, angle - self.roll_angle());
+        self.rotation = roll * self.rotation;
+        self.roll = roll * self.roll;
+    }
+
+    pub(crate) fn reset_roll(&mut self) {
+        self.rotation = self.roll.inverse() * self.rotation;
+        self.roll = Quat::IDENTITY;
+    }
+
     pub(crate) fn stop_movement(&mut self) {
         self.orbit_velocity = Vec2::ZERO;
         self.fly_velocity = Vec3::ZERO;
+        self.pan_velocity = Vec2::ZERO;
     }
 }
@@ -0,0 +1,118 @@
+//! Finding the floor of a scene, to stand it upright and put the floor at height zero with one
+//! click, instead of fiddling with the up axis by hand.
+
+use brush_render::bounding_box::BoundingBox;
+use glam::Vec3;
+
+use crate::compare::ViewSplats;
+
+// Most splats to fit the floor to, the rest are skipped over.
+const MAX_POINTS: usize = 20_000;
+// Splats to count the inliers of each plane on. Counting on all of them is a few seconds of
+// frozen UI on the web, where this runs on the main thread, while this many already gives
+// about the same floor.
+const SCORE_POINTS: usize = 2_000;
+// Planes to try.
+const ITERATIONS: usize = 1000;
+// Distance from the floor (relative to the size of the scene) of splats on the floor.
+const INLIER_DISTANCE: f32 = 0.005;
+// Most the floor may be tilted away from the current up axis, in degrees, before any plane is
+// considered instead.
+const MAX_TILT: f32 = 45.0;
+// At most this fraction of the splats may be below the floor.
+const MAX_BELOW: f32 = 0.2;
+
+/// The floor of a scene, in the space of the splats.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Floor {
+    /// Normal of the floor, pointing up.
+    pub(crate) up: Vec3,
+    /// A point on the floor.
+    pub(crate) point: Vec3,
+}
+
+// Deterministic random numbers, so the same splats always get the same floor.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % max as u64) as usize
+    }
+}
+
+fn fit_plane(points: &[Vec3], up_hint: Option<Vec3>, cameras: &[Vec3]) -> Option<Floor> {
+    let bounds = BoundingBox::from_points_robust(points, 0.01)?;
+    let threshold = INLIER_DISTANCE * bounds.extent.length() * 2.0;
+    let min_alignment = MAX_TILT.to_radians().cos();
+    let mean_camera =
+        (!cameras.is_empty()).then(|| cameras.iter().sum::<Vec3>() / cameras.len() as f32);
+    let scored: Vec<Vec3> = points
+        .iter()
+        .step_by(points.len().div_ceil(SCORE_POINTS))
+        .copied()
+        .collect();
+
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    let mut best: Option<(usize, Floor)> = None;
+    for _ in 0..ITERATIONS {
+        let [a, b, c] = [(); 3].map(|_| points[rng.below(points.len())]);
+        let Some(mut up) = (b - a).cross(c - a).try_normalize() else {
+            continue;
+        };
+        if up_hint.is_some_and(|hint| up.dot(hint).abs() < min_alignment) {
+            continue;
+        }
+
+        // The cameras are above the floor, or without any, most of the scene is.
+        let above = |p: Vec3| up.dot(p - a);
+        let flip = match mean_camera {
+            Some(camera) => above(camera) < 0.0,
+            None => scored.iter().filter(|&&p| above(p) < 0.0).count() > scored.len() / 2,
+        };
+        if flip {
+            up = -up;
+        }
+
+        let (mut inliers, mut below) = (0, 0);
+        for &p in &scored {
+            let height = up.dot(p - a);
+            if height.abs() < threshold {
+                inliers += 1;
+            } else if height < 0.0 {
+                below += 1;
+            }
+        }
+        if below as f32 > MAX_BELOW * scored.len() as f32 {
+            continue;
+        }
+        if best.as_ref().is_none_or(|(count, _)| inliers > *count) {
+            best = Some((inliers, Floor { up, point: a }));
+        }
+    }
+    best.map(|(_, floor)| floor)
+}
+
+/// Find the floor of `splats`, the largest plane with (nearly) nothing below it. Planes close
+/// to perpendicular to `up_hint` are preferred, and the floor is below `cameras`.
+pub(crate) async fn find_floor(
+    splats: ViewSplats,
+    up_hint: Vec3,
+    cameras: Vec<Vec3>,
+) -> Option<Floor> {
+    let means: Vec<f32> = splats.means.val().into_data_async().await.to_vec().ok()?;
+    let count = means.len() / 3;
+    if count < 3 {
+        return None;
+    }
+    let step = count.div_ceil(MAX_POINTS);
+    let points: Vec<Vec3> = means
+        .chunks_exact(3)
+        .step_by(step)
+        .map(Vec3::from_slice)
+        .collect();
+
+    fit_plane(&points, Some(up_hint), &cameras).or_else(|| fit_plane(&points, None, &cameras))
+}
@@ -0,0 +1,536 @@
+//! Paint over the splats with a brush in screen space, eg. to remove a color cast or dim a
+//! reflection. The brush can also select splats, to mirror or duplicate them.
+
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use brush_render::{camera::Camera, gaussian_splats::Splats, render::rgb_to_sh};
+use brush_train::train::TrainBack;
+use burn::module::ParamId;
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{Tensor, activation::sigmoid, backend::Backend};
+use glam::{UVec2, Vec2, Vec3, Vec4};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::compare::ViewSplats;
+use crate::i18n::tr;
+use crate::picking::PickTarget;
+use crate::symmetry::{array_splats, mirror_splats};
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PaintMode {
+    /// Blend the base color of the splats towards a color.
+    Color,
+    /// Scale the opacity of the splats.
+    Opacity,
+    /// Add the splats to the selection.
+    Select,
+    /// Remove the splats from the selection.
+    Deselect,
+}
+
+impl PaintMode {
+    pub(crate) const ALL: [Self; 4] = [Self::Color, Self::Opacity, Self::Select, Self::Deselect];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Color => "Color",
+            Self::Opacity => "Opacity",
+            Self::Select => "Select",
+            Self::Deselect => "Deselect",
+        }
+    }
+
+    fn selects(self) -> bool {
+        matches!(self, Self::Select | Self::Deselect)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PaintSettings {
+    pub(crate) mode: PaintMode,
+    pub(crate) color: [f32; 3],
+    /// What to scale the opacity by in the middle of the brush.
+    pub(crate) opacity_scale: f32,
+    /// Radius of the brush in pixels.
+    pub(crate) radius: f32,
+    /// Part of the radius the brush has full strength in, it falls off to zero towards the edge.
+    pub(crate) hardness: f32,
+    /// How much each dab of the brush changes the splats, from 0 to 1.
+    pub(crate) strength: f32,
+}
+
+impl Default for PaintSettings {
+    fn default() -> Self {
+        Self {
+            mode: PaintMode::Color,
+            color: [0.5, 0.5, 0.5],
+            opacity_scale: 0.5,
+            radius: 30.0,
+            hardness: 0.5,
+            strength: 0.2,
+        }
+    }
+}
+
+// Splats up to this much further away than the surface under the brush are painted too.
+const DEPTH_MARGIN: f32 = 0.05;
+
+/// How much a dab of the brush at `center` covers each splat, from 0 to 1. Only splats in front
+/// of `max_depth` are affected, so the brush doesn't paint through surfaces.
+fn dab_weights<B: Backend>(
+    means: Tensor<B, 2>,
+    camera: &Camera,
+    size: UVec2,
+    center: Vec2,
+    max_depth: f32,
+    settings: &PaintSettings,
+) -> Tensor<B, 1> {
+    let device = means.device();
+    let [n, _] = means.dims();
+    let world_to_local = camera.world_to_local();
+
+    // Rows of the transposed rotation, to rotate the means as row vectors.
+    let rotation = Tensor::<B, 1>::from_floats(world_to_local.matrix3.to_cols_array(), &device)
+        .reshape([3, 3]);
+    let translation =
+        Tensor::<B, 1>::from_floats(world_to_local.translation.to_array(), &device).reshape([1, 3]);
+    let means_local = means.matmul(rotation) + translation;
+
+    let focal = camera.focal(size);
+    let cam_center = camera.center(size);
+    let z = means_local.clone().slice([0..n, 2..3]);
+    let x = means_local.clone().slice([0..n, 0..1]) / z.clone() * focal.x + cam_center.x;
+    let y = means_local.slice([0..n, 1..2]) / z.clone() * focal.y + cam_center.y;
+    let dist = ((x - center.x).powf_scalar(2.0) + (y - center.y).powf_scalar(2.0)).sqrt();
+
+    let falloff = (1.0 - settings.hardness).max(1e-3);
+    let weight = ((-dist / settings.radius.max(1.0) + 1.0) / falloff).clamp(0.0, 1.0);
+
+    let in_front = z.clone().greater_elem(camera.near).float()
+        * z.lower_elem(max_depth * (1.0 + DEPTH_MARGIN)).float();
+    (weight * in_front).reshape([n])
+}
+
+/// Paint the splats with a weight from 0 to 1 per splat.
+pub(crate) fn paint<B: Backend>(
+    splats: &Splats<B>,
+    weight: Tensor<B, 1>,
+    settings: &PaintSettings,
+) -> Splats<B> {
+    let [n, coeffs, _] = splats.sh_coeffs.dims();
+    let device = splats.device();
+
+    let (sh_coeffs, raw_opacity) = match settings.mode {
+        PaintMode::Color => {
+            let sh = splats.sh_coeffs_f32();
+            let dc = sh.clone().slice([0..n, 0..1, 0..3]);
+            let target = Tensor::<B, 1>::from_floats(settings.color.map(rgb_to_sh), &device)
+                .reshape([1, 1, 3]);
+            let dc = dc.clone() + (target - dc) * weight.reshape([n, 1, 1]);
+            let sh = if coeffs > 1 {
+                Tensor::cat(vec![dc, sh.slice([0..n, 1..coeffs, 0..3])], 1)
+            } else {
+                dc
+            };
+            (sh, splats.raw_opacity.val())
+        }
+        PaintMode::Opacity => {
+            let opacity = sigmoid(splats.raw_opacity.val());
+            let scale = (weight * (settings.opacity_scale - 1.0)) + 1.0;
+            let opacity = (opacity * scale).clamp(1e-4, 1.0 - 1e-4);
+            let raw_opacity = (opacity.clone() / (-opacity + 1.0)).log();
+            (splats.sh_coeffs_f32(), raw_opacity)
+        }
+        PaintMode::Select | PaintMode::Deselect => {
+            (splats.sh_coeffs_f32(), splats.raw_opacity.val())
+        }
+    };
+
+    Splats::from_tensor_data(
+        splats.means.val(),
+        splats.rotation.val(),
+        splats.log_scales.val(),
+        sh_coeffs,
+        raw_opacity,
+    )
+}
+
+// Color selected splats are tinted towards in the view.
+const SELECTION_COLOR: [f32; 3] = [1.0, 0.35, 0.1];
+
+/// Tint the selected splats, to show the selection.
+fn highlight_selection(splats: &ViewSplats, selection: Tensor<InnerBack, 1>) -> ViewSplats {
+    let settings = PaintSettings {
+        mode: PaintMode::Color,
+        color: SELECTION_COLOR,
+        ..Default::default()
+    };
+    paint(splats, selection * 0.6, &settings)
+}
+
+/// An edit of the selected splats.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum SelectionEdit {
+    /// Add copies reflected through the plane perpendicular to `axis` (0 for X, 1 for Y, 2 for Z)
+    /// at `center` along it.
+    Mirror { axis: usize, center: f32 },
+    /// Add `count` copies, each moved by `step` from the last.
+    Array { step: Vec3, count: u32 },
+}
+
+enum Painted {
+    Splats(ViewSplats),
+    /// The selection mask, and how many splats are selected.
+    Selection(Tensor<InnerBack, 1>, u32),
+}
+
+async fn count_selected(selection: Tensor<InnerBack, 1>) -> u32 {
+    let count = selection.sum().into_data_async().await.to_vec::<f32>();
+    count.ok().and_then(|c| c.first().copied()).unwrap_or(0.0) as u32
+}
+
+/// Select the splats on the side of `section` that isn't cut away, or all splats without one.
+async fn select_all(splats: ViewSplats, section: Option<Vec4>) -> Painted {
+    let [n, _] = splats.means.dims();
+    let selection = match section {
+        Some(plane) => {
+            let device = splats.device();
+            let normal = Tensor::<InnerBack, 1>::from_floats(plane.truncate().to_array(), &device)
+                .reshape([3, 1]);
+            let dist = splats.means.val().matmul(normal).reshape([n]) + plane.w;
+            dist.lower_equal_elem(0.0).float()
+        }
+        None => Tensor::ones([n], &splats.device()),
+    };
+    let count = count_selected(selection.clone()).await;
+    Painted::Selection(selection, count)
+}
+
+async fn edit_selection(
+    splats: ViewSplats,
+    selection: Tensor<InnerBack, 1>,
+    edit: SelectionEdit,
+) -> Painted {
+    let indices = selection.greater_elem(0.5).argwhere_async().await;
+    if indices.dims()[0] == 0 {
+        return Painted::Splats(splats);
+    }
+    let indices = indices.squeeze(1);
+    let edited = match edit {
+        SelectionEdit::Mirror { axis, center } => mirror_splats(&splats, indices, axis, center),
+        SelectionEdit::Array { step, count } => array_splats(&splats, indices, step, count),
+    };
+    Painted::Splats(edited)
+}
+
+/// Paint the dabs at `centers` of the render `target` onto `splats`, or onto the `selection` when
+/// selecting.
+async fn paint_dabs(
+    splats: ViewSplats,
+    selection: Option<Tensor<InnerBack, 1>>,
+    target: PickTarget,
+    centers: Vec<Vec2>,
+    settings: PaintSettings,
+) -> Painted {
+    let surfaces = target.pick(&centers).await;
+    let mut weight = Tensor::zeros([splats.num_splats() as usize], &splats.device());
+    for (center, surface) in centers.into_iter().zip(surfaces) {
+        // Nothing to paint where there's no surface.
+        let Some(surface) = surface else {
+            continue;
+        };
+        let dab = dab_weights(
+            splats.means.val(),
+            &target.camera,
+            target.size,
+            center,
+            target.depth(surface),
+            &settings,
+        );
+        weight = Tensor::max_pair(weight, dab);
+    }
+
+    if !settings.mode.selects() {
+        let weight = weight * settings.strength;
+        return Painted::Splats(paint(&splats, weight, &settings));
+    }
+    let brushed = weight.greater_elem(0.0).float();
+    let selection = selection.unwrap_or_else(|| brushed.zeros_like());
+    let selection = if settings.mode == PaintMode::Select {
+        Tensor::max_pair(selection, brushed)
+    } else {
+        selection * (-brushed + 1.0)
+    };
+    let count = count_selected(selection.clone()).await;
+    Painted::Selection(selection, count)
+}
+
+/// Paints the splats while dragging over the view.
+pub(crate) struct PaintTool {
+    pub(crate) settings: PaintSettings,
+    // Dabs that haven't been painted yet, in pixels.
+    queued: Vec<Vec2>,
+    pending: Option<Receiver<Painted>>,
+    // The splats before the last stroke or edit, to undo it.
+    undo: Option<ViewSplats>,
+    stroke_started: bool,
+    // 1 for selected splats, 0 for others.
+    selection: Option<Tensor<InnerBack, 1>>,
+    selected_count: u32,
+    // The splats with the selection highlighted, and the splats they were made from.
+    preview: Option<(ParamId, ViewSplats)>,
+    // How far apart copies of the selection are placed, picked from the scale of the scene.
+    array_step: Option<Vec3>,
+    array_count: u32,
+}
+
+impl PaintTool {
+    pub(crate) fn new() -> Self {
+        Self {
+            settings: PaintSettings::default(),
+            queued: vec![],
+            pending: None,
+            undo: None,
+            stroke_started: false,
+            selection: None,
+            selected_count: 0,
+            preview: None,
+            array_step: None,
+            array_count: 1,
+        }
+    }
+
+    /// Add a dab at `pixel` to the current stroke.
+    pub(crate) fn dab(&mut self, pixel: Vec2) {
+        if self.queued.last() != Some(&pixel) {
+            self.queued.push(pixel);
+        }
+    }
+
+    /// End the current stroke, the next dab starts a new one.
+    pub(crate) fn end_stroke(&mut self) {
+        self.stroke_started = false;
+    }
+
+    fn spawn(&mut self, ctx: &egui::Context, job: impl Future<Output = Painted> + Send + 'static) {
+        let (sender, receiver) = channel();
+        self.pending = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let _ = sender.send(job.await);
+        });
+        ctx.request_repaint();
+    }
+
+    fn set_selection(&mut self, selection: Option<Tensor<InnerBack, 1>>, count: u32) {
+        self.selection = selection;
+        self.selected_count = count;
+        self.preview = None;
+    }
+
+    /// Paint the queued dabs on `splats`, and return the painted splats once they're done.
+    pub(crate) fn update(
+        &mut self,
+        ctx: &egui::Context,
+        splats: &ViewSplats,
+        target: Option<&PickTarget>,
+    ) -> Option<ViewSplats> {
+        let mut painted = None;
+        if let Some(receiver) = self.pending.as_ref() {
+            match receiver.try_recv() {
+                Ok(Painted::Splats(splats)) => {
+                    self.pending = None;
+                    painted = Some(splats);
+                }
+                Ok(Painted::Selection(selection, count)) => {
+                    self.pending = None;
+                    self.set_selection(Some(selection), count);
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint(),
+                Err(TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+        let splats = painted.clone().unwrap_or_else(|| splats.clone());
+
+        // Splats added by an edit aren't selected, and splats removed by undoing it are dropped.
+        let n = splats.num_splats() as usize;
+        if let Some(selection) = self.selection.clone() {
+            let [len] = selection.dims();
+            if len < n {
+                let added = Tensor::zeros([n - len], &selection.device());
+                self.selection = Some(Tensor::cat(vec![selection, added], 0));
+            } else if len > n {
+                self.selection = Some(selection.slice([0..n]));
+            }
+        }
+
+        // Paint one batch of dabs at a time, so each starts from the result of the last.
+        if let Some(target) = target.filter(|_| self.pending.is_none() && !self.queued.is_empty()) {
+            if !self.stroke_started && !self.settings.mode.selects() {
+                self.stroke_started = true;
+                self.undo = Some(splats.clone());
+            }
+
+            let job = paint_dabs(
+                splats,
+                self.selection.clone(),
+                target.clone(),
+                std::mem::take(&mut self.queued),
+                self.settings,
+            );
+            self.spawn(ctx, job);
+        }
+        painted
+    }
+
+    /// The splats to show for `splats`, with the selection highlighted.
+    pub(crate) fn preview(&mut self, splats: &ViewSplats) -> ViewSplats {
+        let Some(selection) = self.selection.clone().filter(|_| self.selected_count > 0) else {
+            return splats.clone();
+        };
+        match &self.preview {
+            Some((id, preview)) if *id == splats.means.id => preview.clone(),
+            _ => {
+                let preview = highlight_selection(splats, selection);
+                self.preview = Some((splats.means.id, preview.clone()));
+                preview
+            }
+        }
+    }
+
+    /// How many splats are selected.
+    pub(crate) fn selected_count(&self) -> u32 {
+        self.selected_count
+    }
+
+    /// The selection mask, 1 for selected splats and 0 for others, if any splats are selected.
+    pub(crate) fn selection(&self) -> Option<Tensor<InnerBack, 1>> {
+        self.selection.clone().filter(|_| self.selected_count > 0)
+    }
+
+    /// Select the splats on the side of the `section` plane that isn't cut away, or all splats.
+    pub(crate) fn select_all(
+        &mut self,
+        ctx: &egui::Context,
+        splats: &ViewSplats,
+        section: Option<Vec4>,
+    ) {
+        if self.pending.is_none() {
+            self.spawn(ctx, select_all(splats.clone(), section));
+        }
+    }
+
+    pub(crate) fn clear_selection(&mut self) {
+        self.set_selection(None, 0);
+    }
+
+    /// Apply `edit` to the selected splats. The edited splats are returned from `update`.
+    pub(crate) fn edit(&mut self, ctx: &egui::Context, splats: &ViewSplats, edit: SelectionEdit) {
+        let Some(selection) = self.selection.clone() else {
+            return;
+        };
+        if self.pending.is_none() {
+            self.undo = Some(splats.clone());
+            self.spawn(ctx, edit_selection(splats.clone(), selection, edit));
+        }
+    }
+
+    /// The splats before the last stroke or edit, if it can be undone.
+    pub(crate) fn take_undo(&mut self) -> Option<ViewSplats> {
+        self.undo.take()
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        egui::ComboBox::from_id_salt("paint_mode")
+            .selected_text(tr(settings.mode.label()))
+            .show_ui(ui, |ui| {
+                for mode in PaintMode::ALL {
+                    ui.selectable_value(&mut settings.mode, mode, tr(mode.label()));
+                }
+            });
+        match settings.mode {
+            PaintMode::Color => {
+                ui.color_edit_button_rgb(&mut settings.color);
+            }
+            PaintMode::Opacity => {
+                ui.add(egui::Slider::new(&mut settings.opacity_scale, 0.0..=2.0).text(tr("Scale")));
+            }
+            PaintMode::Select | PaintMode::Deselect => {}
+        }
+        ui.add(egui::Slider::new(&mut settings.radius, 2.0..=200.0).text(tr("Radius")));
+        if !settings.mode.selects() {
+            ui.add(egui::Slider::new(&mut settings.hardness, 0.0..=1.0).text(tr("Hardness")));
+            ui.add(egui::Slider::new(&mut settings.strength, 0.0..=1.0).text(tr("Strength")));
+        }
+    }
+
+    /// Show the selection, and buttons to edit it.
+    ///
+    /// `section` is the plane of the section cut, to select what isn't cut away, and
+    /// `mirror_plane` the axis and position of the cut when it's along one of the axes.
+    pub(crate) fn selection_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        splats: &ViewSplats,
+        section: Option<Vec4>,
+        mirror_plane: Option<(usize, f32)>,
+        focus_distance: f32,
+    ) {
+        ui.label(format!("{} {}", self.selected_count, tr("selected")));
+        if ui
+            .button(tr("Select all"))
+            .on_hover_text(tr(
+                "Select all splats that aren't cut away by the section cut",
+            ))
+            .clicked()
+        {
+            self.select_all(ui.ctx(), splats, section);
+        }
+        if self.selected_count == 0 {
+            return;
+        }
+        if ui.button(tr("Clear selection")).clicked() {
+            self.clear_selection();
+        }
+
+        if ui
+            .add_enabled(mirror_plane.is_some(), egui::Button::new(tr("⇋ Mirror")))
+            .on_hover_text(tr(
+                "Add a mirrored copy of the selection on the other side of the cut",
+            ))
+            .on_disabled_hover_text(tr(
+                "Cut along X, Y or Z with 🔪 Section to place the plane to mirror through",
+            ))
+            .clicked()
+        {
+            if let Some((axis, center)) = mirror_plane {
+                self.edit(ui.ctx(), splats, SelectionEdit::Mirror { axis, center });
+            }
+        }
+
+        let step = self
+            .array_step
+            .get_or_insert(Vec3::X * 0.2 * focus_distance.max(1e-3));
+        let speed = 0.01 * focus_distance.max(1e-3);
+        let mut duplicate = false;
+        ui.menu_button(tr("⧉ Duplicate"), |ui| {
+            ui.add(egui::Slider::new(&mut self.array_count, 1..=20).text(tr("Copies")));
+            ui.horizontal(|ui| {
+                ui.label(tr("Offset"));
+                ui.add(egui::DragValue::new(&mut step.x).speed(speed).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut step.y).speed(speed).prefix("y: "));
+                ui.add(egui::DragValue::new(&mut step.z).speed(speed).prefix("z: "));
+            });
+            duplicate = ui.button(tr("Duplicate")).clicked();
+        });
+        if duplicate {
+            let edit = SelectionEdit::Array {
+                step: *step,
+                count: self.array_count,
+            };
+            self.edit(ui.ctx(), splats, edit);
+        }
+    }
+}
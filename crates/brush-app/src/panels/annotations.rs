@@ -0,0 +1,139 @@
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use glam::{Affine3A, Vec3};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::annotations::{Annotation, annotations_from_json, annotations_to_json};
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use crate::scene_graph::SceneNodeKind;
+
+async fn import_annotations() -> anyhow::Result<Vec<Annotation>> {
+    let file = rrfd::pick_file().await?;
+    annotations_from_json(&file.read().await?)
+}
+
+async fn export_annotations(annotations: Vec<Annotation>) -> anyhow::Result<()> {
+    let json = annotations_to_json(&annotations)?;
+    let file = rrfd::save_file("annotations.json").await?;
+    file.write(json.as_bytes()).await?;
+    Ok(())
+}
+
+/// Lists the notes placed in the scene, to edit them, jump to them, or export them.
+pub(crate) struct AnnotationsPanel {
+    import_receiver: Option<Receiver<anyhow::Result<Vec<Annotation>>>>,
+    err: Option<String>,
+}
+
+impl AnnotationsPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            import_receiver: None,
+            err: None,
+        }
+    }
+}
+
+impl AppPanel for AnnotationsPanel {
+    fn title(&self) -> String {
+        tr("Annotations").to_owned()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        if let Some(receiver) = self.import_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(Ok(annotations)) => {
+                    context.annotations.extend(annotations);
+                    self.import_receiver = None;
+                }
+                Ok(Err(e)) => {
+                    self.err = Some(e.to_string());
+                    self.import_receiver = None;
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.import_receiver = None,
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(tr("Import JSON…")).clicked() {
+                let (sender, receiver) = channel();
+                self.import_receiver = Some(receiver);
+                self.err = None;
+                tokio_wasm::task::spawn(async move {
+                    let _ = sender.send(import_annotations().await);
+                });
+            }
+
+            if ui
+                .add_enabled(
+                    !context.annotations.is_empty(),
+                    egui::Button::new(tr("Export JSON…")),
+                )
+                .clicked()
+            {
+                let annotations = context.annotations.clone();
+                tokio_wasm::task::spawn(async move {
+                    if let Err(e) = export_annotations(annotations).await {
+                        log::error!("Failed to export annotations: {e}");
+                    }
+                });
+            }
+        });
+
+        if let Some(err) = &self.err {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+
+        if context.annotations.is_empty() {
+            ui.label(tr(
+                "Turn on 📌 Annotate in the scene, and click on the splats to add a note.",
+            ));
+            return;
+        }
+
+        ui.separator();
+
+        let mut remove = None;
+        let mut go_to = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (i, annotation) in context.annotations.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut annotation.text);
+                    if ui
+                        .button("🎯")
+                        .on_hover_text(tr("Look at this note"))
+                        .clicked()
+                    {
+                        go_to = Some(annotation.position);
+                    }
+                    if ui.button("🗑").on_hover_text(tr("Remove")).clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+        });
+
+        if let Some(i) = remove {
+            context.annotations.remove(i);
+        }
+        if let Some(position) = go_to {
+            look_at(context, position);
+        }
+    }
+}
+
+/// Turn the camera towards `position`, in the space of the splats, keeping its distance.
+fn look_at(context: &mut AppContext, position: Vec3) {
+    let world_from_splats = context
+        .scene_graph
+        .resolve_kind(SceneNodeKind::Splats)
+        .map_or(Affine3A::IDENTITY, |(_, transform)| {
+            context.model_local_to_world * transform * context.model_local_to_world.inverse()
+        });
+    let target = world_from_splats.transform_point3(position);
+    let rotation = context.camera.rotation;
+    let distance = context.controls.focus_distance;
+    context.move_camera_to(target - rotation * Vec3::Z * distance, rotation);
+}
@@ -0,0 +1,163 @@
+use std::sync::mpsc::{Receiver, channel};
+
+use brush_process::data_source::DataSource;
+#[cfg(not(target_family = "wasm"))]
+use brush_process::download_cache::{self as store, CacheEntry};
+#[cfg(target_family = "wasm")]
+use brush_process::web_cache::{self as store, CacheEntry};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::i18n::tr;
+use crate::locale;
+
+fn format_size(bytes: u64) -> String {
+    let mb = bytes as f64 / (1024.0 * 1024.0);
+    if mb >= 1024.0 {
+        format!("{} GB", locale::number(mb / 1024.0, 2))
+    } else {
+        format!("{} MB", locale::number(mb, 1))
+    }
+}
+
+/// Lists the cached data, to load it again or free up space. On the web it's kept in the
+/// browser, otherwise downloads are kept on disk.
+pub(crate) struct CacheUi {
+    entries: Option<Vec<CacheEntry>>,
+    pending: Option<Receiver<anyhow::Result<Vec<CacheEntry>>>>,
+    err: Option<String>,
+}
+
+impl CacheUi {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: None,
+            pending: None,
+            err: None,
+        }
+    }
+
+    /// Run an action on the cache, and list the entries again afterwards.
+    fn refresh_after(&mut self, action: impl Future<Output = anyhow::Result<()>> + 'static) {
+        let (sender, receiver) = channel();
+        self.pending = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let result = match action.await {
+                Ok(()) => store::entries().await,
+                Err(e) => Err(e),
+            };
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Draw the list of cached data. Returns a source to load when an entry is opened.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) -> Option<DataSource> {
+        if let Some(receiver) = self.pending.as_ref() {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    self.pending = None;
+                    match result {
+                        Ok(entries) => {
+                            self.entries = Some(entries);
+                            self.err = None;
+                        }
+                        Err(e) => self.err = Some(e.to_string()),
+                    }
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        if self.entries.is_none() && self.pending.is_none() && self.err.is_none() {
+            self.refresh_after(async { Ok(()) });
+        }
+
+        let mut open = None;
+
+        ui.collapsing(tr("Cached data"), |ui| {
+            #[cfg(target_family = "wasm")]
+            ui.label(tr(
+                "Uploaded and downloaded data is kept in the browser, to load it again without \
+                 uploading or downloading it.",
+            ));
+            #[cfg(not(target_family = "wasm"))]
+            {
+                ui.label(tr(
+                    "Downloaded data is kept on disk, to load it again without downloading it. \
+                     Interrupted downloads continue where they stopped.",
+                ));
+                let dir = store::dir().to_string_lossy().into_owned();
+                if ui
+                    .link(&dir)
+                    .on_hover_text(tr("Copy the path of the download cache"))
+                    .clicked()
+                {
+                    ui.ctx().copy_text(dir);
+                }
+            }
+
+            if let Some(err) = &self.err {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+
+            let entries = self.entries.clone().unwrap_or_default();
+            let total: u64 = entries.iter().map(|e| e.size).sum();
+
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} {}, {}",
+                    entries.len(),
+                    tr("entries"),
+                    format_size(total)
+                ));
+
+                if self.pending.is_some() {
+                    ui.spinner();
+                }
+                if ui.button(tr("⟳ Refresh")).clicked() {
+                    self.refresh_after(async { Ok(()) });
+                }
+                if ui
+                    .add_enabled(!entries.is_empty(), egui::Button::new(tr("🗑 Clear all")))
+                    .clicked()
+                {
+                    self.refresh_after(store::clear());
+                }
+            });
+
+            for entry in entries {
+                ui.horizontal(|ui| {
+                    // Interrupted downloads continue when their url is loaded again.
+                    let source = if entry.partial {
+                        DataSource::Url(entry.source.clone())
+                    } else {
+                        DataSource::Cached(entry.key.clone())
+                    };
+                    let label = if entry.partial {
+                        tr("Resume")
+                    } else {
+                        tr("Open")
+                    };
+                    if ui.button(label).clicked() {
+                        open = Some(source);
+                    }
+                    if ui
+                        .button("🗑")
+                        .on_hover_text(tr("Remove from cache"))
+                        .clicked()
+                    {
+                        let key = entry.key.clone();
+                        self.refresh_after(async move { store::remove(&key).await });
+                    }
+                    ui.label(format_size(entry.size));
+                    if entry.partial {
+                        ui.weak(tr("(partial)"));
+                    }
+                    ui.label(&entry.source);
+                });
+            }
+        });
+
+        open
+    }
+}
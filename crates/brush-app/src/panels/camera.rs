@@ -0,0 +1,126 @@
+//! Settings of the viewer camera, to compose shots: the field of view, roll, and the intrinsics
+//! of the training cameras.
+
+use brush_render::camera::Camera;
+use egui::{DragValue, Slider};
+use glam::Vec2;
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+
+// Height of a full frame (35mm) sensor, to express the field of view as a focal length.
+const FULL_FRAME_HEIGHT_MM: f64 = 24.0;
+
+/// Change the vertical field of view of `camera` to `fov_y`, keeping the aspect ratio of its
+/// pixels.
+fn set_fov_y(camera: &mut Camera, fov_y: f64) {
+    let scale = (fov_y / 2.0).tan() / (camera.fov_y / 2.0).tan();
+    camera.fov_x = 2.0 * ((camera.fov_x / 2.0).tan() * scale).atan();
+    camera.fov_y = fov_y;
+}
+
+pub(crate) struct CameraPanel;
+
+impl CameraPanel {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+impl AppPanel for CameraPanel {
+    fn title(&self) -> String {
+        tr("Camera").to_owned()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        egui::Grid::new("camera_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr("Field of view"));
+                let mut fov = context.camera.fov_y.to_degrees();
+                if ui
+                    .add(Slider::new(&mut fov, 5.0..=150.0).suffix("°"))
+                    .changed()
+                {
+                    set_fov_y(&mut context.camera, fov.to_radians());
+                }
+                ui.end_row();
+
+                ui.label(tr("Focal length")).on_hover_text(tr(
+                    "The focal length giving this field of view on a full frame (35mm) camera",
+                ));
+                let mut focal = FULL_FRAME_HEIGHT_MM / (2.0 * (context.camera.fov_y / 2.0).tan());
+                if ui
+                    .add(
+                        DragValue::new(&mut focal)
+                            .range(5.0..=1000.0)
+                            .speed(0.5)
+                            .suffix(" mm"),
+                    )
+                    .changed()
+                {
+                    set_fov_y(
+                        &mut context.camera,
+                        2.0 * (FULL_FRAME_HEIGHT_MM / (2.0 * focal)).atan(),
+                    );
+                }
+                ui.end_row();
+
+                ui.label(tr("Roll"));
+                ui.horizontal(|ui| {
+                    let mut roll = context.controls.roll_angle().to_degrees();
+                    if ui
+                        .add(Slider::new(&mut roll, -180.0..=180.0).suffix("°"))
+                        .changed()
+                    {
+                        context.controls.set_roll(roll.to_radians());
+                    }
+                    if ui.button(tr("Reset")).clicked() {
+                        context.controls.reset_roll();
+                    }
+                });
+                ui.end_row();
+            });
+
+        ui.separator();
+
+        let train = &context.dataset.train;
+        let nearest = train
+            .get_nearest_view(context.camera.local_to_world())
+            .map(|i| train.views[i].clone());
+        match nearest {
+            Some(view) => {
+                ui.label(format!("{} {}", tr("Nearest training view:"), view.path));
+                if ui
+                    .button(tr("Match its intrinsics"))
+                    .on_hover_text(tr(
+                        "Use the field of view, principal point and aspect ratio of this view, \
+                         keeping the camera where it is",
+                    ))
+                    .clicked()
+                {
+                    context.camera.fov_x = view.camera.fov_x;
+                    context.camera.fov_y = view.camera.fov_y;
+                    context.camera.center_uv = view.camera.center_uv;
+                    context.view_aspect =
+                        Some(view.image.width() as f32 / view.image.height() as f32);
+                }
+            }
+            None => {
+                ui.label(tr("Load a dataset to match the intrinsics of its cameras."));
+            }
+        }
+
+        if context.view_aspect.is_some()
+            && ui
+                .button(tr("Fill the viewport"))
+                .on_hover_text(tr(
+                    "Stop matching the aspect ratio of a view, and center the principal point",
+                ))
+                .clicked()
+        {
+            context.view_aspect = None;
+            context.camera.center_uv = Vec2::splat(0.5);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use brush_dataset::Dataset;
+use brush_process::process_loop::ProcessMessage;
+use brush_render::camera::Distortion;
+use brush_train::scene::SceneView;
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use crate::locale;
+
+// How many of the most common image sizes to list separately.
+const TOP_RESOLUTIONS: usize = 4;
+
+// Guess of the camera model from its distortion, for datasets that don't name it.
+fn camera_model(distortion: &Distortion) -> &'static str {
+    if distortion.is_none() {
+        "Pinhole"
+    } else if distortion.k3 == 0.0 && distortion.p1 == 0.0 && distortion.p2 == 0.0 {
+        "Radial"
+    } else {
+        "OpenCV"
+    }
+}
+
+/// A summary of a dataset, to judge whether it's worth training on before spending hours on it.
+struct DatasetStats {
+    train_views: usize,
+    eval_views: usize,
+    // Image sizes, and how many views have them, most common first.
+    resolutions: Vec<((u32, u32), usize)>,
+    // Camera models, and how many cameras use them.
+    camera_models: BTreeMap<String, usize>,
+    sparse_points: usize,
+    reprojection_error: Option<f32>,
+    // Size of the box around the cameras.
+    extent: glam::Vec3,
+    // Median distance between a view and the view closest to it.
+    view_spacing: Option<f32>,
+}
+
+impl DatasetStats {
+    fn new(dataset: &Dataset) -> Self {
+        let eval_views = dataset
+            .eval
+            .as_ref()
+            .map_or(&[][..], |eval| &eval.views[..]);
+        let views: Vec<&SceneView> = dataset.train.views.iter().chain(eval_views).collect();
+
+        let mut resolutions = BTreeMap::new();
+        for view in &views {
+            *resolutions
+                .entry((view.image.width(), view.image.height()))
+                .or_insert(0) += 1;
+        }
+        let mut resolutions: Vec<_> = resolutions.into_iter().collect();
+        resolutions.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+        let mut camera_models = BTreeMap::new();
+        let mut groups = HashSet::new();
+        for view in &views {
+            if groups.insert(view.camera_group) {
+                // A SIMPLE_RADIAL camera without distortion is still one, only guess the model
+                // when the dataset doesn't say.
+                let model = dataset
+                    .preprocess
+                    .camera_models
+                    .get(&view.camera_group)
+                    .cloned()
+                    .unwrap_or_else(|| camera_model(&view.camera.distortion).to_owned());
+                *camera_models.entry(model).or_insert(0) += 1;
+            }
+        }
+
+        // Points are seen from many views, count each only once.
+        let mut points = HashSet::new();
+        let mut error_sum = 0.0;
+        let mut error_count = 0;
+        for view in &views {
+            points.extend(
+                view.sparse_points
+                    .iter()
+                    .map(|(_, position)| position.to_array().map(f32::to_bits)),
+            );
+            if let Some(error) = view.reprojection_error(&view.camera) {
+                error_sum += error * view.sparse_points.len() as f32;
+                error_count += view.sparse_points.len();
+            }
+        }
+
+        let positions: Vec<glam::Vec3> = views.iter().map(|view| view.camera.position).collect();
+        let mut spacings = nearest_distances(&positions);
+        let view_spacing = (!spacings.is_empty()).then(|| {
+            let mid = spacings.len() / 2;
+            *spacings.select_nth_unstable_by(mid, f32::total_cmp).1
+        });
+
+        Self {
+            train_views: dataset.train.views.len(),
+            eval_views: eval_views.len(),
+            resolutions,
+            camera_models,
+            sparse_points: points.len(),
+            reprojection_error: (error_count > 0).then(|| error_sum / error_count as f32),
+            extent: dataset.train.bounds().extent * 2.0,
+            view_spacing,
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("dataset_stats_grid")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(tr("Views"));
+                ui.label(format!(
+                    "{} ({} {}, {} {})",
+                    locale::count(self.train_views as u64 + self.eval_views as u64),
+                    locale::count(self.train_views as u64),
+                    tr("train"),
+                    locale::count(self.eval_views as u64),
+                    tr("eval"),
+                ));
+                ui.end_row();
+
+                ui.label(tr("Resolutions"));
+                ui.vertical(|ui| {
+                    for ((width, height), count) in self.resolutions.iter().take(TOP_RESOLUTIONS) {
+                        ui.label(format!(
+                            "{width}×{height}: {}",
+                            locale::count(*count as u64)
+                        ));
+                    }
+                    let others: usize = self
+                        .resolutions
+                        .iter()
+                        .skip(TOP_RESOLUTIONS)
+                        .map(|(_, count)| count)
+                        .sum();
+                    if others > 0 {
+                        ui.label(format!("{}: {}", tr("Other"), locale::count(others as u64)));
+                    }
+                });
+                ui.end_row();
+
+                ui.label(tr("Camera models"));
+                ui.label(
+                    self.camera_models
+                        .iter()
+                        .map(|(model, count)| format!("{model} ×{count}"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                );
+                ui.end_row();
+
+                ui.label(tr("Sparse points"));
+                ui.label(locale::count(self.sparse_points as u64));
+                ui.end_row();
+
+                ui.label(tr("Reprojection error")).on_hover_text(tr(
+                    "Mean distance between where the sparse points were seen, and where the \
+                     cameras project them. A few pixels or more means the poses are off.",
+                ));
+                ui.label(self.reprojection_error.map_or("--".to_owned(), |error| {
+                    format!("{} px", locale::number(f64::from(error), 2))
+                }));
+                ui.end_row();
+
+                ui.label(tr("Extent"))
+                    .on_hover_text(tr("Size of the box around the cameras"));
+                ui.label(format!(
+                    "{} × {} × {}",
+                    locale::length(f64::from(self.extent.x)),
+                    locale::length(f64::from(self.extent.y)),
+                    locale::length(f64::from(self.extent.z)),
+                ));
+                ui.end_row();
+
+                ui.label(tr("Capture density")).on_hover_text(tr(
+                    "Median distance from a view to the closest other view. Views far apart \
+                     compared to the scene see too little overlap to train well.",
+                ));
+                ui.label(self.view_spacing.map_or("--".to_owned(), |spacing| {
+                    format!(
+                        "{} {}",
+                        tr("a view every"),
+                        locale::length(f64::from(spacing))
+                    )
+                }));
+                ui.end_row();
+            });
+    }
+}
+
+/// Distance from each position to the closest other one. Sweeps over the positions sorted along
+/// x, so only the neighbours closer along x than the best distance so far are compared.
+fn nearest_distances(positions: &[glam::Vec3]) -> Vec<f32> {
+    let mut sorted = positions.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x));
+    if sorted.len() < 2 {
+        return vec![];
+    }
+
+    (0..sorted.len())
+        .map(|i| {
+            let mut best = f32::INFINITY;
+            for other in sorted[i + 1..].iter() {
+                if other.x - sorted[i].x > best {
+                    break;
+                }
+                best = best.min(sorted[i].distance(*other));
+            }
+            for other in sorted[..i].iter().rev() {
+                if sorted[i].x - other.x > best {
+                    break;
+                }
+                best = best.min(sorted[i].distance(*other));
+            }
+            best
+        })
+        .collect()
+}
+
+/// Summarizes the loaded dataset, to see whether it's any good before training on it for hours.
+pub(crate) struct DatasetStatsPanel {
+    // The stats, and the views they were computed for.
+    stats: Option<(Arc<Vec<SceneView>>, DatasetStats)>,
+    // Stats being computed, and the views they're for.
+    pending: Option<(Arc<Vec<SceneView>>, Receiver<DatasetStats>)>,
+}
+
+impl DatasetStatsPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            stats: None,
+            pending: None,
+        }
+    }
+
+    fn receive(&mut self, ctx: &egui::Context) {
+        if let Some((views, receiver)) = &self.pending {
+            match receiver.try_recv() {
+                Ok(stats) => {
+                    self.stats = Some((views.clone(), stats));
+                    self.pending = None;
+                    ctx.request_repaint();
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint(),
+                Err(TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+    }
+}
+
+impl AppPanel for DatasetStatsPanel {
+    fn title(&self) -> String {
+        tr("Dataset stats").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        if matches!(message, ProcessMessage::NewSource) {
+            self.stats = None;
+            self.pending = None;
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        let views = &context.dataset.train.views;
+        if views.is_empty() {
+            ui.label(tr("No dataset loaded."));
+            return;
+        }
+
+        // The dataset is sent again for every few views loaded, so only compute the stats once
+        // it's all there, and away from the UI thread.
+        let computed_for = |v: &Arc<Vec<SceneView>>| Arc::ptr_eq(v, views);
+        let current = self.stats.as_ref().is_some_and(|(v, _)| computed_for(v))
+            || self.pending.as_ref().is_some_and(|(v, _)| computed_for(v));
+        if !context.loading() && !current {
+            let (sender, receiver) = channel();
+            self.pending = Some((views.clone(), receiver));
+            let dataset = context.dataset.clone();
+            tokio_wasm::task::spawn(async move {
+                let _ = sender.send(DatasetStats::new(&dataset));
+            });
+        }
+        self.receive(ui.ctx());
+
+        match &self.stats {
+            Some((_, stats)) => {
+                egui::ScrollArea::vertical().show(ui, |ui| stats.ui(ui));
+            }
+            None if context.loading() => {
+                ui.label(tr("Stats show once the dataset is loaded."));
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(tr("Computing dataset stats…"));
+                });
+            }
+        }
+    }
+}
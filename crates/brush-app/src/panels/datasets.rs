@@ -1,4 +1,7 @@
 use crate::app::{AppContext, AppPanel};
+use crate::calibration::IntrinsicsEditor;
+use crate::i18n::tr;
+use crate::matches::MatchViewer;
 use brush_process::process_loop::ProcessMessage;
 use brush_train::scene::{Scene, SceneView, ViewImageType, ViewType};
 use egui::{Slider, TextureHandle, TextureOptions, pos2};
@@ -29,6 +32,8 @@ impl SelectedView {
 pub(crate) struct DatasetPanel {
     view_type: ViewType,
     selected_view: Option<SelectedView>,
+    intrinsics: IntrinsicsEditor,
+    matches: MatchViewer,
 }
 
 impl DatasetPanel {
@@ -36,13 +41,15 @@ impl DatasetPanel {
         Self {
             view_type: ViewType::Train,
             selected_view: None,
+            intrinsics: IntrinsicsEditor::new(),
+            matches: MatchViewer::new(),
         }
     }
 }
 
 impl AppPanel for DatasetPanel {
     fn title(&self) -> String {
-        "Dataset".to_owned()
+        tr("Dataset").to_owned()
     }
 
     fn on_message(&mut self, message: &ProcessMessage, context: &mut AppContext) {
@@ -200,8 +207,24 @@ impl AppPanel for DatasetPanel {
             }
         }
 
+        if !context.loading() && !context.dataset.train.views.is_empty() {
+            egui::CollapsingHeader::new(tr("Camera intrinsics"))
+                .id_salt("camera_intrinsics")
+                .show(ui, |ui| self.intrinsics.ui(ui, context));
+        }
+
+        if !context.loading() {
+            if let Some(selected) = self.selected_view.as_ref() {
+                let views = selected_scene(selected.view_type, context).views.clone();
+                let index = selected.index;
+                egui::CollapsingHeader::new(tr("Feature matches"))
+                    .id_salt("feature_matches")
+                    .show(ui, |ui| self.matches.ui(ui, &views, index));
+            }
+        }
+
         if context.loading() && context.training() {
-            ui.label("Loading...");
+            ui.label(tr("Loading..."));
         }
     }
 
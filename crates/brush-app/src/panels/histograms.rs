@@ -0,0 +1,224 @@
+use std::sync::mpsc::{Receiver, channel};
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use brush_process::process_loop::ProcessMessage;
+use brush_render::{
+    gaussian_splats::Splats,
+    render::SH_C0,
+    stats::{self, Histogram},
+};
+use brush_train::train::TrainBack;
+use burn::tensor::{Tensor, backend::AutodiffBackend};
+use egui_plot::{Bar, BarChart, Plot};
+use std::time::Duration;
+use tokio_with_wasm::alias as tokio_wasm;
+use web_time::Instant;
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+type ViewSplats = Splats<InnerBack>;
+
+// Don't recompute the histograms more often than this while training.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+struct NamedHistogram {
+    name: &'static str,
+    hist: Histogram,
+}
+
+async fn named_histogram(
+    name: &'static str,
+    values: Tensor<InnerBack, 1>,
+    bins: usize,
+) -> anyhow::Result<NamedHistogram> {
+    let hist = stats::histogram(values, bins)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read histogram {e:?}"))?;
+    Ok(NamedHistogram { name, hist })
+}
+
+async fn splat_histograms(splats: &ViewSplats, bins: usize) -> anyhow::Result<Vec<NamedHistogram>> {
+    let n = splats.num_splats() as usize;
+    if n == 0 {
+        return Ok(vec![]);
+    }
+
+    let log_scales = splats.log_scales.val();
+    let max_log_scale: Tensor<_, 1> = log_scales.clone().max_dim(1).squeeze(1);
+    let min_log_scale: Tensor<_, 1> = log_scales.min_dim(1).squeeze(1);
+
+    let sh_dc = splats.sh_coeffs_f32().slice([0..n, 0..1]).reshape([n, 3]);
+    let brightness: Tensor<_, 1> = sh_dc.mean_dim(1).squeeze(1) * SH_C0 + 0.5;
+
+    let ln_10 = std::f32::consts::LN_10;
+
+    Ok(vec![
+        named_histogram("Opacity", splats.opacity(), bins).await?,
+        named_histogram(
+            "Scale (log10 of largest axis)",
+            max_log_scale.clone() / ln_10,
+            bins,
+        )
+        .await?,
+        named_histogram(
+            "Anisotropy (log10 of largest / smallest axis)",
+            (max_log_scale - min_log_scale) / ln_10,
+            bins,
+        )
+        .await?,
+        named_histogram("Base color brightness", brightness, bins).await?,
+    ])
+}
+
+pub(crate) struct HistogramsPanel {
+    latest_splats: Option<ViewSplats>,
+    splats_changed: bool,
+    last_update: Option<Instant>,
+
+    histograms: Vec<NamedHistogram>,
+    pending: Option<Receiver<anyhow::Result<Vec<NamedHistogram>>>>,
+    err: Option<String>,
+
+    bins: usize,
+    log_counts: bool,
+}
+
+impl HistogramsPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            latest_splats: None,
+            splats_changed: false,
+            last_update: None,
+            histograms: vec![],
+            pending: None,
+            err: None,
+            bins: 64,
+            log_counts: false,
+        }
+    }
+
+    fn start_update(&mut self) {
+        let Some(splats) = self.latest_splats.clone() else {
+            return;
+        };
+
+        let (sender, receiver) = channel();
+        self.pending = Some(receiver);
+        self.splats_changed = false;
+        self.last_update = Some(Instant::now());
+
+        let bins = self.bins;
+        tokio_wasm::task::spawn(async move {
+            let _ = sender.send(splat_histograms(&splats, bins).await);
+        });
+    }
+
+    fn draw_histogram(&self, ui: &mut egui::Ui, named: &NamedHistogram) {
+        let hist = &named.hist;
+        ui.label(format!(
+            "{} ({:.3} to {:.3})",
+            named.name, hist.min, hist.max
+        ));
+
+        let width = hist.bin_width().max(1e-12) as f64;
+        let bars = hist
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let height = if self.log_counts {
+                    (count as f64 + 1.0).log10()
+                } else {
+                    count as f64
+                };
+                Bar::new(hist.min as f64 + (i as f64 + 0.5) * width, height).width(width)
+            })
+            .collect();
+
+        Plot::new(named.name)
+            .height(120.0)
+            .allow_scroll(false)
+            .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(bars)));
+    }
+}
+
+impl AppPanel for HistogramsPanel {
+    fn title(&self) -> String {
+        tr("Histograms").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        match message {
+            ProcessMessage::NewSource => {
+                *self = Self {
+                    bins: self.bins,
+                    log_counts: self.log_counts,
+                    ..Self::new()
+                };
+            }
+            ProcessMessage::ViewSplats { splats, .. }
+            | ProcessMessage::TrainStep { splats, .. } => {
+                self.latest_splats = Some(*splats.clone());
+                self.splats_changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
+        if let Some(receiver) = self.pending.as_ref() {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    self.pending = None;
+                    match result {
+                        Ok(histograms) => {
+                            self.histograms = histograms;
+                            self.err = None;
+                        }
+                        Err(e) => self.err = Some(e.to_string()),
+                    }
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        let due = self
+            .last_update
+            .is_none_or(|last| last.elapsed() > UPDATE_INTERVAL);
+        if self.pending.is_none() && self.splats_changed && due {
+            self.start_update();
+        }
+
+        if self.latest_splats.is_none() {
+            ui.label(tr(
+                "Load or train splats to see histograms of their parameters.",
+            ));
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Bins"));
+            let changed = ui
+                .add(egui::DragValue::new(&mut self.bins).range(8..=256))
+                .changed();
+            ui.checkbox(&mut self.log_counts, tr("Log counts"));
+            if changed {
+                self.splats_changed = true;
+            }
+            if self.pending.is_some() {
+                ui.spinner();
+            }
+        });
+
+        if let Some(err) = &self.err {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for hist in &self.histograms {
+                self.draw_histogram(ui, hist);
+            }
+        });
+    }
+}
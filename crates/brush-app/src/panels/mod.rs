@@ -1,15 +1,41 @@
+mod annotations;
+mod camera;
+mod dataset_stats;
 mod datasets;
 mod settings;
 
+#[cfg(not(target_os = "ios"))]
+mod cache;
+
+mod histograms;
+mod plots;
+mod preprocess;
 mod presets;
+mod recent;
+mod refine_log;
 mod scene;
+mod scene_graph;
+mod script;
+mod shortcuts;
+mod snapshot_diff;
 mod stats;
 mod tracing_debug;
 
+pub(crate) use annotations::*;
+pub(crate) use camera::*;
+pub(crate) use dataset_stats::*;
 pub(crate) use datasets::*;
+pub(crate) use histograms::*;
+pub(crate) use plots::*;
+pub(crate) use preprocess::*;
 pub(crate) use presets::*;
+pub(crate) use refine_log::*;
 pub(crate) use scene::*;
+pub(crate) use scene_graph::*;
+pub(crate) use script::*;
 pub(crate) use settings::*;
+pub(crate) use shortcuts::*;
+pub(crate) use snapshot_diff::*;
 pub(crate) use stats::*;
 #[allow(unused)]
 pub(crate) use tracing_debug::*;
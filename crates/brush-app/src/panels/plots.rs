@@ -0,0 +1,240 @@
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::Duration;
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use brush_process::process_loop::ProcessMessage;
+use brush_train::train::TrainBack;
+use burn::tensor::Tensor;
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use tokio_with_wasm::alias as tokio_wasm;
+use web_time::Instant;
+
+// Reading the loss back from the GPU stalls training a little, so it's sampled at about the rate
+// the UI refreshes at instead of every step.
+const LOSS_READ_INTERVAL: Duration = Duration::from_millis(100);
+
+const LR_NAMES: [&str; 5] = ["mean", "rotation", "scale", "coeffs", "opacity"];
+
+/// A series of (iteration, value) points.
+type Series = Vec<[f64; 2]>;
+
+#[derive(Default)]
+struct Metrics {
+    loss: Series,
+    splats: Series,
+    psnr: Series,
+    ssim: Series,
+    learning_rates: [Series; 5],
+}
+
+impl Metrics {
+    fn all_series(&self) -> Vec<(String, &Series)> {
+        let mut series = vec![
+            ("loss".to_owned(), &self.loss),
+            ("splats".to_owned(), &self.splats),
+            ("psnr".to_owned(), &self.psnr),
+            ("ssim".to_owned(), &self.ssim),
+        ];
+        for (name, lr) in LR_NAMES.iter().zip(&self.learning_rates) {
+            series.push((format!("lr_{name}"), lr));
+        }
+        series
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = "metric,iter,value\n".to_owned();
+        for (name, series) in self.all_series() {
+            for [iter, value] in series {
+                csv += &format!("{name},{iter},{value}\n");
+            }
+        }
+        csv
+    }
+}
+
+pub(crate) struct PlotsPanel {
+    metrics: Metrics,
+    // The loss needs to be read back from the GPU, which is done async.
+    latest_loss: Option<(u32, Tensor<TrainBack, 1>)>,
+    loss_reading: bool,
+    last_loss_read: Option<Instant>,
+    loss_sender: Sender<(u32, f32)>,
+    loss_receiver: Receiver<(u32, f32)>,
+    link_axes: bool,
+}
+
+impl PlotsPanel {
+    pub(crate) fn new() -> Self {
+        let (loss_sender, loss_receiver) = channel();
+        Self {
+            metrics: Metrics::default(),
+            latest_loss: None,
+            loss_reading: false,
+            last_loss_read: None,
+            loss_sender,
+            loss_receiver,
+            link_axes: true,
+        }
+    }
+
+    fn plot(&self, ui: &mut egui::Ui, id: &str, lines: Vec<(String, &Series)>) {
+        let mut plot = Plot::new(id)
+            .height(140.0)
+            .legend(Legend::default())
+            .x_axis_label(tr("step"));
+
+        if self.link_axes {
+            plot = plot.link_axis("plots_x_axis", [true, false]);
+        }
+
+        plot.show(ui, |plot_ui| {
+            for (name, series) in lines {
+                plot_ui.line(Line::new(PlotPoints::from(series.clone())).name(name));
+            }
+        });
+    }
+
+    fn read_loss(&mut self) {
+        while let Ok((iter, loss)) = self.loss_receiver.try_recv() {
+            self.metrics.loss.push([iter as f64, loss as f64]);
+            self.loss_reading = false;
+        }
+
+        let due = self
+            .last_loss_read
+            .is_none_or(|last| last.elapsed() >= LOSS_READ_INTERVAL);
+        if self.loss_reading || !due {
+            return;
+        }
+        let Some((iter, loss)) = self.latest_loss.take() else {
+            return;
+        };
+
+        self.loss_reading = true;
+        self.last_loss_read = Some(Instant::now());
+        let sender = self.loss_sender.clone();
+        tokio_wasm::task::spawn(async move {
+            let loss = loss.into_scalar_async().await;
+            let _ = sender.send((iter, loss));
+        });
+    }
+}
+
+impl AppPanel for PlotsPanel {
+    fn title(&self) -> String {
+        tr("Plots").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        match message {
+            ProcessMessage::NewSource | ProcessMessage::StartLoading { .. } => {
+                // Make a new channel so in-flight readbacks from a previous run are dropped.
+                *self = Self {
+                    link_axes: self.link_axes,
+                    ..Self::new()
+                };
+            }
+            ProcessMessage::TrainStep {
+                splats,
+                stats,
+                iter,
+                timestamp: _,
+            } => {
+                let x = *iter as f64;
+                let m = &mut self.metrics;
+                m.splats.push([x, splats.num_splats() as f64]);
+
+                let lrs = [
+                    stats.lr_mean,
+                    stats.lr_rotation,
+                    stats.lr_scale,
+                    stats.lr_coeffs,
+                    stats.lr_opac,
+                ];
+                for (series, lr) in m.learning_rates.iter_mut().zip(lrs) {
+                    series.push([x, lr]);
+                }
+
+                self.latest_loss = Some((*iter, stats.loss.clone()));
+            }
+            ProcessMessage::EvalResult {
+                iter,
+                avg_psnr,
+                avg_ssim,
+                ..
+            } => {
+                let x = *iter as f64;
+                self.metrics.psnr.push([x, *avg_psnr as f64]);
+                self.metrics.ssim.push([x, *avg_ssim as f64]);
+            }
+            _ => {}
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
+        self.read_loss();
+
+        if self.metrics.splats.is_empty() {
+            ui.label(tr("Start training to see plots of the training progress."));
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.link_axes, tr("Link steps"))
+                .on_hover_text(tr("Zoom and pan all plots together"));
+
+            if ui.button(tr("⬆ Export CSV")).clicked() {
+                let csv = self.metrics.to_csv();
+                tokio_wasm::task::spawn(async move {
+                    let file = match rrfd::save_file("training_metrics.csv").await {
+                        Ok(file) => file,
+                        Err(e) => {
+                            log::error!("Failed to save file: {e}");
+                            return;
+                        }
+                    };
+                    if let Err(e) = file.write(csv.as_bytes()).await {
+                        log::error!("Failed to write file: {e}");
+                    }
+                });
+            }
+        });
+        ui.label(tr("Scroll to zoom, drag to pan and double click to reset."));
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let m = &self.metrics;
+
+            ui.label(tr("Loss"));
+            self.plot(ui, "plot_loss", vec![("loss".to_owned(), &m.loss)]);
+
+            if !m.psnr.is_empty() {
+                ui.label(tr("Eval PSNR"));
+                self.plot(ui, "plot_psnr", vec![("psnr".to_owned(), &m.psnr)]);
+
+                ui.label(tr("Eval SSIM"));
+                self.plot(ui, "plot_ssim", vec![("ssim".to_owned(), &m.ssim)]);
+            }
+
+            ui.label(tr("Splat count"));
+            self.plot(ui, "plot_splats", vec![("splats".to_owned(), &m.splats)]);
+
+            // Learning rates span orders of magnitude, so plot them on a log scale.
+            let log_lrs: Vec<Series> = m
+                .learning_rates
+                .iter()
+                .map(|s| s.iter().map(|[x, y]| [*x, y.max(1e-12).log10()]).collect())
+                .collect();
+            ui.label(tr("Learning rates (log10)"));
+            self.plot(
+                ui,
+                "plot_lr",
+                LR_NAMES
+                    .iter()
+                    .zip(&log_lrs)
+                    .map(|(name, s)| ((*name).to_owned(), s))
+                    .collect(),
+            );
+        });
+    }
+}
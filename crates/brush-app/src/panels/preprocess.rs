@@ -0,0 +1,501 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+use brush_dataset::{LoadDataseConfig, preprocess};
+use brush_process::process_loop::{ProcessMessage, start_process};
+use brush_train::scene::SceneView;
+use egui::{Color32, Rect, Slider, TextureHandle, TextureOptions, pos2};
+use image::DynamicImage;
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use crate::recent::RecentLocation;
+
+/// Quality measures of a view, to preview which views the filters would skip.
+#[derive(Clone, Copy)]
+struct FrameQuality {
+    sharpness: f32,
+    underexposed: f32,
+    overexposed: f32,
+}
+
+impl FrameQuality {
+    fn measure(image: &DynamicImage) -> Self {
+        let (underexposed, overexposed) = preprocess::clipped_fractions(image);
+        Self {
+            sharpness: preprocess::sharpness(image),
+            underexposed,
+            overexposed,
+        }
+    }
+
+    fn skipped_by(&self, config: &LoadDataseConfig) -> bool {
+        config.min_sharpness.is_some_and(|min| self.sharpness < min)
+            || config
+                .max_underexposed
+                .is_some_and(|max| self.underexposed > max)
+            || config
+                .max_overexposed
+                .is_some_and(|max| self.overexposed > max)
+    }
+}
+
+struct Preview {
+    index: usize,
+    show_mask: bool,
+    texture: TextureHandle,
+}
+
+fn preview_image(view: &SceneView, show_mask: bool) -> egui::ColorImage {
+    let image = &view.image;
+    let size = [image.width() as usize, image.height() as usize];
+    if show_mask {
+        // Show the alpha channel, which holds the mask of masked images.
+        let alpha: Vec<u8> = image.to_rgba8().pixels().map(|p| p[3]).collect();
+        egui::ColorImage::from_gray(size, &alpha)
+    } else if image.color().has_alpha() {
+        egui::ColorImage::from_rgba_unmultiplied(size, &image.to_rgba8().into_vec())
+    } else {
+        egui::ColorImage::from_rgb(size, &image.to_rgb8().into_vec())
+    }
+}
+
+/// Previews how the dataset is preprocessed while loading (downscaling, cropping, and skipping
+/// blurry frames), and reloads the dataset with new settings.
+pub(crate) struct PreprocessPanel {
+    config: Option<LoadDataseConfig>,
+    index: usize,
+    show_mask: bool,
+    preview: Option<Preview>,
+    // Similarity of a view to the view before it.
+    similarity: Option<(usize, f32)>,
+    // Quality of each training view, in the order they were loaded.
+    quality: Vec<FrameQuality>,
+    quality_receiver: Option<Receiver<FrameQuality>>,
+}
+
+impl PreprocessPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            config: None,
+            index: 0,
+            show_mask: false,
+            preview: None,
+            similarity: None,
+            quality: vec![],
+            quality_receiver: None,
+        }
+    }
+
+    fn update_quality(&mut self, ctx: &egui::Context, views: &[SceneView]) {
+        if let Some(receiver) = self.quality_receiver.as_ref() {
+            loop {
+                match receiver.try_recv() {
+                    Ok(quality) => self.quality.push(quality),
+                    Err(TryRecvError::Empty) => {
+                        ctx.request_repaint();
+                        return;
+                    }
+                    Err(TryRecvError::Disconnected) => {
+                        self.quality_receiver = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Measure any views that were loaded since.
+        if self.quality.len() < views.len() {
+            let images: Vec<_> = views[self.quality.len()..]
+                .iter()
+                .map(|v| v.image.clone())
+                .collect();
+            let (sender, receiver) = std::sync::mpsc::channel();
+            tokio_wasm::task::spawn(async move {
+                for image in images {
+                    if sender.send(FrameQuality::measure(&image)).is_err() {
+                        break;
+                    }
+                }
+            });
+            self.quality_receiver = Some(receiver);
+            ctx.request_repaint();
+        }
+    }
+
+    fn preview_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &SceneView,
+        applied: &LoadDataseConfig,
+        config: &LoadDataseConfig,
+    ) {
+        let dirty = self
+            .preview
+            .as_ref()
+            .is_none_or(|p| p.index != self.index || p.show_mask != self.show_mask);
+        if dirty {
+            self.preview = Some(Preview {
+                index: self.index,
+                show_mask: self.show_mask,
+                texture: ui.ctx().load_texture(
+                    "preprocess_preview",
+                    preview_image(view, self.show_mask),
+                    TextureOptions::default(),
+                ),
+            });
+        }
+        let Some(preview) = self.preview.as_ref() else {
+            return;
+        };
+
+        let img_size = preview.texture.size();
+        let size = egui::Image::new(&preview.texture)
+            .shrink_to_fit()
+            .calc_size(
+                brush_ui::size_for_splat_view(ui),
+                Some(egui::vec2(img_size[0] as f32, img_size[1] as f32)),
+            );
+        let rect = Rect::from_min_size(ui.cursor().min, size.round());
+
+        if !self.show_mask {
+            brush_ui::draw_checkerboard(ui, rect, Color32::WHITE);
+        }
+        ui.painter().image(
+            preview.texture.id(),
+            rect,
+            Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+            Color32::WHITE,
+        );
+
+        // The loaded images might already be cropped, only show the crop on top of that.
+        let crop = config.center_crop.unwrap_or(1.0) / applied.center_crop.unwrap_or(1.0);
+        if crop < 1.0 {
+            let crop_rect = Rect::from_center_size(rect.center(), rect.size() * crop);
+            let shade = Color32::from_black_alpha(160);
+            let painter = ui.painter();
+            painter.rect_filled(
+                Rect::from_min_max(rect.min, pos2(rect.max.x, crop_rect.min.y)),
+                0.0,
+                shade,
+            );
+            painter.rect_filled(
+                Rect::from_min_max(pos2(rect.min.x, crop_rect.max.y), rect.max),
+                0.0,
+                shade,
+            );
+            painter.rect_filled(
+                Rect::from_min_max(
+                    pos2(rect.min.x, crop_rect.min.y),
+                    pos2(crop_rect.min.x, crop_rect.max.y),
+                ),
+                0.0,
+                shade,
+            );
+            painter.rect_filled(
+                Rect::from_min_max(
+                    pos2(crop_rect.max.x, crop_rect.min.y),
+                    pos2(rect.max.x, crop_rect.max.y),
+                ),
+                0.0,
+                shade,
+            );
+            painter.rect_stroke(
+                crop_rect,
+                0.0,
+                (1.0, Color32::YELLOW),
+                egui::StrokeKind::Middle,
+            );
+        }
+        ui.allocate_rect(rect, egui::Sense::hover());
+    }
+}
+
+impl AppPanel for PreprocessPanel {
+    fn title(&self) -> String {
+        tr("Preprocess").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        if let ProcessMessage::NewSource = message {
+            *self = Self::new();
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        let views = context.dataset.train.views.clone();
+        let applied = context.process_args().map(|a| a.load_config.clone());
+        let (Some(applied), false) = (applied, views.is_empty()) else {
+            ui.label(tr("Load a dataset to preview how it is preprocessed."));
+            return;
+        };
+
+        let mut config = self.config.take().unwrap_or_else(|| applied.clone());
+
+        self.index = self.index.min(views.len() - 1);
+        let view = &views[self.index];
+
+        self.preview_ui(ui, view, &applied, &config);
+
+        ui.horizontal(|ui| {
+            let count = views.len();
+            ui.add(
+                Slider::new(&mut self.index, 0..=count - 1)
+                    .suffix(format!("/ {count}"))
+                    .custom_formatter(|num, _| format!("{}", num as usize + 1))
+                    .custom_parser(|s| s.parse::<usize>().ok().map(|n| n as f64 - 1.0)),
+            );
+            let has_alpha = view.image.color().has_alpha();
+            ui.add_enabled(
+                has_alpha,
+                egui::Checkbox::new(&mut self.show_mask, tr("Show mask")),
+            )
+            .on_disabled_hover_text(tr("This image has no mask or transparency."));
+            if !has_alpha {
+                self.show_mask = false;
+            }
+        });
+
+        ui.add_space(6.0);
+        ui.heading(tr("Preprocessing"));
+
+        // Downscaling.
+        ui.label(tr("Max image resolution"));
+        ui.add(
+            Slider::new(&mut config.max_resolution, 32..=4096)
+                .clamping(egui::SliderClamping::Never),
+        );
+
+        // Cropping.
+        let mut crop = config.center_crop.is_some();
+        ui.checkbox(&mut crop, tr("Crop around the center"));
+        if crop {
+            let fraction = config.center_crop.get_or_insert(0.8);
+            ui.add(
+                Slider::new(fraction, 0.1..=1.0)
+                    .custom_formatter(|f, _| format!("{:.0}%", f * 100.0))
+                    .custom_parser(|s| {
+                        s.trim_end_matches('%')
+                            .parse::<f64>()
+                            .ok()
+                            .map(|p| p / 100.0)
+                    }),
+            );
+        } else {
+            config.center_crop = None;
+        }
+
+        let (w, h) = (view.image.width(), view.image.height());
+        let crop_size = |size: u32| {
+            let crop = config.center_crop.unwrap_or(1.0) / applied.center_crop.unwrap_or(1.0);
+            (size as f32 * crop.min(1.0)).round() as u32
+        };
+        let (cw, ch) = (crop_size(w), crop_size(h));
+        let scale = (config.max_resolution as f32 / cw.max(ch) as f32).min(1.0);
+        ui.weak(format!(
+            "{w}×{h} → {}×{}",
+            (cw as f32 * scale).round(),
+            (ch as f32 * scale).round()
+        ));
+
+        // Low quality frames.
+        let mut skip_blurry = config.min_sharpness.is_some();
+        ui.checkbox(&mut skip_blurry, tr("Skip blurry frames"))
+            .on_hover_text(tr(
+                "Sharpness is the variance of the Laplacian of the image. Blurry images have few edges and score low.",
+            ));
+        if skip_blurry {
+            let threshold = config.min_sharpness.get_or_insert(50.0);
+            ui.add(
+                Slider::new(threshold, 1.0..=1000.0)
+                    .logarithmic(true)
+                    .text(tr("Min sharpness")),
+            );
+        } else {
+            config.min_sharpness = None;
+        }
+
+        let percent = |f: f64, _| format!("{:.0}%", f * 100.0);
+        let mut skip_dark = config.max_underexposed.is_some();
+        ui.checkbox(&mut skip_dark, tr("Skip underexposed frames"));
+        if skip_dark {
+            let max = config.max_underexposed.get_or_insert(0.5);
+            ui.add(
+                Slider::new(max, 0.0..=1.0)
+                    .custom_formatter(percent)
+                    .text(tr("Max black pixels")),
+            );
+        } else {
+            config.max_underexposed = None;
+        }
+
+        let mut skip_bright = config.max_overexposed.is_some();
+        ui.checkbox(&mut skip_bright, tr("Skip overexposed frames"));
+        if skip_bright {
+            let max = config.max_overexposed.get_or_insert(0.3);
+            ui.add(
+                Slider::new(max, 0.0..=1.0)
+                    .custom_formatter(percent)
+                    .text(tr("Max blown out pixels")),
+            );
+        } else {
+            config.max_overexposed = None;
+        }
+
+        if skip_blurry || skip_dark || skip_bright {
+            self.update_quality(ui.ctx(), &views);
+
+            if let Some(quality) = self.quality.get(self.index) {
+                let text = format!(
+                    "{}: {:.1}, {:.0}% {}, {:.0}% {}",
+                    tr("This frame"),
+                    quality.sharpness,
+                    quality.underexposed * 100.0,
+                    tr("black"),
+                    quality.overexposed * 100.0,
+                    tr("blown out"),
+                );
+                if quality.skipped_by(&config) {
+                    ui.colored_label(Color32::LIGHT_RED, text);
+                } else {
+                    ui.label(text);
+                }
+            }
+
+            let skipped = self
+                .quality
+                .iter()
+                .filter(|q| q.skipped_by(&config))
+                .count();
+            ui.label(format!(
+                "{skipped} / {} {}",
+                self.quality.len(),
+                tr("frames would be skipped")
+            ));
+            if self.quality_receiver.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(tr("Measuring frame quality…"));
+                });
+            }
+        }
+
+        let mut skip_duplicates = config.dedup_similarity.is_some();
+        ui.checkbox(&mut skip_duplicates, tr("Skip duplicate frames"))
+            .on_hover_text(tr(
+                "Skip frames that look nearly the same as the frame before them, while the camera barely moved.",
+            ));
+        if skip_duplicates {
+            let min = config.dedup_similarity.get_or_insert(0.97);
+            ui.add(
+                Slider::new(min, 0.5..=1.0)
+                    .custom_formatter(percent)
+                    .text(tr("Min similarity")),
+            );
+
+            if self.index > 0 {
+                let similarity = match self.similarity {
+                    Some((index, similarity)) if index == self.index => similarity,
+                    _ => {
+                        let previous = &views[self.index - 1].image;
+                        let similarity = preprocess::similarity(previous, &view.image);
+                        self.similarity = Some((self.index, similarity));
+                        similarity
+                    }
+                };
+                ui.label(format!(
+                    "{}: {:.1}%",
+                    tr("Similarity to the previous frame"),
+                    similarity * 100.0
+                ));
+            }
+        } else {
+            config.dedup_similarity = None;
+        }
+
+        ui.checkbox(
+            &mut config.normalize_colors,
+            tr("Match exposure and white balance"),
+        )
+        .on_hover_text(tr(
+            "Adjust the colors of all frames to match the first frame, for cameras that used automatic exposure or white balance.",
+        ));
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Known color corrections"));
+            let mut file = config.color_calibration.clone().unwrap_or_default();
+            ui.text_edit_singleline(&mut file).on_hover_text(tr(
+                "A JSON file in the dataset, as exported with the splats.",
+            ));
+            config.color_calibration = (!file.is_empty()).then_some(file);
+        });
+
+        let calibration = &context.dataset.preprocess.color_calibration;
+        if !calibration.is_empty() && ui.button(tr("⬆ Export color corrections")).clicked() {
+            match calibration.to_json() {
+                Ok(json) => {
+                    tokio_wasm::task::spawn(async move {
+                        let file = match rrfd::save_file("colors.json").await {
+                            Ok(file) => file,
+                            Err(e) => {
+                                log::error!("Failed to save file: {e}");
+                                return;
+                            }
+                        };
+                        if let Err(e) = file.write(&json).await {
+                            log::error!("Failed to write file: {e}");
+                        }
+                    });
+                }
+                Err(e) => log::error!("Failed to export color corrections: {e}"),
+            }
+        }
+
+        // What was skipped with the current settings.
+        if let Some(summary) = context.dataset.skipped_summary() {
+            ui.add_space(6.0);
+            egui::CollapsingHeader::new(summary)
+                .id_salt("skipped_views")
+                .show(ui, |ui| {
+                    for skipped in &context.dataset.preprocess.skipped {
+                        ui.label(format!("{} ({})", skipped.path, skipped.reason.describe()));
+                    }
+                });
+        }
+
+        ui.add_space(6.0);
+
+        // Settings are applied while loading the images, so reload the data to apply them.
+        let source = context
+            .current_location
+            .as_ref()
+            .map(RecentLocation::to_source);
+        let changed = config.max_resolution != applied.max_resolution
+            || config.center_crop != applied.center_crop
+            || config.min_sharpness != applied.min_sharpness
+            || config.max_underexposed != applied.max_underexposed
+            || config.max_overexposed != applied.max_overexposed
+            || config.dedup_similarity != applied.dedup_similarity
+            || config.normalize_colors != applied.normalize_colors
+            || config.color_calibration != applied.color_calibration;
+        let reload = ui
+            .add_enabled(
+                source.is_some() && changed,
+                egui::Button::new(tr("Reload with these settings")),
+            )
+            .on_disabled_hover_text(if source.is_some() {
+                tr("The settings haven't changed.")
+            } else {
+                tr("This data can't be opened again, open it from the settings panel instead.")
+            })
+            .clicked();
+
+        if let (true, Some(source), Some(args)) = (reload, source, context.process_args()) {
+            let mut args = args.clone();
+            args.load_config = config.clone();
+            context.connect_to(start_process(source, args, context.device.clone()));
+        }
+
+        self.config = Some(config);
+    }
+}
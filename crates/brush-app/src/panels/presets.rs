@@ -1,4 +1,5 @@
 use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
 use brush_process::process_loop::ProcessMessage;
 use egui::Hyperlink;
 
@@ -16,13 +17,13 @@ fn url_button(label: &str, url: &str, ui: &mut egui::Ui) {
 
 impl AppPanel for PresetsPanel {
     fn title(&self) -> String {
-        "Presets".to_owned()
+        tr("Presets").to_owned()
     }
 
     fn on_message(&mut self, _: &ProcessMessage, _: &mut AppContext) {}
 
     fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
-        ui.heading("Mipnerf scenes");
+        ui.heading(tr("Mipnerf scenes"));
 
         egui::Grid::new("mip_grid")
             .num_columns(3)
@@ -43,7 +44,7 @@ impl AppPanel for PresetsPanel {
                 ui.end_row();
             });
 
-        ui.heading("Synthetic blender scenes");
+        ui.heading(tr("Synthetic blender scenes"));
         egui::Grid::new("blend_grid")
             .num_columns(4)
             .spacing([40.0, 4.0])
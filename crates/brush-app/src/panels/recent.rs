@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use brush_process::data_source::DataSource;
+use brush_process::process_loop::ProcessArgs;
+use egui::{TextureHandle, TextureOptions};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::i18n::tr;
+use crate::recent::{RecentLocation, RecentSources};
+
+const THUMBNAIL_DISPLAY_WIDTH: f32 = 120.0;
+
+fn load_thumbnail(ctx: &egui::Context, name: &str, png: &[u8]) -> Option<TextureHandle> {
+    let image = image::load_from_memory(png).ok()?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Some(ctx.load_texture(name, image, TextureOptions::LINEAR))
+}
+
+enum Thumbnail {
+    // Being read from the cache of thumbnails.
+    Reading(Receiver<Option<Vec<u8>>>),
+    // `None` if there is no thumbnail, or it failed to decode.
+    Loaded(Option<TextureHandle>),
+}
+
+/// Lists recently opened data with a thumbnail, to open it again with one click.
+pub(crate) struct RecentUi {
+    thumbnails: HashMap<RecentLocation, Thumbnail>,
+}
+
+impl RecentUi {
+    pub(crate) fn new() -> Self {
+        Self {
+            thumbnails: HashMap::new(),
+        }
+    }
+
+    fn thumbnail(
+        &mut self,
+        ctx: &egui::Context,
+        location: &RecentLocation,
+    ) -> Option<TextureHandle> {
+        let thumbnail = self.thumbnails.entry(location.clone()).or_insert_with(|| {
+            let (sender, receiver) = channel();
+            let id = location.thumbnail_id();
+            let ctx = ctx.clone();
+            tokio_wasm::task::spawn(async move {
+                let png = brush_process::thumbnails::load(&id)
+                    .await
+                    .inspect_err(|e| log::warn!("Failed to read thumbnail: {e}"))
+                    .ok()
+                    .flatten();
+                let _ = sender.send(png);
+                ctx.request_repaint();
+            });
+            Thumbnail::Reading(receiver)
+        });
+
+        if let Thumbnail::Reading(receiver) = thumbnail {
+            match receiver.try_recv() {
+                Ok(png) => {
+                    let name = location.thumbnail_id();
+                    let texture = png.and_then(|png| load_thumbnail(ctx, &name, &png));
+                    *thumbnail = Thumbnail::Loaded(texture);
+                }
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => *thumbnail = Thumbnail::Loaded(None),
+            }
+        }
+        match thumbnail {
+            Thumbnail::Loaded(texture) => texture.clone(),
+            Thumbnail::Reading(_) => None,
+        }
+    }
+
+    /// Draw the list of recent data. Returns a source to load, and the settings to load it with,
+    /// when an entry is clicked.
+    pub(crate) fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        recent: &mut RecentSources,
+    ) -> Option<(DataSource, ProcessArgs)> {
+        let mut open = None;
+        let mut remove = None;
+
+        ui.heading(tr("Recently opened"));
+        ui.add_space(5.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for entry in recent.entries() {
+                let texture = self.thumbnail(ui.ctx(), &entry.location);
+
+                ui.horizontal(|ui| {
+                    let clicked = if let Some(texture) = texture {
+                        let image =
+                            egui::Image::from_texture(&texture).max_width(THUMBNAIL_DISPLAY_WIDTH);
+                        ui.add(egui::Button::image(image)).clicked()
+                    } else {
+                        ui.add(
+                            egui::Button::new("🗋")
+                                .min_size(egui::vec2(THUMBNAIL_DISPLAY_WIDTH, 68.0)),
+                        )
+                        .clicked()
+                    };
+
+                    ui.vertical(|ui| {
+                        let name_clicked = ui.link(&entry.name).clicked();
+                        if clicked || name_clicked {
+                            open = Some((entry.location.to_source(), entry.args.clone()));
+                        }
+                        ui.weak(entry.location.describe());
+
+                        if ui.small_button(tr("Remove")).clicked() {
+                            remove = Some(entry.location.clone());
+                        }
+                    });
+                });
+                ui.add_space(4.0);
+            }
+        });
+
+        if let Some(location) = remove {
+            self.thumbnails.remove(&location);
+            recent.remove(&location);
+            let id = location.thumbnail_id();
+            tokio_wasm::task::spawn(async move {
+                if let Err(e) = brush_process::thumbnails::remove(&id).await {
+                    log::warn!("Failed to remove thumbnail: {e}");
+                }
+            });
+        }
+
+        open
+    }
+
+    /// Show a newly rendered thumbnail.
+    pub(crate) fn set_thumbnail(
+        &mut self,
+        ctx: &egui::Context,
+        location: RecentLocation,
+        png: &[u8],
+    ) {
+        let texture = load_thumbnail(ctx, &location.thumbnail_id(), png);
+        self.thumbnails.insert(location, Thumbnail::Loaded(texture));
+    }
+}
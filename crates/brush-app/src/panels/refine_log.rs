@@ -0,0 +1,79 @@
+use brush_process::process_loop::ProcessMessage;
+use brush_train::train::RefineStats;
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use crate::locale;
+
+/// Lists every refine step of the current run, to see when splats were added or removed, eg. to
+/// explain a jump in memory or a dip in quality.
+pub(crate) struct RefineLogPanel {
+    events: Vec<(u32, RefineStats)>,
+}
+
+impl RefineLogPanel {
+    pub(crate) fn new() -> Self {
+        Self { events: vec![] }
+    }
+}
+
+impl AppPanel for RefineLogPanel {
+    fn title(&self) -> String {
+        tr("Refine log").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        match message {
+            ProcessMessage::NewSource | ProcessMessage::StartLoading { .. } => self.events.clear(),
+            ProcessMessage::RefineStep { stats, iter } => {
+                // Only the counts are listed, don't keep the pruned splats on the GPU.
+                let stats = RefineStats {
+                    pruned_means: None,
+                    ..stats.as_ref().clone()
+                };
+                self.events.push((*iter, stats));
+            }
+            _ => {}
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
+        if self.events.is_empty() {
+            ui.label(tr("Nothing has been refined yet."));
+            return;
+        }
+
+        egui::ScrollArea::both()
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                egui::Grid::new("refine_log")
+                    .num_columns(7)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for header in [
+                            tr("Step"),
+                            tr("Split"),
+                            tr("Cloned"),
+                            tr("Transparent"),
+                            tr("Too big"),
+                            tr("Splats"),
+                            tr("Opacity reset"),
+                        ] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+
+                        for (iter, stats) in &self.events {
+                            ui.label(locale::count(*iter));
+                            ui.label(locale::count(stats.num_split));
+                            ui.label(locale::count(stats.num_cloned));
+                            ui.label(locale::count(stats.num_transparent_pruned));
+                            ui.label(locale::count(stats.num_scale_pruned));
+                            ui.label(locale::count(stats.num_splats));
+                            ui.label(if stats.opacity_reset { "✔" } else { "" });
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
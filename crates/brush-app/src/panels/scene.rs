@@ -1,78 +1,1097 @@
-use brush_dataset::splat_export;
-use brush_process::process_loop::{ControlMessage, ProcessMessage};
-use brush_train::{scene::ViewImageType, train::TrainBack};
+use brush_dataset::splat_export::{self, ExportFormat};
+use brush_process::process_loop::{
+    ControlMessage, EvalMetrics, ProcessArgs, ProcessMessage, RunMetadata, start_process,
+};
+use brush_train::{
+    diagnostics::tracking_heatmap, image::tensor_into_image, scene::ViewImageType, train::TrainBack,
+};
 use brush_ui::burn_texture::BurnTexture;
+use burn::module::ParamId;
 use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{FloatDType, Tensor};
 use core::f32;
 use egui::epaint::mutex::RwLock as EguiRwLock;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::time::Duration;
 
 use brush_render::{
-    camera::{focal_to_fov, fov_to_focal},
-    gaussian_splats::Splats,
+    DepthOrder,
+    bounding_box::BoundingBox,
+    camera::{
+        Camera, DEFAULT_FAR, DEFAULT_NEAR, DisplayTransform, SectionPlane, Shading, focal_to_fov,
+        fov_to_focal,
+    },
+    gaussian_splats::{Splats, TrackedStat},
+    render::BlendMode,
 };
 use eframe::egui_wgpu::Renderer;
 use egui::{Color32, Rect};
-use glam::{Quat, UVec2, Vec3};
+use glam::{Affine3A, IVec3, Quat, UVec2, Vec3};
+use serde::{Deserialize, Serialize};
 use tokio_with_wasm::alias as tokio_wasm;
 use tracing::trace_span;
 use web_time::Instant;
 
+use crate::annotations::AnnotationView;
 use crate::app::{AppContext, AppPanel};
+use crate::compare::{
+    CompareMode, Comparison, ViewSplats, WorstView, draw_comparison, error_comparison,
+    pick_comparison,
+};
+use crate::contribution::{ContributionFilter, measure_views};
+use crate::framing::robust_bounds;
+use crate::i18n::tr;
+use crate::keymap::Action;
+use crate::lighting::{Lighting, ShadingMode};
+use crate::locale;
+use crate::minimap::draw_minimap;
+use crate::orient::{Floor, find_floor};
+use crate::paint::PaintTool;
+use crate::picking::PickTarget;
+use crate::recent::{RecentLocation, THUMBNAIL_WIDTH};
+use crate::reference_layers::{ReferenceOcclusion, draw_reference_layers};
+use crate::refine_highlight::RefineHighlight;
+use crate::scene_graph::SceneNodeKind;
+use crate::section::SectionCut;
+use crate::volumes::VolumeEditor;
+
+use super::recent::RecentUi;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct RenderState {
     size: UVec2,
     cam_pos: Vec3,
     cam_rot: Quat,
+    splats_transform: Affine3A,
+    splats_visible: bool,
+    blend_mode: BlendMode,
+    near: f32,
+    far: f32,
+    section: Option<SectionPlane>,
+    shading: Shading,
+    display: DisplayTransform,
 
     frame: f32,
 }
 
+const SETTINGS_KEY: &str = "scene_panel";
+
+#[derive(Serialize, Deserialize)]
+struct SavedSettings {
+    show_minimap: bool,
+    fast_blending: bool,
+    #[serde(default)]
+    half_precision_colors: bool,
+    #[serde(default)]
+    smooth_camera: Option<bool>,
+}
+
+// Speed of the auto orbit in kiosk mode, in radians per second.
+const KIOSK_ORBIT_SPEED: f32 = 0.1;
+
+// Size of the buckets of camera positions that share view colors, as a fraction of the focus
+// distance.
+const VIEW_COLOR_BUCKET_SIZE: f32 = 0.01;
+
+// Number of buckets to keep view colors of. Each is as large as the colors of all splats.
+const VIEW_COLOR_BUCKETS: usize = 4;
+
+impl RenderState {
+    /// Whether a render of `self` culls and sorts the splats the same as one of `other`, so its
+    /// depth order can be reused. Any change to the camera changes which splats are visible and
+    /// their order, so only changes to how the visible splats are colored qualify.
+    fn can_reuse_order(&self, other: &Self) -> bool {
+        let same_culling = Self {
+            shading: other.shading,
+            display: other.display,
+            frame: other.frame,
+            ..*self
+        } == *other;
+
+        // Weighted blending doesn't sort, so there's little to reuse.
+        same_culling && self.blend_mode == BlendMode::Sorted
+    }
+}
+
+/// A bucket of camera positions close enough to share view colors. The direction each splat is
+/// seen from only depends on where the camera is, so from anywhere in a bucket all splats are
+/// seen from about the same direction as from its center.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct ColorBucket {
+    // Size of the bucket, as a power of two, so it doesn't change while zooming a little.
+    scale: i32,
+    cell: IVec3,
+}
+
+impl ColorBucket {
+    fn new(position: Vec3, focus_distance: f32) -> Self {
+        let size = (focus_distance * VIEW_COLOR_BUCKET_SIZE).max(1e-6);
+        let scale = size.log2().floor() as i32;
+        Self {
+            scale,
+            cell: (position / 2f32.powi(scale)).floor().as_ivec3(),
+        }
+    }
+
+    fn center(&self) -> Vec3 {
+        (self.cell.as_vec3() + 0.5) * 2f32.powi(self.scale)
+    }
+}
+
+/// Splats with the colors seen from the last few buckets of camera positions baked in, so
+/// renders while the camera moves don't need to evaluate the higher SH degrees of every splat.
+#[derive(Default)]
+struct ViewColorCache {
+    // The splats the colors are of.
+    splats: Option<ParamId>,
+    // Most recently used last.
+    buckets: Vec<(
+        ColorBucket,
+        Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+    )>,
+}
+
+impl ViewColorCache {
+    fn get(
+        &mut self,
+        splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+        bucket: ColorBucket,
+    ) -> &Splats<<TrainBack as AutodiffBackend>::InnerBackend> {
+        if self.splats != Some(splats.means.id) {
+            self.clear();
+            self.splats = Some(splats.means.id);
+        }
+        let entry = match self.buckets.iter().position(|(b, _)| *b == bucket) {
+            Some(index) => self.buckets.remove(index),
+            None => {
+                if self.buckets.len() >= VIEW_COLOR_BUCKETS {
+                    self.buckets.remove(0);
+                }
+                (bucket, splats.with_view_colors(bucket.center()))
+            }
+        };
+        self.buckets.push(entry);
+        &self.buckets.last().expect("Just added").1
+    }
+
+    fn clear(&mut self) {
+        self.splats = None;
+        self.buckets.clear();
+    }
+}
+
+/// What's kept of a full render, to reuse for later renders.
+struct FullRender {
+    state: RenderState,
+    // The splats that were rendered, as the order only fits those.
+    splats: ParamId,
+    depth_order: DepthOrder<<TrainBack as AutodiffBackend>::InnerBackend>,
+}
+
+/// Render splats at full precision and encode them as a PNG.
+async fn render_png(
+    splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+    camera: &Camera,
+    size: UVec2,
+    blend_mode: BlendMode,
+) -> anyhow::Result<Vec<u8>> {
+    let (img, _) = splats.render_with_options(camera, size, false, blend_mode, None);
+    let img = tensor_into_image(img.into_data_async().await).to_rgba8();
+    let mut png = vec![];
+    img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+async fn render_hdr(
+    splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+    camera: &Camera,
+    size: UVec2,
+    blend_mode: BlendMode,
+) -> image::Rgba32FImage {
+    let (img, _) = splats.render_with_options(camera, size, false, blend_mode, None);
+    tensor_into_image(img.into_data_async().await).into_rgba32f()
+}
+
 struct ErrorDisplay {
     headline: String,
     context: Vec<String>,
 }
 
+/// What the app knows of the training run, to write next to exports like the process does.
+#[derive(Default)]
+struct RunRecord {
+    dataset_hash: Option<String>,
+    // Whether the dataset has eval views, which are evaluated every `eval_every` steps.
+    has_eval: bool,
+    started: Option<Instant>,
+    loss: Option<Tensor<TrainBack, 1>>,
+    eval: Option<EvalMetrics>,
+}
+
+impl RunRecord {
+    /// Whether the eval of the last `eval_every` step is still running alongside training.
+    fn eval_pending(&self, iter: u32, args: &ProcessArgs) -> bool {
+        let config = &args.process_config;
+        let every = config.eval_every.max(1);
+        let expected = iter / every * every;
+        self.has_eval && expected > config.start_iter && self.eval.is_none_or(|e| e.step < expected)
+    }
+
+    /// How the splats at `iter` were trained, without the loss and number of splats, which are
+    /// only known once the export is ready.
+    fn metadata(&self, iter: u32, args: &ProcessArgs) -> Option<RunMetadata> {
+        let dataset_hash = self.dataset_hash.clone()?;
+        let metadata = match RunMetadata::new(args, dataset_hash) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                log::error!("Failed to record the run: {e}");
+                return None;
+            }
+        };
+        Some(RunMetadata {
+            step: iter,
+            duration_secs: self.started.map_or(0.0, |s| s.elapsed().as_secs_f64()),
+            eval: self.eval,
+            ..metadata
+        })
+    }
+}
+
 pub(crate) struct ScenePanel {
     pub(crate) backbuffer: BurnTexture,
     pub(crate) last_draw: Option<Instant>,
 
     view_splats: Vec<Splats<<TrainBack as AutodiffBackend>::InnerBackend>>,
+    train_iter: u32,
     frame_count: u32,
     frame: f32,
 
     // Ui state.
     live_update: bool,
     paused: bool,
+    show_minimap: bool,
+    // Whether the camera glides on after letting go, and animates jumps to new poses.
+    smooth_camera: bool,
+    // Store the SH coefficients of viewed splats as f16, if the GPU supports it.
+    half_precision_colors: bool,
+    blend_mode: BlendMode,
+    // Near plane and max render distance of the viewer, to peel away the foreground or skip
+    // rendering far away parts of huge scenes.
+    clip_near: f32,
+    clip_far: Option<f32>,
+    section: Option<SectionCut>,
+    lighting: Lighting,
+    // Exposure and gamma of the viewer.
+    display: DisplayTransform,
+    annotation_view: AnnotationView,
+    // The last render, to pick points on the splats from.
+    pick_target: Option<PickTarget>,
+    // Depth of the splats, to hide reference geometry behind them.
+    reference_occlusion: ReferenceOcclusion,
+    // Paints the splats while dragging over the view, when enabled.
+    paint: Option<PaintTool>,
+    // Hides the splats that contribute least, to preview pruning them, when enabled.
+    contribution: Option<ContributionFilter>,
+    // Highlights the splats the last refine added and pruned while training, when enabled.
+    refine_highlight: Option<RefineHighlight>,
+    // Volumes to keep the splats inside of when exporting.
+    volumes: VolumeEditor,
+    // The floor being looked for, to stand the splats upright on.
+    orienting: Option<Receiver<Option<Floor>>>,
+    // Bounds to put the camera in front of, whether they're of splats that were just loaded, and
+    // whether to move the camera to them.
+    framing: Option<(Receiver<Option<BoundingBox>>, bool, bool)>,
+    // Bounds of splats loaded without a dataset, in the space of the splats, for the minimap.
+    splat_bounds: Option<BoundingBox>,
+    // The point on the splats that was double clicked, to orbit around.
+    pivot: Option<Receiver<Option<Vec3>>>,
+    // Whether the splats were changed in the viewer, so they can be exported.
+    edited: bool,
+    gyro_look: bool,
+    // Device and camera rotation when gyro look was enabled.
+    gyro_reference: Option<(Quat, Quat)>,
     err: Option<ErrorDisplay>,
+    // Problems that didn't stop loading, shown until dismissed.
+    warnings: Vec<String>,
     zen: bool,
+    // In kiosk mode, how long to wait without interaction before orbiting.
+    kiosk_idle: Option<Duration>,
+    last_interaction: Instant,
+    recent_ui: RecentUi,
+    // Render a thumbnail for the list of recent data once loading is done.
+    wants_thumbnail: bool,
+    thumbnail: Option<Receiver<(RecentLocation, Vec<u8>)>>,
+    // Where the last export was written, to show it in the file manager.
+    exported: Option<PathBuf>,
+    export_receiver: Option<Receiver<PathBuf>>,
+    run: RunRecord,
+    // Whether an export waits for the eval running alongside training, to include its results.
+    export_waiting: bool,
+    // Export being prepared to share, and its file name and data once it's ready. Browsers only
+    // open the share sheet right after a click, so on the web it's shared with a second click.
+    share_receiver: Option<Receiver<(String, Vec<u8>)>>,
+    share_ready: Option<(String, Vec<u8>)>,
+
+    // Other splats to compare with, rendered from the same camera.
+    compare: Option<Comparison>,
+    compare_backbuffer: BurnTexture,
+    compare_receiver: Option<Receiver<anyhow::Result<(String, ViewSplats)>>>,
+    error_receiver: Option<Receiver<(ViewSplats, Vec<WorstView>)>>,
+    // Views with the largest error, found together with the error heatmap.
+    worst_views: Vec<WorstView>,
 
     // Keep track of what was last rendered.
     last_state: Option<RenderState>,
+    // The last full render, parts of which are reused for later renders.
+    full_render: Option<FullRender>,
+    view_colors: ViewColorCache,
+    // Whether the last render reused old view colors, and should be refined.
+    needs_full_render: bool,
+}
+
+/// Send an export to another app with the share sheet of the system or browser. This opens the
+/// share sheet right away, so on the web it has to be called from a click.
+fn share_export((name, data): &(String, Vec<u8>)) {
+    let shared = rrfd::share_file(name, data);
+    tokio_wasm::task::spawn(async move {
+        if let Err(e) = shared.await {
+            log::error!("Failed to share file: {e}");
+        }
+    });
 }
 
 impl ScenePanel {
+    /// What to store the SH coefficients of viewed splats as. Splats being trained are left as
+    /// they are, as the trainer keeps them at full precision anyway.
+    fn sh_dtype(&self, context: &AppContext) -> FloatDType {
+        if self.half_precision_colors && context.supports_f16 {
+            FloatDType::F16
+        } else {
+            FloatDType::F32
+        }
+    }
+
     pub(crate) fn new(
         device: wgpu::Device,
         queue: wgpu::Queue,
         renderer: Arc<EguiRwLock<Renderer>>,
         zen: bool,
+        kiosk_idle: Option<Duration>,
     ) -> Self {
         Self {
+            compare_backbuffer: BurnTexture::new(renderer.clone(), device.clone(), queue.clone()),
             backbuffer: BurnTexture::new(renderer, device, queue),
+            compare: None,
+            compare_receiver: None,
+            error_receiver: None,
+            worst_views: vec![],
             last_draw: None,
             err: None,
+            warnings: vec![],
             view_splats: vec![],
+            train_iter: 0,
             live_update: true,
             paused: false,
+            show_minimap: false,
+            smooth_camera: true,
+            half_precision_colors: false,
+            blend_mode: BlendMode::Sorted,
+            clip_near: DEFAULT_NEAR,
+            clip_far: None,
+            section: None,
+            lighting: Lighting::new(),
+            display: DisplayTransform::IDENTITY,
+            annotation_view: AnnotationView::new(),
+            pick_target: None,
+            reference_occlusion: ReferenceOcclusion::default(),
+            paint: None,
+            contribution: None,
+            refine_highlight: None,
+            volumes: VolumeEditor::new(),
+            orienting: None,
+            framing: None,
+            splat_bounds: None,
+            pivot: None,
+            edited: false,
+            gyro_look: false,
+            gyro_reference: None,
             last_state: None,
+            full_render: None,
+            view_colors: ViewColorCache::default(),
+            needs_full_render: false,
             zen,
+            kiosk_idle,
+            last_interaction: Instant::now(),
+            recent_ui: RecentUi::new(),
+            wants_thumbnail: false,
+            thumbnail: None,
+            exported: None,
+            export_receiver: None,
+            run: RunRecord::default(),
+            export_waiting: false,
+            share_receiver: None,
+            share_ready: None,
             frame_count: 0,
             frame: 0.0,
         }
     }
 
+    /// Look around by rotating the device, relative to how it was held when enabling this.
+    fn apply_gyro_look(&mut self, context: &mut AppContext) {
+        let Some(orientation) = context.device_orientation.filter(|_| self.gyro_look) else {
+            self.gyro_reference = None;
+            return;
+        };
+        let (device_start, camera_start) = *self
+            .gyro_reference
+            .get_or_insert((orientation, context.controls.rotation));
+
+        // The sensors use x right, y up and z out of the screen, whereas the camera looks
+        // down +z with y down, which is a half turn around x.
+        let flip = Quat::from_rotation_x(std::f32::consts::PI);
+        let delta = flip * (device_start.inverse() * orientation) * flip.inverse();
+        context.controls.rotation = (camera_start * delta).normalize();
+    }
+
+    /// Orbit around the scene in kiosk mode, until someone interacts with it.
+    fn kiosk_orbit(&mut self, ui: &egui::Ui, context: &mut AppContext) {
+        let Some(idle) = self.kiosk_idle else {
+            return;
+        };
+
+        let interacted = ui.input(|i| {
+            i.pointer.is_moving()
+                || i.pointer.any_down()
+                || i.any_touches()
+                || !i.keys_down.is_empty()
+                || i.smooth_scroll_delta != egui::Vec2::ZERO
+        });
+        if interacted {
+            self.last_interaction = Instant::now();
+        }
+
+        let elapsed = self.last_interaction.elapsed();
+        if elapsed > idle {
+            let dt = ui.input(|i| i.predicted_dt);
+            context.controls.auto_orbit(dt * KIOSK_ORBIT_SPEED);
+            ui.ctx().request_repaint();
+        } else {
+            // Wake up again once the idle time has passed.
+            ui.ctx().request_repaint_after(idle - elapsed);
+        }
+    }
+
+    fn set_comparison(&mut self, comparison: Option<Comparison>) {
+        // Keep the mode when comparing with other splats.
+        let previous = self.compare.take();
+        self.compare = comparison.map(|c| match previous {
+            Some(previous) => Comparison {
+                mode: previous.mode,
+                split: previous.split,
+                ..c
+            },
+            None => c,
+        });
+        self.last_state = None;
+    }
+
+    fn compare_ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext, splats: &ViewSplats) {
+        ui.menu_button(tr("⚖ Compare"), |ui| {
+            if ui.button(tr("Pin current splats")).clicked() {
+                let name = if context.training() {
+                    format!("{} {}", tr("Step"), self.train_iter)
+                } else {
+                    tr("Pinned").to_owned()
+                };
+                self.set_comparison(Some(Comparison::new(name, splats.clone())));
+                ui.close_menu();
+            }
+
+            if ui.button(tr("Open .ply file…")).clicked() {
+                let (sender, receiver) = channel();
+                self.compare_receiver = Some(receiver);
+                let device = context.device.clone();
+                tokio_wasm::task::spawn(async move {
+                    let _ = sender.send(pick_comparison(device).await);
+                });
+                ui.close_menu();
+            }
+
+            for session in &context.sessions {
+                if let Some(session_splats) = session.splats() {
+                    if ui.button(session.name()).clicked() {
+                        let comparison = Comparison::new(session.name(), session_splats.clone());
+                        self.set_comparison(Some(comparison));
+                        ui.close_menu();
+                    }
+                }
+            }
+
+            // Prefer the eval views, which the splats weren't trained on.
+            let views = context
+                .dataset
+                .eval
+                .clone()
+                .unwrap_or_else(|| context.dataset.train.clone());
+            let error_heatmap = ui
+                .add_enabled(
+                    !views.views.is_empty() && self.error_receiver.is_none(),
+                    egui::Button::new(tr("Error heatmap")),
+                )
+                .on_hover_text(tr(
+                    "Color the splats by how badly they match the eval views, to find areas that \
+                     need more photos",
+                ));
+            if error_heatmap.clicked() {
+                let (sender, receiver) = channel();
+                self.error_receiver = Some(receiver);
+                let splats = splats.clone();
+                let device = context.device.clone();
+                tokio_wasm::task::spawn(async move {
+                    let _ = sender.send(error_comparison(splats, views, device).await);
+                });
+                ui.close_menu();
+            }
+
+            for (stat, label, hover) in [
+                (
+                    TrackedStat::CreatedStep,
+                    tr("Color by age"),
+                    tr("Blue for the oldest splats, red for the splats refining added last"),
+                ),
+                (
+                    TrackedStat::TimesSeen,
+                    tr("Color by times seen"),
+                    tr("Red for the splats visible in the most training steps"),
+                ),
+                (
+                    TrackedStat::GradAccum,
+                    tr("Color by gradient"),
+                    tr("Red for the splats training moved the hardest, summed over all steps"),
+                ),
+            ] {
+                let button = ui
+                    .add_enabled(splats.tracking.is_some(), egui::Button::new(label))
+                    .on_hover_text(hover)
+                    .on_disabled_hover_text(tr(
+                        "Turn on Track splats in the settings, or train with --track-splats",
+                    ));
+                if button.clicked() {
+                    if let Some(heatmap) = tracking_heatmap(splats, stat) {
+                        self.set_comparison(Some(Comparison::new(label.to_owned(), heatmap)));
+                    }
+                    ui.close_menu();
+                }
+            }
+
+            if !self.worst_views.is_empty() {
+                ui.separator();
+                ui.label(tr("Views with the largest error"));
+                for view in &self.worst_views {
+                    let label = format!("{} ({:.3})", view.name, view.error);
+                    if ui.button(label).clicked() {
+                        context.move_camera_to(view.camera.position, view.camera.rotation);
+                        ui.close_menu();
+                    }
+                }
+            }
+
+            if self.compare.is_some() && ui.button(tr("Stop comparing")).clicked() {
+                self.set_comparison(None);
+                self.worst_views.clear();
+                ui.close_menu();
+            }
+        });
+
+        let Some(compare) = self.compare.as_mut() else {
+            return;
+        };
+        for mode in CompareMode::ALL {
+            ui.selectable_value(&mut compare.mode, mode, tr(mode.label()));
+        }
+        match compare.mode {
+            CompareMode::Flip => {
+                let flip = ui
+                    .selectable_label(compare.show_other, &compare.name)
+                    .on_hover_text(format!(
+                        "{} ({})",
+                        tr("Flip between compared splats"),
+                        context.keymap.describe(ui.ctx(), Action::FlipComparison)
+                    ));
+                if flip.clicked() {
+                    compare.show_other = !compare.show_other;
+                }
+            }
+            CompareMode::Split => {
+                ui.add(egui::Slider::new(&mut compare.split, 0.0..=1.0).show_value(false));
+            }
+        }
+    }
+
+    /// Set the near plane and max render distance of the viewer.
+    fn clip_ui(&mut self, ui: &mut egui::Ui, context: &AppContext) {
+        let clipped = self.clip_near > DEFAULT_NEAR || self.clip_far.is_some();
+        let label = if clipped {
+            egui::RichText::new(tr("✂ Clipping")).color(Color32::LIGHT_BLUE)
+        } else {
+            egui::RichText::new(tr("✂ Clipping"))
+        };
+
+        ui.menu_button(label, |ui| {
+            // Scale the drag speed with the scene, which can be any size.
+            let speed = (context.controls.focus_distance * 0.01).max(1e-3);
+
+            egui::Grid::new("clip_planes")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(tr("Near plane"))
+                        .on_hover_text(tr("Hide splats closer to the camera than this"));
+                    ui.add(
+                        locale::length_drag(&mut self.clip_near)
+                            .speed(speed)
+                            .range(DEFAULT_NEAR..=f32::MAX),
+                    );
+                    ui.end_row();
+
+                    let mut limit_far = self.clip_far.is_some();
+                    ui.checkbox(&mut limit_far, tr("Max distance"))
+                        .on_hover_text(tr(
+                            "Hide splats further from the camera than this, which also makes huge \
+                             scenes faster to render",
+                        ));
+                    if limit_far {
+                        let far = self
+                            .clip_far
+                            .get_or_insert(context.controls.focus_distance * 4.0);
+                        ui.add(
+                            locale::length_drag(far)
+                                .speed(speed)
+                                .range(self.clip_near..=DEFAULT_FAR),
+                        );
+                    } else {
+                        self.clip_far = None;
+                    }
+                    ui.end_row();
+                });
+
+            if clipped && ui.button(tr("Reset")).clicked() {
+                self.clip_near = DEFAULT_NEAR;
+                self.clip_far = None;
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn section_ui(&mut self, ui: &mut egui::Ui, context: &AppContext) {
+        let mut enabled = self.section.is_some();
+        if ui
+            .selectable_label(enabled, tr("🔪 Section"))
+            .on_hover_text(tr("Slice through the splats with a plane, to look inside"))
+            .clicked()
+        {
+            enabled = !enabled;
+            self.section = enabled.then(SectionCut::new);
+        }
+
+        if let Some(section) = self.section.as_mut() {
+            section.ui(ui, context.controls.focus_distance);
+        }
+    }
+
+    fn lighting_ui(&mut self, ui: &mut egui::Ui) {
+        let label = if self.lighting.mode == ShadingMode::Color {
+            egui::RichText::new(tr("💡 Shading"))
+        } else {
+            egui::RichText::new(tr("💡 Shading")).color(Color32::LIGHT_BLUE)
+        };
+        ui.menu_button(label, |ui| {
+            ui.label(tr(
+                "Light the splats or color them by their normals, to judge the shape of the \
+                 splats without their colors",
+            ));
+            self.lighting.ui(ui);
+        });
+    }
+
+    fn display_ui(&mut self, ui: &mut egui::Ui) {
+        let label = if self.display == DisplayTransform::IDENTITY {
+            egui::RichText::new(tr("☀ Exposure"))
+        } else {
+            egui::RichText::new(tr("☀ Exposure")).color(Color32::LIGHT_BLUE)
+        };
+        ui.menu_button(label, |ui| {
+            ui.label(tr(
+                "Only changes how the splats are shown, not the splats or exports",
+            ));
+            ui.add(
+                egui::Slider::new(&mut self.display.exposure, -5.0..=5.0)
+                    .text(tr("Exposure"))
+                    .suffix(" EV"),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.display.gamma, 0.2..=3.0)
+                    .logarithmic(true)
+                    .text(tr("Gamma")),
+            );
+            if self.display != DisplayTransform::IDENTITY && ui.button(tr("Reset")).clicked() {
+                self.display = DisplayTransform::IDENTITY;
+            }
+        });
+    }
+
+    /// Toggle hiding the splats that contribute least to the views, and how many to hide.
+    fn contribution_ui(&mut self, ui: &mut egui::Ui, context: &AppContext) {
+        // Training changes the splats every step, so they'd never be done being measured.
+        if context.training() {
+            if self.contribution.take().is_some() {
+                self.last_state = None;
+            }
+            return;
+        }
+
+        if ui
+            .selectable_label(self.contribution.is_some(), tr("🍂 Prune preview"))
+            .on_hover_text(tr(
+                "Hide the splats that contribute least to the views, to see how pruning them \
+                 would look. The splats are left as they are.",
+            ))
+            .clicked()
+        {
+            self.contribution = match self.contribution {
+                Some(_) => None,
+                None => Some(ContributionFilter::new()),
+            };
+            self.last_state = None;
+        }
+
+        if let Some(contribution) = self.contribution.as_mut() {
+            contribution.ui(ui);
+        }
+    }
+
+    /// Toggle highlighting the splats refining adds and prunes while training.
+    fn refine_highlight_ui(&mut self, ui: &mut egui::Ui, context: &AppContext) {
+        if !context.training() {
+            if self.refine_highlight.take().is_some() {
+                self.last_state = None;
+            }
+            return;
+        }
+
+        if ui
+            .selectable_label(self.refine_highlight.is_some(), tr("🌱 Refine highlight"))
+            .on_hover_text(tr(
+                "After each refine, tint the splats it added green and mark the splats it pruned \
+                 red for a few seconds, to see where the densify settings grow and prune splats",
+            ))
+            .clicked()
+        {
+            self.refine_highlight = match self.refine_highlight {
+                Some(_) => None,
+                None => Some(RefineHighlight::new()),
+            };
+            // Only read back the pruned splats while they're shown, as it stalls refining.
+            context.control_message(ControlMessage::RecordPruned(
+                self.refine_highlight.is_some(),
+            ));
+            self.last_state = None;
+        }
+    }
+
+    /// Look for the floor of the splats, and stand them upright on it.
+    fn orient_ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext, splats: &ViewSplats) {
+        if let Some(receiver) = self.orienting.as_ref() {
+            match receiver.try_recv() {
+                Ok(Some(floor)) => {
+                    context.set_model_floor(floor.up, floor.point);
+                    self.orienting = None;
+                }
+                Ok(None) | Err(TryRecvError::Disconnected) => {
+                    self.warnings
+                        .push(tr("Couldn't find a floor to stand the splats on.").to_owned());
+                    self.orienting = None;
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+            }
+        }
+
+        let clicked = ui
+            .add_enabled(
+                self.orienting.is_none(),
+                egui::Button::new(tr("🧭 Auto-orient")),
+            )
+            .on_hover_text(tr(
+                "Find the floor, then turn the splats upright and move the floor to height zero",
+            ))
+            .clicked();
+        if clicked {
+            let up_hint = context
+                .model_local_to_world
+                .inverse()
+                .transform_vector3(glam::Vec3::NEG_Y);
+            let eval_views = context.dataset.eval.iter().flat_map(|e| e.views.iter());
+            let cameras = context
+                .dataset
+                .train
+                .views
+                .iter()
+                .chain(eval_views)
+                .map(|view| view.camera.position)
+                .collect();
+            let (sender, receiver) = channel();
+            let job = find_floor(splats.clone(), up_hint, cameras);
+            tokio_wasm::task::spawn(async move {
+                let _ = sender.send(job.await);
+            });
+            self.orienting = Some(receiver);
+        }
+    }
+
+    /// Edit the volumes exports keep the splats inside of.
+    fn volumes_ui(&mut self, ui: &mut egui::Ui, context: &AppContext) {
+        self.volumes.receive();
+        let text = tr("📦 Export volumes");
+        let label = if self.volumes.volumes.is_empty() {
+            egui::RichText::new(text)
+        } else {
+            egui::RichText::new(text).color(Color32::LIGHT_BLUE)
+        };
+        let focus_distance = context.controls.focus_distance;
+        let focus = self
+            .pick_target
+            .as_ref()
+            .map_or(glam::Vec3::ZERO, |target| {
+                target.camera.position + target.camera.rotation * glam::Vec3::Z * focus_distance
+            });
+        ui.menu_button(label, |ui| self.volumes.ui(ui, focus, focus_distance));
+    }
+
+    /// Toggle the paint tool and show its settings, painting on the splats of `frame`.
+    fn paint_ui(&mut self, ui: &mut egui::Ui, context: &AppContext, frame: usize) {
+        // Training would paint over any changes.
+        if context.training() {
+            self.paint = None;
+            return;
+        }
+
+        if ui
+            .selectable_label(self.paint.is_some(), tr("🖌 Paint"))
+            .on_hover_text(tr(
+                "Paint the color or opacity of the splats under the brush. Use the right or \
+                 middle mouse button to move the camera while painting.",
+            ))
+            .clicked()
+        {
+            self.paint = match self.paint {
+                Some(_) => None,
+                None => Some(PaintTool::new()),
+            };
+        }
+
+        if let Some(paint) = self.paint.as_mut() {
+            paint.ui(ui);
+            if ui.button(tr("↶ Undo")).clicked() {
+                if let Some(splats) = paint.take_undo() {
+                    self.view_splats[frame] = splats;
+                    self.last_state = None;
+                }
+            }
+
+            // The plane the last frame was cut with.
+            let section = self.last_state.and_then(|s| s.section).map(|s| s.plane);
+            let mirror_plane = self.section.as_ref().and_then(SectionCut::axis_plane);
+            paint.selection_ui(
+                ui,
+                &self.view_splats[frame],
+                section,
+                mirror_plane,
+                context.controls.focus_distance,
+            );
+        }
+    }
+
+    /// Ask for a file to save the splats to, and export them to it.
+    /// Format and file name to export the splats as.
+    fn export_target(&self, context: &AppContext) -> (ExportFormat, String) {
+        context.process_args().map_or_else(
+            || (ExportFormat::Ply, "export.ply".to_owned()),
+            |args| {
+                let config = &args.process_config;
+                (
+                    config.export_format,
+                    config.export_file_name(self.train_iter, args.train_config.total_steps),
+                )
+            },
+        )
+    }
+
+    /// Comments to write in the header of exports, to record which data splats trained in this
+    /// run came from, like the exports of the process.
+    fn export_comments(&self, context: &AppContext) -> Vec<String> {
+        match &self.run.dataset_hash {
+            Some(hash) if context.training() => vec![format!("Dataset hash: {hash}")],
+            _ => vec![],
+        }
+    }
+
+    /// The splats to export, without those the prune preview hides if it prunes exports too.
+    fn splats_to_export(
+        &self,
+        splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+    ) -> anyhow::Result<Splats<<TrainBack as AutodiffBackend>::InnerBackend>> {
+        self.contribution
+            .as_ref()
+            .map_or_else(|| Ok(splats.clone()), |c| c.for_export(splats))
+    }
+
+    fn export(
+        &mut self,
+        splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+        context: &AppContext,
+    ) {
+        let splats = match self.splats_to_export(splats) {
+            Ok(splats) => splats,
+            Err(e) => {
+                self.warnings.push(e.to_string());
+                return;
+            }
+        };
+        let volumes = self.volumes.volumes.clone();
+        let (format, file_name) = self.export_target(context);
+        // Splats trained in this run are written with how they were trained.
+        let metadata = context
+            .process_args()
+            .filter(|_| context.training())
+            .and_then(|args| self.run.metadata(self.train_iter, args));
+        let loss = self.run.loss.clone();
+        let comments = self.export_comments(context);
+        let (sender, receiver) = channel();
+        self.export_receiver = Some(receiver);
+
+        let fut = async move {
+            let file = rrfd::save_file(&file_name).await;
+
+            // Not sure where/how to show this error if any.
+            match file {
+                Err(e) => {
+                    log::error!("Failed to save file: {e}");
+                }
+                Ok(file) => {
+                    let splats = match volumes.apply(splats).await {
+                        Ok(splats) => splats,
+                        Err(e) => {
+                            log::error!("Failed to serialize file: {e}");
+                            return;
+                        }
+                    };
+                    let num_splats = splats.num_splats();
+                    let data =
+                        splat_export::export_splats_with_comments(splats, format, &comments).await;
+
+                    let data = match data {
+                        Ok(data) => data,
+                        Err(e) => {
+                            log::error!("Failed to serialize file: {e}");
+                            return;
+                        }
+                    };
+
+                    if let Err(e) = file.write(&data).await {
+                        log::error!("Failed to write file: {e}");
+                    } else if let Some(path) = file.path() {
+                        // Only files with a path get the metadata next to them, on the web and
+                        // mobile the export is all there is.
+                        if let Some(metadata) = metadata {
+                            let loss = match loss {
+                                Some(loss) => loss.into_scalar_async().await,
+                                None => 0.0,
+                            };
+                            let metadata = RunMetadata {
+                                num_splats,
+                                loss,
+                                ..metadata
+                            };
+                            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                            let run_path = path.with_file_name(format!("{stem}_run.json"));
+                            let written = metadata
+                                .to_json()
+                                .and_then(|json| Ok(std::fs::write(run_path, json)?));
+                            if let Err(e) = written {
+                                log::error!("Failed to write run metadata: {e}");
+                            }
+                        }
+                        let _ = sender.send(path);
+                    }
+                }
+            }
+        };
+
+        tokio_wasm::task::spawn(fut);
+    }
+
+    /// Whether exporting now would miss the results of an eval that's still running.
+    fn eval_pending(&self, context: &AppContext) -> bool {
+        context.training()
+            && !context.process_ended()
+            && context
+                .process_args()
+                .is_some_and(|args| self.run.eval_pending(self.train_iter, args))
+    }
+
+    /// Export the splats, once the eval running alongside training is done.
+    fn request_export(
+        &mut self,
+        splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+        context: &AppContext,
+    ) {
+        if self.eval_pending(context) {
+            self.export_waiting = true;
+        } else {
+            self.export(splats, context);
+        }
+    }
+
+    /// Export the splats to send them to another app, see [`share_export`].
+    fn prepare_share(
+        &mut self,
+        splats: &Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+        context: &AppContext,
+    ) {
+        let splats = match self.splats_to_export(splats) {
+            Ok(splats) => splats,
+            Err(e) => {
+                self.warnings.push(e.to_string());
+                return;
+            }
+        };
+        let volumes = self.volumes.volumes.clone();
+        let (format, file_name) = self.export_target(context);
+        // Other apps only get the name, not the folders of the export path.
+        let file_name = Path::new(&file_name)
+            .file_name()
+            .map_or(file_name.clone(), |n| n.to_string_lossy().into_owned());
+        let comments = self.export_comments(context);
+
+        let (sender, receiver) = channel();
+        self.share_receiver = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let data = match volumes.apply(splats).await {
+                Ok(splats) => {
+                    splat_export::export_splats_with_comments(splats, format, &comments).await
+                }
+                Err(e) => Err(e),
+            };
+            match data {
+                Ok(data) => {
+                    let _ = sender.send((file_name, data));
+                }
+                Err(e) => log::error!("Failed to export splats to share: {e}"),
+            }
+        });
+    }
+
     pub(crate) fn draw_splats(
         &mut self,
         ui: &mut egui::Ui,
@@ -101,10 +1120,28 @@ impl ScenePanel {
 
         let (rect, response) = ui.allocate_exact_size(
             egui::Vec2::new(size.x as f32, size.y as f32),
-            egui::Sense::drag(),
+            egui::Sense::click_and_drag(),
         );
 
-        context.controls.tick(&response, ui);
+        // With the paint tool, the left mouse button paints instead of moving the camera.
+        let painting = self.paint.is_some()
+            && response.is_pointer_button_down_on()
+            && ui.input(|i| i.pointer.primary_down());
+        context.controls.smooth = self.smooth_camera;
+        if !painting {
+            context.controls.tick(&response, ui);
+        }
+        let interrupted = response.dragged()
+            || response.hovered() && ui.input(|r| r.smooth_scroll_delta.y != 0.0);
+        context.update_camera_transition(ui.input(|r| r.predicted_dt), interrupted);
+        if let Some(paint) = self.paint.as_mut() {
+            match response.interact_pointer_pos().filter(|_| painting) {
+                Some(pos) => paint.dab(glam::vec2(pos.x - rect.min.x, pos.y - rect.min.y)),
+                None => paint.end_stroke(),
+            }
+        }
+        self.apply_gyro_look(context);
+        self.kiosk_orbit(ui, context);
 
         let camera = &mut context.camera;
 
@@ -114,14 +1151,43 @@ impl ScenePanel {
         camera.position = total_transform.translation.into();
         camera.rotation = Quat::from_mat3a(&total_transform.matrix3);
 
+        let (splats_visible, splats_transform) = context
+            .scene_graph
+            .resolve_kind(SceneNodeKind::Splats)
+            .unwrap_or((true, Affine3A::IDENTITY));
+
+        // Node transforms are applied in the controls space, so move the camera
+        // into the local space of the splats instead of transforming the splats.
+        let splat_cam_transform = context.model_local_to_world
+            * splats_transform.inverse()
+            * context.controls.local_to_world();
+
+        let near = self.clip_near;
+        let far = self.clip_far.unwrap_or(DEFAULT_FAR).max(near);
+        let section = self
+            .section
+            .as_mut()
+            .map(|section| section.plane(splat_cam_transform, context.controls.focus_distance));
+        let splat_cam_rotation = Quat::from_mat3a(&splat_cam_transform.matrix3).normalize();
+        let shading = self.lighting.shading(splat_cam_rotation);
+
         let state = RenderState {
             size,
             cam_pos: camera.position,
             cam_rot: camera.rotation,
+            splats_transform,
+            splats_visible,
+            blend_mode: self.blend_mode,
+            near,
+            far,
+            section,
+            shading,
+            display: self.display,
             frame: self.frame,
         };
 
-        let dirty = self.last_state != Some(state);
+        let moved = self.last_state != Some(state);
+        let dirty = moved || self.needs_full_render;
 
         if dirty {
             self.last_state = Some(state);
@@ -130,14 +1196,153 @@ impl ScenePanel {
             ui.ctx().request_repaint();
         }
 
+        let mut splat_cam = context.camera.clone();
+        splat_cam.position = splat_cam_transform.translation.into();
+        splat_cam.rotation = splat_cam_rotation;
+        splat_cam.near = near;
+        splat_cam.far = far;
+        splat_cam.section = section;
+        splat_cam.shading = shading;
+        splat_cam.display = self.display;
+
+        let screenshots = context.take_screenshot_requests();
+        if !screenshots.is_empty() {
+            let splats = splats.clone();
+            let camera = splat_cam.clone();
+            let blend_mode = self.blend_mode;
+            tokio_wasm::task::spawn(async move {
+                let result = render_png(&splats, &camera, size, blend_mode)
+                    .await
+                    .map_err(|e| e.to_string());
+                for sender in screenshots {
+                    let _ = sender.send(result.clone());
+                }
+            });
+        }
+
+        let hdr_requests = context.take_hdr_requests();
+        if !hdr_requests.is_empty() {
+            let splats = splats.clone();
+            let camera = splat_cam.clone();
+            let blend_mode = self.blend_mode;
+            tokio_wasm::task::spawn(async move {
+                let image = render_hdr(&splats, &camera, size, blend_mode).await;
+                for sender in hdr_requests {
+                    let _ = sender.send(Ok(image.clone()));
+                }
+            });
+        }
+
         // If this viewport is re-rendering.
         if size.x > 0 && size.y > 0 && dirty {
             let _span = trace_span!("Render splats").entered();
-            let (img, _) = splats.render(&context.camera, size, true);
+
+            let focus_distance = context.controls.focus_distance;
+            let full = self
+                .full_render
+                .as_mut()
+                .filter(|full| full.splats == splats.means.id);
+            let (img, aux) = match full {
+                Some(full) if full.state.can_reuse_order(&state) => {
+                    // Only the coloring changed, so the same splats are visible in the same order.
+                    self.needs_full_render = false;
+                    full.state = state;
+                    splats.render_with_options(
+                        &splat_cam,
+                        size,
+                        true,
+                        self.blend_mode,
+                        Some(full.depth_order.clone()),
+                    )
+                }
+                Some(_) if moved => {
+                    // While the camera moves around the same splats, skip evaluating view
+                    // dependent colors by reusing those baked for where the camera is. Once the
+                    // camera comes to rest, render it properly.
+                    self.needs_full_render = true;
+                    let bucket = ColorBucket::new(splat_cam.position, focus_distance);
+                    self.view_colors.get(&splats, bucket).render_with_options(
+                        &splat_cam,
+                        size,
+                        true,
+                        self.blend_mode,
+                        None,
+                    )
+                }
+                _ => {
+                    self.needs_full_render = false;
+                    let (img, aux) =
+                        splats.render_with_options(&splat_cam, size, true, self.blend_mode, None);
+                    self.full_render = Some(FullRender {
+                        state,
+                        splats: splats.means.id,
+                        depth_order: aux.depth_order(),
+                    });
+                    (img, aux)
+                }
+            };
             self.backbuffer.update_texture(img);
+
+            let target = PickTarget {
+                splats: splats.clone(),
+                camera: splat_cam.clone(),
+                size,
+                aux,
+            };
+            // Only check which annotations are hidden once the camera is at rest.
+            if !self.needs_full_render {
+                self.annotation_view
+                    .update_occlusion(target.clone(), &context.annotations);
+            }
+            if !context.reference_layers.is_empty() {
+                self.reference_occlusion
+                    .update(&target, splat_cam_transform);
+            }
+            self.pick_target = Some(target);
+
+            if let Some(compare) = self.compare.as_ref() {
+                let (img, _) = compare.splats.render_with_options(
+                    &splat_cam,
+                    size,
+                    true,
+                    self.blend_mode,
+                    None,
+                );
+                self.compare_backbuffer.update_texture(img);
+            }
+        }
+
+        if self.wants_thumbnail && !context.loading() {
+            self.wants_thumbnail = false;
+
+            if let Some(location) = context.current_location.clone() {
+                let thumbnail_size =
+                    glam::uvec2(THUMBNAIL_WIDTH, (THUMBNAIL_WIDTH * size.y / size.x).max(1));
+                let (sender, receiver) = channel();
+                self.thumbnail = Some(receiver);
+
+                let splats = splats.clone();
+                let camera = splat_cam.clone();
+                let blend_mode = self.blend_mode;
+                tokio_wasm::task::spawn(async move {
+                    match render_png(&splats, &camera, thumbnail_size, blend_mode).await {
+                        Ok(png) => {
+                            let stored =
+                                brush_process::thumbnails::store(&location.thumbnail_id(), &png);
+                            if let Err(e) = stored.await {
+                                log::warn!("Failed to store thumbnail: {e}");
+                            }
+                            let _ = sender.send((location, png));
+                        }
+                        Err(e) => log::warn!("Failed to render thumbnail: {e}"),
+                    }
+                });
+            }
         }
 
-        if let Some(id) = self.backbuffer.id() {
+        if !splats_visible {
+            ui.painter().rect_filled(rect, 0.0, Color32::BLACK);
+        } else if let Some(id) = self.backbuffer.id() {
             ui.scope(|ui| {
                 let mut background = false;
                 if let Some(view) = context.dataset.train.views.first() {
@@ -154,35 +1359,133 @@ impl ScenePanel {
                     ui.painter().rect_filled(rect, 0.0, Color32::BLACK);
                 }
 
-                ui.painter().image(
-                    id,
-                    rect,
-                    Rect {
-                        min: egui::pos2(0.0, 0.0),
-                        max: egui::pos2(1.0, 1.0),
-                    },
-                    Color32::WHITE,
-                );
+                let comparison = self.compare.as_ref().zip(self.compare_backbuffer.id());
+                if let Some((comparison, other_id)) = comparison {
+                    draw_comparison(ui, rect, id, other_id, comparison);
+                } else {
+                    ui.painter().image(
+                        id,
+                        rect,
+                        Rect {
+                            min: egui::pos2(0.0, 0.0),
+                            max: egui::pos2(1.0, 1.0),
+                        },
+                        Color32::WHITE,
+                    );
+                }
             });
         }
+
+        // Layers added while the camera is at rest need the depth of the last render.
+        if let Some(target) = self.pick_target.as_ref() {
+            if !context.reference_layers.is_empty()
+                && self.reference_occlusion.needs_depth(splat_cam_transform)
+            {
+                self.reference_occlusion.update(target, splat_cam_transform);
+            }
+        }
+        self.reference_occlusion.receive(ui.ctx());
+        draw_reference_layers(ui, rect, size, context, &self.reference_occlusion);
+
+        self.annotation_view
+            .receive(ui.ctx(), &mut context.annotations);
+        if let Some(target) = self.pick_target.clone() {
+            if self
+                .annotation_view
+                .needs_occlusion_update(&context.annotations)
+            {
+                self.annotation_view
+                    .update_occlusion(target.clone(), &context.annotations);
+            }
+            if self.annotation_view.placing && response.clicked() {
+                if let Some(pos) = response.interact_pointer_pos() {
+                    let pixel = pos - rect.min;
+                    self.annotation_view
+                        .place(target.clone(), glam::vec2(pixel.x, pixel.y));
+                }
+            } else if self.paint.is_none() && response.double_clicked() {
+                if let Some(pos) = response.interact_pointer_pos() {
+                    let pixel = pos - rect.min;
+                    let pixel = glam::vec2(pixel.x, pixel.y);
+                    let target = target.clone();
+                    let (sender, receiver) = channel();
+                    tokio_wasm::task::spawn(async move {
+                        let picked = target.pick(&[pixel]).await;
+                        let _ = sender.send(picked.into_iter().next().flatten());
+                    });
+                    self.pivot = Some(receiver);
+                }
+            }
+            self.annotation_view
+                .draw(ui, rect, &target, &context.annotations);
+            self.volumes.draw(ui, rect, &target);
+        }
+
+        if let (Some(paint), Some(hover)) = (self.paint.as_ref(), response.hover_pos()) {
+            ui.painter_at(rect).circle_stroke(
+                hover,
+                paint.settings.radius,
+                egui::Stroke::new(1.5, Color32::WHITE),
+            );
+        }
+
+        // Without views, the minimap shows where the splats are instead.
+        let splat_bounds = self.splat_bounds.as_ref().map(|bounds| {
+            let (_, splats_transform) = context
+                .scene_graph
+                .resolve_kind(SceneNodeKind::Splats)
+                .unwrap_or((true, Affine3A::IDENTITY));
+            BoundingBox {
+                center: splats_transform.transform_point3(bounds.center),
+                extent: bounds.extent,
+            }
+        });
+        if self.show_minimap && (!context.dataset.train.views.is_empty() || splat_bounds.is_some())
+        {
+            draw_minimap(ui, rect, context, splat_bounds.as_ref());
+        }
     }
 }
 
 impl AppPanel for ScenePanel {
     fn title(&self) -> String {
-        "Scene".to_owned()
+        tr("Scene").to_owned()
     }
 
     fn on_message(&mut self, message: &ProcessMessage, context: &mut AppContext) {
         match message {
             ProcessMessage::NewSource => {
+                self.wants_thumbnail = false;
                 self.view_splats = vec![];
+                self.worst_views.clear();
+                // Clipping depends on the size of the scene, so start over for new data.
+                self.clip_near = DEFAULT_NEAR;
+                self.clip_far = None;
+                self.section = None;
+                self.annotation_view = AnnotationView::new();
+                self.paint = None;
+                self.contribution = None;
+                self.refine_highlight = None;
+                self.volumes = VolumeEditor::new();
+                self.orienting = None;
+                self.framing = None;
+                self.splat_bounds = None;
+                self.edited = false;
+                self.pick_target = None;
                 self.frame_count = 0;
                 self.live_update = true;
                 self.paused = false;
                 self.err = None;
+                self.warnings.clear();
                 self.last_state = None;
+                self.full_render = None;
+                self.view_colors.clear();
                 self.frame = 0.0;
+                self.run = RunRecord::default();
+                self.export_waiting = false;
+            }
+            ProcessMessage::SourceOpened { reopen, .. } => {
+                self.wants_thumbnail = reopen.is_some();
             }
             ProcessMessage::ViewSplats {
                 up_axis,
@@ -194,20 +1497,31 @@ impl AppPanel for ScenePanel {
                     context.set_model_up(*up_axis);
                 }
 
+                context
+                    .scene_graph
+                    .get_or_add("Splats", SceneNodeKind::Splats);
+
                 if self.live_update {
                     self.view_splats.truncate(*frame as usize);
-                    self.view_splats.push(*splats.clone());
+                    let dtype = self.sh_dtype(context);
+                    self.view_splats.push(splats.clone().with_sh_dtype(dtype));
                 }
                 self.frame_count = *total_frames;
                 self.last_state = None;
+                self.full_render = None;
+                self.view_colors.clear();
             }
             ProcessMessage::TrainStep {
                 splats,
-                stats: _,
-                iter: _,
+                stats,
+                iter,
                 timestamp: _,
             } => {
+                self.run.loss = Some(stats.loss.clone());
                 self.last_state = None;
+                self.full_render = None;
+                self.view_colors.clear();
+                self.train_iter = *iter;
 
                 let splats = *splats.clone();
 
@@ -215,11 +1529,44 @@ impl AppPanel for ScenePanel {
                     self.view_splats = vec![splats];
                 }
             }
+            ProcessMessage::RefineStep { stats, iter: _ } => {
+                if let Some(highlight) = self.refine_highlight.as_mut() {
+                    highlight.on_refine(stats);
+                }
+            }
+            ProcessMessage::Dataset { data } => {
+                self.run.dataset_hash = Some(data.content_hash());
+                self.run.has_eval = data.eval.is_some();
+                if !data.train.views.is_empty() {
+                    context
+                        .scene_graph
+                        .get_or_add("Training cameras", SceneNodeKind::CameraPath);
+                }
+            }
             ProcessMessage::Error(e) => {
                 let headline = e.to_string();
                 let context = e.chain().skip(1).map(|cause| format!("{cause}")).collect();
                 self.err = Some(ErrorDisplay { headline, context });
             }
+            ProcessMessage::Warning(warning) => self.warnings.push(warning.clone()),
+            ProcessMessage::DoneLoading { training: true } => {
+                self.run.started = Some(Instant::now());
+            }
+            ProcessMessage::EvalResult { .. } => self.run.eval = EvalMetrics::from_message(message),
+            ProcessMessage::DoneLoading { training: false } => {
+                // Without views to start from, look at the splats from outside of them.
+                if let Some(splats) = self.view_splats.first().cloned() {
+                    if context.dataset.train.views.is_empty() {
+                        let (sender, receiver) = channel();
+                        tokio_wasm::task::spawn(async move {
+                            let _ = sender.send(robust_bounds(splats, None).await);
+                        });
+                        // A camera restored from a link is kept, it's what the link is for.
+                        let move_camera = !std::mem::take(&mut context.camera_restored);
+                        self.framing = Some((receiver, true, move_camera));
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -229,27 +1576,164 @@ impl AppPanel for ScenePanel {
 
         self.last_draw = Some(cur_time);
 
+        if let Some(receiver) = self.thumbnail.as_ref() {
+            match receiver.try_recv() {
+                Ok((location, png)) => {
+                    self.thumbnail = None;
+                    self.recent_ui.set_thumbnail(ui.ctx(), location, &png);
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.thumbnail = None,
+            }
+        }
+
+        // Bounds and pivots are in the space of the splats, move them into the space of the
+        // controls.
+        let (_, splats_transform) = context
+            .scene_graph
+            .resolve_kind(SceneNodeKind::Splats)
+            .unwrap_or((true, Affine3A::IDENTITY));
+        if let Some((receiver, loaded, move_camera)) = self.framing.as_ref() {
+            match receiver.try_recv() {
+                Ok(bounds) => {
+                    let (loaded, move_camera) = (*loaded, *move_camera);
+                    self.framing = None;
+                    if let Some(bounds) = bounds {
+                        if loaded {
+                            self.splat_bounds = Some(bounds);
+                        }
+                        if move_camera {
+                            context.frame_bounds(&BoundingBox {
+                                center: splats_transform.transform_point3(bounds.center),
+                                extent: bounds.extent,
+                            });
+                            if loaded {
+                                // Small models need a near plane closer than the default.
+                                self.clip_near =
+                                    DEFAULT_NEAR.min(context.controls.focus_distance * 1e-3);
+                                self.clip_far = None;
+                            }
+                            self.last_state = None;
+                        }
+                    }
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.framing = None,
+            }
+        }
+
+        if let Some(receiver) = self.pivot.as_ref() {
+            match receiver.try_recv() {
+                Ok(pivot) => {
+                    self.pivot = None;
+                    if let Some(pivot) = pivot {
+                        context.set_orbit_pivot(splats_transform.transform_point3(pivot));
+                        self.last_state = None;
+                    }
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.pivot = None,
+            }
+        }
+
+        if let Some(receiver) = self.export_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(path) => {
+                    self.export_receiver = None;
+                    self.exported = Some(path);
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.export_receiver = None,
+            }
+        }
+
+        if let Some(receiver) = self.share_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(export) => {
+                    self.share_receiver = None;
+                    // Android can open the share sheet any time, the web needs another click.
+                    if cfg!(target_family = "wasm") {
+                        self.share_ready = Some(export);
+                    } else {
+                        share_export(&export);
+                    }
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.share_receiver = None,
+            }
+        }
+
+        if let Some(receiver) = self.compare_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    self.compare_receiver = None;
+                    match result {
+                        Ok((name, splats)) => {
+                            self.set_comparison(Some(Comparison::new(name, splats)));
+                        }
+                        Err(e) => log::error!("Failed to load splats to compare with: {e:#}"),
+                    }
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.compare_receiver = None,
+            }
+        }
+
+        if let Some(receiver) = self.error_receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok((heatmap, worst_views)) => {
+                    self.error_receiver = None;
+                    self.worst_views = worst_views;
+                    let comparison = Comparison::new(tr("Error heatmap").to_owned(), heatmap);
+                    self.set_comparison(Some(comparison));
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.error_receiver = None,
+            }
+        }
+
+        if let Some(compare) = self.compare.as_mut() {
+            if context.action_triggered(Action::FlipComparison) {
+                compare.mode = CompareMode::Flip;
+                compare.show_other = !compare.show_other;
+            }
+        }
+
+        if self.view_splats.is_empty() || self.err.is_some() {
+            for sender in context.take_screenshot_requests() {
+                let _ = sender.send(Err("There are no splats to take a screenshot of".to_owned()));
+            }
+            for sender in context.take_hdr_requests() {
+                let _ = sender.send(Err("There are no splats to render".to_owned()));
+            }
+        }
+
         // Empty scene, nothing to show.
         if !context.training() && self.view_splats.is_empty() && self.err.is_none() && !self.zen {
-            ui.heading("Load a ply file or dataset to get started.");
+            ui.heading(tr("Load a ply file or dataset to get started."));
             ui.add_space(5.0);
-            ui.label(
-                r#"
+
+            // Once something has been opened, list it here instead of explaining what to open.
+            let has_recent = !context.recent.entries().is_empty();
+            if !has_recent {
+                ui.label(
+                    r#"
 Load a pretrained .ply file to view it
 
 Or load a dataset to train on. These are zip files with:
     - a transforms.json and images, like the nerfstudio dataset format.
     - COLMAP data, containing the `images` & `sparse` folder."#,
-            );
+                );
+            }
 
             ui.add_space(10.0);
 
             if cfg!(debug_assertions) {
                 ui.scope(|ui| {
                     ui.visuals_mut().override_text_color = Some(Color32::LIGHT_BLUE);
-                    ui.heading(
+                    ui.heading(tr(
                         "Note: running in debug mode, compile with --release for best performance",
-                    );
+                    ));
                 });
 
                 ui.add_space(10.0);
@@ -258,7 +1742,7 @@ Or load a dataset to train on. These are zip files with:
             #[cfg(target_family = "wasm")]
             ui.scope(|ui| {
                 ui.visuals_mut().override_text_color = Some(Color32::YELLOW);
-                ui.heading("Note: Running in browser is still experimental");
+                ui.heading(tr("Note: Running in browser is still experimental"));
 
                 ui.label(
                     r#"
@@ -268,6 +1752,12 @@ For bigger training runs consider using the native app."#,
                 );
             });
 
+            if has_recent {
+                if let Some((source, args)) = self.recent_ui.ui(ui, &mut context.recent) {
+                    context.connect_to(start_process(source, args, context.device.clone()));
+                }
+            }
+
             return;
         }
 
@@ -294,15 +1784,109 @@ For bigger training runs consider using the native app."#,
             let frame = (self.frame * FPS)
                 .rem_euclid(self.frame_count as f32)
                 .floor() as usize;
+            if let Some(paint) = self.paint.as_mut() {
+                let painted = paint.update(
+                    ui.ctx(),
+                    &self.view_splats[frame],
+                    self.pick_target.as_ref(),
+                );
+                if let Some(painted) = painted {
+                    self.view_splats[frame] = painted;
+                    self.edited = true;
+                    self.last_state = None;
+                }
+            }
             let splats = self.view_splats[frame].clone();
 
-            self.draw_splats(ui, context, &splats);
+            let mut shown = match self.paint.as_mut() {
+                Some(paint) => paint.preview(&splats),
+                None => splats.clone(),
+            };
+            // Without a dataset, measure from around the last render.
+            let measurable = !context.dataset.train.views.is_empty() || self.pick_target.is_some();
+            if let Some(contribution) = self.contribution.as_mut().filter(|_| measurable) {
+                let (filtered, changed) = contribution.filter(ui.ctx(), &shown, || {
+                    let target = self.pick_target.as_ref();
+                    let camera = target.map_or(&context.camera, |t| &t.camera);
+                    let size = target.map_or(glam::UVec2::ONE, |t| t.size);
+                    measure_views(
+                        &context.dataset.train,
+                        camera,
+                        size,
+                        context.controls.focus_distance,
+                    )
+                });
+                if changed {
+                    self.last_state = None;
+                }
+                if let Some(filtered) = filtered {
+                    shown = filtered;
+                }
+            }
+            if let Some(highlight) = self.refine_highlight.as_mut() {
+                let (highlighted, changed) = highlight.highlight(ui.ctx(), &shown);
+                if changed {
+                    self.last_state = None;
+                }
+                if let Some(highlighted) = highlighted {
+                    shown = highlighted;
+                }
+            }
+            self.draw_splats(ui, context, &shown);
+
+            let animated =
+                self.view_splats.len() > 1 && self.view_splats.len() as u32 == self.frame_count;
+            if context.action_triggered(Action::TogglePause) {
+                if context.training() {
+                    self.paused = !self.paused;
+                    context.control_message(ControlMessage::Paused(self.paused));
+                } else if animated {
+                    self.paused = !self.paused;
+                }
+            }
+            if (context.training() || self.edited) && context.action_triggered(Action::Export) {
+                self.request_export(&splats, context);
+            }
+            if self.export_waiting && !self.eval_pending(context) {
+                self.export_waiting = false;
+                self.export(&splats, context);
+            }
+            if context.action_triggered(Action::FrameScene) {
+                let selection = self.paint.as_ref().and_then(PaintTool::selection);
+                let (sender, receiver) = channel();
+                let splats = splats.clone();
+                tokio_wasm::task::spawn(async move {
+                    let _ = sender.send(robust_bounds(splats, selection).await);
+                });
+                self.framing = Some((receiver, false, true));
+            }
+
+            if self.kiosk_idle.is_some() {
+                // No controls in kiosk mode, only show that something is still loading.
+                if context.loading() {
+                    ui.spinner();
+                }
+                return;
+            }
 
-            if self.view_splats.len() > 1 && self.view_splats.len() as u32 == self.frame_count {
+            let mut dismissed = None;
+            for (i, warning) in self.warnings.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(Color32::YELLOW, format!("⚠ {warning}"));
+                    if ui.small_button("✖").on_hover_text(tr("Dismiss")).clicked() {
+                        dismissed = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = dismissed {
+                self.warnings.remove(i);
+            }
+
+            if animated {
                 let label = if self.paused {
-                    "⏸ paused"
+                    tr("⏸ paused")
                 } else {
-                    "⏵ playing"
+                    tr("⏵ playing")
                 };
 
                 if ui.selectable_label(!self.paused, label).clicked() {
@@ -313,7 +1897,7 @@ For bigger training runs consider using the native app."#,
             ui.horizontal(|ui| {
                 if context.loading() {
                     ui.horizontal(|ui| {
-                        ui.label("Loading... Please wait.");
+                        ui.label(tr("Loading... Please wait."));
                         ui.spinner();
                     });
                 }
@@ -322,9 +1906,9 @@ For bigger training runs consider using the native app."#,
                     ui.add_space(15.0);
 
                     let label = if self.paused {
-                        "⏸ paused"
+                        tr("⏸ paused")
                     } else {
-                        "⏵ training"
+                        tr("⏵ training")
                     };
 
                     if ui.selectable_label(!self.paused, label).clicked() {
@@ -337,61 +1921,188 @@ For bigger training runs consider using the native app."#,
                     ui.scope(|ui| {
                         ui.style_mut().visuals.selection.bg_fill = Color32::DARK_RED;
                         if ui
-                            .selectable_label(self.live_update, "🔴 Live update splats")
+                            .selectable_label(self.live_update, tr("🔴 Live update splats"))
                             .clicked()
                         {
                             self.live_update = !self.live_update;
                         }
                     });
+                }
 
-                    ui.add_space(15.0);
+                if context.training() || self.edited {
+                    if self.export_waiting {
+                        ui.add_enabled(false, egui::Button::new(tr("⬆ Waiting for the eval…")))
+                            .on_disabled_hover_text(tr(
+                                "The export includes the results of the eval that's running",
+                            ));
+                    } else if ui.button(tr("⬆ Export")).clicked() {
+                        self.request_export(&splats, context);
+                    }
+                    if rrfd::can_share() {
+                        if let Some((name, _)) = &self.share_ready {
+                            let button = ui
+                                .button(format!("{} {name}", tr("📤 Share")))
+                                .on_hover_text(tr("The export is ready, send it to another app"));
+                            if button.clicked() {
+                                if let Some(export) = self.share_ready.take() {
+                                    share_export(&export);
+                                }
+                            }
+                        } else if self.share_receiver.is_some() {
+                            ui.add_enabled(false, egui::Button::new(tr("📤 Preparing export…")));
+                        } else if ui
+                            .button(tr("📤 Share"))
+                            .on_hover_text(tr("Export the splats and send them to another app"))
+                            .clicked()
+                        {
+                            self.prepare_share(&splats, context);
+                        }
+                    }
+                    self.volumes_ui(ui, context);
+                }
+                if let Some(path) = &self.exported {
+                    if rrfd::can_reveal()
+                        && ui
+                            .button(tr("📂 Show export"))
+                            .on_hover_text(path.display().to_string())
+                            .clicked()
+                    {
+                        if let Err(e) = rrfd::reveal_file(path) {
+                            log::error!("Failed to show {}: {e}", path.display());
+                        }
+                    }
+                }
 
-                    if ui.button("⬆ Export").clicked() {
-                        let splats = splats.clone();
+                self.compare_ui(ui, context, &splats);
+                self.paint_ui(ui, context, frame);
+                self.contribution_ui(ui, context);
+                self.refine_highlight_ui(ui, context);
+
+                if ui
+                    .selectable_label(self.annotation_view.placing, tr("📌 Annotate"))
+                    .on_hover_text(tr("Click on the splats to add a note there"))
+                    .clicked()
+                {
+                    self.annotation_view.placing = !self.annotation_view.placing;
+                }
+                self.clip_ui(ui, context);
+                self.section_ui(ui, context);
+                self.orient_ui(ui, context, &splats);
+                self.lighting_ui(ui);
+                self.display_ui(ui);
+
+                if !context.dataset.train.views.is_empty()
+                    && ui
+                        .selectable_label(self.show_minimap, tr("🗺 Minimap"))
+                        .clicked()
+                {
+                    self.show_minimap = !self.show_minimap;
+                }
 
-                        let fut = async move {
-                            let file = rrfd::save_file("export.ply").await;
+                if ui
+                    .selectable_label(self.smooth_camera, tr("🎞 Smooth camera"))
+                    .on_hover_text(tr(
+                        "Ease the camera into bookmarks and views, and let it glide on after \
+                         orbiting or panning",
+                    ))
+                    .clicked()
+                {
+                    self.smooth_camera = !self.smooth_camera;
+                }
 
-                            // Not sure where/how to show this error if any.
-                            match file {
-                                Err(e) => {
-                                    log::error!("Failed to save file: {e}");
-                                }
-                                Ok(file) => {
-                                    let data = splat_export::splat_to_ply(splats).await;
-
-                                    let data = match data {
-                                        Ok(data) => data,
-                                        Err(e) => {
-                                            log::error!("Failed to serialize file: {e}");
-                                            return;
-                                        }
-                                    };
-
-                                    if let Err(e) = file.write(&data).await {
-                                        log::error!("Failed to write file: {e}");
-                                    }
-                                }
-                            }
-                        };
+                if context.orientation_sensor.is_some()
+                    && ui
+                        .selectable_label(self.gyro_look, tr("📱 Look around"))
+                        .on_hover_text(tr("Look around by moving your device"))
+                        .clicked()
+                {
+                    self.gyro_look = !self.gyro_look;
+                    if let Some(set_enabled) = &context.orientation_sensor {
+                        set_enabled(self.gyro_look);
+                    }
+                    if !self.gyro_look {
+                        // Don't start from a stale orientation when enabled again.
+                        context.device_orientation = None;
+                    }
+                }
 
-                        tokio_wasm::task::spawn(fut);
+                if context.supports_f16
+                    && ui
+                        .selectable_label(self.half_precision_colors, tr("Half precision colors"))
+                        .on_hover_text(tr(
+                            "Store the colors of viewed splats at half precision, to use less \
+                             memory for big scenes",
+                        ))
+                        .clicked()
+                {
+                    self.half_precision_colors = !self.half_precision_colors;
+                    let dtype = self.sh_dtype(context);
+                    self.view_splats = std::mem::take(&mut self.view_splats)
+                        .into_iter()
+                        .map(|splats| splats.with_sh_dtype(dtype))
+                        .collect();
+                    self.last_state = None;
+                    self.full_render = None;
+                    self.view_colors.clear();
+                }
+
+                let fast_blend = self.blend_mode == BlendMode::WeightedBlended;
+                if ui
+                    .selectable_label(fast_blend, tr("⚡ Fast blending"))
+                    .on_hover_text(tr(
+                        "Blend splats without sorting them by depth, weighing closer splats \
+                         more. Much faster for huge scenes, but less accurate where splats \
+                         overlap.",
+                    ))
+                    .clicked()
+                {
+                    self.blend_mode = if fast_blend {
+                        BlendMode::Sorted
+                    } else {
+                        BlendMode::WeightedBlended
+                    };
+                }
+
+                #[cfg(target_family = "wasm")]
+                if ui
+                    .button(tr("🔗 Copy share link"))
+                    .on_hover_text(tr("Copy a link that opens the viewer at this view"))
+                    .clicked()
+                {
+                    if let Some(location) = web_sys::window().map(|w| w.location()) {
+                        let base = format!(
+                            "{}{}",
+                            location.origin().unwrap_or_default(),
+                            location.pathname().unwrap_or_default()
+                        );
+                        ui.ctx()
+                            .copy_text(format!("{base}?{}", context.share_query()));
                     }
                 }
 
-                ui.selectable_label(false, "Controls")
+                ui.selectable_label(false, tr("Controls"))
                     .on_hover_ui_at_pointer(|ui| {
-                        ui.heading("Controls");
+                        ui.heading(tr("Controls"));
 
-                        ui.label("• Left click and drag to orbit");
-                        ui.label(
+                        ui.label(tr("• Left click and drag to orbit"));
+                        ui.label(tr(
                             "• Right click, or left click + spacebar, and drag to look around.",
-                        );
-                        ui.label("• Middle click, or left click + control, and drag to pan");
-                        ui.label("• Scroll to zoom");
-                        ui.label("• WASD to fly, Q&E to move up & down.");
-                        ui.label("• Z&C to roll, X to reset roll");
-                        ui.label("• Shift to move faster");
+                        ));
+                        ui.label(tr(
+                            "• Middle click, or left click + control, and drag to pan",
+                        ));
+                        ui.label(tr("• Scroll to zoom"));
+                        ui.label(format!(
+                            "• Double click to orbit around a point, {} to frame the model",
+                            context.keymap.describe(ui.ctx(), Action::FrameScene)
+                        ));
+                        ui.label(tr("• WASD to fly, Q&E to move up & down."));
+                        ui.label(tr("• Z&C to roll, X to reset roll"));
+                        ui.label(tr("• Shift to move faster"));
+                        ui.label(format!(
+                            "• {} for all keyboard shortcuts",
+                            context.keymap.describe(ui.ctx(), Action::ShowHelp)
+                        ));
                     });
             });
         }
@@ -400,4 +2111,27 @@ For bigger training runs consider using the native app."#,
     fn inner_margin(&self) -> f32 {
         0.0
     }
+
+    fn load_settings(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(saved) = eframe::get_value::<SavedSettings>(storage, SETTINGS_KEY) {
+            self.show_minimap = saved.show_minimap;
+            self.half_precision_colors = saved.half_precision_colors;
+            self.smooth_camera = saved.smooth_camera.unwrap_or(true);
+            self.blend_mode = if saved.fast_blending {
+                BlendMode::WeightedBlended
+            } else {
+                BlendMode::Sorted
+            };
+        }
+    }
+
+    fn save_settings(&self, storage: &mut dyn eframe::Storage) {
+        let saved = SavedSettings {
+            show_minimap: self.show_minimap,
+            fast_blending: self.blend_mode == BlendMode::WeightedBlended,
+            half_precision_colors: self.half_precision_colors,
+            smooth_camera: Some(self.smooth_camera),
+        };
+        eframe::set_value(storage, SETTINGS_KEY, &saved);
+    }
 }
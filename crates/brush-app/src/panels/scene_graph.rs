@@ -0,0 +1,447 @@
+use std::sync::mpsc::{Receiver, channel};
+
+use crate::{
+    app::{AppContext, AppPanel},
+    i18n::tr,
+    locale,
+    reference_layers::ReferenceLayer,
+    scene_graph::{NodeTransform, SceneNode, SceneNodeKind},
+};
+use brush_dataset::{
+    deviation::{DeviationResult, compute_deviation},
+    reference_import::{ReferenceGeometry, load_reference_geometry},
+};
+use brush_process::process_loop::ProcessMessage;
+use brush_render::gaussian_splats::Splats;
+use brush_train::train::TrainBack;
+use burn::tensor::backend::AutodiffBackend;
+use egui::DragValue;
+use tokio_with_wasm::alias as tokio_wasm;
+
+type ViewSplats = Splats<<TrainBack as AutodiffBackend>::InnerBackend>;
+
+async fn pick_reference() -> anyhow::Result<ReferenceGeometry> {
+    let file = rrfd::pick_file().await?;
+    load_reference_geometry(file.read().await?).await
+}
+
+pub(crate) struct SceneGraphPanel {
+    selected: Option<u64>,
+    pending_import: Option<Receiver<anyhow::Result<ReferenceGeometry>>>,
+    import_err: Option<String>,
+
+    latest_splats: Option<ViewSplats>,
+    pending_deviation: Option<(u64, Receiver<anyhow::Result<DeviationResult>>)>,
+    deviation_err: Option<String>,
+}
+
+impl SceneGraphPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            selected: None,
+            pending_import: None,
+            import_err: None,
+            latest_splats: None,
+            pending_deviation: None,
+            deviation_err: None,
+        }
+    }
+
+    fn import_reference(&mut self) {
+        let (sender, receiver) = channel();
+        self.pending_import = Some(receiver);
+        self.import_err = None;
+
+        tokio_wasm::task::spawn(async move {
+            let _ = sender.send(pick_reference().await);
+        });
+    }
+
+    fn receive_import(&mut self, ui: &egui::Ui, context: &mut AppContext) {
+        let Some(receiver) = self.pending_import.as_ref() else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(Ok(geometry)) => {
+                let kind = if geometry.edges.is_empty() {
+                    SceneNodeKind::PointCloud
+                } else {
+                    SceneNodeKind::Mesh
+                };
+                let node_id = context.scene_graph.add_node("Reference", kind, None);
+                context.reference_layers.push(ReferenceLayer {
+                    node_id,
+                    geometry,
+                    deviation: None,
+                });
+                self.selected = Some(node_id);
+                self.pending_import = None;
+            }
+            Ok(Err(e)) => {
+                self.import_err = Some(e.to_string());
+                self.pending_import = None;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {
+                ui.ctx().request_repaint();
+            }
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.pending_import = None;
+            }
+        }
+    }
+
+    fn start_deviation(&mut self, node_id: u64, context: &AppContext) {
+        let Some(splats) = self.latest_splats.clone() else {
+            return;
+        };
+        let Some(layer) = context
+            .reference_layers
+            .iter()
+            .find(|l| l.node_id == node_id)
+        else {
+            return;
+        };
+
+        // Bring the reference points into the local space of the splats, taking the transforms
+        // of both nodes in the scene graph into account.
+        let splat_transform = context
+            .scene_graph
+            .resolve_kind(SceneNodeKind::Splats)
+            .map_or(glam::Affine3A::IDENTITY, |(_, t)| t);
+        let Some((_, ref_transform)) = context.scene_graph.resolve(node_id) else {
+            return;
+        };
+        let model = context.model_local_to_world;
+        let ref_to_splats = model * splat_transform.inverse() * ref_transform * model.inverse();
+
+        let mut geometry = layer.geometry.clone();
+        for p in &mut geometry.points {
+            *p = ref_to_splats.transform_point3(*p);
+        }
+
+        let (sender, receiver) = channel();
+        self.pending_deviation = Some((node_id, receiver));
+        self.deviation_err = None;
+
+        tokio_wasm::task::spawn(async move {
+            let _ = sender.send(compute_deviation(&geometry, splats).await);
+        });
+    }
+
+    fn receive_deviation(&mut self, ui: &egui::Ui, context: &mut AppContext) {
+        let Some((node_id, receiver)) = self.pending_deviation.as_ref() else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(Ok(result)) => {
+                if let Some(layer) = context
+                    .reference_layers
+                    .iter_mut()
+                    .find(|l| l.node_id == *node_id)
+                {
+                    layer.deviation = Some(result);
+                }
+                self.pending_deviation = None;
+            }
+            Ok(Err(e)) => {
+                self.deviation_err = Some(e.to_string());
+                self.pending_deviation = None;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {
+                ui.ctx().request_repaint();
+            }
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.pending_deviation = None;
+            }
+        }
+    }
+
+    fn deviation_ui(&mut self, ui: &mut egui::Ui, node_id: u64, context: &mut AppContext) {
+        ui.separator();
+        ui.label(egui::RichText::new(tr("Deviation analysis")).heading());
+
+        let busy = self.pending_deviation.is_some();
+        ui.horizontal(|ui| {
+            let button = ui
+                .add_enabled(
+                    !busy && self.latest_splats.is_some(),
+                    egui::Button::new(tr("Compute deviation")),
+                )
+                .on_hover_text(tr(
+                    "Distance from each reference point to the surface of the splats",
+                ));
+            if button.clicked() {
+                self.start_deviation(node_id, context);
+            }
+            if busy {
+                ui.spinner();
+            }
+        });
+
+        if let Some(err) = &self.deviation_err {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+
+        let Some(layer) = context
+            .reference_layers
+            .iter_mut()
+            .find(|l| l.node_id == node_id)
+        else {
+            return;
+        };
+        let Some(deviation) = &layer.deviation else {
+            return;
+        };
+
+        let stats = deviation.stats;
+        egui::Grid::new("deviation_stats")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (label, value) in [
+                    ("Mean", stats.mean),
+                    ("RMS", stats.rms),
+                    ("Median", stats.median),
+                    ("95th pct", stats.p95),
+                    ("Max", stats.max),
+                ] {
+                    ui.label(tr(label));
+                    ui.label(locale::number(value as f64, 4));
+                    ui.end_row();
+                }
+                ui.label(tr("Points"));
+                ui.label(locale::count(stats.count as u64));
+                ui.end_row();
+            });
+
+        ui.label(
+            egui::RichText::new(tr(
+                "Heatmap: white is on the splat surface, red is far from it.",
+            ))
+            .weak(),
+        );
+
+        let (export, clear) = ui
+            .horizontal(|ui| {
+                (
+                    ui.button(tr("⬆ Export report")).clicked(),
+                    ui.button(tr("Clear")).clicked(),
+                )
+            })
+            .inner;
+
+        if export {
+            let csv = deviation.to_csv(&layer.geometry);
+            tokio_wasm::task::spawn(async move {
+                match rrfd::save_file("deviation.csv").await {
+                    Ok(file) => {
+                        if let Err(e) = file.write(csv.as_bytes()).await {
+                            log::error!("Failed to write file: {e}");
+                        }
+                    }
+                    Err(e) => log::error!("Failed to save file: {e}"),
+                }
+            });
+        }
+
+        if clear {
+            layer.deviation = None;
+        }
+    }
+}
+
+fn node_tree_ui(ui: &mut egui::Ui, nodes: &mut [SceneNode], selected: &mut Option<u64>) {
+    for node in nodes {
+        let header = |ui: &mut egui::Ui, node: &mut SceneNode| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut node.visible, "");
+                let label = format!("{} {}", node.kind.icon(), node.name);
+                if ui
+                    .selectable_label(*selected == Some(node.id), label)
+                    .clicked()
+                {
+                    *selected = Some(node.id);
+                }
+            });
+        };
+
+        if node.children.is_empty() {
+            header(ui, node);
+        } else {
+            let id = ui.make_persistent_id(("scene_node", node.id));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| header(ui, node))
+                .body(|ui| node_tree_ui(ui, &mut node.children, selected));
+        }
+    }
+}
+
+fn vec3_ui(ui: &mut egui::Ui, label: &str, v: &mut glam::Vec3, speed: f32) {
+    ui.label(label);
+    ui.horizontal(|ui| {
+        ui.add(DragValue::new(&mut v.x).speed(speed).prefix("x: "));
+        ui.add(DragValue::new(&mut v.y).speed(speed).prefix("y: "));
+        ui.add(DragValue::new(&mut v.z).speed(speed).prefix("z: "));
+    });
+    ui.end_row();
+}
+
+impl AppPanel for SceneGraphPanel {
+    fn title(&self) -> String {
+        tr("Scene graph").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        match message {
+            ProcessMessage::NewSource => {
+                self.selected = None;
+                self.latest_splats = None;
+            }
+            ProcessMessage::ViewSplats { splats, .. }
+            | ProcessMessage::TrainStep { splats, .. } => {
+                self.latest_splats = Some(*splats.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        self.receive_import(ui, context);
+        self.receive_deviation(ui, context);
+
+        ui.horizontal(|ui| {
+            let button = ui
+                .add_enabled(
+                    self.pending_import.is_none(),
+                    egui::Button::new(tr("Import reference...")),
+                )
+                .on_hover_text(tr(
+                    "Load a PLY, LAS/LAZ or OBJ point cloud or mesh to compare against. It's \
+                     drawn over the splats, except where it's behind them.",
+                ));
+            if button.clicked() {
+                self.import_reference();
+            }
+            if self.pending_import.is_some() {
+                ui.spinner();
+            }
+        });
+
+        if let Some(err) = &self.import_err {
+            ui.colored_label(egui::Color32::RED, format!("Failed to import: {err}"));
+        }
+
+        ui.separator();
+
+        if context.scene_graph.nodes.is_empty() {
+            ui.label(tr("Nothing loaded yet."));
+            return;
+        }
+
+        node_tree_ui(ui, &mut context.scene_graph.nodes, &mut self.selected);
+
+        // Nodes the selected node can be moved under, which excludes itself and its children.
+        let graph = &context.scene_graph;
+        let parents: Vec<(u64, String)> = self.selected.map_or(vec![], |selected| {
+            graph
+                .iter()
+                .filter(|n| !graph.is_within(n.id, selected))
+                .map(|n| (n.id, format!("{} {}", n.kind.icon(), n.name)))
+                .collect()
+        });
+        let current_parent = self.selected.and_then(|id| graph.parent(id));
+        let mut new_parent = current_parent;
+
+        let selected = self.selected.and_then(|id| context.scene_graph.get_mut(id));
+
+        if let Some(node) = selected {
+            ui.separator();
+
+            egui::Grid::new("scene_node_props")
+                .num_columns(2)
+                .spacing([20.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label(tr("Name"));
+                    ui.text_edit_singleline(&mut node.name);
+                    ui.end_row();
+
+                    vec3_ui(ui, "Translation", &mut node.transform.translation, 0.01);
+                    vec3_ui(ui, "Rotation (°)", &mut node.transform.rotation, 0.5);
+
+                    ui.label(tr("Scale"));
+                    ui.add(
+                        DragValue::new(&mut node.transform.scale)
+                            .speed(0.01)
+                            .range(0.001..=1000.0),
+                    );
+                    ui.end_row();
+
+                    ui.label(tr("Parent"));
+                    let name_of = |id: Option<u64>| {
+                        id.and_then(|id| parents.iter().find(|(p, _)| *p == id))
+                            .map_or(tr("None"), |(_, name)| name.as_str())
+                    };
+                    egui::ComboBox::from_id_salt("scene_node_parent")
+                        .selected_text(name_of(new_parent))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut new_parent, None, tr("None"));
+                            for (id, name) in &parents {
+                                ui.selectable_value(&mut new_parent, Some(*id), name);
+                            }
+                        });
+                    ui.end_row();
+                });
+
+            let mut remove = None;
+            ui.horizontal(|ui| {
+                if ui.button(tr("Reset transform")).clicked() {
+                    node.transform = NodeTransform::default();
+                }
+
+                if matches!(node.kind, SceneNodeKind::PointCloud | SceneNodeKind::Mesh)
+                    && ui.button(tr("Remove")).clicked()
+                {
+                    remove = Some(node.id);
+                }
+            });
+
+            let node_id = node.id;
+            let is_reference = matches!(node.kind, SceneNodeKind::PointCloud | SceneNodeKind::Mesh);
+
+            if new_parent != current_parent {
+                context.scene_graph.move_node(node_id, new_parent);
+            }
+
+            if let Some(id) = remove {
+                context.scene_graph.remove(id);
+                context.reference_layers.retain(|l| l.node_id != id);
+                self.selected = None;
+            } else if is_reference {
+                let origin = context
+                    .reference_layers
+                    .iter()
+                    .find(|l| l.node_id == node_id)
+                    .map(|l| l.geometry.origin)
+                    .filter(|origin| *origin != glam::DVec3::ZERO);
+                if let Some(origin) = origin {
+                    ui.label(format!(
+                        "{} {:.3}, {:.3}, {:.3}",
+                        tr("Moved to the origin from"),
+                        origin.x,
+                        origin.y,
+                        origin.z
+                    ))
+                    .on_hover_text(tr(
+                        "Coordinates this far out don't fit in 32 bit floats, so the points were \
+                         moved closer to the origin, the same way as point clouds splats start \
+                         from.",
+                    ));
+                }
+                self.deviation_ui(ui, node_id, context);
+            }
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::VecDeque;
+
+use brush_process::process_loop::{ControlMessage, ProcessMessage, start_process};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot::{self, error::TryRecvError};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::app::{AppContext, AppPanel, HdrRenderResult, ScreenshotResult};
+use crate::hdr::{self, Tonemap};
+use crate::i18n::tr;
+use crate::script::{SCRIPT_FUNCTIONS, ScriptCommand, run_script};
+
+const SETTINGS_KEY: &str = "script_panel";
+
+const EXAMPLE_SCRIPT: &str = r#"// Train with a few different numbers of steps, and export each result.
+for steps in [5000, 10000] {
+    set("total_steps", steps);
+    set("export_name", `steps_${steps}`);
+    load_path("path/to/dataset");
+    wait_for_step(steps);
+    export();
+}
+"#;
+
+// Only keep the last lines of output.
+const MAX_LOG_LINES: usize = 200;
+
+#[derive(Serialize, Deserialize)]
+struct SavedSettings {
+    script: String,
+}
+
+/// Save a rendered image. Natively this writes the file directly, on the web it's downloaded.
+async fn save_render(file: &str, data: &[u8]) -> anyhow::Result<()> {
+    #[cfg(not(target_family = "wasm"))]
+    std::fs::write(file, data)?;
+    #[cfg(target_family = "wasm")]
+    rrfd::save_file(file).await?.write(data).await?;
+    Ok(())
+}
+
+/// Save an unclamped render as a linear EXR, and a tone mapped PNG preview next to it.
+async fn save_exr(
+    file: &str,
+    render: &image::Rgba32FImage,
+    tonemap: Tonemap,
+) -> anyhow::Result<()> {
+    let linear = hdr::render_to_linear(render);
+    let preview = hdr::encode_preview(&linear, tonemap)?;
+    save_render(file, &hdr::encode_exr(linear)?).await?;
+    let preview_file = std::path::Path::new(file).with_extension("png");
+    save_render(&preview_file.to_string_lossy(), &preview).await
+}
+
+fn is_exr(file: &str) -> bool {
+    std::path::Path::new(file)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exr"))
+}
+
+/// Edits and runs scripts that automate the app, see [`crate::script`].
+pub(crate) struct ScriptPanel {
+    script: String,
+    commands: VecDeque<ScriptCommand>,
+    // Screenshot the script is waiting for, with the file to save it to.
+    screenshot: Option<(String, oneshot::Receiver<ScreenshotResult>)>,
+    // Same for a render to save as an EXR.
+    hdr_render: Option<(String, oneshot::Receiver<HdrRenderResult>)>,
+    tonemap: Tonemap,
+    train_iter: u32,
+    // Set when the script opened new data, until the new process has started. Messages of the
+    // previous process can still come in until then.
+    starting: bool,
+    log: Vec<String>,
+}
+
+impl ScriptPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            script: EXAMPLE_SCRIPT.to_owned(),
+            commands: VecDeque::new(),
+            screenshot: None,
+            hdr_render: None,
+            tonemap: Tonemap::default(),
+            train_iter: 0,
+            starting: false,
+            log: vec![],
+        }
+    }
+
+    fn log(&mut self, line: String) {
+        self.log.push(line);
+        let excess = self.log.len().saturating_sub(MAX_LOG_LINES);
+        self.log.drain(..excess);
+    }
+
+    fn stop(&mut self, reason: String) {
+        self.commands.clear();
+        self.screenshot = None;
+        self.hdr_render = None;
+        self.log(reason);
+    }
+
+    fn run(&mut self, context: &AppContext) {
+        let args = context.process_args().cloned().unwrap_or_default();
+        let camera = (context.camera.position, context.camera.rotation);
+        self.screenshot = None;
+        self.hdr_render = None;
+        self.tonemap = Tonemap::default();
+        match run_script(&self.script, args, camera) {
+            Ok(commands) => {
+                self.log(format!("Running {} commands", commands.len()));
+                self.commands = commands.into();
+            }
+            Err(e) => self.stop(format!("Script failed: {e:#}")),
+        }
+    }
+
+    /// Run commands until one has to wait, eg. for training or a screenshot.
+    fn advance(&mut self, context: &mut AppContext) {
+        if let Some((file, receiver)) = &mut self.screenshot {
+            match receiver.try_recv() {
+                Ok(Ok(png)) => {
+                    let file = file.clone();
+                    self.screenshot = None;
+                    tokio_wasm::task::spawn(async move {
+                        if let Err(e) = save_render(&file, &png).await {
+                            log::error!("Failed to save {file}: {e}");
+                        }
+                    });
+                }
+                Ok(Err(e)) => {
+                    self.stop(format!("Failed to take screenshot: {e}"));
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Closed) => self.screenshot = None,
+            }
+        }
+
+        if let Some((file, receiver)) = &mut self.hdr_render {
+            match receiver.try_recv() {
+                Ok(Ok(render)) => {
+                    let file = file.clone();
+                    let tonemap = self.tonemap;
+                    self.hdr_render = None;
+                    tokio_wasm::task::spawn(async move {
+                        if let Err(e) = save_exr(&file, &render, tonemap).await {
+                            log::error!("Failed to save {file}: {e}");
+                        }
+                    });
+                }
+                Ok(Err(e)) => {
+                    self.stop(format!("Failed to render: {e}"));
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Closed) => self.hdr_render = None,
+            }
+        }
+
+        while let Some(command) = self.commands.pop_front() {
+            match command {
+                ScriptCommand::Load(source, args) => {
+                    self.starting = true;
+                    context.connect_to(start_process(source, *args, context.device.clone()));
+                }
+                ScriptCommand::WaitForStep(step) => {
+                    let total_steps = context.process_args().map(|a| a.train_config.total_steps);
+                    let Some(total_steps) = total_steps else {
+                        self.stop(format!("Can't wait for step {step}, nothing is training"));
+                        return;
+                    };
+                    if step > total_steps {
+                        self.stop(format!(
+                            "Can't wait for step {step}, training stops at step {total_steps}"
+                        ));
+                        return;
+                    }
+                    if self.starting || self.train_iter < step {
+                        self.commands.push_front(command);
+                        return;
+                    }
+                }
+                ScriptCommand::Export => context.control_message(ControlMessage::Export),
+                ScriptCommand::SetCamera(position, rotation) => {
+                    context.set_camera_pose(position, rotation);
+                }
+                ScriptCommand::Screenshot(file) if is_exr(&file) => {
+                    let (sender, receiver) = oneshot::channel();
+                    context.request_hdr_render(sender);
+                    self.hdr_render = Some((file, receiver));
+                    return;
+                }
+                ScriptCommand::Screenshot(file) => {
+                    let (sender, receiver) = oneshot::channel();
+                    context.request_screenshot(sender);
+                    self.screenshot = Some((file, receiver));
+                    return;
+                }
+                ScriptCommand::SetTonemap(tonemap) => self.tonemap = tonemap,
+                ScriptCommand::Print(text) => self.log(text),
+            }
+        }
+    }
+}
+
+impl AppPanel for ScriptPanel {
+    fn title(&self) -> String {
+        tr("Script").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, context: &mut AppContext) {
+        match message {
+            ProcessMessage::NewSource => {
+                self.starting = false;
+                self.train_iter = 0;
+            }
+            ProcessMessage::TrainStep { iter, .. } => self.train_iter = *iter,
+            ProcessMessage::Error(e) if !self.commands.is_empty() => {
+                self.stop(format!("Stopped the script after an error: {e:#}"));
+            }
+            _ => {}
+        }
+        self.advance(context);
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        self.advance(context);
+
+        // Only check once all messages are handled, the last steps might still be queued up.
+        if let Some(ScriptCommand::WaitForStep(step)) = self.commands.front() {
+            if !self.starting && context.process_ended() {
+                let reason = format!(
+                    "Training stopped at step {} before reaching step {step}",
+                    self.train_iter
+                );
+                self.stop(reason);
+            }
+        }
+
+        let running = !self.commands.is_empty();
+
+        ui.horizontal(|ui| {
+            if running {
+                if ui.button(tr("⏹ Stop")).clicked() {
+                    self.stop(tr("Stopped the script").to_owned());
+                }
+                ui.spinner();
+                ui.label(format!("{} {}", self.commands.len(), tr("commands left")));
+            } else if ui.button(tr("▶ Run")).clicked() {
+                self.run(context);
+            }
+        });
+
+        ui.add_space(4.0);
+        ui.add_enabled(
+            !running,
+            egui::TextEdit::multiline(&mut self.script)
+                .code_editor()
+                .desired_rows(10)
+                .desired_width(f32::INFINITY),
+        );
+
+        ui.collapsing(tr("Functions"), |ui| {
+            egui::Grid::new("script_functions")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for &(function, description) in SCRIPT_FUNCTIONS {
+                        ui.monospace(function);
+                        ui.label(description);
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if !self.log.is_empty() {
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(150.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in &self.log {
+                        ui.monospace(line);
+                    }
+                });
+        }
+
+        if running {
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn load_settings(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(saved) = eframe::get_value::<SavedSettings>(storage, SETTINGS_KEY) {
+            self.script = saved.script;
+        }
+    }
+
+    fn save_settings(&self, storage: &mut dyn eframe::Storage) {
+        let saved = SavedSettings {
+            script: self.script.clone(),
+        };
+        eframe::set_value(storage, SETTINGS_KEY, &saved);
+    }
+}
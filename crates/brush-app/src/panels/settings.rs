@@ -1,15 +1,175 @@
 use crate::app::{AppContext, AppPanel};
-use brush_dataset::{LoadDataseConfig, ModelConfig};
+use crate::background::BackgroundMode;
+use crate::i18n::{self, Language, tr};
+use crate::locale;
+use brush_dataset::{LoadDataseConfig, ModelConfig, splat_export::ExportFormat};
 use brush_process::{
     data_source::DataSource,
-    process_loop::{ProcessArgs, ProcessConfig, RerunConfig, start_process},
+    process_loop::{
+        ControlMessage, ProcessArgs, ProcessConfig, ProcessMessage, RerunConfig, start_process,
+    },
 };
-use brush_train::train::TrainConfig;
+use brush_train::image::LossSpace;
+use brush_train::train::{LiveTrainConfig, TrainConfig, TrainPreset};
 use egui::Slider;
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_KEY: &str = "settings_panel";
+
+#[derive(Serialize, Deserialize)]
+struct SavedSettings {
+    args: ProcessArgs,
+    url: String,
+}
+
+/// How often to look for new files shared with the app, as they can be added at any time.
+#[cfg(target_os = "ios")]
+const SHARED_FILES_RESCAN: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// The files shared with the app in the Files app, as there's no file picker on iOS.
+#[cfg(target_os = "ios")]
+struct SharedFiles {
+    files: anyhow::Result<Vec<std::path::PathBuf>>,
+    scanned: Option<web_time::Instant>,
+}
+
+#[cfg(target_os = "ios")]
+impl SharedFiles {
+    fn new() -> Self {
+        Self {
+            files: Ok(vec![]),
+            scanned: None,
+        }
+    }
+
+    fn scan() -> anyhow::Result<Vec<std::path::PathBuf>> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(rrfd::documents_dir()?)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == "ply" || ext == "zip")
+            {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) -> Option<DataSource> {
+        ui.collapsing(tr("Shared files"), |ui| {
+            ui.label(tr(
+                "Copy .ply or .zip files to Brush in the Files app to open them here.",
+            ));
+
+            if self
+                .scanned
+                .is_none_or(|scanned| scanned.elapsed() > SHARED_FILES_RESCAN)
+            {
+                self.files = Self::scan();
+                self.scanned = Some(web_time::Instant::now());
+            }
+            ui.ctx().request_repaint_after(SHARED_FILES_RESCAN);
+
+            let files = match &self.files {
+                Ok(files) => files,
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("{err:#}"));
+                    return None;
+                }
+            };
+
+            let mut open = None;
+            for path in files {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if ui.button(name.as_ref()).clicked() {
+                    open = Some(DataSource::Path(path.to_string_lossy().into_owned()));
+                }
+            }
+            open
+        })
+        .body_returned
+        .flatten()
+    }
+}
+
+fn preset_label(preset: TrainPreset) -> &'static str {
+    match preset {
+        TrainPreset::Quality => "Quality",
+        TrainPreset::Balanced => "Balanced",
+        TrainPreset::Compact => "Compact",
+    }
+}
+
+/// Controls for the settings that can change while training. Returns whether any changed.
+fn live_config_ui(ui: &mut egui::Ui, live: &mut LiveTrainConfig) -> bool {
+    let before = *live;
+
+    egui::Grid::new("live_train_config")
+        .num_columns(2)
+        .show(ui, |ui| {
+            let learning_rates = [
+                (tr("Mean learning rate"), &mut live.lr_mean, 1e-7..=1e-3),
+                (
+                    tr("Final mean learning rate"),
+                    &mut live.lr_mean_end,
+                    1e-9..=1e-4,
+                ),
+                (
+                    tr("Color learning rate"),
+                    &mut live.lr_coeffs_dc,
+                    1e-5..=1e-1,
+                ),
+                (tr("Opacity learning rate"), &mut live.lr_opac, 1e-4..=1.0),
+                (tr("Scale learning rate"), &mut live.lr_scale, 1e-5..=1e-1),
+                (
+                    tr("Rotation learning rate"),
+                    &mut live.lr_rotation,
+                    1e-5..=1e-1,
+                ),
+            ];
+            for (label, lr, range) in learning_rates {
+                ui.label(label);
+                ui.add(Slider::new(lr, range).logarithmic(true));
+                ui.end_row();
+            }
+
+            ui.label(tr("SSIM weight"));
+            ui.add(Slider::new(&mut live.ssim_weight, 0.0..=1.0));
+            ui.end_row();
+
+            ui.label(tr("Refine"));
+            ui.add(
+                Slider::new(&mut live.refine_every, 10..=1000)
+                    .clamping(egui::SliderClamping::Never)
+                    .prefix(tr("every "))
+                    .suffix(tr(" steps")),
+            );
+            ui.end_row();
+        });
+
+    let mut limit_splats = live.max_splats.is_some();
+    ui.checkbox(&mut limit_splats, tr("Limit number of splats"));
+    if limit_splats != live.max_splats.is_some() {
+        live.max_splats = limit_splats.then_some(1_000_000);
+    }
+    if let Some(max_splats) = live.max_splats.as_mut() {
+        ui.add(Slider::new(max_splats, 10_000..=10_000_000).logarithmic(true));
+    }
+
+    *live != before
+}
 
 pub(crate) struct SettingsPanel {
     args: ProcessArgs,
+    // Settings of the current training run, which can be changed while it trains.
+    live: Option<LiveTrainConfig>,
     url: String,
+    #[cfg(not(target_os = "ios"))]
+    cache: super::cache::CacheUi,
+    #[cfg(target_os = "ios")]
+    shared_files: SharedFiles,
 }
 
 impl SettingsPanel {
@@ -23,30 +183,100 @@ impl SettingsPanel {
                 ProcessConfig::new(),
                 RerunConfig::new(),
             ),
+            live: None,
             url: "splat.com/example.ply".to_owned(),
+            #[cfg(not(target_os = "ios"))]
+            cache: super::cache::CacheUi::new(),
+            #[cfg(target_os = "ios")]
+            shared_files: SharedFiles::new(),
         }
     }
 }
 
 impl AppPanel for SettingsPanel {
     fn title(&self) -> String {
-        "Settings".to_owned()
+        tr("Settings").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, context: &mut AppContext) {
+        if let ProcessMessage::StartLoading { training } = message {
+            self.live = context
+                .process_args()
+                .filter(|_| *training)
+                .map(|args| args.train_config.live());
+        }
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
         egui::ScrollArea::vertical().show(ui, |ui| {
-            ui.heading("Model Settings");
-            ui.label("Spherical Harmonics Degree:");
+            ui.horizontal(|ui| {
+                ui.label(tr("Language"));
+                let current = i18n::language();
+                egui::ComboBox::from_id_salt("language")
+                    .selected_text(current.name())
+                    .show_ui(ui, |ui| {
+                        for language in Language::ALL {
+                            if ui
+                                .selectable_label(language == current, language.name())
+                                .clicked()
+                            {
+                                i18n::set_language(ui.ctx(), language);
+                            }
+                        }
+                    });
+
+                if ui.button(tr("Reset layout")).clicked() {
+                    context.reset_layout = true;
+                }
+            });
+            locale::settings_ui(ui);
+
+            ui.horizontal(|ui| {
+                ui.label(tr("In the background"));
+                egui::ComboBox::from_id_salt("background_mode")
+                    .selected_text(tr(context.background_mode.label()))
+                    .show_ui(ui, |ui| {
+                        for mode in BackgroundMode::ALL {
+                            ui.selectable_value(
+                                &mut context.background_mode,
+                                mode,
+                                tr(mode.label()),
+                            );
+                        }
+                    });
+            })
+            .response
+            .on_hover_text(tr(
+                "When the window isn't focused, is minimized, or is a hidden browser tab",
+            ));
+
+            context.updates.settings_ui(ui);
+            context.logs.settings_ui(ui);
+
+            if let Some(live) = self.live.as_mut() {
+                ui.heading(tr("Current Training"));
+                ui.label(tr("Changes apply to the current run right away."));
+                if live_config_ui(ui, live) {
+                    context.control_message(ControlMessage::TrainConfig(*live));
+                }
+                ui.add_space(10.0);
+            }
+
+            ui.heading(tr("Model Settings"));
+            ui.label(tr("Spherical Harmonics Degree:"));
             ui.add(Slider::new(&mut self.args.model_config.sh_degree, 0..=4));
 
-            ui.label("Max image resolution");
+            ui.label(tr("Max image resolution"));
             ui.add(
                 Slider::new(&mut self.args.load_config.max_resolution, 32..=2048)
                     .clamping(egui::SliderClamping::Never),
             );
 
             let mut limit_frames = self.args.load_config.max_frames.is_some();
-            if ui.checkbox(&mut limit_frames, "Limit max frames").clicked() {
+            if ui
+                .checkbox(&mut limit_frames, tr("Limit max frames"))
+                .clicked()
+            {
                 self.args.load_config.max_frames = if limit_frames { Some(32) } else { None };
             }
 
@@ -54,9 +284,15 @@ impl AppPanel for SettingsPanel {
                 ui.add(Slider::new(max_frames, 1..=256).clamping(egui::SliderClamping::Never));
             }
 
+            ui.checkbox(
+                &mut self.args.load_config.dense_init,
+                tr("Initialize from dense point cloud"),
+            )
+            .on_hover_text(tr("Use a COLMAP fused.ply instead of the sparse points, if available"));
+
             let mut use_eval_split = self.args.load_config.eval_split_every.is_some();
             if ui
-                .checkbox(&mut use_eval_split, "Split dataset for evaluation")
+                .checkbox(&mut use_eval_split, tr("Split dataset for evaluation"))
                 .clicked()
             {
                 self.args.load_config.eval_split_every =
@@ -67,80 +303,231 @@ impl AppPanel for SettingsPanel {
                 ui.add(
                     Slider::new(eval_split, 2..=32)
                         .clamping(egui::SliderClamping::Never)
-                        .prefix("1 out of ")
-                        .suffix(" frames"),
+                        .prefix(tr("1 out of "))
+                        .suffix(tr(" frames")),
                 );
             }
 
-            ui.heading("Training Settings");
+            ui.heading(tr("Training Settings"));
 
             ui.horizontal(|ui| {
-                ui.label("Train");
+                ui.label(tr("Train"));
 
                 ui.add(
                     egui::Slider::new(&mut self.args.train_config.total_steps, 1..=50000)
                         .clamping(egui::SliderClamping::Never)
-                        .suffix(" steps"),
+                        .suffix(tr(" steps")),
                 );
             });
 
-            ui.heading("Process Settings");
+            ui.horizontal(|ui| {
+                ui.label(tr("Density"));
+                let config = &mut self.args.train_config;
+                let current = config.matching_preset();
+                egui::ComboBox::from_id_salt("train_preset")
+                    .selected_text(current.map_or(tr("Custom"), |p| tr(preset_label(p))))
+                    .show_ui(ui, |ui| {
+                        for preset in TrainPreset::ALL {
+                            if ui
+                                .selectable_label(current == Some(preset), tr(preset_label(preset)))
+                                .clicked()
+                            {
+                                *config = config.clone().apply_preset(preset);
+                            }
+                        }
+                    });
+            })
+            .response
+            .on_hover_text(tr(
+                "How many splats to add while training. Compact trains fewer splats, for small \
+                 exports that render fast.",
+            ));
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Compare colors in"));
+                let space = &mut self.args.train_config.loss_space;
+                ui.selectable_value(space, LossSpace::Srgb, "sRGB");
+                ui.selectable_value(space, LossSpace::Linear, tr("Linear light"));
+            })
+            .response
+            .on_hover_text(tr(
+                "sRGB weighs errors like they're seen, linear light weighs bright areas more",
+            ));
+
+            let mut crops = self.args.train_config.crop_size.is_some();
+            if ui
+                .checkbox(&mut crops, tr("Train on crops"))
+                .on_hover_text(tr("For very large images that don't fit in memory"))
+                .clicked()
+            {
+                self.args.train_config.crop_size = crops.then_some(2048);
+            }
+
+            if let Some(size) = self.args.train_config.crop_size.as_mut() {
+                ui.add(
+                    Slider::new(size, 256..=8192)
+                        .clamping(egui::SliderClamping::Never)
+                        .prefix(tr("up to "))
+                        .suffix(" px"),
+                );
+            }
+
+            let mut motion_blur = self.args.train_config.motion_blur_px.is_some();
+            if ui
+                .checkbox(&mut motion_blur, tr("Model motion blur"))
+                .on_hover_text(tr(
+                    "For blurry frames. Renders each step several times, so training is slower",
+                ))
+                .clicked()
+            {
+                self.args.train_config.motion_blur_px = motion_blur.then_some(8.0);
+            }
+
+            if let Some(blur) = self.args.train_config.motion_blur_px.as_mut() {
+                ui.add(
+                    Slider::new(blur, 1.0..=32.0)
+                        .clamping(egui::SliderClamping::Never)
+                        .prefix(tr("up to "))
+                        .suffix(" px"),
+                );
+            }
+
+            let mut defocus = self.args.train_config.defocus_px.is_some();
+            if ui
+                .checkbox(&mut defocus, tr("Model defocus"))
+                .on_hover_text(tr(
+                    "For shallow depth of field, like macro or portrait shots",
+                ))
+                .clicked()
+            {
+                self.args.train_config.defocus_px = defocus.then_some(3.0);
+            }
+
+            if let Some(blur) = self.args.train_config.defocus_px.as_mut() {
+                ui.add(
+                    Slider::new(blur, 0.5..=8.0)
+                        .clamping(egui::SliderClamping::Never)
+                        .prefix(tr("up to "))
+                        .suffix(" px"),
+                );
+            }
+
+            ui.checkbox(
+                &mut self.args.train_config.track_splats,
+                tr("Track splats"),
+            )
+            .on_hover_text(tr(
+                "Track when each splat was created, how often it was seen and how hard training \
+                 moved it, to color the splats by in the compare menu and export with them",
+            ));
+
+            ui.heading(tr("Process Settings"));
 
             ui.horizontal(|ui| {
-                ui.label("Evaluate");
+                ui.label(tr("Evaluate"));
                 ui.add(
                     egui::Slider::new(&mut self.args.process_config.eval_every, 1..=5000)
                         .clamping(egui::SliderClamping::Never)
-                        .prefix("every ")
-                        .suffix(" steps"),
+                        .prefix(tr("every "))
+                        .suffix(tr(" steps")),
                 );
             });
+            ui.checkbox(
+                &mut self.args.process_config.eval_in_background,
+                tr("Evaluate in the background"),
+            )
+            .on_hover_text(tr("Keep training while evaluating, instead of pausing"));
+
+            #[cfg(not(target_family = "wasm"))]
+            ui.horizontal(|ui| {
+                ui.label(tr("Background GPU"));
+                let mut name = self
+                    .args
+                    .process_config
+                    .background_gpu
+                    .clone()
+                    .unwrap_or_default();
+                ui.add(
+                    egui::TextEdit::singleline(&mut name)
+                        .hint_text(tr("eg. discrete:1"))
+                        .desired_width(100.0),
+                );
+                self.args.process_config.background_gpu =
+                    Some(name.trim().to_owned()).filter(|name| !name.is_empty());
+            })
+            .response
+            .on_hover_text(tr(
+                "Run evals and exports on another GPU, so they don't slow down training. The GPU \
+                 that trains can be picked too, which gives them their own queue on it.",
+            ));
 
             #[cfg(not(target_family = "wasm"))]
             {
                 ui.horizontal(|ui| {
-                    ui.label("Export");
+                    ui.label(tr("Export"));
                     ui.add(
                         egui::Slider::new(&mut self.args.process_config.export_every, 1..=15000)
                             .clamping(egui::SliderClamping::Never)
-                            .prefix("every ")
-                            .suffix(" steps"),
+                            .prefix(tr("every "))
+                            .suffix(tr(" steps")),
                     );
                 });
             }
 
+            let process_config = &mut self.args.process_config;
+            ui.horizontal(|ui| {
+                ui.label(tr("Export format"));
+                egui::ComboBox::from_id_salt("export_format")
+                    .selected_text(process_config.export_format.label())
+                    .show_ui(ui, |ui| {
+                        for format in ExportFormat::ALL {
+                            ui.selectable_value(
+                                &mut process_config.export_format,
+                                format,
+                                format.label(),
+                            );
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Export name"));
+                ui.text_edit_singleline(&mut process_config.export_name);
+            })
+            .response
+            .on_hover_text(tr("{iter} is replaced by the step, {timestamp} by the current time."));
+
             #[cfg(all(not(target_family = "wasm"), not(target_os = "android")))]
             {
-                ui.heading("Rerun Settings");
+                ui.heading(tr("Rerun Settings"));
 
                 ui.horizontal(|ui| {
                     ui.spacing_mut().item_spacing.x = 0.0;
                     ui.hyperlink_to("Rerun.io", "https://rerun.io");
-                    ui.label(" settings");
+                    ui.label(tr(" settings"));
                 });
                 let rerun_config = &mut self.args.rerun_config;
-                ui.checkbox(&mut rerun_config.rerun_enabled, "Enable rerun");
+                ui.checkbox(&mut rerun_config.rerun_enabled, tr("Enable rerun"));
 
                 if rerun_config.rerun_enabled {
                     ui.label(
-                    "Open the brush_blueprint.rbl in the rerun viewer for a good default layout.",
+                    tr("Open the brush_blueprint.rbl in the rerun viewer for a good default layout."),
                 );
 
                     ui.horizontal(|ui| {
-                        ui.label("Log train stats");
+                        ui.label(tr("Log train stats"));
                         ui.add(
                             egui::Slider::new(
                                 &mut rerun_config.rerun_log_train_stats_every,
                                 1..=1000,
                             )
                             .clamping(egui::SliderClamping::Never)
-                            .prefix("every ")
-                            .suffix(" steps"),
+                            .prefix(tr("every "))
+                            .suffix(tr(" steps")),
                         );
                     });
 
                     let mut visualize_splats = rerun_config.rerun_log_splats_every.is_some();
-                    ui.checkbox(&mut visualize_splats, "Visualize splats");
+                    ui.checkbox(&mut visualize_splats, tr("Visualize splats"));
                     if visualize_splats != rerun_config.rerun_log_splats_every.is_some() {
                         rerun_config.rerun_log_splats_every =
                             if visualize_splats { Some(500) } else { None };
@@ -150,7 +537,7 @@ impl AppPanel for SettingsPanel {
                         ui.add(
                             egui::Slider::new(every, 1..=5000)
                                 .clamping(egui::SliderClamping::Never)
-                                .text("Visualize splats every"),
+                                .text(tr("Visualize splats every")),
                         );
                     }
                 }
@@ -158,28 +545,41 @@ impl AppPanel for SettingsPanel {
 
             ui.add_space(20.0);
 
-            ui.label("Select a .ply to visualize, or a .zip with training data.");
+            ui.label(tr(
+                "Select a .ply to visualize, or a .zip with training data.",
+            ));
 
-            let file = ui.button("Load file").clicked();
+            let can_pick_file = !cfg!(target_os = "ios");
+            let file = can_pick_file && ui.button(tr("Load file")).clicked();
 
-            let can_pick_dir = !cfg!(target_family = "wasm") && !cfg!(target_os = "android");
-            let dir = can_pick_dir && ui.button("Load directory").clicked();
+            let can_pick_dir = !cfg!(target_family = "wasm")
+                && !cfg!(target_os = "android")
+                && !cfg!(target_os = "ios");
+            let dir = can_pick_dir && ui.button(tr("Load directory")).clicked();
 
             ui.add_space(10.0);
             ui.text_edit_singleline(&mut self.url);
 
-            let url = ui.button("Load URL").clicked();
+            let url = ui.button(tr("Load URL")).clicked();
 
             ui.add_space(10.0);
 
-            if file || dir || url {
-                let source = if file {
-                    DataSource::PickFile
-                } else if dir {
-                    DataSource::PickDirectory
-                } else {
-                    DataSource::Url(self.url.clone())
-                };
+            #[cfg(not(target_os = "ios"))]
+            let cached = self.cache.ui(ui);
+            #[cfg(target_os = "ios")]
+            let cached = self.shared_files.ui(ui);
+
+            let source = if file {
+                Some(DataSource::PickFile)
+            } else if dir {
+                Some(DataSource::PickDirectory)
+            } else if url {
+                Some(DataSource::Url(self.url.clone()))
+            } else {
+                cached
+            };
+
+            if let Some(source) = source {
                 context.connect_to(start_process(
                     source,
                     self.args.clone(),
@@ -190,4 +590,19 @@ impl AppPanel for SettingsPanel {
             ui.add_space(10.0);
         });
     }
+
+    fn load_settings(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(saved) = eframe::get_value::<SavedSettings>(storage, SETTINGS_KEY) {
+            self.args = saved.args;
+            self.url = saved.url;
+        }
+    }
+
+    fn save_settings(&self, storage: &mut dyn eframe::Storage) {
+        let saved = SavedSettings {
+            args: self.args.clone(),
+            url: self.url.clone(),
+        };
+        eframe::set_value(storage, SETTINGS_KEY, &saved);
+    }
 }
@@ -0,0 +1,82 @@
+use egui::{Key, KeyboardShortcut};
+
+use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
+use crate::keymap::{Action, Keymap};
+
+/// Lists the keyboard shortcuts, and lets them be rebound.
+pub(crate) struct ShortcutsPanel {
+    // Action waiting for a new shortcut to be pressed.
+    listening: Option<Action>,
+}
+
+impl ShortcutsPanel {
+    pub(crate) fn new() -> Self {
+        Self { listening: None }
+    }
+}
+
+impl AppPanel for ShortcutsPanel {
+    fn title(&self) -> String {
+        tr("Shortcuts").to_owned()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
+        if let Some(action) = self.listening {
+            let pressed = ui.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+
+            if let Some((key, modifiers)) = pressed {
+                match key {
+                    Key::Escape => {}
+                    Key::Backspace | Key::Delete => context.keymap.set_shortcut(action, None),
+                    _ => context
+                        .keymap
+                        .set_shortcut(action, Some(KeyboardShortcut::new(modifiers, key))),
+                }
+                self.listening = None;
+            }
+        }
+        context.capturing_keys = self.listening.is_some();
+
+        ui.label(tr(
+            "Click a shortcut and press the new keys. Escape cancels, backspace removes the shortcut.",
+        ));
+        ui.add_space(6.0);
+
+        egui::Grid::new("shortcuts_grid")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for &(action, _) in context.keymap.bindings() {
+                    ui.label(action.description());
+
+                    let listening = self.listening == Some(action);
+                    let text = if listening {
+                        tr("Press a key…").to_owned()
+                    } else {
+                        context.keymap.describe(ui.ctx(), action)
+                    };
+                    if ui.selectable_label(listening, text).clicked() {
+                        self.listening = if listening { None } else { Some(action) };
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(6.0);
+        if ui.button(tr("Reset to defaults")).clicked() {
+            context.keymap = Keymap::default();
+            self.listening = None;
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use brush_process::process_loop::ProcessMessage;
+use brush_render::diff::{SnapshotDiff, diff_splats};
+use egui::{ColorImage, TextureHandle, TextureOptions};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::app::{AppContext, AppPanel};
+use crate::compare::ViewSplats;
+use crate::i18n::tr;
+use crate::locale;
+use crate::reference_layers::heatmap_color;
+
+// Cells of the grid the splats are binned in, along each axis.
+const GRID_RESOLUTION: usize = 32;
+// The oldest snapshots are dropped beyond this many, they hold on to GPU memory.
+const MAX_SNAPSHOTS: usize = 8;
+const HEATMAP_SIZE: f32 = 256.0;
+
+struct Snapshot {
+    name: String,
+    splats: ViewSplats,
+}
+
+// Splats picked to compare.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pick {
+    Current,
+    Snapshot(usize),
+}
+
+/// Compares snapshots of the splats, eg. before and after changing a setting, by how many splats
+/// there are and where their mass moved, rather than by a single quality number.
+pub(crate) struct SnapshotDiffPanel {
+    latest: Option<ViewSplats>,
+    train_iter: Option<u32>,
+    snapshots: Vec<Snapshot>,
+    // The splats to compare, None when the picked snapshot was dropped.
+    before: Option<Pick>,
+    after: Option<Pick>,
+    // The axis the change is summed along for the heatmap.
+    axis: usize,
+    diff: Option<(String, SnapshotDiff)>,
+    heatmap: Option<TextureHandle>,
+    pending: Option<Receiver<(String, anyhow::Result<SnapshotDiff>)>>,
+    err: Option<String>,
+}
+
+impl SnapshotDiffPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            latest: None,
+            train_iter: None,
+            snapshots: vec![],
+            before: Some(Pick::Current),
+            after: Some(Pick::Current),
+            axis: 1,
+            diff: None,
+            heatmap: None,
+            pending: None,
+            err: None,
+        }
+    }
+
+    fn name(&self, pick: Option<Pick>) -> String {
+        match pick {
+            Some(Pick::Current) => tr("Current").to_owned(),
+            Some(Pick::Snapshot(i)) => self
+                .snapshots
+                .get(i)
+                .map_or_else(|| "--".to_owned(), |s| s.name.clone()),
+            None => "--".to_owned(),
+        }
+    }
+
+    fn splats(&self, pick: Option<Pick>) -> Option<ViewSplats> {
+        match pick? {
+            Pick::Current => self.latest.clone(),
+            Pick::Snapshot(i) => self.snapshots.get(i).map(|s| s.splats.clone()),
+        }
+    }
+
+    fn take_snapshot(&mut self) {
+        let Some(splats) = self.latest.clone() else {
+            return;
+        };
+        let name = match self.train_iter {
+            Some(iter) => format!("{} {iter}", tr("Step")),
+            None => format!("{} {}", tr("Snapshot"), self.snapshots.len() + 1),
+        };
+        self.snapshots.push(Snapshot { name, splats });
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.remove(0);
+            // Snapshots after the dropped one move up, the dropped one can't be compared anymore.
+            self.after = self.after.and_then(|pick| match pick {
+                Pick::Snapshot(i) => i.checked_sub(1).map(Pick::Snapshot),
+                Pick::Current => Some(Pick::Current),
+            });
+        }
+        // Compare the new snapshot with what comes after it.
+        self.before = Some(Pick::Snapshot(self.snapshots.len() - 1));
+    }
+
+    fn start_diff(&mut self) {
+        let (Some(before), Some(after)) = (self.splats(self.before), self.splats(self.after))
+        else {
+            return;
+        };
+        let label = format!("{} → {}", self.name(self.before), self.name(self.after));
+        let (sender, receiver) = channel();
+        self.pending = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let diff = diff_splats(&before, &after, GRID_RESOLUTION)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to read back the splats: {e:?}"));
+            let _ = sender.send((label, diff));
+        });
+    }
+
+    fn snapshot_combo(&self, ui: &mut egui::Ui, label: &str, selected: &mut Option<Pick>) {
+        egui::ComboBox::from_label(label)
+            .selected_text(self.name(*selected))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(selected, Some(Pick::Current), tr("Current"));
+                for (i, snapshot) in self.snapshots.iter().enumerate() {
+                    ui.selectable_value(selected, Some(Pick::Snapshot(i)), &snapshot.name);
+                }
+            });
+    }
+
+    fn heatmap(&mut self, ctx: &egui::Context, diff: &SnapshotDiff) -> TextureHandle {
+        if let Some(heatmap) = &self.heatmap {
+            return heatmap.clone();
+        }
+        let change = diff.projected_change(self.axis);
+        let range = change.iter().fold(0.0f32, |max, c| max.max(c.abs()));
+        let image = ColorImage {
+            size: [diff.resolution, diff.resolution],
+            pixels: change.iter().map(|&c| heatmap_color(c, range)).collect(),
+        };
+        let heatmap = ctx.load_texture("snapshot_diff", image, TextureOptions::NEAREST);
+        self.heatmap = Some(heatmap.clone());
+        heatmap
+    }
+
+    fn diff_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((label, diff)) = self.diff.take() else {
+            return;
+        };
+        ui.separator();
+        ui.strong(&label);
+
+        let delta = |before: f64, after: f64, decimals: usize| {
+            let sign = if after >= before { "+" } else { "-" };
+            format!(
+                "{} → {} ({sign}{})",
+                locale::number(before, decimals),
+                locale::number(after, decimals),
+                locale::number((after - before).abs(), decimals)
+            )
+        };
+        egui::Grid::new("snapshot_diff_grid")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(tr("Splats"));
+                ui.label(delta(
+                    diff.splats_before.into(),
+                    diff.splats_after.into(),
+                    0,
+                ));
+                ui.end_row();
+
+                ui.label(tr("Opacity mass"))
+                    .on_hover_text(tr("The opacity of all splats summed"));
+                ui.label(delta(diff.mass_before.into(), diff.mass_after.into(), 0));
+                ui.end_row();
+
+                ui.label(tr("Moved mass")).on_hover_text(tr(
+                    "How much of the opacity is in other parts of the scene, from 0% (spread \
+                     the same way) to 100% (nothing in common)",
+                ));
+                ui.label(format!(
+                    "{}%",
+                    locale::number(f64::from(diff.moved_mass) * 100.0, 1)
+                ));
+                ui.end_row();
+
+                ui.label(tr("Center of mass moved"));
+                ui.label(locale::length(diff.center_shift.into()));
+                ui.end_row();
+            });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Seen along"));
+            for (axis, name) in ["X", "Y", "Z"].into_iter().enumerate() {
+                if ui.selectable_value(&mut self.axis, axis, name).changed() {
+                    self.heatmap = None;
+                }
+            }
+        });
+        let heatmap = self.heatmap(ui.ctx(), &diff);
+        let size = ui.available_width().min(HEATMAP_SIZE);
+        ui.image((heatmap.id(), egui::vec2(size, size)))
+            .on_hover_text(tr(
+                "Red where splats gained opacity, blue where they lost it, summed along the axis",
+            ));
+        self.diff = Some((label, diff));
+    }
+}
+
+impl AppPanel for SnapshotDiffPanel {
+    fn title(&self) -> String {
+        tr("Snapshot diff").to_owned()
+    }
+
+    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+        match message {
+            ProcessMessage::NewSource => *self = Self::new(),
+            ProcessMessage::ViewSplats { splats, .. } => {
+                self.latest = Some(*splats.clone());
+                self.train_iter = None;
+            }
+            ProcessMessage::TrainStep { splats, iter, .. } => {
+                self.latest = Some(*splats.clone());
+                self.train_iter = Some(*iter);
+            }
+            _ => {}
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
+        if let Some(receiver) = self.pending.as_ref() {
+            match receiver.try_recv() {
+                Ok((label, result)) => {
+                    self.pending = None;
+                    self.heatmap = None;
+                    match result {
+                        Ok(diff) => {
+                            self.diff = Some((label, diff));
+                            self.err = None;
+                        }
+                        Err(e) => self.err = Some(e.to_string()),
+                    }
+                }
+                Err(TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        if self.latest.is_none() {
+            ui.label(tr("Load or train splats to compare snapshots of them."));
+            return;
+        }
+
+        if ui
+            .button(tr("📸 Take snapshot"))
+            .on_hover_text(tr(
+                "Keep the current splats, to compare them with later ones, eg. after changing \
+                 a setting",
+            ))
+            .clicked()
+        {
+            self.take_snapshot();
+        }
+
+        let mut before = self.before;
+        let mut after = self.after;
+        self.snapshot_combo(ui, tr("Before"), &mut before);
+        self.snapshot_combo(ui, tr("After"), &mut after);
+        self.before = before;
+        self.after = after;
+
+        ui.horizontal(|ui| {
+            let compare = ui.add_enabled(
+                self.before.is_some()
+                    && self.after.is_some()
+                    && self.before != self.after
+                    && self.pending.is_none(),
+                egui::Button::new(tr("Compare")),
+            );
+            if compare.clicked() {
+                self.start_diff();
+            }
+            if self.pending.is_some() {
+                ui.spinner();
+            }
+        });
+
+        if let Some(err) = &self.err {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        self.diff_ui(ui);
+    }
+}
@@ -1,83 +1,164 @@
 use crate::app::{AppContext, AppPanel};
-use brush_process::process_loop::ProcessMessage;
+use crate::debug_bundle::DebugInfo;
+use crate::i18n::tr;
+use crate::locale;
+use crate::train_view::TrainView;
+use brush_process::process_loop::{ControlMessage, ProcessMessage, RateLimit, TrainProgress};
 
 use burn_cubecl::cubecl::Runtime;
 use burn_wgpu::{WgpuDevice, WgpuRuntime};
 use std::time::Duration;
+use tokio_with_wasm::alias as tokio_wasm;
 use web_time::Instant;
 use wgpu::AdapterInfo;
 
 pub(crate) struct StatsPanel {
     device: WgpuDevice,
 
-    last_train_step: (Instant, u32),
-    train_iter_per_s: f32,
+    progress: TrainProgress,
     last_eval: Option<String>,
     cur_sh_degree: u32,
+    // Size of the scene training is scaled with, and whether it was estimated.
+    scene_scale: Option<(f32, bool)>,
+    dataset_hash: Option<String>,
 
     training_started: bool,
+    rate_limit: RateLimit,
     num_splats: u32,
     frames: u32,
 
     start_load_time: Instant,
     adapter_info: AdapterInfo,
+    // The last error of the process, and the errors that caused it, for debug bundles.
+    last_error: Option<Vec<String>>,
+    include_dataset: bool,
+    show_train_view: bool,
+    train_view: TrainView,
 }
 
 impl StatsPanel {
     pub(crate) fn new(device: WgpuDevice, adapter_info: AdapterInfo) -> Self {
         Self {
             device,
-            last_train_step: (Instant::now(), 0),
-            train_iter_per_s: 0.0,
+            progress: TrainProgress::new(0, 0),
             last_eval: None,
             training_started: false,
+            rate_limit: RateLimit::default(),
             num_splats: 0,
             frames: 0,
             cur_sh_degree: 0,
+            scene_scale: None,
+            dataset_hash: None,
             start_load_time: Instant::now(),
             adapter_info,
+            last_error: None,
+            include_dataset: false,
+            show_train_view: false,
+            train_view: TrainView::new(),
         }
     }
+
+    fn save_debug_bundle(&self, context: &AppContext) {
+        let info = DebugInfo {
+            adapter: &self.adapter_info,
+            process_args: context.process_args(),
+            error: self.last_error.as_deref(),
+            dataset: self.include_dataset.then_some(&context.dataset),
+        };
+        let zip = match info.to_zip() {
+            Ok(zip) => zip,
+            Err(e) => {
+                log::error!("Failed to create debug bundle: {e}");
+                return;
+            }
+        };
+        tokio_wasm::task::spawn(async move {
+            let saved = match rrfd::save_file("brush-debug.zip").await {
+                Ok(file) => file.write(&zip).await.map_err(anyhow::Error::from),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = saved {
+                log::error!("Failed to save debug bundle: {e}");
+            }
+        });
+    }
 }
 
-fn bytes_format(bytes: u64) -> String {
-    let unit = 1000;
+/// Controls to limit how fast training runs. Returns whether the limits changed.
+fn rate_limit_ui(ui: &mut egui::Ui, limit: &mut RateLimit) -> bool {
+    let before = *limit;
 
-    if bytes < unit {
-        format!("{bytes} B")
-    } else {
-        let size = bytes as f64;
-        let exp = match size.log(1000.0).floor() as usize {
-            0 => 1,
-            e => e,
-        };
-        let unit_prefix = b"KMGTPEZY";
-        format!(
-            "{:.2} {}B",
-            (size / unit.pow(exp as u32) as f64),
-            unit_prefix[exp - 1] as char,
-        )
+    let mut cap_steps = limit.max_steps_per_second.is_some();
+    ui.checkbox(&mut cap_steps, tr("Limit steps per second"));
+    if cap_steps != limit.max_steps_per_second.is_some() {
+        limit.max_steps_per_second = cap_steps.then_some(10.0);
     }
+    if let Some(steps) = limit.max_steps_per_second.as_mut() {
+        ui.add(
+            egui::Slider::new(steps, 0.5..=100.0)
+                .logarithmic(true)
+                .suffix(tr(" steps/s")),
+        );
+    }
+
+    let mut use_duty_cycle = limit.duty_cycle.is_some();
+    ui.checkbox(&mut use_duty_cycle, tr("Only train part of the time"))
+        .on_hover_text(tr(
+            "Sleep between steps, so other applications get to use the GPU as well.",
+        ));
+    if use_duty_cycle != limit.duty_cycle.is_some() {
+        limit.duty_cycle = use_duty_cycle.then_some(0.5);
+    }
+    if let Some(duty) = limit.duty_cycle.as_mut() {
+        let mut percent = *duty * 100.0;
+        ui.add(egui::Slider::new(&mut percent, 5.0..=100.0).suffix(tr("% of the time")));
+        *duty = percent / 100.0;
+    }
+
+    *limit != before
 }
 
 impl AppPanel for StatsPanel {
     fn title(&self) -> String {
-        "Stats".to_owned()
+        tr("Stats").to_owned()
     }
 
-    fn on_message(&mut self, message: &ProcessMessage, _: &mut AppContext) {
+    fn on_message(&mut self, message: &ProcessMessage, context: &mut AppContext) {
         match message {
             ProcessMessage::NewSource => {
+                let include_dataset = self.include_dataset;
+                let show_train_view = self.show_train_view;
                 *self = Self::new(self.device.clone(), self.adapter_info.clone());
+                self.include_dataset = include_dataset;
+                self.show_train_view = show_train_view;
+            }
+            ProcessMessage::Error(e) => {
+                self.last_error = Some(e.chain().map(|cause| cause.to_string()).collect());
             }
             ProcessMessage::StartLoading { training } => {
                 self.start_load_time = Instant::now();
-                self.last_train_step = (Instant::now(), 0);
-                self.train_iter_per_s = 0.0;
+                self.progress = context
+                    .process_args()
+                    .map_or(TrainProgress::new(0, 0), |args| {
+                        TrainProgress::new(
+                            args.train_config.total_steps,
+                            args.train_config.refine_stop_iter,
+                        )
+                    });
                 self.num_splats = 0;
                 self.cur_sh_degree = 0;
+                self.scene_scale = None;
+                self.dataset_hash = None;
                 self.last_eval = None;
                 self.training_started = *training;
+                self.rate_limit = context
+                    .process_args()
+                    .map(|args| args.process_config.rate_limit())
+                    .unwrap_or_default();
+                // A new process doesn't calculate the pixel gradients until asked to.
+                if *training && self.show_train_view {
+                    context.control_message(ControlMessage::RecordPixelGrads(true));
+                }
             }
             ProcessMessage::ViewSplats {
                 up_axis: _,
@@ -91,58 +172,122 @@ impl AppPanel for StatsPanel {
             }
             ProcessMessage::TrainStep {
                 splats,
-                stats: _,
+                stats,
                 iter,
                 timestamp,
             } => {
+                if self.show_train_view {
+                    self.train_view.on_step(stats, *iter);
+                }
                 self.cur_sh_degree = splats.sh_degree();
                 self.num_splats = splats.num_splats();
-                let current_iter_per_s = (iter - self.last_train_step.1) as f32
-                    / (*timestamp - self.last_train_step.0).as_secs_f32();
-                self.train_iter_per_s = 0.95 * self.train_iter_per_s + 0.05 * current_iter_per_s;
-                self.last_train_step = (*timestamp, *iter);
+                self.progress.update(*timestamp, *iter, self.num_splats);
+            }
+            ProcessMessage::Dataset { data } => {
+                self.dataset_hash = Some(data.content_hash());
+            }
+            ProcessMessage::SceneScale { scale, estimated } => {
+                self.scene_scale = Some((*scale, *estimated));
             }
             ProcessMessage::EvalResult {
                 iter: _,
                 avg_psnr,
+                psnr_ci,
                 avg_ssim,
+                ssim_ci,
             } => {
-                self.last_eval = Some(format!("{avg_psnr:.2} PSNR, {avg_ssim:.3} SSIM"));
+                self.last_eval = Some(format!(
+                    "{} ± {} PSNR, {} ± {} SSIM",
+                    locale::number(f64::from(*avg_psnr), 2),
+                    locale::number(f64::from(*psnr_ci), 2),
+                    locale::number(f64::from(*avg_ssim), 3),
+                    locale::number(f64::from(*ssim_ci), 3)
+                ));
             }
             _ => {}
         }
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
+    fn ui(&mut self, ui: &mut egui::Ui, context: &mut AppContext) {
         egui::Grid::new("stats_grid")
             .num_columns(2)
             .spacing([40.0, 4.0])
             .striped(true)
             .show(ui, |ui| {
-                ui.label("Splats");
-                ui.label(format!("{}", self.num_splats));
+                ui.label(tr("Splats"));
+                ui.label(locale::count(self.num_splats));
                 ui.end_row();
 
-                ui.label("SH Degree");
+                ui.label(tr("SH Degree"));
                 ui.label(format!("{}", self.cur_sh_degree));
                 ui.end_row();
 
                 if self.frames > 0 {
-                    ui.label("Frames");
-                    ui.label(format!("{}", self.frames));
+                    ui.label(tr("Frames"));
+                    ui.label(locale::count(self.frames));
                     ui.end_row();
                 }
 
                 if self.training_started {
-                    ui.label("Train step");
-                    ui.label(format!("{}", self.last_train_step.1));
+                    ui.label(tr("Train step"));
+                    ui.label(locale::count(self.progress.iter()));
+                    ui.end_row();
+
+                    ui.label(tr("Steps/s"));
+                    ui.label(locale::number(self.progress.iter_per_s(), 1));
                     ui.end_row();
 
-                    ui.label("Steps/s");
-                    ui.label(format!("{:.1}", self.train_iter_per_s));
+                    ui.label(tr("Time remaining"));
+                    ui.label(self.progress.eta().map_or("--".to_owned(), |eta| {
+                        // Round duration to seconds.
+                        let eta = Duration::from_secs(eta.as_secs());
+                        format!("{}", humantime::Duration::from(eta))
+                    }));
                     ui.end_row();
 
-                    ui.label("Last eval:");
+                    if let Some((scale, estimated)) = self.scene_scale {
+                        ui.label(tr("Scene scale")).on_hover_text(tr(
+                            "Scales the learning rate of the splat positions and the refine \
+                             thresholds, so small and large scenes train with the same settings",
+                        ));
+                        let source = if estimated {
+                            tr("estimated")
+                        } else {
+                            tr("set")
+                        };
+                        ui.label(format!(
+                            "{} ({source})",
+                            locale::number(f64::from(scale), 3)
+                        ));
+                        ui.end_row();
+                    }
+
+                    if let Some(hash) = &self.dataset_hash {
+                        ui.label(tr("Dataset hash")).on_hover_text(tr(
+                            "Hash of the image paths, image sizes and camera poses. Runs with the \
+                             same hash trained on the same data",
+                        ));
+                        let short = hash.get(..12).unwrap_or(hash);
+                        let copy = ui
+                            .add(egui::Label::new(short).sense(egui::Sense::click()))
+                            .on_hover_text(format!("{hash}\n{}", tr("Click to copy")));
+                        if copy.clicked() {
+                            ui.ctx().copy_text(hash.clone());
+                        }
+                        ui.end_row();
+                    }
+
+                    ui.label(tr("Projected splats"));
+                    ui.label(
+                        self.progress
+                            .projected_splats()
+                            .map_or("--".to_owned(), locale::count),
+                    );
+                    ui.end_row();
+
+                    ui.label(tr("Last eval:")).on_hover_text(tr(
+                        "Averaged over all eval views, ± the 95% confidence interval",
+                    ));
                     ui.label(if let Some(eval) = self.last_eval.as_ref() {
                         eval
                     } else {
@@ -150,7 +295,7 @@ impl AppPanel for StatsPanel {
                     });
                     ui.end_row();
 
-                    ui.label("Training time");
+                    ui.label(tr("Training time"));
                     // Round duration to seconds.
                     let elapsed = Duration::from_secs(self.start_load_time.elapsed().as_secs());
                     ui.label(format!("{}", humantime::Duration::from(elapsed)));
@@ -160,22 +305,45 @@ impl AppPanel for StatsPanel {
                 let client = WgpuRuntime::client(&self.device);
                 let memory = client.memory_usage();
 
-                ui.label("GPU memory");
+                ui.label(tr("GPU memory"));
                 ui.end_row();
 
-                ui.label("Bytes in use");
-                ui.label(bytes_format(memory.bytes_in_use));
+                ui.label(tr("Bytes in use"));
+                ui.label(locale::bytes(memory.bytes_in_use));
                 ui.end_row();
 
-                ui.label("Bytes reserved");
-                ui.label(bytes_format(memory.bytes_reserved));
+                ui.label(tr("Bytes reserved"));
+                ui.label(locale::bytes(memory.bytes_reserved));
                 ui.end_row();
 
-                ui.label("Active allocations");
-                ui.label(format!("{}", memory.number_allocs));
+                ui.label(tr("Active allocations"));
+                ui.label(locale::count(memory.number_allocs));
                 ui.end_row();
             });
 
+        if self.training_started {
+            ui.collapsing(tr("Rate limit"), |ui| {
+                if rate_limit_ui(ui, &mut self.rate_limit) {
+                    context.control_message(ControlMessage::RateLimit(self.rate_limit));
+                }
+            });
+
+            let toggled = ui
+                .checkbox(&mut self.show_train_view, tr("Show the last training view"))
+                .on_hover_text(tr(
+                    "The render and ground truth of the view trained on last, the error between \
+                     them, and the gradient of the loss for each pixel",
+                ))
+                .changed();
+            // The gradients cost training time, only calculate them while they're shown.
+            if toggled {
+                context.control_message(ControlMessage::RecordPixelGrads(self.show_train_view));
+            }
+            if self.show_train_view {
+                self.train_view.ui(ui);
+            }
+        }
+
         // On WASM, adapter info is mostly private, not worth showing.
         if !cfg!(target_family = "wasm") {
             egui::Grid::new("gpu_grid")
@@ -186,15 +354,15 @@ impl AppPanel for StatsPanel {
                     ui.label("GPU");
                     ui.end_row();
 
-                    ui.label("Name");
+                    ui.label(tr("Name"));
                     ui.label(&self.adapter_info.name);
                     ui.end_row();
 
-                    ui.label("Type");
+                    ui.label(tr("Type"));
                     ui.label(format!("{:?}", self.adapter_info.device_type));
                     ui.end_row();
 
-                    ui.label("Driver");
+                    ui.label(tr("Driver"));
                     ui.label(format!(
                         "{}, {}",
                         self.adapter_info.driver, self.adapter_info.driver_info
@@ -202,5 +370,22 @@ impl AppPanel for StatsPanel {
                     ui.end_row();
                 });
         }
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui
+                .button(tr("🐞 Save debug bundle…"))
+                .on_hover_text(tr(
+                    "Save the GPU, settings, last error and recent logs to a zip, to attach to a GitHub issue. Nothing is sent anywhere.",
+                ))
+                .clicked()
+            {
+                self.save_debug_bundle(context);
+            }
+            ui.checkbox(&mut self.include_dataset, tr("Include dataset info"))
+                .on_hover_text(tr(
+                    "The number of views, and the cameras and image sizes of the first views",
+                ));
+        });
     }
 }
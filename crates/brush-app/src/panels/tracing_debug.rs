@@ -1,4 +1,5 @@
 use crate::app::{AppContext, AppPanel};
+use crate::i18n::tr;
 
 #[derive(Default)]
 pub(crate) struct TracingPanel {
@@ -12,10 +13,10 @@ impl AppPanel for TracingPanel {
 
     fn ui(&mut self, ui: &mut egui::Ui, _: &mut AppContext) {
         let mut checked = sync_span::is_enabled();
-        ui.checkbox(&mut checked, "Sync scopes");
+        ui.checkbox(&mut checked, tr("Sync scopes"));
         sync_span::set_enabled(checked);
 
-        ui.checkbox(&mut self.constant_redraw, "Constant redraw");
+        ui.checkbox(&mut self.constant_redraw, tr("Constant redraw"));
 
         // Nb: this redraws the whole context so this will include the splat views.
         if self.constant_redraw {
@@ -0,0 +1,120 @@
+//! Find the points on the splats under pixels of a render.
+
+use brush_render::{RenderAux, camera::Camera};
+use brush_train::train::TrainBack;
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{Int, Tensor, TensorData};
+use glam::{UVec2, Vec2, Vec3};
+
+use crate::compare::ViewSplats;
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+
+/// A render to pick points from.
+#[derive(Clone)]
+pub(crate) struct PickTarget {
+    pub(crate) splats: ViewSplats,
+    pub(crate) camera: Camera,
+    pub(crate) size: UVec2,
+    pub(crate) aux: RenderAux<InnerBack>,
+}
+
+async fn read_ints(tensor: Tensor<InnerBack, 1, Int>) -> Option<Vec<i32>> {
+    tensor.into_data_async().await.to_vec().ok()
+}
+
+impl PickTarget {
+    /// Depth of `point` (in the space of the splats) seen from the camera of the render.
+    pub(crate) fn depth(&self, point: Vec3) -> f32 {
+        self.camera.world_to_local().transform_point3(point).z
+    }
+
+    /// Pixel `point` (in the space of the splats) is rendered at, if it's in front of the camera.
+    pub(crate) fn project(&self, point: Vec3) -> Option<Vec2> {
+        let local = self.camera.world_to_local().transform_point3(point);
+        (local.z > self.camera.near).then(|| {
+            self.camera.focal(self.size) * local.truncate() / local.z
+                + self.camera.center(self.size)
+        })
+    }
+
+    /// Points on the splats under `pixels`, in the space of the splats.
+    ///
+    /// A pixel picks the last splat that was blended into it, which for opaque surfaces is close
+    /// to the surface. The point is at the depth of that splat, on the ray through the pixel.
+    pub(crate) async fn pick(&self, pixels: &[Vec2]) -> Vec<Option<Vec3>> {
+        let device = self.splats.device();
+        let [h, w] = self.aux.final_index.dims();
+        let pixel_ids: Vec<Option<i32>> = pixels
+            .iter()
+            .map(|p| {
+                let inside = p.x >= 0.0 && p.y >= 0.0 && p.x < w as f32 && p.y < h as f32;
+                inside.then(|| p.y as i32 * w as i32 + p.x as i32)
+            })
+            .collect();
+        let ids: Vec<i32> = pixel_ids.iter().flatten().copied().collect();
+        if ids.is_empty() {
+            return vec![None; pixels.len()];
+        }
+        let int_tensor = |values: Vec<i32>| {
+            let len = values.len();
+            Tensor::<InnerBack, 1, Int>::from_data(TensorData::new(values, [len]), &device)
+        };
+
+        let final_index = self
+            .aux
+            .final_index
+            .clone()
+            .reshape([h * w])
+            .select(0, int_tensor(ids));
+        let Some(final_index) = read_ints(final_index).await else {
+            return vec![None; pixels.len()];
+        };
+
+        // Pixels where no splat was blended in have nothing to pick.
+        let isects: Vec<i32> = final_index
+            .iter()
+            .filter(|&&i| i > 0)
+            .map(|i| i - 1)
+            .collect();
+        if isects.is_empty() {
+            return vec![None; pixels.len()];
+        }
+        let compact_gid = self
+            .aux
+            .compact_gid_from_isect
+            .clone()
+            .select(0, int_tensor(isects));
+        let global_gid = self
+            .aux
+            .global_from_compact_gid
+            .clone()
+            .select(0, compact_gid);
+        let means = self.splats.means.val().select(0, global_gid);
+        let Ok(means) = means.into_data_async().await.to_vec::<f32>() else {
+            return vec![None; pixels.len()];
+        };
+
+        let local_to_world = self.camera.local_to_world();
+        let focal = self.camera.focal(self.size);
+        let center = self.camera.center(self.size);
+        let mut picked = means
+            .chunks_exact(3)
+            .map(|m| self.depth(Vec3::new(m[0], m[1], m[2])));
+        let mut final_index = final_index.into_iter();
+
+        pixels
+            .iter()
+            .zip(pixel_ids)
+            .map(|(pixel, id)| {
+                id?;
+                if final_index.next()? <= 0 {
+                    return None;
+                }
+                let depth = picked.next()?;
+                let dir = ((*pixel - center) / focal).extend(1.0);
+                Some(local_to_world.transform_point3(dir * depth))
+            })
+            .collect()
+    }
+}
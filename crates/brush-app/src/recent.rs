@@ -0,0 +1,96 @@
+//! Recently opened data, listed on the start screen to open it again.
+
+use brush_process::data_source::DataSource;
+use brush_process::process_loop::ProcessArgs;
+use serde::{Deserialize, Serialize};
+
+const MAX_RECENT: usize = 8;
+
+/// Width of the thumbnails, the height depends on the aspect ratio of the view.
+pub(crate) const THUMBNAIL_WIDTH: u32 = 160;
+
+/// Where recently opened data can be opened from again.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum RecentLocation {
+    Url(String),
+    Path(String),
+    /// An entry in the browser cache, see [`brush_process::data_source::DataSource::Cached`].
+    Cached(String),
+}
+
+impl RecentLocation {
+    pub(crate) fn from_source(source: &DataSource) -> Option<Self> {
+        match source {
+            DataSource::Url(url) => Some(Self::Url(url.clone())),
+            DataSource::Path(path) => Some(Self::Path(path.clone())),
+            DataSource::Cached(key) => Some(Self::Cached(key.clone())),
+            DataSource::PickFile | DataSource::PickDirectory | DataSource::Bytes(_) => None,
+        }
+    }
+
+    pub(crate) fn to_source(&self) -> DataSource {
+        match self {
+            Self::Url(url) => DataSource::Url(url.clone()),
+            Self::Path(path) => DataSource::Path(path.clone()),
+            Self::Cached(key) => DataSource::Cached(key.clone()),
+        }
+    }
+
+    /// Identifies the location in the cache of thumbnails, see [`brush_process::thumbnails`].
+    pub(crate) fn thumbnail_id(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Longer description of where the data is, eg. the full path.
+    pub(crate) fn describe(&self) -> &str {
+        match self {
+            Self::Url(url) => url,
+            Self::Path(path) => path,
+            Self::Cached(_) => "Browser cache",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RecentSource {
+    pub(crate) name: String,
+    pub(crate) location: RecentLocation,
+    /// Settings the data was opened with, to open it the same way again.
+    #[serde(default)]
+    pub(crate) args: ProcessArgs,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct RecentSources {
+    // Most recently opened first.
+    entries: Vec<RecentSource>,
+}
+
+impl RecentSources {
+    pub(crate) fn entries(&self) -> &[RecentSource] {
+        &self.entries
+    }
+
+    /// Move `location` to the front of the list. Returns the entries that no longer fit.
+    pub(crate) fn add(
+        &mut self,
+        name: String,
+        location: RecentLocation,
+        args: ProcessArgs,
+    ) -> Vec<RecentSource> {
+        self.entries.retain(|e| e.location != location);
+        self.entries.insert(
+            0,
+            RecentSource {
+                name,
+                location,
+                args,
+            },
+        );
+        self.entries.split_off(MAX_RECENT.min(self.entries.len()))
+    }
+
+    pub(crate) fn remove(&mut self, location: &RecentLocation) {
+        self.entries.retain(|e| &e.location != location);
+    }
+}
@@ -0,0 +1,255 @@
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use brush_dataset::{deviation::DeviationResult, reference_import::ReferenceGeometry};
+use egui::{
+    Color32, Rect, Stroke,
+    epaint::{Vertex, WHITE_UV},
+    vec2,
+};
+use glam::{Affine3A, UVec2, Vec2, Vec3};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::{app::AppContext, picking::PickTarget, scene_graph::SceneNodeKind};
+
+// Drawing is done with the egui painter, so cap the amount of geometry per layer
+// to keep the UI responsive.
+const MAX_POINTS: usize = 250_000;
+const MAX_EDGES: usize = 100_000;
+
+// The painter has no depth buffer, so the depth of the splats is read back at every few pixels
+// of the last render, and geometry behind it isn't drawn.
+const DEPTH_STEP: u32 = 4;
+// Geometry is only hidden this far behind the splats, as the depth of a pixel is that of the
+// last splat blended into it, which is a bit behind the surface.
+const OCCLUSION_MARGIN: f32 = 0.02;
+
+const DEFAULT_COLOR: Color32 = Color32::from_rgb(80, 255, 160);
+
+/// Reference geometry shown alongside the splats, linked to a node in the scene graph.
+pub struct ReferenceLayer {
+    pub node_id: u64,
+    pub geometry: ReferenceGeometry,
+    pub deviation: Option<DeviationResult>,
+}
+
+/// Map a signed deviation to a blue - white - red color ramp, saturating at `range`.
+pub(crate) fn heatmap_color(deviation: f32, range: f32) -> Color32 {
+    let t = (deviation / range.max(1e-6)).clamp(-1.0, 1.0);
+    let fade = ((1.0 - t.abs()) * 255.0) as u8;
+    if t < 0.0 {
+        Color32::from_rgb(fade, fade, 255)
+    } else {
+        Color32::from_rgb(255, fade, fade)
+    }
+}
+
+struct Projection {
+    world_to_cam: Affine3A,
+    focal: glam::Vec2,
+    center: glam::Vec2,
+    origin: egui::Pos2,
+}
+
+impl Projection {
+    // Position on the screen, and depth from the camera.
+    fn project(&self, p: Vec3) -> Option<(egui::Pos2, f32)> {
+        let p_cam = self.world_to_cam.transform_point3(p);
+        if p_cam.z < 0.01 {
+            return None;
+        }
+        let pix = self.focal * p_cam.truncate() / p_cam.z + self.center;
+        Some((self.origin + vec2(pix.x, pix.y), p_cam.z))
+    }
+}
+
+/// Depth of the splats at a grid of pixels of a render, in the space of the viewer camera.
+pub(crate) struct SplatDepth {
+    // Transform of the camera in the space of the splats the depth was read for, as the depth
+    // only fits renders from there.
+    camera_to_splats: Affine3A,
+    size: UVec2,
+    columns: u32,
+    // Infinite where no splats were rendered.
+    depths: Vec<f32>,
+}
+
+impl SplatDepth {
+    async fn read(target: PickTarget, camera_to_splats: Affine3A) -> Self {
+        let columns = target.size.x.div_ceil(DEPTH_STEP);
+        let rows = target.size.y.div_ceil(DEPTH_STEP);
+        let pixels: Vec<Vec2> = (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| Vec2::new(x as f32, y as f32)))
+            .map(|cell| (cell + 0.5) * DEPTH_STEP as f32)
+            .collect();
+        let splats_to_camera = camera_to_splats.inverse();
+        let depths = target
+            .pick(&pixels)
+            .await
+            .into_iter()
+            .map(|point| point.map_or(f32::INFINITY, |p| splats_to_camera.transform_point3(p).z))
+            .collect();
+        Self {
+            camera_to_splats,
+            size: target.size,
+            columns,
+            depths,
+        }
+    }
+
+    // Whether something at `depth` is hidden behind the splats at `pixel`.
+    fn occludes(&self, pixel: Vec2, depth: f32) -> bool {
+        let cell = (pixel / DEPTH_STEP as f32).as_uvec2();
+        let index = cell.y * self.columns + cell.x;
+        self.depths
+            .get(index as usize)
+            .is_some_and(|&splats| splats < depth * (1.0 - OCCLUSION_MARGIN))
+    }
+}
+
+/// Reads back the depth of the splats to hide reference geometry behind them.
+#[derive(Default)]
+pub(crate) struct ReferenceOcclusion {
+    receiver: Option<Receiver<SplatDepth>>,
+    depth: Option<SplatDepth>,
+}
+
+impl ReferenceOcclusion {
+    /// Read the depth of the render `target`, made by a camera at `camera_to_splats`, unless the
+    /// last read isn't done yet.
+    pub(crate) fn update(&mut self, target: &PickTarget, camera_to_splats: Affine3A) {
+        if self.receiver.is_some() {
+            return;
+        }
+        let (sender, receiver) = channel();
+        self.receiver = Some(receiver);
+        let target = target.clone();
+        tokio_wasm::task::spawn(async move {
+            let _ = sender.send(SplatDepth::read(target, camera_to_splats).await);
+        });
+    }
+
+    /// Whether there's no depth for renders by a camera at `camera_to_splats` yet.
+    pub(crate) fn needs_depth(&self, camera_to_splats: Affine3A) -> bool {
+        self.depth
+            .as_ref()
+            .is_none_or(|d| d.camera_to_splats != camera_to_splats)
+    }
+
+    pub(crate) fn receive(&mut self, ctx: &egui::Context) {
+        if let Some(receiver) = self.receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(depth) => {
+                    self.receiver = None;
+                    self.depth = Some(depth);
+                    ctx.request_repaint();
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint(),
+                Err(TryRecvError::Disconnected) => self.receiver = None,
+            }
+        }
+    }
+}
+
+/// Draw all visible reference layers over the rendered splats in `rect`, leaving out what's
+/// behind the splats. While the camera moves, until the depth of the splats is read back for
+/// the new view, everything is drawn.
+pub(crate) fn draw_reference_layers(
+    ui: &egui::Ui,
+    rect: Rect,
+    size: UVec2,
+    context: &AppContext,
+    occlusion: &ReferenceOcclusion,
+) {
+    let painter = ui.painter_at(rect);
+
+    let (splats_visible, splats_transform) = context
+        .scene_graph
+        .resolve_kind(SceneNodeKind::Splats)
+        .unwrap_or((true, Affine3A::IDENTITY));
+    let camera_to_splats = context.model_local_to_world
+        * splats_transform.inverse()
+        * context.controls.local_to_world();
+    let splat_depth = occlusion
+        .depth
+        .as_ref()
+        .filter(|d| splats_visible && d.size == size && d.camera_to_splats == camera_to_splats);
+    let hidden = |pos: egui::Pos2, depth_at: f32| {
+        let pixel = Vec2::new(pos.x - rect.min.x, pos.y - rect.min.y);
+        splat_depth.is_some_and(|d| d.occludes(pixel, depth_at))
+    };
+
+    for layer in &context.reference_layers {
+        let Some((visible, node_transform)) = context.scene_graph.resolve(layer.node_id) else {
+            continue;
+        };
+        if !visible {
+            continue;
+        }
+
+        // Same convention as the splats: the node transform is applied in the space of the controls.
+        let cam_to_world = context.model_local_to_world
+            * node_transform.inverse()
+            * context.controls.local_to_world();
+
+        let proj = Projection {
+            world_to_cam: cam_to_world.inverse(),
+            focal: context.camera.focal(size),
+            center: context.camera.center(size),
+            origin: rect.min,
+        };
+
+        let geom = &layer.geometry;
+
+        // Show the deviation heatmap on the vertices instead of a wireframe when available.
+        if !geom.edges.is_empty() && layer.deviation.is_none() {
+            let stride = geom.edges.len().div_ceil(MAX_EDGES);
+            let stroke = Stroke::new(1.0, DEFAULT_COLOR.gamma_multiply(0.6));
+            let shapes = geom.edges.iter().step_by(stride).filter_map(|&[a, b]| {
+                let (a, depth_a) = proj.project(geom.points[a as usize])?;
+                let (b, depth_b) = proj.project(geom.points[b as usize])?;
+                if hidden(a, depth_a) && hidden(b, depth_b) {
+                    return None;
+                }
+                Some(egui::Shape::line_segment([a, b], stroke))
+            });
+            painter.extend(shapes);
+        } else {
+            let stride = geom.points.len().div_ceil(MAX_POINTS);
+            let mut mesh = egui::Mesh::default();
+
+            for (i, p) in geom.points.iter().enumerate().step_by(stride) {
+                let Some((pos, depth)) = proj.project(*p) else {
+                    continue;
+                };
+                if !rect.contains(pos) || hidden(pos, depth) {
+                    continue;
+                }
+                let color = if let Some(deviation) = &layer.deviation {
+                    heatmap_color(deviation.distances[i], deviation.stats.p95)
+                } else {
+                    geom.colors
+                        .get(i)
+                        .map_or(DEFAULT_COLOR, |c| Color32::from_rgb(c[0], c[1], c[2]))
+                };
+
+                let idx = mesh.vertices.len() as u32;
+                for offset in [
+                    vec2(-1.0, -1.0),
+                    vec2(1.0, -1.0),
+                    vec2(1.0, 1.0),
+                    vec2(-1.0, 1.0),
+                ] {
+                    mesh.vertices.push(Vertex {
+                        pos: pos + offset,
+                        uv: WHITE_UV,
+                        color,
+                    });
+                }
+                mesh.add_triangle(idx, idx + 1, idx + 2);
+                mesh.add_triangle(idx, idx + 2, idx + 3);
+            }
+
+            painter.add(mesh);
+        }
+    }
+}
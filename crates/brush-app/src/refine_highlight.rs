@@ -0,0 +1,120 @@
+//! Highlighting what refining changes while training: the splats it added are tinted green, and
+//! markers show where it pruned splats, fading out over a few seconds.
+
+use brush_render::gaussian_splats::{Splats, inverse_sigmoid};
+use brush_render::render::rgb_to_sh;
+use brush_train::train::{RefineStats, TrainBack};
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{Int, Tensor};
+use web_time::Instant;
+
+use crate::compare::ViewSplats;
+use crate::paint::{PaintMode, PaintSettings, paint};
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+
+const FADE_SECONDS: f32 = 3.0;
+const ADDED_COLOR: [f32; 3] = [0.1, 0.9, 0.2];
+const PRUNED_COLOR: [f32; 3] = [1.0, 0.1, 0.1];
+const PRUNED_OPACITY: f32 = 0.9;
+
+struct Refine {
+    time: Instant,
+    num_splats: u32,
+    num_added: u32,
+    pruned_means: Option<Tensor<InnerBack, 2>>,
+}
+
+pub(crate) struct RefineHighlight {
+    last: Option<Refine>,
+}
+
+impl RefineHighlight {
+    pub(crate) fn new() -> Self {
+        Self { last: None }
+    }
+
+    pub(crate) fn on_refine(&mut self, stats: &RefineStats) {
+        self.last = Some(Refine {
+            time: Instant::now(),
+            num_splats: stats.num_splats,
+            num_added: stats.num_added(),
+            pruned_means: stats.pruned_means.clone(),
+        });
+    }
+
+    /// The splats to show for `splats`, with the last refine highlighted, if it hasn't faded out
+    /// yet. Also returns whether the splats to show changed since the last call.
+    pub(crate) fn highlight(
+        &mut self,
+        ctx: &egui::Context,
+        splats: &ViewSplats,
+    ) -> (Option<ViewSplats>, bool) {
+        let Some(refine) = self.last.as_ref() else {
+            return (None, false);
+        };
+        let fade = 1.0 - refine.time.elapsed().as_secs_f32() / FADE_SECONDS;
+        if fade <= 0.0 {
+            self.last = None;
+            return (None, true);
+        }
+        ctx.request_repaint();
+
+        let n = splats.num_splats();
+        let device = splats.device();
+        // Added splats are at the end, until the next refine changes the number of splats.
+        let shown = if n == refine.num_splats && refine.num_added > 0 {
+            let added = Tensor::<InnerBack, 1, Int>::arange(0..n as i64, &device)
+                .greater_equal_elem((n - refine.num_added) as i64)
+                .float();
+            let settings = PaintSettings {
+                mode: PaintMode::Color,
+                color: ADDED_COLOR,
+                ..Default::default()
+            };
+            paint(splats, added * fade, &settings)
+        } else {
+            splats.clone()
+        };
+
+        let Some(means) = refine.pruned_means.clone() else {
+            return (Some(shown), true);
+        };
+        let [pruned, _] = means.dims();
+        let [_, coeffs, _] = shown.sh_coeffs.dims();
+        // Markers the typical size of the splats, whatever the scale of the scene.
+        let log_scales = shown
+            .log_scales
+            .val()
+            .mean()
+            .reshape([1, 1])
+            .repeat_dim(0, pruned)
+            .repeat_dim(1, 3);
+        let rotations = Tensor::<InnerBack, 1>::from_floats([1.0, 0.0, 0.0, 0.0], &device)
+            .reshape([1, 4])
+            .repeat_dim(0, pruned);
+        let color = Tensor::<InnerBack, 1>::from_floats(PRUNED_COLOR.map(rgb_to_sh), &device)
+            .reshape([1, 1, 3]);
+        let color = if coeffs > 1 {
+            Tensor::cat(vec![color, Tensor::zeros([1, coeffs - 1, 3], &device)], 1)
+        } else {
+            color
+        };
+        let opacity = Tensor::<InnerBack, 1>::from_floats(
+            [inverse_sigmoid((PRUNED_OPACITY * fade).max(1e-4))],
+            &device,
+        );
+
+        let highlighted = Splats::from_tensor_data(
+            Tensor::cat(vec![shown.means.val(), means], 0),
+            Tensor::cat(vec![shown.rotation.val(), rotations], 0),
+            Tensor::cat(vec![shown.log_scales.val(), log_scales], 0),
+            Tensor::cat(vec![shown.sh_coeffs_f32(), color.repeat_dim(0, pruned)], 0),
+            Tensor::cat(
+                vec![shown.raw_opacity.val(), opacity.repeat_dim(0, pruned)],
+                0,
+            ),
+        );
+        (Some(highlighted), true)
+    }
+}
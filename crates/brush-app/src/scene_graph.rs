@@ -0,0 +1,208 @@
+use glam::{Affine3A, EulerRot, Quat, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneNodeKind {
+    Splats,
+    PointCloud,
+    CameraPath,
+    Mesh,
+}
+
+impl SceneNodeKind {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Splats => "✨",
+            Self::PointCloud => "⁘",
+            Self::CameraPath => "📷",
+            Self::Mesh => "🔷",
+        }
+    }
+}
+
+/// Transform of a node relative to its parent, kept in an editable form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeTransform {
+    pub translation: Vec3,
+    /// Euler angles (XYZ) in degrees.
+    pub rotation: Vec3,
+    pub scale: f32,
+}
+
+impl Default for NodeTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Vec3::ZERO,
+            scale: 1.0,
+        }
+    }
+}
+
+impl NodeTransform {
+    pub fn to_affine(&self) -> Affine3A {
+        let rot = Quat::from_euler(
+            EulerRot::XYZ,
+            self.rotation.x.to_radians(),
+            self.rotation.y.to_radians(),
+            self.rotation.z.to_radians(),
+        );
+        Affine3A::from_scale_rotation_translation(Vec3::splat(self.scale), rot, self.translation)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+    pub id: u64,
+    pub name: String,
+    pub kind: SceneNodeKind,
+    pub visible: bool,
+    pub transform: NodeTransform,
+    pub children: Vec<SceneNode>,
+}
+
+/// A simple tree of everything shown in the scene view.
+///
+/// Nodes are looked up by id, which stays stable when nodes are renamed or moved around.
+#[derive(Debug, Clone, Default)]
+pub struct SceneGraph {
+    pub nodes: Vec<SceneNode>,
+    next_id: u64,
+}
+
+impl SceneGraph {
+    /// Add a new node as a child of `parent`, or at the root of the graph if that's `None` or
+    /// not in the graph, returning its id.
+    pub fn add_node(&mut self, name: &str, kind: SceneNodeKind, parent: Option<u64>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let node = SceneNode {
+            id,
+            name: name.to_owned(),
+            kind,
+            visible: true,
+            transform: NodeTransform::default(),
+            children: vec![],
+        };
+        self.insert(node, parent);
+        id
+    }
+
+    fn insert(&mut self, node: SceneNode, parent: Option<u64>) {
+        match parent.and_then(|parent| find_mut(&mut self.nodes, parent)) {
+            Some(parent) => parent.children.push(node),
+            None => self.nodes.push(node),
+        }
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut SceneNode> {
+        find_mut(&mut self.nodes, id)
+    }
+
+    /// Id of the parent of a node, `None` for nodes at the root.
+    pub fn parent(&self, id: u64) -> Option<u64> {
+        self.iter()
+            .find(|n| n.children.iter().any(|c| c.id == id))
+            .map(|n| n.id)
+    }
+
+    /// Whether `id` is `ancestor` or one of its descendants.
+    pub fn is_within(&self, id: u64, ancestor: u64) -> bool {
+        let mut current = Some(id);
+        while let Some(node) = current {
+            if node == ancestor {
+                return true;
+            }
+            current = self.parent(node);
+        }
+        false
+    }
+
+    /// Move a node with its children under `parent`, or to the root for `None`. Its transform
+    /// stays relative to its parent. Nodes can't be moved into themselves or their children.
+    pub fn move_node(&mut self, id: u64, parent: Option<u64>) {
+        if parent.is_some_and(|parent| self.is_within(parent, id)) {
+            return;
+        }
+        fn take(nodes: &mut Vec<SceneNode>, id: u64) -> Option<SceneNode> {
+            if let Some(index) = nodes.iter().position(|n| n.id == id) {
+                return Some(nodes.remove(index));
+            }
+            nodes.iter_mut().find_map(|n| take(&mut n.children, id))
+        }
+        if let Some(node) = take(&mut self.nodes, id) {
+            self.insert(node, parent);
+        }
+    }
+
+    /// Find the first node of a given kind, or add one if there is none yet.
+    pub fn get_or_add(&mut self, name: &str, kind: SceneNodeKind) -> u64 {
+        if let Some(node) = self.iter().find(|n| n.kind == kind) {
+            return node.id;
+        }
+        self.add_node(name, kind, None)
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        fn remove_from(nodes: &mut Vec<SceneNode>, id: u64) {
+            nodes.retain(|n| n.id != id);
+            for node in nodes {
+                remove_from(&mut node.children, id);
+            }
+        }
+        remove_from(&mut self.nodes, id);
+    }
+
+    /// Iterate over all nodes, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &SceneNode> {
+        fn collect<'a>(nodes: &'a [SceneNode], out: &mut Vec<&'a SceneNode>) {
+            for node in nodes {
+                out.push(node);
+                collect(&node.children, out);
+            }
+        }
+        let mut out = vec![];
+        collect(&self.nodes, &mut out);
+        out.into_iter()
+    }
+
+    /// Visibility & world transform of a node, taking all its parents into account.
+    pub fn resolve(&self, id: u64) -> Option<(bool, Affine3A)> {
+        fn find(
+            nodes: &[SceneNode],
+            id: u64,
+            vis: bool,
+            parent: Affine3A,
+        ) -> Option<(bool, Affine3A)> {
+            for node in nodes {
+                let vis = vis && node.visible;
+                let transform = parent * node.transform.to_affine();
+                if node.id == id {
+                    return Some((vis, transform));
+                }
+                if let Some(found) = find(&node.children, id, vis, transform) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+        find(&self.nodes, id, true, Affine3A::IDENTITY)
+    }
+
+    /// Visibility & world transform of the first node of the given kind.
+    pub fn resolve_kind(&self, kind: SceneNodeKind) -> Option<(bool, Affine3A)> {
+        let id = self.iter().find(|n| n.kind == kind)?.id;
+        self.resolve(id)
+    }
+}
+
+fn find_mut(nodes: &mut [SceneNode], id: u64) -> Option<&mut SceneNode> {
+    for node in nodes {
+        if node.id == id {
+            return Some(node);
+        }
+        if let Some(found) = find_mut(&mut node.children, id) {
+            return Some(found);
+        }
+    }
+    None
+}
@@ -0,0 +1,274 @@
+//! Scripts to automate the app, written in [Rhai](https://rhai.rs), eg. to train on a dataset
+//! with a few different settings and export the results.
+//!
+//! Running a script doesn't do anything by itself: it makes a list of [`ScriptCommand`]s, which
+//! the script panel then runs one after the other, waiting for training where needed. Scripts
+//! can use loops and variables to make these commands, but never block the UI.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use brush_process::{data_source::DataSource, process_loop::ProcessArgs};
+use glam::{Quat, Vec3};
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+
+use crate::hdr::Tonemap;
+
+// Stop scripts that take too long to make their commands, eg. because of an endless loop.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Functions scripts can call, shown in the script panel.
+pub(crate) const SCRIPT_FUNCTIONS: &[(&str, &str)] = &[
+    ("load_url(url)", "Open data from a url, and train on it"),
+    (
+        "load_path(path)",
+        "Open a file or directory, and train on it",
+    ),
+    (
+        "set(name, value)",
+        "Change a setting for the next load, eg. set(\"total_steps\", 5000)",
+    ),
+    (
+        "wait_for_step(step)",
+        "Wait until training reaches this step",
+    ),
+    ("export()", "Export the splats to the export path"),
+    (
+        "camera_pose()",
+        "The current camera, as [x, y, z, qx, qy, qz, qw]",
+    ),
+    ("camera(pose)", "Move the camera to a pose"),
+    (
+        "screenshot(file)",
+        "Save the current view to a PNG, or to a linear EXR with a PNG preview for .exr files",
+    ),
+    (
+        "render_path(poses, frames, file)",
+        "Render frames moving through the poses, {frame} in the file name is the frame number",
+    ),
+    (
+        "tonemap(name)",
+        "Tone mapping of EXR previews: \"clamp\", \"aces\" or \"filmic\"",
+    ),
+    ("print(text)", "Show text in the script log"),
+];
+
+/// A single thing for the app to do.
+pub(crate) enum ScriptCommand {
+    /// Open data, and train on it with these settings.
+    Load(DataSource, Box<ProcessArgs>),
+    /// Wait until training has reached this step.
+    WaitForStep(u32),
+    /// Export the splats, as configured by the export settings.
+    Export,
+    SetCamera(Vec3, Quat),
+    /// Render the current view to a PNG with this file name, or to a linear EXR and a PNG
+    /// preview of it if the name ends in `.exr`.
+    Screenshot(String),
+    /// Tone mapping of the PNG previews of EXR renders.
+    SetTonemap(Tonemap),
+    Print(String),
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+struct ScriptState {
+    commands: Vec<ScriptCommand>,
+    args: ProcessArgs,
+    camera: (Vec3, Quat),
+}
+
+/// Set a field of the process arguments by name, eg. `total_steps` or
+/// `train_config.total_steps`.
+fn set_arg(args: &ProcessArgs, key: &str, value: serde_json::Value) -> anyhow::Result<ProcessArgs> {
+    let mut json = serde_json::to_value(args)?;
+    let sections = json
+        .as_object_mut()
+        .context("Arguments should be an object")?;
+    let (section, field) = key
+        .rsplit_once('.')
+        .map_or((None, key), |(section, field)| (Some(section), field));
+    let slot = sections
+        .iter_mut()
+        .filter(|(name, _)| section.is_none_or(|s| s == name.as_str()))
+        .find_map(|(_, fields)| fields.get_mut(field))
+        .with_context(|| format!("Unknown setting {key}"))?;
+    *slot = value;
+    serde_json::from_value(json).with_context(|| format!("Invalid value for {key}"))
+}
+
+fn floats<const N: usize>(values: &Array) -> ScriptResult<[f32; N]> {
+    let values = values
+        .iter()
+        .map(|v| v.as_float().or_else(|_| v.as_int().map(|i| i as f64)))
+        .map(|v| v.map(|v| v as f32))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Expected an array of numbers, got {e}"))?;
+    values
+        .try_into()
+        .map_err(|values: Vec<f32>| format!("Expected {N} numbers, got {}", values.len()).into())
+}
+
+fn pose_from_array(pose: &Array) -> ScriptResult<(Vec3, Quat)> {
+    let [x, y, z, qx, qy, qz, qw] = floats::<7>(pose)?;
+    Ok((
+        glam::vec3(x, y, z),
+        Quat::from_xyzw(qx, qy, qz, qw).normalize(),
+    ))
+}
+
+fn pose_to_array((position, rotation): (Vec3, Quat)) -> Array {
+    position
+        .to_array()
+        .into_iter()
+        .chain(rotation.to_array())
+        .map(|v| Dynamic::from_float(v as f64))
+        .collect()
+}
+
+/// Commands to move the camera through `poses` over `frames` frames, rendering each frame.
+fn render_path(poses: &Array, frames: u32, file: &str) -> ScriptResult<Vec<ScriptCommand>> {
+    let poses = poses
+        .iter()
+        .map(|pose| {
+            let pose = pose
+                .clone()
+                .into_array()
+                .map_err(|e| format!("Expected an array of poses, got {e}"))?;
+            pose_from_array(&pose)
+        })
+        .collect::<ScriptResult<Vec<_>>>()?;
+    if poses.len() < 2 || frames < 2 {
+        return Err("A path needs at least 2 poses and 2 frames".into());
+    }
+
+    let digits = frames.to_string().len();
+    let mut commands = vec![];
+    for frame in 0..frames {
+        // Move through the poses at a constant rate.
+        let t = frame as f32 / (frames - 1) as f32 * (poses.len() - 1) as f32;
+        let segment = (t.floor() as usize).min(poses.len() - 2);
+        let t = t - segment as f32;
+        let (start_pos, start_rot) = poses[segment];
+        let (end_pos, end_rot) = poses[segment + 1];
+        commands.push(ScriptCommand::SetCamera(
+            start_pos.lerp(end_pos, t),
+            start_rot.slerp(end_rot, t),
+        ));
+        let name = file.replace("{frame}", &format!("{frame:0digits$}"));
+        commands.push(ScriptCommand::Screenshot(name));
+    }
+    Ok(commands)
+}
+
+/// Run a script, returning the commands it made. `args` are the settings used for data loaded
+/// by the script, unless the script changes them, and `camera` is the current camera pose.
+pub(crate) fn run_script(
+    script: &str,
+    args: ProcessArgs,
+    camera: (Vec3, Quat),
+) -> anyhow::Result<Vec<ScriptCommand>> {
+    let state = Arc::new(Mutex::new(ScriptState {
+        commands: vec![],
+        args,
+        camera,
+    }));
+    let push = {
+        let state = state.clone();
+        move |command| {
+            state.lock().expect("Lock poisoned").commands.push(command);
+        }
+    };
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    engine.on_print({
+        let push = push.clone();
+        move |text| push(ScriptCommand::Print(text.to_owned()))
+    });
+    engine.register_fn("load_url", {
+        let state = state.clone();
+        move |url: &str| {
+            let mut state = state.lock().expect("Lock poisoned");
+            let args = Box::new(state.args.clone());
+            state
+                .commands
+                .push(ScriptCommand::Load(DataSource::Url(url.to_owned()), args));
+        }
+    });
+    engine.register_fn("load_path", {
+        let state = state.clone();
+        move |path: &str| {
+            let mut state = state.lock().expect("Lock poisoned");
+            let args = Box::new(state.args.clone());
+            state
+                .commands
+                .push(ScriptCommand::Load(DataSource::Path(path.to_owned()), args));
+        }
+    });
+    engine.register_fn("set", {
+        let state = state.clone();
+        move |key: &str, value: Dynamic| -> ScriptResult<()> {
+            let value = serde_json::to_value(&value).map_err(|e| e.to_string())?;
+            let mut state = state.lock().expect("Lock poisoned");
+            state.args = set_arg(&state.args, key, value).map_err(|e| format!("{e:#}"))?;
+            Ok(())
+        }
+    });
+    engine.register_fn("wait_for_step", {
+        let push = push.clone();
+        move |step: i64| push(ScriptCommand::WaitForStep(step.max(0) as u32))
+    });
+    engine.register_fn("export", {
+        let push = push.clone();
+        move || push(ScriptCommand::Export)
+    });
+    engine.register_fn("camera_pose", {
+        let state = state.clone();
+        move || pose_to_array(state.lock().expect("Lock poisoned").camera)
+    });
+    engine.register_fn("camera", {
+        let state = state.clone();
+        move |pose: Array| -> ScriptResult<()> {
+            let (position, rotation) = pose_from_array(&pose)?;
+            let mut state = state.lock().expect("Lock poisoned");
+            state.camera = (position, rotation);
+            state
+                .commands
+                .push(ScriptCommand::SetCamera(position, rotation));
+            Ok(())
+        }
+    });
+    engine.register_fn("screenshot", {
+        let push = push.clone();
+        move |file: &str| push(ScriptCommand::Screenshot(file.to_owned()))
+    });
+    engine.register_fn("tonemap", {
+        let push = push.clone();
+        move |name: &str| -> ScriptResult<()> {
+            let tonemap = Tonemap::from_name(name).ok_or_else(|| {
+                format!("Unknown tone mapping {name}, expected clamp, aces or filmic")
+            })?;
+            push(ScriptCommand::SetTonemap(tonemap));
+            Ok(())
+        }
+    });
+    engine.register_fn("render_path", {
+        let state = state.clone();
+        move |poses: Array, frames: i64, file: &str| -> ScriptResult<()> {
+            let commands = render_path(&poses, frames.clamp(0, u32::MAX as i64) as u32, file)?;
+            state
+                .lock()
+                .expect("Lock poisoned")
+                .commands
+                .extend(commands);
+            Ok(())
+        }
+    });
+
+    engine.run(script).map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let commands = std::mem::take(&mut state.lock().expect("Lock poisoned").commands);
+    Ok(commands)
+}
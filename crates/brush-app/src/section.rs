@@ -0,0 +1,130 @@
+//! Section cuts, which slice away part of the splats to look inside, eg. at the rooms of a scanned
+//! building.
+
+use brush_render::camera::SectionPlane;
+use glam::{Affine3A, Vec3};
+
+use crate::i18n::tr;
+use crate::locale;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SectionAxis {
+    X,
+    Y,
+    Z,
+    /// Cut perpendicular to the view direction when the cut was started.
+    View,
+}
+
+impl SectionAxis {
+    pub(crate) const ALL: [Self; 4] = [Self::X, Self::Y, Self::Z, Self::View];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::X => "X",
+            Self::Y => "Y",
+            Self::Z => "Z",
+            Self::View => "View",
+        }
+    }
+}
+
+pub(crate) struct SectionCut {
+    pub(crate) axis: SectionAxis,
+    /// How far the plane is moved along its normal from where the cut was started.
+    pub(crate) offset: f32,
+    /// Cut away the other side of the plane.
+    pub(crate) flip: bool,
+    /// Highlight the splats along the cut.
+    pub(crate) highlight: bool,
+    // Point and normal of the plane at zero offset, in the space of the splats. Picked from the
+    // camera on the next render, eg. after changing the axis.
+    anchor: Option<(Vec3, Vec3)>,
+}
+
+impl SectionCut {
+    pub(crate) fn new() -> Self {
+        Self {
+            axis: SectionAxis::View,
+            offset: 0.0,
+            flip: false,
+            highlight: true,
+            anchor: None,
+        }
+    }
+
+    /// Start over from the current camera, eg. after changing the axis.
+    pub(crate) fn reset_anchor(&mut self) {
+        self.anchor = None;
+        self.offset = 0.0;
+    }
+
+    /// Plane to cut the splats with. `cam_transform` is the camera in the space of the splats,
+    /// and a new cut starts at the point the camera is focused on.
+    pub(crate) fn plane(&mut self, cam_transform: Affine3A, focus_distance: f32) -> SectionPlane {
+        let axis = self.axis;
+        let (origin, normal) = *self.anchor.get_or_insert_with(|| {
+            let forward = cam_transform.transform_vector3(Vec3::Z).normalize();
+            let origin = cam_transform.transform_point3(Vec3::Z * focus_distance);
+            // Cut away what's in front of the focus point, towards the camera.
+            let normal = match axis {
+                SectionAxis::X => Vec3::X,
+                SectionAxis::Y => Vec3::Y,
+                SectionAxis::Z => Vec3::Z,
+                SectionAxis::View => -forward,
+            };
+            (origin, normal)
+        });
+
+        let normal = if self.flip { -normal } else { normal };
+        let origin = origin + normal * self.offset;
+        SectionPlane {
+            plane: normal.extend(-normal.dot(origin)),
+            highlight_width: if self.highlight {
+                0.005 * focus_distance
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// Axis (0 for X, 1 for Y, 2 for Z) and position along it of the cut, if it's along one of
+    /// the axes of the splats.
+    pub(crate) fn axis_plane(&self) -> Option<(usize, f32)> {
+        let axis = match self.axis {
+            SectionAxis::X => 0,
+            SectionAxis::Y => 1,
+            SectionAxis::Z => 2,
+            SectionAxis::View => return None,
+        };
+        let (origin, _) = self.anchor?;
+        Some((axis, origin[axis] + self.offset))
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui, focus_distance: f32) {
+        let mut axis = self.axis;
+        egui::ComboBox::from_id_salt("section_axis")
+            .selected_text(tr(axis.label()))
+            .show_ui(ui, |ui| {
+                for option in SectionAxis::ALL {
+                    ui.selectable_value(&mut axis, option, tr(option.label()));
+                }
+            });
+        if axis != self.axis {
+            self.axis = axis;
+            self.reset_anchor();
+        }
+
+        // Sweep through a range around the focus point that scales with the scene.
+        let range = 2.0 * focus_distance.max(1e-3);
+        ui.add(
+            egui::Slider::new(&mut self.offset, -range..=range)
+                .text(tr("Offset"))
+                .custom_formatter(|v, _| locale::length(v))
+                .custom_parser(locale::parse_length)
+                .clamping(egui::SliderClamping::Never),
+        );
+        ui.checkbox(&mut self.flip, tr("Flip side"));
+        ui.checkbox(&mut self.highlight, tr("Highlight cut"));
+    }
+}
@@ -0,0 +1,81 @@
+//! Processes kept running in the background while another one is shown, eg. to view a splat
+//! while training another.
+//!
+//! All sessions run on the device of the app, and share its GPU queue: a session training in the
+//! background slows down the one that's shown. Separate queues per session would need the splats
+//! of a session moved to the device the UI renders with whenever it's shown, which isn't supported.
+
+use brush_process::process_loop::{ProcessMessage, RunningProcess};
+
+use crate::compare::ViewSplats;
+
+/// Remember `message` to replay it when a session is shown again. Of most messages only the latest
+/// of each kind is kept, which is enough to restore the panels, but all warnings and eval results
+/// are kept, as the panels list them all.
+pub(crate) fn record_replay(replay: &mut Vec<ProcessMessage>, message: &ProcessMessage) {
+    // These only matter as they happen.
+    if matches!(
+        message,
+        ProcessMessage::NewSource | ProcessMessage::RefineStep { .. }
+    ) {
+        return;
+    }
+
+    let keep_all = matches!(
+        message,
+        ProcessMessage::Warning(_) | ProcessMessage::EvalResult { .. }
+    );
+    let kind = std::mem::discriminant(message);
+    let existing = replay
+        .iter_mut()
+        .find(|m| std::mem::discriminant(*m) == kind);
+    match existing {
+        Some(existing) if !keep_all => *existing = message.clone(),
+        _ => replay.push(message.clone()),
+    }
+}
+
+/// Name of a session, eg. the file name of its data.
+pub(crate) fn session_name(replay: &[ProcessMessage]) -> String {
+    let name = replay.iter().find_map(|m| match m {
+        ProcessMessage::SourceOpened { name, .. } => Some(name.clone()),
+        _ => None,
+    });
+    let step = replay.iter().find_map(|m| match m {
+        ProcessMessage::TrainStep { iter, .. } => Some(*iter),
+        _ => None,
+    });
+    let name = name.unwrap_or_else(|| "Loading…".to_owned());
+    match step {
+        Some(step) => format!("{name} ({step})"),
+        None => name,
+    }
+}
+
+/// A process running in the background.
+pub(crate) struct Session {
+    pub(crate) process: RunningProcess,
+    pub(crate) replay: Vec<ProcessMessage>,
+}
+
+impl Session {
+    pub(crate) fn name(&self) -> String {
+        session_name(&self.replay)
+    }
+
+    /// The latest splats of this session, if there are any yet.
+    pub(crate) fn splats(&self) -> Option<&ViewSplats> {
+        self.replay.iter().rev().find_map(|m| match m {
+            ProcessMessage::TrainStep { splats, .. }
+            | ProcessMessage::ViewSplats { splats, .. } => Some(splats.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Handle the messages that came in since last time, so the process doesn't wait for them.
+    pub(crate) fn receive_messages(&mut self) {
+        while let Ok(message) = self.process.messages.try_recv() {
+            record_replay(&mut self.replay, &message);
+        }
+    }
+}
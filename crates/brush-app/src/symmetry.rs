@@ -0,0 +1,109 @@
+//! Mirror and duplicate a selection of splats, eg. to fill in the side of a symmetric object
+//! that wasn't captured.
+
+use brush_render::gaussian_splats::Splats;
+use burn::tensor::{Int, Tensor, backend::Backend};
+use glam::Vec3;
+
+/// Sign of each spherical harmonics coefficient when reflecting along `axis` (0 for X, 1 for Y,
+/// 2 for Z).
+///
+/// Real spherical harmonics are either even or odd along each axis, so reflecting along one of
+/// the axes only flips the sign of the odd ones.
+fn sh_reflection_signs(axis: usize, coeffs: usize) -> Vec<f32> {
+    let mut signs = Vec::with_capacity(coeffs);
+    let mut l = 0i32;
+    while signs.len() < coeffs {
+        for m in -l..=l {
+            let odd = match axis {
+                // cos(mφ) terms are odd for odd m, sin(mφ) terms for even m.
+                0 => (m.abs() + i32::from(m < 0)) % 2 == 1,
+                // Only the sin(mφ) terms are odd in y.
+                1 => m < 0,
+                _ => (l + m.abs()) % 2 == 1,
+            };
+            signs.push(if odd { -1.0 } else { 1.0 });
+        }
+        l += 1;
+    }
+    signs.truncate(coeffs);
+    signs
+}
+
+/// Append copies of the splats at `indices` to `splats`.
+fn append_copies<B: Backend>(
+    splats: &Splats<B>,
+    means: Tensor<B, 2>,
+    rotations: Tensor<B, 2>,
+    sh_coeffs: Tensor<B, 3>,
+    indices: Tensor<B, 1, Int>,
+) -> Splats<B> {
+    let log_scales = splats.log_scales.val().select(0, indices.clone());
+    let raw_opacity = splats.raw_opacity.val().select(0, indices);
+    Splats::from_tensor_data(
+        Tensor::cat(vec![splats.means.val(), means], 0),
+        Tensor::cat(vec![splats.rotation.val(), rotations], 0),
+        Tensor::cat(vec![splats.log_scales.val(), log_scales], 0),
+        Tensor::cat(vec![splats.sh_coeffs_f32(), sh_coeffs], 0),
+        Tensor::cat(vec![splats.raw_opacity.val(), raw_opacity], 0),
+    )
+}
+
+/// Add mirrored copies of the splats at `indices`, reflected through the plane perpendicular to
+/// `axis` (0 for X, 1 for Y, 2 for Z) at `center` along it.
+pub(crate) fn mirror_splats<B: Backend>(
+    splats: &Splats<B>,
+    indices: Tensor<B, 1, Int>,
+    axis: usize,
+    center: f32,
+) -> Splats<B> {
+    let device = splats.device();
+    let [_, coeffs, _] = splats.sh_coeffs.dims();
+
+    let mut scale = [1.0; 3];
+    scale[axis] = -1.0;
+    let mut shift = [0.0; 3];
+    shift[axis] = 2.0 * center;
+    let means = splats.means.val().select(0, indices.clone())
+        * Tensor::<B, 1>::from_floats(scale, &device).reshape([1, 3])
+        + Tensor::<B, 1>::from_floats(shift, &device).reshape([1, 3]);
+
+    // Reflecting a rotation keeps the part around the axis, and reverses the parts around the
+    // other axes. The reflected scales are the same, as the splats are symmetric.
+    let mut quat_scale = [1.0, -1.0, -1.0, -1.0];
+    quat_scale[axis + 1] = 1.0;
+    let rotations = splats.rotation.val().select(0, indices.clone())
+        * Tensor::<B, 1>::from_floats(quat_scale, &device).reshape([1, 4]);
+
+    let signs = Tensor::<B, 1>::from_floats(sh_reflection_signs(axis, coeffs).as_slice(), &device)
+        .reshape([1, coeffs, 1]);
+    let sh_coeffs = splats.sh_coeffs_f32().select(0, indices.clone()) * signs;
+
+    append_copies(splats, means, rotations, sh_coeffs, indices)
+}
+
+/// Add `count` copies of the splats at `indices`, each moved by `step` from the last.
+pub(crate) fn array_splats<B: Backend>(
+    splats: &Splats<B>,
+    indices: Tensor<B, 1, Int>,
+    step: Vec3,
+    count: u32,
+) -> Splats<B> {
+    let device = splats.device();
+    let means = splats.means.val().select(0, indices.clone());
+    let copies: Vec<_> = (1..=count)
+        .map(|i| {
+            let offset = (step * i as f32).to_array();
+            means.clone() + Tensor::<B, 1>::from_floats(offset, &device).reshape([1, 3])
+        })
+        .collect();
+    let rotations = splats.rotation.val().select(0, indices.clone());
+    let sh_coeffs = splats.sh_coeffs_f32().select(0, indices.clone());
+    append_copies(
+        splats,
+        Tensor::cat(copies, 0),
+        Tensor::cat(vec![rotations; count as usize], 0),
+        Tensor::cat(vec![sh_coeffs; count as usize], 0),
+        Tensor::cat(vec![indices; count as usize], 0),
+    )
+}
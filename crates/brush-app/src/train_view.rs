@@ -0,0 +1,208 @@
+//! A lightweight look at what training sees, without rerun: the render of the last trained view,
+//! its ground truth, the error between the two, and where the loss pushes the render the
+//! hardest.
+
+use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+
+use brush_train::train::{TrainBack, TrainStepStats};
+use egui::{Color32, ColorImage, TextureHandle, TextureOptions};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::i18n::tr;
+
+// Longest side of the images shown, larger views are subsampled.
+const MAX_SIZE: usize = 512;
+// The error and gradient are scaled so this fraction of the pixels isn't saturated.
+const NORMALIZE_QUANTILE: f32 = 0.99;
+
+struct ViewImages {
+    iter: u32,
+    path: String,
+    // Render, ground truth, error and gradient.
+    images: [ColorImage; 4],
+}
+
+pub(crate) struct TrainView {
+    shown: Option<(u32, String, [TextureHandle; 4])>,
+    pending: Option<Receiver<ViewImages>>,
+}
+
+// Black to red to yellow to white, for values from 0 to 1.
+fn heat_color(value: f32) -> Color32 {
+    let value = value.clamp(0.0, 1.0) * 3.0;
+    let channel = |offset: f32| ((value - offset).clamp(0.0, 1.0) * 255.0) as u8;
+    Color32::from_rgb(channel(0.0), channel(1.0), channel(2.0))
+}
+
+fn rgb_color(rgb: [f32; 3]) -> Color32 {
+    let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8);
+    Color32::from_rgb(r, g, b)
+}
+
+fn heat_image(size: [usize; 2], values: &[f32]) -> ColorImage {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let index = ((sorted.len() as f32 * NORMALIZE_QUANTILE) as usize).min(sorted.len() - 1);
+    let scale = sorted[index].max(1e-8);
+
+    ColorImage {
+        size,
+        pixels: values.iter().map(|v| heat_color(v / scale)).collect(),
+    }
+}
+
+fn view_images(
+    iter: u32,
+    path: String,
+    gt: &image::DynamicImage,
+    [height, width, channels]: [usize; 3],
+    pred: &[f32],
+    grads: &[f32],
+) -> ViewImages {
+    let gt = gt.to_rgb8();
+    let step = height.max(width).div_ceil(MAX_SIZE).max(1);
+    let size = [width.div_ceil(step), height.div_ceil(step)];
+
+    let mut render = Vec::with_capacity(size[0] * size[1]);
+    let mut truth = Vec::with_capacity(render.capacity());
+    let mut error = Vec::with_capacity(render.capacity());
+    let mut gradient = Vec::with_capacity(render.capacity());
+    for y in (0..height).step_by(step) {
+        for x in (0..width).step_by(step) {
+            let offset = (y * width + x) * channels;
+            let rgb = [0, 1, 2].map(|c| pred[offset + c].clamp(0.0, 1.0));
+            // The ground truth is the size of the render, but don't trust it with the indexing.
+            let gt_rgb = gt
+                .get_pixel(
+                    (x as u32).min(gt.width() - 1),
+                    (y as u32).min(gt.height() - 1),
+                )
+                .0
+                .map(|c| c as f32 / 255.0);
+
+            render.push(rgb_color(rgb));
+            truth.push(rgb_color(gt_rgb));
+            error.push((0..3).map(|c| (rgb[c] - gt_rgb[c]).abs()).sum::<f32>() / 3.0);
+            gradient.push(
+                grads[offset..offset + channels]
+                    .iter()
+                    .map(|g| g * g)
+                    .sum::<f32>()
+                    .sqrt(),
+            );
+        }
+    }
+
+    ViewImages {
+        iter,
+        path,
+        images: [
+            ColorImage {
+                size,
+                pixels: render,
+            },
+            ColorImage {
+                size,
+                pixels: truth,
+            },
+            heat_image(size, &error),
+            heat_image(size, &gradient),
+        ],
+    }
+}
+
+impl TrainView {
+    pub(crate) fn new() -> Self {
+        Self {
+            shown: None,
+            pending: None,
+        }
+    }
+
+    /// Read back the images of a training step, unless the last step is still being read, or
+    /// the step has no pixel gradients.
+    pub(crate) fn on_step(&mut self, stats: &TrainStepStats<TrainBack>, iter: u32) {
+        if self.pending.is_some() {
+            return;
+        }
+        let Some(grads) = stats.pixel_grads.clone() else {
+            return;
+        };
+        let pred = stats.pred_image.clone().inner();
+        let grads = grads.inner();
+        let gt = Arc::clone(&stats.gt_views.image);
+        let path = stats.gt_views.path.clone();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.pending = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let dims = pred.dims();
+            let pred = pred.into_data_async().await.to_vec::<f32>();
+            let grads = grads.into_data_async().await.to_vec::<f32>();
+            match (pred, grads) {
+                (Ok(pred), Ok(grads)) => {
+                    let _ = sender.send(view_images(iter, path, &gt, dims, &pred, &grads));
+                }
+                (Err(e), _) | (_, Err(e)) => {
+                    log::error!("Failed to read back the training view: {e:?}");
+                }
+            }
+        });
+    }
+
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(receiver) = &self.pending {
+            match receiver.try_recv() {
+                Ok(view) => {
+                    let ctx = ui.ctx().clone();
+                    let mut names = ["render", "truth", "error", "gradient"].into_iter();
+                    let textures = view.images.map(|image| {
+                        let name = names.next().expect("One name per image");
+                        ctx.load_texture(
+                            format!("train_view_{name}"),
+                            image,
+                            TextureOptions::NEAREST,
+                        )
+                    });
+                    self.shown = Some((view.iter, view.path, textures));
+                    self.pending = None;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => ui.ctx().request_repaint(),
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        let Some((iter, path, textures)) = &self.shown else {
+            ui.label(tr("Waiting for the next training step…"));
+            return;
+        };
+        ui.label(format!("{} {iter}: {path}", tr("Step")));
+
+        let labels = [
+            tr("Render"),
+            tr("Ground truth"),
+            tr("Error"),
+            tr("Loss gradient"),
+        ];
+        let width = (ui.available_width() - ui.spacing().item_spacing.x) / 2.0;
+        egui::Grid::new("train_view_grid").show(ui, |ui| {
+            for (row, (labels, textures)) in labels.chunks(2).zip(textures.chunks(2)).enumerate() {
+                for label in labels {
+                    ui.label(*label);
+                }
+                ui.end_row();
+                for texture in textures {
+                    let size = texture.size_vec2();
+                    let response = ui.image((texture.id(), size * (width / size.x)));
+                    if row == 1 {
+                        response.on_hover_text(tr(
+                            "Brighter is larger, scaled to the largest values of this step",
+                        ));
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
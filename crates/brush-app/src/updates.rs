@@ -0,0 +1,401 @@
+//! Check GitHub for newer releases of Brush, as fixes for GPU drivers and kernels ship often.
+//! On desktop, the new release can replace the running binary, to be used after a restart.
+
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::i18n::tr;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/ArthurBrussee/brush/releases/latest";
+
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Target the release archives are built for, see dist-workspace.toml.
+const TARGET: Option<&str> = if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+    Some("x86_64-unknown-linux-gnu")
+} else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+    Some("x86_64-pc-windows-msvc")
+} else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+    Some("aarch64-apple-darwin")
+} else {
+    None
+};
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(Clone)]
+pub(crate) struct Release {
+    pub(crate) version: String,
+    /// Page with the release notes.
+    pub(crate) page_url: String,
+    // Archive with the app for this platform, if there is one.
+    download_url: Option<String>,
+    // The SHA-256 checksum file cargo-dist publishes next to the archive.
+    checksum_url: Option<String>,
+}
+
+/// Major, minor and patch version, ignoring any prefix like "v" and suffix like "-alpha".
+fn parse_version(version: &str) -> Option<[u32; 3]> {
+    let version = version.trim_start_matches('v');
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|p| p.parse().ok());
+    let version = [
+        parts.next()??,
+        parts.next().flatten().unwrap_or(0),
+        parts.next().flatten().unwrap_or(0),
+    ];
+    Some(version)
+}
+
+fn http_client() -> anyhow::Result<reqwest::Client> {
+    use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+
+    // The GitHub API refuses requests without a user agent.
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_static(concat!("brush/", env!("CARGO_PKG_VERSION"))),
+    );
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .build()?)
+}
+
+/// The latest release, if it's newer than the running version.
+async fn newer_release() -> anyhow::Result<Option<Release>> {
+    let response = http_client()?
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let release: GithubRelease = serde_json::from_str(&response)?;
+
+    let latest = parse_version(&release.tag_name)
+        .with_context(|| format!("Unknown version {}", release.tag_name))?;
+    let current = parse_version(CURRENT_VERSION).context("Unknown version of Brush")?;
+    if latest <= current {
+        return Ok(None);
+    }
+
+    let archive = TARGET.and_then(|target| {
+        release.assets.iter().find(|a| {
+            a.name.contains(target) && (a.name.ends_with(".zip") || a.name.ends_with(".tar.gz"))
+        })
+    });
+    let checksum_url = archive.and_then(|archive| {
+        let name = format!("{}.sha256", archive.name);
+        release
+            .assets
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| a.browser_download_url.clone())
+    });
+    Ok(Some(Release {
+        version: release.tag_name,
+        page_url: release.html_url,
+        download_url: archive.map(|a| a.browser_download_url.clone()),
+        checksum_url,
+    }))
+}
+
+/// Find the file named `name` in a .zip or .tar.gz archive.
+#[cfg(not(any(target_family = "wasm", target_os = "android", target_os = "ios")))]
+fn extract_file(archive: &[u8], archive_name: &str, name: &str) -> anyhow::Result<Vec<u8>> {
+    use std::io::{Cursor, Read as _};
+
+    let mut data = vec![];
+    if archive_name.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive))?;
+        let index = (0..zip.len())
+            .find(|&i| {
+                zip.name_for_index(i)
+                    .is_some_and(|n| n.rsplit('/').next() == Some(name))
+            })
+            .with_context(|| format!("No {name} in the release"))?;
+        zip.by_index(index)?.read_to_end(&mut data)?;
+    } else {
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(Cursor::new(archive)));
+        let mut entry = tar
+            .entries()?
+            .filter_map(Result::ok)
+            .find(|e| e.path().is_ok_and(|p| p.file_name() == Some(name.as_ref())))
+            .with_context(|| format!("No {name} in the release"))?;
+        entry.read_to_end(&mut data)?;
+    }
+    Ok(data)
+}
+
+/// Check `archive` against a checksum file, as written by `sha256sum`: the hex digest, followed
+/// by the file name.
+#[cfg(not(any(target_family = "wasm", target_os = "android", target_os = "ios")))]
+fn verify_checksum(archive: &[u8], checksum_file: &str) -> anyhow::Result<()> {
+    use sha2::{Digest, Sha256};
+
+    let expected = checksum_file
+        .split_whitespace()
+        .next()
+        .context("Empty checksum file")?;
+    let actual = format!("{:x}", Sha256::digest(archive));
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!("The download doesn't match its checksum, not installing it");
+    }
+    Ok(())
+}
+
+/// Download the release, check it against its checksum, and replace the running binary with it.
+#[cfg(not(any(target_family = "wasm", target_os = "android", target_os = "ios")))]
+async fn install(download_url: &str, checksum_url: Option<&str>) -> anyhow::Result<()> {
+    let checksum_url = checksum_url.context("The release has no checksum, not installing it")?;
+    let client = http_client()?;
+    let checksum_file = client
+        .get(checksum_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let archive = client
+        .get(download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    verify_checksum(&archive, &checksum_file)?;
+
+    let exe = std::env::current_exe()?;
+    let exe_name = exe
+        .file_name()
+        .context("Unknown binary")?
+        .to_string_lossy()
+        .into_owned();
+    let binary = extract_file(&archive, download_url, &exe_name)?;
+
+    // Write the new binary next to the old one first, so a failed write doesn't leave a broken
+    // binary behind.
+    let new_exe = exe.with_extension("new");
+    std::fs::write(&new_exe, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        std::fs::set_permissions(&new_exe, std::fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows can't replace a running binary, but it can rename it.
+    #[cfg(windows)]
+    {
+        let old_exe = exe.with_extension("old");
+        let _ = std::fs::remove_file(&old_exe);
+        std::fs::rename(&exe, &old_exe)?;
+        if let Err(e) = std::fs::rename(&new_exe, &exe) {
+            // Put the old binary back, so there's still a Brush to start.
+            std::fs::rename(&old_exe, &exe)?;
+            let _ = std::fs::remove_file(&new_exe);
+            return Err(e.into());
+        }
+    }
+    #[cfg(not(windows))]
+    std::fs::rename(new_exe, &exe)?;
+    Ok(())
+}
+
+#[cfg(any(target_family = "wasm", target_os = "android", target_os = "ios"))]
+async fn install(download_url: &str, checksum_url: Option<&str>) -> anyhow::Result<()> {
+    let _ = (download_url, checksum_url);
+    anyhow::bail!("Updating isn't supported on this platform")
+}
+
+enum UpdateState {
+    Idle,
+    Checking,
+    UpToDate,
+    Available(Release),
+    Installing(Release),
+    Installed(Release),
+    Failed(String),
+}
+
+pub(crate) struct Updates {
+    /// Check for a new release when the app starts.
+    pub(crate) check_on_startup: bool,
+    state: UpdateState,
+    // Result of checking or installing in the background.
+    receiver: Option<Receiver<UpdateState>>,
+    // Whether the notification about a new release was closed.
+    dismissed: bool,
+}
+
+impl Updates {
+    /// The web app is always the latest version, and can't be updated.
+    pub(crate) const SUPPORTED: bool = cfg!(not(target_family = "wasm"));
+
+    pub(crate) fn new() -> Self {
+        Self {
+            check_on_startup: false,
+            state: UpdateState::Idle,
+            receiver: None,
+            dismissed: false,
+        }
+    }
+
+    pub(crate) fn check(&mut self, ctx: &egui::Context) {
+        if !Self::SUPPORTED {
+            return;
+        }
+        self.state = UpdateState::Checking;
+        self.dismissed = false;
+        let (sender, receiver) = channel();
+        self.receiver = Some(receiver);
+        let ctx = ctx.clone();
+        tokio_wasm::task::spawn(async move {
+            let state = match newer_release().await {
+                Ok(Some(release)) => UpdateState::Available(release),
+                Ok(None) => UpdateState::UpToDate,
+                Err(e) => {
+                    log::warn!("Failed to check for updates: {e:#}");
+                    UpdateState::Failed(e.to_string())
+                }
+            };
+            let _ = sender.send(state);
+            ctx.request_repaint();
+        });
+    }
+
+    fn install(&mut self, ctx: &egui::Context, release: Release) {
+        let Some(download_url) = release.download_url.clone() else {
+            return;
+        };
+        let checksum_url = release.checksum_url.clone();
+        self.state = UpdateState::Installing(release.clone());
+        let (sender, receiver) = channel();
+        self.receiver = Some(receiver);
+        let ctx = ctx.clone();
+        tokio_wasm::task::spawn(async move {
+            let state = match install(&download_url, checksum_url.as_deref()).await {
+                Ok(()) => UpdateState::Installed(release),
+                Err(e) => {
+                    log::error!("Failed to install update: {e:#}");
+                    UpdateState::Failed(e.to_string())
+                }
+            };
+            let _ = sender.send(state);
+            ctx.request_repaint();
+        });
+    }
+
+    fn receive(&mut self) {
+        if let Some(receiver) = self.receiver.as_ref() {
+            match receiver.try_recv() {
+                Ok(state) => {
+                    self.state = state;
+                    self.receiver = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.receiver = None,
+            }
+        }
+    }
+
+    /// Notification of a new release, until it's dismissed.
+    pub(crate) fn notification_ui(&mut self, ctx: &egui::Context) {
+        self.receive();
+
+        let (release, installing, installed) = match &self.state {
+            UpdateState::Available(release) => (release.clone(), false, false),
+            UpdateState::Installing(release) => (release.clone(), true, false),
+            UpdateState::Installed(release) => (release.clone(), false, true),
+            _ => return,
+        };
+        if self.dismissed {
+            return;
+        }
+
+        egui::TopBottomPanel::top("update_notification").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                if installing {
+                    ui.label(format!("{} {}…", tr("Downloading Brush"), release.version));
+                    ui.spinner();
+                } else if installed {
+                    ui.label(format!(
+                        "{} {}, {}",
+                        tr("Updated to Brush"),
+                        release.version,
+                        tr("restart Brush to use it.")
+                    ));
+                } else {
+                    ui.label(format!(
+                        "{} {}",
+                        tr("A new version is available:"),
+                        release.version
+                    ));
+                    if release.download_url.is_some() && ui.button(tr("Update")).clicked() {
+                        self.install(ctx, release.clone());
+                    }
+                }
+                if ui.link(tr("Release notes")).clicked() {
+                    ctx.open_url(egui::OpenUrl::new_tab(&release.page_url));
+                }
+                if ui.small_button("✖").clicked() {
+                    self.dismissed = true;
+                }
+            });
+        });
+    }
+
+    pub(crate) fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        if !Self::SUPPORTED {
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.checkbox(
+                &mut self.check_on_startup,
+                tr("Check for updates on startup"),
+            );
+            let checking = matches!(self.state, UpdateState::Checking);
+            if ui
+                .add_enabled(!checking, egui::Button::new(tr("Check now")))
+                .clicked()
+            {
+                self.check(ui.ctx());
+            }
+        });
+
+        match &self.state {
+            UpdateState::Checking => {
+                ui.spinner();
+            }
+            UpdateState::UpToDate => {
+                ui.label(format!("{} ({CURRENT_VERSION})", tr("Brush is up to date")));
+            }
+            UpdateState::Failed(err) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            UpdateState::Available(release)
+            | UpdateState::Installing(release)
+            | UpdateState::Installed(release) => {
+                ui.label(format!(
+                    "{} {}",
+                    tr("A new version is available:"),
+                    release.version
+                ));
+            }
+            UpdateState::Idle => {}
+        }
+    }
+}
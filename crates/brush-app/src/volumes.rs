@@ -0,0 +1,252 @@
+//! Editing the volumes exports keep the splats inside of, and drawing their outlines over the
+//! splats.
+
+use brush_dataset::export_volumes::{ExportVolume, ExportVolumes};
+use egui::{Color32, Rect, Stroke};
+use glam::{Quat, Vec3};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::i18n::tr;
+use crate::locale;
+use crate::picking::PickTarget;
+
+const OUTLINE_COLOR: Color32 = Color32::from_rgb(255, 170, 0);
+// Segments of the circles drawn around spheres.
+const CIRCLE_SEGMENTS: usize = 48;
+
+pub(crate) struct VolumeEditor {
+    pub(crate) volumes: ExportVolumes,
+    show_outlines: bool,
+    loaded: Option<std::sync::mpsc::Receiver<ExportVolumes>>,
+}
+
+impl VolumeEditor {
+    pub(crate) fn new() -> Self {
+        Self {
+            volumes: ExportVolumes::default(),
+            show_outlines: true,
+            loaded: None,
+        }
+    }
+
+    fn load(&mut self) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.loaded = Some(receiver);
+        tokio_wasm::task::spawn(async move {
+            let volumes = match rrfd::pick_file().await {
+                Ok(file) => file
+                    .read()
+                    .await
+                    .map_err(Into::into)
+                    .and_then(|data| ExportVolumes::from_json(&data)),
+                Err(e) => Err(e),
+            };
+            match volumes {
+                Ok(volumes) => {
+                    let _ = sender.send(volumes);
+                }
+                Err(e) => log::error!("Failed to load export volumes: {e}"),
+            }
+        });
+    }
+
+    fn save(&self) {
+        let volumes = self.volumes.clone();
+        tokio_wasm::task::spawn(async move {
+            let saved = match (volumes.to_json(), rrfd::save_file("volumes.json").await) {
+                (Ok(data), Ok(file)) => file.write(&data).await.map_err(anyhow::Error::from),
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            };
+            if let Err(e) = saved {
+                log::error!("Failed to save export volumes: {e}");
+            }
+        });
+    }
+
+    /// Pick up volumes loaded from a file.
+    pub(crate) fn receive(&mut self) {
+        if let Some(volumes) = self.loaded.as_ref().and_then(|r| r.try_recv().ok()) {
+            self.volumes = volumes;
+            self.loaded = None;
+        }
+    }
+
+    /// Edit the volumes. New volumes are placed at `focus`, with a size scaled by
+    /// `focus_distance`.
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui, focus: Vec3, focus_distance: f32) {
+        ui.label(tr(
+            "Exports only keep the splats inside these volumes. Without any, all splats are kept.",
+        ));
+
+        let speed = (focus_distance * 0.01).max(1e-3);
+        let mut removed = None;
+        for (i, volume) in self.volumes.volumes.iter_mut().enumerate() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                match volume {
+                    ExportVolume::Box { .. } => ui.strong(tr("Box")),
+                    ExportVolume::Sphere { .. } => ui.strong(tr("Sphere")),
+                };
+                if ui.small_button("✖").on_hover_text(tr("Remove")).clicked() {
+                    removed = Some(i);
+                }
+            });
+            egui::Grid::new(("export_volume", i))
+                .num_columns(2)
+                .show(ui, |ui| match volume {
+                    ExportVolume::Box {
+                        center,
+                        half_extent,
+                        rotation,
+                    } => {
+                        ui.label(tr("Center"));
+                        vec_ui(ui, center, speed);
+                        ui.end_row();
+
+                        ui.label(tr("Size"));
+                        let mut size = *half_extent * 2.0;
+                        vec_ui(ui, &mut size, speed);
+                        *half_extent = size.max(Vec3::ZERO) / 2.0;
+                        ui.end_row();
+
+                        ui.label(tr("Rotation"));
+                        let (x, y, z) = rotation.to_euler(glam::EulerRot::XYZ);
+                        let mut degrees = Vec3::new(x, y, z) * 180.0 / std::f32::consts::PI;
+                        ui.horizontal(|ui| {
+                            for value in [&mut degrees.x, &mut degrees.y, &mut degrees.z] {
+                                ui.add(egui::DragValue::new(value).speed(1.0).suffix("°"));
+                            }
+                        });
+                        let radians = degrees * std::f32::consts::PI / 180.0;
+                        *rotation =
+                            Quat::from_euler(glam::EulerRot::XYZ, radians.x, radians.y, radians.z);
+                        ui.end_row();
+                    }
+                    ExportVolume::Sphere { center, radius } => {
+                        ui.label(tr("Center"));
+                        vec_ui(ui, center, speed);
+                        ui.end_row();
+
+                        ui.label(tr("Radius"));
+                        ui.add(
+                            locale::length_drag(radius)
+                                .speed(speed)
+                                .range(0.0..=f32::MAX),
+                        );
+                        ui.end_row();
+                    }
+                });
+        }
+        if let Some(i) = removed {
+            self.volumes.volumes.remove(i);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let size = focus_distance * 0.25;
+            if ui.button(tr("➕ Box")).clicked() {
+                self.volumes.volumes.push(ExportVolume::Box {
+                    center: focus,
+                    half_extent: Vec3::splat(size),
+                    rotation: Quat::IDENTITY,
+                });
+            }
+            if ui.button(tr("➕ Sphere")).clicked() {
+                self.volumes.volumes.push(ExportVolume::Sphere {
+                    center: focus,
+                    radius: size,
+                });
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Soft edge"))
+                .on_hover_text(tr("Fade out the splats up to this far outside the volumes"));
+            ui.add(
+                locale::length_drag(&mut self.volumes.falloff)
+                    .speed(speed)
+                    .range(0.0..=f32::MAX),
+            );
+        });
+        ui.checkbox(&mut self.show_outlines, tr("Show outlines"));
+
+        ui.horizontal(|ui| {
+            if ui.button(tr("Load…")).clicked() {
+                self.load();
+            }
+            if ui
+                .button(tr("Save…"))
+                .on_hover_text(tr("Save the volumes to use with --export-volumes"))
+                .clicked()
+            {
+                self.save();
+            }
+        });
+    }
+
+    /// Draw the outlines of the volumes over the render of `target` in `rect`.
+    pub(crate) fn draw(&self, ui: &egui::Ui, rect: Rect, target: &PickTarget) {
+        if !self.show_outlines {
+            return;
+        }
+        let painter = ui.painter_at(rect);
+        let stroke = Stroke::new(1.5, OUTLINE_COLOR);
+        let line = |a: Vec3, b: Vec3| {
+            if let (Some(a), Some(b)) = (target.project(a), target.project(b)) {
+                painter.line_segment(
+                    [
+                        rect.min + egui::vec2(a.x, a.y),
+                        rect.min + egui::vec2(b.x, b.y),
+                    ],
+                    stroke,
+                );
+            }
+        };
+
+        for volume in &self.volumes.volumes {
+            match *volume {
+                ExportVolume::Box {
+                    center,
+                    half_extent,
+                    rotation,
+                } => {
+                    let corner = |i: usize| {
+                        let sign = Vec3::new(
+                            if i & 1 == 0 { -1.0 } else { 1.0 },
+                            if i & 2 == 0 { -1.0 } else { 1.0 },
+                            if i & 4 == 0 { -1.0 } else { 1.0 },
+                        );
+                        center + rotation * (sign * half_extent)
+                    };
+                    // Edges connect corners differing in one axis.
+                    for i in 0..8 {
+                        for axis in [1, 2, 4] {
+                            if i & axis == 0 {
+                                line(corner(i), corner(i | axis));
+                            }
+                        }
+                    }
+                }
+                ExportVolume::Sphere { center, radius } => {
+                    for (u, v) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+                        let point = |i: usize| {
+                            let angle = std::f32::consts::TAU * i as f32 / CIRCLE_SEGMENTS as f32;
+                            center + (u * angle.cos() + v * angle.sin()) * radius
+                        };
+                        for i in 0..CIRCLE_SEGMENTS {
+                            line(point(i), point(i + 1));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn vec_ui(ui: &mut egui::Ui, value: &mut Vec3, speed: f32) {
+    ui.horizontal(|ui| {
+        for component in [&mut value.x, &mut value.y, &mut value.z] {
+            ui.add(locale::length_drag(component).speed(speed));
+        }
+    });
+}
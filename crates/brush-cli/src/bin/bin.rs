@@ -0,0 +1,43 @@
+#![recursion_limit = "256"]
+
+//! Headless entry point. This doesn't depend on egui, eframe or any windowing libraries, making
+//! for a slim binary to run on servers.
+
+use brush_cli::Cli;
+use brush_process::process_loop::start_process;
+use clap::{Error, Parser, error::ErrorKind};
+
+fn main() -> Result<(), Error> {
+    let args = Cli::parse();
+
+    brush_cli::logging::init(&args.logging, None, vec![]).map_err(|e| {
+        Error::raw(
+            ErrorKind::InvalidValue,
+            format!("Failed to set up logging: {e:#}\n"),
+        )
+    })?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to initialize tokio runtime");
+
+    if let Some(command) = args.command {
+        return runtime.block_on(brush_cli::run_command(command));
+    }
+
+    let Some(source) = args.source else {
+        return Err(Error::raw(
+            ErrorKind::MissingRequiredArgument,
+            "A source to train on must be provided, this build has no viewer.\n",
+        ));
+    };
+
+    runtime.block_on(async {
+        let device = brush_render::burn_init_setup().await;
+        let process = start_process(source, args.process, device.clone());
+        brush_cli::run_headless(process, device, args.log_format).await;
+    });
+
+    Ok(())
+}
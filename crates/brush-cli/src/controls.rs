@@ -0,0 +1,100 @@
+use std::io::BufRead;
+
+use brush_process::process_loop::ControlMessage;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+pub const COMMAND_HELP: &str =
+    "commands: pause, resume, step [N], export, export-every N (SIGUSR1 also exports)";
+
+/// Parse a control command as typed on stdin, eg. `step 100`.
+pub fn parse_command(line: &str) -> Result<ControlMessage, String> {
+    let mut tokens = line.split_whitespace();
+    let command = tokens.next().unwrap_or_default();
+    let count = tokens.next().map(str::parse::<u32>).transpose();
+    let Ok(count) = count else {
+        return Err(format!("Invalid number in '{line}'"));
+    };
+
+    match (command, count) {
+        ("pause", None) => Ok(ControlMessage::Paused(true)),
+        ("resume", None) => Ok(ControlMessage::Paused(false)),
+        ("step", count) => Ok(ControlMessage::Step(count.unwrap_or(1))),
+        ("export", None) => Ok(ControlMessage::Export),
+        ("export-every", Some(every)) => Ok(ControlMessage::ExportEvery(every)),
+        _ => Err(format!("Unknown command '{line}', {COMMAND_HELP}")),
+    }
+}
+
+/// Short description of what a control message does, to confirm it to the user.
+pub fn describe(control: &ControlMessage) -> String {
+    match control {
+        ControlMessage::Paused(true) => "Paused training".to_owned(),
+        ControlMessage::Paused(false) => "Resumed training".to_owned(),
+        ControlMessage::Step(steps) => format!("Training {steps} steps, then pausing"),
+        ControlMessage::Export => "Exporting after the next step".to_owned(),
+        ControlMessage::ExportEvery(every) => format!("Exporting every {every} steps"),
+        ControlMessage::Suspend(true) => "Suspended training".to_owned(),
+        ControlMessage::Suspend(false) => "Resumed suspended training".to_owned(),
+        ControlMessage::Throttle(Some(delay)) => format!("Waiting {delay:?} after every step"),
+        ControlMessage::Throttle(None) => "Training at full speed".to_owned(),
+        ControlMessage::RateLimit(limit) => format!("Limiting training to {limit:?}"),
+        ControlMessage::TrainConfig(live) => format!("Changed the training config to {live:?}"),
+        ControlMessage::RecordPruned(true) => "Recording the pruned splats".to_owned(),
+        ControlMessage::RecordPruned(false) => "Stopped recording the pruned splats".to_owned(),
+        ControlMessage::RecordPixelGrads(true) => "Recording the pixel gradients".to_owned(),
+        ControlMessage::RecordPixelGrads(false) => {
+            "Stopped recording the pixel gradients".to_owned()
+        }
+    }
+}
+
+fn forward_signals(sender: UnboundedSender<Result<ControlMessage, String>>) {
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let Ok(mut export_signal) = signal(SignalKind::user_defined1()) else {
+            log::warn!("Failed to listen for SIGUSR1");
+            return;
+        };
+
+        while export_signal.recv().await.is_some() {
+            if sender.send(Ok(ControlMessage::Export)).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Signals are unix only.
+    #[cfg(not(unix))]
+    drop(sender);
+}
+
+/// Listen for control commands for a running process.
+///
+/// SIGUSR1 always triggers an export. When `read_stdin` is set, commands are also read from stdin,
+/// one per line, see [`parse_command`]. Lines that fail to parse are passed on as an error.
+pub fn spawn_controls(read_stdin: bool) -> UnboundedReceiver<Result<ControlMessage, String>> {
+    let (sender, receiver) = unbounded_channel();
+
+    if read_stdin {
+        let sender = sender.clone();
+        // Stdin reads are blocking, so read from a separate thread.
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if sender.send(parse_command(&line)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    forward_signals(sender);
+    receiver
+}
@@ -0,0 +1,97 @@
+//! Converting splats between file formats. This runs on the CPU, so it works on machines without
+//! a GPU, eg. as a step in a pipeline.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use brush_dataset::splat_export::{ExportFormat, export_splats};
+use brush_dataset::splat_import::load_splat_file;
+use brush_render::gaussian_splats::Splats;
+use burn::backend::NdArray;
+use clap::Args;
+use tokio::io::AsyncRead;
+
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    /// Splats to convert: a ply file as written by Brush or other trainers, a .splat or an .spz
+    /// file. Compressed plys can't be read.
+    pub input: PathBuf,
+
+    /// File to write the splats to. The format is picked from its extension (.ply,
+    /// .compressed.ply, .splat, .spz, .points.ply or .las), unless --format is given.
+    pub output: PathBuf,
+
+    /// Format to write, instead of the one matching the extension of the output.
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// Write the quantized variant of the format, ie. a compressed ply instead of a ply.
+    #[arg(long)]
+    pub quantize: bool,
+
+    /// Drop the spherical harmonics above this degree. 0 only keeps the base colors, which makes
+    /// for much smaller files. Higher than the degree of the input is an error.
+    #[arg(long, value_name = "DEGREE")]
+    pub sh_degree: Option<u32>,
+}
+
+/// Read the splats of the file `name` (a ply, .splat or .spz) on the CPU, and how many frames it
+/// has. Animated splats are read at their first frame.
+pub(crate) async fn read_splats(
+    reader: impl AsyncRead + Unpin + 'static,
+    name: &str,
+) -> anyhow::Result<(Splats<NdArray>, u32)> {
+    let (message, frames) = load_splat_file::<_, NdArray>(reader, name, Default::default())
+        .await
+        .with_context(|| format!("Failed to load {name}"))?;
+    if let Some(summary) = message.meta.report.summary() {
+        log::warn!("{name}: {summary}");
+    }
+    Ok((message.splats, frames))
+}
+
+/// Read the splats of `args.input`, and write them to `args.output`.
+pub async fn convert(args: ConvertArgs) -> anyhow::Result<()> {
+    let format = match args.format {
+        Some(format) => format,
+        None => ExportFormat::from_path(&args.output.to_string_lossy()).with_context(|| {
+            format!(
+                "Can't tell which format to write to {}, pass --format",
+                args.output.display()
+            )
+        })?,
+    };
+    let format = if args.quantize {
+        format.quantized()
+    } else {
+        format
+    };
+
+    let data = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let name = args.input.display().to_string();
+    let (splats, frames) = read_splats(std::io::Cursor::new(data), &name).await?;
+    if frames > 1 {
+        log::warn!("{name} is animated, only its first frame is converted");
+    }
+
+    let splats = match args.sh_degree {
+        Some(degree) if degree > splats.sh_degree() => anyhow::bail!(
+            "{name} has spherical harmonics up to degree {}, they can't be raised to {degree}",
+            splats.sh_degree()
+        ),
+        Some(degree) => splats.with_sh_degree(degree),
+        None => splats,
+    };
+    let count = splats.num_splats();
+    let bytes = export_splats(splats, format).await?;
+    std::fs::write(&args.output, bytes)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    println!(
+        "Wrote {count} splats to {} ({})",
+        args.output.display(),
+        format.label()
+    );
+    Ok(())
+}
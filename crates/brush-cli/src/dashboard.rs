@@ -0,0 +1,484 @@
+use std::{collections::VecDeque, time::Duration};
+
+use brush_process::process_loop::{ControlMessage, ProcessMessage, RunningProcess, TrainProgress};
+use burn_cubecl::cubecl::Runtime;
+use burn_wgpu::{WgpuDevice, WgpuRuntime};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Gauge, Paragraph, Sparkline},
+};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use crate::controls::{describe, spawn_controls};
+
+// Number of samples kept for each chart.
+const HISTORY_LEN: usize = 512;
+// Number of lines kept in the event log.
+const LOG_LEN: usize = 64;
+// How often to redraw when no messages are coming in.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+struct History {
+    values: VecDeque<f64>,
+}
+
+impl History {
+    fn push(&mut self, value: f64) {
+        if self.values.len() == HISTORY_LEN {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    fn last(&self) -> Option<f64> {
+        self.values.back().copied()
+    }
+
+    /// Sparklines only show integers, so rescale the most recent `width` values to 1-101.
+    fn bars(&self, width: usize) -> Vec<u64> {
+        let start = self.values.len().saturating_sub(width);
+        let recent = self.values.range(start..);
+        let min = recent.clone().copied().fold(f64::INFINITY, f64::min);
+        let max = recent.clone().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = (max - min).max(1e-12);
+        recent
+            .map(|v| 1 + ((v - min) / range * 100.0) as u64)
+            .collect()
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{size:.2} {}", UNITS[unit])
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+struct Dashboard {
+    status: String,
+    total_steps: u32,
+    iter: u32,
+    paused: bool,
+    finished: bool,
+    export_every: u32,
+    // Digits typed before a command, eg. '100s' steps 100 times.
+    count: String,
+
+    progress: TrainProgress,
+    last_eval: Option<(u32, f32, f32)>,
+
+    loss: History,
+    psnr: History,
+    splats: History,
+    memory: History,
+
+    log: VecDeque<String>,
+}
+
+impl Dashboard {
+    fn new(total_steps: u32, refine_stop_iter: u32, export_every: u32) -> Self {
+        Self {
+            status: "Starting up".to_owned(),
+            total_steps,
+            iter: 0,
+            paused: false,
+            finished: false,
+            export_every,
+            count: String::new(),
+            progress: TrainProgress::new(total_steps, refine_stop_iter),
+            last_eval: None,
+            loss: History::default(),
+            psnr: History::default(),
+            splats: History::default(),
+            memory: History::default(),
+            log: VecDeque::new(),
+        }
+    }
+
+    fn log(&mut self, line: impl Into<String>) {
+        if self.log.len() == LOG_LEN {
+            self.log.pop_front();
+        }
+        self.log.push_back(line.into());
+    }
+
+    fn finish(&mut self, status: &str) {
+        self.finished = true;
+        self.status = status.to_owned();
+        self.log(format!("{status}, press q to exit"));
+    }
+
+    fn control(&mut self, process: &RunningProcess, control: ControlMessage) {
+        match control {
+            ControlMessage::Paused(paused) => self.paused = paused,
+            ControlMessage::Step(_) => self.paused = true,
+            ControlMessage::ExportEvery(every) => self.export_every = every,
+            ControlMessage::Export
+            | ControlMessage::Suspend(_)
+            | ControlMessage::Throttle(_)
+            | ControlMessage::RateLimit(_)
+            | ControlMessage::TrainConfig(_)
+            | ControlMessage::RecordPruned(_)
+            | ControlMessage::RecordPixelGrads(_) => {}
+        }
+        self.log(describe(&control));
+        let _ = process.control.send(control);
+    }
+
+    /// Map a key press to a control message.
+    fn key_control(&mut self, code: KeyCode) -> Option<ControlMessage> {
+        if let KeyCode::Char(c @ '0'..='9') = code {
+            self.count.push(c);
+            return None;
+        }
+
+        let count = self.count.parse::<u32>().ok();
+        self.count.clear();
+
+        match code {
+            KeyCode::Char('p' | ' ') => Some(ControlMessage::Paused(!self.paused)),
+            KeyCode::Char('s') => Some(ControlMessage::Step(count.unwrap_or(1))),
+            KeyCode::Char('e') => Some(ControlMessage::Export),
+            KeyCode::Char('+') => Some(ControlMessage::ExportEvery(
+                count.unwrap_or(self.export_every.saturating_mul(2)),
+            )),
+            KeyCode::Char('-') => Some(ControlMessage::ExportEvery(
+                count.unwrap_or(self.export_every / 2).max(1),
+            )),
+            _ => None,
+        }
+    }
+
+    async fn on_message(&mut self, message: &ProcessMessage, device: &WgpuDevice) {
+        match message {
+            ProcessMessage::NewSource => {
+                self.status = "Starting process".to_owned();
+            }
+            ProcessMessage::SourceOpened { .. } => {}
+            ProcessMessage::StartLoading { training } => {
+                if *training {
+                    self.status = "Loading data".to_owned();
+                } else {
+                    self.log("Only training is supported in the CLI (try passing --with-viewer to view a splat)");
+                    self.finish("Nothing to train");
+                }
+            }
+            ProcessMessage::Error(error) => {
+                self.log(format!("Error: {error:?}"));
+                self.finish("Failed");
+            }
+            ProcessMessage::Warning(warning) => self.log(format!("Warning: {warning}")),
+            ProcessMessage::ViewSplats { .. } => {}
+            ProcessMessage::Dataset { data } => {
+                let skipped = data
+                    .skipped_summary()
+                    .map_or(String::new(), |s| format!(", {s}"));
+                self.status = format!(
+                    "Loading data, {} training, {} eval views{skipped}",
+                    data.train.views.len(),
+                    data.eval.as_ref().map_or(0, |v| v.views.len()),
+                );
+            }
+            ProcessMessage::SceneScale { scale, estimated } => {
+                let source = if *estimated { "estimated" } else { "set" };
+                self.log(format!("Scene scale {scale:.3} ({source})"));
+            }
+            ProcessMessage::DoneLoading { .. } => {
+                self.status = "Dataset loaded".to_owned();
+                self.log(self.status.clone());
+            }
+            ProcessMessage::TrainStep {
+                splats,
+                stats,
+                iter,
+                timestamp,
+            } => {
+                self.status = "Training".to_owned();
+
+                self.progress.update(*timestamp, *iter, splats.num_splats());
+                self.iter = *iter;
+
+                let loss = stats.loss.clone().into_scalar_async().await;
+                self.loss.push(loss as f64);
+                self.splats.push(splats.num_splats() as f64);
+
+                let memory = WgpuRuntime::client(device).memory_usage();
+                self.memory.push(memory.bytes_in_use as f64);
+            }
+            ProcessMessage::RefineStep { stats, iter } => {
+                self.log(format!("Step {iter}: refine, {stats}"));
+            }
+            ProcessMessage::EvalResult {
+                iter,
+                avg_psnr,
+                psnr_ci,
+                avg_ssim,
+                ssim_ci,
+            } => {
+                self.psnr.push(*avg_psnr as f64);
+                self.last_eval = Some((*iter, *avg_psnr, *avg_ssim));
+                self.log(format!(
+                    "Step {iter}: eval {avg_psnr:.2} ± {psnr_ci:.2} PSNR, \
+                     {avg_ssim:.3} ± {ssim_ci:.3} SSIM"
+                ));
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame<'_>) {
+        let [header, progress, charts, log, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let status = if self.paused && !self.finished {
+            "Paused"
+        } else {
+            &self.status
+        };
+        let eta = self
+            .progress
+            .eta()
+            .filter(|_| !self.finished)
+            .map_or("--".to_owned(), |eta| format_duration(eta.as_secs_f64()));
+        let eval = self.last_eval.map_or("--".to_owned(), |(_, psnr, ssim)| {
+            format!("{psnr:.2} PSNR, {ssim:.3} SSIM")
+        });
+
+        let label = Style::default().fg(Color::DarkGray);
+        let header_line = Line::from(vec![
+            Span::styled(status.to_owned(), Style::default().fg(Color::Cyan)),
+            Span::styled("   steps/s ", label),
+            Span::raw(format!("{:.1}", self.progress.iter_per_s())),
+            Span::styled("   ETA ", label),
+            Span::raw(eta),
+            Span::styled("   last eval ", label),
+            Span::raw(eval),
+        ]);
+        frame.render_widget(
+            Paragraph::new(header_line).block(Block::bordered().title(" Brush ")),
+            header,
+        );
+
+        let ratio = if self.total_steps > 0 {
+            (self.iter as f64 / self.total_steps as f64).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" Progress "))
+                .gauge_style(Style::default().fg(Color::Blue))
+                .ratio(ratio)
+                .label(format!("{}/{}", self.iter, self.total_steps)),
+            progress,
+        );
+
+        let [top, bottom] = Layout::vertical([Constraint::Fill(1); 2]).areas(charts);
+        let [loss_area, psnr_area] = Layout::horizontal([Constraint::Fill(1); 2]).areas(top);
+        let [splats_area, memory_area] = Layout::horizontal([Constraint::Fill(1); 2]).areas(bottom);
+
+        let current = |history: &History, format: &dyn Fn(f64) -> String| {
+            history.last().map_or("--".to_owned(), format)
+        };
+
+        draw_history(
+            frame,
+            loss_area,
+            format!(" Loss {} ", current(&self.loss, &|v| format!("{v:.4}"))),
+            &self.loss,
+            Color::Yellow,
+        );
+        draw_history(
+            frame,
+            psnr_area,
+            format!(
+                " Eval PSNR {} ",
+                current(&self.psnr, &|v| format!("{v:.2}"))
+            ),
+            &self.psnr,
+            Color::Green,
+        );
+        draw_history(
+            frame,
+            splats_area,
+            format!(
+                " Splats {}{} ",
+                current(&self.splats, &|v| format!("{v}")),
+                self.progress
+                    .projected_splats()
+                    .map_or(String::new(), |n| format!(", projected {n}")),
+            ),
+            &self.splats,
+            Color::Magenta,
+        );
+        draw_history(
+            frame,
+            memory_area,
+            format!(" GPU memory {} ", current(&self.memory, &format_bytes)),
+            &self.memory,
+            Color::Cyan,
+        );
+
+        let num_lines = log.height.saturating_sub(2) as usize;
+        let lines: Vec<Line<'_>> = self
+            .log
+            .iter()
+            .skip(self.log.len().saturating_sub(num_lines))
+            .map(|l| Line::raw(l.as_str()))
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Events ")),
+            log,
+        );
+
+        frame.render_widget(
+            Paragraph::new(Line::styled(
+                format!(
+                    " p/space pause or resume · [N]s step N · e export now · +/- export every {} steps · q stop {}",
+                    self.export_every, self.count
+                ),
+                label,
+            )),
+            footer,
+        );
+    }
+}
+
+fn draw_history(frame: &mut Frame<'_>, area: Rect, title: String, history: &History, color: Color) {
+    let data = history.bars(area.width.saturating_sub(2) as usize);
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(title))
+            .data(data)
+            .max(101)
+            .style(Style::default().fg(color)),
+        area,
+    );
+}
+
+/// Read key presses on a separate thread, as crossterm only has blocking reads.
+fn spawn_key_reader() -> UnboundedReceiver<KeyEvent> {
+    let (sender, receiver) = unbounded_channel();
+
+    std::thread::spawn(move || {
+        loop {
+            match event::poll(REDRAW_INTERVAL) {
+                Ok(true) => {
+                    if let Ok(Event::Key(key)) = event::read() {
+                        if sender.send(key).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Ok(false) => {
+                    if sender.is_closed() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    receiver
+}
+
+async fn run(
+    terminal: &mut DefaultTerminal,
+    process: RunningProcess,
+    device: &WgpuDevice,
+) -> std::io::Result<()> {
+    let mut process = process;
+    let mut dashboard = Dashboard::new(
+        process.start_args.train_config.total_steps,
+        process.start_args.train_config.refine_stop_iter,
+        process.start_args.process_config.export_every,
+    );
+    let mut keys = spawn_key_reader();
+    // Stdin is used for key presses, but still listen for signals.
+    let mut controls = spawn_controls(false);
+
+    if cfg!(debug_assertions) {
+        dashboard.log("Running in debug mode, compile with --release for best performance");
+    }
+
+    loop {
+        while let Ok(key) = keys.try_recv() {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                // The terminal is in raw mode, so ctrl+c doesn't send a signal.
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(());
+                }
+                code if !dashboard.finished => {
+                    if let Some(control) = dashboard.key_control(code) {
+                        dashboard.control(&process, control);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        while let Ok(control) = controls.try_recv() {
+            match control {
+                Ok(control) if !dashboard.finished => dashboard.control(&process, control),
+                Ok(_) => {}
+                Err(error) => dashboard.log(error),
+            }
+        }
+
+        terminal.draw(|frame| dashboard.draw(frame))?;
+
+        if dashboard.finished {
+            tokio::time::sleep(REDRAW_INTERVAL).await;
+            continue;
+        }
+
+        // Wait for a message, but wake up regularly to handle input.
+        match tokio::time::timeout(REDRAW_INTERVAL, process.messages.recv()).await {
+            Ok(Some(message)) => dashboard.on_message(&message, device).await,
+            Ok(None) => dashboard.finish("Training finished"),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Show a full screen dashboard of the training progress, with keyboard controls
+/// to pause, export or stop the run.
+pub async fn process_dashboard(process: RunningProcess, device: WgpuDevice) {
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, process, &device).await;
+    ratatui::restore();
+
+    if let Err(e) = result {
+        log::error!("Failed to draw dashboard: {e}");
+    }
+}
@@ -0,0 +1,187 @@
+//! Summaries of splat files and datasets, eg. to debug files attached to issue reports.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use anyhow::Context;
+use brush_dataset::brush_vfs::BrushVfs;
+use brush_dataset::splat_import::ply_dialect;
+use brush_dataset::{Dataset, LoadDataseConfig, ModelConfig, load_dataset};
+use brush_process::data_source::DataSource;
+use burn::backend::NdArray;
+use clap::Args;
+use serde_json::{Value, json};
+use tokio_stream::StreamExt;
+
+use crate::convert::read_splats;
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// Splat file (ply, .splat or .spz), dataset (directory or zip, tar.gz or 7z archive) or URL
+    /// to inspect.
+    #[arg(value_name = "PATH_OR_URL")]
+    pub source: DataSource,
+
+    /// Print the summary as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+// Extensions of the splat files that can be summarized.
+const SPLAT_EXTENSIONS: [&str; 3] = ["ply", "splat", "spz"];
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+async fn splat_info(vfs: &mut BrushVfs, path: &Path) -> anyhow::Result<Value> {
+    let name = path.display().to_string();
+    let dialect = match extension(path).as_str() {
+        "splat" => "Splat file (antimatter15)".to_owned(),
+        "spz" => "Spz file (Niantic)".to_owned(),
+        _ => {
+            let dialect = ply_dialect(vfs.open_path(path).await?).await?;
+            // The splats of compressed plys can't be read, but the header still tells a bit.
+            if dialect.compressed {
+                return Ok(json!({
+                    "file": name,
+                    "dialect": dialect.description,
+                    "splats": dialect.vertices,
+                }));
+            }
+            dialect.description
+        }
+    };
+    let (splats, frames) = read_splats(vfs.open_path(path).await?, &name).await?;
+
+    let means: Vec<f32> = splats
+        .means
+        .val()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(|e| anyhow::anyhow!("Failed to read splats {e:?}"))?;
+    let (min, max) = means.chunks_exact(3).fold(
+        ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+        |(min, max), mean| {
+            (
+                [0, 1, 2].map(|i| min[i].min(mean[i])),
+                [0, 1, 2].map(|i| max[i].max(mean[i])),
+            )
+        },
+    );
+
+    Ok(json!({
+        "file": name,
+        "dialect": dialect,
+        "splats": splats.num_splats(),
+        "sh_degree": splats.sh_degree(),
+        "frames": frames,
+        "bounds": { "min": min, "max": max },
+    }))
+}
+
+async fn dataset_info(vfs: BrushVfs) -> anyhow::Result<Value> {
+    let (mut splat_stream, mut data_stream) = load_dataset::<NdArray>(
+        vfs,
+        &LoadDataseConfig::new(),
+        &ModelConfig::new(),
+        &Default::default(),
+    )
+    .await?;
+    let mut dataset = Dataset::empty();
+    while let Some(d) = data_stream.next().await {
+        dataset = d.context("Failed to load the dataset")?;
+    }
+    let mut initial_points = 0;
+    while let Some(message) = splat_stream.next().await {
+        initial_points = message?.splats.num_splats();
+    }
+
+    let eval_views = dataset.eval.as_ref().map_or(&[][..], |e| &e.views[..]);
+    let views: Vec<_> = dataset.train.views.iter().chain(eval_views).collect();
+    let cameras: HashSet<_> = views.iter().map(|v| v.camera_group).collect();
+    let sparse_points: HashSet<_> = views
+        .iter()
+        .flat_map(|v| v.sparse_points.iter())
+        .map(|(_, position)| position.to_array().map(f32::to_bits))
+        .collect();
+    let mut resolutions = BTreeMap::new();
+    for view in &views {
+        let size = format!("{}x{}", view.image.width(), view.image.height());
+        *resolutions.entry(size).or_insert(0) += 1;
+    }
+
+    Ok(json!({
+        "train_views": dataset.train.views.len(),
+        "eval_views": eval_views.len(),
+        "cameras": cameras.len(),
+        "resolutions": resolutions,
+        "sparse_points": sparse_points.len(),
+        "initial_points": initial_points,
+        "extent": dataset.train.estimate_extent(),
+        "skipped": dataset.skipped_summary(),
+        "warnings": dataset.warnings(),
+        "hash": dataset.content_hash(),
+    }))
+}
+
+fn print_value(key: &str, value: &Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    let mut label = key.replace('_', " ");
+    if let Some(first) = label.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    match value {
+        Value::Null => {}
+        Value::Array(items) if items.is_empty() => {}
+        Value::Object(fields) => {
+            println!("{pad}{label}:");
+            for (key, value) in fields {
+                print_value(key, value, indent + 1);
+            }
+        }
+        Value::Array(items) if !items.iter().all(Value::is_number) => {
+            println!("{pad}{label}:");
+            for item in items {
+                match item {
+                    Value::String(text) => println!("{pad}  - {text}"),
+                    item => print_value("-", item, indent + 1),
+                }
+            }
+        }
+        Value::String(text) => println!("{pad}{label}: {text}"),
+        value => println!("{pad}{label}: {value}"),
+    }
+}
+
+/// Print a summary of the splats or dataset of `args.source`.
+pub async fn info(args: InfoArgs) -> anyhow::Result<()> {
+    let mut vfs = args.source.open().await?.vfs;
+    let paths: Vec<_> = vfs.file_names().collect();
+
+    let splat_files = !paths.is_empty()
+        && paths
+            .iter()
+            .all(|p| SPLAT_EXTENSIONS.contains(&extension(p).as_str()));
+    let summary = if splat_files {
+        let mut files = vec![];
+        for path in &paths {
+            files.push(splat_info(&mut vfs, path).await?);
+        }
+        json!({ "splat_files": files })
+    } else {
+        json!({ "dataset": dataset_info(vfs).await? })
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else if let Value::Object(fields) = &summary {
+        for (key, value) in fields {
+            print_value(key, value, 0);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use brush_process::process_loop::{ProcessMessage, RunningProcess, TrainProgress};
+use serde_json::{Value, json};
+use web_time::Instant;
+
+use crate::controls::{describe, spawn_controls};
+
+fn emit(event: &Value) {
+    println!("{event}");
+}
+
+/// Report progress as JSON, one event per line, for consumption by other tools.
+///
+/// Every event has a `type` field, one of `loading`, `warning`, `dataset`, `scene_scale`,
+/// `train_step`, `refine`, `eval`, `control`, `control_error`, `error` or `done`. The `dataset`
+/// event carries a `hash` of the views, and `eval` events repeat it as `dataset_hash`, to tell
+/// which data the results are for. Control commands are read from stdin.
+pub async fn process_json(process: RunningProcess) {
+    let mut process = process;
+
+    let total_steps = process.start_args.train_config.total_steps;
+    let mut train_start: Option<Instant> = None;
+    let mut progress = TrainProgress::new(
+        total_steps,
+        process.start_args.train_config.refine_stop_iter,
+    );
+
+    let mut controls = spawn_controls(true);
+    // Hash of the dataset, to tell which data eval results are for.
+    let mut dataset_hash = None;
+
+    loop {
+        let msg = tokio::select! {
+            msg = process.messages.recv() => msg,
+            Some(control) = controls.recv() => {
+                match control {
+                    Ok(control) => {
+                        emit(&json!({ "type": "control", "message": describe(&control) }));
+                        let _ = process.control.send(control);
+                    }
+                    Err(error) => {
+                        emit(&json!({ "type": "control_error", "message": error }));
+                    }
+                }
+                continue;
+            }
+        };
+
+        let Some(msg) = msg else {
+            break;
+        };
+
+        match msg {
+            ProcessMessage::NewSource
+            | ProcessMessage::SourceOpened { .. }
+            | ProcessMessage::ViewSplats { .. } => {}
+            ProcessMessage::StartLoading { training } => {
+                if !training {
+                    emit(&json!({
+                        "type": "error",
+                        "message": "Only training is supported in the CLI",
+                    }));
+                    break;
+                }
+                emit(&json!({ "type": "loading" }));
+            }
+            ProcessMessage::Error(error) => {
+                emit(&json!({ "type": "error", "message": format!("{error:?}") }));
+                break;
+            }
+            ProcessMessage::Warning(warning) => {
+                emit(&json!({ "type": "warning", "message": warning }));
+            }
+            ProcessMessage::Dataset { data } => {
+                let hash = data.content_hash();
+                emit(&json!({
+                    "type": "dataset",
+                    "hash": hash,
+                    "train_views": data.train.views.len(),
+                    "eval_views": data.eval.as_ref().map_or(0, |v| v.views.len()),
+                    "skipped_views": data
+                        .preprocess
+                        .skipped
+                        .iter()
+                        .map(|s| json!({ "path": s.path, "reason": s.reason.describe() }))
+                        .collect::<Vec<_>>(),
+                }));
+                dataset_hash = Some(hash);
+            }
+            ProcessMessage::SceneScale { scale, estimated } => {
+                emit(&json!({ "type": "scene_scale", "scale": scale, "estimated": estimated }));
+            }
+            ProcessMessage::DoneLoading { .. } => {}
+            ProcessMessage::TrainStep {
+                splats,
+                stats,
+                iter,
+                timestamp,
+            } => {
+                let start_time = *train_start.get_or_insert(timestamp);
+                let elapsed = timestamp.duration_since(start_time).as_secs_f64();
+                progress.update(timestamp, iter, splats.num_splats());
+
+                let loss = stats.loss.clone().into_scalar_async().await;
+
+                emit(&json!({
+                    "type": "train_step",
+                    "step": iter,
+                    "total_steps": total_steps,
+                    "loss": loss,
+                    "splats": splats.num_splats(),
+                    "sh_degree": splats.sh_degree(),
+                    "elapsed_secs": elapsed,
+                    "steps_per_sec": progress.iter_per_s(),
+                    "eta_secs": progress.eta().map(|eta| eta.as_secs_f64()),
+                    "projected_splats": progress.projected_splats(),
+                }));
+            }
+            ProcessMessage::RefineStep { stats, iter } => {
+                emit(&json!({
+                    "type": "refine",
+                    "step": iter,
+                    "num_split": stats.num_split,
+                    "num_cloned": stats.num_cloned,
+                    "num_transparent_pruned": stats.num_transparent_pruned,
+                    "num_scale_pruned": stats.num_scale_pruned,
+                    "num_unseen_pruned": stats.num_unseen_pruned,
+                    "opacity_reset": stats.opacity_reset,
+                    "splats": stats.num_splats,
+                }));
+            }
+            ProcessMessage::EvalResult {
+                iter,
+                avg_psnr,
+                psnr_ci,
+                avg_ssim,
+                ssim_ci,
+            } => {
+                emit(&json!({
+                    "type": "eval",
+                    "step": iter,
+                    "psnr": avg_psnr,
+                    "psnr_ci": psnr_ci,
+                    "ssim": avg_ssim,
+                    "ssim_ci": ssim_ci,
+                    "dataset_hash": dataset_hash,
+                }));
+            }
+        }
+    }
+
+    emit(&json!({ "type": "done" }));
+}
@@ -1,9 +1,42 @@
 #![recursion_limit = "256"]
 
+pub mod controls;
+pub mod convert;
+pub mod dashboard;
+pub mod info;
+pub mod json;
+pub mod logging;
 pub mod ui;
 
-use brush_process::{data_source::DataSource, process_loop::ProcessArgs};
-use clap::{Error, Parser, builder::ArgPredicate, error::ErrorKind};
+use brush_process::{
+    data_source::DataSource,
+    process_loop::{ProcessArgs, RunningProcess},
+};
+use burn_wgpu::WgpuDevice;
+use clap::{Error, Parser, Subcommand, ValueEnum, builder::ArgPredicate, error::ErrorKind};
+use convert::ConvertArgs;
+use info::InfoArgs;
+use logging::LogConfig;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Interactive progress bars.
+    Pretty,
+    /// One JSON event per line on stdout.
+    Json,
+    /// Full screen dashboard with live charts and keyboard controls.
+    Dashboard,
+}
+
+/// Tools that run instead of training or viewing.
+#[derive(Subcommand)]
+pub enum Command {
+    /// Convert splats to another file format, without needing a GPU.
+    Convert(ConvertArgs),
+    /// Summarize a splat file or dataset: splat counts, bounds and file dialect, or views,
+    /// cameras and points.
+    Info(InfoArgs),
+}
 
 #[derive(Parser)]
 #[command(
@@ -13,6 +46,9 @@ use clap::{Error, Parser, builder::ArgPredicate, error::ErrorKind};
     about = "Brush - universal splats"
 )]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Source to load from (path or URL).
     #[arg(value_name = "PATH_OR_URL")]
     pub source: Option<DataSource>,
@@ -25,8 +61,15 @@ pub struct Cli {
     )]
     pub with_viewer: bool,
 
+    /// How to report progress when running without a viewer.
+    #[arg(long, value_enum, default_value = "pretty")]
+    pub log_format: LogFormat,
+
     #[clap(flatten)]
     pub process: ProcessArgs,
+
+    #[clap(flatten)]
+    pub logging: LogConfig,
 }
 
 impl Cli {
@@ -40,3 +83,21 @@ impl Cli {
         Ok(self)
     }
 }
+
+/// Run a tool from the command line.
+pub async fn run_command(command: Command) -> Result<(), Error> {
+    let result = match command {
+        Command::Convert(args) => convert::convert(args).await,
+        Command::Info(args) => info::info(args).await,
+    };
+    result.map_err(|e| Error::raw(ErrorKind::Io, format!("{e:#}\n")))
+}
+
+/// Report the progress of a process on the terminal until it finishes.
+pub async fn run_headless(process: RunningProcess, device: WgpuDevice, log_format: LogFormat) {
+    match log_format {
+        LogFormat::Pretty => ui::process_ui(process).await,
+        LogFormat::Json => json::process_json(process).await,
+        LogFormat::Dashboard => dashboard::process_dashboard(process, device).await,
+    }
+}
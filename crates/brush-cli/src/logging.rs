@@ -0,0 +1,164 @@
+//! Logging through a tracing subscriber, shared by the headless and desktop binaries. Log levels
+//! can be set per module, and logs can be written to daily files, so they can be attached to
+//! issue reports.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Context as _;
+use clap::Args;
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry,
+    fmt::{self, format::Writer, time::FormatTime},
+    layer::SubscriberExt as _,
+    reload,
+    util::SubscriberInitExt as _,
+};
+
+/// Levels of the recorded logs, unless set with `--log-filter` or `RUST_LOG`. Wgpu and naga
+/// are very chatty at the info level.
+pub const DEFAULT_FILTER: &str = "info,wgpu_core=warn,wgpu_hal=warn,naga=warn";
+
+// Number of daily log files to keep.
+const MAX_LOG_FILES: usize = 7;
+
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+#[derive(Args, Clone, Debug, Default)]
+pub struct LogConfig {
+    /// Log levels, as a comma separated list of `level` or `module=level`, eg.
+    /// `info,brush_train=debug`. Defaults to `RUST_LOG`.
+    #[arg(long, help_heading = "Logging")]
+    pub log_filter: Option<String>,
+
+    /// Write logs to daily files in this folder. The app defaults to a folder in its data
+    /// folder.
+    #[arg(long, help_heading = "Logging")]
+    pub log_dir: Option<PathBuf>,
+}
+
+struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    profiling: reload::Handle<Vec<BoxedLayer>, Registry>,
+    log_dir: Option<PathBuf>,
+    // Whether the filter was given on the command line or in `RUST_LOG`.
+    explicit_filter: bool,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+
+static CLOCK_24H: AtomicBool = AtomicBool::new(true);
+
+/// Show the time of log messages from 0 to 23 hours, or with AM and PM.
+pub fn set_clock_24h(clock_24h: bool) {
+    CLOCK_24H.store(clock_24h, Ordering::Relaxed);
+}
+
+/// Local time of log messages, see [`set_clock_24h`].
+pub struct LogTime;
+
+impl FormatTime for LogTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        let format = if CLOCK_24H.load(Ordering::Relaxed) {
+            "%Y-%m-%d %H:%M:%S%.3f"
+        } else {
+            "%Y-%m-%d %I:%M:%S%.3f %p"
+        };
+        write!(w, "{}", chrono::Local::now().format(format))
+    }
+}
+
+fn parse_filter(directives: &str) -> anyhow::Result<EnvFilter> {
+    Ok(EnvFilter::builder().parse(directives)?)
+}
+
+/// Set up logging to stderr and to the log folder, if any. `layers` also receive the logs, with
+/// the same filter as the log files.
+///
+/// The terminal only shows errors unless a filter is set, to not interfere with the progress
+/// bars.
+pub fn init(
+    config: &LogConfig,
+    default_dir: Option<PathBuf>,
+    mut layers: Vec<BoxedLayer>,
+) -> anyhow::Result<()> {
+    let explicit = config
+        .log_filter
+        .clone()
+        .or_else(|| std::env::var(EnvFilter::DEFAULT_ENV).ok());
+    let directives = explicit.as_deref().unwrap_or(DEFAULT_FILTER);
+    let (filter, filter_handle) = reload::Layer::new(parse_filter(directives)?);
+    let stderr_filter = parse_filter(explicit.as_deref().unwrap_or("error"))?;
+
+    let log_dir = config.log_dir.clone().or(default_dir);
+    if let Some(dir) = &log_dir {
+        let appender = tracing_appender::rolling::Builder::new()
+            .rotation(tracing_appender::rolling::Rotation::DAILY)
+            .filename_prefix("brush")
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)?;
+        layers.push(
+            fmt::layer()
+                .with_ansi(false)
+                .with_timer(LogTime)
+                .with_writer(appender)
+                .boxed(),
+        );
+    }
+
+    // Profilers are only known once the app has a device, see `add_profiling_layers`.
+    let (profiling, profiling_handle) = reload::Layer::new(Vec::<BoxedLayer>::new());
+
+    let all_layers: Vec<BoxedLayer> = vec![
+        profiling.boxed(),
+        layers.with_filter(filter).boxed(),
+        fmt::layer()
+            .with_timer(LogTime)
+            .with_writer(std::io::stderr)
+            .with_filter(stderr_filter)
+            .boxed(),
+    ];
+    tracing_subscriber::registry().with(all_layers).try_init()?;
+
+    let _ = LOGGING.set(Logging {
+        filter: filter_handle,
+        profiling: profiling_handle,
+        log_dir,
+        explicit_filter: explicit.is_some(),
+    });
+    Ok(())
+}
+
+/// Change the levels of the recorded logs, in the same format as `--log-filter`.
+pub fn set_filter(directives: &str) -> anyhow::Result<()> {
+    let logging = LOGGING.get().context("Logging isn't set up")?;
+    logging.filter.reload(parse_filter(directives)?)?;
+    Ok(())
+}
+
+/// Whether the levels were given on the command line or in `RUST_LOG`, which take precedence
+/// over saved settings.
+pub fn has_explicit_filter() -> bool {
+    LOGGING.get().is_some_and(|l| l.explicit_filter)
+}
+
+/// Whether [`init`] was called, so the logs can be configured.
+pub fn is_initialized() -> bool {
+    LOGGING.get().is_some()
+}
+
+/// Folder the logs are written to.
+pub fn log_dir() -> Option<&'static Path> {
+    LOGGING.get().and_then(|l| l.log_dir.as_deref())
+}
+
+/// Add layers that receive all spans and events, unfiltered, eg. for profiling.
+pub fn add_profiling_layers(layers: Vec<BoxedLayer>) -> anyhow::Result<()> {
+    let logging = LOGGING.get().context("Logging isn't set up")?;
+    logging
+        .profiling
+        .modify(|profiling| profiling.extend(layers))?;
+    Ok(())
+}
@@ -1,8 +1,10 @@
 use std::time::Duration;
 
-use brush_process::process_loop::{ProcessMessage, RunningProcess};
+use brush_process::process_loop::{ProcessMessage, RunningProcess, TrainProgress};
 use indicatif::{ProgressBar, ProgressStyle};
 
+use crate::controls::{COMMAND_HELP, describe, spawn_controls};
+
 pub async fn process_ui(process: RunningProcess) {
     let mut process = process;
 
@@ -48,12 +50,11 @@ pub async fn process_ui(process: RunningProcess) {
 
     let train_progress = ProgressBar::new(process.start_args.train_config.total_steps as u64)
         .with_style(
-            ProgressStyle::with_template(
-                "[{elapsed}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg} ({per_sec}, {eta} remaining)",
-            )
-            .expect("Invalid indicatif config").progress_chars("◍○○"),
+            ProgressStyle::with_template("[{elapsed}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+                .expect("Invalid indicatif config")
+                .progress_chars("◍○○"),
         )
-        .with_message("Steps");
+        .with_message("steps");
 
     let sp = indicatif::MultiProgress::new();
     let main_spinner = sp.add(main_spinner);
@@ -75,11 +76,38 @@ pub async fn process_ui(process: RunningProcess) {
             sp.println("ℹ️  running in debug mode, compile with --release for best performance");
     }
 
-    while let Some(msg) = process.messages.recv().await {
+    let train_config = &process.start_args.train_config;
+    let mut progress = TrainProgress::new(train_config.total_steps, train_config.refine_stop_iter);
+
+    let _ = sp.println(format!("⌨️  {COMMAND_HELP}"));
+    let mut controls = spawn_controls(true);
+
+    loop {
+        let msg = tokio::select! {
+            msg = process.messages.recv() => msg,
+            Some(control) = controls.recv() => {
+                match control {
+                    Ok(control) => {
+                        let _ = sp.println(format!("⏯️  {}", describe(&control)));
+                        let _ = process.control.send(control);
+                    }
+                    Err(error) => {
+                        let _ = sp.println(format!("❌ {error}"));
+                    }
+                }
+                continue;
+            }
+        };
+
+        let Some(msg) = msg else {
+            break;
+        };
+
         match msg {
             ProcessMessage::NewSource => {
                 main_spinner.set_message("Starting process...");
             }
+            ProcessMessage::SourceOpened { .. } => {}
             ProcessMessage::StartLoading { training } => {
                 if !training {
                     // Display a big warning saying viewing splats from the CLI doesn't make sense.
@@ -92,12 +120,18 @@ pub async fn process_ui(process: RunningProcess) {
                 let _ = sp.println(format!("❌ Error: {error:?}"));
                 break;
             }
+            ProcessMessage::Warning(warning) => {
+                let _ = sp.println(format!("⚠ {warning}"));
+            }
             ProcessMessage::ViewSplats { .. } => {
                 // I guess we're already showing a warning.
             }
             ProcessMessage::Dataset { data } => {
+                let skipped = data
+                    .skipped_summary()
+                    .map_or(String::new(), |s| format!(", {s}"));
                 main_spinner.set_message(format!(
-                    "Loading data... {} training, {} eval views",
+                    "Loading data... {} training, {} eval views{skipped}",
                     data.train.views.len(),
                     data.eval.as_ref().map_or(0, |v| v.views.len()),
                 ));
@@ -110,6 +144,10 @@ pub async fn process_ui(process: RunningProcess) {
                     ));
                 }
             }
+            ProcessMessage::SceneScale { scale, estimated } => {
+                let source = if estimated { "estimated" } else { "set" };
+                let _ = sp.println(format!("📏 Scene scale {scale:.3} ({source})"));
+            }
             ProcessMessage::DoneLoading { .. } => {
                 main_spinner.set_message("Dataset loaded");
             }
@@ -117,23 +155,43 @@ pub async fn process_ui(process: RunningProcess) {
                 splats,
                 stats: _,
                 iter,
-                timestamp: _,
+                timestamp,
             } => {
+                progress.update(timestamp, iter, splats.num_splats());
+
                 main_spinner.set_message("Training");
                 train_progress.set_position(iter as u64);
-                stats_spinner.set_message(format!("Current splat count {}", splats.num_splats()));
-                // Progress bar.
+                train_progress.set_message(format!(
+                    "steps ({:.1} steps/s, {} remaining)",
+                    progress.iter_per_s(),
+                    progress.eta().map_or("--".to_owned(), |eta| {
+                        // Round duration to seconds.
+                        let eta = Duration::from_secs(eta.as_secs());
+                        humantime::Duration::from(eta).to_string()
+                    }),
+                ));
+
+                let projected = progress
+                    .projected_splats()
+                    .map_or(String::new(), |n| format!(", projected {n} at the end"));
+                stats_spinner.set_message(format!(
+                    "Current splat count {}{projected}",
+                    splats.num_splats()
+                ));
             }
-            ProcessMessage::RefineStep { .. } => {
-                // Do we show this info somewhere?
+            ProcessMessage::RefineStep { stats, iter } => {
+                let _ = sp.println(format!("🔀  Step {iter}: refine, {stats}"));
             }
             ProcessMessage::EvalResult {
                 iter,
                 avg_psnr,
+                psnr_ci,
                 avg_ssim,
+                ssim_ci,
             } => {
                 eval_spinner.set_message(format!(
-                    "Eval iter {iter}: PSNR {avg_psnr}, ssim {avg_ssim}"
+                    "Eval iter {iter}: PSNR {avg_psnr:.2} ± {psnr_ci:.2}, \
+                     ssim {avg_ssim:.3} ± {ssim_ci:.3}"
                 ));
                 // Show eval results.
             }
@@ -6,7 +6,7 @@
 // rfd on wasm, nor is drag-and-dropping folders in egui.
 use std::{
     collections::HashMap,
-    io::{Cursor, Read},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -17,6 +17,8 @@ use tokio::{
     io::{AsyncRead, AsyncReadExt},
     sync::Mutex,
 };
+#[cfg(not(target_family = "wasm"))]
+use tokio_with_wasm::alias as tokio_wasm;
 
 use zip::{
     ZipArchive,
@@ -42,6 +44,357 @@ impl AsRef<[u8]> for ZipData {
     }
 }
 
+/// A file read with positional reads, so clones of an archive can read it independently, without
+/// reading all of it into memory.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Clone)]
+pub struct FileData {
+    file: Arc<std::fs::File>,
+    len: u64,
+    pos: u64,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Read for FileData {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        #[cfg(unix)]
+        let read = std::os::unix::fs::FileExt::read_at(&*self.file, buf, self.pos)?;
+        #[cfg(windows)]
+        let read = std::os::windows::fs::FileExt::seek_read(&*self.file, buf, self.pos)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Seek for FileData {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = pos.ok_or(std::io::ErrorKind::InvalidInput)?;
+        Ok(self.pos)
+    }
+}
+
+/// Where the data of a zip archive is read from.
+#[derive(Clone)]
+pub enum ZipSource {
+    Memory(Cursor<ZipData>),
+    #[cfg(not(target_family = "wasm"))]
+    File(FileData),
+}
+
+impl Read for ZipSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Memory(cursor) => cursor.read(buf),
+            #[cfg(not(target_family = "wasm"))]
+            Self::File(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for ZipSource {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Self::Memory(cursor) => cursor.seek(pos),
+            #[cfg(not(target_family = "wasm"))]
+            Self::File(file) => file.seek(pos),
+        }
+    }
+}
+
+/// Kinds of archives a dataset can be packed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// Including zip64, for archives and files over 4GB.
+    Zip,
+    Tar,
+    TarGz,
+    SevenZ,
+}
+
+impl ArchiveKind {
+    /// Number of bytes at the start of a file [`Self::detect`] needs.
+    pub const PEEK_LEN: usize = 512;
+
+    /// The kind of archive a file is, from its first bytes.
+    pub fn detect(peek: &[u8]) -> Option<Self> {
+        if peek.starts_with(b"PK") {
+            Some(Self::Zip)
+        } else if peek.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::TarGz)
+        } else if peek.starts_with(&[b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c]) {
+            Some(Self::SevenZ)
+        } else if peek.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+// Decompressed data of a tar archive, read from a reader as it's needed, so the archive itself
+// is never in memory as a whole.
+struct TarStream<R> {
+    reader: R,
+    gz: Option<flate2::write::GzDecoder<Vec<u8>>>,
+    // Data that was read and decompressed, but not taken yet.
+    buffer: Vec<u8>,
+    chunk: Vec<u8>,
+    ended: bool,
+}
+
+impl<R: AsyncRead + Unpin> TarStream<R> {
+    fn new(kind: ArchiveKind, reader: R) -> Self {
+        Self {
+            reader,
+            gz: (kind == ArchiveKind::TarGz).then(|| flate2::write::GzDecoder::new(vec![])),
+            buffer: vec![],
+            chunk: vec![0; 1 << 16],
+            ended: false,
+        }
+    }
+
+    // The next `len` bytes, or None if the data ends before them.
+    async fn take(&mut self, len: usize) -> std::io::Result<Option<Vec<u8>>> {
+        while self.buffer.len() < len && !self.ended {
+            let read = self.reader.read(&mut self.chunk).await?;
+            self.ended = read == 0;
+            match self.gz.as_mut() {
+                Some(gz) => {
+                    if self.ended {
+                        gz.try_finish()?;
+                    } else {
+                        gz.write_all(&self.chunk[..read])?;
+                        gz.flush()?;
+                    }
+                    self.buffer.append(gz.get_mut());
+                }
+                None => self.buffer.extend_from_slice(&self.chunk[..read]),
+            }
+        }
+        if self.buffer.len() < len {
+            return Ok(None);
+        }
+        let rest = self.buffer.split_off(len);
+        Ok(Some(std::mem::replace(&mut self.buffer, rest)))
+    }
+}
+
+// The path a pax extended header sets, if any.
+fn pax_path(data: &[u8]) -> Option<PathBuf> {
+    let mut rest = data;
+    // Records are "<length> <key>=<value>\n", the length including itself.
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?.strip_suffix(b"\n")?;
+        rest = &rest[len..];
+        if let Some(path) = record.strip_prefix(b"path=") {
+            return Some(PathBuf::from(String::from_utf8_lossy(path).into_owned()));
+        }
+    }
+    None
+}
+
+// Read the files of a tar archive into memory, entry by entry as the archive is read.
+async fn stream_tar(
+    kind: ArchiveKind,
+    reader: impl AsyncRead + Unpin,
+) -> anyhow::Result<PathReader> {
+    const BLOCK: usize = 512;
+
+    let mut stream = TarStream::new(kind, reader);
+    let mut paths = PathReader::default();
+    // Path of the next entry, from a GNU long name or pax header before it.
+    let mut next_path = None;
+
+    while let Some(block) = stream.take(BLOCK).await? {
+        // The archive ends with empty blocks.
+        if block.iter().all(|&b| b == 0) {
+            break;
+        }
+        let header = tar::Header::from_byte_slice(&block);
+        let size = usize::try_from(header.entry_size()?)?;
+        let mut data = stream
+            .take(size.next_multiple_of(BLOCK))
+            .await?
+            .context("The archive ends in the middle of a file")?;
+        data.truncate(size);
+
+        let entry_type = header.entry_type();
+        if entry_type.is_gnu_longname() {
+            let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+            next_path = Some(PathBuf::from(
+                String::from_utf8_lossy(&data[..end]).into_owned(),
+            ));
+        } else if entry_type.is_pax_local_extensions() {
+            next_path = pax_path(&data).or(next_path);
+        } else {
+            let path = match next_path.take() {
+                Some(path) => path,
+                None => header.path()?.into_owned(),
+            };
+            if entry_type.is_file() {
+                paths.add(&path, Cursor::new(data));
+            }
+        }
+    }
+    Ok(paths)
+}
+
+// Read all files of a 7z archive into memory. Its index is at the end, so the whole archive
+// has to be read first.
+fn unpack_7z_to_memory(data: Vec<u8>) -> anyhow::Result<PathReader> {
+    let mut paths = PathReader::default();
+    let len = data.len() as u64;
+    let mut archive =
+        sevenz_rust::SevenZReader::new(Cursor::new(data), len, sevenz_rust::Password::empty())?;
+    archive.for_each_entries(|entry, reader| {
+        if !entry.is_directory() {
+            let mut buffer = Vec::with_capacity(entry.size() as usize);
+            reader.read_to_end(&mut buffer)?;
+            paths.add(Path::new(entry.name()), Cursor::new(buffer));
+        }
+        Ok(true)
+    })?;
+    Ok(paths)
+}
+
+// `path` if it stays inside the directory it's relative to, so archives can't write elsewhere.
+#[cfg(not(target_family = "wasm"))]
+fn contained_path(path: &Path) -> Option<&Path> {
+    use std::path::Component;
+
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        .then_some(path)
+}
+
+// Unpack a 7z archive into `dir`, skipping entries that would end up outside of it.
+#[cfg(not(target_family = "wasm"))]
+fn unpack_7z_to_disk(path: &Path, dir: &Path) -> anyhow::Result<()> {
+    let file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut archive = sevenz_rust::SevenZReader::new(
+        std::io::BufReader::new(file),
+        len,
+        sevenz_rust::Password::empty(),
+    )?;
+    archive.for_each_entries(|entry, reader| {
+        let Some(name) = contained_path(Path::new(entry.name())) else {
+            log::warn!(
+                "Skipping {:?} in the archive, it's outside of it",
+                entry.name()
+            );
+            return Ok(true);
+        };
+        let target = dir.join(name);
+        if entry.is_directory() {
+            std::fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = std::io::BufWriter::new(std::fs::File::create(&target)?);
+            std::io::copy(reader, &mut file)?;
+            file.flush()?;
+        }
+        Ok(true)
+    })?;
+    Ok(())
+}
+
+// Number of unpacked archives kept in the temporary directory. The least recently used others
+// are removed when an archive is unpacked or opened, as they can be gigabytes.
+#[cfg(not(target_family = "wasm"))]
+const KEEP_UNPACKED: usize = 2;
+
+// Remove the unpacked archives in `root` beyond the [`KEEP_UNPACKED`] most recently used.
+#[cfg(not(target_family = "wasm"))]
+fn remove_old_unpacked(root: &Path) -> std::io::Result<()> {
+    let mut unpacked = vec![];
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.file_type()?.is_dir() || name.ends_with(".partial") {
+            continue;
+        }
+        let used = std::fs::metadata(root.join(format!("{name}.used")))
+            .or_else(|_| entry.metadata())?
+            .modified()?;
+        unpacked.push((used, name));
+    }
+    unpacked.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (_, name) in unpacked.into_iter().skip(KEEP_UNPACKED) {
+        log::info!("Removing the unpacked archive {name} from {root:?}");
+        std::fs::remove_dir_all(root.join(&name))?;
+        let _ = std::fs::remove_file(root.join(format!("{name}.used")));
+    }
+    Ok(())
+}
+
+// Unpack a tar or 7z archive into a temporary directory, one file at a time. Archives that were
+// unpacked before, and haven't changed since, are not unpacked again.
+#[cfg(not(target_family = "wasm"))]
+fn unpack_to_disk(path: &Path, kind: ArchiveKind) -> anyhow::Result<PathBuf> {
+    use sha2::{Digest, Sha256};
+
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |t| t.as_secs());
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update(metadata.len().to_le_bytes());
+    hasher.update(modified.to_le_bytes());
+    let key: String = hasher.finalize()[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    let root = std::env::temp_dir().join("brush-archives");
+    let dir = root.join(&key);
+    let unpacked = dir.is_dir();
+    if unpacked {
+        log::info!("Using the unpacked archive in {dir:?}");
+    } else {
+        // Unpack next to the final directory first, so an interrupted unpack isn't used.
+        let partial = root.join(format!("{key}.partial"));
+        if partial.exists() {
+            std::fs::remove_dir_all(&partial)?;
+        }
+        std::fs::create_dir_all(&partial)?;
+        log::info!("Unpacking {path:?} into {dir:?}");
+
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        match kind {
+            ArchiveKind::Zip => anyhow::bail!("Zip archives are read as they're opened"),
+            // Tar skips entries that would end up outside of the directory.
+            ArchiveKind::Tar => tar::Archive::new(file).unpack(&partial)?,
+            ArchiveKind::TarGz => {
+                tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(&partial)?;
+            }
+            ArchiveKind::SevenZ => unpack_7z_to_disk(path, &partial)?,
+        }
+        std::fs::rename(&partial, &dir)?;
+    }
+
+    // Mark when the archive was last used, to keep the ones in use.
+    std::fs::write(root.join(format!("{key}.used")), [])?;
+    if let Err(e) = remove_old_unpacked(&root) {
+        log::warn!("Failed to remove old unpacked archives from {root:?}: {e}");
+    }
+    Ok(dir)
+}
+
 #[derive(Clone, Default)]
 pub struct PathReader {
     paths: HashMap<PathBuf, SharedRead>,
@@ -66,9 +419,35 @@ impl PathReader {
     }
 }
 
+// All files in a directory and its subdirectories, relative to it.
+#[cfg(not(target_family = "wasm"))]
+async fn walk_dir(dir: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
+    let dir = PathBuf::from(dir.as_ref());
+
+    let mut paths = Vec::new();
+    let mut stack = vec![dir.clone()];
+
+    while let Some(path) = stack.pop() {
+        let mut read_dir = tokio::fs::read_dir(&path).await?;
+
+        while let Some(entry) = read_dir.next_entry().await? {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path.clone());
+            }
+            paths.push(
+                path.strip_prefix(dir.clone())
+                    .map_err(|_e| std::io::ErrorKind::InvalidInput)?
+                    .to_path_buf(),
+            );
+        }
+    }
+    Ok(paths)
+}
+
 #[derive(Clone)]
 pub enum BrushVfs {
-    Zip(ZipArchive<Cursor<ZipData>>),
+    Zip(ZipArchive<ZipSource>),
     Manual(PathReader),
     #[cfg(not(target_family = "wasm"))]
     Directory(PathBuf, Vec<PathBuf>),
@@ -83,10 +462,59 @@ impl BrushVfs {
         let zip_data = ZipData {
             data: Arc::new(bytes),
         };
-        let archive = ZipArchive::new(Cursor::new(zip_data))?;
+        let archive = ZipArchive::new(ZipSource::Memory(Cursor::new(zip_data)))?;
         Ok(Self::Zip(archive))
     }
 
+    /// Read an archive, keeping the data of a zip archive in memory to read files from as they're
+    /// opened. The files of tar archives are read into memory as the archive is read, without
+    /// keeping the archive. 7z archives are read whole first, as their index is at the end.
+    ///
+    /// On the web there are no files to read archives from instead, so zip and 7z archives have
+    /// to fit in memory there. Their indices are at the end, so they can't be streamed like tar.
+    pub async fn from_archive_reader(
+        kind: ArchiveKind,
+        reader: impl AsyncRead + Unpin,
+    ) -> anyhow::Result<Self> {
+        match kind {
+            ArchiveKind::Zip => Ok(Self::from_zip_reader(reader).await?),
+            ArchiveKind::Tar | ArchiveKind::TarGz => {
+                Ok(Self::Manual(stream_tar(kind, reader).await?))
+            }
+            ArchiveKind::SevenZ => {
+                let mut bytes = vec![];
+                let mut reader = reader;
+                reader.read_to_end(&mut bytes).await?;
+                Ok(Self::Manual(unpack_7z_to_memory(bytes)?))
+            }
+        }
+    }
+
+    /// Read an archive file without reading it into memory: files of a zip archive are read from
+    /// the file as they're opened, other archives are unpacked into a temporary directory.
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn from_archive_file(path: &Path, kind: ArchiveKind) -> anyhow::Result<Self> {
+        let path = path.to_owned();
+        // Reading the index of a zip archive or unpacking an archive is blocking io, which can
+        // take a while for big archives, so keep it off the async runtime.
+        if kind == ArchiveKind::Zip {
+            let archive = tokio_wasm::task::spawn_blocking(move || {
+                let file = std::fs::File::open(path)?;
+                let len = file.metadata()?.len();
+                ZipArchive::new(ZipSource::File(FileData {
+                    file: Arc::new(file),
+                    len,
+                    pos: 0,
+                }))
+            })
+            .await??;
+            return Ok(Self::Zip(archive));
+        }
+        let dir = tokio_wasm::task::spawn_blocking(move || unpack_to_disk(&path, kind)).await??;
+        let paths = walk_dir(&dir).await?;
+        Ok(Self::Directory(dir, paths))
+    }
+
     pub fn from_paths(paths: PathReader) -> Self {
         Self::Manual(paths)
     }
@@ -95,10 +523,13 @@ impl BrushVfs {
         #[cfg(not(target_family = "wasm"))]
         {
             if dir.is_file() {
-                let file = tokio::fs::File::open(dir).await?;
-                if dir.extension().is_some_and(|e| e == "zip") {
-                    Ok(Self::from_zip_reader(file).await?)
+                let mut file = tokio::fs::File::open(dir).await?;
+                let mut peek = vec![0; ArchiveKind::PEEK_LEN];
+                let peeked = file.read(&mut peek).await?;
+                if let Some(kind) = ArchiveKind::detect(&peek[..peeked]) {
+                    Self::from_archive_file(dir, kind).await
                 } else {
+                    let file = tokio::fs::File::open(dir).await?;
                     // Make a VFS with just this file.
                     let mut paths = PathReader::default();
                     paths.add(dir, file);
@@ -106,30 +537,6 @@ impl BrushVfs {
                 }
             } else {
                 // Make a VFS with all files contained in the directory.
-                async fn walk_dir(dir: impl AsRef<Path>) -> std::io::Result<Vec<PathBuf>> {
-                    let dir = PathBuf::from(dir.as_ref());
-
-                    let mut paths = Vec::new();
-                    let mut stack = vec![dir.clone()];
-
-                    while let Some(path) = stack.pop() {
-                        let mut read_dir = tokio::fs::read_dir(&path).await?;
-
-                        while let Some(entry) = read_dir.next_entry().await? {
-                            let path = entry.path();
-                            if path.is_dir() {
-                                stack.push(path.clone());
-                            }
-                            paths.push(
-                                path.strip_prefix(dir.clone())
-                                    .map_err(|_e| std::io::ErrorKind::InvalidInput)?
-                                    .to_path_buf(),
-                            );
-                        }
-                    }
-                    Ok(paths)
-                }
-
                 Ok(Self::Directory(dir.to_path_buf(), walk_dir(dir).await?))
             }
         }
@@ -181,3 +588,243 @@ impl BrushVfs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ArchiveKind, BrushVfs};
+    use flate2::{Compression, write::GzEncoder};
+    use std::path::{Path, PathBuf};
+    use tokio::io::AsyncReadExt;
+
+    // An empty directory for a test to write to.
+    #[cfg(not(target_family = "wasm"))]
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brush-vfs-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("Failed to create test directory");
+        dir
+    }
+
+    // A 7z archive of `files`, stored without compression: one stream of all files, which a
+    // "copy" coder passes on as is.
+    fn seven_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        fn crc(data: &[u8]) -> [u8; 4] {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            crc.sum().to_le_bytes()
+        }
+        // Numbers below 0x80 take a single byte.
+        fn num(n: usize) -> u8 {
+            u8::try_from(n)
+                .ok()
+                .filter(|&n| n < 0x80)
+                .expect("Too large")
+        }
+
+        let packed: Vec<u8> = files.iter().flat_map(|(_, data)| data.to_vec()).collect();
+
+        // Header, main streams, pack info, unpack info with one folder, and the files in it.
+        let mut header = vec![0x01, 0x04, 0x06, 0x00, 0x01, 0x09, num(packed.len()), 0x00];
+        header.extend([0x07, 0x0b, 0x01, 0x00, 0x01, 0x01, 0x00]);
+        header.extend([0x0c, num(packed.len()), 0x00]);
+        header.extend([0x08, 0x0d, num(files.len()), 0x09]);
+        header.extend(
+            files[..files.len() - 1]
+                .iter()
+                .map(|(_, data)| num(data.len())),
+        );
+        header.extend([0x0a, 0x01]);
+        header.extend(files.iter().flat_map(|&(_, data)| crc(data)));
+        header.extend([0x00, 0x00]);
+        // Files info with their names.
+        let names: Vec<u8> = files
+            .iter()
+            .flat_map(|(name, _)| name.encode_utf16().chain([0]).flat_map(u16::to_le_bytes))
+            .collect();
+        header.extend([0x05, num(files.len()), 0x11, num(names.len() + 1), 0x00]);
+        header.extend(names);
+        header.extend([0x00, 0x00]);
+
+        let mut start = (packed.len() as u64).to_le_bytes().to_vec();
+        start.extend((header.len() as u64).to_le_bytes());
+        start.extend(crc(&header));
+        let mut archive = vec![b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c, 0x00, 0x04];
+        archive.extend(crc(&start));
+        archive.extend(start);
+        archive.extend(packed);
+        archive.extend(header);
+        archive
+    }
+
+    const SEVEN_ZIP_FILES: [(&str, &[u8]); 3] = [
+        ("scene/images/a.png", b"image"),
+        ("scene/sparse/b.bin", b"point"),
+        ("../evil.txt", b"evil"),
+    ];
+
+    #[tokio::test]
+    async fn reads_7z_archives() {
+        let data = seven_zip(&SEVEN_ZIP_FILES);
+        assert_eq!(ArchiveKind::detect(&data), Some(ArchiveKind::SevenZ));
+        let mut vfs = BrushVfs::from_archive_reader(ArchiveKind::SevenZ, data.as_slice())
+            .await
+            .expect("Failed to read archive");
+
+        let mut contents = String::new();
+        vfs.open_path(Path::new("scene/images/a.png"))
+            .await
+            .expect("Missing file")
+            .read_to_string(&mut contents)
+            .await
+            .expect("Failed to read file");
+        assert_eq!(contents, "image");
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn unpacks_7z_inside_directory() {
+        let dir = test_dir("7z");
+        let archive = dir.join("scene.7z");
+        std::fs::write(&archive, seven_zip(&SEVEN_ZIP_FILES)).expect("Failed to write archive");
+        let unpacked = dir.join("unpacked");
+        std::fs::create_dir_all(&unpacked).expect("Failed to create directory");
+
+        super::unpack_7z_to_disk(&archive, &unpacked).expect("Failed to unpack archive");
+        let read = |path: PathBuf| std::fs::read(path).expect("Missing file");
+        assert_eq!(read(unpacked.join("scene/images/a.png")), b"image");
+        assert_eq!(read(unpacked.join("scene/sparse/b.bin")), b"point");
+        assert!(!dir.join("evil.txt").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn reads_zip64_archive_files() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let dir = test_dir("zip64");
+        let path = dir.join("scene.zip");
+        let file = std::fs::File::create(&path).expect("Failed to create archive");
+        let mut zip = zip::ZipWriter::new(file);
+        // Files that might be over 4GB get zip64 sizes.
+        let options = SimpleFileOptions::default().large_file(true);
+        zip.start_file("scene/images/a.png", options)
+            .expect("Failed to add file");
+        zip.write_all(b"image").expect("Failed to write file");
+        zip.finish().expect("Failed to write archive");
+
+        let mut vfs = BrushVfs::from_archive_file(&path, ArchiveKind::Zip)
+            .await
+            .expect("Failed to read archive");
+        let mut contents = String::new();
+        vfs.open_path(Path::new("scene/images/a.png"))
+            .await
+            .expect("Missing file")
+            .read_to_string(&mut contents)
+            .await
+            .expect("Failed to read file");
+        assert_eq!(contents, "image");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn removes_least_recently_used_unpacked() {
+        use std::time::{Duration, SystemTime};
+
+        let root = test_dir("unpacked");
+        let now = SystemTime::now();
+        for (name, age) in [("old", 30), ("new", 10), ("older", 40), ("newest", 0)] {
+            std::fs::create_dir_all(root.join(name)).expect("Failed to create directory");
+            let used = std::fs::File::create(root.join(format!("{name}.used")))
+                .expect("Failed to mark use");
+            used.set_modified(now - Duration::from_secs(age))
+                .expect("Failed to set time");
+        }
+        // An unpack in progress is left alone.
+        std::fs::create_dir_all(root.join("busy.partial")).expect("Failed to create directory");
+
+        super::remove_old_unpacked(&root).expect("Failed to remove old archives");
+        for (name, kept) in [
+            ("newest", true),
+            ("new", true),
+            ("old", false),
+            ("older", false),
+            ("busy.partial", true),
+        ] {
+            assert_eq!(root.join(name).is_dir(), kept, "{name}");
+        }
+        assert!(!root.join("old.used").exists());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn reads_tar_gz_archives() {
+        let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+        for (name, data) in [
+            ("scene/images/a.png", b"image"),
+            ("scene/sparse/b.bin", b"point"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, &data[..])
+                .expect("Failed to add file");
+        }
+        let data = builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .expect("Failed to write archive");
+
+        let kind = ArchiveKind::detect(&data);
+        assert_eq!(kind, Some(ArchiveKind::TarGz));
+        let mut vfs = BrushVfs::from_archive_reader(ArchiveKind::TarGz, data.as_slice())
+            .await
+            .expect("Failed to read archive");
+        assert_eq!(vfs.file_names().count(), 2);
+
+        let mut contents = String::new();
+        vfs.open_path(Path::new("scene/sparse/b.bin"))
+            .await
+            .expect("Missing file")
+            .read_to_string(&mut contents)
+            .await
+            .expect("Failed to read file");
+        assert_eq!(contents, "point");
+    }
+
+    #[tokio::test]
+    async fn reads_long_tar_paths() {
+        let name = format!("scene/images/{}.png", "a".repeat(120));
+        let mut builder = tar::Builder::new(vec![]);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, &name, &b"image"[..])
+            .expect("Failed to add file");
+        let data = builder.into_inner().expect("Failed to write archive");
+
+        assert_eq!(ArchiveKind::detect(&data), Some(ArchiveKind::Tar));
+        let vfs = BrushVfs::from_archive_reader(ArchiveKind::Tar, data.as_slice())
+            .await
+            .expect("Failed to read archive");
+        let names: Vec<_> = vfs.file_names().collect();
+        assert_eq!(names, vec![Path::new(&name).to_path_buf()]);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn contained_paths() {
+        assert!(super::contained_path(Path::new("images/a.png")).is_some());
+        assert!(super::contained_path(Path::new("./images/a.png")).is_some());
+        assert!(super::contained_path(Path::new("../a.png")).is_none());
+        assert!(super::contained_path(Path::new("images/../../a.png")).is_none());
+        assert!(super::contained_path(Path::new("/etc/a.png")).is_none());
+    }
+}
@@ -0,0 +1,465 @@
+//! Datasets to train on, and the configs to load them and to initialize splats.
+
+use burn::config::Config;
+
+use crate::WasmNotSend;
+use crate::preprocess::{self, PreprocessReport};
+
+use async_fn_stream::fn_stream;
+use brush_train::scene::{Scene, SceneView};
+use core::f32;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+
+use clap::Args;
+use glam::{DVec3, Mat3, Mat4, Vec3};
+use sha2::{Digest, Sha256};
+use tokio_stream::Stream;
+use tokio_with_wasm::alias as tokio_wasm;
+
+#[derive(Config, Debug, Args)]
+pub struct LoadDataseConfig {
+    /// Max nr. of frames of dataset to load
+    #[arg(long, help_heading = "Dataset Options")]
+    pub max_frames: Option<usize>,
+    /// Max resolution of images to load.
+    #[arg(long, help_heading = "Dataset Options", default_value = "1800")]
+    #[config(default = 1920)]
+    pub max_resolution: u32,
+    /// Crop images to this fraction (0-1) of their width and height, around the center.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub center_crop: Option<f32>,
+    /// Skip blurry frames, whose sharpness (variance of the Laplacian) is below this threshold.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub min_sharpness: Option<f32>,
+    /// Skip overexposed frames, where more than this fraction (0-1) of the pixels is blown out.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub max_overexposed: Option<f32>,
+    /// Skip underexposed frames, where more than this fraction (0-1) of the pixels is black.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub max_underexposed: Option<f32>,
+    /// Skip frames that are nearly identical to the frame before them: the images are more
+    /// similar (0-1) than this, and the camera barely moved. Useful for datasets from videos.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub dedup_similarity: Option<f32>,
+    /// Match the exposure and white balance of all frames to the first frame. Useful when the
+    /// camera used automatic exposure or white balance.
+    #[arg(long, help_heading = "Dataset Options", default_value = "false")]
+    #[config(default = false)]
+    pub normalize_colors: bool,
+    /// Known color corrections of the views: a JSON file in the dataset, as written next to
+    /// exports. Views that aren't listed are corrected as usual.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub color_calibration: Option<String>,
+    /// Corrected camera intrinsics, as a JSON list with an entry per camera group, eg.
+    /// `[{"camera_group": 1, "fov_x": 60, "fov_y": 45, "center_uv": [0.5, 0.5], "k1": -0.1}]`.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub intrinsics: Option<String>,
+    /// Mask out the sky with this ONNX segmentation model (needs the `onnx` feature). The model
+    /// is looked up in the dataset first, then on disk.
+    #[arg(long, help_heading = "Dataset Options")]
+    pub sky_model: Option<String>,
+    /// Resolution auxiliary models, like the sky model, run at.
+    #[arg(long, help_heading = "Dataset Options", default_value = "512")]
+    #[config(default = 512)]
+    pub aux_model_resolution: u32,
+    /// Create an eval dataset by selecting every nth image
+    #[arg(long, help_heading = "Dataset Options")]
+    pub eval_split_every: Option<usize>,
+    /// Load only every nth frame
+    #[arg(long, help_heading = "Dataset Options")]
+    pub subsample_frames: Option<u32>,
+    /// Load only every nth point from the initial sfm data
+    #[arg(long, help_heading = "Dataset Options")]
+    pub subsample_points: Option<u32>,
+    /// Initialize from the dense COLMAP point cloud (fused.ply) instead of the sparse points,
+    /// if the dataset contains one.
+    #[arg(long, help_heading = "Dataset Options", default_value = "false")]
+    #[config(default = false)]
+    pub dense_init: bool,
+    /// Max nr. of points to take from the dense point cloud. The cloud is subsampled
+    /// to stay within this budget.
+    #[arg(long, help_heading = "Dataset Options", default_value = "1000000")]
+    #[config(default = 1000000)]
+    pub dense_init_max_points: u32,
+}
+
+#[derive(Config, Debug, Args)]
+pub struct ModelConfig {
+    /// SH degree of spalts.
+    #[arg(long, help_heading = "Model Options", default_value = "3")]
+    #[config(default = 3)]
+    pub sh_degree: u32,
+    /// Point cloud in the dataset to initialize splats from (.ply, .las, .laz or .e57).
+    /// By default a single point cloud in the dataset is picked up automatically.
+    #[arg(long, help_heading = "Model Options")]
+    pub init_path: Option<String>,
+    /// Merge initial points that fall in the same voxel of this size.
+    #[arg(long, help_heading = "Model Options")]
+    pub init_voxel_size: Option<f32>,
+    /// Only keep initial points that are at least this far apart (Poisson-disk subsampling).
+    #[arg(long, help_heading = "Model Options")]
+    pub init_poisson_radius: Option<f32>,
+    /// Add this many random points around the scene to help cover the background.
+    #[arg(long, help_heading = "Model Options", default_value = "0")]
+    #[config(default = 0)]
+    pub init_background_points: usize,
+    /// Initial opacity of the splats (0-1). By default uses a random low opacity.
+    #[arg(long, help_heading = "Model Options")]
+    pub init_opacity: Option<f32>,
+    /// Initial size of the splats. By default based on the distance to neighbouring points.
+    #[arg(long, help_heading = "Model Options")]
+    pub init_scale: Option<f32>,
+    /// Without a point cloud, random splats are placed between this near and far distance from
+    /// the cameras, relative to the size of the camera bounds.
+    #[arg(long, help_heading = "Model Options", default_value = "0.25")]
+    #[config(default = 0.25)]
+    pub init_near: f32,
+    /// Far distance of the random splats, see `init_near`.
+    #[arg(long, help_heading = "Model Options", default_value = "1.0")]
+    #[config(default = 1.0)]
+    pub init_far: f32,
+}
+
+fn solve_cubic(a: f32, b: f32, c: f32, d: f32) -> (f32, f32, f32) {
+    // Convert to depressed cubic t^3 + pt + q = 0
+    let p = (3.0 * a * c - b * b) / (3.0 * a * a);
+    let q = (2.0 * b * b * b - 9.0 * a * b * c + 27.0 * a * a * d) / (27.0 * a * a * a);
+    // For symmetric matrices, we know D <= 0 (three real roots)
+    let phi = (-q / (2.0 * f32::sqrt(-(p * p * p) / 27.0))).acos();
+    let t1 = 2.0 * f32::sqrt(-p / 3.0) * f32::cos(phi / 3.0);
+    let t2 = 2.0 * f32::sqrt(-p / 3.0) * f32::cos((phi + 2.0 * std::f32::consts::PI) / 3.0);
+    let t3 = 2.0 * f32::sqrt(-p / 3.0) * f32::cos((phi + 4.0 * std::f32::consts::PI) / 3.0);
+    // Convert back to original cubic
+    let mut roots = [t1 - b / (3.0 * a), t2 - b / (3.0 * a), t3 - b / (3.0 * a)];
+    roots.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Less)); // sort in descending order
+    roots.into()
+}
+
+#[allow(clippy::needless_range_loop)]
+fn find_eigenvector(matrix: Mat3, eigenvalue: f32) -> Vec3 {
+    // Create matrix (A - λI)
+    let m = Mat3::from_cols(
+        matrix.col(0) - Vec3::new(eigenvalue, 0.0, 0.0),
+        matrix.col(1) - Vec3::new(0.0, eigenvalue, 0.0),
+        matrix.col(2) - Vec3::new(0.0, 0.0, eigenvalue),
+    );
+    // Convert matrix to array for easier manipulation
+    let mut m_arr = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            m_arr[i][j] = m.col(j)[i];
+        }
+    }
+    // Gaussian elimination with pivoting
+    for i in 0..2 {
+        // Find pivot
+        let mut max_element = m_arr[i][i].abs();
+        let mut max_row = i;
+        for k in (i + 1)..3 {
+            if m_arr[k][i].abs() > max_element {
+                max_element = m_arr[k][i].abs();
+                max_row = k;
+            }
+        }
+        // Swap maximum row with current row
+        if max_row != i {
+            for j in 0..3 {
+                let temp = m_arr[i][j];
+                m_arr[i][j] = m_arr[max_row][j];
+                m_arr[max_row][j] = temp;
+            }
+        }
+        // Make all rows below this one 0 in current column
+        for k in (i + 1)..3 {
+            let c = -m_arr[k][i] / m_arr[i][i];
+            for j in i..3 {
+                if i == j {
+                    m_arr[k][j] = 0.0;
+                } else {
+                    m_arr[k][j] += c * m_arr[i][j];
+                }
+            }
+        }
+    }
+    // Back substitution
+    let mut x = Vec3::new(0.0, 0.0, 1.0); // Set z = 1 as we have infinite solutions
+    if m_arr[1][1].abs() > 1e-10 {
+        x.y = -m_arr[1][2] / m_arr[1][1];
+    }
+    if m_arr[0][0].abs() > 1e-10 {
+        x.x = -(m_arr[0][1] * x.y + m_arr[0][2] * x.z) / m_arr[0][0];
+    }
+    // Normalize eigenvector
+    x.normalize()
+}
+
+pub fn compute_sorted_eigenvectors(matrix: Mat3) -> (Vec3, Vec3, Vec3) {
+    // Calculate coefficients of characteristic polynomial
+    // det(A - λI) = -λ^3 + c2λ^2 + c1λ + c0
+    let a = -1.0;
+    let b = matrix.col(0).x + matrix.col(1).y + matrix.col(2).z;
+    let c = matrix.col(1).z * matrix.col(2).y
+        + matrix.col(0).z * matrix.col(2).x
+        + matrix.col(0).y * matrix.col(1).x
+        - matrix.col(0).x * matrix.col(1).y
+        - matrix.col(1).y * matrix.col(2).z
+        - matrix.col(0).x * matrix.col(2).z;
+    let d = matrix.col(0).x * matrix.col(1).y * matrix.col(2).z
+        + matrix.col(0).y * matrix.col(1).z * matrix.col(2).x
+        + matrix.col(0).z * matrix.col(1).x * matrix.col(2).y
+        - matrix.col(0).x * matrix.col(1).z * matrix.col(2).y
+        - matrix.col(0).y * matrix.col(1).x * matrix.col(2).z
+        - matrix.col(0).z * matrix.col(1).y * matrix.col(2).x;
+    // Find eigenvalues
+    let eigenvalues = solve_cubic(a, b, c, d);
+    // Find eigenvectors
+    (
+        find_eigenvector(matrix, eigenvalues.0),
+        find_eigenvector(matrix, eigenvalues.1),
+        find_eigenvector(matrix, eigenvalues.2),
+    )
+}
+
+#[derive(Clone)]
+pub struct Dataset {
+    pub train: Scene,
+    pub eval: Option<Scene>,
+    /// What was done to the views while loading, see [`LoadDataseConfig`].
+    pub preprocess: PreprocessReport,
+}
+
+impl Dataset {
+    pub fn empty() -> Self {
+        Self {
+            train: Scene::new(vec![]),
+            eval: None,
+            preprocess: PreprocessReport::default(),
+        }
+    }
+
+    pub fn from_views(
+        train_views: Vec<SceneView>,
+        eval_views: Vec<SceneView>,
+        preprocess: PreprocessReport,
+    ) -> Self {
+        Self {
+            preprocess,
+            train: Scene::new(train_views),
+            eval: if eval_views.is_empty() {
+                None
+            } else {
+                Some(Scene::new(eval_views))
+            },
+        }
+    }
+
+    /// Hash of the views: their image paths and sizes, and their cameras. Two runs with the same
+    /// hash trained on the same views, in the same poses. The order the views were loaded in
+    /// doesn't matter.
+    pub fn content_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        let splits =
+            std::iter::once(("train", &self.train)).chain(self.eval.iter().map(|e| ("eval", e)));
+        for (split, scene) in splits {
+            hasher.update(split.as_bytes());
+            let mut views: Vec<&SceneView> = scene.views.iter().collect();
+            views.sort_by(|a, b| a.path.cmp(&b.path));
+            for view in views {
+                let camera = &view.camera;
+                // Prefix the path with its length, so it can't run into the numbers after it.
+                hasher.update((view.path.len() as u64).to_le_bytes());
+                hasher.update(view.path.as_bytes());
+                hasher.update(view.image.width().to_le_bytes());
+                hasher.update(view.image.height().to_le_bytes());
+                hasher.update(camera.fov_x.to_le_bytes());
+                hasher.update(camera.fov_y.to_le_bytes());
+                let floats = camera
+                    .center_uv
+                    .to_array()
+                    .into_iter()
+                    .chain(camera.position.to_array())
+                    .chain(camera.rotation.to_array());
+                for value in floats {
+                    hasher.update(value.to_le_bytes());
+                }
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Move all cameras and sparse points by `offset`, eg. along with a point cloud that was moved
+    /// closer to the origin.
+    pub fn translated(&self, offset: DVec3) -> Self {
+        let translate = |p: Vec3| (p.as_dvec3() + offset).as_vec3();
+        let scene = |scene: &Scene| {
+            let views = scene
+                .views
+                .iter()
+                .map(|view| {
+                    let mut view = view.clone();
+                    view.camera.position = translate(view.camera.position);
+                    view.sparse_points = Arc::new(
+                        view.sparse_points
+                            .iter()
+                            .map(|&(uv, p)| (uv, translate(p)))
+                            .collect(),
+                    );
+                    view
+                })
+                .collect();
+            Scene::new(views)
+        };
+        Self {
+            train: scene(&self.train),
+            eval: self.eval.as_ref().map(scene),
+            preprocess: self.preprocess.clone(),
+        }
+    }
+
+    /// Report of the views that were skipped while loading, if any.
+    pub fn skipped_summary(&self) -> Option<String> {
+        let skipped = &self.preprocess.skipped;
+        (!skipped.is_empty()).then(|| preprocess::summary(skipped))
+    }
+
+    pub fn estimate_up(&self) -> Vec3 {
+        // based on https://github.com/jonbarron/camp_zipnerf/blob/8e6d57e3aee34235faf3ef99decca0994efe66c9/camp_zipnerf/internal/camera_utils.py#L233
+        let (c2ws, ts): (Vec<_>, Vec<_>) = self
+            .train
+            .views
+            .iter()
+            .chain(self.eval.iter().flat_map(|e| e.views.as_slice()))
+            .map(|v| (v.camera.local_to_world(), v.camera.position))
+            .collect();
+
+        let mean_t = ts.iter().sum::<Vec3>() / ts.len() as f32;
+
+        // Compute 3x3 covariance by t^T * t ((3, N) * (N, 3) -> (3, 3))
+        let cov = ts.iter().map(|&p| p - mean_t).fold(Mat3::ZERO, |acc, p| {
+            acc + Mat3::from_cols(p * p.x, p * p.y, p * p.z).transpose()
+        });
+        let (e0, e1, e2) = compute_sorted_eigenvectors(cov);
+        let mut rot = Mat3::from_cols(e0, e1, e2).transpose();
+
+        if rot.determinant() < 0.0 {
+            let diag = Mat3::from_diagonal(Vec3::new(1.0, 1.0, -1.0));
+            rot = diag.mul_mat3(&rot);
+        }
+
+        let mut transform = Mat4::from_cols(
+            rot.col(0).extend(0.0),
+            rot.col(1).extend(0.0),
+            rot.col(2).extend(0.0),
+            rot.mul_vec3(-mean_t).extend(1.0),
+        );
+
+        let mut y_axis_z = 0.0;
+        for c2w in c2ws {
+            y_axis_z += transform.mul_mat4(&Mat4::from(c2w)).col(1).z;
+        }
+
+        // Flip coordinate system if z component of y-axis is negative
+        if y_axis_z < 0.0 {
+            let scale = Mat4::from_scale(Vec3::new(1.0, -1.0, -1.0));
+            transform = scale.mul_mat4(&transform);
+        }
+
+        Vec3::new(-transform.col(0).z, -transform.col(1).z, transform.col(2).z)
+    }
+}
+
+// Aborts the tasks that are still running when dropped, eg. when loading is cancelled.
+struct AbortOnDrop<T>(VecDeque<tokio_wasm::task::JoinHandle<T>>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+pub(crate) fn stream_fut_parallel<T: Send + 'static>(
+    futures: Vec<impl Future<Output = T> + WasmNotSend + 'static>,
+) -> impl Stream<Item = T> {
+    let parallel = if cfg!(target_family = "wasm") {
+        1
+    } else {
+        std::thread::available_parallelism()
+            .map(|x| x.get())
+            .unwrap_or(8)
+    };
+
+    log::info!("Loading stream with {parallel} threads");
+
+    let mut futures = futures;
+    fn_stream(|emitter| async move {
+        while !futures.is_empty() {
+            // Spawn a batch of threads.
+            let mut handles = AbortOnDrop(
+                futures
+                    .drain(..futures.len().min(parallel))
+                    .map(|fut| tokio_wasm::spawn(fut))
+                    .collect(),
+            );
+            // Stream each of them. A handle is only taken out once it's done, so dropping the
+            // stream stops the rest of the batch.
+            while let Some(handle) = handles.0.front_mut() {
+                let result = handle.await.expect("Underlying stream panicked");
+                handles.0.pop_front();
+                emitter.emit(result).await;
+
+                // Everything runs on the main thread on the web, so let the browser
+                // update the UI in between decoding images.
+                #[cfg(target_family = "wasm")]
+                tokio_wasm::task::yield_now().await;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use brush_render::camera::Camera;
+    use brush_train::scene::ViewImageType;
+    use glam::Quat;
+
+    use super::*;
+
+    fn view(path: &str, position: Vec3) -> SceneView {
+        SceneView {
+            path: path.to_owned(),
+            camera: Camera::new(position, Quat::IDENTITY, 1.0, 0.8, glam::vec2(0.5, 0.5)),
+            image: Arc::new(image::DynamicImage::new_rgb8(8, 6)),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![]),
+        }
+    }
+
+    #[test]
+    fn content_hash_ignores_load_order() {
+        let views = || vec![view("a.png", Vec3::X), view("b.png", Vec3::Y)];
+        let dataset = Dataset::from_views(views(), vec![], PreprocessReport::default());
+        let reversed = views().into_iter().rev().collect();
+        let reversed = Dataset::from_views(reversed, vec![], PreprocessReport::default());
+        assert_eq!(dataset.content_hash(), reversed.content_hash());
+        assert_eq!(dataset.content_hash().len(), 64);
+
+        let moved = vec![view("a.png", Vec3::X), view("b.png", Vec3::Z)];
+        let moved = Dataset::from_views(moved, vec![], PreprocessReport::default());
+        assert_ne!(dataset.content_hash(), moved.content_hash());
+
+        // The same views split differently aren't the same dataset.
+        let split = Dataset::from_views(
+            vec![view("a.png", Vec3::X)],
+            vec![view("b.png", Vec3::Y)],
+            PreprocessReport::default(),
+        );
+        assert_ne!(dataset.content_hash(), split.content_hash());
+    }
+}
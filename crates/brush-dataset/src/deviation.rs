@@ -0,0 +1,248 @@
+use anyhow::anyhow;
+use ball_tree::BallTree;
+use brush_render::gaussian_splats::Splats;
+use burn::{prelude::Backend, tensor::DataError};
+use glam::{Quat, Vec3};
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::reference_import::ReferenceGeometry;
+
+/// Summary statistics of the distances between reference points and the splat surface.
+#[derive(Debug, Clone, Copy)]
+pub struct DeviationStats {
+    pub count: usize,
+    pub mean: f32,
+    pub rms: f32,
+    pub median: f32,
+    pub p95: f32,
+    pub max: f32,
+}
+
+pub struct DeviationResult {
+    /// Distance from each reference point to the splat surface. Splats don't say which of their
+    /// sides is the outside, so this is unsigned.
+    pub distances: Vec<f32>,
+    pub stats: DeviationStats,
+}
+
+impl DeviationStats {
+    fn from_distances(distances: &[f32]) -> Self {
+        let count = distances.len();
+        let n = count.max(1) as f32;
+
+        let mut sorted = distances.to_vec();
+        sorted.sort_unstable_by(f32::total_cmp);
+        let percentile = |p: f32| {
+            sorted
+                .get(((sorted.len() as f32 * p) as usize).min(sorted.len().saturating_sub(1)))
+                .copied()
+                .unwrap_or(0.0)
+        };
+
+        Self {
+            count,
+            mean: distances.iter().sum::<f32>() / n,
+            rms: (distances.iter().map(|d| d * d).sum::<f32>() / n).sqrt(),
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            max: sorted.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+impl DeviationResult {
+    /// Write the per point deviations as CSV, with the summary statistics as a header comment.
+    pub fn to_csv(&self, geometry: &ReferenceGeometry) -> String {
+        let s = &self.stats;
+        let mut csv = format!(
+            "# count={} mean={} rms={} median={} p95={} max={}\nx,y,z,deviation\n",
+            s.count, s.mean, s.rms, s.median, s.p95, s.max
+        );
+        for (p, d) in geometry.points.iter().zip(&self.distances) {
+            csv += &format!("{},{},{},{d}\n", p.x, p.y, p.z);
+        }
+        csv
+    }
+}
+
+// Splats more transparent than this aren't part of the surface, eg. floaters, and are ignored.
+const MIN_OPACITY: f32 = 0.5;
+
+// Number of nearby splats to measure the distance to, as the splat with the nearest center isn't
+// always the one with the nearest surface, eg. next to a large splat.
+const NEIGHBOURS: usize = 8;
+
+// A splat approximated as a flat disk along its shortest axis.
+#[derive(Clone, Copy, Debug)]
+struct Disk {
+    center: Vec3,
+    normal: Vec3,
+    // Two standard deviations along the longest axis.
+    radius: f32,
+}
+
+impl Disk {
+    // Distance from `point` to the closest point on the disk.
+    fn distance(&self, point: Vec3) -> f32 {
+        let offset = point - self.center;
+        let height = offset.dot(self.normal);
+        let radial = (offset - height * self.normal).length();
+        height.hypot((radial - self.radius).max(0.0))
+    }
+}
+
+// The splats that make up the surface, as disks.
+fn surface_disks(
+    means: &[f32],
+    log_scales: &[f32],
+    rotations: &[f32],
+    opacities: &[f32],
+) -> Vec<Disk> {
+    means
+        .chunks_exact(3)
+        .zip(log_scales.chunks_exact(3))
+        .zip(rotations.chunks_exact(4))
+        .zip(opacities)
+        .filter(|(_, opacity)| **opacity >= MIN_OPACITY)
+        .map(|(((mean, scale), rot), _)| {
+            let axis = if scale[0] <= scale[1] && scale[0] <= scale[2] {
+                Vec3::X
+            } else if scale[1] <= scale[2] {
+                Vec3::Y
+            } else {
+                Vec3::Z
+            };
+            Disk {
+                center: Vec3::from_slice(mean),
+                normal: Quat::from_xyzw(rot[1], rot[2], rot[3], rot[0]) * axis,
+                radius: 2.0 * scale[0].max(scale[1]).max(scale[2]).exp(),
+            }
+        })
+        .collect()
+}
+
+// Distance from each point to the closest of the disks near it.
+async fn surface_distances(points: &[Vec3], disks: &[Disk]) -> Vec<f32> {
+    let tree_pos: Vec<[f64; 3]> = disks
+        .iter()
+        .map(|d| [d.center.x as f64, d.center.y as f64, d.center.z as f64])
+        .collect();
+    let indices: Vec<usize> = (0..tree_pos.len()).collect();
+    let tree = BallTree::new(tree_pos, indices);
+
+    let mut distances = Vec::with_capacity(points.len());
+
+    for (i, p) in points.iter().enumerate() {
+        // Occasionally yield.
+        if i % 5000 == 0 {
+            tokio_wasm::task::yield_now().await;
+        }
+
+        let pos = [p.x as f64, p.y as f64, p.z as f64];
+        let distance = tree
+            .query()
+            .nn(&pos)
+            .take(NEIGHBOURS)
+            .map(|(_, _, &nearest)| disks[nearest].distance(*p))
+            .fold(f32::INFINITY, f32::min);
+        distances.push(distance);
+    }
+    distances
+}
+
+/// Compute the distance from each reference point to the surface of the splats.
+///
+/// Each splat is approximated as a disk along its shortest axis, which works well for trained
+/// scenes where splats flatten out along surfaces, and the distance is to the closest of the
+/// disks near the point. Mostly transparent splats, like floaters, aren't counted as surface. The
+/// distance is unsigned, as splats don't have an inside and outside.
+pub async fn compute_deviation<B: Backend>(
+    geometry: &ReferenceGeometry,
+    splats: Splats<B>,
+) -> anyhow::Result<DeviationResult> {
+    let read_err = |e: DataError| anyhow!("Failed to read data from splat {e:?}");
+    let means: Vec<f32> = splats
+        .means
+        .val()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(read_err)?;
+    let log_scales: Vec<f32> = splats
+        .log_scales
+        .val()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(read_err)?;
+    let rotations: Vec<f32> = splats
+        .rotations_normed()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(read_err)?;
+    let opacities: Vec<f32> = splats
+        .opacity()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(read_err)?;
+
+    let disks = surface_disks(&means, &log_scales, &rotations, &opacities);
+    if disks.is_empty() {
+        anyhow::bail!("No opaque splats to compare against.");
+    }
+
+    let distances = surface_distances(&geometry.points, &disks).await;
+    let stats = DeviationStats::from_distances(&distances);
+    Ok(DeviationResult { distances, stats })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{surface_disks, surface_distances};
+    use glam::{Vec3, vec3};
+
+    #[tokio::test]
+    async fn distance_to_a_plane() {
+        let mut means = vec![];
+        let mut log_scales = vec![];
+        let mut rotations = vec![];
+        let mut opacities = vec![];
+        // Flat, opaque splats on the z = 0 plane, with transparent floaters above it.
+        for (z, opacity) in [(0.0, 0.9), (0.25, 0.05)] {
+            for x in 0..10 {
+                for y in 0..10 {
+                    means.extend([x as f32 * 0.1, y as f32 * 0.1, z]);
+                    log_scales.extend([0.05f32.ln(), 0.05f32.ln(), 0.001f32.ln()]);
+                    rotations.extend([1.0, 0.0, 0.0, 0.0]);
+                    opacities.push(opacity);
+                }
+            }
+        }
+        let disks = surface_disks(&means, &log_scales, &rotations, &opacities);
+        assert_eq!(disks.len(), 100);
+        assert!(
+            disks
+                .iter()
+                .all(|d| d.normal.abs().abs_diff_eq(Vec3::Z, 1e-6))
+        );
+
+        // Points above and below the plane are as far from it as they're offset.
+        let points: Vec<Vec3> = [0.25, -0.1]
+            .into_iter()
+            .flat_map(|z| (0..10).map(move |i| vec3(i as f32 * 0.1 + 0.03, 0.42, z)))
+            .collect();
+        let distances = surface_distances(&points, &disks).await;
+        for (point, distance) in points.iter().zip(distances) {
+            assert!((distance - point.z.abs()).abs() < 1e-5);
+        }
+
+        // Beyond the edge of the plane, the distance is to its edge.
+        let beyond = [vec3(1.5, 0.42, 0.0), vec3(1.5, 0.42, 0.3)];
+        let distances = surface_distances(&beyond, &disks).await;
+        let edge = 0.9 + 2.0 * 0.05;
+        assert!((distances[0] - (1.5 - edge)).abs() < 1e-4);
+        assert!((distances[1] - (1.5f32 - edge).hypot(0.3)).abs() < 1e-4);
+    }
+}
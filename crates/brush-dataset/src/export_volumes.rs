@@ -0,0 +1,187 @@
+//! Volumes to keep splats inside of when exporting, to ship only the object of interest of a
+//! cluttered capture. Splats near the edge of the volumes fade out instead of being cut off.
+
+use anyhow::anyhow;
+use brush_render::gaussian_splats::{Splats, inverse_sigmoid};
+use burn::prelude::Backend;
+use burn::tensor::{DataError, Int, Tensor, TensorData};
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "kebab-case")]
+pub enum ExportVolume {
+    Box {
+        center: Vec3,
+        /// Half the size of the box along each of its axes.
+        half_extent: Vec3,
+        #[serde(default)]
+        rotation: Quat,
+    },
+    Sphere {
+        center: Vec3,
+        radius: f32,
+    },
+}
+
+impl ExportVolume {
+    /// Distance from `point` to the volume, zero inside of it.
+    pub fn distance(&self, point: Vec3) -> f32 {
+        match *self {
+            Self::Box {
+                center,
+                half_extent,
+                rotation,
+            } => {
+                let local = rotation.inverse() * (point - center);
+                (local.abs() - half_extent).max(Vec3::ZERO).length()
+            }
+            Self::Sphere { center, radius } => (point.distance(center) - radius).max(0.0),
+        }
+    }
+}
+
+/// The splats to keep when exporting, those inside any of the volumes. Without volumes, all
+/// splats are kept.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportVolumes {
+    pub volumes: Vec<ExportVolume>,
+    /// Distance outside of the volumes over which the opacity of the splats fades out.
+    #[serde(default)]
+    pub falloff: f32,
+}
+
+impl ExportVolumes {
+    pub fn is_empty(&self) -> bool {
+        self.volumes.is_empty()
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn from_json(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    /// How much of the opacity of a splat at `point` is kept, from 1 inside the volumes to 0
+    /// at the falloff distance outside of them.
+    pub fn weight(&self, point: Vec3) -> f32 {
+        if self.volumes.is_empty() {
+            return 1.0;
+        }
+        let distance = self
+            .volumes
+            .iter()
+            .map(|volume| volume.distance(point))
+            .fold(f32::INFINITY, f32::min);
+        if distance <= 0.0 {
+            1.0
+        } else if self.falloff > 0.0 {
+            // Smoothstep, so the fade doesn't have a visible edge either.
+            let t = (1.0 - distance / self.falloff).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        } else {
+            0.0
+        }
+    }
+
+    /// The splats inside the volumes, with the splats near their edges faded out. Fails when
+    /// none of the splats are inside the volumes.
+    pub async fn apply<B: Backend>(&self, splats: Splats<B>) -> anyhow::Result<Splats<B>> {
+        if self.is_empty() {
+            return Ok(splats);
+        }
+
+        let read_err = |e: DataError| anyhow!("Failed to read data from splat {e:?}");
+        let means: Vec<f32> = splats
+            .means
+            .val()
+            .into_data_async()
+            .await
+            .to_vec()
+            .map_err(read_err)?;
+        let raw_opacity: Vec<f32> = splats
+            .raw_opacity
+            .val()
+            .into_data_async()
+            .await
+            .to_vec()
+            .map_err(read_err)?;
+
+        let mut kept = vec![];
+        let mut opacity = vec![];
+        for (i, (mean, raw)) in means.chunks_exact(3).zip(&raw_opacity).enumerate() {
+            let weight = self.weight(Vec3::from_slice(mean));
+            if weight > 0.0 {
+                kept.push(i as i32);
+                let faded = (1.0 / (1.0 + (-raw).exp())) * weight;
+                opacity.push(if weight < 1.0 {
+                    inverse_sigmoid(faded)
+                } else {
+                    *raw
+                });
+            }
+        }
+
+        anyhow::ensure!(
+            !kept.is_empty(),
+            "None of the {} splats are inside the export volumes",
+            raw_opacity.len()
+        );
+
+        let device = splats.device();
+        let num = kept.len();
+        let indices = Tensor::<B, 1, Int>::from_ints(kept.as_slice(), &device);
+        Ok(Splats::from_tensor_data(
+            splats.means.val().select(0, indices.clone()),
+            splats.rotation.val().select(0, indices.clone()),
+            splats.log_scales.val().select(0, indices.clone()),
+            splats.sh_coeffs.val().select(0, indices),
+            Tensor::from_data(TensorData::new(opacity, [num]), &device),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falloff_outside_volumes() {
+        let volumes = ExportVolumes {
+            volumes: vec![
+                ExportVolume::Sphere {
+                    center: Vec3::ZERO,
+                    radius: 1.0,
+                },
+                ExportVolume::Box {
+                    center: Vec3::X * 5.0,
+                    half_extent: Vec3::ONE,
+                    rotation: Quat::IDENTITY,
+                },
+            ],
+            falloff: 1.0,
+        };
+        assert_eq!(
+            volumes.weight(Vec3::new(0.5, 0.0, 0.0)).to_bits(),
+            1f32.to_bits()
+        );
+        assert_eq!(
+            volumes.weight(Vec3::new(5.9, 0.9, 0.0)).to_bits(),
+            1f32.to_bits()
+        );
+        assert_eq!(
+            volumes.weight(Vec3::new(1.5, 0.0, 0.0)).to_bits(),
+            0.5f32.to_bits()
+        );
+        assert_eq!(
+            volumes.weight(Vec3::new(0.0, 3.0, 0.0)).to_bits(),
+            0f32.to_bits()
+        );
+
+        let json = volumes.to_json().expect("Volumes should serialize");
+        let read = ExportVolumes::from_json(&json).expect("Volumes should deserialize");
+        assert_eq!(read, volumes);
+    }
+}
@@ -8,21 +8,27 @@ use super::DataStream;
 use crate::{
     Dataset, LoadDataseConfig,
     brush_vfs::BrushVfs,
-    formats::{clamp_img_to_max_size, find_mask_path, load_image},
+    formats::{find_mask_path, load_image},
+    intrinsics::IntrinsicsOverrides,
+    preprocess::{
+        ColorNormalizer, DuplicateFilter, PreprocessReport, Preprocessed, SkyMasker,
+        load_color_calibration, preprocess_view,
+    },
     splat_import::SplatMessage,
     stream_fut_parallel,
 };
 use anyhow::{Context, Result};
 use async_fn_stream::try_fn_stream;
 use brush_render::{
-    camera::{self, Camera},
+    camera::{self, Camera, Distortion},
     gaussian_splats::Splats,
     render::rgb_to_sh,
 };
 use brush_train::scene::SceneView;
 use burn::prelude::Backend;
+use colmap_reader::CameraModel;
 use glam::Vec3;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tokio_stream::StreamExt;
 
 fn find_base_path(archive: &BrushVfs, search_path: &str) -> Option<PathBuf> {
@@ -64,10 +70,67 @@ fn find_mask_and_img(vfs: &BrushVfs, paths: &[PathBuf]) -> Result<(PathBuf, Opti
         .context("No candidates found")
 }
 
+/// Lens distortion of a COLMAP camera. Only the radial-tangential part of the full OpenCV model
+/// is kept, fisheye models aren't supported.
+fn camera_distortion(cam_data: &colmap_reader::Camera) -> Distortion {
+    let p = |i: usize| cam_data.params.get(i).copied().unwrap_or(0.0) as f32;
+    match cam_data.model {
+        CameraModel::SimpleRadial => Distortion {
+            k1: p(3),
+            ..Distortion::NONE
+        },
+        CameraModel::Radial => Distortion {
+            k1: p(3),
+            k2: p(4),
+            ..Distortion::NONE
+        },
+        CameraModel::OpenCV => Distortion {
+            k1: p(4),
+            k2: p(5),
+            p1: p(6),
+            p2: p(7),
+            ..Distortion::NONE
+        },
+        CameraModel::FullOpenCV => Distortion {
+            k1: p(4),
+            k2: p(5),
+            p1: p(6),
+            p2: p(7),
+            k3: p(8),
+        },
+        _ => Distortion::NONE,
+    }
+}
+
+async fn read_points(vfs: &mut BrushVfs) -> Option<HashMap<i64, colmap_reader::Point3D>> {
+    let points_path = vfs.file_names().find(|p| {
+        if let Some(path) = p.to_str().map(|p| p.to_lowercase()) {
+            path.ends_with("points3d.txt") || path.ends_with("points3d.bin")
+        } else {
+            false
+        }
+    })?;
+
+    let is_binary = matches!(
+        points_path.extension().and_then(|p| p.to_str()),
+        Some("bin")
+    );
+
+    let mut points_file = vfs.open_path(&points_path).await.ok()?;
+    // Ignore broken points data, the dataset can be trained without it.
+    colmap_reader::read_points3d(&mut points_file, is_binary)
+        .await
+        .ok()
+}
+
 async fn read_views(
     vfs: BrushVfs,
     load_args: LoadDataseConfig,
-) -> Result<Vec<impl Future<Output = Result<SceneView>>>> {
+    points: Arc<HashMap<i64, colmap_reader::Point3D>>,
+) -> Result<(
+    Vec<impl Future<Output = Result<Preprocessed>>>,
+    BTreeMap<u32, String>,
+)> {
     log::info!("Loading colmap dataset");
     let mut vfs = vfs;
 
@@ -96,6 +159,11 @@ async fn read_views(
         colmap_reader::read_images(&mut buf_reader, is_binary).await?
     };
 
+    let camera_models = cam_model_data
+        .values()
+        .map(|cam| (cam.id.max(0) as u32, cam.model.name().to_owned()))
+        .collect();
+
     let mut img_info_list = img_infos.into_iter().collect::<Vec<_>>();
 
     log::info!("Loading colmap dataset with {} images", img_info_list.len());
@@ -109,6 +177,8 @@ async fn read_views(
         .map(move |(_, img_info)| {
             let cam_data = cam_model_data[&img_info.camera_id].clone();
             let mut vfs = vfs.clone();
+            let load_args = load_args.clone();
+            let points = points.clone();
 
             // Create a future to handle loading the image.
             async move {
@@ -134,28 +204,37 @@ async fn read_views(
                     .await
                     .with_context(|| format!("Failed to load image {}", img_info.name))?;
 
-                let image = clamp_img_to_max_size(Arc::new(image), load_args.max_resolution);
-
                 // Convert w2c to c2w.
                 let world_to_cam =
                     glam::Affine3A::from_rotation_translation(img_info.quat, img_info.tvec);
                 let cam_to_world = world_to_cam.inverse();
                 let (_, quat, translation) = cam_to_world.to_scale_rotation_translation();
 
-                let camera = Camera::new(translation, quat, fovx, fovy, center_uv);
+                let mut camera = Camera::new(translation, quat, fovx, fovy, center_uv);
+                camera.distortion = camera_distortion(&cam_data);
+
+                let size = glam::vec2(cam_data.width as f32, cam_data.height as f32);
+                let sparse_points = img_info
+                    .xys
+                    .iter()
+                    .zip(&img_info.point3d_ids)
+                    .filter_map(|(xy, id)| Some((*xy / size, points.get(id)?.xyz)))
+                    .collect();
 
                 let view = SceneView {
                     path: path.to_string_lossy().to_string(),
                     camera,
-                    image,
+                    image: Arc::new(image),
                     img_type,
+                    camera_group: img_info.camera_id.max(0) as u32,
+                    sparse_points: Arc::new(sparse_points),
                 };
-                Ok(view)
+                Ok(preprocess_view(view, &load_args))
             }
         })
         .collect();
 
-    Ok(handles)
+    Ok((handles, camera_models))
 }
 
 pub(crate) async fn load_dataset<B: Backend>(
@@ -163,7 +242,9 @@ pub(crate) async fn load_dataset<B: Backend>(
     load_args: &LoadDataseConfig,
     device: &B::Device,
 ) -> Result<(DataStream<SplatMessage<B>>, DataStream<Dataset>)> {
-    let mut handles = read_views(vfs.clone(), load_args.clone()).await?;
+    let points_data = Arc::new(read_points(&mut vfs).await.unwrap_or_default());
+    let (mut handles, camera_models) =
+        read_views(vfs.clone(), load_args.clone(), points_data.clone()).await?;
 
     if let Some(subsample) = load_args.subsample_frames {
         handles = handles.into_iter().step_by(subsample as usize).collect();
@@ -171,93 +252,90 @@ pub(crate) async fn load_dataset<B: Backend>(
 
     let mut train_views = vec![];
     let mut eval_views = vec![];
+    let mut report = PreprocessReport {
+        color_calibration: load_color_calibration(&mut vfs, load_args).await?,
+        camera_models,
+        ..Default::default()
+    };
+    let sky = SkyMasker::new(&mut vfs, load_args).await?;
+    let colors = ColorNormalizer::new(load_args, report.color_calibration.clone());
+    let handles = colors.normalize(sky.mask(handles));
+    let mut duplicates = DuplicateFilter::new(load_args);
+    let intrinsics = IntrinsicsOverrides::new(load_args)?;
 
     let load_args = load_args.clone();
     let device = device.clone();
 
     let mut i = 0;
     let stream = stream_fut_parallel(handles).map(move |view| {
-        let view = view.context("Failed to load COLMAP view")?;
-
-        if let Some(eval_period) = load_args.eval_split_every {
-            if i % eval_period == 0 {
-                eval_views.push(view);
-            } else {
-                train_views.push(view);
+        let (view, correction) = view.context("Failed to load COLMAP view")?;
+        match intrinsics.apply(duplicates.filter(view), &mut report) {
+            Preprocessed::View(view) => {
+                if let Some(correction) = correction {
+                    report
+                        .color_calibration
+                        .views
+                        .insert(view.path.clone(), correction);
+                }
+                if let Some(eval_period) = load_args.eval_split_every {
+                    if i % eval_period == 0 {
+                        eval_views.push(view);
+                    } else {
+                        train_views.push(view);
+                    }
+                } else {
+                    train_views.push(view);
+                }
+                i += 1;
             }
-        } else {
-            train_views.push(view);
+            Preprocessed::Skipped(skip) => report.skipped.push(skip),
         }
 
-        i += 1;
-        Ok(Dataset::from_views(train_views.clone(), eval_views.clone()))
+        Ok(Dataset::from_views(
+            train_views.clone(),
+            eval_views.clone(),
+            report.clone(),
+        ))
     });
 
     let init_stream = try_fn_stream(|emitter| async move {
-        let points_path = vfs.file_names().find(|p| {
-            if let Some(path) = p.to_str().map(|p| p.to_lowercase()) {
-                path.ends_with("points3d.txt") || path.ends_with("points3d.bin")
-            } else {
-                false
-            }
-        });
-
-        let Some(points_path) = points_path else {
-            return Ok(());
-        };
-
-        let is_binary = matches!(
-            points_path.extension().and_then(|p| p.to_str()),
-            Some("bin")
-        );
-
-        // Extract COLMAP sfm points.
-        let points_data = {
-            let mut points_file = vfs
-                .open_path(&points_path)
-                .await
-                .context("Failed to read COLMAP points file")?;
-            colmap_reader::read_points3d(&mut points_file, is_binary).await
-        };
-
         // Ignore empty points data.
-        if let Ok(points_data) = points_data {
-            if !points_data.is_empty() {
-                log::info!("Starting from colmap points {}", points_data.len());
-
-                let mut positions: Vec<Vec3> = points_data.values().map(|p| p.xyz).collect();
-                let mut colors: Vec<f32> = points_data
-                    .values()
-                    .flat_map(|p| {
-                        [
-                            rgb_to_sh(p.rgb[0] as f32 / 255.0),
-                            rgb_to_sh(p.rgb[1] as f32 / 255.0),
-                            rgb_to_sh(p.rgb[2] as f32 / 255.0),
-                        ]
-                    })
-                    .collect();
-
-                // Other dataloaders handle subsampling in the ply import. Here just
-                // do it manually, maybe nice to unify at some point.
-                if let Some(subsample) = load_args.subsample_points {
-                    positions = positions.into_iter().step_by(subsample as usize).collect();
-                    colors = colors.into_iter().step_by(subsample as usize * 3).collect();
-                }
-
-                let init_splat =
-                    Splats::from_raw(&positions, None, None, Some(&colors), None, &device);
-                emitter
-                    .emit(SplatMessage {
-                        meta: crate::splat_import::SplatMetadata {
-                            up_axis: None,
-                            total_splats: init_splat.num_splats(),
-                            frame_count: 1,
-                            current_frame: 0,
-                        },
-                        splats: init_splat,
-                    })
-                    .await;
+        if !points_data.is_empty() {
+            log::info!("Starting from colmap points {}", points_data.len());
+
+            let mut positions: Vec<Vec3> = points_data.values().map(|p| p.xyz).collect();
+            let mut colors: Vec<f32> = points_data
+                .values()
+                .flat_map(|p| {
+                    [
+                        rgb_to_sh(p.rgb[0] as f32 / 255.0),
+                        rgb_to_sh(p.rgb[1] as f32 / 255.0),
+                        rgb_to_sh(p.rgb[2] as f32 / 255.0),
+                    ]
+                })
+                .collect();
+
+            // Other dataloaders handle subsampling in the ply import. Here just
+            // do it manually, maybe nice to unify at some point.
+            if let Some(subsample) = load_args.subsample_points {
+                positions = positions.into_iter().step_by(subsample as usize).collect();
+                colors = colors.into_iter().step_by(subsample as usize * 3).collect();
             }
+
+            let init_splat = Splats::from_raw(&positions, None, None, Some(&colors), None, &device);
+            emitter
+                .emit(SplatMessage {
+                    meta: crate::splat_import::SplatMetadata {
+                        up_axis: None,
+                        total_splats: init_splat.num_splats(),
+                        frame_count: 1,
+                        current_frame: 0,
+                        report: Default::default(),
+                        trained: false,
+                    },
+                    splats: init_splat,
+                })
+                .await;
         }
 
         Ok(())
@@ -1,8 +1,11 @@
 use crate::{
-    Dataset, LoadDataseConfig, WasmNotSend,
+    Dataset, LoadDataseConfig, ModelConfig, WasmNotSend,
     brush_vfs::BrushVfs,
-    splat_import::{SplatMessage, load_splat_from_ply},
+    pointcloud_import::{is_point_cloud_path, point_cloud_init, read_point_cloud},
+    splat_import::{SplatMessage, load_splat_from_ply, ply_vertex_count},
 };
+use anyhow::Context;
+use async_fn_stream::try_fn_stream;
 use brush_train::scene::ViewImageType;
 use burn::prelude::Backend;
 use image::DynamicImage;
@@ -13,7 +16,7 @@ use std::{
     sync::Arc,
 };
 use tokio::io::AsyncReadExt;
-use tokio_stream::Stream;
+use tokio_stream::{Stream, StreamExt};
 
 pub mod colmap;
 pub mod nerfstudio;
@@ -25,6 +28,7 @@ pub type DataStream<T> = Pin<Box<dyn DynStream<anyhow::Result<T>> + 'static>>;
 pub async fn load_dataset<B: Backend>(
     mut vfs: BrushVfs,
     load_args: &LoadDataseConfig,
+    model_args: &ModelConfig,
     device: &B::Device,
 ) -> anyhow::Result<(DataStream<SplatMessage<B>>, DataStream<Dataset>)> {
     let mut err_context = anyhow::anyhow!("Attempting to load dataset.");
@@ -53,27 +57,88 @@ pub async fn load_dataset<B: Backend>(
         }
     };
 
-    // If there's an initial ply file, override the init stream with that.
-    let path: Vec<_> = vfs
-        .file_names()
-        .filter(|x| x.extension().is_some_and(|ext| ext == "ply"))
-        .collect();
-
-    let init_stream = if path.len() == 1 {
-        let main_path = path.first().expect("unreachable");
-        log::info!("Using ply {main_path:?} as initial point cloud.");
-
-        let reader = vfs.open_path(main_path).await?;
-        Box::pin(load_splat_from_ply(
-            reader,
-            load_args.subsample_points,
-            device.clone(),
-        ))
+    let init_path = if let Some(init_path) = &model_args.init_path {
+        let path = vfs
+            .file_names()
+            .find(|p| p.ends_with(init_path))
+            .with_context(|| format!("Initial point cloud {init_path} not found in dataset"))?;
+        Some(path)
     } else {
-        stream.0
+        // If there's a single initial ply file, or otherwise a single lidar point cloud,
+        // override the init stream with that. A dense COLMAP cloud is only used when asked for.
+        let plys: Vec<_> = vfs
+            .file_names()
+            .filter(|x| x.extension().is_some_and(|ext| ext == "ply") && !is_dense_cloud(x))
+            .collect();
+        let clouds: Vec<_> = vfs
+            .file_names()
+            .filter(|p| is_point_cloud_path(p))
+            .collect();
+        let dense = vfs.file_names().find(|p| is_dense_cloud(p));
+
+        if load_args.dense_init && dense.is_some() {
+            dense
+        } else if plys.len() == 1 {
+            plys.into_iter().next()
+        } else if plys.is_empty() && clouds.len() == 1 {
+            clouds.into_iter().next()
+        } else {
+            None
+        }
+    };
+
+    let (init_stream, dataset_stream) = stream;
+    let mut dataset_stream = dataset_stream;
+
+    let init_stream: DataStream<SplatMessage<B>> = if let Some(init_path) = init_path {
+        log::info!("Using {init_path:?} as initial point cloud.");
+        let reader = vfs.open_path(&init_path).await?;
+
+        if is_point_cloud_path(&init_path) {
+            // Read the point cloud up front, the cameras have to be moved along with it.
+            let cloud = read_point_cloud(reader, &init_path).await?;
+            let (message, offset) =
+                point_cloud_init(&cloud, &init_path, load_args.subsample_points, device);
+            if offset != glam::DVec3::ZERO {
+                log::info!(
+                    "Point cloud is far from the origin, moving it and the cameras by {offset}"
+                );
+                dataset_stream =
+                    Box::pin(dataset_stream.map(move |d| d.map(|d| d.translated(offset))));
+            }
+            Box::pin(try_fn_stream(|emitter| async move {
+                emitter.emit(message).await;
+                Ok(())
+            }))
+        } else if is_dense_cloud(&init_path) {
+            // Dense clouds can easily have tens of millions of points, subsample to
+            // stay within budget.
+            let count = ply_vertex_count(reader).await?;
+            let budget = load_args.dense_init_max_points.max(1) as usize;
+            let stride = (count.div_ceil(budget) as u32)
+                .max(load_args.subsample_points.unwrap_or(1))
+                .max(1);
+            log::info!("Dense point cloud has {count} points, taking every {stride}th point.");
+
+            let reader = vfs.open_path(&init_path).await?;
+            Box::pin(load_splat_from_ply(reader, Some(stride), device.clone()))
+        } else {
+            Box::pin(load_splat_from_ply(
+                reader,
+                load_args.subsample_points,
+                device.clone(),
+            ))
+        }
+    } else {
+        init_stream
     };
 
-    Ok((init_stream, stream.1))
+    Ok((init_stream, dataset_stream))
+}
+
+fn is_dense_cloud(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.eq_ignore_ascii_case("fused.ply"))
 }
 
 fn find_mask_path(vfs: &BrushVfs, path: &Path) -> Option<PathBuf> {
@@ -1,10 +1,17 @@
 use super::DataStream;
-use super::clamp_img_to_max_size;
 use super::find_mask_path;
 use super::load_image;
 use crate::Dataset;
 use crate::LoadDataseConfig;
 use crate::brush_vfs::BrushVfs;
+use crate::intrinsics::IntrinsicsOverrides;
+use crate::preprocess::ColorNormalizer;
+use crate::preprocess::DuplicateFilter;
+use crate::preprocess::PreprocessReport;
+use crate::preprocess::Preprocessed;
+use crate::preprocess::SkyMasker;
+use crate::preprocess::load_color_calibration;
+use crate::preprocess::preprocess_view;
 use crate::splat_import::SplatMessage;
 use crate::splat_import::load_splat_from_ply;
 use crate::stream_fut_parallel;
@@ -12,9 +19,10 @@ use anyhow::Context;
 use anyhow::Result;
 use async_fn_stream::try_fn_stream;
 use brush_render::camera::fov_to_focal;
-use brush_render::camera::{Camera, focal_to_fov};
+use brush_render::camera::{Camera, Distortion, focal_to_fov};
 use brush_train::scene::SceneView;
 use burn::prelude::Backend;
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::path::Path;
 use std::sync::Arc;
@@ -22,7 +30,7 @@ use tokio::io::AsyncReadExt;
 use tokio_stream::StreamExt;
 
 #[derive(serde::Deserialize, Clone)]
-#[allow(unused)] // not reading camera distortions yet.
+#[allow(unused)] // not reading fisheye distortions yet.
 struct JsonScene {
     // Horizontal FOV.
     camera_angle_x: Option<f64>,
@@ -34,7 +42,7 @@ struct JsonScene {
     /// Focal length y
     fl_y: Option<f64>,
 
-    // Not really used atm.
+    // Only used to tell fisheye cameras apart.
     camera_model: Option<String>,
     // Nerfstudio doesn't mention this in their format? But fine to include really.
     ply_file_path: Option<String>,
@@ -65,7 +73,7 @@ struct JsonScene {
 }
 
 #[derive(serde::Deserialize, Clone)]
-#[allow(unused)] // not reading camera distortions yet.
+#[allow(unused)] // not reading fisheye distortions yet.
 struct FrameData {
     // Horizontal FOV.
     camera_angle_x: Option<f64>,
@@ -86,7 +94,7 @@ struct FrameData {
     /// Image height. Should be an integer but read as float, fine to truncate.
     h: Option<f64>,
 
-    // Nb: These are unused currently until we can optimize distorted cameras.
+    // Nb: Splats are rendered without distortion, these are only used to compare with the images.
     /// First radial distortion parameter used by [`OPENCV`, `OPENCV_FISHEYE`]
     k1: Option<f64>,
     /// Second radial distortion parameter used by [`OPENCV`, `OPENCV_FISHEYE`]
@@ -104,17 +112,89 @@ struct FrameData {
     file_path: String,
 }
 
+impl FrameData {
+    fn has_intrinsics(&self) -> bool {
+        self.camera_angle_x.is_some()
+            || self.camera_angle_y.is_some()
+            || self.fl_x.is_some()
+            || self.fl_y.is_some()
+            || self.cx.is_some()
+            || self.cy.is_some()
+    }
+
+    /// The intrinsics this frame sets itself, compared bitwise.
+    fn intrinsics_key(&self) -> [Option<u64>; 14] {
+        [
+            self.camera_angle_x,
+            self.camera_angle_y,
+            self.fl_x,
+            self.fl_y,
+            self.cx,
+            self.cy,
+            self.w,
+            self.h,
+            self.k1,
+            self.k2,
+            self.k3,
+            self.k4,
+            self.p1,
+            self.p2,
+        ]
+        .map(|v| v.map(f64::to_bits))
+    }
+}
+
+/// Puts frames with identical intrinsics in the same camera group. Frames without their own
+/// intrinsics use the scene intrinsics and share group 0.
+#[derive(Default)]
+struct CameraGroups {
+    intrinsics: Vec<[Option<u64>; 14]>,
+    /// Camera model of each group, as given by the transforms file.
+    camera_models: BTreeMap<u32, String>,
+}
+
+impl CameraGroups {
+    fn group(&mut self, frame: &FrameData, camera_model: Option<&str>) -> u32 {
+        let group = if frame.has_intrinsics() {
+            let key = frame.intrinsics_key();
+            let index = self
+                .intrinsics
+                .iter()
+                .position(|k| *k == key)
+                .unwrap_or_else(|| {
+                    self.intrinsics.push(key);
+                    self.intrinsics.len() - 1
+                });
+            index as u32 + 1
+        } else {
+            0
+        };
+        if let Some(model) = camera_model {
+            self.camera_models
+                .entry(group)
+                .or_insert_with(|| model.to_owned());
+        }
+        group
+    }
+}
+
 fn read_transforms_file(
     scene: JsonScene,
     transforms_path: &Path,
     vfs: BrushVfs,
     load_args: &LoadDataseConfig,
-) -> Vec<impl Future<Output = anyhow::Result<SceneView>> + use<>> {
+    groups: &mut CameraGroups,
+) -> Vec<impl Future<Output = anyhow::Result<Preprocessed>> + use<>> {
+    let fisheye = scene
+        .camera_model
+        .as_deref()
+        .is_some_and(|model| model.contains("FISHEYE"));
     let iter = scene
         .frames
         .into_iter()
         .take(load_args.max_frames.unwrap_or(usize::MAX))
         .map(move |frame| {
+            let camera_group = groups.group(&frame, scene.camera_model.as_deref());
             let mut archive = vfs.clone();
             let load_args = load_args.clone();
             let transforms_path = transforms_path.to_path_buf();
@@ -151,8 +231,6 @@ fn read_transforms_file(
                 let w = frame.w.or(scene.w).unwrap_or(image.width() as f64) as u32;
                 let h = frame.h.or(scene.h).unwrap_or(image.height() as f64) as u32;
 
-                let image = clamp_img_to_max_size(image, load_args.max_resolution);
-
                 let fovx = frame
                     .camera_angle_x
                     .or(frame.fl_x.map(|fx| focal_to_fov(fx, w)))
@@ -183,13 +261,29 @@ fn read_transforms_file(
 
                 let cuv = glam::vec2((cx / w as f64) as f32, (cy / h as f64) as f32);
 
+                let mut camera = Camera::new(translation, rotation, fovx, fovy, cuv);
+                if !fisheye {
+                    let param = |frame: Option<f64>, scene: Option<f64>| {
+                        frame.or(scene).unwrap_or(0.0) as f32
+                    };
+                    camera.distortion = Distortion {
+                        k1: param(frame.k1, scene.k1),
+                        k2: param(frame.k2, scene.k2),
+                        k3: param(frame.k3, scene.k3),
+                        p1: param(frame.p1, scene.p1),
+                        p2: param(frame.p2, scene.p2),
+                    };
+                }
+
                 let view = SceneView {
                     path: frame.file_path.clone(),
-                    camera: Camera::new(translation, rotation, fovx, fovy, cuv),
+                    camera,
                     image,
                     img_type,
+                    camera_group,
+                    sparse_points: Arc::new(vec![]),
                 };
-                anyhow::Result::<SceneView>::Ok(view)
+                anyhow::Result::<Preprocessed>::Ok(preprocess_view(view, &load_args))
             }
         });
 
@@ -228,11 +322,14 @@ pub async fn read_dataset<B: Backend>(
         .await?;
     let train_scene: JsonScene = serde_json::from_str(&buf)?;
 
+    // Eval frames with the same intrinsics as training frames share their group.
+    let mut groups = CameraGroups::default();
     let mut train_handles = read_transforms_file(
         train_scene.clone(),
         &transforms_path,
         vfs.clone(),
         load_args,
+        &mut groups,
     );
 
     if let Some(subsample) = load_args.subsample_frames {
@@ -249,6 +346,11 @@ pub async fn read_dataset<B: Backend>(
     let dataset_stream = try_fn_stream(|emitter| async move {
         let mut train_views = vec![];
         let mut eval_views = vec![];
+        let mut report = PreprocessReport {
+            color_calibration: load_color_calibration(&mut data_clone, &load_args_clone).await?,
+            ..Default::default()
+        };
+        let sky = SkyMasker::new(&mut data_clone, &load_args_clone).await?;
 
         // Use transforms_val as eval, or _test if no _val is present. (Brush doesn't really have any notion of a test
         let eval_trans_path = json_files
@@ -278,17 +380,36 @@ pub async fn read_dataset<B: Backend>(
                 eval_trans_path,
                 data_clone,
                 &load_args_clone,
+                &mut groups,
             ))
         } else {
             None
         };
+        report.camera_models = groups.camera_models;
 
-        let train_handles = stream_fut_parallel(train_handles);
+        let colors = ColorNormalizer::new(&load_args_clone, report.color_calibration.clone());
+        let train_handles = stream_fut_parallel(colors.normalize(sky.mask(train_handles)));
         let mut train_handles = std::pin::pin!(train_handles);
 
+        let mut duplicates = DuplicateFilter::new(&load_args_clone);
+        let intrinsics = IntrinsicsOverrides::new(&load_args_clone)?;
         let mut i = 0;
         while let Some(view) = train_handles.next().await {
-            let view = view.context("Failed to load training view from json")?;
+            let (view, correction) = view.context("Failed to load training view from json")?;
+            let view = match intrinsics.apply(duplicates.filter(view), &mut report) {
+                Preprocessed::View(view) => view,
+                Preprocessed::Skipped(skip) => {
+                    report.skipped.push(skip);
+                    continue;
+                }
+            };
+
+            if let Some(correction) = correction {
+                report
+                    .color_calibration
+                    .views
+                    .insert(view.path.clone(), correction);
+            }
 
             if let Some(eval_period) = load_args_clone.eval_split_every {
                 // Include extra eval images only when the dataset doesn't have them.
@@ -302,21 +423,42 @@ pub async fn read_dataset<B: Backend>(
             }
 
             emitter
-                .emit(Dataset::from_views(train_views.clone(), eval_views.clone()))
+                .emit(Dataset::from_views(
+                    train_views.clone(),
+                    eval_views.clone(),
+                    report.clone(),
+                ))
                 .await;
 
             i += 1;
         }
 
         if let Some(val_stream) = val_stream {
-            let val_handles = stream_fut_parallel(val_stream);
+            let val_handles = stream_fut_parallel(colors.normalize(sky.mask(val_stream)));
             let mut val_handles = std::pin::pin!(val_handles);
             while let Some(view) = val_handles.next().await {
-                let view = view.context("Failed to load eval view from json")?;
+                let (view, correction) = view.context("Failed to load eval view from json")?;
+                let view = match intrinsics.apply(view, &mut report) {
+                    Preprocessed::View(view) => view,
+                    Preprocessed::Skipped(skip) => {
+                        report.skipped.push(skip);
+                        continue;
+                    }
+                };
+                if let Some(correction) = correction {
+                    report
+                        .color_calibration
+                        .views
+                        .insert(view.path.clone(), correction);
+                }
 
                 eval_views.push(view);
                 emitter
-                    .emit(Dataset::from_views(train_views.clone(), eval_views.clone()))
+                    .emit(Dataset::from_views(
+                        train_views.clone(),
+                        eval_views.clone(),
+                        report.clone(),
+                    ))
                     .await;
             }
         }
@@ -353,3 +495,27 @@ pub async fn read_dataset<B: Backend>(
 
     Ok((Box::pin(splat_stream), Box::pin(dataset_stream)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(json: &str) -> FrameData {
+        let frame = format!(r#"{{"transform_matrix": [], "file_path": "a.png", {json}}}"#);
+        serde_json::from_str(&frame).expect("Valid frame")
+    }
+
+    #[test]
+    fn frames_with_the_same_intrinsics_share_a_group() {
+        let mut groups = CameraGroups::default();
+        let a = frame(r#""fl_x": 500.0, "cx": 320.0"#);
+        let b = frame(r#""fl_x": 600.0, "cx": 320.0"#);
+        let plain = frame(r#""k1": 0.1"#);
+
+        assert_eq!(groups.group(&a, None), 1);
+        assert_eq!(groups.group(&b, None), 2);
+        assert_eq!(groups.group(&a, None), 1);
+        assert_eq!(groups.group(&plain, Some("OPENCV")), 0);
+        assert_eq!(groups.camera_models[&0], "OPENCV");
+    }
+}
@@ -0,0 +1,114 @@
+//! Corrected camera intrinsics, for when structure from motion got them wrong or a dataset
+//! doesn't have them. They're entered per camera group in the dataset panel of the app, and
+//! passed along as [`LoadDataseConfig::intrinsics`].
+
+use anyhow::Context;
+use brush_render::camera::{Camera, Distortion};
+use brush_train::scene::SceneView;
+use serde::{Deserialize, Serialize};
+
+use crate::LoadDataseConfig;
+use crate::preprocess::{PreprocessReport, Preprocessed};
+
+/// Intrinsics of all views of a camera group. They don't depend on the resolution of the
+/// images, and apply to the views as loaded, after cropping.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraIntrinsics {
+    pub camera_group: u32,
+    /// Horizontal field of view, in degrees.
+    pub fov_x: f64,
+    /// Vertical field of view, in degrees.
+    pub fov_y: f64,
+    /// Principal point, in uv coordinates of the image.
+    pub center_uv: [f32; 2],
+    /// Radial distortion.
+    #[serde(default)]
+    pub k1: f32,
+    #[serde(default)]
+    pub k2: f32,
+    #[serde(default)]
+    pub k3: f32,
+    /// Tangential distortion.
+    #[serde(default)]
+    pub p1: f32,
+    #[serde(default)]
+    pub p2: f32,
+}
+
+impl CameraIntrinsics {
+    pub fn of_camera(camera_group: u32, camera: &Camera) -> Self {
+        let Distortion { k1, k2, k3, p1, p2 } = camera.distortion;
+        Self {
+            camera_group,
+            fov_x: camera.fov_x.to_degrees(),
+            fov_y: camera.fov_y.to_degrees(),
+            center_uv: camera.center_uv.to_array(),
+            k1,
+            k2,
+            k3,
+            p1,
+            p2,
+        }
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.fov_x = self.fov_x.to_radians();
+        camera.fov_y = self.fov_y.to_radians();
+        camera.center_uv = self.center_uv.into();
+        camera.distortion = Distortion {
+            k1: self.k1,
+            k2: self.k2,
+            k3: self.k3,
+            p1: self.p1,
+            p2: self.p2,
+        };
+    }
+
+    /// Parse a list of intrinsics, as in [`LoadDataseConfig::intrinsics`].
+    pub fn list_from_json(json: &str) -> anyhow::Result<Vec<Self>> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn list_to_json(intrinsics: &[Self]) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(intrinsics)?)
+    }
+}
+
+/// Replaces the intrinsics of views with those given in [`LoadDataseConfig::intrinsics`].
+pub(crate) struct IntrinsicsOverrides {
+    intrinsics: Vec<CameraIntrinsics>,
+}
+
+impl IntrinsicsOverrides {
+    pub(crate) fn new(load_args: &LoadDataseConfig) -> anyhow::Result<Self> {
+        let intrinsics = load_args
+            .intrinsics
+            .as_deref()
+            .map(CameraIntrinsics::list_from_json)
+            .transpose()
+            .context("Failed to read the camera intrinsics")?
+            .unwrap_or_default();
+        Ok(Self { intrinsics })
+    }
+
+    /// Replaces the intrinsics of the view, and keeps the ones it was loaded with in the report.
+    pub(crate) fn apply(&self, view: Preprocessed, report: &mut PreprocessReport) -> Preprocessed {
+        let Preprocessed::View(view) = view else {
+            return view;
+        };
+        let Some(intrinsics) = self
+            .intrinsics
+            .iter()
+            .find(|i| i.camera_group == view.camera_group)
+        else {
+            return Preprocessed::View(view);
+        };
+        let loaded = &mut report.loaded_intrinsics;
+        if loaded.iter().all(|i| i.camera_group != view.camera_group) {
+            loaded.push(CameraIntrinsics::of_camera(view.camera_group, &view.camera));
+        }
+        let mut camera = view.camera;
+        intrinsics.apply(&mut camera);
+        Preprocessed::View(SceneView { camera, ..view })
+    }
+}
@@ -0,0 +1,211 @@
+use std::{io::Cursor, path::Path};
+
+use anyhow::Context;
+use brush_render::{gaussian_splats::Splats, render::rgb_to_sh};
+use burn::prelude::Backend;
+use glam::{DVec3, Vec3};
+use las::Read as _;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::splat_import::{SplatMessage, SplatMetadata};
+
+/// Whether this is a (lidar) point cloud format that can be used to initialize splats.
+pub fn is_point_cloud_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            ["las", "laz", "e57"]
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e))
+        })
+}
+
+// Coordinates further from the origin than this lose too much precision as f32 (about 1mm at
+// 1e4), so point clouds this far away, eg. georeferenced ones, are moved to the origin.
+const MAX_COORDINATE: f64 = 1e4;
+
+pub(crate) struct PointCloud {
+    // Full precision, as georeferenced coordinates don't fit in a f32.
+    pub(crate) positions: Vec<DVec3>,
+    // Colors in 0-1 range, if the source has any.
+    pub(crate) colors: Option<Vec<[f32; 3]>>,
+}
+
+impl PointCloud {
+    /// The positions as f32, moved by an offset to keep them close to the origin. Returns the
+    /// offset, which has to be added to anything else in the same coordinates, eg. the cameras.
+    pub(crate) fn recentered(&self) -> (Vec<Vec3>, DVec3) {
+        let (min, max) = self.positions.iter().fold(
+            (DVec3::splat(f64::MAX), DVec3::splat(f64::MIN)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let center = (min + max) / 2.0;
+        let offset = if center.abs().max_element() > MAX_COORDINATE {
+            -center
+        } else {
+            DVec3::ZERO
+        };
+        let positions = self
+            .positions
+            .iter()
+            .map(|p| (*p + offset).as_vec3())
+            .collect();
+        (positions, offset)
+    }
+}
+
+pub(crate) fn read_las(data: Vec<u8>) -> anyhow::Result<PointCloud> {
+    let mut reader = las::Reader::new(Cursor::new(data)).context("Invalid LAS/LAZ file")?;
+
+    let num_points = reader.header().number_of_points() as usize;
+    let mut positions = Vec::with_capacity(num_points);
+    let mut colors = Vec::with_capacity(num_points);
+
+    for point in reader.points() {
+        let point = point?;
+        positions.push(glam::dvec3(point.x, point.y, point.z));
+        if let Some(color) = point.color {
+            colors.push([color.red, color.green, color.blue]);
+        }
+    }
+
+    let colors = (colors.len() == positions.len()).then(|| {
+        // LAS stores 16 bit colors, but plenty of writers just store 8 bit values.
+        let max = colors.iter().flatten().copied().max().unwrap_or(0);
+        let scale = if max <= u8::MAX as u16 {
+            u8::MAX as f32
+        } else {
+            u16::MAX as f32
+        };
+        colors
+            .iter()
+            .map(|c| {
+                [
+                    c[0] as f32 / scale,
+                    c[1] as f32 / scale,
+                    c[2] as f32 / scale,
+                ]
+            })
+            .collect()
+    });
+
+    Ok(PointCloud { positions, colors })
+}
+
+fn read_e57(data: Vec<u8>) -> anyhow::Result<PointCloud> {
+    let mut e57 = e57::E57Reader::new(Cursor::new(data)).context("Invalid E57 file")?;
+
+    let mut positions = vec![];
+    let mut colors = vec![];
+    let mut all_have_color = true;
+
+    // E57 files can contain multiple scans, the simple iterator
+    // transforms them all into a common frame.
+    for pc in e57.pointclouds() {
+        all_have_color &= pc.has_color();
+
+        for point in e57.pointcloud_simple(&pc)? {
+            let point = point?;
+            if let e57::CartesianCoordinate::Valid { x, y, z } = point.cartesian {
+                positions.push(glam::dvec3(x, y, z));
+                colors.push([point.color.red, point.color.green, point.color.blue]);
+            }
+        }
+    }
+
+    Ok(PointCloud {
+        positions,
+        colors: all_have_color.then_some(colors),
+    })
+}
+
+/// Read a LAS, LAZ or E57 point cloud, eg. to initialize splats from.
+pub(crate) async fn read_point_cloud(
+    mut reader: impl AsyncRead + Unpin,
+    path: &Path,
+) -> anyhow::Result<PointCloud> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data).await?;
+
+    let is_e57 = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("e57"));
+
+    let cloud = if is_e57 {
+        read_e57(data)?
+    } else {
+        read_las(data)?
+    };
+
+    if cloud.positions.is_empty() {
+        anyhow::bail!("Point cloud {path:?} contains no points.");
+    }
+    Ok(cloud)
+}
+
+/// Initial splats at the points of a point cloud. Also returns the offset the points were moved
+/// by, see [`PointCloud::recentered`].
+pub(crate) fn point_cloud_init<B: Backend>(
+    cloud: &PointCloud,
+    path: &Path,
+    subsample_points: Option<u32>,
+    device: &B::Device,
+) -> (SplatMessage<B>, DVec3) {
+    let (mut positions, offset) = cloud.recentered();
+    let mut colors = cloud.colors.clone();
+
+    if let Some(subsample) = subsample_points {
+        let subsample = subsample.max(1) as usize;
+        positions = positions.into_iter().step_by(subsample).collect();
+        colors = colors.map(|c| c.into_iter().step_by(subsample).collect());
+    }
+
+    log::info!(
+        "Starting from point cloud {path:?} with {} points",
+        positions.len()
+    );
+
+    let sh_coeffs: Option<Vec<f32>> =
+        colors.map(|colors| colors.iter().flat_map(|c| c.map(rgb_to_sh)).collect());
+
+    let splats = Splats::from_raw(&positions, None, None, sh_coeffs.as_deref(), None, device);
+
+    let message = SplatMessage {
+        meta: SplatMetadata {
+            up_axis: None,
+            total_splats: splats.num_splats(),
+            frame_count: 1,
+            current_frame: 0,
+            report: Default::default(),
+            trained: false,
+        },
+        splats,
+    };
+    (message, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PointCloud;
+    use glam::{DVec3, dvec3};
+
+    #[test]
+    fn recentering_keeps_georeferenced_precision() {
+        let base = dvec3(512_345.678, 5_401_234.567, 312.5);
+        let cloud = PointCloud {
+            positions: vec![base, base + DVec3::X * 0.01, base + DVec3::Z * 2.0],
+            colors: None,
+        };
+        let (positions, offset) = cloud.recentered();
+        assert!(offset.length() > 1e6);
+        let step = positions[1] - positions[0];
+        assert!((step.x - 0.01).abs() < 1e-4);
+        assert!(((positions[2].as_dvec3() - offset) - cloud.positions[2]).length() < 1e-4);
+
+        let local = PointCloud {
+            positions: vec![dvec3(1.0, 2.0, 3.0)],
+            colors: None,
+        };
+        assert_eq!(local.recentered().1, DVec3::ZERO);
+    }
+}
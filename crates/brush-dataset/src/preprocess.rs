@@ -0,0 +1,919 @@
+//! Optional preprocessing of views while the dataset loads: cropping, downscaling, matching
+//! colors, and skipping low quality frames, eg. blurry or badly exposed frames from a video.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use brush_inference::{AuxMap, AuxModel, AuxModelKind};
+use brush_render::camera::{focal_to_fov, fov_to_focal};
+use brush_train::scene::{SceneView, ViewImageType};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncReadExt, sync::watch};
+
+use crate::{
+    LoadDataseConfig, WasmNotSend, brush_vfs::BrushVfs, formats::clamp_img_to_max_size,
+    intrinsics::CameraIntrinsics,
+};
+
+// Images are analyzed at this resolution, so thresholds don't depend on the image size.
+const ANALYSIS_RESOLUTION: u32 = 512;
+
+// Brightness (0-255) at which pixels count as crushed to black or blown out to white.
+const UNDEREXPOSED_LEVEL: u8 = 5;
+const OVEREXPOSED_LEVEL: u8 = 250;
+
+// Images are compared at this resolution to find duplicates.
+const DUPLICATE_RESOLUTION: u32 = 64;
+// Frames that look the other way aren't duplicates, however similar they look.
+const DUPLICATE_MAX_ANGLE: f32 = 5.0;
+
+/// Why a view was skipped while loading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    Blurry,
+    Overexposed,
+    Underexposed,
+    /// Nearly identical to the frame before it.
+    Duplicate,
+}
+
+impl SkipReason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Blurry => "blurry",
+            Self::Overexposed => "overexposed",
+            Self::Underexposed => "underexposed",
+            Self::Duplicate => "duplicate",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SkippedView {
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+/// A one line report of the skipped views, eg. "Skipped 3 frames (2 blurry, 1 overexposed)".
+pub fn summary(skipped: &[SkippedView]) -> String {
+    let counts: Vec<_> = [
+        SkipReason::Blurry,
+        SkipReason::Overexposed,
+        SkipReason::Underexposed,
+        SkipReason::Duplicate,
+    ]
+    .into_iter()
+    .filter_map(|reason| {
+        let count = skipped.iter().filter(|s| s.reason == reason).count();
+        (count > 0).then(|| format!("{count} {}", reason.describe()))
+    })
+    .collect();
+    format!("Skipped {} frames ({})", skipped.len(), counts.join(", "))
+}
+
+pub(crate) enum Preprocessed {
+    View(SceneView),
+    Skipped(SkippedView),
+}
+
+fn analysis_image(image: &DynamicImage) -> DynamicImage {
+    if image.width() > ANALYSIS_RESOLUTION || image.height() > ANALYSIS_RESOLUTION {
+        image.resize(
+            ANALYSIS_RESOLUTION,
+            ANALYSIS_RESOLUTION,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        image.clone()
+    }
+}
+
+/// Crop the view to `fraction` of its width and height, around the center of the image.
+/// The camera intrinsics are adjusted to match the cropped image.
+pub fn center_crop(view: SceneView, fraction: f32) -> SceneView {
+    let fraction = fraction.clamp(0.01, 1.0);
+    if fraction >= 1.0 {
+        return view;
+    }
+
+    let (w, h) = (view.image.width(), view.image.height());
+    let crop_w = ((w as f32 * fraction).round() as u32).max(1);
+    let crop_h = ((h as f32 * fraction).round() as u32).max(1);
+    crop(view, (w - crop_w) / 2, (h - crop_h) / 2, crop_w, crop_h)
+}
+
+/// Crop the view to the `crop_w` by `crop_h` pixels starting at `x`, `y`. The camera intrinsics
+/// are adjusted to match the cropped image.
+pub fn crop(view: SceneView, x: u32, y: u32, crop_w: u32, crop_h: u32) -> SceneView {
+    let (w, h) = (view.image.width(), view.image.height());
+
+    // The focal length in pixels stays the same, the image just gets smaller.
+    let mut camera = view.camera;
+    camera.fov_x = focal_to_fov(fov_to_focal(camera.fov_x, w), crop_w);
+    camera.fov_y = focal_to_fov(fov_to_focal(camera.fov_y, h), crop_h);
+    let to_crop_uv = |uv: glam::Vec2| {
+        (uv * glam::vec2(w as f32, h as f32) - glam::vec2(x as f32, y as f32))
+            / glam::vec2(crop_w as f32, crop_h as f32)
+    };
+    camera.center_uv = to_crop_uv(camera.center_uv);
+    let sparse_points = view
+        .sparse_points
+        .iter()
+        .map(|&(uv, position)| (to_crop_uv(uv), position))
+        .collect();
+
+    SceneView {
+        image: Arc::new(view.image.crop_imm(x, y, crop_w, crop_h)),
+        camera,
+        sparse_points: Arc::new(sparse_points),
+        ..view
+    }
+}
+
+/// How sharp an image is, as the variance of the Laplacian of the image brightness (0-255).
+/// Blurry images have few edges, and so a low variance. Typical sharp photos score in the
+/// hundreds, while anything below ~50 tends to be noticeably blurry.
+pub fn sharpness(image: &DynamicImage) -> f32 {
+    let gray = analysis_image(image).to_luma32f();
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 {
+        return 0.0;
+    }
+
+    let at = |x: u32, y: u32| gray.get_pixel(x, y)[0] * 255.0;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let lap = at(x - 1, y) + at(x + 1, y) + at(x, y - 1) + at(x, y + 1) - 4.0 * at(x, y);
+            sum += lap as f64;
+            sum_sq += (lap * lap) as f64;
+        }
+    }
+    let count = ((w - 2) * (h - 2)) as f64;
+    let mean = sum / count;
+    (sum_sq / count - mean * mean) as f32
+}
+
+/// The fraction of pixels (0-1) that are crushed to black, and blown out to white. Transparent
+/// or masked out pixels aren't counted.
+pub fn clipped_fractions(image: &DynamicImage) -> (f32, f32) {
+    let image = analysis_image(image).to_luma_alpha8();
+    let mut dark = 0;
+    let mut bright = 0;
+    let mut count = 0;
+    for pixel in image.pixels() {
+        let [luma, alpha] = pixel.0;
+        if alpha == 0 {
+            continue;
+        }
+        count += 1;
+        if luma <= UNDEREXPOSED_LEVEL {
+            dark += 1;
+        } else if luma >= OVEREXPOSED_LEVEL {
+            bright += 1;
+        }
+    }
+    let count = count.max(1) as f32;
+    (dark as f32 / count, bright as f32 / count)
+}
+
+fn duplicate_thumbnail(image: &DynamicImage) -> image::GrayImage {
+    image
+        .resize_exact(
+            DUPLICATE_RESOLUTION,
+            DUPLICATE_RESOLUTION,
+            image::imageops::FilterType::Triangle,
+        )
+        .to_luma8()
+}
+
+fn thumbnail_similarity(a: &image::GrayImage, b: &image::GrayImage) -> f32 {
+    let diff: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(&a, &b)| a.abs_diff(b) as u64)
+        .sum();
+    1.0 - diff as f32 / (a.as_raw().len().max(1) as f32 * 255.0)
+}
+
+/// How alike two images look (0-1), from the difference of small grayscale thumbnails.
+pub fn similarity(a: &DynamicImage, b: &DynamicImage) -> f32 {
+    thumbnail_similarity(&duplicate_thumbnail(a), &duplicate_thumbnail(b))
+}
+
+/// Skips frames that are nearly identical to the frame before them, eg. from a video where the
+/// camera stood still. Frames have to be passed in capture order.
+pub(crate) struct DuplicateFilter {
+    min_similarity: Option<f32>,
+    // Thumbnail and camera of the last frame that was kept.
+    last: Option<(image::GrayImage, glam::Vec3, glam::Quat)>,
+    // Average distance between consecutive frames, to judge what a small baseline is.
+    step_sum: f32,
+    step_count: u32,
+}
+
+impl DuplicateFilter {
+    pub(crate) fn new(load_args: &LoadDataseConfig) -> Self {
+        Self {
+            min_similarity: load_args.dedup_similarity,
+            last: None,
+            step_sum: 0.0,
+            step_count: 0,
+        }
+    }
+
+    pub(crate) fn filter(&mut self, view: Preprocessed) -> Preprocessed {
+        match view {
+            Preprocessed::View(view) if self.is_duplicate(&view) => {
+                log::info!("Skipping duplicate view {}", view.path);
+                Preprocessed::Skipped(SkippedView {
+                    path: view.path,
+                    reason: SkipReason::Duplicate,
+                })
+            }
+            view => view,
+        }
+    }
+
+    fn is_duplicate(&mut self, view: &SceneView) -> bool {
+        let Some(min_similarity) = self.min_similarity else {
+            return false;
+        };
+
+        let thumb = duplicate_thumbnail(&view.image);
+        let (position, rotation) = (view.camera.position, view.camera.rotation);
+
+        if let Some((last_thumb, last_position, last_rotation)) = &self.last {
+            let step = position.distance(*last_position);
+            let mean_step = self.step_sum / self.step_count.max(1) as f32;
+            self.step_sum += step;
+            self.step_count += 1;
+
+            let small_baseline = self.step_count == 1 || step < mean_step;
+            let angle = rotation.angle_between(*last_rotation).to_degrees();
+            if small_baseline
+                && angle < DUPLICATE_MAX_ANGLE
+                && thumbnail_similarity(&thumb, last_thumb) >= min_similarity
+            {
+                return true;
+            }
+        }
+
+        self.last = Some((thumb, position, rotation));
+        false
+    }
+}
+
+// Limit how much the colors of a view are stretched, so a view of eg. a white wall doesn't get
+// blown up to match a colorful reference.
+const MAX_COLOR_GAIN: f32 = 2.0;
+
+/// Mean and standard deviation of the red, green and blue channels (0-255), ignoring
+/// transparent or masked out pixels.
+pub fn color_stats(image: &DynamicImage) -> ([f32; 3], [f32; 3]) {
+    let image = analysis_image(image).to_rgba8();
+    let mut sum = [0.0f64; 3];
+    let mut sum_sq = [0.0f64; 3];
+    let mut count = 0.0f64;
+    for pixel in image.pixels().filter(|p| p[3] > 0) {
+        for (c, &v) in pixel.0[..3].iter().enumerate() {
+            sum[c] += v as f64;
+            sum_sq[c] += v as f64 * v as f64;
+        }
+        count += 1.0;
+    }
+    let count = count.max(1.0);
+    let mean = sum.map(|s| s / count);
+    let variance = [0, 1, 2].map(|c| (sum_sq[c] / count - mean[c] * mean[c]).max(0.0));
+    let stddev = variance.map(|v| v.sqrt() as f32);
+    (mean.map(|m| m as f32), stddev)
+}
+
+/// Gain and offset per color channel (in 0-255 units) that were applied to the colors of a view.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColorCorrection {
+    pub gain: [f32; 3],
+    pub offset: [f32; 3],
+}
+
+impl ColorCorrection {
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let adjust = |c: usize, v: u8| {
+            (v as f32 * self.gain[c] + self.offset[c])
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+
+        if image.color().has_alpha() {
+            let mut image = image.to_rgba8();
+            for pixel in image.pixels_mut() {
+                for (c, v) in pixel.0.iter_mut().take(3).enumerate() {
+                    *v = adjust(c, *v);
+                }
+            }
+            DynamicImage::from(image)
+        } else {
+            let mut image = image.to_rgb8();
+            for pixel in image.pixels_mut() {
+                for (c, v) in pixel.0.iter_mut().take(3).enumerate() {
+                    *v = adjust(c, *v);
+                }
+            }
+            DynamicImage::from(image)
+        }
+    }
+}
+
+/// The color corrections of all views, by the path of the view. Written alongside exports, and
+/// can be read back in with [`LoadDataseConfig::color_calibration`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ColorCalibration {
+    pub views: BTreeMap<String, ColorCorrection>,
+}
+
+impl ColorCalibration {
+    pub fn is_empty(&self) -> bool {
+        self.views.is_empty()
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn from_json(data: &[u8]) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+/// Read the known color corrections from the dataset, if any were given.
+pub(crate) async fn load_color_calibration(
+    vfs: &mut BrushVfs,
+    load_args: &LoadDataseConfig,
+) -> anyhow::Result<ColorCalibration> {
+    let Some(name) = &load_args.color_calibration else {
+        return Ok(ColorCalibration::default());
+    };
+    let path = vfs
+        .file_names()
+        .find(|p| p.ends_with(name))
+        .with_context(|| format!("Color calibration {name} not found in dataset"))?;
+
+    let mut data = vec![];
+    vfs.open_path(&path).await?.read_to_end(&mut data).await?;
+    let calibration = ColorCalibration::from_json(&data)
+        .with_context(|| format!("Failed to read color calibration {name}"))?;
+    log::info!(
+        "Using color corrections of {} views from {name}",
+        calibration.views.len()
+    );
+    Ok(calibration)
+}
+
+type ColorStats = ([f32; 3], [f32; 3]);
+
+/// Matches the exposure and white balance of views to a reference view, with a gain and offset
+/// per color channel. Useful when the capture device used automatic exposure or white balance.
+/// Views with a known correction use that instead.
+///
+/// This runs as the views are decoded, in parallel. Each view shares its colors with the views
+/// after it, and the reference is the first view that wasn't skipped.
+#[derive(Clone)]
+pub(crate) struct ColorNormalizer {
+    enabled: bool,
+    known: Arc<ColorCalibration>,
+    // The colors of each view, in order, once it's decoded. Closed without any colors when the
+    // view was skipped or failed to load.
+    views: Arc<Mutex<Vec<watch::Receiver<Option<ColorStats>>>>>,
+}
+
+impl ColorNormalizer {
+    pub(crate) fn new(load_args: &LoadDataseConfig, known: ColorCalibration) -> Self {
+        Self {
+            enabled: load_args.normalize_colors,
+            known: Arc::new(known),
+            views: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Correct the colors of the views once they're decoded, along with the correction that was
+    /// computed for each view, if any. The views have to be in capture order, and come after the
+    /// views of earlier calls.
+    pub(crate) fn normalize<F>(
+        &self,
+        handles: Vec<F>,
+    ) -> Vec<
+        impl Future<Output = anyhow::Result<(Preprocessed, Option<ColorCorrection>)>>
+        + WasmNotSend
+        + 'static,
+    >
+    where
+        F: Future<Output = anyhow::Result<Preprocessed>> + WasmNotSend + 'static,
+    {
+        handles
+            .into_iter()
+            .map(|handle| {
+                let (colors, receiver) = watch::channel(None);
+                let earlier = {
+                    let mut views = self.views.lock().expect("Lock poisoned");
+                    let earlier = views.clone();
+                    views.push(receiver);
+                    earlier
+                };
+                let normalizer = self.clone();
+                async move { Ok(normalizer.apply(handle.await?, colors, earlier).await) }
+            })
+            .collect()
+    }
+
+    async fn apply(
+        &self,
+        view: Preprocessed,
+        colors: watch::Sender<Option<ColorStats>>,
+        earlier: Vec<watch::Receiver<Option<ColorStats>>>,
+    ) -> (Preprocessed, Option<ColorCorrection>) {
+        let Preprocessed::View(view) = view else {
+            return (view, None);
+        };
+
+        let known = self.known.views.get(&view.path).copied();
+        let image = match known {
+            Some(known) => Arc::new(known.apply(&view.image)),
+            None => view.image.clone(),
+        };
+        if !self.enabled {
+            return (Preprocessed::View(SceneView { image, ..view }), None);
+        }
+
+        let stats = color_stats(&image);
+        let _ = colors.send(Some(stats));
+        let reference = first_colors(earlier).await;
+
+        let correction = reference
+            .filter(|_| known.is_none())
+            .map(|(ref_mean, ref_stddev)| {
+                let (mean, stddev) = stats;
+                let gain = [0, 1, 2].map(|c| {
+                    (ref_stddev[c] / stddev[c].max(1.0)).clamp(1.0 / MAX_COLOR_GAIN, MAX_COLOR_GAIN)
+                });
+                let offset = [0, 1, 2].map(|c| ref_mean[c] - gain[c] * mean[c]);
+                ColorCorrection { gain, offset }
+            });
+        let image = match correction {
+            Some(correction) => Arc::new(correction.apply(&image)),
+            None => image,
+        };
+        (Preprocessed::View(SceneView { image, ..view }), correction)
+    }
+}
+
+// The colors of the first view that wasn't skipped, waiting for each view to be decoded.
+async fn first_colors(views: Vec<watch::Receiver<Option<ColorStats>>>) -> Option<ColorStats> {
+    for mut view in views {
+        if let Ok(colors) = view.wait_for(Option::is_some).await {
+            return *colors;
+        }
+    }
+    None
+}
+
+/// Read a file the dataset refers to: from the dataset if it has the file, or else from disk.
+async fn read_file(vfs: &mut BrushVfs, name: &str) -> anyhow::Result<Vec<u8>> {
+    let mut reader = if let Some(path) = vfs.file_names().find(|p| p.ends_with(name)) {
+        vfs.open_path(&path).await?
+    } else {
+        #[cfg(not(target_family = "wasm"))]
+        {
+            let path = std::path::Path::new(name);
+            BrushVfs::from_directory(path)
+                .await?
+                .open_path(path)
+                .await?
+        }
+        #[cfg(target_family = "wasm")]
+        anyhow::bail!("{name} not found in the dataset")
+    };
+    let mut data = vec![];
+    reader.read_to_end(&mut data).await?;
+    Ok(data)
+}
+
+/// Make the sky transparent, where the sky map is more than half sure it's sky. Pixels that were
+/// already masked out stay masked out.
+fn mask_sky(view: SceneView, sky: &AuxMap) -> SceneView {
+    let mut image = view.image.to_rgba8();
+    for (pixel, sky) in image.pixels_mut().zip(sky.pixels()) {
+        if sky[0] > 0.5 {
+            pixel[3] = 0;
+        }
+    }
+    SceneView {
+        image: Arc::new(image.into()),
+        img_type: ViewImageType::Masked,
+        ..view
+    }
+}
+
+/// Masks out the sky with a user supplied segmentation model. The sky is infinitely far away,
+/// which otherwise tends to end up as floaters close to the cameras. The model runs on the views
+/// as they're decoded, in parallel.
+#[derive(Clone)]
+pub(crate) struct SkyMasker {
+    model: Option<Arc<AuxModel>>,
+}
+
+impl SkyMasker {
+    pub(crate) async fn new(
+        vfs: &mut BrushVfs,
+        load_args: &LoadDataseConfig,
+    ) -> anyhow::Result<Self> {
+        let Some(path) = &load_args.sky_model else {
+            return Ok(Self { model: None });
+        };
+        let data = read_file(vfs, path)
+            .await
+            .with_context(|| format!("Failed to read sky model {path}"))?;
+        let size = load_args.aux_model_resolution;
+        let model = AuxModel::load(&data, AuxModelKind::SkyMask, (size, size))
+            .with_context(|| format!("Failed to load sky model {path}"))?;
+        Ok(Self {
+            model: Some(Arc::new(model)),
+        })
+    }
+
+    /// Mask out the sky of the views once they're decoded.
+    pub(crate) fn mask<F>(
+        &self,
+        handles: Vec<F>,
+    ) -> Vec<impl Future<Output = anyhow::Result<Preprocessed>> + WasmNotSend + 'static>
+    where
+        F: Future<Output = anyhow::Result<Preprocessed>> + WasmNotSend + 'static,
+    {
+        handles
+            .into_iter()
+            .map(|handle| {
+                let model = self.model.clone();
+                async move {
+                    let view = handle.await?;
+                    let (Some(model), Preprocessed::View(view)) = (model, &view) else {
+                        return Ok(view);
+                    };
+                    let sky = model
+                        .run(&view.image)
+                        .with_context(|| format!("Failed to find the sky in {}", view.path))?;
+                    Ok(Preprocessed::View(mask_sky(view.clone(), &sky)))
+                }
+            })
+            .collect()
+    }
+}
+
+/// What happened to the views while loading.
+#[derive(Clone, Debug, Default)]
+pub struct PreprocessReport {
+    pub skipped: Vec<SkippedView>,
+    /// Color corrections of the views, both given and computed.
+    pub color_calibration: ColorCalibration,
+    /// Intrinsics of the camera groups as loaded, before they were replaced by
+    /// [`LoadDataseConfig::intrinsics`].
+    pub loaded_intrinsics: Vec<CameraIntrinsics>,
+    /// Camera model of each camera group, as the dataset names it, eg. `SIMPLE_RADIAL`.
+    pub camera_models: BTreeMap<u32, String>,
+}
+
+fn check_quality(image: &DynamicImage, load_args: &LoadDataseConfig) -> Option<SkipReason> {
+    if load_args
+        .min_sharpness
+        .is_some_and(|min| sharpness(image) < min)
+    {
+        return Some(SkipReason::Blurry);
+    }
+
+    if load_args.max_underexposed.is_some() || load_args.max_overexposed.is_some() {
+        let (dark, bright) = clipped_fractions(image);
+        if load_args.max_underexposed.is_some_and(|max| dark > max) {
+            return Some(SkipReason::Underexposed);
+        }
+        if load_args.max_overexposed.is_some_and(|max| bright > max) {
+            return Some(SkipReason::Overexposed);
+        }
+    }
+    None
+}
+
+/// Apply the preprocessing options of `load_args` to a freshly loaded view, which might skip
+/// the view altogether.
+pub(crate) fn preprocess_view(view: SceneView, load_args: &LoadDataseConfig) -> Preprocessed {
+    let view = if let Some(fraction) = load_args.center_crop {
+        center_crop(view, fraction)
+    } else {
+        view
+    };
+
+    let view = SceneView {
+        image: clamp_img_to_max_size(view.image, load_args.max_resolution),
+        ..view
+    };
+
+    if let Some(reason) = check_quality(&view.image, load_args) {
+        log::info!("Skipping {} view {}", reason.describe(), view.path);
+        return Preprocessed::Skipped(SkippedView {
+            path: view.path,
+            reason,
+        });
+    }
+
+    Preprocessed::View(view)
+}
+
+#[cfg(test)]
+mod tests {
+    use brush_render::camera::Camera;
+    use glam::{Quat, Vec3, uvec2};
+
+    use super::*;
+
+    // A view looking down +z, with an image where every pixel has its own color.
+    fn view(width: u32, height: u32) -> SceneView {
+        let image =
+            image::RgbImage::from_fn(width, height, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        SceneView {
+            path: "view.png".to_owned(),
+            camera: Camera::new(Vec3::ZERO, Quat::IDENTITY, 1.0, 0.8, glam::vec2(0.45, 0.55)),
+            image: Arc::new(image.into()),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![(glam::vec2(0.5, 0.5), Vec3::Z)]),
+        }
+    }
+
+    #[test]
+    fn center_crop_keeps_what_points_project_to() {
+        let full = view(200, 100);
+        let cropped = center_crop(full.clone(), 0.5);
+        assert_eq!((cropped.image.width(), cropped.image.height()), (100, 50));
+
+        let (full_size, crop_size) = (uvec2(200, 100), uvec2(100, 50));
+        assert!(
+            full.camera
+                .focal(full_size)
+                .abs_diff_eq(cropped.camera.focal(crop_size), 1e-3)
+        );
+
+        let full_image = full.image.to_rgb8();
+        let crop_image = cropped.image.to_rgb8();
+        for world in [
+            Vec3::Z,
+            glam::vec3(0.1, -0.05, 1.0),
+            glam::vec3(-0.2, 0.1, 2.0),
+        ] {
+            let in_full = full.camera.project(world, full_size).unwrap();
+            let in_crop = cropped.camera.project(world, crop_size).unwrap();
+            // The crop starts at (50, 25).
+            assert!(in_full.abs_diff_eq(in_crop + glam::vec2(50.0, 25.0), 1e-2));
+            let (x, y) = (in_crop.x as u32, in_crop.y as u32);
+            assert_eq!(
+                crop_image.get_pixel(x, y),
+                full_image.get_pixel(x + 50, y + 25)
+            );
+        }
+
+        let (uv, _) = cropped.sparse_points[0];
+        assert!(uv.abs_diff_eq(glam::vec2(0.5, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn center_crop_clamps_the_fraction() {
+        let full = view(200, 100);
+        let same = center_crop(full.clone(), 1.5);
+        assert_eq!((same.image.width(), same.image.height()), (200, 100));
+        assert_eq!(same.camera.fov_x, full.camera.fov_x);
+
+        let tiny = center_crop(full, 0.0);
+        assert_eq!((tiny.image.width(), tiny.image.height()), (2, 1));
+    }
+
+    // A checkerboard of `cell` pixel squares, which gets smoother the bigger the cells are.
+    fn checkerboard(size: u32, cell: u32) -> DynamicImage {
+        let image = image::GrayImage::from_fn(size, size, |x, y| {
+            image::Luma([if (x / cell + y / cell) % 2 == 0 {
+                30
+            } else {
+                220
+            }])
+        });
+        image.into()
+    }
+
+    #[test]
+    fn blurry_images_are_less_sharp() {
+        let sharp = checkerboard(256, 4);
+        let blurry = sharp.blur(4.0);
+        assert!(sharpness(&sharp) > 1000.0);
+        assert!(sharpness(&blurry) < sharpness(&sharp) / 10.0);
+        assert_eq!(sharpness(&DynamicImage::new_luma8(64, 64)), 0.0);
+        // Too small to have any edges.
+        assert_eq!(sharpness(&DynamicImage::new_luma8(2, 2)), 0.0);
+    }
+
+    #[test]
+    fn clipped_fractions_ignore_transparent_pixels() {
+        // A quarter black, a quarter white, and the bottom half transparent white.
+        let image = image::RgbaImage::from_fn(64, 64, |x, y| match (x < 32, y < 32) {
+            (true, true) => image::Rgba([0, 0, 0, 255]),
+            (false, true) => image::Rgba([255, 255, 255, 255]),
+            _ => image::Rgba([255, 255, 255, 0]),
+        });
+        let (dark, bright) = clipped_fractions(&image.into());
+        assert!((dark - 0.5).abs() < 1e-3, "{dark}");
+        assert!((bright - 0.5).abs() < 1e-3, "{bright}");
+
+        let gray = DynamicImage::from(image::GrayImage::from_pixel(16, 16, image::Luma([128])));
+        assert_eq!(clipped_fractions(&gray), (0.0, 0.0));
+        // Nothing to count, rather than dividing by zero.
+        let transparent = DynamicImage::new_rgba8(16, 16);
+        assert_eq!(clipped_fractions(&transparent), (0.0, 0.0));
+    }
+
+    fn is_skipped(filter: &mut DuplicateFilter, view: SceneView) -> bool {
+        matches!(
+            filter.filter(Preprocessed::View(view)),
+            Preprocessed::Skipped(_)
+        )
+    }
+
+    fn moved(view: &SceneView, position: Vec3, rotation: Quat) -> SceneView {
+        let mut view = view.clone();
+        view.camera.position = position;
+        view.camera.rotation = rotation;
+        view
+    }
+
+    fn dedup_config(similarity: Option<f32>) -> LoadDataseConfig {
+        let mut config = LoadDataseConfig::new();
+        config.dedup_similarity = similarity;
+        config
+    }
+
+    #[test]
+    fn duplicate_filter_skips_frames_that_barely_change() {
+        let mut filter = DuplicateFilter::new(&dedup_config(Some(0.95)));
+        let first = view(64, 64);
+        assert!(!is_skipped(&mut filter, first.clone()));
+        // Same image, the camera barely moved.
+        assert!(is_skipped(
+            &mut filter,
+            moved(&first, Vec3::X * 0.01, Quat::IDENTITY)
+        ));
+
+        // A different image is kept, even from the same place.
+        let mut other = view(64, 64);
+        other.image = Arc::new(checkerboard(64, 8));
+        assert!(!is_skipped(&mut filter, other));
+    }
+
+    #[test]
+    fn duplicate_filter_keeps_frames_that_look_elsewhere() {
+        let mut filter = DuplicateFilter::new(&dedup_config(Some(0.95)));
+        let first = view(64, 64);
+        assert!(!is_skipped(&mut filter, first.clone()));
+
+        // The image looks the same, but the camera turned.
+        let turned = Quat::from_rotation_y(30f32.to_radians());
+        assert!(!is_skipped(&mut filter, moved(&first, Vec3::ZERO, turned)));
+    }
+
+    #[test]
+    fn duplicate_filter_keeps_frames_that_moved_far() {
+        let mut filter = DuplicateFilter::new(&dedup_config(Some(0.95)));
+        let gradient = view(64, 64);
+        let mut checkers = view(64, 64);
+        checkers.image = Arc::new(checkerboard(64, 8));
+
+        // Steps of 1 between frames that all look different.
+        for i in 0..4 {
+            let frame = if i % 2 == 0 { &gradient } else { &checkers };
+            let position = Vec3::X * i as f32;
+            assert!(!is_skipped(
+                &mut filter,
+                moved(frame, position, Quat::IDENTITY)
+            ));
+        }
+
+        // The last frame again: skipped after a small step, kept after a big one.
+        let last = Vec3::X * 3.0;
+        assert!(is_skipped(
+            &mut filter,
+            moved(&checkers, last + Vec3::X * 0.1, Quat::IDENTITY)
+        ));
+        assert!(!is_skipped(
+            &mut filter,
+            moved(&checkers, last + Vec3::X * 10.0, Quat::IDENTITY)
+        ));
+    }
+
+    #[test]
+    fn duplicate_filter_does_nothing_when_disabled() {
+        let mut filter = DuplicateFilter::new(&dedup_config(None));
+        let first = view(64, 64);
+        for _ in 0..3 {
+            assert!(!is_skipped(&mut filter, first.clone()));
+        }
+    }
+
+    fn gray_view(path: &str, level: u8) -> SceneView {
+        let mut view = view(32, 32);
+        view.path = path.to_owned();
+        // Some texture, so the colors have a spread to match.
+        let image = image::RgbImage::from_fn(32, 32, |x, _| {
+            let v = level.saturating_add((x % 2) as u8 * 20);
+            image::Rgb([v, v, v])
+        });
+        view.image = Arc::new(image.into());
+        view
+    }
+
+    async fn normalize(
+        normalizer: &ColorNormalizer,
+        views: Vec<SceneView>,
+    ) -> Vec<(SceneView, Option<ColorCorrection>)> {
+        let handles = views
+            .into_iter()
+            .map(|view| async move { anyhow::Ok(Preprocessed::View(view)) })
+            .collect();
+        let mut normalized = vec![];
+        for handle in normalizer.normalize(handles) {
+            match handle.await.unwrap() {
+                (Preprocessed::View(view), correction) => normalized.push((view, correction)),
+                (Preprocessed::Skipped(_), _) => panic!("No views should be skipped"),
+            }
+        }
+        normalized
+    }
+
+    #[tokio::test]
+    async fn color_normalizer_does_nothing_when_disabled() {
+        let normalizer =
+            ColorNormalizer::new(&LoadDataseConfig::new(), ColorCalibration::default());
+        let views = vec![gray_view("a", 100), gray_view("b", 40)];
+        let normalized = normalize(&normalizer, views.clone()).await;
+        for ((view, correction), original) in normalized.iter().zip(&views) {
+            assert!(correction.is_none());
+            assert!(Arc::ptr_eq(&view.image, &original.image));
+        }
+    }
+
+    #[tokio::test]
+    async fn color_normalizer_matches_the_first_view() {
+        let mut config = LoadDataseConfig::new();
+        config.normalize_colors = true;
+        let normalizer = ColorNormalizer::new(&config, ColorCalibration::default());
+
+        let views = vec![gray_view("a", 100), gray_view("b", 40)];
+        let normalized = normalize(&normalizer, views.clone()).await;
+
+        // The first view is the reference.
+        assert!(normalized[0].1.is_none());
+        assert!(Arc::ptr_eq(&normalized[0].0.image, &views[0].image));
+
+        assert!(normalized[1].1.is_some());
+        let (mean, _) = color_stats(&normalized[1].0.image);
+        let (ref_mean, _) = color_stats(&views[0].image);
+        assert!((mean[0] - ref_mean[0]).abs() < 1.0, "{mean:?} {ref_mean:?}");
+    }
+
+    #[test]
+    fn sky_mask_clears_the_sky_and_keeps_the_rest() {
+        let mut view = view(4, 2);
+        // An already masked out pixel on the ground.
+        let mut image = view.image.to_rgba8();
+        image.get_pixel_mut(0, 1)[3] = 0;
+        view.image = Arc::new(image.into());
+
+        // The top row is sky, one pixel too unsure to count.
+        let sky = AuxMap::from_fn(4, 2, |x, y| {
+            image::Luma([match (x, y) {
+                (3, 0) => 0.4,
+                (_, 0) => 0.9,
+                _ => 0.1,
+            }])
+        });
+        let masked = mask_sky(view.clone(), &sky);
+        assert_eq!(masked.img_type, ViewImageType::Masked);
+
+        let before = view.image.to_rgba8();
+        let after = masked.image.to_rgba8();
+        for (x, y, pixel) in after.enumerate_pixels() {
+            let was = before.get_pixel(x, y);
+            assert_eq!(pixel.0[..3], was.0[..3]);
+            let alpha = if y == 0 && x != 3 { 0 } else { was[3] };
+            assert_eq!(pixel[3], alpha, "pixel {x}, {y}");
+        }
+        assert_eq!(after.get_pixel(0, 1)[3], 0);
+    }
+}
@@ -0,0 +1,222 @@
+use std::io::Cursor;
+
+use anyhow::Context;
+use glam::{DVec3, Vec3};
+use ply_rs::{
+    parser::Parser,
+    ply::{Property, PropertyAccess},
+};
+use tokio::io::BufReader;
+use tokio_with_wasm::alias as tokio_wasm;
+
+use crate::pointcloud_import::read_las;
+use crate::splat_import::decode_element;
+
+/// Reference geometry (eg. a survey scan or ground truth mesh) to compare a splat scene against.
+///
+/// Meshes are kept as a list of unique edges, as that's all that's needed to draw a wireframe.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceGeometry {
+    pub points: Vec<Vec3>,
+    /// Per point colors, empty if the source has no colors.
+    pub colors: Vec<[u8; 3]>,
+    pub edges: Vec<[u32; 2]>,
+    /// Where the origin of `points` is in the coordinates of the file. Georeferenced coordinates
+    /// don't fit in a f32, so points far from the origin are moved closer to it first.
+    pub origin: DVec3,
+}
+
+impl ReferenceGeometry {
+    fn add_face(&mut self, indices: &[u32]) {
+        for (i, &a) in indices.iter().enumerate() {
+            let b = indices[(i + 1) % indices.len()];
+            self.edges.push([a.min(b), a.max(b)]);
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        // Edges are shared between neighbouring faces, only keep one of each.
+        self.edges.sort_unstable();
+        self.edges.dedup();
+
+        let num_points = self.points.len() as u32;
+        self.edges.retain(|e| e[0] != e[1] && e[1] < num_points);
+
+        if self.colors.len() != self.points.len() {
+            self.colors.clear();
+        }
+        self
+    }
+}
+
+#[derive(Default)]
+struct RefElement {
+    pos: Vec3,
+    color: Option<[u8; 3]>,
+    face: Vec<u32>,
+}
+
+fn color_channel(property: &Property) -> Option<u8> {
+    match property {
+        Property::UChar(v) => Some(*v),
+        Property::Float(v) => Some((v.clamp(0.0, 1.0) * 255.0) as u8),
+        _ => None,
+    }
+}
+
+impl PropertyAccess for RefElement {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn set_property(&mut self, key: &str, property: Property) {
+        match (key, &property) {
+            ("x", Property::Float(v)) => self.pos.x = *v,
+            ("y", Property::Float(v)) => self.pos.y = *v,
+            ("z", Property::Float(v)) => self.pos.z = *v,
+            ("x", Property::Double(v)) => self.pos.x = *v as f32,
+            ("y", Property::Double(v)) => self.pos.y = *v as f32,
+            ("z", Property::Double(v)) => self.pos.z = *v as f32,
+            ("red" | "green" | "blue", _) => {
+                let Some(value) = color_channel(&property) else {
+                    return;
+                };
+                let color = self.color.get_or_insert([255; 3]);
+                let channel = match key {
+                    "red" => 0,
+                    "green" => 1,
+                    _ => 2,
+                };
+                color[channel] = value;
+            }
+            ("vertex_indices" | "vertex_index", Property::ListInt(list)) => {
+                self.face = list.iter().map(|&i| i as u32).collect();
+            }
+            ("vertex_indices" | "vertex_index", Property::ListUInt(list)) => {
+                self.face.clone_from(list);
+            }
+            _ => (),
+        }
+    }
+}
+
+async fn load_ply(data: Vec<u8>) -> anyhow::Result<ReferenceGeometry> {
+    let mut reader = BufReader::new(Cursor::new(data));
+    let parser = Parser::<RefElement>::new();
+    let header = parser.read_header(&mut reader).await?;
+
+    let mut geom = ReferenceGeometry::default();
+
+    for element in &header.elements {
+        for i in 0..element.count {
+            // Occasionally yield.
+            if i % 5000 == 0 {
+                tokio_wasm::task::yield_now().await;
+            }
+
+            let elem = decode_element(&mut reader, &parser, &header, element).await?;
+
+            match element.name.as_str() {
+                "vertex" => {
+                    geom.points.push(elem.pos);
+                    if let Some(color) = elem.color {
+                        geom.colors.push(color);
+                    }
+                }
+                "face" if elem.face.len() >= 2 => geom.add_face(&elem.face),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(geom.finish())
+}
+
+fn parse_obj_index(token: &str, num_points: usize) -> Option<u32> {
+    // Faces can be specified as v, v/vt, v//vn or v/vt/vn. Only the vertex index matters here.
+    let idx: i64 = token.split('/').next()?.parse().ok()?;
+    let idx = if idx < 0 {
+        num_points as i64 + idx
+    } else {
+        idx - 1
+    };
+    u32::try_from(idx).ok()
+}
+
+fn load_obj(data: &[u8]) -> anyhow::Result<ReferenceGeometry> {
+    let text = std::str::from_utf8(data).context("OBJ file is not valid text")?;
+    let mut geom = ReferenceGeometry::default();
+
+    for (line_num, line) in text.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let values: Vec<f32> = tokens.filter_map(|t| t.parse().ok()).collect();
+                if values.len() < 3 {
+                    anyhow::bail!("Invalid vertex on line {}", line_num + 1);
+                }
+                geom.points
+                    .push(glam::vec3(values[0], values[1], values[2]));
+                // Some exporters append vertex colors.
+                if values.len() >= 6 {
+                    geom.colors.push([
+                        (values[3].clamp(0.0, 1.0) * 255.0) as u8,
+                        (values[4].clamp(0.0, 1.0) * 255.0) as u8,
+                        (values[5].clamp(0.0, 1.0) * 255.0) as u8,
+                    ]);
+                }
+            }
+            Some("f" | "l") => {
+                let indices: Vec<u32> = tokens
+                    .filter_map(|t| parse_obj_index(t, geom.points.len()))
+                    .collect();
+                if indices.len() >= 2 {
+                    geom.add_face(&indices);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(geom.finish())
+}
+
+fn load_las(data: Vec<u8>) -> anyhow::Result<ReferenceGeometry> {
+    let cloud = read_las(data)?;
+    let colors = cloud
+        .colors
+        .map(|colors| {
+            colors
+                .iter()
+                .map(|c| c.map(|v| (v.clamp(0.0, 1.0) * 255.0) as u8))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Moved the same way as point clouds used to initialize the splats, so a reference loaded
+    // from the same file lines up with them.
+    let (points, offset) = cloud.recentered();
+    let geom = ReferenceGeometry {
+        points,
+        colors,
+        edges: vec![],
+        origin: -offset,
+    };
+    Ok(geom.finish())
+}
+
+/// Load reference geometry from a PLY (point cloud or mesh), LAS/LAZ (point cloud) or OBJ file.
+pub async fn load_reference_geometry(data: Vec<u8>) -> anyhow::Result<ReferenceGeometry> {
+    let geom = if data.starts_with(b"ply") {
+        load_ply(data).await?
+    } else if data.starts_with(b"LASF") {
+        load_las(data)?
+    } else {
+        load_obj(&data)?
+    };
+
+    if geom.points.is_empty() {
+        anyhow::bail!("Reference file contains no points.");
+    }
+
+    Ok(geom)
+}
@@ -2,7 +2,9 @@ use brush_train::image::view_to_sample;
 use brush_train::scene::Scene;
 use brush_train::train::SceneBatch;
 use burn::prelude::Backend;
-use rand::{SeedableRng, seq::SliceRandom};
+
+use crate::preprocess::crop;
+use rand::{Rng, SeedableRng, seq::SliceRandom};
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::Receiver;
 use tokio_with_wasm::alias as tokio_wasm;
@@ -12,7 +14,9 @@ pub struct SceneLoader<B: Backend> {
 }
 
 impl<B: Backend> SceneLoader<B> {
-    pub fn new(scene: &Scene, seed: u64, device: &B::Device) -> Self {
+    /// Load random views of the scene. With a `crop_size`, views are cropped to a random
+    /// square of at most that many pixels, so that very large images fit in memory.
+    pub fn new(scene: &Scene, seed: u64, crop_size: Option<u32>, device: &B::Device) -> Self {
         let scene = scene.clone();
         // The bounded size == number of batches to prefetch.
         let (tx, rx) = mpsc::channel(5);
@@ -32,7 +36,14 @@ impl<B: Backend> SceneLoader<B> {
                             .pop()
                             .expect("Need at least one view in dataset")
                     });
-                    let view = scene.views[index].clone();
+                    let mut view = scene.views[index].clone();
+                    if let Some(size) = crop_size {
+                        let (w, h) = (view.image.width(), view.image.height());
+                        let (crop_w, crop_h) = (size.clamp(1, w), size.clamp(1, h));
+                        let x = rng.random_range(0..=w - crop_w);
+                        let y = rng.random_range(0..=h - crop_h);
+                        view = crop(view, x, y, crop_w, crop_h);
+                    }
                     (view_to_sample(&view, &device), view)
                 };
 
@@ -55,3 +66,62 @@ impl<B: Backend> SceneLoader<B> {
             .expect("Somehow lost data loading channel!")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use brush_render::camera::Camera;
+    use brush_train::image::view_to_sample_data;
+    use brush_train::scene::{Scene, SceneView, ViewImageType};
+    use brush_train::train::TrainBack;
+    use burn::backend::wgpu::WgpuDevice;
+    use glam::{Quat, Vec3, uvec2, vec2};
+
+    use super::SceneLoader;
+
+    #[tokio::test]
+    async fn random_crops_keep_the_image_and_camera_together() {
+        // Every pixel has its own color, so the crop shows where it was taken from.
+        let image = image::RgbImage::from_fn(200, 100, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        let full = SceneView {
+            path: "view.png".to_owned(),
+            camera: Camera::new(Vec3::ZERO, Quat::IDENTITY, 1.0, 0.8, vec2(0.45, 0.55)),
+            image: Arc::new(image.into()),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![]),
+        };
+        let point = Vec3::new(0.1, -0.05, 2.0);
+        let full_px = full
+            .camera
+            .project(point, uvec2(200, 100))
+            .expect("In front of the camera");
+
+        let scene = Scene::new(vec![full]);
+        let device = WgpuDevice::DefaultDevice;
+        let mut loader = SceneLoader::<TrainBack>::new(&scene, 0, Some(32), &device);
+        for _ in 0..4 {
+            let batch = loader.next_batch().await;
+            let view = &batch.gt_view;
+            assert_eq!((view.image.width(), view.image.height()), (32, 32));
+
+            let sample = batch
+                .gt_image
+                .into_data()
+                .to_vec::<f32>()
+                .expect("Float data");
+            let expected = view_to_sample_data(view)
+                .to_vec::<f32>()
+                .expect("Float data");
+            assert_eq!(sample, expected);
+
+            let [x, y, _] = view.image.to_rgb8().get_pixel(0, 0).0;
+            let crop_px = view
+                .camera
+                .project(point, uvec2(32, 32))
+                .expect("In front of the camera");
+            assert!((crop_px + vec2(x as f32, y as f32) - full_px).length() < 1e-3);
+        }
+    }
+}
@@ -1,14 +1,93 @@
+use std::io::{Cursor, Write};
+
 use anyhow::anyhow;
-use brush_render::gaussian_splats::Splats;
+use brush_render::{
+    gaussian_splats::{Splats, TrackedStat},
+    render::SH_C0,
+};
 use burn::{prelude::Backend, tensor::DataError};
+use flate2::{Compression, write::GzEncoder};
 use glam::{Quat, Vec3};
+use las::Write as _;
 use ply_rs::{
     ply::{self, Ply, PropertyDef, PropertyType, ScalarType},
     writer::Writer,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::splat_import::GaussianData;
 
+/// File formats splats can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    /// Full precision ply, as used by the original 3DGS implementation.
+    #[default]
+    Ply,
+    /// Quantized ply as used by `PlayCanvas` and `SuperSplat`, about 4x smaller.
+    CompressedPly,
+    /// The `.splat` format supported by many web viewers. Only stores base colors.
+    Splat,
+    /// Niantic's gzipped spz format, supports up to SH degree 3.
+    Spz,
+    /// The centers of the splats as a point cloud ply, with colors, normals, opacity and scale.
+    PointCloudPly,
+    /// The centers of the splats as a LAS point cloud, with colors, opacity and scale.
+    Las,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 6] = [
+        Self::Ply,
+        Self::CompressedPly,
+        Self::Splat,
+        Self::Spz,
+        Self::PointCloudPly,
+        Self::Las,
+    ];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Ply => "ply",
+            Self::CompressedPly => "compressed.ply",
+            Self::Splat => "splat",
+            Self::Spz => "spz",
+            Self::PointCloudPly => "points.ply",
+            Self::Las => "las",
+        }
+    }
+
+    /// The format of a file named `path`, judging by its extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.to_lowercase();
+        // Prefer the longest extension, so "scene.compressed.ply" isn't taken for a plain ply.
+        Self::ALL
+            .into_iter()
+            .filter(|format| path.ends_with(&format!(".{}", format.extension())))
+            .max_by_key(|format| format.extension().len())
+    }
+
+    /// The quantized variant of this format, if it has one.
+    pub fn quantized(self) -> Self {
+        match self {
+            Self::Ply => Self::CompressedPly,
+            format => format,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ply => "PLY",
+            Self::CompressedPly => "Compressed PLY",
+            Self::Splat => ".splat",
+            Self::Spz => "SPZ",
+            Self::PointCloudPly => "Point cloud PLY",
+            Self::Las => "LAS point cloud",
+        }
+    }
+}
+
 async fn read_splat_data<B: Backend>(splats: Splats<B>) -> Result<Vec<GaussianData>, DataError> {
     let means = splats.means.val().into_data_async().await.to_vec()?;
     let log_scales = splats.log_scales.val().into_data_async().await.to_vec()?;
@@ -16,8 +95,7 @@ async fn read_splat_data<B: Backend>(splats: Splats<B>) -> Result<Vec<GaussianDa
     let opacities = splats.raw_opacity.val().into_data_async().await.to_vec()?;
 
     let sh_coeffs = splats
-        .sh_coeffs
-        .val()
+        .sh_coeffs_f32()
         .permute([0, 2, 1]) // Permute to inria format ([n, channel, coeffs]).
         .into_data_async()
         .await
@@ -25,6 +103,19 @@ async fn read_splat_data<B: Backend>(splats: Splats<B>) -> Result<Vec<GaussianDa
 
     let sh_coeffs_num = splats.sh_coeffs.dims()[1];
 
+    let mut tracked = vec![];
+    if let Some(tracking) = &splats.tracking {
+        for stat in TrackedStat::ALL {
+            tracked.push(
+                tracking
+                    .stat(stat)
+                    .into_data_async()
+                    .await
+                    .to_vec::<f32>()?,
+            );
+        }
+    }
+
     let splats = (0..splats.num_splats())
         .map(|i| {
             let i = i as usize;
@@ -59,6 +150,8 @@ async fn read_splat_data<B: Backend>(splats: Splats<B>) -> Result<Vec<GaussianDa
                 ),
                 sh_dc,
                 sh_coeffs_rest,
+                tracked: (!tracked.is_empty())
+                    .then(|| [tracked[0][i], tracked[1][i], tracked[2][i]]),
             }
         })
         .collect();
@@ -92,6 +185,15 @@ pub async fn splat_to_ply<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u
         ));
     }
 
+    if splats.tracking.is_some() {
+        for stat in TrackedStat::ALL {
+            properties.push(PropertyDef::new(
+                stat.name(),
+                PropertyType::Scalar(ScalarType::Float),
+            ));
+        }
+    }
+
     let mut ply: Ply<GaussianData> = Ply::new();
 
     // Create PLY header
@@ -108,3 +210,775 @@ pub async fn splat_to_ply<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u
     writer.write_ply(&mut buf, &mut ply)?;
     Ok(buf)
 }
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn to_u8(x: f32) -> u8 {
+    x.round().clamp(0.0, 255.0) as u8
+}
+
+fn base_color(splat: &GaussianData) -> Vec3 {
+    Vec3::from_array(splat.sh_dc) * SH_C0 + 0.5
+}
+
+// Spread the lower 10 bits of x out to every third bit.
+fn spread_bits(x: u32) -> u32 {
+    let x = (x | (x << 16)) & 0x0300_00ff;
+    let x = (x | (x << 8)) & 0x0300_f00f;
+    let x = (x | (x << 4)) & 0x030c_30c3;
+    (x | (x << 2)) & 0x0924_9249
+}
+
+fn morton_code(pos: Vec3, min: Vec3, max: Vec3) -> u32 {
+    let norm = ((pos - min) / (max - min).max(Vec3::splat(1e-12)) * 1023.0)
+        .clamp(Vec3::ZERO, Vec3::splat(1023.0))
+        .as_uvec3();
+    spread_bits(norm.x) | (spread_bits(norm.y) << 1) | (spread_bits(norm.z) << 2)
+}
+
+fn pack_unorm(value: f32, bits: u32) -> u32 {
+    let max = (1 << bits) - 1;
+    ((value * max as f32 + 0.5).floor() as u32).min(max)
+}
+
+fn normalize_range(value: Vec3, min: Vec3, max: Vec3) -> Vec3 {
+    ((value - min) / (max - min).max(Vec3::splat(1e-12))).clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+fn pack_111011(value: Vec3) -> u32 {
+    (pack_unorm(value.x, 11) << 21) | (pack_unorm(value.y, 10) << 11) | pack_unorm(value.z, 11)
+}
+
+// Store the 3 smallest components of the quaternion, and the index of the largest.
+fn pack_rotation(rot: Quat) -> u32 {
+    let rot = rot.normalize();
+    let comps = [rot.w, rot.x, rot.y, rot.z];
+    let largest = (0..4)
+        .max_by(|&a, &b| comps[a].abs().total_cmp(&comps[b].abs()))
+        .unwrap_or(0);
+    let sign = comps[largest].signum();
+    let norm = std::f32::consts::FRAC_1_SQRT_2;
+
+    (0..4)
+        .filter(|&i| i != largest)
+        .fold(largest as u32, |packed, i| {
+            (packed << 10) | pack_unorm(comps[i] * sign * norm + 0.5, 10)
+        })
+}
+
+/// Write splats in the compressed ply format used by `PlayCanvas` & `SuperSplat`.
+///
+/// Splats are sorted spatially and grouped in chunks of 256, each chunk storing the bounds
+/// its splats are quantized in.
+pub async fn splat_to_compressed_ply<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u8>> {
+    let splats = splats.with_normed_rotations();
+    let data = read_splat_data(splats)
+        .await
+        .map_err(|e| anyhow!("Failed to read data from splat {e:?}"))?;
+    Ok(write_compressed_ply(&data))
+}
+
+fn write_compressed_ply(data: &[GaussianData]) -> Vec<u8> {
+    const CHUNK_SIZE: usize = 256;
+
+    let min = data.iter().fold(Vec3::MAX, |m, s| m.min(s.means));
+    let max = data.iter().fold(Vec3::MIN, |m, s| m.max(s.means));
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by_cached_key(|&i| morton_code(data[i].means, min, max));
+
+    let num_rest = data.first().map_or(0, |s| s.sh_coeffs_rest.len());
+    let num_chunks = data.len().div_ceil(CHUNK_SIZE);
+
+    let mut header = format!(
+        "ply\nformat binary_little_endian 1.0\ncomment Exported from Brush\nelement chunk {num_chunks}\n"
+    );
+    for prefix in ["min", "max"] {
+        for axis in ["x", "y", "z"] {
+            header += &format!("property float {prefix}_{axis}\n");
+        }
+    }
+    for prefix in ["min", "max"] {
+        for axis in ["x", "y", "z"] {
+            header += &format!("property float {prefix}_scale_{axis}\n");
+        }
+    }
+    for prefix in ["min", "max"] {
+        for channel in ["r", "g", "b"] {
+            header += &format!("property float {prefix}_{channel}\n");
+        }
+    }
+    header += &format!("element vertex {}\n", data.len());
+    for name in [
+        "packed_position",
+        "packed_rotation",
+        "packed_scale",
+        "packed_color",
+    ] {
+        header += &format!("property uint {name}\n");
+    }
+    if num_rest > 0 {
+        header += &format!("element sh {}\n", data.len());
+        for i in 0..num_rest {
+            header += &format!("property uchar f_rest_{i}\n");
+        }
+    }
+    header += "end_header\n";
+
+    let mut chunk_data = vec![];
+    let mut vertex_data = vec![];
+
+    for chunk in order.chunks(CHUNK_SIZE) {
+        let bounds = |f: &dyn Fn(&GaussianData) -> Vec3| {
+            chunk.iter().fold((Vec3::MAX, Vec3::MIN), |(lo, hi), &i| {
+                let v = f(&data[i]);
+                (lo.min(v), hi.max(v))
+            })
+        };
+        let (pos_min, pos_max) = bounds(&|s| s.means);
+        // Clamp extreme scales so they don't ruin the precision of the whole chunk.
+        let (scale_min, scale_max) =
+            bounds(&|s| s.log_scale.clamp(Vec3::splat(-20.0), Vec3::splat(20.0)));
+        let (color_min, color_max) = bounds(&base_color);
+
+        for v in [pos_min, pos_max, scale_min, scale_max, color_min, color_max] {
+            for c in v.to_array() {
+                chunk_data.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+
+        for &i in chunk {
+            let splat = &data[i];
+            let position = pack_111011(normalize_range(splat.means, pos_min, pos_max));
+            let scale = pack_111011(normalize_range(splat.log_scale, scale_min, scale_max));
+            let color = normalize_range(base_color(splat), color_min, color_max);
+            let color = (pack_unorm(color.x, 8) << 24)
+                | (pack_unorm(color.y, 8) << 16)
+                | (pack_unorm(color.z, 8) << 8)
+                | pack_unorm(sigmoid(splat.opacity), 8);
+
+            for packed in [position, pack_rotation(splat.rotation), scale, color] {
+                vertex_data.extend_from_slice(&packed.to_le_bytes());
+            }
+        }
+    }
+
+    let mut buf = header.into_bytes();
+    buf.extend(chunk_data);
+    buf.extend(vertex_data);
+
+    for &i in &order {
+        buf.extend(
+            data[i]
+                .sh_coeffs_rest
+                .iter()
+                .map(|&sh| ((sh / 8.0 + 0.5) * 256.0).clamp(0.0, 255.0) as u8),
+        );
+    }
+
+    buf
+}
+
+/// Write splats in the `.splat` format, 32 bytes per splat with only base colors.
+///
+/// Splats are sorted by their visual impact, so viewers can progressively load the file.
+pub async fn splat_to_splat<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u8>> {
+    let splats = splats.with_normed_rotations();
+    let data = read_splat_data(splats)
+        .await
+        .map_err(|e| anyhow!("Failed to read data from splat {e:?}"))?;
+    Ok(write_splat(data))
+}
+
+fn write_splat(mut data: Vec<GaussianData>) -> Vec<u8> {
+    let importance = |s: &GaussianData| s.log_scale.element_sum().exp() * sigmoid(s.opacity);
+    data.sort_by(|a, b| importance(b).total_cmp(&importance(a)));
+
+    let mut buf = Vec::with_capacity(data.len() * 32);
+
+    for splat in &data {
+        for v in splat.means.to_array() {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in splat.log_scale.exp().to_array() {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        let color = base_color(splat) * 255.0;
+        buf.extend([
+            to_u8(color.x),
+            to_u8(color.y),
+            to_u8(color.z),
+            to_u8(sigmoid(splat.opacity) * 255.0),
+        ]);
+        let rot = splat.rotation;
+        buf.extend([rot.w, rot.x, rot.y, rot.z].map(|c| to_u8(c * 128.0 + 128.0)));
+    }
+
+    buf
+}
+
+// Sign flips of each SH coefficient when converting from ply (RDF) to spz (RUB) coordinates,
+// which flips the y and z axis.
+pub(crate) const SPZ_SH_FLIP: [f32; 15] = [
+    -1.0, -1.0, 1.0, // Degree 1
+    -1.0, 1.0, 1.0, -1.0, 1.0, // Degree 2
+    -1.0, 1.0, -1.0, -1.0, 1.0, -1.0, 1.0, // Degree 3
+];
+
+/// Write splats in Niantic's spz format (version 2).
+pub async fn splat_to_spz<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u8>> {
+    let sh_degree = splats.sh_degree();
+    let splats = splats.with_normed_rotations();
+    let data = read_splat_data(splats)
+        .await
+        .map_err(|e| anyhow!("Failed to read data from splat {e:?}"))?;
+    write_spz(&data, sh_degree)
+}
+
+pub(crate) const SPZ_MAGIC: u32 = 0x5053_474e;
+const SPZ_FRACTIONAL_BITS: u8 = 12;
+pub(crate) const SPZ_COLOR_SCALE: f32 = 0.15;
+
+fn write_spz(data: &[GaussianData], sh_degree: u32) -> anyhow::Result<Vec<u8>> {
+    const VERSION: u32 = 2;
+
+    let sh_degree = sh_degree.min(3);
+    let num_rest = ((sh_degree + 1) * (sh_degree + 1) - 1) as usize;
+
+    let mut buf = vec![];
+    buf.extend_from_slice(&SPZ_MAGIC.to_le_bytes());
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend([sh_degree as u8, SPZ_FRACTIONAL_BITS, 0, 0]);
+
+    // spz uses RUB coordinates, flip the y and z axis.
+    let flip = Vec3::new(1.0, -1.0, -1.0);
+
+    for splat in data {
+        for v in (splat.means * flip).to_array() {
+            let fixed = (v * (1 << SPZ_FRACTIONAL_BITS) as f32).round() as i32;
+            buf.extend_from_slice(&fixed.to_le_bytes()[0..3]);
+        }
+    }
+    for splat in data {
+        buf.push(to_u8(sigmoid(splat.opacity) * 255.0));
+    }
+    for splat in data {
+        for c in splat.sh_dc {
+            buf.push(to_u8(c * SPZ_COLOR_SCALE * 255.0 + 0.5 * 255.0));
+        }
+    }
+    for splat in data {
+        for s in splat.log_scale.to_array() {
+            buf.push(to_u8((s + 10.0) * 16.0));
+        }
+    }
+    for splat in data {
+        let rot = Quat::from_xyzw(
+            splat.rotation.x,
+            -splat.rotation.y,
+            -splat.rotation.z,
+            splat.rotation.w,
+        );
+        let rot = if rot.w < 0.0 { -rot } else { rot };
+        for c in [rot.x, rot.y, rot.z] {
+            buf.push(to_u8(c * 127.5 + 127.5));
+        }
+    }
+    for splat in data {
+        let rest = &splat.sh_coeffs_rest;
+        let stride = rest.len() / 3;
+        for coeff in 0..num_rest.min(stride) {
+            // Degree 1 is stored with 5 bits of precision, higher degrees with 4.
+            let bucket = if coeff < 3 { 8 } else { 16 };
+            for channel in 0..3 {
+                let sh = rest[channel * stride + coeff] * SPZ_SH_FLIP[coeff];
+                let quantized = (sh * 128.0).round() + 128.0;
+                let quantized = ((quantized as i32 + bucket / 2) / bucket) * bucket;
+                buf.push(quantized.clamp(0, 255) as u8);
+            }
+        }
+    }
+
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(&buf)?;
+    Ok(encoder.finish()?)
+}
+
+/// Write the centers of the splats as a point cloud ply, which most point cloud tools can read.
+///
+/// Each point has the base color, normal, opacity and scale of its splat. The normal is the axis
+/// the splat is flattest along.
+pub async fn splat_to_point_cloud_ply<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u8>> {
+    let normals: Vec<f32> = splats
+        .normals()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(|e| anyhow!("Failed to read data from splat {e:?}"))?;
+    let data = read_splat_data(splats)
+        .await
+        .map_err(|e| anyhow!("Failed to read data from splat {e:?}"))?;
+    Ok(write_point_cloud_ply(&data, &normals))
+}
+
+fn write_point_cloud_ply(data: &[GaussianData], normals: &[f32]) -> Vec<u8> {
+    let mut buf = format!(
+        "ply\nformat binary_little_endian 1.0\ncomment Exported from Brush\nelement vertex {}\n",
+        data.len()
+    )
+    .into_bytes();
+    for name in ["x", "y", "z", "nx", "ny", "nz"] {
+        buf.extend(format!("property float {name}\n").bytes());
+    }
+    for name in ["red", "green", "blue"] {
+        buf.extend(format!("property uchar {name}\n").bytes());
+    }
+    for name in ["opacity", "scale_x", "scale_y", "scale_z"] {
+        buf.extend(format!("property float {name}\n").bytes());
+    }
+    buf.extend(b"end_header\n");
+
+    for (splat, normal) in data.iter().zip(normals.chunks_exact(3)) {
+        for v in splat.means.to_array().iter().chain(normal) {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        let color = base_color(splat) * 255.0;
+        buf.extend([to_u8(color.x), to_u8(color.y), to_u8(color.z)]);
+        let scale = splat.log_scale.exp();
+        for v in [sigmoid(splat.opacity), scale.x, scale.y, scale.z] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    buf
+}
+
+// Describe one float of the extra bytes of each point, as in the LAS 1.4 spec.
+fn las_extra_float(name: &str, description: &str) -> Vec<u8> {
+    const FLOAT_TYPE: u8 = 9;
+    let mut descriptor = vec![0, 0, FLOAT_TYPE, 0];
+    let text = |text: &str, len: usize| {
+        let mut bytes = text.as_bytes()[..text.len().min(len)].to_vec();
+        bytes.resize(len, 0);
+        bytes
+    };
+    descriptor.extend(text(name, 32));
+    // Unused, no data, min, max, scale and offset fields, which don't apply.
+    descriptor.extend([0; 4 + 5 * 24]);
+    descriptor.extend(text(description, 32));
+    descriptor
+}
+
+/// Write the centers of the splats as a LAS point cloud, for GIS and point cloud tools.
+///
+/// Points have the base color of their splat, and its opacity as intensity. The scale of the
+/// splats is stored in extra bytes.
+pub async fn splat_to_las<B: Backend>(splats: Splats<B>) -> anyhow::Result<Vec<u8>> {
+    let data = read_splat_data(splats)
+        .await
+        .map_err(|e| anyhow!("Failed to read data from splat {e:?}"))?;
+    write_las(&data)
+}
+
+fn write_las(data: &[GaussianData]) -> anyhow::Result<Vec<u8>> {
+    let min = data.iter().fold(Vec3::MAX, |m, s| m.min(s.means));
+    let max = data.iter().fold(Vec3::MIN, |m, s| m.max(s.means));
+    // Positions are stored as 32 bit integers, pick a precision that fits the extent.
+    let extent = (max - min).max_element().max(1e-6) as f64;
+    let scale = 10f64.powf((extent / 1e9).log10().ceil());
+    let transform = |offset: f32| las::Transform {
+        scale,
+        offset: offset as f64,
+    };
+
+    let mut builder = las::Builder::from((1, 4));
+    builder.generating_software = "Brush".to_owned();
+    builder.point_format = las::point::Format::new(2)?;
+    builder.point_format.extra_bytes = 12;
+    builder.transforms = las::Vector {
+        x: transform(min.x),
+        y: transform(min.y),
+        z: transform(min.z),
+    };
+    builder.vlrs.push(las::Vlr {
+        user_id: "LASF_Spec".to_owned(),
+        record_id: 4,
+        description: "Splat scales".to_owned(),
+        data: [
+            ("scale_x", "Scale of the splat along its x axis"),
+            ("scale_y", "Scale of the splat along its y axis"),
+            ("scale_z", "Scale of the splat along its z axis"),
+        ]
+        .iter()
+        .flat_map(|(name, description)| las_extra_float(name, description))
+        .collect(),
+    });
+
+    let mut writer = las::Writer::new(Cursor::new(vec![]), builder.into_header()?)?;
+    for splat in data {
+        let color = base_color(splat).clamp(Vec3::ZERO, Vec3::ONE) * u16::MAX as f32;
+        let extra_bytes = splat
+            .log_scale
+            .exp()
+            .to_array()
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        writer.write_point(las::Point {
+            x: splat.means.x as f64,
+            y: splat.means.y as f64,
+            z: splat.means.z as f64,
+            intensity: (sigmoid(splat.opacity) * u16::MAX as f32).round() as u16,
+            return_number: 1,
+            number_of_returns: 1,
+            color: Some(las::Color::new(
+                color.x.round() as u16,
+                color.y.round() as u16,
+                color.z.round() as u16,
+            )),
+            extra_bytes,
+            ..Default::default()
+        })?;
+    }
+    Ok(writer.into_inner()?.into_inner())
+}
+
+/// Serialize splats to the given format.
+pub async fn export_splats<B: Backend>(
+    splats: Splats<B>,
+    format: ExportFormat,
+) -> anyhow::Result<Vec<u8>> {
+    match format {
+        ExportFormat::Ply => splat_to_ply(splats).await,
+        ExportFormat::CompressedPly => splat_to_compressed_ply(splats).await,
+        ExportFormat::Splat => splat_to_splat(splats).await,
+        ExportFormat::Spz => splat_to_spz(splats).await,
+        ExportFormat::PointCloudPly => splat_to_point_cloud_ply(splats).await,
+        ExportFormat::Las => splat_to_las(splats).await,
+    }
+}
+
+/// Export splats like [`export_splats`], with `comments` added to the header of the ply formats,
+/// eg. to record where the splats came from. The other formats have no place for them.
+pub async fn export_splats_with_comments<B: Backend>(
+    splats: Splats<B>,
+    format: ExportFormat,
+    comments: &[String],
+) -> anyhow::Result<Vec<u8>> {
+    // Every ply header written here has this comment, add the others after it.
+    const BRUSH_COMMENT: &[u8] = b"comment Exported from Brush\n";
+
+    let data = export_splats(splats, format).await?;
+    let is_ply = matches!(
+        format,
+        ExportFormat::Ply | ExportFormat::CompressedPly | ExportFormat::PointCloudPly
+    );
+    if !is_ply || comments.is_empty() {
+        return Ok(data);
+    }
+    let Some(start) = data
+        .windows(BRUSH_COMMENT.len())
+        .position(|window| window == BRUSH_COMMENT)
+    else {
+        return Ok(data);
+    };
+    let end = start + BRUSH_COMMENT.len();
+    let mut with_comments = data[..end].to_vec();
+    for comment in comments {
+        // A line break would end the comment, and break the header.
+        with_comments.extend(format!("comment {}\n", comment.replace(['\r', '\n'], " ")).bytes());
+    }
+    with_comments.extend_from_slice(&data[end..]);
+    Ok(with_comments)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use glam::{Quat, Vec3, vec3};
+
+    use super::{
+        SPZ_COLOR_SCALE, SPZ_FRACTIONAL_BITS, SPZ_MAGIC, base_color, las_extra_float, sigmoid,
+        write_compressed_ply, write_las, write_point_cloud_ply, write_splat, write_spz,
+    };
+    use crate::splat_import::{GaussianData, decode_splat_file, decode_spz};
+
+    // More splats than fit in one chunk of the compressed ply, spread over a grid.
+    fn test_splats() -> Vec<GaussianData> {
+        (0..300)
+            .map(|i| GaussianData {
+                means: vec3(
+                    (i % 7) as f32,
+                    (i / 7 % 7) as f32 * 0.5,
+                    (i / 49) as f32 - 3.0,
+                ),
+                log_scale: Vec3::splat(-2.0 - (i % 5) as f32 * 0.1),
+                opacity: 1.0,
+                rotation: Quat::from_rotation_y(i as f32 * 0.01),
+                sh_dc: [(i % 3) as f32 * 0.5 - 0.5, 0.25, -0.25],
+                sh_coeffs_rest: vec![],
+                tracked: None,
+            })
+            .collect()
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().expect("4 bytes"))
+    }
+
+    fn f32_at(data: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(data[offset..offset + 4].try_into().expect("4 bytes"))
+    }
+
+    // The splat closest to `pos`, as the writers reorder the splats.
+    fn closest(splats: &[GaussianData], pos: Vec3) -> &GaussianData {
+        splats
+            .iter()
+            .min_by(|a, b| a.means.distance(pos).total_cmp(&b.means.distance(pos)))
+            .expect("Splats shouldn't be empty")
+    }
+
+    #[test]
+    fn compressed_ply_round_trip() {
+        let splats = test_splats();
+        let data = write_compressed_ply(&splats);
+
+        let header_end = data
+            .windows(11)
+            .position(|w| w == b"end_header\n")
+            .expect("Header should end")
+            + 11;
+        let header = std::str::from_utf8(&data[..header_end]).expect("Header should be text");
+        assert!(header.contains("element chunk 2\n"));
+        assert!(header.contains("element vertex 300\n"));
+
+        let unpack = |packed: u32, min: Vec3, max: Vec3| {
+            let x = (packed >> 21) as f32 / 2047.0;
+            let y = ((packed >> 11) & 0x3ff) as f32 / 1023.0;
+            let z = (packed & 0x7ff) as f32 / 2047.0;
+            min + vec3(x, y, z) * (max - min)
+        };
+        let vertices = header_end + 2 * 18 * 4;
+        assert_eq!(data.len(), vertices + 300 * 16);
+        for i in 0..300 {
+            let chunk = header_end + i / 256 * 18 * 4;
+            let bounds = |v: usize| {
+                Vec3::from_array([0, 1, 2].map(|c| f32_at(&data, chunk + (v * 3 + c) * 4)))
+            };
+            let vertex = vertices + i * 16;
+            let pos = unpack(u32_at(&data, vertex), bounds(0), bounds(1));
+            let splat = closest(&splats, pos);
+            assert!(splat.means.distance(pos) < 0.01);
+
+            let color = u32_at(&data, vertex + 12);
+            let color = vec3(
+                (color >> 24) as f32 / 255.0,
+                ((color >> 16) & 0xff) as f32 / 255.0,
+                ((color >> 8) & 0xff) as f32 / 255.0,
+            );
+            let color = bounds(4) + color * (bounds(5) - bounds(4));
+            assert!(color.abs_diff_eq(base_color(splat), 0.01));
+        }
+    }
+
+    #[test]
+    fn splat_round_trip() {
+        let splats = test_splats();
+        let data = write_splat(test_splats());
+        assert_eq!(data.len(), splats.len() * 32);
+
+        for record in data.chunks_exact(32) {
+            let pos = Vec3::from_array([0, 1, 2].map(|c| f32_at(record, c * 4)));
+            let splat = closest(&splats, pos);
+            assert_eq!(splat.means, pos);
+
+            let scale = Vec3::from_array([3, 4, 5].map(|c| f32_at(record, c * 4)));
+            assert!(scale.abs_diff_eq(splat.log_scale.exp(), 1e-6));
+
+            let color = vec3(record[24] as f32, record[25] as f32, record[26] as f32) / 255.0;
+            assert!(color.abs_diff_eq(base_color(splat), 1.0 / 255.0));
+        }
+    }
+
+    #[test]
+    fn spz_round_trip() {
+        let mut splats = test_splats();
+        // Degree 1 SH, in the inria layout of [channel, coeff].
+        for splat in &mut splats {
+            splat.sh_coeffs_rest = vec![0.5, 0.25, -0.25, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        }
+        let gz = write_spz(&splats, 1).expect("Splats should serialize");
+        let mut data = vec![];
+        GzDecoder::new(gz.as_slice())
+            .read_to_end(&mut data)
+            .expect("Spz should be gzipped");
+
+        let n = splats.len();
+        assert_eq!(u32_at(&data, 0), SPZ_MAGIC);
+        assert_eq!(u32_at(&data, 8), n as u32);
+        assert_eq!(data[12], 1);
+        assert_eq!(data[13], SPZ_FRACTIONAL_BITS);
+        assert_eq!(data.len(), 16 + n * (9 + 1 + 3 + 3 + 3 + 9));
+
+        let positions = 16;
+        let colors = positions + n * 9 + n;
+        let rotations = colors + n * 3 + n * 3;
+        let sh = rotations + n * 3;
+
+        for (i, splat) in splats.iter().enumerate() {
+            let pos = Vec3::from_array([0, 1, 2].map(|c| {
+                let start = positions + i * 9 + c * 3;
+                let bytes = &data[start..start + 3];
+                // Sign extend the 24 bit fixed point value.
+                let fixed = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                fixed as f32 / (1 << SPZ_FRACTIONAL_BITS) as f32
+            }));
+            // spz is RUB, so y and z are flipped.
+            let expected = splat.means * vec3(1.0, -1.0, -1.0);
+            assert!(pos.abs_diff_eq(expected, 1e-3));
+
+            let color = Vec3::from_array(
+                [0, 1, 2]
+                    .map(|c| (data[colors + i * 3 + c] as f32 / 255.0 - 0.5) / SPZ_COLOR_SCALE),
+            );
+            let color_step = 1.0 / 255.0 / SPZ_COLOR_SCALE;
+            assert!(color.abs_diff_eq(Vec3::from_array(splat.sh_dc), color_step));
+
+            let [x, y, z] = [0, 1, 2].map(|c| data[rotations + i * 3 + c] as f32 / 127.5 - 1.0);
+            let w = (1.0 - x * x - y * y - z * z).max(0.0).sqrt();
+            let rot = Quat::from_xyzw(x, -y, -z, w);
+            assert!(rot.dot(splat.rotation).abs() > 0.999);
+
+            // The first degree 1 coefficient (y) flips sign, the last (x) doesn't.
+            let red = |coeff: usize| (data[sh + i * 9 + coeff * 3] as f32 - 128.0) / 128.0;
+            assert!((red(0) + 0.5).abs() < 0.07);
+            assert!((red(2) + 0.25).abs() < 0.07);
+        }
+    }
+
+    #[test]
+    fn splat_reader_round_trip() {
+        let splats = test_splats();
+        let read = decode_splat_file(&write_splat(test_splats())).expect("Splats should read");
+        assert_eq!(read.len(), splats.len());
+        for splat in &read {
+            let original = closest(&splats, splat.means);
+            assert_eq!(original.means, splat.means);
+            assert!(splat.log_scale.abs_diff_eq(original.log_scale, 1e-5));
+            let color = Vec3::from_array(splat.sh_dc);
+            assert!(color.abs_diff_eq(Vec3::from_array(original.sh_dc), 0.01));
+            assert!(splat.rotation.normalize().dot(original.rotation).abs() > 0.999);
+        }
+    }
+
+    #[test]
+    fn spz_reader_round_trip() {
+        let mut splats = test_splats();
+        for splat in &mut splats {
+            splat.sh_coeffs_rest = vec![0.5, 0.25, -0.25, 0.0, 0.125, 0.0, -0.5, 0.0, 0.0];
+        }
+        let gz = write_spz(&splats, 1).expect("Splats should serialize");
+        let read = decode_spz(&gz).expect("Splats should read");
+        assert_eq!(read.len(), splats.len());
+        // spz keeps the order of the splats.
+        for (splat, original) in read.iter().zip(&splats) {
+            assert!(splat.means.abs_diff_eq(original.means, 1e-3));
+            assert!(splat.log_scale.abs_diff_eq(original.log_scale, 0.05));
+            let color = Vec3::from_array(splat.sh_dc);
+            assert!(color.abs_diff_eq(Vec3::from_array(original.sh_dc), 0.03));
+            assert!(splat.rotation.normalize().dot(original.rotation).abs() > 0.999);
+            for (sh, original) in splat.sh_coeffs_rest.iter().zip(&original.sh_coeffs_rest) {
+                assert!((sh - original).abs() < 0.07);
+            }
+        }
+    }
+
+    #[test]
+    fn point_cloud_ply_round_trip() {
+        let splats = test_splats();
+        let normals: Vec<f32> = (0..splats.len())
+            .flat_map(|i| Vec3::from_array([0, 1, 2].map(|c| ((i + c) % 3) as f32)).to_array())
+            .collect();
+        let data = write_point_cloud_ply(&splats, &normals);
+
+        let header_end = data
+            .windows(11)
+            .position(|w| w == b"end_header\n")
+            .expect("Header should end")
+            + 11;
+        let header = std::str::from_utf8(&data[..header_end]).expect("Header should be text");
+        assert!(header.contains("element vertex 300\n"));
+        assert!(header.contains("property uchar red\n"));
+
+        // Positions and normals, colors, then opacity and scales.
+        const RECORD: usize = 6 * 4 + 3 + 4 * 4;
+        assert_eq!(data.len(), header_end + splats.len() * RECORD);
+        for (i, splat) in splats.iter().enumerate() {
+            let record = &data[header_end + i * RECORD..][..RECORD];
+            let pos = Vec3::from_array([0, 1, 2].map(|c| f32_at(record, c * 4)));
+            assert_eq!(pos, splat.means);
+            let normal = Vec3::from_array([3, 4, 5].map(|c| f32_at(record, c * 4)));
+            assert_eq!(normal.to_array(), normals[i * 3..i * 3 + 3]);
+
+            let color = vec3(record[24] as f32, record[25] as f32, record[26] as f32) / 255.0;
+            assert!(color.abs_diff_eq(base_color(splat), 1.0 / 255.0));
+            assert_eq!(f32_at(record, 27), sigmoid(splat.opacity));
+            let scale = Vec3::from_array([1, 2, 3].map(|c| f32_at(record, 27 + c * 4)));
+            assert!(scale.abs_diff_eq(splat.log_scale.exp(), 1e-6));
+        }
+    }
+
+    #[test]
+    fn las_extra_float_follows_the_spec() {
+        let descriptor = las_extra_float("scale_x", "A description longer than thirty two bytes");
+        assert_eq!(descriptor.len(), 192);
+        // Float type, at the offset of the data type.
+        assert_eq!(descriptor[2], 9);
+        assert_eq!(&descriptor[4..11], b"scale_x");
+        assert!(descriptor[11..36].iter().all(|&b| b == 0));
+        // The description is cut off at 32 bytes.
+        assert_eq!(
+            &descriptor[160..192],
+            &b"A description longer than thirty two bytes"[..32]
+        );
+    }
+
+    #[test]
+    fn las_round_trip() {
+        use las::Read as _;
+
+        let splats = test_splats();
+        let data = write_las(&splats).expect("Should write LAS");
+        let mut reader = las::Reader::new(std::io::Cursor::new(data)).expect("Should read LAS");
+        assert_eq!(reader.header().number_of_points(), splats.len() as u64);
+        let vlr = reader
+            .header()
+            .vlrs()
+            .iter()
+            .find(|vlr| vlr.user_id == "LASF_Spec" && vlr.record_id == 4)
+            .expect("Extra bytes should be described");
+        assert_eq!(vlr.data.len(), 3 * 192);
+
+        let points: Vec<_> = reader
+            .points()
+            .collect::<Result<_, _>>()
+            .expect("Points should read");
+        for (point, splat) in points.iter().zip(&splats) {
+            let pos = vec3(point.x as f32, point.y as f32, point.z as f32);
+            assert!(pos.abs_diff_eq(splat.means, 1e-6));
+
+            let color = point.color.expect("Points should have a color");
+            let color = vec3(color.red as f32, color.green as f32, color.blue as f32);
+            let expected = base_color(splat).clamp(Vec3::ZERO, Vec3::ONE);
+            assert!((color / u16::MAX as f32).abs_diff_eq(expected, 1e-4));
+            let intensity = point.intensity as f32 / u16::MAX as f32;
+            assert!((intensity - sigmoid(splat.opacity)).abs() < 1e-4);
+
+            let scale = Vec3::from_array([0, 1, 2].map(|c| f32_at(&point.extra_bytes, c * 4)));
+            assert!(scale.abs_diff_eq(splat.log_scale.exp(), 1e-6));
+        }
+    }
+}
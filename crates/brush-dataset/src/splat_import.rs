@@ -11,13 +11,15 @@ use ply_rs::{
     parser::Parser,
     ply::{ElementDef, Header, Property, PropertyAccess},
 };
-use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
-use tokio_stream::Stream;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio_stream::{Stream, StreamExt};
 use tokio_with_wasm::alias as tokio_wasm;
 use tracing::trace_span;
 
-use anyhow::Result;
-use brush_render::gaussian_splats::Splats;
+use anyhow::{Context, Result};
+use brush_render::gaussian_splats::{Splats, TrackedStat, inverse_sigmoid};
+
+use crate::splat_export::{SPZ_COLOR_SCALE, SPZ_MAGIC, SPZ_SH_FLIP};
 
 pub(crate) struct GaussianData {
     pub(crate) means: Vec3,
@@ -28,6 +30,9 @@ pub(crate) struct GaussianData {
     // NB: This is in the inria format, aka [channels, coeffs]
     // not [coeffs, channels].
     pub(crate) sh_coeffs_rest: Vec<f32>,
+    // Statistics tracked while training, in the order of `TrackedStat::ALL`. Only exported,
+    // splats loaded from a file aren't tracked.
+    pub(crate) tracked: Option<[f32; 3]>,
 }
 
 impl PropertyAccess for GaussianData {
@@ -39,14 +44,17 @@ impl PropertyAccess for GaussianData {
             rotation: Quat::IDENTITY,
             sh_dc: [0.0, 0.0, 0.0],
             sh_coeffs_rest: Vec::new(),
+            tracked: None,
         }
     }
 
     fn set_property(&mut self, key: &str, property: Property) {
         let ascii = key.as_bytes();
 
-        let mut value = if let Property::Float(value) = property {
+        let value = if let Property::Float(value) = property {
             value
+        } else if let Property::Double(value) = property {
+            value as f32
         } else if let Property::UChar(value) = property {
             (value as f32) / (u8::MAX as f32)
         } else if let Property::UShort(value) = property {
@@ -55,11 +63,6 @@ impl PropertyAccess for GaussianData {
             return;
         };
 
-        if value.is_nan() || value.is_infinite() {
-            log::warn!("Invalid numbers in imported splat, defaulting to 0");
-            value = 0.0;
-        }
-
         match ascii {
             b"x" => self.means[0] = value,
             b"y" => self.means[1] = value,
@@ -115,9 +118,135 @@ impl PropertyAccess for GaussianData {
                     None
                 }
             }
-            _ => None,
+            _ => {
+                let stat = TrackedStat::ALL
+                    .iter()
+                    .position(|stat| stat.name() == key)?;
+                self.tracked.map(|tracked| tracked[stat])
+            }
+        }
+    }
+}
+
+impl GaussianData {
+    /// Replace values that aren't finite with zero.
+    fn zero_non_finite(&mut self) {
+        let finite_or_zero = |v: f32| if v.is_finite() { v } else { 0.0 };
+        self.means = self.means.map(finite_or_zero);
+        self.log_scale = self.log_scale.map(finite_or_zero);
+        self.opacity = finite_or_zero(self.opacity);
+        self.rotation = Quat::from_vec4(Vec4::from(self.rotation).map(finite_or_zero));
+    }
+
+    /// Check the splat is valid, and repair what can be repaired. Returns false for splats that
+    /// can't be repaired, and should be dropped.
+    fn validate(&mut self, report: &mut ImportReport) -> bool {
+        if !self.means.is_finite() || !self.log_scale.is_finite() || !self.opacity.is_finite() {
+            report.dropped += 1;
+            return false;
+        }
+
+        let mut repaired = false;
+        // Zero rotations can't be normalized, and break the projection.
+        let rotation = Vec4::from(self.rotation);
+        if !rotation.is_finite() || rotation.length_squared() < 1e-12 {
+            self.rotation = Quat::IDENTITY;
+            repaired = true;
+        }
+        // Huge scales overflow when projected, and tiny ones underflow.
+        let log_scale = self.log_scale.clamp(-MAX_LOG_SCALE, MAX_LOG_SCALE);
+        if log_scale != self.log_scale {
+            self.log_scale = log_scale;
+            repaired = true;
+        }
+        for sh in self.sh_dc.iter_mut().chain(&mut self.sh_coeffs_rest) {
+            if !sh.is_finite() {
+                *sh = 0.0;
+                repaired = true;
+            }
+        }
+
+        report.repaired += u32::from(repaired);
+        true
+    }
+}
+
+// Scales are clamped to exp(-20) to exp(20) times the unit of the scene.
+const MAX_LOG_SCALE: Vec3 = Vec3::splat(20.0);
+
+/// Invalid splats found while importing, eg. from a broken export of another trainer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Splats with positions, scales or opacities that aren't finite, which were dropped.
+    pub dropped: u32,
+    /// Splats with zero rotations, extreme scales, or colors that aren't finite, which were
+    /// repaired.
+    pub repaired: u32,
+}
+
+impl ImportReport {
+    /// Report of the invalid splats, if there were any.
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = vec![];
+        if self.dropped > 0 {
+            parts.push(format!(
+                "dropped {} splats with invalid positions, scales or opacities",
+                self.dropped
+            ));
+        }
+        if self.repaired > 0 {
+            parts.push(format!(
+                "repaired {} splats with invalid rotations, scales or colors",
+                self.repaired
+            ));
+        }
+        (!parts.is_empty()).then(|| format!("Splat file has invalid splats: {}", parts.join(", ")))
+    }
+}
+
+/// Property names other trainers use in place of the names of the Inria ply format, either as
+/// the full name or as a prefix followed by an index.
+const PROPERTY_ALIASES: [(&str, &str); 8] = [
+    ("features_dc_", "f_dc_"),
+    ("features_rest_", "f_rest_"),
+    ("scaling_", "scale_"),
+    ("rotation_", "rot_"),
+    ("opacities", "opacity"),
+    ("diffuse_red", "red"),
+    ("diffuse_green", "green"),
+    ("diffuse_blue", "blue"),
+];
+
+/// The name of the Inria ply format for a property.
+fn canonical_property_name(name: &str) -> String {
+    for (alias, canonical) in PROPERTY_ALIASES {
+        if let Some(rest) = name.strip_prefix(alias) {
+            let matches = if alias.ends_with('_') {
+                rest.parse::<u32>().is_ok()
+            } else {
+                rest.is_empty()
+            };
+            if matches {
+                return format!("{canonical}{rest}");
+            }
+        }
+    }
+    name.to_owned()
+}
+
+/// Read the header of a splat ply, with the properties renamed to the names of the Inria ply
+/// format, so files written by other trainers load the same.
+async fn read_splat_header<T: AsyncBufRead + Unpin>(
+    parser: &Parser<GaussianData>,
+    reader: &mut T,
+) -> Result<Header> {
+    let mut header = parser.read_header(reader).await?;
+    for element in &mut header.elements {
+        for property in &mut element.properties {
+            property.name = canonical_property_name(&property.name);
         }
     }
+    Ok(header)
 }
 
 fn interleave_coeffs(sh_dc: [f32; 3], sh_rest: &[f32]) -> Vec<f32> {
@@ -135,12 +264,12 @@ fn interleave_coeffs(sh_dc: [f32; 3], sh_rest: &[f32]) -> Vec<f32> {
     result
 }
 
-async fn decode_splat<T: AsyncBufRead + Unpin + 'static>(
+pub(crate) async fn decode_element<T: AsyncBufRead + Unpin + 'static, E: PropertyAccess>(
     reader: &mut T,
-    parser: &Parser<GaussianData>,
+    parser: &Parser<E>,
     header: &Header,
     element: &ElementDef,
-) -> tokio::io::Result<GaussianData> {
+) -> tokio::io::Result<E> {
     match header.encoding {
         ply_rs::ply::Encoding::Ascii => {
             let mut ascii_line = String::new();
@@ -158,11 +287,87 @@ async fn decode_splat<T: AsyncBufRead + Unpin + 'static>(
     }
 }
 
+/// Read just the header of a ply file to find out how many vertices it has.
+pub async fn ply_vertex_count<T: AsyncRead + Unpin>(reader: T) -> Result<usize> {
+    let mut reader = BufReader::new(reader);
+    let header = Parser::<GaussianData>::new()
+        .read_header(&mut reader)
+        .await?;
+    Ok(header
+        .elements
+        .iter()
+        .find(|e| e.name == "vertex")
+        .map_or(0, |e| e.count))
+}
+
+/// Which kind of ply a file is, see [`ply_dialect`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlyDialect {
+    /// Which tool wrote the file, or what it holds.
+    pub description: String,
+    /// Whether the splats are quantized in chunks, which can't be read.
+    pub compressed: bool,
+    pub vertices: usize,
+}
+
+/// Describe which kind of ply `reader` holds, judging by its header, eg. to tell which tool wrote
+/// a file.
+pub async fn ply_dialect<T: AsyncRead + Unpin>(reader: T) -> Result<PlyDialect> {
+    let mut reader = BufReader::new(reader);
+    let header = Parser::<GaussianData>::new()
+        .read_header(&mut reader)
+        .await?;
+    let Some(vertex) = header.elements.iter().find(|e| e.name == "vertex") else {
+        anyhow::bail!("The ply has no vertices");
+    };
+    let names: HashSet<String> = vertex
+        .properties
+        .iter()
+        .map(|p| canonical_property_name(&p.name))
+        .collect();
+    let renamed = vertex
+        .properties
+        .iter()
+        .any(|p| canonical_property_name(&p.name) != p.name);
+    let compressed = header.elements.iter().any(|e| e.name == "chunk");
+
+    let mut description = if compressed {
+        "Compressed splat ply (PlayCanvas / SuperSplat)".to_owned()
+    } else if names.contains("f_dc_0") || names.contains("scale_0") {
+        if renamed {
+            "Splat ply with renamed properties (other trainers)".to_owned()
+        } else {
+            "Splat ply (Inria 3DGS)".to_owned()
+        }
+    } else if names.contains("red") {
+        "Point cloud ply with colors".to_owned()
+    } else {
+        "Point cloud ply".to_owned()
+    };
+    let deltas = header
+        .elements
+        .iter()
+        .filter(|e| e.name.starts_with("delta_vertex_"))
+        .count();
+    if deltas > 0 {
+        description += &format!(", animated with {deltas} delta frames");
+    }
+    Ok(PlyDialect {
+        description,
+        compressed,
+        vertices: vertex.count,
+    })
+}
+
 pub struct SplatMetadata {
     pub up_axis: Option<Vec3>,
     pub total_splats: u32,
     pub frame_count: u32,
     pub current_frame: u32,
+    /// Invalid splats found while reading the splats so far.
+    pub report: ImportReport,
+    /// Whether the splats have trained shapes, rather than being the points of a point cloud.
+    pub trained: bool,
 }
 
 pub struct SplatMessage<B: Backend> {
@@ -190,7 +395,13 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
     try_fn_stream(|emitter| async move {
         let gaussian_parser = Parser::<GaussianData>::new();
 
-        let header = gaussian_parser.read_header(&mut reader).await?;
+        let header = read_splat_header(&gaussian_parser, &mut reader).await?;
+        if header.elements.iter().any(|e| e.name == "chunk") {
+            anyhow::bail!(
+                "Compressed plys (eg. from SuperSplat) can't be read, only written. Save the \
+                 splats as a regular ply first."
+            );
+        }
 
         let up_axis = header
             .comments
@@ -209,8 +420,14 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
             .filter(|e| e.name.starts_with("delta_vertex_"))
             .count() as u32;
 
+        let trained = header.elements.iter().any(|e| {
+            e.name == "chunk"
+                || (e.name == "vertex" && e.properties.iter().any(|p| p.name == "scale_0"))
+        });
+
         let mut final_splat = None;
         let mut frame = 0;
+        let mut report = ImportReport::default();
 
         let mut meta_min = QuantMeta {
             mean: Vec3::ZERO,
@@ -251,6 +468,9 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
                 .then(|| Vec::with_capacity(element.count));
 
             if element.name == "vertex" {
+                if properties.contains("packed_position") {
+                    anyhow::bail!("Compressed plys can't be read, only written.");
+                }
                 if ["x", "y", "z"].into_iter().any(|p| !properties.contains(p)) {
                     anyhow::bail!("Invalid splat ply. Missing properties!");
                 }
@@ -281,6 +501,8 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
                                     up_axis,
                                     frame_count,
                                     current_frame: frame,
+                                    report,
+                                    trained,
                                 },
                                 splats,
                             })
@@ -295,8 +517,19 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
                         }
                     }
 
-                    let splat =
-                        decode_splat(&mut reader, &gaussian_parser, &header, element).await?;
+                    let mut splat =
+                        decode_element(&mut reader, &gaussian_parser, &header, element).await?;
+
+                    if !splat.validate(&mut report) {
+                        if frame_count == 0 {
+                            continue;
+                        }
+                        // The delta frames refer to the splats by index, so hide the splat
+                        // instead of dropping it.
+                        splat.zero_non_finite();
+                        splat.log_scale = -MAX_LOG_SCALE;
+                        splat.opacity = -MAX_LOG_SCALE.x;
+                    }
 
                     means.push(splat.means);
                     if let Some(scales) = log_scales.as_mut() {
@@ -334,17 +567,19 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
                             up_axis,
                             frame_count,
                             current_frame: frame,
+                            report,
+                            trained,
                         },
                         splats,
                     })
                     .await;
             } else if element.name.starts_with("meta_delta_min_") {
-                let splat = decode_splat(&mut reader, &gaussian_parser, &header, element).await?;
+                let splat = decode_element(&mut reader, &gaussian_parser, &header, element).await?;
                 meta_min.mean = splat.means;
                 meta_min.rotation = splat.rotation.into();
                 meta_min.scale = splat.log_scale;
             } else if element.name.starts_with("meta_delta_max_") {
-                let splat = decode_splat(&mut reader, &gaussian_parser, &header, element).await?;
+                let splat = decode_element(&mut reader, &gaussian_parser, &header, element).await?;
                 meta_max.mean = splat.means;
                 meta_max.rotation = splat.rotation.into();
                 meta_max.scale = splat.log_scale;
@@ -360,8 +595,9 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
                     }
                     // The splat we decode is normed to 0-1 (if quantized), so rescale to
                     // actual values afterwards.
-                    let splat_enc =
-                        decode_splat(&mut reader, &gaussian_parser, &header, element).await?;
+                    let mut splat_enc =
+                        decode_element(&mut reader, &gaussian_parser, &header, element).await?;
+                    splat_enc.zero_non_finite();
 
                     // Let's only animate transforms for now.
                     means.push(splat_enc.means * (meta_max.mean - meta_min.mean) + meta_min.mean);
@@ -428,15 +664,504 @@ pub fn load_splat_from_ply<T: AsyncRead + Unpin + 'static, B: Backend>(
                             up_axis,
                             frame_count,
                             current_frame: frame,
+                            report,
+                            trained,
                         },
                         splats: new_splat,
                     })
                     .await;
 
                 frame += 1;
+            } else {
+                // Skip any other elements, eg. faces or the cameras some trainers write.
+                for _ in 0..element.count {
+                    decode_element(&mut reader, &gaussian_parser, &header, element).await?;
+                }
             }
         }
 
         Ok(())
     })
 }
+
+fn splat_message<B: Backend>(mut data: Vec<GaussianData>, device: &B::Device) -> SplatMessage<B> {
+    let mut report = ImportReport::default();
+    data.retain_mut(|splat| splat.validate(&mut report));
+
+    let means: Vec<Vec3> = data.iter().map(|s| s.means).collect();
+    let rotations: Vec<Quat> = data.iter().map(|s| s.rotation.normalize()).collect();
+    let log_scales: Vec<Vec3> = data.iter().map(|s| s.log_scale).collect();
+    let opacities: Vec<f32> = data.iter().map(|s| s.opacity).collect();
+    let sh_coeffs: Vec<f32> = data
+        .iter()
+        .flat_map(|s| interleave_coeffs(s.sh_dc, &s.sh_coeffs_rest))
+        .collect();
+
+    let splats = Splats::from_raw(
+        &means,
+        Some(&rotations),
+        Some(&log_scales),
+        Some(&sh_coeffs),
+        Some(&opacities),
+        device,
+    );
+    SplatMessage {
+        meta: SplatMetadata {
+            up_axis: None,
+            total_splats: splats.num_splats(),
+            frame_count: 1,
+            current_frame: 0,
+            report,
+            trained: true,
+        },
+        splats,
+    }
+}
+
+fn raw_opacity(alpha: u8) -> f32 {
+    inverse_sigmoid((alpha as f32 / 255.0).clamp(1e-4, 1.0 - 1e-4))
+}
+
+fn read_f32s<const N: usize>(data: &[u8]) -> [f32; N] {
+    std::array::from_fn(|i| {
+        f32::from_le_bytes([
+            data[i * 4],
+            data[i * 4 + 1],
+            data[i * 4 + 2],
+            data[i * 4 + 3],
+        ])
+    })
+}
+
+/// Decode the `.splat` format, 32 bytes per splat, as written by
+/// [`splat_to_splat`](crate::splat_export::splat_to_splat).
+pub(crate) fn decode_splat_file(data: &[u8]) -> Result<Vec<GaussianData>> {
+    if data.len() % 32 != 0 {
+        anyhow::bail!("Invalid .splat file, its size isn't a multiple of 32 bytes");
+    }
+    Ok(data
+        .chunks_exact(32)
+        .map(|record| {
+            let [x, y, z, sx, sy, sz] = read_f32s(record);
+            let [w, qx, qy, qz] = [28, 29, 30, 31].map(|i| (record[i] as f32 - 128.0) / 128.0);
+            GaussianData {
+                means: Vec3::new(x, y, z),
+                log_scale: Vec3::new(sx, sy, sz).ln(),
+                opacity: raw_opacity(record[27]),
+                rotation: Quat::from_xyzw(qx, qy, qz, w),
+                sh_dc: [24, 25, 26].map(|i| rgb_to_sh(record[i] as f32 / 255.0)),
+                sh_coeffs_rest: vec![],
+                tracked: None,
+            }
+        })
+        .collect())
+}
+
+/// Read splats in the `.splat` format, which only has base colors.
+pub fn load_splat_from_splat<B: Backend>(
+    data: &[u8],
+    device: &B::Device,
+) -> Result<SplatMessage<B>> {
+    Ok(splat_message(decode_splat_file(data)?, device))
+}
+
+/// Decode Niantic's spz format (version 2), as written by
+/// [`splat_to_spz`](crate::splat_export::splat_to_spz).
+pub(crate) fn decode_spz(data: &[u8]) -> Result<Vec<GaussianData>> {
+    use std::io::Read as _;
+
+    let mut raw = vec![];
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut raw)
+        .map_err(|e| anyhow::anyhow!("Invalid spz file, it's not gzipped: {e}"))?;
+
+    let header = |i: usize| u32::from_le_bytes([raw[i], raw[i + 1], raw[i + 2], raw[i + 3]]);
+    if raw.len() < 16 || header(0) != SPZ_MAGIC {
+        anyhow::bail!("Invalid spz file");
+    }
+    let version = header(4);
+    if version != 2 {
+        anyhow::bail!("Only version 2 spz files can be read, this file is version {version}");
+    }
+    let n = header(8) as usize;
+    let sh_degree = raw[12] as usize;
+    let fractional_bits = raw[13];
+    if sh_degree > 3 || fractional_bits > 24 {
+        anyhow::bail!("Invalid spz file, SH degree {sh_degree} with {fractional_bits} bits");
+    }
+    let num_rest = (sh_degree + 1) * (sh_degree + 1) - 1;
+
+    let positions = 16;
+    let alphas = positions + n * 9;
+    let colors = alphas + n;
+    let scales = colors + n * 3;
+    let rotations = scales + n * 3;
+    let sh = rotations + n * 3;
+    if raw.len() < sh + n * num_rest * 3 {
+        anyhow::bail!("Invalid spz file, it's cut off");
+    }
+
+    // spz uses RUB coordinates, flip the y and z axis back.
+    let flip = Vec3::new(1.0, -1.0, -1.0);
+    let fixed_scale = (1u32 << fractional_bits) as f32;
+
+    Ok((0..n)
+        .map(|i| {
+            let mean = Vec3::from_array([0, 1, 2].map(|c| {
+                let start = positions + i * 9 + c * 3;
+                // Sign extend the 24 bit fixed point value.
+                let fixed =
+                    i32::from_le_bytes([0, raw[start], raw[start + 1], raw[start + 2]]) >> 8;
+                fixed as f32 / fixed_scale
+            }));
+            let [x, y, z] = [0, 1, 2].map(|c| raw[rotations + i * 3 + c] as f32 / 127.5 - 1.0);
+            let w = (1.0 - x * x - y * y - z * z).max(0.0).sqrt();
+
+            let mut sh_coeffs_rest = vec![0.0; num_rest * 3];
+            for coeff in 0..num_rest {
+                for channel in 0..3 {
+                    let value = raw[sh + (i * num_rest + coeff) * 3 + channel] as f32;
+                    sh_coeffs_rest[channel * num_rest + coeff] =
+                        (value - 128.0) / 128.0 * SPZ_SH_FLIP[coeff];
+                }
+            }
+
+            GaussianData {
+                means: mean * flip,
+                log_scale: Vec3::from_array(
+                    [0, 1, 2].map(|c| raw[scales + i * 3 + c] as f32 / 16.0 - 10.0),
+                ),
+                opacity: raw_opacity(raw[alphas + i]),
+                rotation: Quat::from_xyzw(x, -y, -z, w),
+                sh_dc: [0, 1, 2]
+                    .map(|c| (raw[colors + i * 3 + c] as f32 / 255.0 - 0.5) / SPZ_COLOR_SCALE),
+                sh_coeffs_rest,
+                tracked: None,
+            }
+        })
+        .collect())
+}
+
+/// Read splats in Niantic's spz format (version 2).
+pub fn load_splat_from_spz<B: Backend>(data: &[u8], device: &B::Device) -> Result<SplatMessage<B>> {
+    Ok(splat_message(decode_spz(data)?, device))
+}
+
+/// Read the splats of the file `name` (a ply, .splat or .spz), and how many frames it has.
+/// Animated splats are read at their first frame, with the report of all frames.
+pub async fn load_splat_file<T: AsyncRead + Unpin + 'static, B: Backend>(
+    mut reader: T,
+    name: &str,
+    device: B::Device,
+) -> Result<(SplatMessage<B>, u32)> {
+    let lower = name.to_lowercase();
+    if lower.ends_with(".splat") || lower.ends_with(".spz") {
+        let mut data = vec![];
+        reader.read_to_end(&mut data).await?;
+        let message = if lower.ends_with(".splat") {
+            load_splat_from_splat(&data, &device)?
+        } else {
+            load_splat_from_spz(&data, &device)?
+        };
+        return Ok((message, 1));
+    }
+
+    let stream = load_splat_from_ply(reader, None, device);
+    let mut stream = std::pin::pin!(stream);
+    let mut first: Option<SplatMessage<B>> = None;
+    let mut frames = 0;
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        let frame = message.meta.current_frame;
+        frames = frames.max(frame + 1);
+        if frame == 0 {
+            first = Some(message);
+        } else if let Some(first) = first.as_mut() {
+            first.meta.report = message.meta.report;
+        }
+    }
+    let first = first.with_context(|| format!("No splats in {name}"))?;
+    Ok((first, frames))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use glam::{Quat, Vec3};
+    use ply_rs::parser::Parser;
+    use tokio::io::BufReader;
+
+    use super::{
+        GaussianData, ImportReport, canonical_property_name, decode_element, ply_dialect,
+        read_splat_header,
+    };
+
+    async fn read_first_vertex(ply: &str) -> GaussianData {
+        let mut reader = BufReader::new(Cursor::new(ply.as_bytes().to_vec()));
+        let parser = Parser::<GaussianData>::new();
+        let header = read_splat_header(&parser, &mut reader)
+            .await
+            .expect("Header should parse");
+        let vertex = header
+            .elements
+            .iter()
+            .find(|e| e.name == "vertex")
+            .expect("Ply should have vertices");
+        decode_element(&mut reader, &parser, &header, vertex)
+            .await
+            .expect("Vertex should parse")
+    }
+
+    async fn dialect(header: &str) -> (String, bool) {
+        let dialect = ply_dialect(Cursor::new(header.as_bytes().to_vec()))
+            .await
+            .expect("Header should parse");
+        assert_eq!(dialect.vertices, 2);
+        (dialect.description, dialect.compressed)
+    }
+
+    #[tokio::test]
+    async fn ply_dialects() {
+        let inria = "ply
+format binary_little_endian 1.0
+element vertex 2
+property float x
+property float y
+property float z
+property float f_dc_0
+property float scale_0
+end_header
+";
+        assert_eq!(
+            dialect(inria).await,
+            ("Splat ply (Inria 3DGS)".to_owned(), false)
+        );
+
+        let renamed = "ply
+format binary_little_endian 1.0
+element vertex 2
+property float x
+property float y
+property float z
+property float scaling_0
+end_header
+";
+        let (description, _) = dialect(renamed).await;
+        assert!(description.contains("renamed"), "{description}");
+
+        let points = "ply
+format binary_little_endian 1.0
+element vertex 2
+property double x
+property double y
+property double z
+property uchar red
+property uchar green
+property uchar blue
+end_header
+";
+        assert_eq!(
+            dialect(points).await,
+            ("Point cloud ply with colors".to_owned(), false)
+        );
+
+        // As written by PlayCanvas, with the quantization ranges in a chunk per 256 splats.
+        let compressed = "ply
+format binary_little_endian 1.0
+element chunk 1
+property float min_x
+property float max_x
+element vertex 2
+property uint packed_position
+property uint packed_rotation
+property uint packed_scale
+property uint packed_color
+end_header
+";
+        let (description, is_compressed) = dialect(compressed).await;
+        assert!(is_compressed);
+        assert!(description.starts_with("Compressed"), "{description}");
+
+        let animated = "ply
+format binary_little_endian 1.0
+element vertex 2
+property float x
+property float y
+property float z
+property float f_dc_0
+element delta_vertex_1 2
+property float x
+element delta_vertex_2 2
+property float x
+end_header
+";
+        let (description, _) = dialect(animated).await;
+        assert!(
+            description.ends_with("animated with 2 delta frames"),
+            "{description}"
+        );
+    }
+
+    #[test]
+    fn canonical_names() {
+        assert_eq!(canonical_property_name("features_dc_2"), "f_dc_2");
+        assert_eq!(canonical_property_name("features_rest_44"), "f_rest_44");
+        assert_eq!(canonical_property_name("scaling_0"), "scale_0");
+        assert_eq!(canonical_property_name("rotation_3"), "rot_3");
+        assert_eq!(canonical_property_name("opacities"), "opacity");
+        assert_eq!(canonical_property_name("diffuse_red"), "red");
+        // Only full names or prefixes followed by an index are renamed.
+        assert_eq!(canonical_property_name("rotation"), "rotation");
+        assert_eq!(canonical_property_name("opacities_0"), "opacities_0");
+        assert_eq!(canonical_property_name("f_dc_0"), "f_dc_0");
+    }
+
+    // As written by the Inria reference code, gsplat and OpenSplat, with (zero) normals.
+    #[tokio::test]
+    async fn inria_ply() {
+        let splat = read_first_vertex(
+            "ply
+format ascii 1.0
+element vertex 1
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float f_dc_0
+property float f_dc_1
+property float f_dc_2
+property float f_rest_0
+property float f_rest_1
+property float f_rest_2
+property float opacity
+property float scale_0
+property float scale_1
+property float scale_2
+property float rot_0
+property float rot_1
+property float rot_2
+property float rot_3
+end_header
+1 2 3 0 0 0 0.1 0.2 0.3 0.4 0.5 0.6 0.7 -1 -2 -3 1 0 0 0
+",
+        )
+        .await;
+        assert_eq!(splat.means, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(splat.sh_dc, [0.1, 0.2, 0.3]);
+        assert_eq!(splat.sh_coeffs_rest, vec![0.4, 0.5, 0.6]);
+        assert_eq!(splat.opacity, 0.7);
+        assert_eq!(splat.log_scale, Vec3::new(-1.0, -2.0, -3.0));
+        assert_eq!(splat.rotation, Quat::IDENTITY);
+    }
+
+    // Properties in another order, named after the parameters of the Inria code, with extra
+    // properties that aren't used.
+    #[tokio::test]
+    async fn renamed_properties() {
+        let splat = read_first_vertex(
+            "ply
+format ascii 1.0
+comment Written by some other trainer
+element vertex 1
+property float opacities
+property float rotation_0
+property float rotation_1
+property float rotation_2
+property float rotation_3
+property float scaling_0
+property float scaling_1
+property float scaling_2
+property float features_dc_0
+property float features_dc_1
+property float features_dc_2
+property float features_rest_0
+property uchar confidence
+property float x
+property float y
+property float z
+end_header
+0.5 0 1 0 0 -4 -5 -6 0.1 0.2 0.3 0.9 255 4 5 6
+",
+        )
+        .await;
+        assert_eq!(splat.means, Vec3::new(4.0, 5.0, 6.0));
+        assert_eq!(splat.sh_dc, [0.1, 0.2, 0.3]);
+        assert_eq!(splat.sh_coeffs_rest, vec![0.9]);
+        assert_eq!(splat.opacity, 0.5);
+        assert_eq!(splat.log_scale, Vec3::new(-4.0, -5.0, -6.0));
+        assert_eq!(splat.rotation, Quat::from_xyzw(1.0, 0.0, 0.0, 0.0));
+    }
+
+    // Double precision positions and 8 bit colors, as in exports of scanning apps and point
+    // clouds.
+    #[tokio::test]
+    async fn double_positions_and_colors() {
+        let splat = read_first_vertex(
+            "ply
+format ascii 1.0
+element vertex 1
+property double x
+property double y
+property double z
+property uchar diffuse_red
+property uchar diffuse_green
+property uchar diffuse_blue
+end_header
+-1.5 2.5 1000.25 255 0 255
+",
+        )
+        .await;
+        assert_eq!(splat.means, Vec3::new(-1.5, 2.5, 1000.25));
+        assert_eq!(splat.sh_dc[0], super::rgb_to_sh(1.0));
+        assert_eq!(splat.sh_dc[1], super::rgb_to_sh(0.0));
+    }
+
+    #[test]
+    fn invalid_splats() {
+        let splat = || GaussianData {
+            means: Vec3::ONE,
+            log_scale: Vec3::splat(-3.0),
+            opacity: 0.5,
+            rotation: Quat::IDENTITY,
+            sh_dc: [0.1, 0.2, 0.3],
+            sh_coeffs_rest: vec![0.4, 0.5, 0.6],
+            tracked: None,
+        };
+        let mut report = ImportReport::default();
+
+        assert!(splat().validate(&mut report));
+        assert_eq!(report, ImportReport::default());
+        assert_eq!(report.summary(), None);
+
+        let mut nan_mean = splat();
+        nan_mean.means.y = f32::NAN;
+        assert!(!nan_mean.validate(&mut report));
+        let mut infinite_scale = splat();
+        infinite_scale.log_scale.x = f32::INFINITY;
+        assert!(!infinite_scale.validate(&mut report));
+
+        let mut zero_rotation = splat();
+        zero_rotation.rotation = Quat::from_xyzw(0.0, 0.0, 0.0, 0.0);
+        assert!(zero_rotation.validate(&mut report));
+        assert_eq!(zero_rotation.rotation, Quat::IDENTITY);
+
+        let mut broken_colors = splat();
+        broken_colors.sh_coeffs_rest[1] = f32::NAN;
+        broken_colors.log_scale.z = 1000.0;
+        assert!(broken_colors.validate(&mut report));
+        assert_eq!(broken_colors.sh_coeffs_rest, vec![0.4, 0.0, 0.6]);
+        assert_eq!(broken_colors.log_scale, Vec3::new(-3.0, -3.0, 20.0));
+
+        assert_eq!(
+            report,
+            ImportReport {
+                dropped: 2,
+                repaired: 2
+            }
+        );
+        assert!(report.summary().is_some());
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use brush_render::{
+    bounding_box::quantile,
+    gaussian_splats::{Splats, inverse_sigmoid},
+    render::rgb_to_sh,
+};
+use burn::prelude::Backend;
+use glam::{IVec3, Vec3};
+use rand::Rng;
+
+use crate::ModelConfig;
+
+fn cell(pos: Vec3, size: f32) -> IVec3 {
+    (pos / size).floor().as_ivec3()
+}
+
+/// Average all points that fall in the same voxel.
+fn voxel_downsample(
+    positions: &[Vec3],
+    colors: &[Vec3],
+    voxel_size: f32,
+) -> (Vec<Vec3>, Vec<Vec3>) {
+    let mut voxels: HashMap<IVec3, (Vec3, Vec3, f32)> = HashMap::new();
+    // Keep track of insertion order so the result is deterministic.
+    let mut order = vec![];
+
+    for (pos, col) in positions.iter().zip(colors) {
+        let key = cell(*pos, voxel_size);
+        let entry = voxels.entry(key).or_insert_with(|| {
+            order.push(key);
+            (Vec3::ZERO, Vec3::ZERO, 0.0)
+        });
+        entry.0 += *pos;
+        entry.1 += *col;
+        entry.2 += 1.0;
+    }
+
+    order
+        .iter()
+        .map(|key| {
+            let (pos, col, count) = voxels[key];
+            (pos / count, col / count)
+        })
+        .unzip()
+}
+
+/// Greedily keep points that are at least `radius` away from all previously kept points.
+fn poisson_disk_downsample(
+    positions: &[Vec3],
+    colors: &[Vec3],
+    radius: f32,
+) -> (Vec<Vec3>, Vec<Vec3>) {
+    let mut grid: HashMap<IVec3, Vec<Vec3>> = HashMap::new();
+    let mut kept_pos = vec![];
+    let mut kept_col = vec![];
+
+    for (pos, col) in positions.iter().zip(colors) {
+        let key = cell(*pos, radius);
+
+        let mut too_close = false;
+        'search: for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let neighbours = grid.get(&(key + IVec3::new(x, y, z)));
+                    if neighbours.is_some_and(|n| n.iter().any(|p| p.distance(*pos) < radius)) {
+                        too_close = true;
+                        break 'search;
+                    }
+                }
+            }
+        }
+
+        if !too_close {
+            grid.entry(key).or_default().push(*pos);
+            kept_pos.push(*pos);
+            kept_col.push(*col);
+        }
+    }
+
+    (kept_pos, kept_col)
+}
+
+fn uses_init_options(config: &ModelConfig) -> bool {
+    config.init_voxel_size.is_some()
+        || config.init_poisson_radius.is_some()
+        || config.init_background_points > 0
+        || config.init_opacity.is_some()
+        || config.init_scale.is_some()
+}
+
+/// Apply the initialization options in [`ModelConfig`] to a set of initial splats.
+///
+/// When any option is set, the splats are rebuilt from just their positions and base colors,
+/// so this is meant for point clouds and random splats, not for continuing from already trained
+/// splats (see [`crate::splat_import::SplatMetadata::trained`]).
+pub async fn apply_init_config<B: Backend>(
+    splats: Splats<B>,
+    config: &ModelConfig,
+    rng: &mut impl Rng,
+) -> anyhow::Result<Splats<B>> {
+    if !uses_init_options(config) {
+        return Ok(splats);
+    }
+
+    let device = splats.device();
+    let n = splats.num_splats() as usize;
+
+    let means: Vec<f32> = splats
+        .means
+        .val()
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(|e| anyhow!("Failed to read splat means {e:?}"))?;
+    let sh_dc: Vec<f32> = splats
+        .sh_coeffs
+        .val()
+        .slice([0..n, 0..1])
+        .reshape([n, 3])
+        .into_data_async()
+        .await
+        .to_vec()
+        .map_err(|e| anyhow!("Failed to read splat colors {e:?}"))?;
+
+    let mut positions: Vec<Vec3> = means.chunks_exact(3).map(Vec3::from_slice).collect();
+    let mut colors: Vec<Vec3> = sh_dc.chunks_exact(3).map(Vec3::from_slice).collect();
+
+    if let Some(voxel_size) = config.init_voxel_size {
+        (positions, colors) = voxel_downsample(&positions, &colors, voxel_size.max(1e-6));
+    }
+
+    if let Some(radius) = config.init_poisson_radius {
+        (positions, colors) = poisson_disk_downsample(&positions, &colors, radius.max(1e-6));
+    }
+
+    log::info!(
+        "Initial point cloud reduced from {n} to {} points",
+        positions.len()
+    );
+
+    if config.init_background_points > 0 {
+        let center = if positions.is_empty() {
+            Vec3::ZERO
+        } else {
+            positions.iter().copied().sum::<Vec3>() / positions.len() as f32
+        };
+        // Ignore far away outliers, which would put the background points too far out.
+        let mut distances: Vec<f32> = positions.iter().map(|p| p.distance(center)).collect();
+        let radius = quantile(&mut distances, 0.95).unwrap_or(0.0).max(1e-3);
+
+        // Sample points in a shell from 1x to 2x the bounding sphere of the scene,
+        // to give the background something to work with.
+        for _ in 0..config.init_background_points {
+            let dir = loop {
+                let v = Vec3::new(
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                );
+                if let Some(dir) = v.try_normalize() {
+                    break dir;
+                }
+            };
+            let dist = radius * rng.random_range(1.0..2.0);
+            positions.push(center + dir * dist);
+            colors.push(Vec3::splat(rgb_to_sh(0.5)));
+        }
+    }
+
+    if positions.is_empty() {
+        anyhow::bail!("No initial points left after subsampling.");
+    }
+
+    let sh_coeffs: Vec<f32> = colors.iter().flat_map(|c| c.to_array()).collect();
+    let log_scales = config
+        .init_scale
+        .map(|s| vec![Vec3::splat(s.max(1e-6).ln()); positions.len()]);
+    let opacities = config
+        .init_opacity
+        .map(|o| vec![inverse_sigmoid(o.clamp(1e-4, 1.0 - 1e-4)); positions.len()]);
+
+    Ok(Splats::from_raw(
+        &positions,
+        None,
+        log_scales.as_deref(),
+        Some(&sh_coeffs),
+        opacities.as_deref(),
+        &device,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{poisson_disk_downsample, voxel_downsample};
+    use glam::{Vec3, vec3};
+
+    #[test]
+    fn voxel_downsample_averages_points_per_voxel() {
+        let positions = [
+            vec3(0.1, 0.1, 0.1),
+            vec3(0.3, 0.1, 0.1),
+            vec3(1.5, 0.5, 0.5),
+        ];
+        let colors = [Vec3::ZERO, Vec3::ONE, Vec3::splat(0.5)];
+        let (positions, colors) = voxel_downsample(&positions, &colors, 1.0);
+        assert_eq!(positions.len(), 2);
+        assert!(positions[0].abs_diff_eq(vec3(0.2, 0.1, 0.1), 1e-6));
+        assert!(positions[1].abs_diff_eq(vec3(1.5, 0.5, 0.5), 1e-6));
+        assert!(colors.iter().all(|c| c.abs_diff_eq(Vec3::splat(0.5), 1e-6)));
+    }
+
+    #[test]
+    fn poisson_disk_downsample_keeps_points_apart() {
+        let positions: Vec<Vec3> = (0..100).map(|i| vec3(i as f32 * 0.01, 0.0, 0.0)).collect();
+        let colors = vec![Vec3::ONE; positions.len()];
+        let (kept, kept_colors) = poisson_disk_downsample(&positions, &colors, 0.1);
+        assert_eq!(kept.len(), kept_colors.len());
+        // About every 10th point is kept, depending on float rounding.
+        assert!((9..=10).contains(&kept.len()));
+        for (i, a) in kept.iter().enumerate() {
+            for b in &kept[i + 1..] {
+                assert!(a.distance(*b) >= 0.1);
+            }
+        }
+    }
+}
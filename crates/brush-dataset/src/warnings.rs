@@ -0,0 +1,169 @@
+//! Checks for datasets that load fine, but are likely to train into a poor result.
+
+use std::collections::HashMap;
+
+use brush_train::scene::SceneView;
+use glam::Vec3;
+
+use crate::Dataset;
+
+/// Fewer views than this rarely cover a scene well enough.
+const MIN_VIEWS: usize = 20;
+/// When the viewing directions of the cameras average out to a vector this long, they all look
+/// the same way, and see the scene from one side.
+const ONE_SIDED: f32 = 0.9;
+/// Views with a horizontal field of view narrower than this (in degrees) see hardly any
+/// parallax, which makes depth hard to recover.
+const MIN_FOV_DEGREES: f64 = 10.0;
+/// Largest ratio between the pixel counts of the largest and smallest images.
+const MAX_RESOLUTION_RATIO: u64 = 4;
+
+impl Dataset {
+    /// Problems with the dataset that don't stop training, but predict poor results, as
+    /// sentences to show to the user.
+    pub fn warnings(&self) -> Vec<String> {
+        let views: Vec<&SceneView> = self
+            .train
+            .views
+            .iter()
+            .chain(self.eval.iter().flat_map(|e| e.views.as_slice()))
+            .collect();
+        if views.is_empty() {
+            return vec![];
+        }
+
+        let mut warnings = vec![];
+
+        let train_views = self.train.views.len();
+        if train_views < MIN_VIEWS {
+            warnings.push(format!(
+                "Only {train_views} views to train on, at least {MIN_VIEWS} are recommended."
+            ));
+        }
+
+        let mean_forward = views
+            .iter()
+            .map(|view| view.camera.rotation * Vec3::Z)
+            .sum::<Vec3>()
+            / views.len() as f32;
+        if views.len() > 1 && mean_forward.length() > ONE_SIDED {
+            warnings.push(
+                "All views look in about the same direction, so the scene is only seen from one \
+                 side. It will look wrong from other sides."
+                    .to_owned(),
+            );
+        }
+
+        let narrow = views
+            .iter()
+            .filter(|view| view.camera.fov_x.to_degrees() < MIN_FOV_DEGREES)
+            .count();
+        if narrow > 0 {
+            warnings.push(format!(
+                "{narrow} views have a very long focal length (a field of view under \
+                 {MIN_FOV_DEGREES}°), which makes their depth hard to recover."
+            ));
+        }
+
+        // Rotated images (eg. by EXIF orientation) whose intrinsics weren't rotated along with
+        // them, show up as images in portrait with a camera in landscape or the other way around,
+        // or as one camera taking both.
+        let mut group_orientations: HashMap<u32, (bool, bool)> = HashMap::new();
+        let mut mismatched = 0;
+        for view in &views {
+            let (width, height) = (view.image.width(), view.image.height());
+            let fov_portrait = view.camera.fov_y > view.camera.fov_x;
+            if width != height && (height > width) != fov_portrait {
+                mismatched += 1;
+            }
+            let seen = group_orientations.entry(view.camera_group).or_default();
+            if height > width {
+                seen.0 = true;
+            } else {
+                seen.1 = true;
+            }
+        }
+        if mismatched > 0 {
+            warnings.push(format!(
+                "{mismatched} views have images rotated compared to their camera. Rotated images \
+                 need rotated intrinsics, otherwise they won't train well."
+            ));
+        }
+        let mixed_groups = group_orientations
+            .values()
+            .filter(|(portrait, landscape)| *portrait && *landscape)
+            .count();
+        if mixed_groups > 0 {
+            warnings.push(format!(
+                "{mixed_groups} cameras took both portrait and landscape images. Rotated images \
+                 need their own intrinsics, otherwise they won't train well."
+            ));
+        }
+
+        let pixels = views
+            .iter()
+            .map(|view| u64::from(view.image.width()) * u64::from(view.image.height()));
+        let smallest = pixels.clone().min().unwrap_or_default();
+        let largest = pixels.max().unwrap_or_default();
+        if largest > smallest * MAX_RESOLUTION_RATIO {
+            warnings.push(format!(
+                "The views differ a lot in resolution, from {smallest} to {largest} pixels. Detail \
+                 from the large images is lost where small images see the same part.",
+            ));
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use brush_render::camera::Camera;
+    use brush_train::scene::ViewImageType;
+    use glam::Quat;
+
+    use super::*;
+    use crate::preprocess::PreprocessReport;
+
+    fn view(position: Vec3, rotation: Quat, width: u32, height: u32) -> SceneView {
+        let fov_x = 1.0;
+        let fov_y = 2.0 * ((fov_x * 0.5_f64).tan() * f64::from(height) / f64::from(width)).atan();
+        SceneView {
+            path: String::new(),
+            camera: Camera::new(position, rotation, fov_x, fov_y, glam::vec2(0.5, 0.5)),
+            image: Arc::new(image::DynamicImage::new_rgb8(width, height)),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![]),
+        }
+    }
+
+    #[test]
+    fn orbit_without_warnings() {
+        let views = (0..24)
+            .map(|i| {
+                let rotation = Quat::from_rotation_y(i as f32 * std::f32::consts::TAU / 24.0);
+                view(rotation * Vec3::NEG_Z * 3.0, rotation, 64, 48)
+            })
+            .collect();
+        let dataset = Dataset::from_views(views, vec![], PreprocessReport::default());
+        assert!(dataset.warnings().is_empty(), "{:?}", dataset.warnings());
+    }
+
+    #[test]
+    fn one_sided_and_mixed() {
+        let views = (0..24)
+            .map(|i| {
+                let (width, height) = if i % 2 == 0 { (64, 48) } else { (48, 64) };
+                view(Vec3::X * i as f32, Quat::IDENTITY, width, height)
+            })
+            .collect();
+        let dataset = Dataset::from_views(views, vec![], PreprocessReport::default());
+        let warnings = dataset.warnings();
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(warnings[0].contains("one side"));
+        assert!(warnings[1].contains("portrait and landscape"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Runs auxiliary models on training images, eg. to estimate depth or find the sky.
+//!
+//! Models are ONNX files supplied by the user. They should take a single `[1, 3, height, width]`
+//! RGB image, normalized with the usual ImageNet mean and standard deviation, and output a single
+//! channel map (`[1, 1, h, w]`, `[1, h, w]` or `[h, w]`). The output is resized to the size of the
+//! image it was run on.
+
+use image::{DynamicImage, ImageBuffer, Luma};
+
+/// A single channel map predicted for an image.
+pub type AuxMap = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+/// What a model predicts, which decides how its output is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuxModelKind {
+    /// Relative depth or disparity.
+    Depth,
+    /// Probability (0-1) that a pixel is sky.
+    SkyMask,
+    /// How interesting (0-1) a pixel is.
+    Saliency,
+}
+
+#[cfg_attr(not(feature = "onnx"), allow(dead_code))]
+pub struct AuxModel {
+    kind: AuxModelKind,
+    // Resolution the model runs at, width by height.
+    input_size: (u32, u32),
+    #[cfg(feature = "onnx")]
+    plan: tract_onnx::prelude::TypedRunnableModel<tract_onnx::prelude::TypedModel>,
+}
+
+impl AuxModel {
+    /// Load an ONNX model, which runs on images resized to `input_size` (width, height).
+    #[cfg(feature = "onnx")]
+    pub fn load(onnx: &[u8], kind: AuxModelKind, input_size: (u32, u32)) -> anyhow::Result<Self> {
+        use tract_onnx::prelude::*;
+
+        let (width, height) = (input_size.0 as usize, input_size.1 as usize);
+        let plan = tract_onnx::onnx()
+            .model_for_read(&mut std::io::Cursor::new(onnx))?
+            .with_input_fact(
+                0,
+                InferenceFact::dt_shape(f32::datum_type(), tvec!(1, 3, height, width)),
+            )?
+            .into_optimized()?
+            .into_runnable()?;
+
+        Ok(Self {
+            kind,
+            input_size,
+            plan,
+        })
+    }
+
+    /// Load an ONNX model. Brush was built without ONNX support, so this always fails.
+    #[cfg(not(feature = "onnx"))]
+    pub fn load(
+        _onnx: &[u8],
+        _kind: AuxModelKind,
+        _input_size: (u32, u32),
+    ) -> anyhow::Result<Self> {
+        anyhow::bail!("Brush was built without support for ONNX models (the `onnx` feature)")
+    }
+
+    pub fn kind(&self) -> AuxModelKind {
+        self.kind
+    }
+
+    /// The image as a normalized NCHW tensor, resized to the input size of the model.
+    #[cfg(feature = "onnx")]
+    fn input_data(&self, image: &DynamicImage) -> Vec<f32> {
+        const IMAGENET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+        const IMAGENET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+        let (width, height) = self.input_size;
+        let image = image
+            .resize_exact(width, height, image::imageops::FilterType::Triangle)
+            .to_rgb8();
+        let plane = (width * height) as usize;
+        let mut data = vec![0.0; plane * 3];
+        for (i, pixel) in image.pixels().enumerate() {
+            for (c, (mean, stddev)) in IMAGENET_MEAN.iter().zip(IMAGENET_STD).enumerate() {
+                let value = pixel[c] as f32 / 255.0;
+                data[c * plane + i] = (value - mean) / stddev;
+            }
+        }
+        data
+    }
+
+    /// Run the model on `image`, returning its prediction at the size of the image.
+    #[cfg(feature = "onnx")]
+    pub fn run(&self, image: &DynamicImage) -> anyhow::Result<AuxMap> {
+        use tract_onnx::prelude::*;
+
+        let (width, height) = self.input_size;
+        let input = tract_ndarray::Array4::from_shape_vec(
+            (1, 3, height as usize, width as usize),
+            self.input_data(image),
+        )?;
+        let outputs = self.plan.run(tvec!(Tensor::from(input).into()))?;
+        let output = outputs
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Model has no outputs"))?
+            .to_array_view::<f32>()?;
+
+        let shape = output.shape();
+        let &[out_h, out_w] = &shape[shape.len().saturating_sub(2)..] else {
+            anyhow::bail!("Expected a 2D output map, got shape {shape:?}");
+        };
+        // Take the first channel, if there are several.
+        let data: Vec<f32> = output.iter().copied().take(out_h * out_w).collect();
+        let map = AuxMap::from_raw(out_w as u32, out_h as u32, data)
+            .ok_or_else(|| anyhow::anyhow!("Model output doesn't match its shape"))?;
+
+        Ok(image::imageops::resize(
+            &map,
+            image.width(),
+            image.height(),
+            image::imageops::FilterType::Triangle,
+        ))
+    }
+
+    /// Run the model on `image`. Brush was built without ONNX support, so this always fails.
+    #[cfg(not(feature = "onnx"))]
+    pub fn run(&self, _image: &DynamicImage) -> anyhow::Result<AuxMap> {
+        anyhow::bail!("Brush was built without support for ONNX models (the `onnx` feature)")
+    }
+}
@@ -1,21 +1,40 @@
-use std::io::Cursor;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::{path::Path, str::FromStr};
 
 use anyhow::anyhow;
 
 use brush_dataset::WasmNotSend;
-use brush_dataset::brush_vfs::{BrushVfs, PathReader};
+use brush_dataset::brush_vfs::{ArchiveKind, BrushVfs, PathReader};
 use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
-use tokio_stream::StreamExt;
-use tokio_util::io::StreamReader;
+
+/// Data that is already in memory, eg. handed over by a page that embeds the viewer.
+#[derive(Clone)]
+pub struct DataBytes {
+    pub name: String,
+    pub data: Arc<[u8]>,
+}
+
+impl std::fmt::Debug for DataBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataBytes")
+            .field("name", &self.name)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
 
 #[derive(Clone, Debug)]
 pub enum DataSource {
     PickFile,
     PickDirectory,
+    /// A url to download, which can end with a `#sha256=<hex>` checksum the data has to match.
     Url(String),
     Path(String),
+    /// An entry in the browser cache on the web, see `web_cache`, or in the download cache
+    /// otherwise, see `download_cache`.
+    Cached(String),
+    Bytes(DataBytes),
 }
 
 // Implement FromStr to allow Clap to parse string arguments into DataSource
@@ -40,11 +59,59 @@ async fn read_at_most<R: AsyncRead + Unpin>(
     limit: usize,
 ) -> std::io::Result<Vec<u8>> {
     let mut buffer = vec![0; limit];
-    let bytes_read = reader.read(&mut buffer).await?;
+    let mut bytes_read = 0;
+    while bytes_read < limit {
+        let read = reader.read(&mut buffer[bytes_read..]).await?;
+        if read == 0 {
+            break;
+        }
+        bytes_read += read;
+    }
     buffer.truncate(bytes_read);
     Ok(buffer)
 }
 
+/// Split the `#sha256=<hex>` checksum off a url, if it has one.
+pub fn split_checksum(url: &str) -> (&str, Option<&str>) {
+    match url.rsplit_once("#sha256=") {
+        Some((url, sha256)) => (url, Some(sha256)),
+        None => (url, None),
+    }
+}
+
+#[cfg(target_family = "wasm")]
+fn matches_checksum(data: &[u8], sha256: Option<&str>) -> bool {
+    use sha2::{Digest, Sha256};
+    sha256
+        .is_none_or(|expected| format!("{:x}", Sha256::digest(data)).eq_ignore_ascii_case(expected))
+}
+
+// Open a cache entry, reading it in chunks.
+#[cfg(target_family = "wasm")]
+async fn vfs_from_cache(key: &str) -> anyhow::Result<BrushVfs> {
+    let reader = crate::web_cache::reader(key)
+        .await?
+        .ok_or_else(|| anyhow!("{key} is no longer in the cache"))?;
+    DataSource::vfs_from_reader(reader).await
+}
+
+// Caching is best effort, eg. the storage quota might be exceeded.
+#[cfg(target_family = "wasm")]
+async fn store_in_cache(key: &str, data: &[u8]) {
+    if let Err(e) = crate::web_cache::store(key, data).await {
+        log::warn!("Failed to cache {key}: {e}");
+    }
+}
+
+/// Data that has been opened by [`DataSource::open`].
+pub struct OpenedSource {
+    pub vfs: BrushVfs,
+    /// Name to show for the data, eg. the file name or url.
+    pub name: String,
+    /// Opens the same data again without asking for it, eg. the path of a picked file.
+    pub reopen: Option<DataSource>,
+}
+
 impl DataSource {
     async fn vfs_from_reader(
         reader: impl AsyncRead + WasmNotSend + Unpin + 'static,
@@ -52,17 +119,15 @@ impl DataSource {
         // Small hack to peek some bytes: Read them
         // and add them at the start again.
         let mut data = BufReader::new(reader);
-        let peek = read_at_most(&mut data, 64).await?;
+        let peek = read_at_most(&mut data, ArchiveKind::PEEK_LEN).await?;
         let reader = std::io::Cursor::new(peek.clone()).chain(data);
 
         if peek.as_slice().starts_with(b"ply") {
             let mut path_reader = PathReader::default();
             path_reader.add(Path::new("input.ply"), reader);
             Ok(BrushVfs::from_paths(path_reader))
-        } else if peek.starts_with(b"PK") {
-            BrushVfs::from_zip_reader(reader)
-                .await
-                .map_err(|e| anyhow::anyhow!(e))
+        } else if let Some(kind) = ArchiveKind::detect(&peek) {
+            BrushVfs::from_archive_reader(kind, reader).await
         } else if peek.starts_with(b"<!DOCTYPE html>") {
             anyhow::bail!(
                 "Failed to download data (are you trying to download from Google Drive? You might have to use the proxy."
@@ -72,38 +137,175 @@ impl DataSource {
             let path = Path::new(&string);
             BrushVfs::from_directory(path).await
         } else {
-            anyhow::bail!("only zip and ply files are supported.")
+            anyhow::bail!("Only ply files, and zip, tar.gz and 7z archives are supported.")
+        }
+    }
+
+    // Open a file, without reading it into memory if it's an archive.
+    #[cfg(not(target_family = "wasm"))]
+    async fn vfs_from_file(path: &Path) -> anyhow::Result<BrushVfs> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let peek = read_at_most(&mut file, ArchiveKind::PEEK_LEN).await?;
+        match ArchiveKind::detect(&peek) {
+            Some(kind) => BrushVfs::from_archive_file(path, kind).await,
+            None => Self::vfs_from_reader(tokio::fs::File::open(path).await?).await,
         }
     }
 
-    pub async fn into_vfs(self) -> anyhow::Result<BrushVfs> {
+    /// Name to show for this source, eg. the name of a file. Not known for picked data before
+    /// it is picked.
+    fn name(&self) -> Option<String> {
         match self {
+            Self::PickFile | Self::PickDirectory => None,
+            Self::Url(url) => Some(url.clone()),
+            Self::Path(path) => Some(
+                Path::new(path)
+                    .file_name()
+                    .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned()),
+            ),
+            #[cfg(target_family = "wasm")]
+            Self::Cached(key) => Some(crate::web_cache::source_from_key(key)),
+            #[cfg(not(target_family = "wasm"))]
+            Self::Cached(key) => Some(key.clone()),
+            Self::Bytes(bytes) => Some(bytes.name.clone()),
+        }
+    }
+
+    /// Open the data, asking the user to pick it if needed.
+    pub async fn open(self) -> anyhow::Result<OpenedSource> {
+        match self {
+            #[cfg(feature = "file-dialogs")]
             Self::PickFile => {
                 let picked = rrfd::pick_file().await.map_err(|e| anyhow!(e))?;
-                let data = picked.read().await;
-                let reader = Cursor::new(data);
-                Self::vfs_from_reader(reader).await
+                let name = picked.file_name();
+                #[cfg(not(target_family = "wasm"))]
+                let reopen = picked
+                    .path()
+                    .map(|p| Self::Path(p.to_string_lossy().into_owned()));
+                #[cfg(not(target_family = "wasm"))]
+                if let Some(path) = picked.path() {
+                    return Ok(OpenedSource {
+                        vfs: Self::vfs_from_file(&path).await?,
+                        name,
+                        reopen,
+                    });
+                }
+                let data = picked.read().await?;
+                #[cfg(target_family = "wasm")]
+                let reopen = {
+                    let key = crate::web_cache::upload_key(&name, data.len() as u64);
+                    store_in_cache(&key, &data).await;
+                    Some(Self::Cached(key))
+                };
+                let reader = std::io::Cursor::new(data);
+                Ok(OpenedSource {
+                    vfs: Self::vfs_from_reader(reader).await?,
+                    name,
+                    reopen,
+                })
             }
+            #[cfg(feature = "file-dialogs")]
             Self::PickDirectory => {
                 let picked = rrfd::pick_directory().await.map_err(|e| anyhow!(e))?;
-                BrushVfs::from_directory(&picked).await
+                let reopen = Self::Path(picked.to_string_lossy().into_owned());
+                Ok(OpenedSource {
+                    vfs: BrushVfs::from_directory(&picked).await?,
+                    name: reopen.name().unwrap_or_default(),
+                    reopen: Some(reopen),
+                })
+            }
+            #[cfg(not(feature = "file-dialogs"))]
+            Self::PickFile | Self::PickDirectory => {
+                anyhow::bail!("File dialogs are not supported in this build, pass a path or URL.")
+            }
+            source => {
+                let name = source.name().unwrap_or_default();
+                // Data that was handed over in memory isn't kept around, so it can't be opened
+                // again.
+                let reopen = (!matches!(source, Self::Bytes(_))).then(|| source.clone());
+                Ok(OpenedSource {
+                    vfs: source.into_vfs().await?,
+                    name,
+                    reopen,
+                })
+            }
+        }
+    }
+
+    async fn into_vfs(self) -> anyhow::Result<BrushVfs> {
+        match self {
+            Self::PickFile | Self::PickDirectory => {
+                anyhow::bail!("Picked data has to be opened with DataSource::open")
             }
             Self::Url(url) => {
-                let mut url = url.clone();
+                let (url, sha256) = split_checksum(&url);
+                let mut url = url.to_owned();
                 if !url.starts_with("http://") && !url.starts_with("https://") {
                     url = format!("https://{url}");
                 }
-                let response = reqwest::get(url)
-                    .await
-                    .map_err(|e| anyhow!(e))?
-                    .bytes_stream();
-
-                let response =
-                    response.map(|b| b.map_err(|_e| std::io::ErrorKind::ConnectionAborted));
-                let reader = StreamReader::new(response);
-                Self::vfs_from_reader(reader).await
+
+                // On the web, keep downloads around so reloading the page doesn't download them again.
+                #[cfg(target_family = "wasm")]
+                {
+                    use crate::web_cache::Download;
+                    match crate::web_cache::download(&url, sha256).await? {
+                        Download::Cached(key) => vfs_from_cache(&key).await,
+                        Download::Uncached => {
+                            let data = reqwest::get(&url)
+                                .await
+                                .map_err(|e| anyhow!(e))?
+                                .bytes()
+                                .await
+                                .map_err(|e| anyhow!(e))?;
+                            anyhow::ensure!(
+                                matches_checksum(&data, sha256),
+                                "The download of {url} is corrupt, it doesn't match its sha256 \
+                                 checksum"
+                            );
+                            Self::vfs_from_reader(std::io::Cursor::new(data)).await
+                        }
+                    }
+                }
+
+                // Otherwise download to disk, so interrupted downloads can be resumed.
+                #[cfg(not(target_family = "wasm"))]
+                {
+                    let path = crate::download_cache::download(&url, sha256).await?;
+                    Self::vfs_from_file(&path).await
+                }
             }
             Self::Path(path) => BrushVfs::from_directory(&PathBuf::from(path)).await,
+            #[cfg(target_family = "wasm")]
+            Self::Cached(key) => vfs_from_cache(&key).await,
+            #[cfg(not(target_family = "wasm"))]
+            Self::Cached(key) => {
+                let path = crate::download_cache::path(&key);
+                anyhow::ensure!(path.is_file(), "{key} is no longer in the download cache");
+                Self::vfs_from_file(&path).await
+            }
+            Self::Bytes(bytes) => Self::vfs_from_reader(std::io::Cursor::new(bytes.data)).await,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::split_checksum;
+
+    #[test]
+    fn splits_checksum_off_url() {
+        assert_eq!(
+            split_checksum("https://example.com/garden.zip#sha256=ABC123"),
+            ("https://example.com/garden.zip", Some("ABC123"))
+        );
+        assert_eq!(
+            split_checksum("https://example.com/garden.zip#part"),
+            ("https://example.com/garden.zip#part", None)
+        );
+        // Only the last checksum counts.
+        assert_eq!(
+            split_checksum("example.com/a#sha256=1#sha256=2"),
+            ("example.com/a#sha256=1", Some("2"))
+        );
+    }
+}
@@ -0,0 +1,557 @@
+//! Cache of data downloaded from urls, on disk.
+//!
+//! Datasets can be gigabytes, and connections drop. Downloads are written to a partial file
+//! first, which is resumed with a range request when the download is interrupted. The range is
+//! only sent along with the `ETag` or `Last-Modified` of the first response, so a file that
+//! changed on the server since is downloaded from the start rather than spliced onto the old
+//! data. The file is only used once it's complete, and matches its checksum if one was given.
+//!
+//! A complete download without a checksum is checked against the server before it's used, and
+//! downloaded again if it changed. A checksum pins the data, so then it's only checked against
+//! that. On the web, downloads are streamed into the browser's storage by `web_cache` instead.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_stream::StreamExt;
+
+const PARTIAL_EXTENSION: &str = "part";
+const INFO_EXTENSION: &str = "json";
+// Times to try downloading a url, each attempt resuming where the last one stopped.
+const MAX_ATTEMPTS: u32 = 5;
+// Largest download to write to disk, unless `BRUSH_MAX_DOWNLOAD_SIZE` says otherwise.
+const MAX_DOWNLOAD_SIZE: u64 = 64 << 30;
+
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    /// Name of the entry in the cache, see [`path`].
+    pub key: String,
+    /// The url the data came from.
+    pub source: String,
+    pub size: u64,
+    /// Whether the download was interrupted, and is resumed the next time the url is opened.
+    pub partial: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryInfo {
+    url: String,
+    /// Checksum of the complete download.
+    sha256: Option<String>,
+    /// `ETag`, or else `Last-Modified`, of the response the partial download is from, to only
+    /// resume it if the data didn't change.
+    #[serde(default)]
+    validator: Option<String>,
+}
+
+/// Directory the downloads are kept in, `BRUSH_DOWNLOAD_CACHE` if set.
+pub fn dir() -> PathBuf {
+    std::env::var_os("BRUSH_DOWNLOAD_CACHE").map_or_else(
+        || {
+            dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("brush")
+                .join("downloads")
+        },
+        PathBuf::from,
+    )
+}
+
+/// Largest download to write to disk in bytes, `BRUSH_MAX_DOWNLOAD_SIZE` if set.
+pub fn max_size() -> u64 {
+    std::env::var("BRUSH_MAX_DOWNLOAD_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(MAX_DOWNLOAD_SIZE)
+}
+
+#[derive(Debug)]
+struct TooLarge {
+    size: u64,
+    limit: u64,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The download is {} bytes, more than the limit of {} bytes, see \
+             BRUSH_MAX_DOWNLOAD_SIZE",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// Cache key for data downloaded from a url: a hash of the url, and its file name to recognize
+/// it by.
+pub fn url_key(url: &str) -> String {
+    let hash: String = Sha256::digest(url.as_bytes())[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    let name: String = url
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .take(64)
+        .collect();
+    format!("{hash}_{name}")
+}
+
+/// Path of the complete download of `key`.
+pub fn path(key: &str) -> PathBuf {
+    dir().join(key)
+}
+
+fn partial_path(key: &str) -> PathBuf {
+    dir().join(format!("{key}.{PARTIAL_EXTENSION}"))
+}
+
+fn info_path(key: &str) -> PathBuf {
+    dir().join(format!("{key}.{INFO_EXTENSION}"))
+}
+
+async fn read_info(key: &str) -> anyhow::Result<EntryInfo> {
+    let data = tokio::fs::read(info_path(key)).await?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+async fn write_info(key: &str, info: &EntryInfo) -> anyhow::Result<()> {
+    tokio::fs::write(info_path(key), serde_json::to_vec_pretty(info)?).await?;
+    Ok(())
+}
+
+async fn hash_file(path: &PathBuf) -> anyhow::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// The `ETag` of a response, or its `Last-Modified` date if it has none.
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    [reqwest::header::ETAG, reqwest::header::LAST_MODIFIED]
+        .iter()
+        .find_map(|name| response.headers().get(name)?.to_str().ok())
+        .map(str::to_owned)
+}
+
+// Download the rest of `url` into the partial file of `key`.
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    key: &str,
+    info: &mut EntryInfo,
+) -> anyhow::Result<()> {
+    let partial = partial_path(key);
+    let offset = tokio::fs::metadata(&partial).await.map_or(0, |m| m.len());
+
+    let mut request = client.get(url);
+    // Without a validator there's no telling whether the data changed, so start over.
+    if let Some(validator) = info.validator.as_deref().filter(|_| offset > 0) {
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={offset}-"))
+            .header(reqwest::header::IF_RANGE, validator);
+    }
+    let response = request.send().await?;
+    // Nothing left to download, the last attempt got everything.
+    if offset > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(());
+    }
+    let response = response.error_for_status()?;
+
+    // Servers that don't support ranges, or whose data changed, send all of the data again.
+    let resume = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    if !resume {
+        info.validator = response_validator(&response);
+        write_info(key, info).await?;
+    }
+    let mut file = if resume {
+        log::info!("Resuming the download of {url} at {offset} bytes");
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&partial)
+            .await?
+    } else {
+        tokio::fs::File::create(&partial).await?
+    };
+
+    let start = if resume { offset } else { 0 };
+    let expected = response.content_length();
+    let limit = max_size();
+    if let Some(expected) = expected.filter(|e| start + e > limit) {
+        return Err(TooLarge {
+            size: start + expected,
+            limit,
+        }
+        .into());
+    }
+
+    let mut written = 0;
+    let mut stream = response.bytes_stream();
+    let streamed: anyhow::Result<()> = async {
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            written += chunk.len() as u64;
+            if start + written > limit {
+                return Err(TooLarge {
+                    size: start + written,
+                    limit,
+                }
+                .into());
+            }
+            file.write_all(&chunk).await?;
+        }
+        Ok(())
+    }
+    .await;
+    // Keep what did arrive when the connection drops, to resume from there.
+    file.flush().await?;
+    streamed?;
+
+    if let Some(expected) = expected {
+        anyhow::ensure!(
+            written >= expected,
+            "The connection closed after {written} of {expected} bytes"
+        );
+    }
+    Ok(())
+}
+
+// Whether the server still has the data of a complete download of `size` bytes. This asks for
+// the data after its end, which servers that support ranges answer without sending any data.
+async fn is_unchanged(
+    client: &reqwest::Client,
+    url: &str,
+    info: &EntryInfo,
+    size: u64,
+) -> anyhow::Result<bool> {
+    let mut request = client
+        .get(url)
+        .header(reqwest::header::RANGE, format!("bytes={size}-"));
+    if let Some(validator) = &info.validator {
+        request = request.header(reqwest::header::IF_RANGE, validator);
+    }
+    let response = request.send().await?;
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        // The data ends where the download does, unless the server says otherwise.
+        let total = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|range| {
+                range
+                    .to_str()
+                    .ok()?
+                    .strip_prefix("bytes */")?
+                    .parse::<u64>()
+                    .ok()
+            });
+        return Ok(total.is_none_or(|total| total == size));
+    }
+    let response = response.error_for_status()?;
+    // More data, or all of it as it changed, or as the server doesn't support ranges.
+    Ok(response.status() == StatusCode::OK
+        && match &info.validator {
+            Some(validator) => response_validator(&response).as_ref() == Some(validator),
+            None => response.content_length() == Some(size),
+        })
+}
+
+// Whether the complete download of `key` can be used, rather than downloading it again.
+async fn is_cache_valid(
+    client: &reqwest::Client,
+    url: &str,
+    key: &str,
+    sha256: Option<&str>,
+) -> anyhow::Result<bool> {
+    let path = path(key);
+    let info = read_info(key).await.ok();
+
+    if let Some(expected) = sha256 {
+        let actual = match info.as_ref().and_then(|info| info.sha256.clone()) {
+            Some(actual) => actual,
+            // Eg. the info was deleted, the data could be anything.
+            None => {
+                let actual = hash_file(&path).await?;
+                let info = EntryInfo {
+                    url: url.to_owned(),
+                    sha256: Some(actual.clone()),
+                    validator: info.and_then(|info| info.validator),
+                };
+                write_info(key, &info).await?;
+                actual
+            }
+        };
+        if !actual.eq_ignore_ascii_case(expected) {
+            log::warn!(
+                "The cached download of {url} doesn't match its checksum, downloading it again"
+            );
+            return Ok(false);
+        }
+        return Ok(true);
+    }
+
+    let info = info.unwrap_or(EntryInfo {
+        url: url.to_owned(),
+        sha256: None,
+        validator: None,
+    });
+    let size = tokio::fs::metadata(&path).await?.len();
+    match is_unchanged(client, url, &info, size).await {
+        Ok(true) => Ok(true),
+        Ok(false) => {
+            log::info!("{url} changed since it was downloaded, downloading it again");
+            Ok(false)
+        }
+        Err(e) => {
+            log::warn!("Couldn't check whether {url} changed, loading it from the cache: {e:#}");
+            Ok(true)
+        }
+    }
+}
+
+/// Download `url` into the cache, or resume an earlier download of it, and check it matches the
+/// `sha256` checksum if given. Returns the path of the downloaded file. Data that is already in
+/// the cache is only downloaded again if it doesn't match the checksum, or without one, if it
+/// changed on the server. When the server can't be reached, the cached data is used.
+pub async fn download(url: &str, sha256: Option<&str>) -> anyhow::Result<PathBuf> {
+    let key = url_key(url);
+    let path = path(&key);
+    tokio::fs::create_dir_all(dir())
+        .await
+        .with_context(|| format!("Failed to create the download cache {:?}", dir()))?;
+
+    let client = reqwest::Client::new();
+    if tokio::fs::try_exists(&path).await? {
+        if is_cache_valid(&client, url, &key, sha256).await? {
+            log::info!("Loading {url} from the download cache");
+            return Ok(path);
+        }
+        remove(&key).await?;
+    }
+
+    // Keep the validator of an interrupted download, to resume it.
+    let mut info = EntryInfo {
+        url: url.to_owned(),
+        sha256: None,
+        validator: read_info(&key).await.ok().and_then(|info| info.validator),
+    };
+    write_info(&key, &info).await?;
+
+    let mut attempt = 1;
+    while let Err(e) = download_attempt(&client, url, &key, &mut info).await {
+        if e.is::<TooLarge>() {
+            remove(&key).await?;
+            return Err(e.context(format!("Failed to download {url}")));
+        }
+        if attempt == MAX_ATTEMPTS {
+            return Err(e.context(format!(
+                "Failed to download {url}, opening it again resumes the download"
+            )));
+        }
+        log::warn!("Download of {url} was interrupted, retrying: {e:#}");
+        tokio::time::sleep(Duration::from_secs(u64::from(attempt))).await;
+        attempt += 1;
+    }
+
+    let partial = partial_path(&key);
+    let actual = hash_file(&partial).await?;
+    if let Some(expected) = sha256 {
+        if !actual.eq_ignore_ascii_case(expected) {
+            remove(&key).await?;
+            anyhow::bail!(
+                "The download of {url} is corrupt: its sha256 checksum is {actual}, but should be \
+                 {expected}"
+            );
+        }
+    }
+    tokio::fs::rename(&partial, &path).await?;
+    info.sha256 = Some(actual);
+    write_info(&key, &info).await?;
+    Ok(path)
+}
+
+/// List all downloads in the cache, including interrupted ones.
+pub async fn entries() -> anyhow::Result<Vec<CacheEntry>> {
+    let mut entries = vec![];
+    let Ok(mut files) = tokio::fs::read_dir(dir()).await else {
+        return Ok(entries);
+    };
+    let suffix = format!(".{INFO_EXTENSION}");
+
+    while let Some(file) = files.next_entry().await? {
+        let name = file.file_name().to_string_lossy().into_owned();
+        let Some(key) = name.strip_suffix(&suffix) else {
+            continue;
+        };
+        // Downloads can have the extension of the info files as well.
+        let Ok(info) = read_info(key).await else {
+            continue;
+        };
+        let (size, partial) = match tokio::fs::metadata(path(key)).await {
+            Ok(metadata) => (metadata.len(), false),
+            Err(_) => (
+                tokio::fs::metadata(partial_path(key))
+                    .await
+                    .map_or(0, |m| m.len()),
+                true,
+            ),
+        };
+        entries.push(CacheEntry {
+            key: key.to_owned(),
+            source: info.url,
+            size,
+            partial,
+        });
+    }
+
+    entries.sort_by(|a, b| a.source.cmp(&b.source));
+    Ok(entries)
+}
+
+/// Remove a download from the cache, complete or not.
+pub async fn remove(key: &str) -> anyhow::Result<()> {
+    for path in [path(key), partial_path(key), info_path(key)] {
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(anyhow::Error::from(e).context(format!("Failed to remove {path:?}")));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Remove all downloads from the cache.
+pub async fn clear() -> anyhow::Result<()> {
+    for entry in entries().await? {
+        remove(&entry.key).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    use super::url_key;
+
+    #[test]
+    fn url_key_has_hash_and_name() {
+        let key = url_key("https://example.com/data/garden.zip?x=1&y=2");
+        assert!(key.ends_with("_garden.zipx1y2"));
+        assert_ne!(key, url_key("https://example.org/data/garden.zip?x=1&y=2"));
+        assert_eq!(key, url_key("https://example.com/data/garden.zip?x=1&y=2"));
+        // Urls ending in a slash only have the hash.
+        assert_eq!(url_key("https://example.com/").len(), 17);
+    }
+
+    // Serve `body` over http, cutting off the first response halfway, and record the range of
+    // every request.
+    fn serve(body: Vec<u8>) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let url = format!(
+            "http://{}/data.bin",
+            listener.local_addr().expect("Address")
+        );
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let recorded = ranges.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("Failed to accept");
+                let mut range = None;
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.expect("Failed to read request").to_ascii_lowercase();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("range: bytes=") {
+                        range = Some(value.trim_end_matches('-').to_owned());
+                    }
+                }
+                recorded.lock().expect("Lock poisoned").push(range.clone());
+                let len = body.len();
+                let header = |status: &str, extra: String| {
+                    format!("HTTP/1.1 {status}\r\nETag: \"v1\"\r\nConnection: close\r\n{extra}\r\n")
+                };
+                let response = match range.map(|r| r.parse::<usize>().expect("Range start")) {
+                    None => {
+                        let mut response =
+                            header("200 OK", format!("Content-Length: {len}\r\n")).into_bytes();
+                        response.extend(&body[..len / 2]);
+                        response
+                    }
+                    Some(start) if start >= len => header(
+                        "416 Range Not Satisfiable",
+                        format!("Content-Range: bytes */{len}\r\nContent-Length: 0\r\n"),
+                    )
+                    .into_bytes(),
+                    Some(start) => {
+                        let extra = format!(
+                            "Content-Range: bytes {start}-{}/{len}\r\nContent-Length: {}\r\n",
+                            len - 1,
+                            len - start
+                        );
+                        let mut response = header("206 Partial Content", extra).into_bytes();
+                        response.extend(&body[start..]);
+                        response
+                    }
+                };
+                let _ = stream.write_all(&response);
+            }
+        });
+        (url, ranges)
+    }
+
+    #[tokio::test]
+    async fn resumes_and_revalidates_downloads() {
+        let dir = std::env::temp_dir().join(format!("brush-download-test-{}", std::process::id()));
+        // SAFETY: No other test reads the environment.
+        unsafe { std::env::set_var("BRUSH_DOWNLOAD_CACHE", &dir) };
+
+        let body: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let (url, ranges) = serve(body.clone());
+
+        // The first response is cut off, and the rest is asked for with a range.
+        let path = super::download(&url, None)
+            .await
+            .expect("Failed to download");
+        assert_eq!(std::fs::read(&path).expect("Failed to read download"), body);
+        let half = (body.len() / 2).to_string();
+        assert_eq!(
+            *ranges.lock().expect("Lock poisoned"),
+            [None, Some(half.clone())]
+        );
+
+        // Opening it again only checks whether it changed.
+        let cached = super::download(&url, None)
+            .await
+            .expect("Failed to load cache");
+        assert_eq!(cached, path);
+        assert_eq!(
+            *ranges.lock().expect("Lock poisoned"),
+            [None, Some(half), Some(body.len().to_string())]
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -3,4 +3,10 @@
 pub mod rerun_tools;
 
 pub mod data_source;
+#[cfg(not(target_family = "wasm"))]
+pub mod download_cache;
 pub mod process_loop;
+pub mod thumbnails;
+
+#[cfg(target_family = "wasm")]
+pub mod web_cache;
@@ -0,0 +1,237 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::{Receiver, Sender, channel, error::SendError};
+
+use super::ProcessMessage;
+
+/// What to do when a subscriber doesn't keep up with the messages of a process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait until the subscriber has room for the message. This slows the process down to the
+    /// speed of the subscriber.
+    Wait,
+    /// Skip training steps the subscriber has no room for, eg. for a viewer that only needs the
+    /// latest splats. Other messages are always delivered.
+    SkipSteps,
+}
+
+struct Subscriber {
+    sender: Sender<ProcessMessage>,
+    backpressure: Backpressure,
+    // Whether the process keeps running for this subscriber.
+    owner: bool,
+}
+
+/// Sends the messages of a process to any number of subscribers, eg. the UI, a logger, or a
+/// server streaming the training to a browser. Each subscriber has its own channel.
+#[derive(Clone)]
+pub struct MessageBus {
+    // None once the process has ended.
+    subscribers: Arc<Mutex<Option<Vec<Subscriber>>>>,
+}
+
+impl Default for MessageBus {
+    fn default() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Some(vec![]))),
+        }
+    }
+}
+
+impl MessageBus {
+    /// Receive all messages sent from now on, buffering up to `capacity` of them.
+    ///
+    /// The process doesn't keep running for these subscribers: it stops once the receiver in
+    /// [`RunningProcess::messages`](super::RunningProcess::messages) is dropped. The receiver
+    /// returns `None` after the last message of the process.
+    pub fn subscribe(
+        &self,
+        capacity: usize,
+        backpressure: Backpressure,
+    ) -> Receiver<ProcessMessage> {
+        self.add_subscriber(capacity, backpressure, false)
+    }
+
+    /// Subscribe the owner of the process. The process stops once all owners are gone.
+    pub(crate) fn subscribe_owner(&self, capacity: usize) -> Receiver<ProcessMessage> {
+        self.add_subscriber(capacity, Backpressure::Wait, true)
+    }
+
+    fn add_subscriber(
+        &self,
+        capacity: usize,
+        backpressure: Backpressure,
+        owner: bool,
+    ) -> Receiver<ProcessMessage> {
+        let (sender, receiver) = channel(capacity.max(1));
+        // When the process has already ended, the sender is dropped right away.
+        if let Some(subscribers) = self.subscribers.lock().expect("Lock poisoned").as_mut() {
+            subscribers.push(Subscriber {
+                sender,
+                backpressure,
+                owner,
+            });
+        }
+        receiver
+    }
+
+    /// Send a message to all subscribers. Fails once the owners of the process are gone.
+    pub(crate) async fn send(
+        &self,
+        message: ProcessMessage,
+    ) -> Result<(), SendError<ProcessMessage>> {
+        // Don't hold on to the lock while waiting for subscribers.
+        let subscribers: Vec<_> = {
+            let mut subscribers = self.subscribers.lock().expect("Lock poisoned");
+            let Some(subscribers) = subscribers.as_mut() else {
+                return Err(SendError(message));
+            };
+            subscribers.retain(|s| !s.sender.is_closed());
+            subscribers
+                .iter()
+                .map(|s| (s.sender.clone(), s.backpressure, s.owner))
+                .collect()
+        };
+
+        if !subscribers.iter().any(|&(_, _, owner)| owner) {
+            return Err(SendError(message));
+        }
+
+        let skippable = matches!(message, ProcessMessage::TrainStep { .. });
+        for (sender, backpressure, _) in subscribers {
+            // Closed subscribers are removed on the next message.
+            if skippable && backpressure == Backpressure::SkipSteps {
+                let _ = sender.try_send(message.clone());
+            } else {
+                let _ = sender.send(message.clone()).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// End the messages of the process: subscribers receive `None` once they've read all
+    /// messages sent before this.
+    pub(crate) fn close(&self) {
+        self.subscribers.lock().expect("Lock poisoned").take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use brush_render::{camera::Camera, gaussian_splats::Splats};
+    use brush_train::scene::{SceneView, ViewImageType};
+    use brush_train::train::TrainStepStats;
+    use burn::tensor::Tensor;
+    use burn_wgpu::WgpuDevice;
+    use glam::{Quat, Vec3};
+    use web_time::Instant;
+
+    use super::{Backpressure, MessageBus};
+    use crate::process_loop::ProcessMessage;
+
+    fn train_step(iter: u32) -> ProcessMessage {
+        let device = WgpuDevice::DefaultDevice;
+        let splats = Splats::from_raw(
+            &[Vec3::ZERO],
+            None,
+            Some(&[Vec3::ZERO]),
+            None,
+            None,
+            &device,
+        );
+        let gt_views = SceneView {
+            path: "view.png".to_owned(),
+            camera: Camera::new(Vec3::ZERO, Quat::IDENTITY, 1.0, 1.0, glam::vec2(0.5, 0.5)),
+            image: Arc::new(image::DynamicImage::new_rgb8(1, 1)),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![]),
+        };
+        let stats = TrainStepStats {
+            pred_image: Tensor::zeros([1, 1, 4], &device),
+            pixel_grads: None,
+            gt_views,
+            num_intersections: Tensor::zeros([1], &device),
+            num_visible: Tensor::zeros([1], &device),
+            loss: Tensor::zeros([1], &device),
+            lr_mean: 0.0,
+            lr_rotation: 0.0,
+            lr_scale: 0.0,
+            lr_coeffs: 0.0,
+            lr_opac: 0.0,
+        };
+        ProcessMessage::TrainStep {
+            splats: Box::new(splats),
+            stats: Box::new(stats),
+            iter,
+            timestamp: Instant::now(),
+        }
+    }
+
+    fn iter(message: Option<ProcessMessage>) -> Option<u32> {
+        match message {
+            Some(ProcessMessage::TrainStep { iter, .. }) => Some(iter),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_subscribers_skip_steps_without_stalling() {
+        let bus = MessageBus::default();
+        let mut owner = bus.subscribe_owner(16);
+        let mut slow = bus.subscribe(1, Backpressure::SkipSteps);
+
+        // Nothing reads the slow subscriber, but all steps still go out.
+        let send_all = async {
+            for i in 0..10 {
+                bus.send(train_step(i)).await.expect("Owner is subscribed");
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), send_all)
+            .await
+            .expect("Sending shouldn't wait for the slow subscriber");
+
+        for i in 0..10 {
+            assert_eq!(iter(owner.recv().await), Some(i));
+        }
+        // The slow subscriber only got the step it had room for.
+        assert_eq!(iter(slow.recv().await), Some(0));
+        assert!(slow.try_recv().is_err());
+
+        // Other messages are never skipped, once there's room again.
+        bus.send(ProcessMessage::NewSource)
+            .await
+            .expect("Owner is subscribed");
+        assert!(matches!(slow.recv().await, Some(ProcessMessage::NewSource)));
+    }
+
+    #[tokio::test]
+    async fn process_ends_with_its_owner() {
+        let bus = MessageBus::default();
+        let owner = bus.subscribe_owner(4);
+        let mut logger = bus.subscribe(4, Backpressure::Wait);
+
+        // Dropped subscribers don't hold up the others.
+        drop(bus.subscribe(1, Backpressure::Wait));
+        bus.send(ProcessMessage::NewSource)
+            .await
+            .expect("Owner is subscribed");
+        bus.send(ProcessMessage::NewSource)
+            .await
+            .expect("Owner is subscribed");
+        assert!(logger.recv().await.is_some());
+
+        // Other subscribers don't keep the process running.
+        drop(owner);
+        assert!(bus.send(ProcessMessage::NewSource).await.is_err());
+
+        // Once closed, subscribers read what's left, then see the end.
+        bus.close();
+        assert!(logger.recv().await.is_some());
+        assert!(logger.recv().await.is_none());
+        assert!(bus.subscribe(4, Backpressure::Wait).recv().await.is_none());
+    }
+}
@@ -1,7 +1,13 @@
+mod message_bus;
 mod process;
 mod process_args;
+mod run_metadata;
+mod train_progress;
 
 mod train_stream;
 
+pub use message_bus::*;
 pub use process::*;
 pub use process_args::*;
+pub use run_metadata::*;
+pub use train_progress::*;
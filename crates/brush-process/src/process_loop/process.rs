@@ -1,40 +1,65 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use burn::prelude::Backend;
 use burn::tensor::backend::AutodiffBackend;
-use burn_cubecl::cubecl::Runtime;
 use web_time::Instant;
 
-use crate::{data_source::DataSource, rerun_tools::VisualizeTools};
-use brush_dataset::{Dataset, brush_vfs::BrushVfs, splat_import};
+use crate::data_source::DataSource;
+use crate::rerun_tools::{RerunLogger, VisualizeTools};
+use brush_dataset::{
+    Dataset, WasmNotSend, brush_vfs::BrushVfs, splat_import, splat_init::apply_init_config,
+};
 use brush_render::gaussian_splats::{RandomSplatsConfig, Splats};
-use brush_train::train::{RefineStats, TrainBack, TrainStepStats};
-use burn::{backend::Autodiff, module::AutodiffModule};
-use burn_wgpu::{Wgpu, WgpuDevice, WgpuRuntime};
+use brush_train::callback::TrainHooks;
+use brush_train::eval::mean_with_ci;
+use brush_train::scene::{Scene, SceneView};
+use brush_train::train::{LiveTrainConfig, RefineStats, TrainBack, TrainStepStats};
+use burn::{
+    backend::Autodiff,
+    module::{AutodiffModule, Module},
+};
+use burn_wgpu::{Wgpu, WgpuDevice};
 use glam::Vec3;
 use rand::SeedableRng;
 use tokio::sync::mpsc::{Receiver, unbounded_channel};
-use tokio::sync::mpsc::{Sender, UnboundedReceiver};
-use tokio::sync::mpsc::{UnboundedSender, channel};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio_stream::StreamExt;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tokio_util::task::TaskTracker;
+use tokio_with_wasm::alias::task::JoinHandle;
 
 #[allow(unused)]
 use brush_dataset::splat_export;
 
 use super::{
-    ProcessArgs,
+    Backpressure, EvalMetrics, MessageBus, ProcessArgs, RateLimit, RunMetadata,
     train_stream::{self, train_stream},
 };
 
+// Messages buffered for rerun before it starts skipping training steps.
+const RERUN_CAPACITY: usize = 4;
+
+#[derive(Clone)]
 pub enum ProcessMessage {
     NewSource,
+    /// The data of the source was opened.
+    SourceOpened {
+        /// Name of the data, eg. a file name or url.
+        name: String,
+        /// Opens the same data again without asking for it, if possible.
+        reopen: Option<DataSource>,
+    },
     StartLoading {
         training: bool,
     },
     /// Some process errored out, and want to display this error
     /// to the user.
-    Error(anyhow::Error),
+    Error(Arc<anyhow::Error>),
+    /// Something went wrong that didn't stop the process, to show to the user.
+    Warning(String),
     /// Loaded a splat from a ply file.
     ///
     /// Nb: This includes all the intermediately loaded splats.
@@ -49,6 +74,12 @@ pub enum ProcessMessage {
     Dataset {
         data: Dataset,
     },
+    /// Size of the scene the learning rate of the means and the refine thresholds are scaled
+    /// with, and whether it was estimated from the cameras rather than set.
+    SceneScale {
+        scale: f32,
+        estimated: bool,
+    },
     /// Splat, or dataset and initial splat, are done loading.
     #[allow(unused)]
     DoneLoading {
@@ -73,36 +104,87 @@ pub enum ProcessMessage {
     EvalResult {
         iter: u32,
         avg_psnr: f32,
+        /// Half width of the 95% confidence interval of the PSNR over the eval views.
+        psnr_ci: f32,
         avg_ssim: f32,
+        /// Half width of the 95% confidence interval of the SSIM over the eval views.
+        ssim_ci: f32,
     },
 }
 
 #[derive(Debug, Clone)]
 pub enum ControlMessage {
     Paused(bool),
+    /// Train this many more steps, then pause.
+    Step(u32),
+    /// Export the splats after the next training step, regardless of `export_every`.
+    Export,
+    /// Change how often the splats are exported.
+    ExportEvery(u32),
+    /// Pause training until this is sent again with `false`, separately from [`Self::Paused`],
+    /// eg. while the app is in the background.
+    Suspend(bool),
+    /// Wait this long after every training step, to use less power.
+    Throttle(Option<Duration>),
+    /// Change how fast training may run.
+    RateLimit(RateLimit),
+    /// Change part of the training config, starting from the next step.
+    TrainConfig(LiveTrainConfig),
+    /// Whether to read back the splats each refine prunes, see
+    /// [`brush_train::train::TrainConfig::record_pruned`]. Kept separately from
+    /// [`Self::TrainConfig`], so changing the settings doesn't turn it off.
+    RecordPruned(bool),
+    /// Whether to calculate the loss gradient of each pixel, see
+    /// [`brush_train::train::TrainConfig::record_pixel_grads`]. Kept separately like
+    /// [`Self::RecordPruned`].
+    RecordPixelGrads(bool),
+}
+
+/// Name of an export without the extension, to name other files written alongside it.
+#[allow(unused)]
+fn export_file_stem(export_name: &str) -> String {
+    let path = Path::new(export_name);
+    let name = path
+        .file_name()
+        .map_or(export_name.into(), |n| n.to_string_lossy());
+    let stem = name.split('.').next().unwrap_or_default();
+    path.with_file_name(stem).to_string_lossy().into_owned()
 }
 
 async fn process_loop(
     source: DataSource,
-    output: Sender<ProcessMessage>,
+    output: MessageBus,
     args: ProcessArgs,
     device: WgpuDevice,
     control_receiver: UnboundedReceiver<ControlMessage>,
+    hooks: TrainHooks,
+    cancel: &CancellationToken,
+    exports: &TaskTracker,
 ) {
     if output.send(ProcessMessage::NewSource).await.is_err() {
         return;
     }
 
-    let vfs = source.into_vfs().await;
-
-    let vfs = match vfs {
-        Ok(vfs) => vfs,
+    let opened = match source.open().await {
+        Ok(opened) => opened,
         Err(e) => {
-            let _ = output.send(ProcessMessage::Error(e)).await;
+            let _ = output.send(ProcessMessage::Error(Arc::new(e))).await;
             return;
         }
     };
 
+    if output
+        .send(ProcessMessage::SourceOpened {
+            name: opened.name,
+            reopen: opened.reopen,
+        })
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let vfs = opened.vfs;
+
     let paths: Vec<_> = vfs.file_names().collect();
     log::info!("Mounted VFS with {} files", paths.len());
 
@@ -111,18 +193,32 @@ async fn process_loop(
         .all(|p| p.extension().is_some_and(|p| p == "ply"))
     {
         view_process_loop(paths, output.clone(), vfs, device).await
+    } else if cfg!(target_os = "ios") {
+        Err(anyhow::anyhow!(
+            "Training isn't supported on iOS yet, only viewing .ply files."
+        ))
     } else {
-        train_process_loop(output.clone(), vfs, device, control_receiver, &args).await
+        train_process_loop(
+            output.clone(),
+            vfs,
+            device,
+            control_receiver,
+            &args,
+            hooks,
+            cancel,
+            exports,
+        )
+        .await
     };
 
     if let Err(e) = result {
-        let _ = output.send(ProcessMessage::Error(e)).await;
+        let _ = output.send(ProcessMessage::Error(Arc::new(e))).await;
     }
 }
 
 async fn view_process_loop(
     paths: Vec<std::path::PathBuf>,
-    output: Sender<ProcessMessage>,
+    output: MessageBus,
     vfs: BrushVfs,
     device: WgpuDevice,
 ) -> Result<(), anyhow::Error> {
@@ -147,9 +243,11 @@ async fn view_process_loop(
         );
 
         let mut splat_stream = std::pin::pin!(splat_stream);
+        let mut report = splat_import::ImportReport::default();
 
         while let Some(message) = splat_stream.next().await {
             let message = message?;
+            report = message.meta.report;
 
             // If there's multiple ply files in a zip, don't support animated plys, that would
             // get rather mind bending.
@@ -172,6 +270,11 @@ async fn view_process_loop(
                 return Ok(());
             }
         }
+
+        if let Some(summary) = report.summary() {
+            log::warn!("{}: {summary}", path.display());
+            let _ = output.send(ProcessMessage::Warning(summary)).await;
+        }
     }
 
     let _ = output
@@ -180,30 +283,170 @@ async fn view_process_loop(
     Ok(())
 }
 
+/// Evaluate `splats` on every view of `eval_scene`, optionally saving the renders to `save_dir`
+/// and logging them to rerun. The results are logged to rerun from the message.
+#[allow(unused_variables)]
+async fn run_eval(
+    splats: Splats<<TrainBack as AutodiffBackend>::InnerBackend>,
+    eval_scene: Scene,
+    iter: u32,
+    device: WgpuDevice,
+    save_dir: Option<PathBuf>,
+    dataset_hash: String,
+    visualize: Arc<VisualizeTools>,
+) -> anyhow::Result<ProcessMessage> {
+    let mut psnrs = vec![];
+    let mut ssims = vec![];
+    let mut paths = vec![];
+
+    for sample in brush_train::eval::eval_stats(splats, &eval_scene, &device) {
+        psnrs.push(sample.psnr.clone().into_scalar_async().await);
+        ssims.push(sample.ssim.clone().into_scalar_async().await);
+        paths.push(sample.view.path.clone());
+        visualize.log_eval_sample(iter, &sample).await?;
+
+        #[cfg(not(target_family = "wasm"))]
+        if let Some(save_dir) = save_dir.as_ref() {
+            log::info!("Saving eval image to disk.");
+
+            let eval_render = brush_train::image::tensor_into_image(
+                sample.rendered.clone().into_data_async().await,
+            );
+            let rendered: image::DynamicImage = eval_render.to_rgb8().into();
+
+            let img_name = Path::new(&sample.view.path)
+                .file_stem()
+                .expect("No file name for eval view.")
+                .to_string_lossy();
+
+            let path = save_dir.join(format!("{img_name}.png"));
+            tokio::fs::create_dir_all(save_dir).await?;
+
+            log::info!("Saving eval view to {path:?}");
+
+            rendered.save(path)?;
+        }
+    }
+
+    let (avg_psnr, psnr_ci) = mean_with_ci(&psnrs);
+    let (avg_ssim, ssim_ci) = mean_with_ci(&ssims);
+
+    // Write the results next to the images, with the data they were evaluated on.
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(save_dir) = save_dir.as_ref() {
+        let views: Vec<_> = paths
+            .iter()
+            .zip(psnrs.iter().zip(&ssims))
+            .map(|(path, (psnr, ssim))| {
+                serde_json::json!({ "path": path, "psnr": psnr, "ssim": ssim })
+            })
+            .collect();
+        let report = serde_json::json!({
+            "step": iter,
+            "dataset_hash": dataset_hash,
+            "psnr": avg_psnr,
+            "psnr_ci": psnr_ci,
+            "ssim": avg_ssim,
+            "ssim_ci": ssim_ci,
+            "views": views,
+        });
+        tokio::fs::create_dir_all(save_dir).await?;
+        tokio::fs::write(
+            save_dir.join("report.json"),
+            serde_json::to_vec_pretty(&report)?,
+        )
+        .await?;
+    }
+
+    Ok(ProcessMessage::EvalResult {
+        iter,
+        avg_psnr,
+        psnr_ci,
+        avg_ssim,
+        ssim_ci,
+    })
+}
+
+/// Wait for the evals running alongside training to finish.
+async fn wait_for_evals(evals: &TaskTracker) {
+    evals.close();
+    evals.wait().await;
+    evals.reopen();
+}
+
+/// Run an eval alongside training, and send its result when it's done. Cancelling the process
+/// stops the eval at its next await point.
+fn spawn_background_eval(
+    eval: impl Future<Output = anyhow::Result<ProcessMessage>> + WasmNotSend + 'static,
+    output: MessageBus,
+    last_eval: Arc<std::sync::Mutex<Option<EvalMetrics>>>,
+    cancel: CancellationToken,
+) -> JoinHandle<()> {
+    tokio_with_wasm::alias::task::spawn(async move {
+        let Some(result) = cancel.run_until_cancelled(eval).await else {
+            log::info!("Background eval cancelled");
+            return;
+        };
+        let msg = result.unwrap_or_else(|e| ProcessMessage::Error(Arc::new(e)));
+        if let Some(metrics) = EvalMetrics::from_message(&msg) {
+            *last_eval.lock().expect("Lock poisoned") = Some(metrics);
+        }
+        let _ = output.send(msg).await;
+    })
+}
+
 async fn train_process_loop(
-    output: Sender<ProcessMessage>,
+    output: MessageBus,
     vfs: BrushVfs,
     device: WgpuDevice,
     control_receiver: UnboundedReceiver<ControlMessage>,
     process_args: &ProcessArgs,
+    hooks: TrainHooks,
+    // Cancelled with the process, to stop work that runs alongside it.
+    cancel: &CancellationToken,
+    // Exports still being written. These are waited for before the process ends.
+    #[allow(unused)] exports: &TaskTracker,
 ) -> Result<(), anyhow::Error> {
     let process_config = &process_args.process_config;
 
+    // Read the export volumes before loading anything, so a broken file fails right away, not
+    // at the first export.
+    #[cfg(not(target_family = "wasm"))]
+    let export_volumes = process_config.read_export_volumes().await?;
+
     let _ = output
         .send(ProcessMessage::StartLoading { training: true })
         .await;
 
+    let rerun_config = &process_args.rerun_config;
+    let visualize = Arc::new(VisualizeTools::new(rerun_config.rerun_enabled));
+    if rerun_config.rerun_enabled {
+        // Rerun logs from its own subscriber, which skips steps instead of slowing training down.
+        let messages = output.subscribe(RERUN_CAPACITY, Backpressure::SkipSteps);
+        let logger = RerunLogger::new(
+            visualize.clone(),
+            rerun_config.clone(),
+            process_args.train_config.total_steps,
+            device.clone(),
+        );
+        tokio_with_wasm::alias::task::spawn(logger.run(messages));
+    }
+
     <Autodiff<Wgpu> as Backend>::seed(process_config.seed);
     let mut rng = rand::rngs::StdRng::from_seed([process_config.seed as u8; 32]);
 
     // Load initial splats if included
     let mut initial_splats = None;
+    let mut initial_trained = false;
 
     let mut dataset = Dataset::empty();
-    let (mut splat_stream, mut data_stream) =
-        brush_dataset::load_dataset(vfs.clone(), &process_args.load_config, &device).await?;
-
-    let visualize = VisualizeTools::new(process_args.rerun_config.rerun_enabled);
+    let (mut splat_stream, mut data_stream) = brush_dataset::load_dataset(
+        vfs.clone(),
+        &process_args.load_config,
+        &process_args.model_config,
+        &device,
+    )
+    .await?;
 
     // Read dataset stream.
     while let Some(d) = data_stream.next().await {
@@ -216,11 +459,19 @@ async fn train_process_loop(
             .await;
     }
 
-    visualize.log_scene(&dataset.train, process_args.rerun_config.rerun_max_img_size)?;
+    if let Some(summary) = dataset.skipped_summary() {
+        log::info!("{summary}");
+    }
+
+    for warning in dataset.warnings() {
+        log::warn!("{warning}");
+        let _ = output.send(ProcessMessage::Warning(warning)).await;
+    }
 
     let estimated_up = dataset.estimate_up();
 
     // Read initial splats if any.
+    let mut report = splat_import::ImportReport::default();
     while let Some(message) = splat_stream.next().await {
         let message = message?;
         let msg = ProcessMessage::ViewSplats {
@@ -234,9 +485,16 @@ async fn train_process_loop(
         if output.send(msg).await.is_err() {
             return Ok(());
         }
+        report = message.meta.report;
+        initial_trained = message.meta.trained;
         initial_splats = Some(message.splats);
     }
 
+    if let Some(summary) = report.summary() {
+        log::warn!("{summary}");
+        let _ = output.send(ProcessMessage::Warning(summary)).await;
+    }
+
     let _ = output
         .send(ProcessMessage::DoneLoading { training: true })
         .await;
@@ -247,47 +505,162 @@ async fn train_process_loop(
         // By default, spawn the splats in bounds.
         let bounds = dataset.train.bounds();
         let bounds_extent = bounds.extent.length();
-        // Arbitrarily assume area of interest is 0.2 - 0.75 of scene bounds.
-        // Somewhat specific to the blender scenes
-        let adjusted_bounds = dataset
-            .train
-            .adjusted_bounds(bounds_extent * 0.25, bounds_extent);
+        // By default assume the area of interest is 0.25 - 1 of scene bounds in front of the
+        // cameras. Somewhat specific to the blender scenes.
+        let model_config = &process_args.model_config;
+        let adjusted_bounds = dataset.train.adjusted_bounds(
+            bounds_extent * model_config.init_near,
+            bounds_extent * model_config.init_far,
+        );
 
         let config = RandomSplatsConfig::new();
         Splats::from_random_config(&config, adjusted_bounds, &mut rng, &device)
     };
 
+    // The init options rebuild the splats from their positions and colors, which would throw away
+    // the shapes of already trained splats.
+    let splats = if initial_trained {
+        splats
+    } else {
+        apply_init_config(splats, &process_args.model_config, &mut rng).await?
+    };
     let splats = splats.with_sh_degree(process_args.model_config.sh_degree);
 
     let mut control_receiver = control_receiver;
 
     let eval_scene = dataset.eval.clone();
+    let dataset_hash = dataset.content_hash();
+    log::info!("Dataset hash {dataset_hash}");
+    #[allow(unused)]
+    let color_calibration = dataset.preprocess.color_calibration.clone();
+
+    // A fixed set of views to render with each export, spread evenly over the eval views,
+    // or the training views if there are none.
+    #[allow(unused)]
+    let export_render_views: Vec<SceneView> =
+        process_config.export_render_views.map_or(vec![], |count| {
+            let views = &dataset.eval.as_ref().unwrap_or(&dataset.train).views;
+            let count = (count as usize).min(views.len());
+            (0..count)
+                .map(|i| views[i * views.len() / count].clone())
+                .collect()
+        });
+
+    let scene_scale = process_args.train_config.scene_scale;
+    let scene_extent = scene_scale
+        .or_else(|| dataset.train.estimate_extent())
+        .unwrap_or(1.0);
+    log::info!("Scene scale {scene_extent}");
+    let _ = output
+        .send(ProcessMessage::SceneScale {
+            scale: scene_extent,
+            estimated: scene_scale.is_none(),
+        })
+        .await;
+
+    let (config_sender, config_receiver) = unbounded_channel();
     let stream = train_stream(
         dataset,
         splats,
         process_args.train_config.clone(),
+        scene_extent,
         device.clone(),
         process_args.process_config.start_iter,
+        config_receiver,
+        hooks,
     );
     let mut stream = std::pin::pin!(stream);
 
     let mut train_paused = false;
+    // Number of steps to still run while paused.
+    let mut steps_to_run = 0;
+    let mut suspended = false;
+    let mut throttle = None;
+    let mut rate_limit = process_config.rate_limit();
+    let mut live_config = process_args.train_config.live();
+
+    let background_device = match process_config.background_device()? {
+        Some(device) => {
+            log::info!("Running evals and exports on {device:?}");
+            Some(brush_render::burn_init_extra(device).await)
+        }
+        None => None,
+    };
+
+    let mut step_start = Instant::now();
+    #[allow(unused)]
+    let train_start = Instant::now();
+    // The last eval, written with the exports. Background evals fill it in when they finish.
+    let last_eval = Arc::new(std::sync::Mutex::new(None));
+    // Evals running alongside training. Only one runs at a time, so they don't pile up when
+    // they take longer than `eval_every` steps.
+    let evals = TaskTracker::new();
+    #[allow(unused)]
+    let mut export_requested = false;
+    #[allow(unused)]
+    let mut export_every = process_config.export_every;
 
     loop {
-        let control = if train_paused {
-            control_receiver.recv().await
-        } else {
-            control_receiver.try_recv().ok()
-        };
+        // Measure this before waiting for control messages, which can take a while when paused.
+        let step_time = step_start.elapsed();
+
+        // Handle all pending control messages, and wait for more while paused.
+        loop {
+            let control = if (train_paused && steps_to_run == 0) || suspended {
+                control_receiver.recv().await
+            } else {
+                control_receiver.try_recv().ok()
+            };
+
+            let Some(control) = control else {
+                break;
+            };
 
-        if let Some(control) = control {
             match control {
                 ControlMessage::Paused(paused) => {
                     train_paused = paused;
+                    steps_to_run = 0;
+                }
+                ControlMessage::Step(steps) => {
+                    train_paused = true;
+                    steps_to_run += steps;
+                }
+                ControlMessage::Export => {
+                    export_requested = true;
+                }
+                ControlMessage::ExportEvery(every) => {
+                    export_every = every.max(1);
+                }
+                ControlMessage::Suspend(suspend) => suspended = suspend,
+                ControlMessage::Throttle(delay) => throttle = delay,
+                ControlMessage::RateLimit(limit) => rate_limit = limit,
+                ControlMessage::TrainConfig(live) => {
+                    live_config = LiveTrainConfig {
+                        record_pruned: live_config.record_pruned,
+                        record_pixel_grads: live_config.record_pixel_grads,
+                        ..live
+                    };
+                    let _ = config_sender.send(live_config);
+                }
+                ControlMessage::RecordPruned(record) => {
+                    live_config.record_pruned = record;
+                    let _ = config_sender.send(live_config);
+                }
+                ControlMessage::RecordPixelGrads(record) => {
+                    live_config.record_pixel_grads = record;
+                    let _ = config_sender.send(live_config);
                 }
             }
         }
 
+        let delay = rate_limit
+            .delay(step_time)
+            .max(throttle.unwrap_or_default());
+        if !delay.is_zero() {
+            tokio_with_wasm::alias::time::sleep(delay).await;
+        }
+        step_start = Instant::now();
+
         let msg = stream.next().await;
 
         let Some(msg) = msg else {
@@ -308,6 +681,8 @@ async fn train_process_loop(
                 let export_path =
                     Path::new(process_config.export_path.as_deref().unwrap_or(".")).to_owned();
 
+                steps_to_run = steps_to_run.saturating_sub(1);
+
                 // We just finished iter 'iter', now starting iter + 1.
                 let iter = iter + 1;
                 let is_last_step = iter == process_args.train_config.total_steps;
@@ -316,117 +691,181 @@ async fn train_process_loop(
                 // before doing a refine.
                 if iter % process_config.eval_every == 0 || is_last_step {
                     if let Some(eval_scene) = eval_scene.as_ref() {
-                        let mut psnr = 0.0;
-                        let mut ssim = 0.0;
-                        let mut count = 0;
-
+                        wait_for_evals(&evals).await;
                         log::info!("Running evaluation for iteration {iter}");
-
-                        for sample in brush_train::eval::eval_stats(
-                            *splats.clone(),
-                            eval_scene,
-                            None,
-                            &mut rng,
-                            &device,
-                        ) {
-                            count += 1;
-                            psnr += sample.psnr.clone().into_scalar_async().await;
-                            ssim += sample.ssim.clone().into_scalar_async().await;
-                            visualize.log_eval_sample(iter, &sample).await?;
-
-                            #[cfg(not(target_family = "wasm"))]
-                            if process_args.process_config.eval_save_to_disk {
-                                log::info!("Saving eval image to disk.");
-
-                                let eval_render = brush_train::image::tensor_into_image(
-                                    sample.rendered.clone().into_data_async().await,
-                                );
-                                let rendered: image::DynamicImage = eval_render.to_rgb8().into();
-
-                                let img_name = Path::new(&sample.view.path)
-                                    .file_stem()
-                                    .expect("No file name for eval view.")
-                                    .to_string_lossy();
-
-                                let path = Path::new(&export_path)
-                                    .join(format!("eval_{iter}"))
-                                    .join(format!("{img_name}.png"));
-
-                                let parent = path.parent().expect("Eval must have a filename");
-                                tokio::fs::create_dir_all(parent).await?;
-
-                                log::info!("Saving eval view to {path:?}");
-
-                                rendered.save(path)?;
-                            }
-                        }
-
-                        psnr /= count as f32;
-                        ssim /= count as f32;
-
-                        visualize.log_eval_stats(iter, psnr, ssim)?;
-
-                        if output
-                            .send(ProcessMessage::EvalResult {
+                        let save_dir = process_config
+                            .eval_save_to_disk
+                            .then(|| export_path.join(format!("eval_{iter}")));
+
+                        let in_background =
+                            process_config.eval_in_background || background_device.is_some();
+                        // The last eval is waited for, so it's done before the process ends.
+                        if in_background && !is_last_step {
+                            let eval_device = background_device.as_ref().unwrap_or(&device);
+                            let eval = run_eval(
+                                (*splats.clone()).to_device(eval_device),
+                                eval_scene.clone(),
                                 iter,
-                                avg_psnr: psnr,
-                                avg_ssim: ssim,
-                            })
-                            .await
-                            .is_err()
-                        {
-                            break;
+                                eval_device.clone(),
+                                save_dir,
+                                dataset_hash.clone(),
+                                visualize.clone(),
+                            );
+                            spawn_background_eval(
+                                evals.track_future(eval),
+                                output.clone(),
+                                Arc::clone(&last_eval),
+                                cancel.clone(),
+                            );
+                        } else {
+                            let result = run_eval(
+                                *splats.clone(),
+                                eval_scene.clone(),
+                                iter,
+                                device.clone(),
+                                save_dir,
+                                dataset_hash.clone(),
+                                visualize.clone(),
+                            )
+                            .await?;
+                            *last_eval.lock().expect("Lock poisoned") =
+                                EvalMetrics::from_message(&result);
+                            if output.send(result).await.is_err() {
+                                break;
+                            }
                         }
                     }
                 }
 
-                let client = WgpuRuntime::client(&device);
-                visualize.log_memory(iter, &client.memory_usage())?;
-
                 // TODO: Support this on WASM somehow. Maybe have user pick a file once,
                 // and write to it repeatedly?
                 #[cfg(not(target_family = "wasm"))]
-                if iter % process_config.export_every == 0 || is_last_step {
-                    let splats = *splats.clone();
+                if iter % export_every == 0 || is_last_step || export_requested {
+                    export_requested = false;
+                    // Write the exports with the result of the last eval.
+                    wait_for_evals(&evals).await;
+
+                    let splats = match export_volumes.apply(*splats.clone()).await {
+                        Ok(splats) => splats,
+                        Err(e) => {
+                            // Better to have all splats than no export at all.
+                            let warning = format!("Exporting all splats, {e:#}");
+                            log::warn!("{warning}");
+                            let _ = output.send(ProcessMessage::Warning(warning)).await;
+                            *splats.clone()
+                        }
+                    };
                     let output_send = output.clone();
 
-                    let total_steps = process_args.train_config.total_steps;
-
-                    // Ad-hoc format string.
-                    let digits = (total_steps as f64).log10().ceil() as usize;
                     let export_name = process_config
-                        .export_name
-                        .replace("{iter}", &format!("{iter:0digits$}"));
+                        .export_file_name(iter, process_args.train_config.total_steps);
 
                     tokio::fs::create_dir_all(&export_path).await?;
 
-                    // Nb: this COULD easily be done in the spawned future as well,
-                    // but for memory reasons it's not great to keep another copy of the
-                    // field.
-                    let splat_data = splat_export::splat_to_ply(splats).await?;
+                    // Write the color corrections of the views, to be able to apply them
+                    // again to other renders.
+                    if !color_calibration.is_empty() {
+                        let export_stem = export_file_stem(&export_name);
+                        tokio::fs::write(
+                            export_path.join(format!("{export_stem}_colors.json")),
+                            color_calibration.to_json()?,
+                        )
+                        .await?;
+                    }
 
-                    tokio::task::spawn(async move {
-                        if let Err(e) = tokio::fs::write(export_path.join(&export_name), splat_data)
-                            .await
-                            .with_context(|| format!("Failed to export ply {export_path:?}"))
-                        {
-                            let _ = output_send.send(ProcessMessage::Error(e)).await;
+                    // Write how the splats were made, to trace them back to this run.
+                    let loss = stats.loss.clone().into_scalar_async().await;
+                    let metadata = RunMetadata {
+                        step: iter,
+                        num_splats: splats.num_splats(),
+                        duration_secs: train_start.elapsed().as_secs_f64(),
+                        loss,
+                        eval: *last_eval.lock().expect("Lock poisoned"),
+                        ..RunMetadata::new(process_args, dataset_hash.clone())?
+                    };
+                    tokio::fs::write(
+                        export_path.join(format!("{}_run.json", export_file_stem(&export_name))),
+                        metadata.to_json()?,
+                    )
+                    .await?;
+
+                    if !export_render_views.is_empty() {
+                        let export_stem = export_file_stem(&export_name);
+                        log::info!("Rendering {} views for export", export_render_views.len());
+
+                        for (index, view) in export_render_views.iter().enumerate() {
+                            let res = glam::uvec2(view.image.width(), view.image.height());
+                            let (rendered, _) = splats.render(&view.camera, res, false);
+                            let rendered = brush_train::image::tensor_into_image(
+                                rendered.into_data_async().await,
+                            );
+                            let rendered: image::DynamicImage = rendered.to_rgb8().into();
+
+                            let img_name = Path::new(&view.path).file_stem().map_or_else(
+                                || format!("view_{index}"),
+                                |n| n.to_string_lossy().into_owned(),
+                            );
+                            let img_path =
+                                export_path.join(format!("{export_stem}_{img_name}.png"));
+                            // Encoding a png takes a while, keep it off the async runtime.
+                            tokio::task::spawn_blocking(move || rendered.save(img_path))
+                                .await?
+                                .context("Failed to save export render")?;
                         }
-                    });
-                }
-
-                if let Some(every) = process_args.rerun_config.rerun_log_splats_every {
-                    if iter % every == 0 || is_last_step {
-                        visualize.log_splats(iter, *splats.clone()).await?;
                     }
-                }
-
-                visualize.log_splat_stats(iter, &splats)?;
 
-                // Log out train stats.
-                if iter % process_args.rerun_config.rerun_log_train_stats_every == 0 || is_last_step
-                {
-                    visualize.log_train_stats(iter, *stats.clone()).await?;
+                    // Record which data the splats were trained on in the file itself.
+                    let comments = vec![format!("Dataset hash: {dataset_hash}")];
+
+                    if let Some(background_device) = background_device.as_ref() {
+                        // Serialize on the background device, without holding up training.
+                        // The process waits for it before it ends.
+                        let splats = splats.to_device(background_device);
+                        let export_format = process_config.export_format;
+                        exports.spawn(async move {
+                            let written = match splat_export::export_splats_with_comments(
+                                splats,
+                                export_format,
+                                &comments,
+                            )
+                            .await
+                            {
+                                Ok(splat_data) => {
+                                    tokio::fs::write(export_path.join(&export_name), splat_data)
+                                        .await
+                                        .with_context(|| {
+                                            format!("Failed to export splats to {export_path:?}")
+                                        })
+                                }
+                                Err(e) => Err(e),
+                            };
+                            if let Err(e) = written {
+                                let _ = output_send.send(ProcessMessage::Error(Arc::new(e))).await;
+                            }
+                        });
+                    } else {
+                        // Nb: this COULD easily be done in the spawned future as well,
+                        // but for memory reasons it's not great to keep another copy of the
+                        // field.
+                        let splat_data = splat_export::export_splats_with_comments(
+                            splats,
+                            process_config.export_format,
+                            &comments,
+                        )
+                        .await?;
+
+                        exports.spawn(async move {
+                            if let Err(e) =
+                                tokio::fs::write(export_path.join(&export_name), splat_data)
+                                    .await
+                                    .with_context(|| {
+                                        format!("Failed to export splats to {export_path:?}")
+                                    })
+                            {
+                                let _ = output_send.send(ProcessMessage::Error(Arc::new(e))).await;
+                            }
+                        });
+                    }
                 }
 
                 // How frequently to update the UI after a training step.
@@ -451,8 +890,6 @@ async fn train_process_loop(
                 }
             }
             train_stream::TrainMessage::RefineStep { stats, iter } => {
-                visualize.log_refine_stats(iter, &stats)?;
-
                 if output
                     .send(ProcessMessage::RefineStep { stats, iter })
                     .await
@@ -468,28 +905,110 @@ async fn train_process_loop(
 }
 
 pub struct RunningProcess {
+    pub source: DataSource,
     pub start_args: ProcessArgs,
+    /// Messages of the process. The process stops when this is dropped.
     pub messages: Receiver<ProcessMessage>,
+    /// To subscribe to the messages of the process elsewhere, eg. to log them.
+    pub bus: MessageBus,
     pub control: UnboundedSender<ControlMessage>,
+    /// Cancels the process when the running process is dropped, even if it's not sending
+    /// any messages, eg. while it's loading data. Everything the process holds on to, like
+    /// the splats on the GPU, is freed right away.
+    pub cancel_on_drop: DropGuard,
 }
 
 pub fn start_process(source: DataSource, args: ProcessArgs, device: WgpuDevice) -> RunningProcess {
+    start_process_with_hooks(source, args, device, TrainHooks::default())
+}
+
+/// Start a process, which registers `hooks` with its trainer, eg. to add a custom loss.
+pub fn start_process_with_hooks(
+    source: DataSource,
+    args: ProcessArgs,
+    device: WgpuDevice,
+    hooks: TrainHooks,
+) -> RunningProcess {
     log::info!("Starting process with source {:?}", source);
 
+    let args = ProcessArgs {
+        train_config: args.train_config.with_requested_preset(),
+        ..args
+    };
+
     // Create a small channel. We don't want 10 updated splats to be stuck in the queue eating up memory!
     // Bigger channels could mean the train loop spends less time waiting for the UI though.
     // create a channel for the train loop.
-    let (sender, receiver) = channel(1);
+    let bus = MessageBus::default();
+    let receiver = bus.subscribe_owner(1);
     let (train_sender, train_receiver) = unbounded_channel();
 
     let args_loop = args.clone();
+    let source_loop = source.clone();
+    let bus_loop = bus.clone();
+    let cancel = CancellationToken::new();
+    let cancel_loop = cancel.clone();
     tokio_with_wasm::alias::task::spawn(async move {
-        process_loop(source, sender, args_loop, device, train_receiver).await;
+        let exports = TaskTracker::new();
+        let process = process_loop(
+            source_loop,
+            bus_loop.clone(),
+            args_loop,
+            device,
+            train_receiver,
+            hooks,
+            &cancel_loop,
+            &exports,
+        );
+        // Cancelling drops the process future at its next await point.
+        if cancel_loop.run_until_cancelled(process).await.is_none() {
+            log::info!("Process cancelled");
+        }
+        // Finish writing exports, also when cancelled, so the last export isn't cut off. Their
+        // errors are still reported before the messages end.
+        exports.close();
+        exports.wait().await;
+        bus_loop.close();
     });
 
     RunningProcess {
+        source,
         start_args: args,
         messages: receiver,
+        bus,
         control: train_sender,
+        cancel_on_drop: cancel.drop_guard(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use tokio_util::sync::CancellationToken;
+
+    use super::{MessageBus, ProcessMessage, spawn_background_eval};
+
+    #[tokio::test]
+    async fn cancel_stops_background_eval() {
+        let bus = MessageBus::default();
+        let mut messages = bus.subscribe_owner(4);
+        let last_eval = Arc::new(Mutex::new(None));
+        let cancel = CancellationToken::new();
+
+        // An eval that never finishes by itself.
+        let eval = std::future::pending::<anyhow::Result<ProcessMessage>>();
+        let handle = spawn_background_eval(eval, bus.clone(), last_eval.clone(), cancel.clone());
+        cancel.cancel();
+        tokio::time::timeout(Duration::from_secs(10), handle)
+            .await
+            .expect("Cancelling should stop the eval")
+            .expect("Eval shouldn't panic");
+
+        // Nothing was sent or recorded.
+        bus.close();
+        assert!(messages.recv().await.is_none());
+        assert!(last_eval.lock().expect("Lock poisoned").is_none());
     }
 }
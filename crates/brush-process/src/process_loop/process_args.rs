@@ -1,7 +1,14 @@
-use brush_dataset::{LoadDataseConfig, ModelConfig};
+use std::time::Duration;
+
+use anyhow::Context;
+use brush_dataset::{
+    LoadDataseConfig, ModelConfig, export_volumes::ExportVolumes, splat_export::ExportFormat,
+};
 use brush_train::train::TrainConfig;
 use burn::config::Config;
+use burn_wgpu::WgpuDevice;
 use clap::Args;
+use web_time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Config, Args)]
 pub struct ProcessConfig {
@@ -17,6 +24,18 @@ pub struct ProcessConfig {
     #[arg(long, help_heading = "Process options", default_value = "false")]
     #[config(default = false)]
     pub eval_save_to_disk: bool,
+    /// Run evals next to training instead of pausing it, so long runs with big eval sets don't
+    /// stall. One eval runs at a time, and evals are waited for before the next one, exports,
+    /// and the eval after the last step.
+    #[arg(long, help_heading = "Process options", default_value = "false")]
+    #[config(default = false)]
+    pub eval_in_background: bool,
+
+    /// GPU to run evals and export serialization on, so they don't slow down training, eg.
+    /// `discrete:1` for the second discrete GPU, `integrated` or `cpu`. Picking the GPU that
+    /// trains gives them their own queue on it.
+    #[arg(long, help_heading = "Process options")]
+    pub background_gpu: Option<String>,
 
     /// Export every this many steps.
     #[arg(long, help_heading = "Process options", default_value = "5000")]
@@ -29,19 +48,143 @@ pub struct ProcessConfig {
     #[arg(long, help_heading = "Process options")]
     pub export_path: Option<String>,
 
-    /// Filename of exported ply file
+    /// Filename of exported files. `{iter}` is replaced by the training step and `{timestamp}`
+    /// by the unix time in seconds. The extension of the export format is added if missing, and
+    /// replaces the extension of another export format.
     #[arg(
         long,
         help_heading = "Process options",
-        default_value = "./export_{iter}.ply"
+        default_value = "export_{iter}"
     )]
-    #[config(default = "String::from(\"./export_{iter}.ply\")")]
+    #[config(default = "String::from(\"export_{iter}\")")]
     pub export_name: String,
 
+    /// Also render this many eval views (or training views if there is no eval split)
+    /// to PNGs with each export, to get a visual timeline of the training.
+    #[arg(long, help_heading = "Process options")]
+    pub export_render_views: Option<u32>,
+
+    /// File format of exported splats.
+    #[arg(
+        long,
+        help_heading = "Process options",
+        value_enum,
+        default_value = "ply"
+    )]
+    #[config(default = "ExportFormat::Ply")]
+    pub export_format: ExportFormat,
+
+    /// JSON file with boxes and spheres to keep the splats inside of in exports, fading out the
+    /// splats near their edges. Read once when the process starts.
+    #[arg(long, help_heading = "Process options")]
+    pub export_volumes: Option<String>,
+
     /// Iterationto resume from
     #[config(default = 0)]
     #[arg(long, help_heading = "Process options", default_value = "0")]
     pub start_iter: u32,
+
+    /// Train at most this many steps per second, to leave the GPU to other applications.
+    #[arg(long, help_heading = "Process options")]
+    pub max_steps_per_second: Option<f32>,
+
+    /// Fraction (0-1) of the time to spend training, sleeping the rest of the time. This keeps
+    /// the GPU (and fans) quieter, for training in the background. The fraction is approximate,
+    /// as steps are timed on the CPU, while the GPU can still be busy with earlier steps.
+    #[arg(long, help_heading = "Process options")]
+    pub duty_cycle: Option<f32>,
+}
+
+/// Limits on how fast training runs, see [`ProcessConfig::rate_limit`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RateLimit {
+    pub max_steps_per_second: Option<f32>,
+    pub duty_cycle: Option<f32>,
+}
+
+impl RateLimit {
+    /// How long to wait after a training step that took `step_time`.
+    ///
+    /// `step_time` is the time the CPU spent on the step. GPU work is queued without waiting for
+    /// it, so this roughly follows the GPU time over many steps, not for each step.
+    pub fn delay(&self, step_time: Duration) -> Duration {
+        let min_step_time = self
+            .max_steps_per_second
+            .filter(|&steps| steps > 0.0)
+            .map_or(Duration::ZERO, |steps| Duration::from_secs_f32(1.0 / steps));
+        // Sleep so training only takes up `duty_cycle` of the time.
+        let duty_sleep = self
+            .duty_cycle
+            .filter(|&duty| duty > 0.0 && duty < 1.0)
+            .map_or(Duration::ZERO, |duty| step_time.mul_f32(1.0 / duty - 1.0));
+        min_step_time.saturating_sub(step_time).max(duty_sleep)
+    }
+}
+
+impl ProcessConfig {
+    /// The device picked with `background_gpu`, if any.
+    pub fn background_device(&self) -> anyhow::Result<Option<WgpuDevice>> {
+        let Some(name) = self.background_gpu.as_deref() else {
+            return Ok(None);
+        };
+        let (kind, index) = name.split_once(':').unwrap_or((name, "0"));
+        let index: usize = index
+            .parse()
+            .with_context(|| format!("Invalid GPU index in {name:?}"))?;
+        let device = match kind {
+            "discrete" => WgpuDevice::DiscreteGpu(index),
+            "integrated" => WgpuDevice::IntegratedGpu(index),
+            "virtual" => WgpuDevice::VirtualGpu(index),
+            "cpu" => WgpuDevice::Cpu,
+            _ => anyhow::bail!(
+                "Unknown GPU {name:?}, expected eg. discrete:1, integrated, virtual or cpu"
+            ),
+        };
+        Ok(Some(device))
+    }
+
+    /// The volumes exports keep the splats inside of, see [`Self::export_volumes`].
+    #[cfg(not(target_family = "wasm"))]
+    pub async fn read_export_volumes(&self) -> anyhow::Result<ExportVolumes> {
+        let Some(path) = &self.export_volumes else {
+            return Ok(ExportVolumes::default());
+        };
+        let data = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Failed to read export volumes {path}"))?;
+        ExportVolumes::from_json(&data)
+            .with_context(|| format!("Failed to parse export volumes {path}"))
+    }
+
+    pub fn rate_limit(&self) -> RateLimit {
+        RateLimit {
+            max_steps_per_second: self.max_steps_per_second,
+            duty_cycle: self.duty_cycle,
+        }
+    }
+
+    /// Fill in the `export_name` template for an export at the given step.
+    pub fn export_file_name(&self, iter: u32, total_steps: u32) -> String {
+        let digits = (total_steps.max(1) as f64).log10().ceil() as usize;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        let name = self
+            .export_name
+            .replace("{iter}", &format!("{iter:0digits$}"))
+            .replace("{timestamp}", &timestamp.to_string());
+
+        let format = self.export_format;
+        match ExportFormat::from_path(&name) {
+            Some(named) if named == format => name,
+            Some(named) => {
+                let stem = &name[..name.len() - named.extension().len() - 1];
+                format!("{stem}.{}", format.extension())
+            }
+            None => format!("{name}.{}", format.extension()),
+        }
+    }
 }
 
 #[derive(Config, Args)]
@@ -88,3 +231,83 @@ impl Default for ProcessArgs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use brush_dataset::splat_export::ExportFormat;
+
+    use super::{ProcessConfig, RateLimit};
+
+    #[test]
+    fn export_name_gets_extension_of_format() {
+        let name = |export_name: &str, export_format| {
+            let config = ProcessConfig {
+                export_name: export_name.to_owned(),
+                export_format,
+                ..ProcessConfig::new()
+            };
+            config.export_file_name(50, 100)
+        };
+        assert_eq!(name("export_{iter}", ExportFormat::Spz), "export_50.spz");
+        assert_eq!(name("scene.ply", ExportFormat::Ply), "scene.ply");
+        assert_eq!(name("scene.ply", ExportFormat::Splat), "scene.splat");
+        assert_eq!(name("scene.compressed.ply", ExportFormat::Ply), "scene.ply");
+        assert_eq!(name("scene.ply", ExportFormat::Las), "scene.las");
+        // Dots that aren't an export format are kept.
+        assert_eq!(name("run.v2", ExportFormat::Ply), "run.v2.ply");
+    }
+
+    #[test]
+    fn no_limit_never_waits() {
+        let limit = RateLimit::default();
+        assert_eq!(limit.delay(Duration::from_millis(10)), Duration::ZERO);
+    }
+
+    #[test]
+    fn max_steps_per_second_pads_short_steps() {
+        let limit = RateLimit {
+            max_steps_per_second: Some(10.0),
+            duty_cycle: None,
+        };
+        let delay = limit.delay(Duration::from_millis(40));
+        assert!(delay.abs_diff(Duration::from_millis(60)) < Duration::from_micros(10));
+        // Steps that are already slow enough aren't delayed.
+        assert_eq!(limit.delay(Duration::from_millis(150)), Duration::ZERO);
+    }
+
+    #[test]
+    fn duty_cycle_sleeps_in_proportion() {
+        let limit = RateLimit {
+            max_steps_per_second: None,
+            duty_cycle: Some(0.25),
+        };
+        // Training a quarter of the time, sleeping three times as long as a step took.
+        let delay = limit.delay(Duration::from_millis(20));
+        assert!(delay.abs_diff(Duration::from_millis(60)) < Duration::from_micros(10));
+
+        // Out of range duty cycles don't limit anything.
+        for duty_cycle in [0.0, 1.0, 1.5] {
+            let limit = RateLimit {
+                max_steps_per_second: None,
+                duty_cycle: Some(duty_cycle),
+            };
+            assert_eq!(limit.delay(Duration::from_millis(20)), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn longest_limit_wins() {
+        let limit = RateLimit {
+            max_steps_per_second: Some(10.0),
+            duty_cycle: Some(0.5),
+        };
+        // The step rate asks for 90ms, the duty cycle for 10ms.
+        let delay = limit.delay(Duration::from_millis(10));
+        assert!(delay.abs_diff(Duration::from_millis(90)) < Duration::from_micros(10));
+        // The step rate asks for nothing, the duty cycle for 200ms.
+        let delay = limit.delay(Duration::from_millis(200));
+        assert!(delay.abs_diff(Duration::from_millis(200)) < Duration::from_micros(10));
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use super::{ProcessArgs, ProcessMessage};
+
+/// Results of an eval, as reported by [`ProcessMessage::EvalResult`].
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct EvalMetrics {
+    pub step: u32,
+    pub psnr: f32,
+    pub psnr_ci: f32,
+    pub ssim: f32,
+    pub ssim_ci: f32,
+}
+
+impl EvalMetrics {
+    pub fn from_message(message: &ProcessMessage) -> Option<Self> {
+        match *message {
+            ProcessMessage::EvalResult {
+                iter,
+                avg_psnr,
+                psnr_ci,
+                avg_ssim,
+                ssim_ci,
+            } => Some(Self {
+                step: iter,
+                psnr: avg_psnr,
+                psnr_ci,
+                ssim: avg_ssim,
+                ssim_ci,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// How an export was made, written next to it, so the splats can be traced back to the run and
+/// reproduced later.
+#[derive(Debug, Serialize)]
+pub struct RunMetadata {
+    pub brush_version: String,
+    /// When the splats were exported, in seconds since the unix epoch.
+    pub exported_at: u64,
+    /// Hash of the dataset, see `Dataset::content_hash`.
+    pub dataset_hash: String,
+    pub step: u32,
+    pub total_steps: u32,
+    pub num_splats: u32,
+    /// Seconds since training started, including any time it was paused.
+    pub duration_secs: f64,
+    /// Loss of the last training step.
+    pub loss: f32,
+    /// The last eval before the export, if any.
+    pub eval: Option<EvalMetrics>,
+    /// All settings of the run.
+    pub args: serde_json::Value,
+}
+
+impl RunMetadata {
+    pub fn new(args: &ProcessArgs, dataset_hash: String) -> anyhow::Result<Self> {
+        Ok(Self {
+            brush_version: env!("CARGO_PKG_VERSION").to_owned(),
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            dataset_hash,
+            step: 0,
+            total_steps: args.train_config.total_steps,
+            num_splats: 0,
+            duration_secs: 0.0,
+            loss: 0.0,
+            eval: None,
+            args: serde_json::to_value(args)?,
+        })
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
@@ -0,0 +1,88 @@
+use std::{collections::VecDeque, time::Duration};
+
+use web_time::Instant;
+
+// Number of steps to measure splat growth over. Splats are added in bursts every refine step,
+// so this needs to span a good number of refines.
+const GROWTH_WINDOW: u32 = 1000;
+
+/// Estimates the remaining training time and final splat count from the progress so far.
+#[derive(Debug, Clone)]
+pub struct TrainProgress {
+    total_steps: u32,
+    refine_stop_iter: u32,
+    last_step: Option<(Instant, u32)>,
+    iter_per_s: f64,
+    splat_counts: VecDeque<(u32, u32)>,
+}
+
+impl TrainProgress {
+    pub fn new(total_steps: u32, refine_stop_iter: u32) -> Self {
+        Self {
+            total_steps,
+            refine_stop_iter,
+            last_step: None,
+            iter_per_s: 0.0,
+            splat_counts: VecDeque::new(),
+        }
+    }
+
+    pub fn update(&mut self, timestamp: Instant, iter: u32, num_splats: u32) {
+        if let Some((last_time, last_iter)) = self.last_step {
+            let elapsed = timestamp.duration_since(last_time).as_secs_f64();
+            if iter > last_iter && elapsed > 0.0 {
+                let cur_iter_per_s = (iter - last_iter) as f64 / elapsed;
+                // Smooth out the speed as individual updates are noisy.
+                self.iter_per_s = if self.iter_per_s == 0.0 {
+                    cur_iter_per_s
+                } else {
+                    0.95 * self.iter_per_s + 0.05 * cur_iter_per_s
+                };
+            }
+        }
+        self.last_step = Some((timestamp, iter));
+
+        self.splat_counts.push_back((iter, num_splats));
+        while self
+            .splat_counts
+            .front()
+            .is_some_and(|&(i, _)| i + GROWTH_WINDOW < iter)
+        {
+            self.splat_counts.pop_front();
+        }
+    }
+
+    pub fn iter(&self) -> u32 {
+        self.last_step.map_or(0, |(_, iter)| iter)
+    }
+
+    pub fn iter_per_s(&self) -> f64 {
+        self.iter_per_s
+    }
+
+    /// Estimated time until training is done, based on the smoothed steps/s.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total_steps.saturating_sub(self.iter());
+        (self.iter_per_s > 0.0).then(|| Duration::from_secs_f64(remaining as f64 / self.iter_per_s))
+    }
+
+    /// Number of splats added per step, over the last stretch of training.
+    pub fn splat_growth(&self) -> Option<f64> {
+        let (first_iter, first_count) = *self.splat_counts.front()?;
+        let (last_iter, last_count) = *self.splat_counts.back()?;
+        (last_iter > first_iter)
+            .then(|| (last_count as f64 - first_count as f64) / (last_iter - first_iter) as f64)
+    }
+
+    /// Projected number of splats at the end of training, assuming the current growth
+    /// continues until refinement stops.
+    pub fn projected_splats(&self) -> Option<u32> {
+        let (iter, count) = *self.splat_counts.back()?;
+        let growth_steps = self
+            .refine_stop_iter
+            .min(self.total_steps)
+            .saturating_sub(iter);
+        let projected = count as f64 + self.splat_growth()? * growth_steps as f64;
+        Some(projected.max(0.0) as u32)
+    }
+}
@@ -3,11 +3,13 @@ use async_fn_stream::try_fn_stream;
 
 use brush_dataset::{Dataset, scene_loader::SceneLoader};
 use brush_render::gaussian_splats::Splats;
+use brush_train::callback::TrainHooks;
 use brush_train::train::TrainBack;
-use brush_train::train::{RefineStats, SplatTrainer, TrainConfig, TrainStepStats};
+use brush_train::train::{LiveTrainConfig, RefineStats, SplatTrainer, TrainConfig, TrainStepStats};
 
 use burn::{module::AutodiffModule, tensor::backend::AutodiffBackend};
 use burn_wgpu::WgpuDevice;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio_stream::Stream;
 use web_time::Instant;
 
@@ -30,23 +32,30 @@ pub(crate) fn train_stream(
     dataset: Dataset,
     initial_splats: Splats<TrainBack>,
     config: TrainConfig,
+    scene_extent: f32,
     device: WgpuDevice,
     start_iter: u32,
+    mut config_updates: UnboundedReceiver<LiveTrainConfig>,
+    hooks: TrainHooks,
 ) -> impl Stream<Item = anyhow::Result<TrainMessage>> {
     try_fn_stream(|emitter| async move {
         let mut splats = initial_splats;
 
         let train_scene = dataset.train.clone();
 
-        let mut dataloader = SceneLoader::new(&train_scene, 42, &device);
+        let mut dataloader = SceneLoader::new(&train_scene, 42, config.crop_size, &device);
 
-        let scene_extent = train_scene.estimate_extent().unwrap_or(1.0);
         let mut trainer = SplatTrainer::new(&config, &device);
+        trainer.add_hooks(hooks);
 
         let mut iter = start_iter;
 
         #[allow(clippy::infinite_loop)]
         loop {
+            while let Ok(live) = config_updates.try_recv() {
+                trainer.update_config(live);
+            }
+
             let batch = dataloader.next_batch().await;
 
             let (new_splats, stats) = trainer.step(scene_extent, iter, batch, splats);
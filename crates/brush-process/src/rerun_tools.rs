@@ -13,6 +13,11 @@ use burn::tensor::backend::AutodiffBackend;
 use burn::tensor::{ElementConversion, activation::sigmoid};
 
 use anyhow::Result;
+use burn_cubecl::cubecl::Runtime;
+use burn_wgpu::{WgpuDevice, WgpuRuntime};
+use tokio::sync::mpsc::Receiver;
+
+use crate::process_loop::{ProcessMessage, RerunConfig};
 
 #[cfg(not(target_family = "wasm"))]
 use brush_rerun::BurnToRerun;
@@ -262,6 +267,14 @@ impl VisualizeTools {
             if rec.is_enabled() {
                 rec.set_time_sequence("iterations", iter);
 
+                let _ = rec.log(
+                    "refine/num_split",
+                    &rerun::Scalar::new(refine.num_split as f64),
+                );
+                let _ = rec.log(
+                    "refine/num_cloned",
+                    &rerun::Scalar::new(refine.num_cloned as f64),
+                );
                 let _ = rec.log(
                     "refine/num_transparent_pruned",
                     &rerun::Scalar::new(refine.num_transparent_pruned as f64),
@@ -270,6 +283,10 @@ impl VisualizeTools {
                     "refine/num_scale_pruned",
                     &rerun::Scalar::new(refine.num_scale_pruned as f64),
                 );
+                let _ = rec.log(
+                    "refine/num_unseen_pruned",
+                    &rerun::Scalar::new(refine.num_unseen_pruned as f64),
+                );
             }
         }
 
@@ -303,3 +320,102 @@ impl VisualizeTools {
         Ok(())
     }
 }
+
+/// Whether something logged every `every` steps is due at `iter`, given when it was last logged.
+/// Training steps can be skipped, so this doesn't rely on seeing the exact multiples of `every`.
+fn due(last: &mut Option<u32>, iter: u32, every: u32, is_last_step: bool) -> bool {
+    let due = is_last_step || last.is_none_or(|last| iter >= last + every.max(1));
+    if due {
+        *last = Some(iter);
+    }
+    due
+}
+
+/// Logs the messages of a process to rerun.
+///
+/// This reads from its own subscriber of the process, which should skip training steps when
+/// logging falls behind (see [`Backpressure::SkipSteps`](crate::process_loop::Backpressure)),
+/// so logging never slows training down. Eval renders aren't part of the messages, and are
+/// logged by the eval itself.
+pub struct RerunLogger {
+    visualize: Arc<VisualizeTools>,
+    config: RerunConfig,
+    total_steps: u32,
+    device: WgpuDevice,
+    // The latest dataset, logged once it's done loading.
+    dataset: Option<Scene>,
+    last_stats: Option<u32>,
+    last_splats: Option<u32>,
+}
+
+impl RerunLogger {
+    pub fn new(
+        visualize: Arc<VisualizeTools>,
+        config: RerunConfig,
+        total_steps: u32,
+        device: WgpuDevice,
+    ) -> Self {
+        Self {
+            visualize,
+            config,
+            total_steps,
+            device,
+            dataset: None,
+            last_stats: None,
+            last_splats: None,
+        }
+    }
+
+    /// Log all messages until the process ends.
+    pub async fn run(mut self, mut messages: Receiver<ProcessMessage>) {
+        while let Some(message) = messages.recv().await {
+            if let Err(e) = self.log(message).await {
+                log::warn!("Failed to log to rerun: {e:#}");
+            }
+        }
+    }
+
+    async fn log(&mut self, message: ProcessMessage) -> Result<()> {
+        let visualize = &self.visualize;
+        match message {
+            ProcessMessage::Dataset { data } => self.dataset = Some(data.train),
+            ProcessMessage::DoneLoading { training: true } => {
+                if let Some(scene) = self.dataset.take() {
+                    visualize.log_scene(&scene, self.config.rerun_max_img_size)?;
+                }
+            }
+            ProcessMessage::TrainStep {
+                splats,
+                stats,
+                iter,
+                ..
+            } => {
+                let is_last_step = iter == self.total_steps;
+                let client = WgpuRuntime::client(&self.device);
+                visualize.log_memory(iter, &client.memory_usage())?;
+                visualize.log_splat_stats(iter, &splats)?;
+
+                if let Some(every) = self.config.rerun_log_splats_every {
+                    if due(&mut self.last_splats, iter, every, is_last_step) {
+                        visualize.log_splats(iter, *splats).await?;
+                    }
+                }
+                let every = self.config.rerun_log_train_stats_every;
+                if due(&mut self.last_stats, iter, every, is_last_step) {
+                    visualize.log_train_stats(iter, *stats).await?;
+                }
+            }
+            ProcessMessage::RefineStep { stats, iter } => {
+                visualize.log_refine_stats(iter, &stats)?;
+            }
+            ProcessMessage::EvalResult {
+                iter,
+                avg_psnr,
+                avg_ssim,
+                ..
+            } => visualize.log_eval_stats(iter, avg_psnr, avg_ssim)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+//! Thumbnails of recently opened data, kept with the cached data rather than in the settings of
+//! the app, which are rewritten as a whole every time they're saved.
+
+use sha2::{Digest, Sha256};
+
+/// Prefix of the thumbnails in the browser cache, so they're not listed as cached data.
+#[cfg(target_family = "wasm")]
+pub(crate) const THUMBNAIL_PREFIX: &str = "thumb_";
+
+// Name of the thumbnail of `id`, which can be anything that identifies the data, eg. its url.
+fn file_name(id: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(id.as_bytes()));
+    format!("{}.png", &hash[..16])
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn path(id: &str) -> std::path::PathBuf {
+    crate::download_cache::dir()
+        .join("thumbnails")
+        .join(file_name(id))
+}
+
+/// Store the PNG thumbnail of the data identified by `id`, replacing any earlier thumbnail.
+pub async fn store(id: &str, png: &[u8]) -> anyhow::Result<()> {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let path = path(id);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(path, png).await?;
+        Ok(())
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        crate::web_cache::store(&format!("{THUMBNAIL_PREFIX}{}", file_name(id)), png).await
+    }
+}
+
+/// Load the PNG thumbnail of the data identified by `id`, if it has one.
+pub async fn load(id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        match tokio::fs::read(path(id)).await {
+            Ok(png) => Ok(Some(png)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        crate::web_cache::read(&format!("{THUMBNAIL_PREFIX}{}", file_name(id))).await
+    }
+}
+
+/// Remove the thumbnail of the data identified by `id`, if it has one.
+pub async fn remove(id: &str) -> anyhow::Result<()> {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        match tokio::fs::remove_file(path(id)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+    #[cfg(target_family = "wasm")]
+    {
+        let key = format!("{THUMBNAIL_PREFIX}{}", file_name(id));
+        if crate::web_cache::read(&key).await?.is_some() {
+            crate::web_cache::remove(&key).await?;
+        }
+        Ok(())
+    }
+}
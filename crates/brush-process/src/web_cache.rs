@@ -0,0 +1,445 @@
+//! Cache of uploaded and downloaded data in the browser's Origin Private File System (OPFS).
+//!
+//! Datasets can be gigabytes, so keeping them around means reloading the page doesn't require
+//! uploading or downloading them again. Downloads are streamed into the cache, and read back
+//! from it in chunks. A cached download is checked against the server before it's used, with its
+//! `ETag` or `Last-Modified`, or else its size, and downloaded again if it changed.
+
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use anyhow::anyhow;
+use js_sys::{Array, AsyncIterator, IteratorNext, Uint8Array};
+use reqwest::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio_stream::StreamExt;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, FileSystemDirectoryHandle, FileSystemFileHandle, FileSystemGetDirectoryOptions,
+    FileSystemGetFileOptions, FileSystemWritableFileStream,
+};
+
+const CACHE_DIR: &str = "brush-cache";
+
+const URL_PREFIX: &str = "url_";
+const UPLOAD_PREFIX: &str = "upload_";
+const INFO_SUFFIX: &str = ".info.json";
+
+// Size of the chunks cached data is read back in.
+const READ_CHUNK: f64 = 8.0 * 1024.0 * 1024.0;
+
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    /// Name of the entry in the cache, see [`load`].
+    pub key: String,
+    /// The url or file name the data came from.
+    pub source: String,
+    pub size: u64,
+    /// Always false, entries are only stored once they're complete.
+    pub partial: bool,
+}
+
+/// What's known about a cache entry, stored next to it.
+#[derive(Serialize, Deserialize)]
+struct EntryInfo {
+    size: u64,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+fn js_err(e: JsValue) -> anyhow::Error {
+    anyhow!("{}", e.as_string().unwrap_or_else(|| format!("{e:?}")))
+}
+
+/// Cache key for data downloaded from a url.
+pub fn url_key(url: &str) -> String {
+    format!("{URL_PREFIX}{}", urlencoding::encode(url))
+}
+
+/// Cache key for an uploaded file. Files with the same name but another size get their own
+/// entry.
+pub fn upload_key(file_name: &str, size: u64) -> String {
+    format!("{UPLOAD_PREFIX}{size}_{}", urlencoding::encode(file_name))
+}
+
+/// The url or file name an entry in the cache came from.
+pub fn source_from_key(key: &str) -> String {
+    let encoded = key
+        .strip_prefix(URL_PREFIX)
+        .or_else(|| {
+            let rest = key.strip_prefix(UPLOAD_PREFIX)?;
+            // Skip the size.
+            Some(rest.split_once('_').map_or(rest, |(_, name)| name))
+        })
+        .unwrap_or(key);
+    urlencoding::decode(encoded).map_or_else(|_| key.to_owned(), |s| s.into_owned())
+}
+
+async fn cache_dir() -> anyhow::Result<FileSystemDirectoryHandle> {
+    let window = web_sys::window().ok_or_else(|| anyhow!("No window to access storage"))?;
+    let root: FileSystemDirectoryHandle =
+        JsFuture::from(window.navigator().storage().get_directory())
+            .await
+            .map_err(js_err)?
+            .unchecked_into();
+
+    let options = FileSystemGetDirectoryOptions::new();
+    options.set_create(true);
+    let dir = JsFuture::from(root.get_directory_handle_with_options(CACHE_DIR, &options))
+        .await
+        .map_err(js_err)?;
+    Ok(dir.unchecked_into())
+}
+
+async fn file_handle(key: &str, create: bool) -> anyhow::Result<Option<FileSystemFileHandle>> {
+    let dir = cache_dir().await?;
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(create);
+    // Fails with a NotFoundError if the file doesn't exist.
+    let handle = JsFuture::from(dir.get_file_handle_with_options(key, &options)).await;
+    Ok(handle.ok().map(JsCast::unchecked_into))
+}
+
+async fn file_blob(handle: &FileSystemFileHandle) -> anyhow::Result<Blob> {
+    let file = JsFuture::from(handle.get_file()).await.map_err(js_err)?;
+    Ok(file.unchecked_into())
+}
+
+async fn read_blob(blob: &Blob) -> anyhow::Result<Vec<u8>> {
+    let buffer = JsFuture::from(blob.array_buffer()).await.map_err(js_err)?;
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
+
+async fn read_info(key: &str) -> Option<EntryInfo> {
+    let handle = file_handle(&format!("{key}{INFO_SUFFIX}"), false)
+        .await
+        .ok()??;
+    let data = read_blob(&file_blob(&handle).await.ok()?).await.ok()?;
+    let info: EntryInfo = serde_json::from_slice(&data).ok()?;
+
+    // Only trust entries that were written completely.
+    let handle = file_handle(key, false).await.ok()??;
+    let size = file_blob(&handle).await.ok()?.size() as u64;
+    (size == info.size).then_some(info)
+}
+
+/// Reads an entry of the cache in chunks, so it doesn't have to fit in memory at once.
+pub struct CacheReader {
+    blob: Blob,
+    offset: f64,
+    chunk: Vec<u8>,
+    pos: usize,
+    pending: Option<JsFuture>,
+}
+
+impl AsyncRead for CacheReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.pos < this.chunk.len() {
+                let len = buf.remaining().min(this.chunk.len() - this.pos);
+                buf.put_slice(&this.chunk[this.pos..this.pos + len]);
+                this.pos += len;
+                return Poll::Ready(Ok(()));
+            }
+            let size = this.blob.size();
+            if this.offset >= size {
+                return Poll::Ready(Ok(()));
+            }
+
+            if this.pending.is_none() {
+                let end = (this.offset + READ_CHUNK).min(size);
+                let slice = this
+                    .blob
+                    .slice_with_f64_and_f64(this.offset, end)
+                    .map_err(|e| std::io::Error::other(js_err(e)))?;
+                this.pending = Some(JsFuture::from(slice.array_buffer()));
+            }
+            let pending = this.pending.as_mut().expect("Just set");
+            let buffer = ready!(Pin::new(pending).poll(cx));
+            this.pending = None;
+            let buffer = buffer.map_err(|e| std::io::Error::other(js_err(e)))?;
+            this.chunk = Uint8Array::new(&buffer).to_vec();
+            this.pos = 0;
+            this.offset += this.chunk.len() as f64;
+        }
+    }
+}
+
+/// Read the entry for `key` from the cache, if there is one.
+pub async fn reader(key: &str) -> anyhow::Result<Option<CacheReader>> {
+    let Some(handle) = file_handle(key, false).await? else {
+        return Ok(None);
+    };
+    Ok(Some(CacheReader {
+        blob: file_blob(&handle).await?,
+        offset: 0.0,
+        chunk: vec![],
+        pos: 0,
+        pending: None,
+    }))
+}
+
+/// Read all of the entry for `key` from the cache, if there is one. Only meant for small entries.
+pub async fn read(key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(handle) = file_handle(key, false).await? else {
+        return Ok(None);
+    };
+    Ok(Some(read_blob(&file_blob(&handle).await?).await?))
+}
+
+/// Writes an entry of the cache. The entry only replaces an existing entry for the same key once
+/// it's finished.
+struct CacheWriter {
+    key: String,
+    writable: FileSystemWritableFileStream,
+    size: u64,
+}
+
+impl CacheWriter {
+    async fn create(key: &str) -> anyhow::Result<Self> {
+        let handle = file_handle(key, true)
+            .await?
+            .ok_or_else(|| anyhow!("Failed to create cache file"))?;
+        let writable = JsFuture::from(handle.create_writable())
+            .await
+            .map_err(js_err)?
+            .unchecked_into();
+        Ok(Self {
+            key: key.to_owned(),
+            writable,
+            size: 0,
+        })
+    }
+
+    async fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let promise = self.writable.write_with_u8_array(data).map_err(js_err)?;
+        JsFuture::from(promise).await.map_err(js_err)?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    // Discard what was written, eg. when running out of quota.
+    async fn abort(self) {
+        let _ = JsFuture::from(self.writable.abort()).await;
+    }
+
+    async fn finish(self, info: &EntryInfo) -> anyhow::Result<()> {
+        JsFuture::from(self.writable.close())
+            .await
+            .map_err(js_err)?;
+
+        let mut writer = Self::create(&format!("{}{INFO_SUFFIX}", self.key)).await?;
+        let written = writer.write(&serde_json::to_vec(info)?).await;
+        if let Err(e) = written {
+            writer.abort().await;
+            return Err(e);
+        }
+        JsFuture::from(writer.writable.close())
+            .await
+            .map_err(js_err)?;
+        Ok(())
+    }
+}
+
+/// Store data in the cache, replacing any existing entry for `key`.
+pub async fn store(key: &str, data: &[u8]) -> anyhow::Result<()> {
+    let mut writer = CacheWriter::create(key).await?;
+    if let Err(e) = writer.write(data).await {
+        writer.abort().await;
+        return Err(e);
+    }
+    let info = EntryInfo {
+        size: writer.size,
+        etag: None,
+        last_modified: None,
+        sha256: None,
+    };
+    writer.finish(&info).await
+}
+
+fn header_value(response: &reqwest::Response, name: header::HeaderName) -> Option<String> {
+    Some(response.headers().get(name)?.to_str().ok()?.to_owned())
+}
+
+// Whether a response for a url is the same data as its cached entry.
+fn is_unchanged(response: &reqwest::Response, cached: &EntryInfo) -> bool {
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return true;
+    }
+    let etag = header_value(response, header::ETAG);
+    let last_modified = header_value(response, header::LAST_MODIFIED);
+    if cached.etag.is_some() || cached.last_modified.is_some() {
+        // Servers don't have to answer conditional requests, so compare the validators as well.
+        return (etag.is_some() || last_modified.is_some())
+            && etag == cached.etag
+            && last_modified == cached.last_modified;
+    }
+    // Without validators, the size is all there is to go by.
+    etag.is_none() && last_modified.is_none() && response.content_length() == Some(cached.size)
+}
+
+/// A download by [`download`].
+pub enum Download {
+    /// The data is in the cache under this key.
+    Cached(String),
+    /// The data couldn't be cached, eg. as the storage quota ran out, and has to be downloaded
+    /// without the cache.
+    Uncached,
+}
+
+/// Download `url` into the cache, checking it matches the `sha256` checksum if given. A cached
+/// download is only downloaded again if it changed on the server, or doesn't match the
+/// checksum. When the server can't be reached, the cached data is used.
+pub async fn download(url: &str, sha256: Option<&str>) -> anyhow::Result<Download> {
+    let key = url_key(url);
+    let cached = read_info(&key).await.filter(|info| {
+        sha256.is_none_or(|expected| {
+            info.sha256
+                .as_deref()
+                .is_some_and(|actual| actual.eq_ignore_ascii_case(expected))
+        })
+    });
+
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(info) = &cached {
+        if let Some(etag) = &info.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        } else if let Some(last_modified) = &info.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) if cached.is_some() => {
+            log::warn!("Couldn't check whether {url} changed, loading it from the cache: {e}");
+            return Ok(Download::Cached(key));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if cached
+        .as_ref()
+        .is_some_and(|info| is_unchanged(&response, info))
+    {
+        log::info!("Loading {url} from the cache");
+        return Ok(Download::Cached(key));
+    }
+    let response = response.error_for_status()?;
+
+    let etag = header_value(&response, header::ETAG);
+    let last_modified = header_value(&response, header::LAST_MODIFIED);
+    let mut writer = match CacheWriter::create(&key).await {
+        Ok(writer) => writer,
+        Err(e) => {
+            log::warn!("Failed to cache {url}: {e}");
+            return Ok(Download::Uncached);
+        }
+    };
+
+    let mut hasher = Sha256::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                writer.abort().await;
+                return Err(e.into());
+            }
+        };
+        hasher.update(&chunk);
+        if let Err(e) = writer.write(&chunk).await {
+            log::warn!("Failed to cache {url}: {e}");
+            writer.abort().await;
+            return Ok(Download::Uncached);
+        }
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if let Some(expected) = sha256 {
+        if !actual.eq_ignore_ascii_case(expected) {
+            writer.abort().await;
+            anyhow::bail!(
+                "The download of {url} is corrupt: its sha256 checksum is {actual}, but should be \
+                 {expected}"
+            );
+        }
+    }
+
+    let info = EntryInfo {
+        size: writer.size,
+        etag,
+        last_modified,
+        sha256: Some(actual),
+    };
+    writer.finish(&info).await?;
+    Ok(Download::Cached(key))
+}
+
+/// List all entries in the cache.
+pub async fn entries() -> anyhow::Result<Vec<CacheEntry>> {
+    let dir = cache_dir().await?;
+    let iter: AsyncIterator = dir.entries();
+    let mut entries = vec![];
+
+    loop {
+        let next: IteratorNext = JsFuture::from(iter.next().map_err(js_err)?)
+            .await
+            .map_err(js_err)?
+            .unchecked_into();
+        if next.done() {
+            break;
+        }
+
+        let pair = Array::from(&next.value());
+        let Some(key) = pair.get(0).as_string().filter(|key| {
+            !key.ends_with(INFO_SUFFIX) && !key.starts_with(crate::thumbnails::THUMBNAIL_PREFIX)
+        }) else {
+            continue;
+        };
+        let Ok(handle) = pair.get(1).dyn_into::<FileSystemFileHandle>() else {
+            continue;
+        };
+        let size = file_blob(&handle).await?.size() as u64;
+
+        entries.push(CacheEntry {
+            source: source_from_key(&key),
+            key,
+            size,
+            partial: false,
+        });
+    }
+
+    entries.sort_by(|a, b| a.source.cmp(&b.source));
+    Ok(entries)
+}
+
+/// Remove a single entry from the cache.
+pub async fn remove(key: &str) -> anyhow::Result<()> {
+    let dir = cache_dir().await?;
+    // Entries stored before their info was kept don't have one.
+    let _ = JsFuture::from(dir.remove_entry(&format!("{key}{INFO_SUFFIX}"))).await;
+    JsFuture::from(dir.remove_entry(key))
+        .await
+        .map_err(js_err)?;
+    Ok(())
+}
+
+/// Remove all entries from the cache.
+pub async fn clear() -> anyhow::Result<()> {
+    for entry in entries().await? {
+        remove(&entry.key).await?;
+    }
+    Ok(())
+}
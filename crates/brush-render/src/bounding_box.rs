@@ -4,6 +4,16 @@ pub struct BoundingBox {
     pub extent: glam::Vec3,
 }
 
+/// Value at quantile `q` (0-1) of `values`, which are reordered.
+pub fn quantile(values: &mut [f32], q: f32) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    let index = ((values.len() - 1) as f32 * q.clamp(0.0, 1.0)).round() as usize;
+    let (_, value, _) = values.select_nth_unstable_by(index, f32::total_cmp);
+    Some(*value)
+}
+
 impl BoundingBox {
     pub fn from_min_max(min: glam::Vec3, max: glam::Vec3) -> Self {
         Self {
@@ -12,6 +22,27 @@ impl BoundingBox {
         }
     }
 
+    /// Bounds of `points` ignoring outliers: the fraction `outliers` of the points furthest out
+    /// on either side of each axis is left out.
+    pub fn from_points_robust(points: &[glam::Vec3], outliers: f32) -> Option<Self> {
+        let mut min = glam::Vec3::ZERO;
+        let mut max = glam::Vec3::ZERO;
+        for axis in 0..3 {
+            let mut values: Vec<f32> = points.iter().map(|p| p[axis]).collect();
+            min[axis] = quantile(&mut values, outliers)?;
+            max[axis] = quantile(&mut values, 1.0 - outliers)?;
+        }
+        Some(Self::from_min_max(min, max))
+    }
+
+    /// Grow the box around its center, eg. by 1.1 to leave some room around it.
+    pub fn inflated(self, factor: f32) -> Self {
+        Self {
+            center: self.center,
+            extent: self.extent * factor,
+        }
+    }
+
     pub fn min(&self) -> glam::Vec3 {
         self.center - self.extent
     }
@@ -20,3 +51,20 @@ impl BoundingBox {
         self.center + self.extent
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BoundingBox;
+    use glam::{Vec3, vec3};
+
+    #[test]
+    fn robust_bounds_ignore_outliers() {
+        let mut points: Vec<Vec3> = (0..100).map(|i| Vec3::splat(i as f32 / 99.0)).collect();
+        points.push(vec3(1000.0, -1000.0, 0.5));
+
+        let bounds = BoundingBox::from_points_robust(&points, 0.02).expect("Points are not empty");
+        assert!(bounds.max().x < 1.0 + 1e-6);
+        assert!(bounds.min().y > -1e-6);
+        assert!(BoundingBox::from_points_robust(&[], 0.02).is_none());
+    }
+}
@@ -7,9 +7,9 @@ use burn_ir::{CustomOpIr, HandleContainer, OperationIr};
 use burn_wgpu::WgpuRuntime;
 
 use crate::{
-    BBase, RenderAuxPrimitive, SplatForward,
+    BBase, DepthOrderPrimitive, RenderAuxPrimitive, SplatForward,
     camera::Camera,
-    render::{calc_tile_bounds, max_intersections, render_forward},
+    render::{BlendMode, calc_tile_bounds, max_intersections, render_forward},
     shaders,
 };
 
@@ -24,6 +24,8 @@ impl<F: FloatElement, I: IntElement, BT: BoolElement> SplatForward<Self> for BBa
         sh_coeffs: FloatTensor<Self>,
         raw_opacity: FloatTensor<Self>,
         render_u32_buffer: bool,
+        blend_mode: BlendMode,
+        depth_order: Option<DepthOrderPrimitive<Self>>,
     ) -> (FloatTensor<Self>, RenderAuxPrimitive<Self>) {
         render_forward(
             camera,
@@ -34,6 +36,8 @@ impl<F: FloatElement, I: IntElement, BT: BoolElement> SplatForward<Self> for BBa
             sh_coeffs,
             raw_opacity,
             render_u32_buffer,
+            blend_mode,
+            depth_order,
         )
     }
 }
@@ -50,11 +54,14 @@ impl<F: FloatElement, I: IntElement, BT: BoolElement> SplatForward<Self>
         sh_coeffs: FloatTensor<Self>,
         raw_opacity: FloatTensor<Self>,
         render_u32_buffer: bool,
+        blend_mode: BlendMode,
+        depth_order: Option<DepthOrderPrimitive<Self>>,
     ) -> (FloatTensor<Self>, RenderAuxPrimitive<Self>) {
         struct CustomOp<F: FloatElement, I: IntElement, BT: BoolElement> {
             cam: Camera,
             img_size: glam::UVec2,
             render_u32_buffer: bool,
+            blend_mode: BlendMode,
             desc: CustomOpIr,
             _c: PhantomData<(F, I, BT)>,
         }
@@ -66,31 +73,50 @@ impl<F: FloatElement, I: IntElement, BT: BoolElement> SplatForward<Self>
                 self: Box<Self>,
                 h: &mut HandleContainer<FusionHandle<FusionCubeRuntime<WgpuRuntime, BT>>>,
             ) {
-                let (
-                    [means, log_scales, quats, sh_coeffs, raw_opacity],
-                    [
-                        projected_splats,
-                        uniforms_buffer,
-                        num_intersections,
-                        num_visible,
-                        final_index,
-                        tile_offsets,
-                        compact_gid_from_isect,
-                        global_from_compact_gid,
-                        radii,
-                        out_img,
-                    ],
-                ) = self.desc.consume();
+                // The depth order is an optional extra input.
+                let CustomOpIr {
+                    inputs, outputs, ..
+                } = self.desc;
+                let [means, log_scales, quats, sh_coeffs, raw_opacity, order @ ..] =
+                    inputs.as_slice()
+                else {
+                    panic!("Missing inputs for render_splats");
+                };
+                let [
+                    projected_splats,
+                    uniforms_buffer,
+                    num_intersections,
+                    num_visible,
+                    final_index,
+                    tile_offsets,
+                    compact_gid_from_isect,
+                    global_from_compact_gid,
+                    radii,
+                    out_img,
+                ] = outputs.as_slice()
+                else {
+                    panic!("Missing outputs for render_splats");
+                };
+
+                let depth_order = match order {
+                    [order_gids, order_num_visible] => Some(DepthOrderPrimitive {
+                        global_from_compact_gid: h.get_int_tensor::<BBase<F, I, BT>>(order_gids),
+                        num_visible: h.get_int_tensor::<BBase<F, I, BT>>(order_num_visible),
+                    }),
+                    _ => None,
+                };
 
                 let (img, aux) = BBase::<F, I, BT>::render_splats(
                     &self.cam,
                     self.img_size,
-                    h.get_float_tensor::<BBase<F, I, BT>>(&means),
-                    h.get_float_tensor::<BBase<F, I, BT>>(&log_scales),
-                    h.get_float_tensor::<BBase<F, I, BT>>(&quats),
-                    h.get_float_tensor::<BBase<F, I, BT>>(&sh_coeffs),
-                    h.get_float_tensor::<BBase<F, I, BT>>(&raw_opacity),
+                    h.get_float_tensor::<BBase<F, I, BT>>(means),
+                    h.get_float_tensor::<BBase<F, I, BT>>(log_scales),
+                    h.get_float_tensor::<BBase<F, I, BT>>(quats),
+                    h.get_float_tensor::<BBase<F, I, BT>>(sh_coeffs),
+                    h.get_float_tensor::<BBase<F, I, BT>>(raw_opacity),
                     self.render_u32_buffer,
+                    self.blend_mode,
+                    depth_order,
                 );
 
                 // Register output.
@@ -155,15 +181,24 @@ impl<F: FloatElement, I: IntElement, BT: BoolElement> SplatForward<Self>
             radii: client.tensor_uninitialized(vec![num_points], DType::F32),
         };
 
+        let mut inputs = vec![
+            means.into_ir(),
+            log_scales.into_ir(),
+            quats.into_ir(),
+            sh_coeffs.into_ir(),
+            raw_opacity.into_ir(),
+        ];
+        let mut streams = vec![stream];
+        if let Some(order) = depth_order {
+            streams.push(order.global_from_compact_gid.stream);
+            streams.push(order.num_visible.stream);
+            inputs.push(order.global_from_compact_gid.into_ir());
+            inputs.push(order.num_visible.into_ir());
+        }
+
         let desc = CustomOpIr::new(
             "render_splats",
-            &[
-                means.into_ir(),
-                log_scales.into_ir(),
-                quats.into_ir(),
-                sh_coeffs.into_ir(),
-                raw_opacity.into_ir(),
-            ],
+            &inputs,
             &[
                 aux.projected_splats.to_ir_out(),
                 aux.uniforms_buffer.to_ir_out(),
@@ -182,11 +217,12 @@ impl<F: FloatElement, I: IntElement, BT: BoolElement> SplatForward<Self>
             cam: cam.clone(),
             img_size,
             render_u32_buffer,
+            blend_mode,
             desc: desc.clone(),
             _c: PhantomData {},
         };
 
-        client.register(vec![stream], OperationIr::Custom(desc), op);
+        client.register(streams, OperationIr::Custom(desc), op);
 
         (out_img, aux)
     }
@@ -1,12 +1,136 @@
 use glam::Affine3A;
 
-#[derive(Debug, Default, Clone)]
+/// Splats closer to the camera than this are never rendered.
+pub const DEFAULT_NEAR: f32 = 0.01;
+/// Splats further from the camera than this are never rendered.
+pub const DEFAULT_FAR: f32 = 1e10;
+
+/// Cuts away all splats on one side of a plane, eg. to look inside a building.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectionPlane {
+    /// Plane as normal and offset. Splats with `normal.dot(position) + offset > 0` are hidden.
+    pub plane: glam::Vec4,
+    /// Splats closer than this to the plane are highlighted, to show where the cut is.
+    pub highlight_width: f32,
+}
+
+/// How the splats are colored, eg. to judge the geometry of the splats without their colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Shading {
+    /// The colors of the splats.
+    #[default]
+    Color,
+    /// Light the base color of the splats with a directional light. The normal of each splat is
+    /// the axis it's flattest along.
+    Lit {
+        /// Direction towards the light.
+        light_dir: glam::Vec3,
+        /// How much light reaches all splats, from 0 to 1.
+        ambient: f32,
+    },
+    /// Color the splats by their normal in camera space.
+    Normals,
+}
+
+/// Adjusts the colors of 8 bit renders for display, like the exposure and gamma of a photo
+/// editor. Float renders, as used for training, are never adjusted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayTransform {
+    /// Exposure in stops, applied to the linear colors. 1 doubles the brightness.
+    pub exposure: f32,
+    /// Gamma applied after converting back to sRGB, above 1 brightens the shadows.
+    pub gamma: f32,
+}
+
+impl DisplayTransform {
+    pub const IDENTITY: Self = Self {
+        exposure: 0.0,
+        gamma: 1.0,
+    };
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Lens distortion, in the radial-tangential model of OpenCV (Brown-Conrady). Applies to
+/// coordinates on the image plane at depth 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distortion {
+    pub k1: f32,
+    pub k2: f32,
+    pub k3: f32,
+    pub p1: f32,
+    pub p2: f32,
+}
+
+impl Distortion {
+    pub const NONE: Self = Self {
+        k1: 0.0,
+        k2: 0.0,
+        k3: 0.0,
+        p1: 0.0,
+        p2: 0.0,
+    };
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Where a point on the undistorted image plane ends up in the image.
+    pub fn distort(&self, xy: glam::Vec2) -> glam::Vec2 {
+        let r2 = xy.length_squared();
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        let xy2 = 2.0 * xy.x * xy.y;
+        glam::vec2(
+            xy.x * radial + self.p1 * xy2 + self.p2 * (r2 + 2.0 * xy.x * xy.x),
+            xy.y * radial + self.p1 * (r2 + 2.0 * xy.y * xy.y) + self.p2 * xy2,
+        )
+    }
+}
+
+impl Default for Distortion {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub fov_x: f64,
     pub fov_y: f64,
     pub center_uv: glam::Vec2,
     pub position: glam::Vec3,
     pub rotation: glam::Quat,
+    /// Only splats with a depth between the near and far plane are rendered.
+    pub near: f32,
+    pub far: f32,
+    pub section: Option<SectionPlane>,
+    pub shading: Shading,
+    pub display: DisplayTransform,
+    /// Lens distortion of the images taken with this camera. Splats are always rendered without
+    /// distortion, this is only used to compare renders with such images.
+    pub distortion: Distortion,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            fov_x: 0.0,
+            fov_y: 0.0,
+            center_uv: glam::Vec2::ZERO,
+            position: glam::Vec3::ZERO,
+            rotation: glam::Quat::IDENTITY,
+            near: DEFAULT_NEAR,
+            far: DEFAULT_FAR,
+            section: None,
+            shading: Shading::Color,
+            display: DisplayTransform::IDENTITY,
+            distortion: Distortion::NONE,
+        }
+    }
 }
 
 impl Camera {
@@ -23,6 +147,12 @@ impl Camera {
             center_uv,
             position,
             rotation,
+            near: DEFAULT_NEAR,
+            far: DEFAULT_FAR,
+            section: None,
+            shading: Shading::Color,
+            display: DisplayTransform::IDENTITY,
+            distortion: Distortion::NONE,
         }
     }
 
@@ -40,6 +170,17 @@ impl Camera {
         )
     }
 
+    /// Pixel of an image of `img_size` taken by this camera a point in the world ends up at,
+    /// including the lens distortion. None if the point is behind the camera.
+    pub fn project(&self, world: glam::Vec3, img_size: glam::UVec2) -> Option<glam::Vec2> {
+        let local = self.world_to_local().transform_point3(world);
+        if local.z <= 0.0 {
+            return None;
+        }
+        let xy = self.distortion.distort(local.truncate() / local.z);
+        Some(xy * self.focal(img_size) + self.center(img_size))
+    }
+
     pub fn local_to_world(&self) -> Affine3A {
         Affine3A::from_rotation_translation(self.rotation, self.position)
     }
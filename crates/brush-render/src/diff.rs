@@ -0,0 +1,158 @@
+//! Differences between two snapshots of splats, eg. of a run at two steps, or of runs with
+//! different settings.
+//!
+//! Splats don't keep their identity between snapshots, as refining adds and removes them, so the
+//! snapshots are compared by where their mass is: the opacity of the splats, binned into a grid
+//! over the scene.
+
+use burn::prelude::Backend;
+use burn::tensor::DataError;
+use glam::Vec3;
+
+use crate::bounding_box::BoundingBox;
+use crate::gaussian_splats::Splats;
+
+// Part of the splats on either side of each axis left out of the grid, so outliers don't stretch
+// it. They're counted in the cells at its edge.
+const OUTLIERS: f32 = 0.01;
+
+#[derive(Clone)]
+pub struct SnapshotDiff {
+    pub splats_before: u32,
+    pub splats_after: u32,
+    /// Summed opacity of the splats.
+    pub mass_before: f32,
+    pub mass_after: f32,
+    /// Part of the mass that is in other cells after than before, from 0 (spread the same way) to
+    /// 1 (nothing in common). Only where the mass is counts, not how much there is.
+    pub moved_mass: f32,
+    /// How far the center of mass moved.
+    pub center_shift: f32,
+    /// Bounds of the grid, around most splats of both snapshots.
+    pub bounds: BoundingBox,
+    /// Number of cells of the grid along each axis.
+    pub resolution: usize,
+    /// Mass after minus mass before of every cell, with x changing fastest, then y, then z.
+    pub cell_change: Vec<f32>,
+}
+
+impl SnapshotDiff {
+    /// Compare two snapshots, given as the position and opacity of each splat, on a grid of
+    /// `resolution` cells along each axis.
+    pub fn from_points(before: &[(Vec3, f32)], after: &[(Vec3, f32)], resolution: usize) -> Self {
+        let resolution = resolution.max(1);
+        let positions: Vec<Vec3> = before.iter().chain(after).map(|(p, _)| *p).collect();
+        let bounds = BoundingBox::from_points_robust(&positions, OUTLIERS)
+            .unwrap_or_else(|| BoundingBox::from_min_max(Vec3::ZERO, Vec3::ZERO));
+        let min = bounds.min();
+        let size = (bounds.extent * 2.0).max(Vec3::splat(1e-6));
+
+        let bin = |points: &[(Vec3, f32)]| {
+            let mut cells = vec![0.0; resolution.pow(3)];
+            let mut center = Vec3::ZERO;
+            for &(position, mass) in points {
+                let cell = ((position - min) / size * resolution as f32)
+                    .floor()
+                    .clamp(Vec3::ZERO, Vec3::splat(resolution as f32 - 1.0))
+                    .as_uvec3();
+                let [x, y, z] = cell.to_array().map(|c| c as usize);
+                cells[x + resolution * (y + resolution * z)] += mass;
+                center += position * mass;
+            }
+            let mass: f32 = cells.iter().sum();
+            (cells, mass, center / mass.max(1e-12))
+        };
+        let (cells_before, mass_before, center_before) = bin(before);
+        let (cells_after, mass_after, center_after) = bin(after);
+
+        let moved_mass = 0.5
+            * cells_before
+                .iter()
+                .zip(&cells_after)
+                .map(|(b, a)| (a / mass_after.max(1e-12) - b / mass_before.max(1e-12)).abs())
+                .sum::<f32>();
+
+        Self {
+            splats_before: before.len() as u32,
+            splats_after: after.len() as u32,
+            mass_before,
+            mass_after,
+            moved_mass: moved_mass.min(1.0),
+            center_shift: center_after.distance(center_before),
+            bounds,
+            resolution,
+            cell_change: cells_after
+                .iter()
+                .zip(&cells_before)
+                .map(|(a, b)| a - b)
+                .collect(),
+        }
+    }
+
+    /// The change of the cells summed along `axis` (0 for X, 1 for Y, 2 for Z), as a square
+    /// image of `resolution` rows. Rows follow the next axis after `axis`, columns the one after
+    /// that, eg. Z and X for the Y axis.
+    pub fn projected_change(&self, axis: usize) -> Vec<f32> {
+        let res = self.resolution;
+        let (row_axis, col_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut image = vec![0.0; res * res];
+        for (index, change) in self.cell_change.iter().enumerate() {
+            let cell = [index % res, (index / res) % res, index / (res * res)];
+            image[cell[row_axis] * res + cell[col_axis]] += change;
+        }
+        image
+    }
+}
+
+async fn read_points<B: Backend>(splats: &Splats<B>) -> Result<Vec<(Vec3, f32)>, DataError> {
+    let means = splats.means.val().into_data_async().await.to_vec::<f32>()?;
+    let opacity = splats.opacity().into_data_async().await.to_vec::<f32>()?;
+    Ok(means
+        .chunks_exact(3)
+        .map(Vec3::from_slice)
+        .zip(opacity)
+        .collect())
+}
+
+/// Compare two snapshots of splats, on a grid of `resolution` cells along each axis. This reads
+/// back the positions and opacities of both.
+pub async fn diff_splats<B: Backend>(
+    before: &Splats<B>,
+    after: &Splats<B>,
+    resolution: usize,
+) -> Result<SnapshotDiff, DataError> {
+    let before = read_points(before).await?;
+    let after = read_points(after).await?;
+    Ok(SnapshotDiff::from_points(&before, &after, resolution))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotDiff;
+    use glam::{Vec3, vec3};
+
+    #[test]
+    fn moved_mass_between_snapshots() {
+        let before: Vec<_> = (0..100)
+            .map(|i| (vec3(i as f32 / 99.0, 0.0, 0.0), 1.0))
+            .collect();
+        let same = SnapshotDiff::from_points(&before, &before, 4);
+        assert!(same.moved_mass.abs() < 1e-6);
+        assert!(same.cell_change.iter().all(|c| c.abs() < 1e-6));
+
+        // Move the left half of the splats to the right end.
+        let after: Vec<_> = before
+            .iter()
+            .map(|&(p, m)| (if p.x < 0.5 { Vec3::X } else { p }, m))
+            .collect();
+        let moved = SnapshotDiff::from_points(&before, &after, 4);
+        assert_eq!(moved.splats_after, 100);
+        assert!((moved.mass_after - moved.mass_before).abs() < 1e-3);
+        assert!((moved.moved_mass - 0.5).abs() < 0.05);
+        assert!(moved.center_shift > 0.1);
+
+        let projected = moved.projected_change(1);
+        assert_eq!(projected.len(), 16);
+        assert!(projected.iter().sum::<f32>().abs() < 1e-3);
+    }
+}
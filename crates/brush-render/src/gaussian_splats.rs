@@ -1,15 +1,16 @@
 use crate::{
-    RenderAux, SplatForward,
-    bounding_box::BoundingBox,
+    DepthOrder, RenderAux, SplatForward,
+    bounding_box::{BoundingBox, quantile},
     camera::Camera,
-    render::{sh_coeffs_for_degree, sh_degree_from_coeffs},
+    render::{BlendMode, SH_C0, sh_coeffs_for_degree, sh_degree_from_coeffs},
+    sh::sh_to_color,
 };
 use ball_tree::BallTree;
 use burn::{
     config::Config,
     module::{Module, Param, ParamId},
     prelude::Backend,
-    tensor::{Tensor, TensorData, TensorPrimitive, activation::sigmoid},
+    tensor::{DType, FloatDType, Int, Tensor, TensorData, TensorPrimitive, activation::sigmoid},
 };
 use glam::{Quat, Vec3};
 use rand::Rng;
@@ -27,6 +28,91 @@ pub struct Splats<B: Backend> {
     pub log_scales: Param<Tensor<B, 2>>,
     pub sh_coeffs: Param<Tensor<B, 3>>,
     pub raw_opacity: Param<Tensor<B, 1>>,
+    /// Statistics gathered while training, if training was asked to track them.
+    pub tracking: Option<SplatTracking<B>>,
+}
+
+/// A statistic of each splat tracked while training.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackedStat {
+    /// The training step the splat was added at, by initialization, splitting or cloning.
+    CreatedStep,
+    /// The number of training steps the splat was visible in.
+    TimesSeen,
+    /// The norm of the gradient of the position of the splat, summed over all steps.
+    GradAccum,
+}
+
+impl TrackedStat {
+    pub const ALL: [Self; 3] = [Self::CreatedStep, Self::TimesSeen, Self::GradAccum];
+
+    /// Name of the statistic, also its property name in exported ply files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::CreatedStep => "created_step",
+            Self::TimesSeen => "times_seen",
+            Self::GradAccum => "grad_accum",
+        }
+    }
+}
+
+/// Statistics of every splat gathered while training, eg. to study how splats evolve. They
+/// aren't parameters, so they're not optimized, and rendering doesn't use them.
+#[derive(Module, Debug)]
+pub struct SplatTracking<B: Backend> {
+    pub created_step: Tensor<B, 1>,
+    pub times_seen: Tensor<B, 1>,
+    pub grad_accum: Tensor<B, 1>,
+}
+
+impl<B: Backend> SplatTracking<B> {
+    /// Tracking for `num_splats` splats created at `step`.
+    pub fn new(num_splats: u32, step: u32, device: &B::Device) -> Self {
+        let n = num_splats as usize;
+        Self {
+            created_step: Tensor::full([n], step as f32, device),
+            times_seen: Tensor::zeros([n], device),
+            grad_accum: Tensor::zeros([n], device),
+        }
+    }
+
+    pub fn stat(&self, stat: TrackedStat) -> Tensor<B, 1> {
+        match stat {
+            TrackedStat::CreatedStep => self.created_step.clone(),
+            TrackedStat::TimesSeen => self.times_seen.clone(),
+            TrackedStat::GradAccum => self.grad_accum.clone(),
+        }
+    }
+
+    /// Count a training step, where `seen` is 1 for the splats that were visible and 0 for
+    /// others, and `grad_norm` the norm of the gradient of their positions.
+    pub fn record_step(self, seen: Tensor<B, 1>, grad_norm: Tensor<B, 1>) -> Self {
+        Self {
+            created_step: self.created_step,
+            times_seen: self.times_seen + seen,
+            grad_accum: self.grad_accum + grad_norm,
+        }
+    }
+
+    /// Keep the statistics of the splats at `indices`.
+    pub fn select(self, indices: Tensor<B, 1, Int>) -> Self {
+        Self {
+            created_step: self.created_step.select(0, indices.clone()),
+            times_seen: self.times_seen.select(0, indices.clone()),
+            grad_accum: self.grad_accum.select(0, indices),
+        }
+    }
+
+    /// Add statistics for `count` splats created at `step`.
+    pub fn appended(self, count: u32, step: u32) -> Self {
+        let device = self.created_step.device();
+        let added = Self::new(count, step, &device);
+        Self {
+            created_step: Tensor::cat(vec![self.created_step, added.created_step], 0),
+            times_seen: Tensor::cat(vec![self.times_seen, added.times_seen], 0),
+            grad_accum: Tensor::cat(vec![self.grad_accum, added.grad_accum], 0),
+        }
+    }
 }
 
 fn norm_vec<B: Backend>(vec: Tensor<B, 2>) -> Tensor<B, 2> {
@@ -113,14 +199,20 @@ impl<B: Backend> Splats<B> {
             let empty = vec![(); tree_pos.len()];
             let tree = BallTree::new(tree_pos.clone(), empty);
 
-            let extents: Vec<_> = tree_pos
+            let distances: Vec<f32> = tree_pos
                 .iter()
                 .map(|p| {
                     // Get average of 4 nearest distances.
-                    0.5 * tree.query().nn(p).skip(1).take(2).map(|x| x.1).sum::<f64>() / 2.0
+                    (0.5 * tree.query().nn(p).skip(1).take(2).map(|x| x.1).sum::<f64>() / 2.0)
+                        as f32
                 })
-                .map(|p| p.max(1e-12))
-                .map(|p| p.ln() as f32)
+                .collect();
+
+            // Outlier points far from everything else would otherwise start out huge.
+            let max_distance = quantile(&mut distances.clone(), 0.99).unwrap_or(f32::MAX);
+            let extents: Vec<_> = distances
+                .iter()
+                .map(|p| p.clamp(1e-12, max_distance.max(1e-12)).ln())
                 .collect();
 
             Tensor::<B, 1>::from_floats(extents.as_slice(), device)
@@ -204,6 +296,7 @@ impl<B: Backend> Splats<B> {
             rotation: Param::initialized(ParamId::new(), rotation.detach().require_grad()),
             raw_opacity: Param::initialized(ParamId::new(), raw_opacity.detach().require_grad()),
             log_scales: Param::initialized(ParamId::new(), log_scales.detach().require_grad()),
+            tracking: None,
         }
     }
 
@@ -223,11 +316,78 @@ impl<B: Backend> Splats<B> {
         norm_vec(self.rotation.val())
     }
 
+    /// Normal of each splat, the axis it's flattest along. Splats on a surface tend to flatten
+    /// along it, so this estimates the normal of the surface, but it's meaningless for round
+    /// splats. The normals can point to either side of the surface.
+    pub fn normals(&self) -> Tensor<B, 2> {
+        let [n, _] = self.means.dims();
+        let quats = self.rotations_normed();
+        let w = quats.clone().slice([0..n, 0..1]);
+        let x = quats.clone().slice([0..n, 1..2]);
+        let y = quats.clone().slice([0..n, 2..3]);
+        let z = quats.slice([0..n, 3..4]);
+
+        // Columns of the rotation matrix, which are the axes of the splats.
+        let axis_x = Tensor::cat(
+            vec![
+                -(y.clone() * y.clone() + z.clone() * z.clone()) * 2.0 + 1.0,
+                (x.clone() * y.clone() + w.clone() * z.clone()) * 2.0,
+                (x.clone() * z.clone() - w.clone() * y.clone()) * 2.0,
+            ],
+            1,
+        );
+        let axis_y = Tensor::cat(
+            vec![
+                (x.clone() * y.clone() - w.clone() * z.clone()) * 2.0,
+                -(x.clone() * x.clone() + z.clone() * z.clone()) * 2.0 + 1.0,
+                (y.clone() * z.clone() + w.clone() * x.clone()) * 2.0,
+            ],
+            1,
+        );
+        let axis_z = Tensor::cat(
+            vec![
+                (x.clone() * z.clone() + w.clone() * y.clone()) * 2.0,
+                (y.clone() * z.clone() - w * x.clone()) * 2.0,
+                -(x.clone() * x + y.clone() * y) * 2.0 + 1.0,
+            ],
+            1,
+        );
+        let axes = Tensor::stack::<3>(vec![axis_x, axis_y, axis_z], 1);
+        let flattest = self
+            .log_scales
+            .val()
+            .argmin(1)
+            .reshape([n, 1, 1])
+            .repeat_dim(2, 3);
+        axes.gather(1, flattest).reshape([n, 3])
+    }
+
     pub fn with_normed_rotations(mut self) -> Self {
         self.rotation = self.rotation.map(|r| norm_vec(r));
         self
     }
 
+    /// These splats with their SH coefficients stored as `dtype`. Storing them as f16 halves the
+    /// memory of what takes up most of it, and renders read them as they are. Converting them
+    /// needs a GPU that supports f16 in shaders though.
+    pub fn with_sh_dtype(mut self, dtype: FloatDType) -> Self {
+        if self.sh_coeffs.val().dtype() != DType::from(dtype) {
+            self.sh_coeffs = self.sh_coeffs.map(|coeffs| coeffs.cast(dtype));
+        }
+        self
+    }
+
+    /// The SH coefficients as f32, whichever precision they're stored in. Use this to compute
+    /// with them, rather than the stored coefficients.
+    pub fn sh_coeffs_f32(&self) -> Tensor<B, 3> {
+        let coeffs = self.sh_coeffs.val();
+        if coeffs.dtype() == DType::F32 {
+            coeffs
+        } else {
+            coeffs.cast(FloatDType::F32)
+        }
+    }
+
     pub fn sh_degree(&self) -> u32 {
         let [_, coeffs, _] = self.sh_coeffs.dims();
         sh_degree_from_coeffs(coeffs as u32)
@@ -236,6 +396,23 @@ impl<B: Backend> Splats<B> {
     pub fn device(&self) -> B::Device {
         self.means.device()
     }
+
+    /// These splats with the colors they have seen from `position` baked into SH degree 0, so
+    /// renders near there don't need to evaluate the higher degrees for every splat again.
+    pub fn with_view_colors(&self, position: Vec3) -> Self {
+        if self.sh_degree() == 0 {
+            return self.clone();
+        }
+        let means = self.means.val();
+        let device = means.device();
+        let [n, _] = means.dims();
+        let position = Tensor::<B, 1>::from_floats(position.to_array(), &device).reshape([1, 3]);
+        let dirs = norm_vec(means - position);
+        let colors = sh_to_color(self.sh_coeffs_f32(), dirs);
+        let mut splats = self.clone();
+        splats.sh_coeffs = Param::initialized(ParamId::new(), (colors / SH_C0).reshape([n, 1, 3]));
+        splats
+    }
 }
 
 impl<B: Backend + SplatForward<B>> Splats<B> {
@@ -247,6 +424,19 @@ impl<B: Backend + SplatForward<B>> Splats<B> {
         camera: &Camera,
         img_size: glam::UVec2,
         render_u32_buffer: bool,
+    ) -> (Tensor<B, 3>, RenderAux<B>) {
+        self.render_with_options(camera, img_size, render_u32_buffer, BlendMode::Sorted, None)
+    }
+
+    /// Like [`Self::render`], but with a choice of how overlapping splats are blended, and
+    /// optionally reusing the [`DepthOrder`] of an earlier render of these splats.
+    pub fn render_with_options(
+        &self,
+        camera: &Camera,
+        img_size: glam::UVec2,
+        render_u32_buffer: bool,
+        blend_mode: BlendMode,
+        depth_order: Option<DepthOrder<B>>,
     ) -> (Tensor<B, 3>, RenderAux<B>) {
         let (img, aux) = B::render_splats(
             camera,
@@ -257,6 +447,8 @@ impl<B: Backend + SplatForward<B>> Splats<B> {
             self.sh_coeffs.val().into_primitive().tensor(),
             self.raw_opacity.val().into_primitive().tensor(),
             render_u32_buffer,
+            blend_mode,
+            depth_order.map(DepthOrder::into_primitive),
         );
 
         let img = Tensor::from_primitive(TensorPrimitive::Float(img));
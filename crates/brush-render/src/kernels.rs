@@ -2,6 +2,12 @@ use super::shaders::{map_gaussian_to_intersects, project_forward, project_visibl
 use brush_kernel::kernel_source_gen;
 
 kernel_source_gen!(ProjectSplats {}, project_forward);
-kernel_source_gen!(ProjectVisible {}, project_visible);
+kernel_source_gen!(ProjectVisible { sh_f16 }, project_visible);
 kernel_source_gen!(MapGaussiansToIntersect {}, map_gaussian_to_intersects);
-kernel_source_gen!(Rasterize { raster_u32 }, rasterize);
+kernel_source_gen!(
+    Rasterize {
+        raster_u32,
+        weighted_blend
+    },
+    rasterize
+);
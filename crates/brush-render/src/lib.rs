@@ -8,6 +8,7 @@ use burn_fusion::Fusion;
 use burn_wgpu::graphics::AutoGraphicsApi;
 use burn_wgpu::{RuntimeOptions, WgpuDevice, WgpuRuntime};
 use camera::Camera;
+use render::BlendMode;
 use shaders::helpers::TILE_WIDTH;
 use wgpu::{Adapter, Device, Queue};
 
@@ -21,8 +22,11 @@ mod tests;
 
 pub mod bounding_box;
 pub mod camera;
+pub mod diff;
 pub mod gaussian_splats;
 pub mod render;
+pub mod sh;
+pub mod stats;
 
 #[derive(Debug, Clone)]
 pub struct RenderAuxPrimitive<B: Backend> {
@@ -64,6 +68,33 @@ pub struct RenderAux<B: Backend> {
     pub radii: Tensor<B, 1>,
 }
 
+/// The culled and depth sorted splats of a render, see [`RenderAux::depth_order`].
+#[derive(Debug, Clone)]
+pub struct DepthOrderPrimitive<B: Backend> {
+    pub global_from_compact_gid: IntTensor<B>,
+    pub num_visible: IntTensor<B>,
+}
+
+/// The culled and depth sorted splats of a render.
+///
+/// A render of the same splats from the same camera, eg. with other shading or exposure, can
+/// reuse this to skip culling and depth sorting. Any other camera sees other splats in another
+/// order, so this can't be reused then.
+#[derive(Debug, Clone)]
+pub struct DepthOrder<B: Backend> {
+    pub global_from_compact_gid: Tensor<B, 1, Int>,
+    pub num_visible: Tensor<B, 1, Int>,
+}
+
+impl<B: Backend> DepthOrder<B> {
+    pub fn into_primitive(self) -> DepthOrderPrimitive<B> {
+        DepthOrderPrimitive {
+            global_from_compact_gid: self.global_from_compact_gid.into_primitive(),
+            num_visible: self.num_visible.into_primitive(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderStats {
     pub num_visible: u32,
@@ -74,6 +105,13 @@ const INTERSECTS_UPPER_BOUND: u32 = shaders::map_gaussian_to_intersects::WORKGRO
 const GAUSSIANS_UPPER_BOUND: u32 = 256 * 65535;
 
 impl<B: Backend> RenderAux<B> {
+    pub fn depth_order(&self) -> DepthOrder<B> {
+        DepthOrder {
+            global_from_compact_gid: self.global_from_compact_gid.clone(),
+            num_visible: self.num_visible.clone(),
+        }
+    }
+
     #[allow(clippy::single_range_in_vec_init)]
     pub fn calc_tile_depth(&self) -> Tensor<B, 2, Int> {
         let bins = self.tile_offsets.clone();
@@ -201,6 +239,8 @@ pub trait SplatForward<B: Backend> {
     /// The [`xy_grad_dummy`] variable is only used to carry screenspace xy gradients.
     /// This function can optionally render a "u32" buffer, which is a packed RGBA (8 bits per channel)
     /// buffer. This is useful when the results need to be displayed immediately.
+    /// Only [`BlendMode::Sorted`] renders can be differentiated.
+    /// A [`DepthOrderPrimitive`] from an earlier render can be passed in to skip culling and sorting.
     fn render_splats(
         camera: &Camera,
         img_size: glam::UVec2,
@@ -210,6 +250,8 @@ pub trait SplatForward<B: Backend> {
         sh_coeffs: FloatTensor<B>,
         raw_opacity: FloatTensor<B>,
         render_u32_buffer: bool,
+        blend_mode: BlendMode,
+        depth_order: Option<DepthOrderPrimitive<B>>,
     ) -> (FloatTensor<B>, RenderAuxPrimitive<B>);
 }
 
@@ -235,3 +277,10 @@ pub async fn burn_init_setup() -> WgpuDevice {
         .await;
     WgpuDevice::DefaultDevice
 }
+
+/// Set up another device to run work on next to the default one, eg. evals next to training.
+/// This can be the same GPU as the default device, which gives the work its own queue.
+pub async fn burn_init_extra(device: WgpuDevice) -> WgpuDevice {
+    burn_wgpu::init_setup_async::<AutoGraphicsApi>(&device, burn_options()).await;
+    device
+}
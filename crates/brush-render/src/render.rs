@@ -3,8 +3,8 @@ use super::shaders;
 use std::mem::{offset_of, size_of};
 
 use crate::{
-    BBase, INTERSECTS_UPPER_BOUND, RenderAuxPrimitive,
-    camera::Camera,
+    BBase, DepthOrderPrimitive, INTERSECTS_UPPER_BOUND, RenderAuxPrimitive,
+    camera::{Camera, Shading},
     dim_check::DimCheck,
     kernels::{MapGaussiansToIntersect, ProjectSplats, ProjectVisible, Rasterize},
 };
@@ -26,6 +26,19 @@ use glam::{ivec2, uvec2};
 
 pub const SH_C0: f32 = shaders::project_visible::SH_C0;
 
+/// How the splats overlapping a pixel are blended together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Sort splats by depth, and alpha blend them front to back.
+    #[default]
+    Sorted,
+    /// Skip the depth sort, and blend with an average of the colors weighted by their alpha and
+    /// depth instead, favouring closer splats (weighted blended order independent transparency).
+    /// This is much faster for huge scenes but less accurate where splats overlap, and can't be
+    /// differentiated, so it's only meant for viewing.
+    WeightedBlended,
+}
+
 pub const fn sh_coeffs_for_degree(degree: u32) -> u32 {
     (degree + 1).pow(2)
 }
@@ -78,6 +91,8 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
     sh_coeffs: CubeTensor<WgpuRuntime>,
     raw_opacities: CubeTensor<WgpuRuntime>,
     raster_u32: bool,
+    blend_mode: BlendMode,
+    depth_order: Option<DepthOrderPrimitive<BBase<F, I, BT>>>,
 ) -> (CubeTensor<WgpuRuntime>, RenderAuxPrimitive<BBase<F, I, BT>>) {
     assert!(
         img_size[0] > 0 && img_size[1] > 0,
@@ -87,6 +102,10 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
     let device = &means.device.clone();
     let client = means.client.clone();
 
+    // Weighted blending needs the depths of the splats, which an earlier order doesn't have.
+    // Without a sort there's little to save by reusing it anyway.
+    let depth_order = depth_order.filter(|_| blend_mode == BlendMode::Sorted);
+
     // Check whether any work needs to be flushed.
     tracing::trace_span!("pre setup", sync_burn = true).in_scope(|| {});
 
@@ -126,6 +145,10 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
         shaders::helpers::RenderUniforms {
             viewmat: glam::Mat4::from(camera.world_to_local()).to_cols_array_2d(),
             camera_position: [camera.position.x, camera.position.y, camera.position.z, 0.0],
+            // Without a section, use a plane that never cuts anything.
+            section_plane: camera
+                .section
+                .map_or([0.0, 0.0, 0.0, -1.0], |s| s.plane.into()),
             focal: camera.focal(img_size).into(),
             pixel_center: camera.center(img_size).into(),
             img_size: ivec2(img_size.x as i32, img_size.y as i32).into(),
@@ -134,6 +157,24 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
             num_intersections: 0,
             sh_degree,
             total_splats,
+            near: camera.near,
+            far: camera.far,
+            section_highlight: camera.section.map_or(0.0, |s| s.highlight_width),
+            light: match camera.shading {
+                Shading::Lit { light_dir, ambient } => {
+                    light_dir.normalize_or_zero().extend(ambient)
+                }
+                Shading::Color | Shading::Normals => glam::Vec4::ZERO,
+            }
+            .into(),
+            shading: match camera.shading {
+                Shading::Color => 0,
+                Shading::Lit { .. } => 1,
+                Shading::Normals => 2,
+            },
+            exposure: camera.display.exposure.exp2(),
+            gamma: camera.display.gamma.max(0.01),
+            padding: [0.0; 2],
         },
         device,
         &client,
@@ -146,7 +187,30 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
 
     let radii = BBase::<F, I, BT>::float_zeros([num_points].into(), device);
 
-    let (global_from_compact_gid, num_visible) = {
+    let num_vis_field_offset = offset_of!(shaders::helpers::RenderUniforms, num_visible) / 4;
+
+    // The view depth of each splat, in the compact order when not sorted.
+    let mut view_depths = None;
+
+    let (uniforms_buffer, global_from_compact_gid, num_visible) = if let Some(order) = depth_order {
+        assert_eq!(
+            order.global_from_compact_gid.shape.dims[0], num_points,
+            "Depth order must be from a render of the same splats"
+        );
+
+        // Reuse the culling and depth order of an earlier render. Only the number of visible splats
+        // has to be written to the uniforms.
+        let uniforms_buffer = BBase::<F, I, BT>::int_slice_assign(
+            uniforms_buffer,
+            &[num_vis_field_offset..num_vis_field_offset + 1],
+            order.num_visible.clone(),
+        );
+        (
+            uniforms_buffer,
+            order.global_from_compact_gid,
+            order.num_visible,
+        )
+    } else {
         let global_from_presort_gid = BBase::<F, I, BT>::int_zeros([num_points].into(), device);
         let depths = create_tensor([num_points], device, client, DType::F32);
 
@@ -170,20 +234,31 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
         });
 
         // Get just the number of visible splats from the uniforms buffer.
-        let num_vis_field_offset = offset_of!(shaders::helpers::RenderUniforms, num_visible) / 4;
         let num_visible = BBase::<F, I, BT>::int_slice(
             uniforms_buffer.clone(),
             &[num_vis_field_offset..num_vis_field_offset + 1],
         );
 
-        let (_, global_from_compact_gid) = tracing::trace_span!("DepthSort", sync_burn = true)
-            .in_scope(|| {
-                // Interpret the depth as a u32. This is fine for a radix sort, as long as the depth > 0.0,
-                // which we know to be the case given how we cull splats.
-                radix_argsort(depths, global_from_presort_gid, &num_visible, 32)
-            });
-
-        (global_from_compact_gid, num_visible)
+        let global_from_compact_gid = match blend_mode {
+            BlendMode::Sorted => {
+                let (_, global_from_compact_gid) =
+                    tracing::trace_span!("DepthSort", sync_burn = true).in_scope(|| {
+                        // Interpret the depth as a u32. This is fine for a radix sort, as long as the depth > 0.0,
+                        // which we know to be the case given how we cull splats. The keys are the full
+                        // float bits rather than depths quantized to a range, so huge depth ranges
+                        // can't overflow the keys, and depths only one ulp apart still sort apart.
+                        radix_argsort(depths, global_from_presort_gid, &num_visible, 32)
+                    });
+                global_from_compact_gid
+            }
+            // Blending is order independent, so the splats don't need to be depth sorted.
+            BlendMode::WeightedBlended => {
+                view_depths = Some(depths);
+                global_from_presort_gid
+            }
+        };
+
+        (uniforms_buffer, global_from_compact_gid, num_visible)
     };
 
     let projected_size = size_of::<shaders::helpers::ProjectedSplat>() / size_of::<f32>();
@@ -202,7 +277,7 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
         // SAFETY: Kernel has to contain no OOB indexing.
         unsafe {
         client.execute_unchecked(
-            ProjectVisible::task(),
+            ProjectVisible::task(sh_coeffs.dtype == DType::F16),
             CubeCount::Dynamic(num_vis_wg.clone().handle.binding()),
             vec![
                 uniforms_buffer.clone().handle.binding(),
@@ -317,19 +392,26 @@ pub(crate) fn render_forward<F: FloatElement, I: IntElement, BT: BoolElement>(
         DType::I32,
     );
 
+    let weighted_blend = blend_mode == BlendMode::WeightedBlended;
+    let mut bindings = vec![
+        uniforms_buffer.clone().handle.binding(),
+        compact_gid_from_isect.handle.clone().binding(),
+        tile_offsets.handle.clone().binding(),
+        projected_splats.handle.clone().binding(),
+        out_img.handle.clone().binding(),
+        final_index.handle.clone().binding(),
+    ];
+    if weighted_blend {
+        let depths = view_depths.expect("Weighted blending always projects the splats");
+        bindings.push(depths.handle.binding());
+    }
+
     // SAFETY: Kernel has to contain no OOB indexing.
     unsafe {
         client.execute_unchecked(
-            Rasterize::task(raster_u32),
+            Rasterize::task(raster_u32, weighted_blend),
             calc_cube_count([img_size.x, img_size.y], Rasterize::WORKGROUP_SIZE),
-            vec![
-                uniforms_buffer.clone().handle.binding(),
-                compact_gid_from_isect.handle.clone().binding(),
-                tile_offsets.handle.clone().binding(),
-                projected_splats.handle.clone().binding(),
-                out_img.handle.clone().binding(),
-                final_index.handle.clone().binding(),
-            ],
+            bindings,
         );
     }
 
@@ -0,0 +1,85 @@
+//! Spherical harmonics evaluated with tensor operations, matching `sh_coeffs_to_color` in the
+//! `project_visible` shader.
+
+use burn::prelude::Backend;
+use burn::tensor::Tensor;
+
+use crate::render::{SH_C0, sh_degree_from_coeffs};
+
+/// Values of the SH bases up to `degree` for each of the `[N, 3]` unit directions, as `[N, K]`,
+/// in the order the coefficients of splats are stored in.
+pub fn sh_basis<B: Backend>(degree: u32, dirs: Tensor<B, 2>) -> Tensor<B, 2> {
+    let [n, _] = dirs.dims();
+    let x = dirs.clone().slice([0..n, 0..1]);
+    let y = dirs.clone().slice([0..n, 1..2]);
+    let z = dirs.slice([0..n, 2..3]);
+
+    let mut bases = vec![Tensor::ones([n, 1], &x.device()) * SH_C0];
+    if degree >= 1 {
+        let tmp_0a = 0.488_602_5;
+        bases.extend([-y.clone() * tmp_0a, z.clone() * tmp_0a, -x.clone() * tmp_0a]);
+    }
+    if degree >= 2 {
+        let z2 = z.clone() * z.clone();
+        let tmp_0b = z.clone() * -1.092_548_4;
+        let tmp_1a = 0.546_274_2;
+        let c1 = x.clone() * x.clone() - y.clone() * y.clone();
+        let s1 = x.clone() * y.clone() * 2.0;
+        let sh6 = z2.clone() * 0.946_174_7 - 0.315_391_57;
+        bases.extend([
+            s1.clone() * tmp_1a,
+            tmp_0b.clone() * y.clone(),
+            sh6.clone(),
+            tmp_0b * x.clone(),
+            c1.clone() * tmp_1a,
+        ]);
+
+        if degree >= 3 {
+            let tmp_0c = z2.clone() * -2.285_229 + 0.457_045_8;
+            let tmp_1b = z.clone() * 1.445_305_7;
+            let tmp_2a = -0.590_043_6;
+            let c2 = x.clone() * c1.clone() - y.clone() * s1.clone();
+            let s2 = x.clone() * s1.clone() + y.clone() * c1.clone();
+            let sh12 = z.clone() * (z2.clone() * 1.865_881_7 - 1.119_529);
+            bases.extend([
+                s2.clone() * tmp_2a,
+                tmp_1b.clone() * s1.clone(),
+                tmp_0c.clone() * y.clone(),
+                sh12.clone(),
+                tmp_0c * x.clone(),
+                tmp_1b * c1.clone(),
+                c2.clone() * tmp_2a,
+            ]);
+
+            if degree >= 4 {
+                let tmp_0d = z.clone() * (z2.clone() * -4.683_326 + 2.007_139_7);
+                let tmp_1c = z2 * 3.311_611_4 - 0.473_087_34;
+                let tmp_2b = z.clone() * -1.770_130_8;
+                let tmp_3a = 0.625_835_7;
+                let c3 = x.clone() * c2.clone() - y.clone() * s2.clone();
+                let s3 = x.clone() * s2.clone() + y.clone() * c2.clone();
+                bases.extend([
+                    s3 * tmp_3a,
+                    tmp_2b.clone() * s2,
+                    tmp_1c.clone() * s1,
+                    tmp_0d.clone() * y,
+                    z * sh12 * 1.984_313_5 - sh6 * 1.006_230_6,
+                    tmp_0d * x,
+                    tmp_1c * c1,
+                    tmp_2b * c2,
+                    c3 * tmp_3a,
+                ]);
+            }
+        }
+    }
+    Tensor::cat(bases, 1)
+}
+
+/// Colors of `[N, K, 3]` SH coefficients seen from the `[N, 3]` unit directions, as `[N, 3]`.
+/// Like in the renderer, this doesn't include the 0.5 offset of the colors.
+pub fn sh_to_color<B: Backend>(coeffs: Tensor<B, 3>, dirs: Tensor<B, 2>) -> Tensor<B, 2> {
+    let [n, k, _] = coeffs.dims();
+    let degree = sh_degree_from_coeffs(k as u32);
+    let basis = sh_basis(degree, dirs).reshape([n, k, 1]);
+    (coeffs * basis).sum_dim(1).reshape([n, 3])
+}
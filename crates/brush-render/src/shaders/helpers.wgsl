@@ -9,6 +9,10 @@ struct RenderUniforms {
     viewmat: mat4x4f,
     // Position of camera (xyz + pad)
     camera_position: vec4f,
+    // Splats on the positive side of this plane (normal xyz, offset w) are cut away.
+    section_plane: vec4f,
+    // Direction towards the light (xyz) and the ambient light (w), when shading is lit.
+    light: vec4f,
     // Focal of camera (fx, fy)
     focal: vec2f,
     // Img resolution (w, h)
@@ -30,6 +34,18 @@ struct RenderUniforms {
     num_intersections: i32,
 #endif
     total_splats: u32,
+    // Only splats with a depth between the near and far plane are rendered.
+    near: f32,
+    far: f32,
+    // Splats this close to the section plane are highlighted.
+    section_highlight: f32,
+    // How to color the splats, 0 for their colors, 1 lit by the light, 2 by their normals.
+    shading: u32,
+    // Multiplier of the linear colors, and the gamma of 8 bit renders.
+    exposure: f32,
+    gamma: f32,
+    // Pads the struct to a multiple of 16 bytes.
+    padding: vec2f,
 }
 
 // nb: this struct has a bunch of padding but that's probably fine.
@@ -283,6 +299,19 @@ fn as_packed(vec: vec3f) -> PackedVec3 {
     return PackedVec3(vec.x, vec.y, vec.z);
 }
 
+// Splat colors are sRGB encoded, like the images they're trained on.
+fn srgb_to_linear(c: vec3f) -> vec3f {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3f(2.4));
+    return select(high, low, c <= vec3f(0.04045));
+}
+
+fn linear_to_srgb(c: vec3f) -> vec3f {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3f(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3f(0.0031308));
+}
+
 fn sigmoid(x: f32) -> f32 {
     return 1.0 / (1.0 + exp(-x));
 }
@@ -34,7 +34,16 @@ fn main(@builtin(global_invocation_id) global_id: vec3u) {
     let R = mat3x3f(viewmat[0].xyz, viewmat[1].xyz, viewmat[2].xyz);
     let mean_c = R * mean + viewmat[3].xyz;
 
-    if mean_c.z < 0.01 || mean_c.z > 1e10 {
+    // The depth is sorted as a u32, which only orders correctly for positive finite floats. Check
+    // the exponent bits explicitly to cull NaN and inf depths (eg. from degenerate means), as NaN
+    // comparisons can't be relied on in shaders.
+    let depth_bits = bitcast<u32>(mean_c.z);
+    if mean_c.z < uniforms.near || mean_c.z > uniforms.far || (depth_bits & 0x7f800000u) == 0x7f800000u {
+        return;
+    }
+
+    // Cull splats cut away by the section plane.
+    if dot(uniforms.section_plane.xyz, mean) + uniforms.section_plane.w > 0.0 {
         return;
     }
 
@@ -12,7 +12,12 @@ struct IsectInfo {
 @group(0) @binding(1) var<storage, read> means: array<helpers::PackedVec3>;
 @group(0) @binding(2) var<storage, read> log_scales: array<helpers::PackedVec3>;
 @group(0) @binding(3) var<storage, read> quats: array<vec4f>;
+#ifdef SH_F16
+// Coefficients stored as f16, two to a u32.
+@group(0) @binding(4) var<storage, read> coeffs: array<u32>;
+#else
 @group(0) @binding(4) var<storage, read> coeffs: array<helpers::PackedVec3>;
+#endif
 @group(0) @binding(5) var<storage, read> raw_opacities: array<f32>;
 
 @group(0) @binding(6) var<storage, read> global_from_compact_gid: array<i32>;
@@ -160,8 +165,19 @@ fn num_sh_coeffs(degree: u32) -> u32 {
     return (degree + 1) * (degree + 1);
 }
 
+#ifdef SH_F16
+fn read_half(index: u32) -> f32 {
+    return unpack2x16float(coeffs[index / 2u])[index % 2u];
+}
+#endif
+
 fn read_coeffs(base_id: ptr<function, u32>) -> vec3f {
+#ifdef SH_F16
+    let first = *base_id * 3u;
+    let ret = vec3f(read_half(first), read_half(first + 1u), read_half(first + 2u));
+#else
     let ret = helpers::as_vec(coeffs[*base_id]);
+#endif
     *base_id += 1u;
     return ret;
 }
@@ -188,6 +204,13 @@ fn main(@builtin(global_invocation_id) gid: vec3u) {
     let R = mat3x3f(viewmat[0].xyz, viewmat[1].xyz, viewmat[2].xyz);
     let mean_c = R * mean + viewmat[3].xyz;
 
+    // When reusing the depth order of an earlier render, splats can have moved behind the camera,
+    // past the near and far plane, or past the section plane. These don't hit any tiles.
+    let section_dist = dot(uniforms.section_plane.xyz, mean) + uniforms.section_plane.w;
+    if mean_c.z < uniforms.near || mean_c.z > uniforms.far || section_dist > 0.0 {
+        return;
+    }
+
     let covar = helpers::calc_cov3d(scale, quat);
     let cov2d = helpers::calc_cov2d(covar, mean_c, uniforms.focal, uniforms.img_size, uniforms.pixel_center, viewmat);
     let conic = helpers::inverse(cov2d);
@@ -243,6 +266,37 @@ fn main(@builtin(global_invocation_id) gid: vec3u) {
 
     var color = sh_coeffs_to_color(sh_degree, viewdir, sh) + vec3f(0.5);
 
+    if uniforms.shading != 0u {
+        // Flat splats are shaped like the surface they're on, so their shortest axis is
+        // close to the normal of that surface.
+        let axes = helpers::quat_to_mat(quat);
+        var normal = axes[2];
+        if scale.x < scale.y && scale.x < scale.z {
+            normal = axes[0];
+        } else if scale.y < scale.z {
+            normal = axes[1];
+        }
+        // Face the normal towards the camera.
+        if dot(normal, viewdir) > 0.0 {
+            normal = -normal;
+        }
+
+        if uniforms.shading == 1u {
+            // Light the base color, without the view dependent color baked into the splats.
+            let albedo = max(SH_C0 * sh.b0_c0 + vec3f(0.5), vec3f(0.0));
+            let ambient = uniforms.light.w;
+            let diffuse = max(dot(normal, uniforms.light.xyz), 0.0);
+            color = albedo * (ambient + (1.0 - ambient) * diffuse);
+        } else {
+            color = (R * normal) * 0.5 + vec3f(0.5);
+        }
+    }
+
+    // Highlight the splats along the section cut.
+    if section_dist > -uniforms.section_highlight {
+        color = mix(color, vec3f(1.0, 0.35, 0.1), 0.8);
+    }
+
     projected[compact_gid] = helpers::create_projected_splat(
         mean2d,
         vec3f(conic[0][0], conic[0][1], conic[1][1]),
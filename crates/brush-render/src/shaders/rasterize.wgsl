@@ -15,6 +15,21 @@
 
 var<workgroup> local_batch: array<helpers::ProjectedSplat, helpers::TILE_SIZE>;
 
+#ifdef WEIGHTED_BLEND
+    // View depth of the splats. Without a sort, the compact order is the order of the depths.
+    @group(0) @binding(6) var<storage, read> depths: array<f32>;
+    var<workgroup> local_depths: array<f32, helpers::TILE_SIZE>;
+
+    // Weight of a splat at depth `z` in the average, from "Weighted Blended Order-Independent
+    // Transparency" (McGuire and Bavoil, 2013, eq. 10). Closer splats count more, as they're more
+    // likely to be in front.
+    fn depth_weight(z: f32) -> f32 {
+        let z_near = z / 5.0;
+        let z_far = z / 200.0;
+        return clamp(10.0 / (1e-5 + z_near * z_near + pow(z_far, 6.0)), 1e-2, 3e3);
+    }
+#endif
+
 // kernel function for rasterizing each tile
 // each thread treats a single pixel
 // each thread group uses the same gaussian data in a tile
@@ -46,6 +61,12 @@ fn main(
     var T = 1.0;
     var pix_out = vec3f(0.0);
 
+    #ifdef WEIGHTED_BLEND
+        // Splats aren't depth sorted, so accumulate a weighted sum of the colors, which doesn't
+        // depend on the order.
+        var weight_sum = 0.0;
+    #endif
+
     // collect and process batches of gaussians
     // each thread loads one gaussian at a time before rasterizing its
     // designated pixel
@@ -65,7 +86,11 @@ fn main(
 
         if i32(local_idx) < remaining {
             let load_isect_id = batch_start + i32(local_idx);
-            local_batch[local_idx] = projected_splats[compact_gid_from_isect[load_isect_id]];
+            let load_compact_gid = compact_gid_from_isect[load_isect_id];
+            local_batch[local_idx] = projected_splats[load_compact_gid];
+            #ifdef WEIGHTED_BLEND
+                local_depths[local_idx] = depths[load_compact_gid];
+            #endif
         }
         // Wait for all writes to complete.
         workgroupBarrier();
@@ -86,16 +111,25 @@ fn main(
             }
 
             let next_T = T * (1.0 - alpha);
-
-            if next_T <= 1e-4f {
-                done = true;
-                break;
-            }
-
-            let vis = alpha * T;
             let clamped_rgb = max(color.rgb, vec3f(0.0));
-            pix_out += clamped_rgb * vis;
-            T = next_T;
+
+            #ifdef WEIGHTED_BLEND
+                // The transmittance is the same in any order, but as there's no front to back
+                // order, all splats have to be blended.
+                let weight = alpha * depth_weight(local_depths[t]);
+                pix_out += clamped_rgb * weight;
+                weight_sum += weight;
+                T = next_T;
+            #else
+                if next_T <= 1e-4f {
+                    done = true;
+                    break;
+                }
+
+                let vis = alpha * T;
+                pix_out += clamped_rgb * vis;
+                T = next_T;
+            #endif
 
             let isect_id = batch_start + t;
             final_idx = isect_id + 1;
@@ -104,7 +138,25 @@ fn main(
 
     if inside {
         let img_alpha = (1.0 - T);
-        let final_color = vec4f(pix_out, img_alpha);
+
+        #ifdef WEIGHTED_BLEND
+            if weight_sum > 0.0 {
+                pix_out *= img_alpha / weight_sum;
+            }
+        #endif
+
+        #ifdef RASTER_U32
+            // Adjust the colors for display, with the exposure applied to the linear colors.
+            var display_rgb = pix_out;
+            if uniforms.exposure != 1.0 || uniforms.gamma != 1.0 {
+                let linear = helpers::srgb_to_linear(max(pix_out, vec3f(0.0))) * uniforms.exposure;
+                display_rgb = pow(helpers::linear_to_srgb(linear), vec3f(1.0 / uniforms.gamma));
+            }
+            let final_color = vec4f(display_rgb, img_alpha);
+        #else
+            let final_color = vec4f(pix_out, img_alpha);
+        #endif
+
         #ifdef RASTER_U32
             let colors_u = vec4u(clamp(final_color * 255.0, vec4f(0.0), vec4f(255.0)));
             let packed: u32 = colors_u.x | (colors_u.y << 8u) | (colors_u.z << 16u) | (colors_u.w << 24u);
@@ -0,0 +1,173 @@
+//! Statistics over (splat parameter) tensors, reduced on the GPU.
+//!
+//! Reading back every value of a multi-million splat scene is slow, so these only read back the
+//! reduced results: a handful of floats or the bin counts of a histogram.
+
+use burn::prelude::{Backend, Tensor};
+use burn::tensor::{DataError, Int};
+
+/// Summary statistics of a set of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TensorStats {
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+/// A histogram with equally sized bins between `min` and `max`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub min: f32,
+    pub max: f32,
+    pub counts: Vec<u32>,
+}
+
+impl Histogram {
+    pub fn bin_width(&self) -> f32 {
+        (self.max - self.min) / self.counts.len().max(1) as f32
+    }
+
+    /// The value range covered by bin `index`.
+    pub fn bin_range(&self, index: usize) -> std::ops::Range<f32> {
+        let width = self.bin_width();
+        let start = self.min + index as f32 * width;
+        start..start + width
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&c| c as u64).sum()
+    }
+
+    /// Approximate value below which a fraction `q` of the values lie.
+    ///
+    /// Interpolates linearly within the bin, so it's only as precise as the bin width.
+    pub fn quantile(&self, q: f32) -> f32 {
+        let target = q.clamp(0.0, 1.0) as f64 * self.total() as f64;
+        let mut seen = 0.0;
+
+        for (i, &count) in self.counts.iter().enumerate() {
+            let count = count as f64;
+            if count > 0.0 && seen + count >= target {
+                let frac = ((target - seen) / count) as f32;
+                let range = self.bin_range(i);
+                return range.start + frac * (range.end - range.start);
+            }
+            seen += count;
+        }
+
+        self.max
+    }
+}
+
+async fn read_floats<B: Backend>(tensor: Tensor<B, 1>) -> Result<Vec<f32>, DataError> {
+    tensor.into_data_async().await.convert::<f32>().to_vec()
+}
+
+/// Compute the min, max and mean of `values` with a single small readback.
+pub async fn tensor_stats<B: Backend>(values: Tensor<B, 1>) -> Result<TensorStats, DataError> {
+    let count = values.dims()[0];
+    if count == 0 {
+        return Ok(TensorStats {
+            count,
+            min: 0.0,
+            max: 0.0,
+            mean: 0.0,
+        });
+    }
+
+    let reduced = Tensor::cat(
+        vec![values.clone().min(), values.clone().max(), values.mean()],
+        0,
+    );
+    let reduced = read_floats(reduced).await?;
+
+    Ok(TensorStats {
+        count,
+        min: reduced[0],
+        max: reduced[1],
+        mean: reduced[2],
+    })
+}
+
+/// Bin `values` into a histogram with `bins` bins spanning `min..max`.
+///
+/// Values outside of the range are counted in the first or last bin.
+pub async fn histogram_in_range<B: Backend>(
+    values: Tensor<B, 1>,
+    min: f32,
+    max: f32,
+    bins: usize,
+) -> Result<Histogram, DataError> {
+    let bins = bins.max(1);
+    let range = (max - min).max(1e-12);
+    let bin_idx = ((values - min) * (bins as f32 / range)).int();
+    let counts = bin_counts(bin_idx, bins).await?;
+    Ok(Histogram { min, max, counts })
+}
+
+/// Bin `values` into a histogram with `bins` bins spanning the full range of the values.
+///
+/// The range is computed on the GPU as well, so nothing is read back until the bins are counted.
+pub async fn histogram<B: Backend>(
+    values: Tensor<B, 1>,
+    bins: usize,
+) -> Result<Histogram, DataError> {
+    let bins = bins.max(1);
+    if values.dims()[0] == 0 {
+        return Ok(Histogram {
+            min: 0.0,
+            max: 0.0,
+            counts: vec![0; bins],
+        });
+    }
+
+    let min = values.clone().min();
+    let max = values.clone().max();
+    let scale = (max.clone() - min.clone()).clamp_min(1e-12).recip() * bins as f32;
+    let bin_idx = ((values - min.clone()) * scale).int();
+    let counts = bin_counts(bin_idx, bins).await?;
+
+    let range = read_floats(Tensor::cat(vec![min, max], 0)).await?;
+    Ok(Histogram {
+        min: range[0],
+        max: range[1],
+        counts,
+    })
+}
+
+async fn bin_counts<B: Backend>(
+    bin_idx: Tensor<B, 1, Int>,
+    bins: usize,
+) -> Result<Vec<u32>, DataError> {
+    let device = bin_idx.device();
+    let n = bin_idx.dims()[0];
+    let bin_idx = bin_idx.clamp(0, bins as i32 - 1);
+
+    let counts = Tensor::<B, 1, Int>::zeros([bins], &device)
+        .scatter(0, bin_idx, Tensor::ones([n], &device))
+        .into_data_async()
+        .await
+        .convert::<i32>()
+        .to_vec::<i32>()?;
+    Ok(counts.into_iter().map(|c| c.max(0) as u32).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Histogram;
+
+    #[test]
+    fn histogram_quantiles() {
+        let hist = Histogram {
+            min: 0.0,
+            max: 4.0,
+            counts: vec![1, 1, 1, 1],
+        };
+        assert_eq!(hist.total(), 4);
+        assert_eq!(hist.bin_range(1), 1.0..2.0);
+        assert!((hist.quantile(0.5) - 2.0).abs() < 1e-6);
+        assert!((hist.quantile(1.0) - 4.0).abs() < 1e-6);
+        assert!(hist.quantile(0.0).abs() < 1e-6);
+    }
+}
@@ -1,7 +1,16 @@
-use crate::{SplatForward, camera::Camera};
+use crate::{
+    SplatForward,
+    camera::{Camera, Shading},
+    gaussian_splats::Splats,
+    render::BlendMode,
+};
 use assert_approx_eq::assert_approx_eq;
-use burn::tensor::{Tensor, TensorPrimitive};
-use burn_wgpu::{Wgpu, WgpuDevice};
+use burn::tensor::{DType, Distribution, FloatDType, Tensor, TensorPrimitive};
+use burn_cubecl::cubecl::{
+    Feature, Runtime,
+    ir::{Elem, FloatKind},
+};
+use burn_wgpu::{Wgpu, WgpuDevice, WgpuRuntime};
 
 type Back = Wgpu;
 
@@ -37,6 +46,8 @@ fn renders_at_all() {
         sh_coeffs.into_primitive().tensor(),
         raw_opacity.into_primitive().tensor(),
         false,
+        BlendMode::Sorted,
+        None,
     );
     aux.into_wrapped().debug_assert_valid();
 
@@ -52,3 +63,205 @@ fn renders_at_all() {
     assert_approx_eq!(rgb_mean, 0.0, 1e-5);
     assert_approx_eq!(alpha_mean, 0.0);
 }
+
+// Small splats around the origin, with random colors up to SH degree 3.
+fn random_splats(device: &WgpuDevice) -> Splats<Back> {
+    let num_points = 64;
+    let means =
+        Tensor::<Back, 2>::random([num_points, 3], Distribution::Uniform(-1.0, 1.0), device);
+    let sh_coeffs = Tensor::<Back, 3>::random(
+        [num_points, 16, 3],
+        Distribution::Uniform(-0.3, 0.3),
+        device,
+    );
+    Splats::from_tensor_data(
+        means,
+        Tensor::<Back, 1>::from_floats(glam::Quat::IDENTITY.to_array(), device)
+            .unsqueeze_dim(0)
+            .repeat_dim(0, num_points),
+        Tensor::<Back, 2>::ones([num_points, 3], device) * -2.0,
+        sh_coeffs,
+        Tensor::<Back, 1>::zeros([num_points], device),
+    )
+}
+
+#[test]
+fn view_colors_render_the_same() {
+    let cam = Camera::new(
+        glam::vec3(0.2, -0.1, -4.0),
+        glam::Quat::IDENTITY,
+        0.8,
+        0.8,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(32, 32);
+    let splats = random_splats(&WgpuDevice::DefaultDevice);
+
+    let baked = splats.with_view_colors(cam.position);
+    assert_eq!(baked.sh_degree(), 0);
+
+    let (full, _) = splats.render(&cam, img_size, false);
+    let (cached, _) = baked.render(&cam, img_size, false);
+    let diff = (full - cached).abs().max().into_scalar();
+    assert!(diff < 1e-4, "Baked colors render differently, by {diff}");
+}
+
+#[test]
+fn f16_colors_render_close_to_f32() {
+    let device = WgpuDevice::DefaultDevice;
+    let f16 = Feature::Type(Elem::Float(FloatKind::F16));
+    if !WgpuRuntime::client(&device)
+        .properties()
+        .feature_enabled(f16)
+    {
+        // Converting the coefficients needs f16 in shaders.
+        return;
+    }
+    let cam = Camera::new(
+        glam::vec3(0.2, -0.1, -4.0),
+        glam::Quat::IDENTITY,
+        0.8,
+        0.8,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(32, 32);
+    let splats = random_splats(&device);
+    let half = splats.clone().with_sh_dtype(FloatDType::F16);
+    assert_eq!(half.sh_coeffs.val().dtype(), DType::F16);
+
+    let (full, _) = splats.render(&cam, img_size, false);
+    let (half, _) = half.render(&cam, img_size, false);
+    let diff = (full - half).abs().max().into_scalar();
+    assert!(diff < 5e-3, "f16 colors render differently, by {diff}");
+}
+
+const RED: [f32; 3] = [2.0, -2.0, -2.0];
+const BLUE: [f32; 3] = [-2.0, -2.0, 2.0];
+
+// Render splats along the view axis, with the given depths and colors.
+fn render_on_axis(
+    depths: &[f32],
+    colors: &[[f32; 3]],
+    log_scales: &[f32],
+    raw_opacity: f32,
+    blend_mode: BlendMode,
+) -> [f32; 3] {
+    let cam = Camera::new(
+        glam::Vec3::ZERO,
+        glam::Quat::IDENTITY,
+        0.8,
+        0.8,
+        glam::vec2(0.5, 0.5),
+    );
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = depths.len();
+    let means: Vec<f32> = depths.iter().flat_map(|&z| [0.0, 0.0, z]).collect();
+    let scales: Vec<f32> = log_scales.iter().flat_map(|&s| [s; 3]).collect();
+    let colors: Vec<f32> = colors.iter().flatten().copied().collect();
+    let splats = Splats::<Back>::from_tensor_data(
+        Tensor::<Back, 1>::from_floats(means.as_slice(), &device).reshape([num_points, 3]),
+        Tensor::<Back, 1>::from_floats(glam::Quat::IDENTITY.to_array(), &device)
+            .unsqueeze_dim(0)
+            .repeat_dim(0, num_points),
+        Tensor::<Back, 1>::from_floats(scales.as_slice(), &device).reshape([num_points, 3]),
+        Tensor::<Back, 1>::from_floats(colors.as_slice(), &device).reshape([num_points, 1, 3]),
+        Tensor::<Back, 1>::full([num_points], raw_opacity, &device),
+    );
+    let (output, _) =
+        splats.render_with_options(&cam, glam::uvec2(16, 16), false, blend_mode, None);
+    let center = output.slice([8..9, 8..9, 0..3]).into_data();
+    let center = center.as_slice::<f32>().expect("Wrong type");
+    [center[0], center[1], center[2]]
+}
+
+#[test]
+fn depth_sort_orders_extreme_depths() {
+    // Depths from just past the near plane to far away, where a huge splat covers the view.
+    let cases = [
+        (vec![0.02, 1e8], vec![-6.0, 18.0]),
+        // Near equal depths, a handful of ulps apart.
+        (vec![5.0, 5.000_01], vec![0.0, 0.0]),
+    ];
+    for (depths, log_scales) in cases {
+        // The front splat covers the one behind it, whichever order they're given in.
+        let front = render_on_axis(&depths, &[RED, BLUE], &log_scales, 10.0, BlendMode::Sorted);
+        let rev_depths: Vec<f32> = depths.iter().rev().copied().collect();
+        let rev_scales: Vec<f32> = log_scales.iter().rev().copied().collect();
+        let reversed = render_on_axis(
+            &rev_depths,
+            &[BLUE, RED],
+            &rev_scales,
+            10.0,
+            BlendMode::Sorted,
+        );
+        for color in [front, reversed] {
+            assert!(
+                color[0] > 0.9 && color[2] < 0.1,
+                "Splat behind drawn in front at depths {depths:?}: {color:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn weighted_blend_favours_closer_splats() {
+    // Half transparent splats that cover the same pixels, as the far one is 5x bigger.
+    let depths = [2.0, 10.0];
+    let log_scales = [0.1f32.ln(), 0.5f32.ln()];
+    for (colors, front) in [([RED, BLUE], 0), ([BLUE, RED], 2)] {
+        let color = render_on_axis(
+            &depths,
+            &colors,
+            &log_scales,
+            0.0,
+            BlendMode::WeightedBlended,
+        );
+        // Without depth weights the colors would be mixed evenly.
+        let back = 2 - front;
+        assert!(
+            color[front] > 4.0 * color[back],
+            "Far splat weighs as much as the near one: {color:?}"
+        );
+    }
+}
+
+#[test]
+fn reused_depth_order_renders_the_same() {
+    let cam = Camera::new(
+        glam::vec3(0.2, -0.1, -4.0),
+        glam::Quat::IDENTITY,
+        0.8,
+        0.8,
+        glam::vec2(0.5, 0.5),
+    );
+    let img_size = glam::uvec2(32, 32);
+    let device = WgpuDevice::DefaultDevice;
+    let num_points = 64;
+    let splats = Splats::from_tensor_data(
+        Tensor::<Back, 2>::random([num_points, 3], Distribution::Uniform(-1.0, 1.0), &device),
+        Tensor::<Back, 1>::from_floats(glam::Quat::IDENTITY.to_array(), &device)
+            .unsqueeze_dim(0)
+            .repeat_dim(0, num_points),
+        Tensor::<Back, 2>::ones([num_points, 3], &device) * -2.0,
+        Tensor::<Back, 3>::random(
+            [num_points, 1, 3],
+            Distribution::Uniform(-1.0, 1.0),
+            &device,
+        ),
+        Tensor::<Back, 1>::zeros([num_points], &device),
+    );
+
+    let (_, aux) = splats.render(&cam, img_size, false);
+    let order = aux.depth_order();
+
+    // Shading the splats differently keeps the same splats visible, in the same order.
+    let shaded = Camera {
+        shading: Shading::Normals,
+        ..cam.clone()
+    };
+    let (fresh, _) = splats.render_with_options(&shaded, img_size, false, BlendMode::Sorted, None);
+    let (reused, _) =
+        splats.render_with_options(&shaded, img_size, false, BlendMode::Sorted, Some(order));
+    let diff = (fresh - reused).abs().max().into_scalar();
+    assert!(diff < 1e-6, "Reused order renders differently, by {diff}");
+}
@@ -4,6 +4,7 @@ fn main() -> miette::Result<()> {
     brush_wgsl::build_modules(
         &[
             "src/shaders/sort_count.wgsl",
+            "src/shaders/sort_dispatch.wgsl",
             "src/shaders/sort_reduce.wgsl",
             "src/shaders/sort_scan_add.wgsl",
             "src/shaders/sort_scan.wgsl",
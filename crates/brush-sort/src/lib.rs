@@ -1,15 +1,12 @@
 use brush_kernel::CubeCount;
-use brush_kernel::create_dispatch_buffer;
 use brush_kernel::create_tensor;
 use brush_kernel::create_uniform_buffer;
 use burn::tensor::DType;
-use burn::tensor::Int;
-use burn::tensor::Tensor;
 use burn::tensor::TensorMetadata;
-use burn_cubecl::CubeBackend;
 use burn_wgpu::CubeTensor;
 use burn_wgpu::WgpuRuntime;
 use shaders::sort_count;
+use shaders::sort_dispatch;
 use shaders::sort_reduce;
 use shaders::sort_scan;
 use shaders::sort_scan_add;
@@ -25,6 +22,7 @@ const BLOCK_SIZE: u32 = WG * ELEMENTS_PER_THREAD;
 const BIN_COUNT: u32 = shaders::sorting::BIN_COUNT;
 
 kernel_source_gen!(SortCount {}, sort_count);
+kernel_source_gen!(SortDispatch {}, sort_dispatch);
 kernel_source_gen!(SortReduce {}, sort_reduce);
 kernel_source_gen!(SortScanAdd {}, sort_scan_add);
 kernel_source_gen!(SortScan {}, sort_scan);
@@ -53,11 +51,27 @@ pub fn radix_argsort(
 
     let max_needed_wgs = max_n.div_ceil(BLOCK_SIZE);
 
-    let num_wgs = create_dispatch_buffer(n_sort.clone(), [BLOCK_SIZE, 1, 1]);
-    let num_reduce_wgs: Tensor<CubeBackend<WgpuRuntime, f32, i32, u32>, 1, Int> =
-        Tensor::from_primitive(create_dispatch_buffer(num_wgs.clone(), [BLOCK_SIZE, 1, 1]))
-            * Tensor::from_ints([BIN_COUNT, 1, 1], device);
-    let num_reduce_wgs: CubeTensor<WgpuRuntime> = num_reduce_wgs.into_primitive();
+    // The scan handles the reduced sums one block at a time, so round up to a whole block.
+    let max_reduce_wgs = BIN_COUNT * max_needed_wgs.div_ceil(BLOCK_SIZE);
+    let reduced_size = max_reduce_wgs.div_ceil(BLOCK_SIZE).max(1) * BLOCK_SIZE;
+
+    // Work out the dispatch sizes on the GPU, as the number of keys is only known there. Large sorts
+    // need more workgroups than fit in one dispatch dimension, these spill over into y.
+    let num_wgs = create_tensor::<1, WgpuRuntime>([3], device, client, DType::I32);
+    let num_reduce_wgs = create_tensor::<1, WgpuRuntime>([3], device, client, DType::I32);
+
+    // SAFETY: wgsl FFI, kernel checked to have no OOB.
+    unsafe {
+        client.execute_unchecked(
+            SortDispatch::task(),
+            CubeCount::Static(1, 1, 1),
+            vec![
+                n_sort.clone().handle.binding(),
+                num_wgs.clone().handle.binding(),
+                num_reduce_wgs.clone().handle.binding(),
+            ],
+        );
+    }
 
     let mut cur_keys = input_keys;
     let mut cur_vals = input_values;
@@ -92,7 +106,7 @@ pub fn radix_argsort(
 
         {
             let reduced_buf =
-                create_tensor::<1, WgpuRuntime>([BLOCK_SIZE as usize], device, client, DType::I32);
+                create_tensor::<1, WgpuRuntime>([reduced_size as usize], device, client, DType::I32);
 
             // SAFETY: Kernel has to contain no OOB indexing.
             unsafe {
@@ -0,0 +1,27 @@
+#import sorting
+
+@group(0) @binding(0) var<storage, read> num_keys_arr: array<u32>;
+@group(0) @binding(1) var<storage, read_write> wg_dispatch: array<u32>;
+@group(0) @binding(2) var<storage, read_write> reduce_dispatch: array<u32>;
+
+@compute
+@workgroup_size(1, 1, 1)
+fn main(@builtin(global_invocation_id) global_id: vec3u) {
+    if global_id.x > 0u {
+        return;
+    }
+
+    let num_keys = num_keys_arr[0];
+    let num_wgs = sorting::div_ceil(num_keys, sorting::BLOCK_SIZE);
+    let num_reduce_wgs = sorting::BIN_COUNT * sorting::div_ceil(num_wgs, sorting::BLOCK_SIZE);
+
+    let wgs = sorting::dispatch_size(num_wgs);
+    wg_dispatch[0] = wgs.x;
+    wg_dispatch[1] = wgs.y;
+    wg_dispatch[2] = 1u;
+
+    let reduce_wgs = sorting::dispatch_size(num_reduce_wgs);
+    reduce_dispatch[0] = reduce_wgs.x;
+    reduce_dispatch[1] = reduce_wgs.y;
+    reduce_dispatch[2] = 1u;
+}
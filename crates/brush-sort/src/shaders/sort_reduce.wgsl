@@ -17,7 +17,7 @@ fn main(
     let num_wgs = sorting::div_ceil(num_keys, sorting::BLOCK_SIZE);
     let num_reduce_wgs = sorting::BIN_COUNT * sorting::div_ceil(num_wgs, sorting::BLOCK_SIZE);
 
-    let group_id = gid.x;
+    let group_id = sorting::group_index(gid);
 
     if group_id >= num_reduce_wgs {
         return;
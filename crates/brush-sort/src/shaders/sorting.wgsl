@@ -23,6 +23,19 @@ const ELEMENTS_PER_THREAD: u32 = 4;
 
 const BLOCK_SIZE = WG * ELEMENTS_PER_THREAD;
 
+// Max number of workgroups in one dispatch dimension. Larger dispatches spill over into y.
+const MAX_DISPATCH_X: u32 = 65535;
+
 fn div_ceil(a: u32, b: u32) -> u32 {
     return (a + b - 1u) / b;
 }
+
+// Number of workgroups to dispatch in x and y to run num_wgs workgroups.
+fn dispatch_size(num_wgs: u32) -> vec2u {
+    return vec2u(min(num_wgs, MAX_DISPATCH_X), div_ceil(num_wgs, MAX_DISPATCH_X));
+}
+
+// Flat workgroup index of a dispatch sized by dispatch_size.
+fn group_index(gid: vec3u) -> u32 {
+    return gid.y * MAX_DISPATCH_X + gid.x;
+}
@@ -62,20 +62,23 @@ impl AdamScaledConfig {
     pub fn init<B: AutodiffBackend, M: AutodiffModule<B>>(
         &self,
     ) -> OptimizerAdaptor<AdamScaled, M, B> {
-        let optim = AdamScaled {
+        let mut optim = OptimizerAdaptor::from(self.init_simple());
+        if let Some(config) = &self.grad_clipping {
+            optim = optim.with_grad_clipping(config.init());
+        }
+        optim
+    }
+
+    /// Initialize Adam to step single tensors with, instead of a module.
+    pub(crate) fn init_simple(&self) -> AdamScaled {
+        AdamScaled {
             momentum: AdaptiveMomentum {
                 beta_1: self.beta_1,
                 beta_2: self.beta_2,
                 epsilon: self.epsilon,
             },
             weight_decay: self.weight_decay.as_ref().map(WeightDecay::new),
-        };
-
-        let mut optim = OptimizerAdaptor::from(optim);
-        if let Some(config) = &self.grad_clipping {
-            optim = optim.with_grad_clipping(config.init());
         }
-        optim
     }
 }
 
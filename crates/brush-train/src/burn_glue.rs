@@ -1,7 +1,7 @@
 use brush_render::{
     BBase, RenderAuxPrimitive, SplatForward,
     camera::Camera,
-    render::{sh_coeffs_for_degree, sh_degree_from_coeffs},
+    render::{BlendMode, sh_coeffs_for_degree, sh_degree_from_coeffs},
 };
 use burn::{
     backend::{
@@ -208,6 +208,8 @@ impl<B: Backend + SplatBackwardOps<B> + SplatForward<B>, C: CheckpointStrategy>
             sh_coeffs.clone().into_primitive(),
             raw_opacity.clone().into_primitive(),
             false,
+            BlendMode::Sorted,
+            None,
         );
 
         let wrapped_aux = RenderAuxPrimitive::<Self> {
@@ -0,0 +1,67 @@
+//! Hooks to extend training without changing the trainer itself, eg. to add a loss term for
+//! a research idea. Register them with [`SplatTrainer::add_aux_loss`] and
+//! [`SplatTrainer::add_callback`], or bundle them in [`TrainHooks`] for a trainer that's created
+//! elsewhere, eg. by a Brush process.
+//!
+//! [`SplatTrainer::add_aux_loss`]: crate::train::SplatTrainer::add_aux_loss
+//! [`SplatTrainer::add_callback`]: crate::train::SplatTrainer::add_callback
+
+use brush_render::gaussian_splats::Splats;
+use burn::tensor::{Tensor, backend::AutodiffBackend};
+
+use crate::train::{SceneBatch, TrainBack};
+
+pub type TrainGradients = <TrainBack as AutodiffBackend>::Gradients;
+
+/// An extra term added to the loss of every step, before the backward pass.
+pub trait AuxLoss: Send {
+    /// Name of the loss, eg. to show it in logs.
+    fn name(&self) -> &str;
+
+    /// The (weighted) loss for this step. `pred_image` is the rendered image, including alpha.
+    fn loss(
+        &mut self,
+        iter: u32,
+        splats: &Splats<TrainBack>,
+        batch: &SceneBatch<TrainBack>,
+        pred_image: Tensor<TrainBack, 3>,
+    ) -> Tensor<TrainBack, 1>;
+}
+
+/// Called at points of each training step. All methods do nothing by default.
+pub trait TrainCallback: Send {
+    /// Called after the backward pass, before the optimizer step. Gradients of the splat
+    /// parameters can be read with eg. `splats.means.grad(grads)`.
+    fn on_gradients(&mut self, _iter: u32, _splats: &Splats<TrainBack>, _grads: &TrainGradients) {}
+
+    /// Whether to refine the splats at this step. `scheduled` is what the refine schedule of
+    /// the [`TrainConfig`](crate::train::TrainConfig) decided, or what previous callbacks
+    /// changed it to.
+    ///
+    /// Refining needs some statistics gathered during the steps before it, which only starts
+    /// shortly before `refine_start_iter`. Earlier requests to refine are ignored.
+    fn should_refine(&mut self, _iter: u32, scheduled: bool) -> bool {
+        scheduled
+    }
+}
+
+/// Losses and callbacks to register with a trainer once it's created.
+#[derive(Default)]
+pub struct TrainHooks {
+    pub aux_losses: Vec<Box<dyn AuxLoss>>,
+    pub callbacks: Vec<Box<dyn TrainCallback>>,
+}
+
+impl TrainHooks {
+    #[must_use]
+    pub fn with_aux_loss(mut self, loss: impl AuxLoss + 'static) -> Self {
+        self.aux_losses.push(Box::new(loss));
+        self
+    }
+
+    #[must_use]
+    pub fn with_callback(mut self, callback: impl TrainCallback + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+}
@@ -0,0 +1,144 @@
+//! Defocus of the images, for captures with a shallow depth of field like macro or portrait
+//! shots. Renders are blurred before comparing them to a view, by a small gaussian learned per
+//! view, so out of focus views don't teach the splats to be blurry.
+//!
+//! The blur is the same over the whole image, so it models views that are out of focus as a
+//! whole better than the depth dependent blur within a view.
+
+use brush_render::gaussian_splats::inverse_sigmoid;
+use burn::backend::wgpu::WgpuDevice;
+use burn::tensor::{
+    Tensor, activation::sigmoid, backend::AutodiffBackend, backend::Backend, module::conv2d,
+    ops::ConvOptions,
+};
+
+use crate::train::TrainBack;
+use crate::view_params::ViewParams;
+
+// Starting blur of all views, in pixels. Most views are in focus.
+const INIT_SIGMA_PX: f32 = 0.3;
+
+// Smallest blur, to keep the kernel finite.
+const MIN_SIGMA_PX: f32 = 0.05;
+
+pub(crate) struct Defocus {
+    /// Largest blur, in pixels, as the standard deviation of the gaussian.
+    max_px: f32,
+    // Blur of each view as the logit of the fraction of `max_px`.
+    logits: ViewParams,
+}
+
+impl Defocus {
+    pub(crate) fn new(max_px: f32, lr: f32) -> Self {
+        let init = inverse_sigmoid((INIT_SIGMA_PX / max_px).clamp(0.01, 0.99));
+        Self {
+            max_px,
+            logits: ViewParams::new(vec![init], lr),
+        }
+    }
+
+    /// Logit of the blur of a view, tracked for gradients.
+    pub(crate) fn logit(&mut self, view: &str, device: &WgpuDevice) -> Tensor<TrainBack, 1> {
+        self.logits.get(view, device)
+    }
+
+    /// Blur a render by the blur of its view.
+    pub(crate) fn apply(
+        &self,
+        logit: Tensor<TrainBack, 1>,
+        img: Tensor<TrainBack, 3>,
+    ) -> Tensor<TrainBack, 3> {
+        let sigma = sigmoid(logit) * self.max_px;
+        blur(img, sigma, (3.0 * self.max_px).ceil() as usize)
+    }
+
+    /// Take a step on the blur of a view.
+    pub(crate) fn step(
+        &mut self,
+        view: &str,
+        logit: &Tensor<TrainBack, 1>,
+        grads: &<TrainBack as AutodiffBackend>::Gradients,
+    ) {
+        self.logits.step(view, logit, grads);
+    }
+}
+
+/// Blur a `[H, W, C]` image by a gaussian with standard deviation `sigma` of shape `[1]`,
+/// cut off at `radius` pixels.
+fn blur<B: Backend>(img: Tensor<B, 3>, sigma: Tensor<B, 1>, radius: usize) -> Tensor<B, 3> {
+    let [h, w, channels] = img.dims();
+    let device = img.device();
+    let size = 2 * radius + 1;
+
+    let offsets: Vec<f32> = (0..size).map(|i| i as f32 - radius as f32).collect();
+    let offsets = Tensor::<B, 1>::from_floats(offsets.as_slice(), &device);
+    let sigma = sigma.clamp_min(MIN_SIGMA_PX);
+    let kernel = (-offsets.powf_scalar(2.0) / (sigma.powf_scalar(2.0) * 2.0)).exp();
+    let kernel = kernel.clone() / kernel.sum();
+
+    // Blur a channel of ones along with the image, to divide by how much of the kernel was
+    // inside the image. Otherwise the edges darken.
+    let img = img.permute([2, 0, 1]).unsqueeze::<4>();
+    let ones = Tensor::ones([1, 1, h, w], &device);
+    let img = Tensor::cat(vec![img, ones], 1);
+
+    // The gaussian is separable, so blur along the rows and then along the columns.
+    let groups = channels + 1;
+    let horizontal = kernel
+        .clone()
+        .reshape([1, 1, 1, size])
+        .repeat_dim(0, groups);
+    let vertical = kernel.reshape([1, 1, size, 1]).repeat_dim(0, groups);
+    let img = conv2d(
+        img,
+        horizontal,
+        None,
+        ConvOptions::new([1, 1], [0, radius], [1, 1], groups),
+    );
+    let img = conv2d(
+        img,
+        vertical,
+        None,
+        ConvOptions::new([1, 1], [radius, 0], [1, 1], groups),
+    );
+
+    let weight = img.clone().slice([0..1, channels..groups, 0..h, 0..w]);
+    let img = img.slice([0..1, 0..channels, 0..h, 0..w]) / weight;
+    img.squeeze::<3>(0).permute([1, 2, 0])
+}
+
+#[cfg(test)]
+mod tests {
+    use burn::{
+        backend::{Wgpu, wgpu::WgpuDevice},
+        tensor::Tensor,
+    };
+
+    use super::blur;
+
+    #[test]
+    fn blur_keeps_flat_images_and_spreads_points() {
+        let device = WgpuDevice::DefaultDevice;
+        let sigma = Tensor::<Wgpu, 1>::from_floats([1.5], &device);
+
+        // Edges don't darken.
+        let flat = Tensor::<Wgpu, 3>::ones([9, 12, 4], &device) * 0.5;
+        let blurred: Vec<f32> = blur(flat, sigma.clone(), 5)
+            .into_data()
+            .to_vec()
+            .expect("Wrong type");
+        assert!(blurred.iter().all(|v| (v - 0.5).abs() < 1e-5));
+
+        // A single bright pixel in the middle spreads out, keeping its total brightness.
+        let mut point = vec![0.0; 15 * 15];
+        point[7 * 15 + 7] = 1.0;
+        let point = Tensor::<Wgpu, 1>::from_floats(point.as_slice(), &device).reshape([15, 15, 1]);
+        let blurred: Vec<f32> = blur(point, sigma, 5)
+            .into_data()
+            .to_vec()
+            .expect("Wrong type");
+        assert!((blurred.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert!(blurred[7 * 15 + 7] < 0.2);
+        assert!(blurred[7 * 15 + 8] > 0.0);
+    }
+}
@@ -0,0 +1,179 @@
+//! Find where splats explain the views badly, eg. parts of a scene that need more photos.
+//!
+//! The error of each view is spread over the splats in proportion to how much they contribute to
+//! each pixel, which is exactly the gradient of the error weighted image with respect to the
+//! splat colors.
+
+use brush_render::gaussian_splats::{Splats, TrackedStat};
+use brush_render::render::SH_C0;
+use burn::backend::wgpu::WgpuDevice;
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{Tensor, TensorPrimitive};
+
+use crate::burn_glue::SplatForwardDiff;
+use crate::image::view_to_sample;
+use crate::scene::{Scene, SceneView};
+use crate::train::TrainBack;
+
+type InnerBack = <TrainBack as AutodiffBackend>::InnerBackend;
+
+/// Mean absolute error of a view.
+pub struct ViewError {
+    pub index: usize,
+    pub error: f32,
+}
+
+pub struct ErrorDiagnostics {
+    /// Average error of the pixels each splat contributes to, weighted by its contribution.
+    pub splat_error: Tensor<InnerBack, 1>,
+    /// How much each splat contributes to all views, zero for splats that are never seen.
+    pub splat_coverage: Tensor<InnerBack, 1>,
+    /// Error of every view, worst first.
+    pub views: Vec<ViewError>,
+}
+
+/// Gradient of the rendered colors of `view`, weighted by `weight` per pixel, with respect to
+/// the base color of every splat.
+fn weighted_color_grad(
+    splats: &Splats<InnerBack>,
+    view: &SceneView,
+    weight: Option<Tensor<InnerBack, 3>>,
+    device: &WgpuDevice,
+) -> (Tensor<InnerBack, 1>, Tensor<TrainBack, 3>) {
+    let splats: Splats<TrainBack> = Splats::from_tensor_data(
+        Tensor::from_inner(splats.means.val()),
+        Tensor::from_inner(splats.rotation.val()),
+        Tensor::from_inner(splats.log_scales.val()),
+        Tensor::from_inner(splats.sh_coeffs.val()),
+        Tensor::from_inner(splats.raw_opacity.val()),
+    );
+
+    let size = glam::uvec2(view.image.width(), view.image.height());
+    let out = <TrainBack as SplatForwardDiff<TrainBack>>::render_splats(
+        &view.camera,
+        size,
+        splats.means.val().into_primitive().tensor(),
+        splats.log_scales.val().into_primitive().tensor(),
+        splats.rotation.val().into_primitive().tensor(),
+        splats.sh_coeffs.val().into_primitive().tensor(),
+        splats.raw_opacity.val().into_primitive().tensor(),
+    );
+    let img: Tensor<TrainBack, 3> = Tensor::from_primitive(TensorPrimitive::Float(out.img));
+    let [h, w, _] = img.dims();
+    let rgb = img.slice([0..h, 0..w, 0..3]);
+
+    let loss = match weight {
+        Some(weight) => (rgb.clone() * Tensor::from_inner(weight)).sum(),
+        None => rgb.clone().sum(),
+    };
+    let grads = loss.backward();
+    let num_splats = splats.num_splats() as usize;
+    let grad = splats.sh_coeffs.val().grad(&grads).map_or_else(
+        || Tensor::zeros([num_splats], device),
+        |g| {
+            g.slice([0..num_splats, 0..1, 0..3])
+                .sum_dim(2)
+                .reshape([num_splats])
+        },
+    );
+    (grad, rgb)
+}
+
+/// Render every view of `scene`, and find how badly each splat explains the views it's seen in.
+pub async fn error_diagnostics(
+    splats: &Splats<InnerBack>,
+    scene: &Scene,
+    device: &WgpuDevice,
+) -> ErrorDiagnostics {
+    let num_splats = splats.num_splats() as usize;
+    let mut splat_error = Tensor::<InnerBack, 1>::zeros([num_splats], device);
+    let mut splat_coverage = Tensor::<InnerBack, 1>::zeros([num_splats], device);
+    let mut views = vec![];
+
+    for (index, view) in scene.views.iter().enumerate() {
+        let (coverage, rgb) = weighted_color_grad(splats, view, None, device);
+        let [h, w, _] = rgb.dims();
+
+        let gt = view_to_sample::<InnerBack>(view, device).slice([0..h, 0..w, 0..3]);
+        let pixel_error = (rgb.inner() - gt).abs().mean_dim(2);
+        let error = pixel_error.clone().mean().into_scalar_async().await;
+        views.push(ViewError { index, error });
+
+        let (weighted_error, _) = weighted_color_grad(splats, view, Some(pixel_error), device);
+        splat_error = splat_error + weighted_error;
+        splat_coverage = splat_coverage + coverage;
+    }
+
+    views.sort_by(|a, b| b.error.total_cmp(&a.error));
+
+    ErrorDiagnostics {
+        splat_error: splat_error / splat_coverage.clone().clamp_min(1e-6),
+        splat_coverage,
+        views,
+    }
+}
+
+// Colors from blue (0) to red (1) for values `t`, as rgb rows.
+fn heat_colors(t: Tensor<InnerBack, 1>) -> Tensor<InnerBack, 2> {
+    let num_splats = t.dims()[0];
+    let t = t.clamp(0.0, 1.0).reshape([num_splats, 1]);
+    let low = Tensor::<InnerBack, 1>::from_floats([0.1, 0.2, 1.0], &t.device()).reshape([1, 3]);
+    let high = Tensor::<InnerBack, 1>::from_floats([1.0, 0.1, 0.0], &t.device()).reshape([1, 3]);
+    low.clone() + (high - low) * t
+}
+
+fn with_colors(splats: &Splats<InnerBack>, colors: Tensor<InnerBack, 2>) -> Splats<InnerBack> {
+    let num_splats = splats.num_splats() as usize;
+    let sh_dc = ((colors - 0.5) / SH_C0).reshape([num_splats, 1, 3]);
+    Splats::from_tensor_data(
+        splats.means.val(),
+        splats.rotation.val(),
+        splats.log_scales.val(),
+        sh_dc,
+        splats.raw_opacity.val(),
+    )
+}
+
+/// Copy of `splats` colored by their error, from blue (low) to red (high). Splats that aren't seen
+/// in any view are gray.
+pub fn error_heatmap(
+    splats: &Splats<InnerBack>,
+    diagnostics: &ErrorDiagnostics,
+) -> Splats<InnerBack> {
+    let num_splats = splats.num_splats() as usize;
+    let error = diagnostics.splat_error.clone();
+
+    // Scale so the average error is in the middle of the color range.
+    let mean = error.clone().mean().clamp_min(1e-6);
+    let colors = heat_colors(error / (mean * 2.0));
+
+    let unseen = diagnostics
+        .splat_coverage
+        .clone()
+        .lower_equal_elem(0.0)
+        .reshape([num_splats, 1])
+        .repeat_dim(1, 3);
+    with_colors(splats, colors.mask_fill(unseen, 0.5))
+}
+
+/// Copy of `splats` colored by a statistic tracked while training, from blue (low) to red (high),
+/// or None if the splats weren't tracked. The creation step goes from the first to the last step
+/// splats were created at, other statistics are scaled so their average is in the middle.
+pub fn tracking_heatmap(
+    splats: &Splats<InnerBack>,
+    stat: TrackedStat,
+) -> Option<Splats<InnerBack>> {
+    let values = splats.tracking.as_ref()?.stat(stat);
+    let t = match stat {
+        TrackedStat::CreatedStep => {
+            let min = values.clone().min();
+            let range = (values.clone().max() - min.clone()).clamp_min(1.0);
+            (values - min) / range
+        }
+        TrackedStat::TimesSeen | TrackedStat::GradAccum => {
+            let mean = values.clone().mean().clamp_min(1e-6);
+            values / (mean * 2.0)
+        }
+    };
+    Some(with_colors(splats, heat_colors(t)))
+}
@@ -1,10 +1,10 @@
+use brush_render::camera::Camera;
 use brush_render::gaussian_splats::Splats;
 use brush_render::{RenderAux, SplatForward};
 use burn::prelude::Backend;
-use burn::tensor::Tensor;
-use rand::seq::IteratorRandom;
+use burn::tensor::{Tensor, TensorData};
 
-use crate::image::view_to_sample;
+use crate::image::view_to_sample_data;
 use crate::scene::{Scene, SceneView};
 use crate::ssim::Ssim;
 
@@ -19,29 +19,90 @@ pub struct EvalSample<B: Backend> {
     pub aux: RenderAux<B>,
 }
 
+/// Resample a sample of a view taken through a distorting lens to how it would look without
+/// the distortion, so it lines up with renders. Also returns a `[H, W, 1]` mask of the pixels
+/// that were inside the original image.
+fn undistort_sample(data: &TensorData, camera: &Camera) -> (TensorData, TensorData) {
+    let [h, w, c] = [data.shape[0], data.shape[1], data.shape[2]];
+    let values = data.as_slice::<f32>().expect("Samples are f32");
+    let size = glam::uvec2(w as u32, h as u32);
+    let focal = camera.focal(size);
+    let center = camera.center(size);
+
+    let mut undistorted = vec![0.0; h * w * c];
+    let mut mask = vec![0.0; h * w];
+    for y in 0..h {
+        for x in 0..w {
+            let pixel = glam::vec2(x as f32 + 0.5, y as f32 + 0.5);
+            let xy = camera.distortion.distort((pixel - center) / focal);
+            // Position in the image, relative to the centers of the pixels.
+            let src = xy * focal + center - 0.5;
+            let max = glam::vec2(w as f32 - 1.0, h as f32 - 1.0);
+            if src.cmplt(glam::Vec2::ZERO).any() || src.cmpgt(max).any() {
+                continue;
+            }
+            let x0 = (src.x.floor() as usize).min(w.saturating_sub(2));
+            let y0 = (src.y.floor() as usize).min(h.saturating_sub(2));
+            let t = src - glam::vec2(x0 as f32, y0 as f32);
+            let at =
+                |x: usize, y: usize, ch: usize| values[(y.min(h - 1) * w + x.min(w - 1)) * c + ch];
+
+            let out = (y * w + x) * c;
+            for ch in 0..c {
+                let top = at(x0, y0, ch) * (1.0 - t.x) + at(x0 + 1, y0, ch) * t.x;
+                let bottom = at(x0, y0 + 1, ch) * (1.0 - t.x) + at(x0 + 1, y0 + 1, ch) * t.x;
+                undistorted[out + ch] = top * (1.0 - t.y) + bottom * t.y;
+            }
+            mask[y * w + x] = 1.0;
+        }
+    }
+    (
+        TensorData::new(undistorted, [h, w, c]),
+        TensorData::new(mask, [h, w, 1]),
+    )
+}
+
+/// Mean of `values`, and the half width of its 95% confidence interval. The interval is zero
+/// for less than two values.
+pub fn mean_with_ci(values: &[f32]) -> (f32, f32) {
+    let n = values.len() as f32;
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f32>() / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / (n - 1.0);
+    (mean, 1.96 * (variance / n).sqrt())
+}
+
+/// Evaluate every view of `eval_scene` in order, so evals at different steps and of different
+/// runs compare the same views.
 pub fn eval_stats<B: Backend + SplatForward<B>>(
     splats: Splats<B>,
     eval_scene: &Scene,
-    num_frames: Option<usize>,
-    rng: &mut impl rand::Rng,
     device: &B::Device,
 ) -> impl Iterator<Item = EvalSample<B>> + 'static {
-    let indices = if let Some(num) = num_frames {
-        (0..eval_scene.views.len()).choose_multiple(rng, num)
-    } else {
-        (0..eval_scene.views.len()).collect()
-    };
-
     let device = device.clone();
     let scene = eval_scene.clone();
 
-    indices.into_iter().map(move |index| {
+    (0..scene.views.len()).map(move |index| {
         let view = scene.views[index].clone();
         // Compare MSE in RGB only, not sure if this should include alpha.
         let res = glam::uvec2(view.image.width(), view.image.height());
 
-        let gt_tensor = view_to_sample::<B>(&view, &device);
-        let gt_rgb = gt_tensor.slice([0..res.y as usize, 0..res.x as usize, 0..3]);
+        let mut gt_data = view_to_sample_data(&view);
+        // Renders have no lens distortion, so compare them to the image without it. Pixels
+        // that were outside the image don't count.
+        let mut mask = None;
+        if !view.camera.distortion.is_none() {
+            let (undistorted, inside) = undistort_sample(&gt_data, &view.camera);
+            gt_data = undistorted;
+            mask = Some(Tensor::<B, 3>::from_data(inside, &device));
+        }
+        let gt_tensor = Tensor::<B, 3>::from_data(gt_data, &device);
+        let mut gt_rgb = gt_tensor.slice([0..res.y as usize, 0..res.x as usize, 0..3]);
 
         let (rendered, aux) = splats.render(&view.camera, res, false);
 
@@ -50,13 +111,21 @@ pub fn eval_stats<B: Backend + SplatForward<B>>(
         // Simulate 8-bit roundtrip for fair comparison.
         let render_rgb = (render_rgb * 255.0).round() / 255.0;
 
-        let mse = (render_rgb.clone() - gt_rgb.clone())
-            .powf_scalar(2.0)
-            .mean();
+        if let Some(mask) = &mask {
+            // Fill the pixels outside with the render, so they don't skew SSIM near the edges.
+            gt_rgb = gt_rgb * mask.clone() + render_rgb.clone() * (-mask.clone() + 1.0);
+        }
+        // Mean over the pixels that count.
+        let masked_mean = |values: Tensor<B, 3>| match &mask {
+            Some(mask) => (values * mask.clone()).sum() / (mask.clone().sum() * 3.0).clamp_min(1.0),
+            None => values.mean(),
+        };
+
+        let mse = masked_mean((render_rgb.clone() - gt_rgb.clone()).powf_scalar(2.0));
 
         let psnr = mse.recip().log() * 10.0 / std::f32::consts::LN_10;
         let ssim_measure = Ssim::new(11, 3, &device);
-        let ssim = ssim_measure.ssim(render_rgb.clone(), gt_rgb).mean();
+        let ssim = masked_mean(ssim_measure.ssim(render_rgb.clone(), gt_rgb));
 
         EvalSample {
             index,
@@ -68,3 +137,53 @@ pub fn eval_stats<B: Backend + SplatForward<B>>(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use brush_render::camera::{Camera, Distortion};
+    use burn::tensor::TensorData;
+
+    use super::{mean_with_ci, undistort_sample};
+
+    #[test]
+    fn mean_with_ci_shrinks_with_more_views() {
+        assert_eq!(mean_with_ci(&[]), (0.0, 0.0));
+        assert_eq!(mean_with_ci(&[25.0]), (25.0, 0.0));
+
+        let (mean, few) = mean_with_ci(&[24.0, 26.0]);
+        assert!((mean - 25.0).abs() < 1e-6);
+        let (_, many) = mean_with_ci(&[24.0, 26.0, 24.0, 26.0, 24.0, 26.0, 24.0, 26.0]);
+        assert!(many > 0.0 && many < few);
+    }
+
+    #[test]
+    fn undistort_sample_masks_outside() {
+        let (w, h) = (21, 15);
+        let values: Vec<f32> = (0..w * h).map(|i| (i % w) as f32).collect();
+        let mut camera = Camera::new(
+            glam::Vec3::ZERO,
+            glam::Quat::IDENTITY,
+            1.0,
+            0.8,
+            glam::vec2(0.5, 0.5),
+        );
+        camera.distortion = Distortion {
+            k1: 0.3,
+            ..Distortion::NONE
+        };
+        let (undistorted, mask) = undistort_sample(&TensorData::new(values, [h, w, 1]), &camera);
+        let undistorted: Vec<f32> = undistorted.to_vec().expect("Wrong type");
+        let mask: Vec<f32> = mask.to_vec().expect("Wrong type");
+
+        // The center stays put, and the corners were outside of the image.
+        let center = (h / 2) * w + w / 2;
+        assert!((undistorted[center] - (w / 2) as f32).abs() < 1e-4);
+        assert!((mask[center] - 1.0).abs() < f32::EPSILON);
+        assert!(mask[0].abs() < f32::EPSILON);
+        assert!(mask[w * h - 1].abs() < f32::EPSILON);
+
+        // Pincushion distortion pulls the image towards the center.
+        let right = center + 7;
+        assert!(undistorted[right] > (w / 2 + 7) as f32);
+    }
+}
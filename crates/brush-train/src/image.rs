@@ -2,20 +2,72 @@ use burn::{
     prelude::Backend,
     tensor::{DType, Tensor, TensorData},
 };
+use clap::ValueEnum;
 use image::{DynamicImage, Rgb32FImage, Rgba32FImage};
+use serde::{Deserialize, Serialize};
 
 use crate::scene::{SceneView, ViewImageType};
 
+/// Color space renders are compared to the images in while training. The splats themselves
+/// always store sRGB encoded colors, like other splat software expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LossSpace {
+    /// The sRGB encoded colors, as stored in most images. Errors in the shadows count for more,
+    /// roughly like they're perceived.
+    #[default]
+    Srgb,
+    /// Linear light. Errors in bright areas count for more, and noise in dark areas for less.
+    Linear,
+}
+
+/// Decode sRGB encoded colors to linear light.
+pub fn srgb_to_linear<B: Backend, const D: usize>(srgb: Tensor<B, D>) -> Tensor<B, D> {
+    let srgb = srgb.clamp_min(0.0);
+    let low = srgb.clone() / 12.92;
+    let high = ((srgb.clone() + 0.055) / 1.055).powf_scalar(2.4);
+    high.mask_where(srgb.lower_equal_elem(0.04045), low)
+}
+
+/// Encode a linear light value as sRGB.
+pub fn linear_to_srgb(linear: f32) -> f32 {
+    let linear = linear.max(0.0);
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 // Converts an image to a train sample. The tensor will be a floating point image with a [0, 1] image.
 //
 // This assume the input image has un-premultiplied alpha, whereas the output has pre-multiplied alpha.
+//
+// 8 and 16 bit images are sRGB encoded, while float images (eg. EXR) hold linear light. Float
+// images are encoded to sRGB, so all samples are in the same space as the splat colors.
 pub fn view_to_sample<B: Backend>(view: &SceneView, device: &B::Device) -> Tensor<B, 3> {
+    Tensor::from_data(view_to_sample_data(view), device)
+}
+
+/// The data of [`view_to_sample`], before uploading it.
+pub fn view_to_sample_data(view: &SceneView) -> TensorData {
     let image = &view.image;
     let (w, h) = (image.width(), image.height());
+    let is_linear = matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
 
-    let tensor_data = if image.color().has_alpha() {
+    if image.color().has_alpha() {
         // Assume image has un-multiplied alpha and convert it to pre-multiplied.
         let mut rgba = image.to_rgba32f();
+        if is_linear {
+            for pixel in rgba.pixels_mut() {
+                for value in pixel.0.iter_mut().take(3) {
+                    *value = linear_to_srgb(*value);
+                }
+            }
+        }
         if view.img_type == ViewImageType::Alpha {
             for pixel in rgba.pixels_mut() {
                 let a = pixel[3];
@@ -26,10 +78,14 @@ pub fn view_to_sample<B: Backend>(view: &SceneView, device: &B::Device) -> Tenso
         }
         TensorData::new(rgba.into_vec(), [h as usize, w as usize, 4])
     } else {
-        TensorData::new(image.to_rgb32f().into_vec(), [h as usize, w as usize, 3])
-    };
-
-    Tensor::from_data(tensor_data, device)
+        let mut rgb = image.to_rgb32f();
+        if is_linear {
+            for value in &mut *rgb {
+                *value = linear_to_srgb(*value);
+            }
+        }
+        TensorData::new(rgb.into_vec(), [h as usize, w as usize, 3])
+    }
 }
 
 pub trait TensorDataToImage {
@@ -59,3 +115,30 @@ pub fn tensor_into_image(data: TensorData) -> DynamicImage {
 
     img
 }
+
+#[cfg(test)]
+mod tests {
+    use burn::{
+        backend::{Wgpu, wgpu::WgpuDevice},
+        tensor::Tensor,
+    };
+
+    use super::{linear_to_srgb, srgb_to_linear};
+
+    #[test]
+    fn srgb_round_trip() {
+        let values = [0.0, 0.02, 0.04045, 0.2, 0.5, 1.0];
+        let device = WgpuDevice::DefaultDevice;
+        let linear = srgb_to_linear(Tensor::<Wgpu, 1>::from_floats(values, &device));
+        let linear: Vec<f32> = linear.into_data().to_vec().expect("Wrong type");
+
+        // Middle gray is about a fifth of the light of white.
+        assert!((linear[4] - 0.214).abs() < 1e-3, "Wrong linear value");
+        for (value, linear) in values.iter().zip(linear) {
+            assert!(
+                (linear_to_srgb(linear) - value).abs() < 1e-4,
+                "{value} doesn't round trip"
+            );
+        }
+    }
+}
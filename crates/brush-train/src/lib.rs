@@ -1,5 +1,7 @@
 #![recursion_limit = "256"]
 
+pub mod callback;
+pub mod diagnostics;
 pub mod eval;
 pub mod ssim;
 pub mod train;
@@ -12,8 +14,11 @@ mod kernels;
 mod shaders;
 
 mod adam_scaled;
+mod defocus;
+mod motion_blur;
 mod stats;
 mod stats_kernel;
+mod view_params;
 
 #[cfg(all(test, not(target_family = "wasm")))]
 mod tests;
@@ -0,0 +1,191 @@
+//! Motion blur of the images, modeled like Deblur-GS: the render compared to a view is the
+//! average of renders from camera poses along a motion, from a start to an end pose learned for
+//! each view. Blurry frames then still teach the splats what the scene looks like, instead of
+//! blurring sharp regions.
+//!
+//! The renderer has no gradients for the camera pose, so instead of moving the camera the splats
+//! are moved the opposite way, which the gradients of the means flow through. The orientations of
+//! the splats aren't turned along, which for motions of a few pixels doesn't matter.
+
+use brush_render::camera::Camera;
+use burn::backend::wgpu::WgpuDevice;
+use burn::tensor::{Tensor, activation::tanh, backend::AutodiffBackend};
+
+use crate::train::TrainBack;
+use crate::view_params::ViewParams;
+
+// Number of poses along the motion to average the renders of.
+const SAMPLE_COUNT: usize = 5;
+
+// Starting distance of the start and end pose from the view, as a fraction of the maximum blur.
+// They need to start apart, or they'd only ever move together.
+const START_SPREAD: f32 = 0.05;
+
+pub(crate) struct MotionBlur {
+    /// Blur, in pixels, of the start and end pose.
+    max_px: f32,
+    // Start and end pose of each view, as a rotation (pitch, yaw, roll) and translation in the
+    // camera frame, before scaling them to the maximum blur.
+    poses: ViewParams,
+}
+
+impl MotionBlur {
+    pub(crate) fn new(max_px: f32, lr: f32) -> Self {
+        let start = [-START_SPREAD, -START_SPREAD, 0.0, 0.0, 0.0, 0.0];
+        let end = start.map(|v| -v);
+        Self {
+            max_px,
+            poses: ViewParams::new([start, end].concat(), lr),
+        }
+    }
+
+    /// Start and end pose of a view, tracked for gradients.
+    pub(crate) fn poses(&mut self, view: &str, device: &WgpuDevice) -> Tensor<TrainBack, 1> {
+        self.poses.get(view, device)
+    }
+
+    /// The means of the splats as seen from poses along the motion, as if they were seen from
+    /// `camera`. Translations blur points `scene_extent` away by at most the maximum blur.
+    pub(crate) fn moved_means(
+        &self,
+        poses: Tensor<TrainBack, 1>,
+        camera: &Camera,
+        img_size: glam::UVec2,
+        scene_extent: f32,
+        means: Tensor<TrainBack, 2>,
+    ) -> Vec<Tensor<TrainBack, 2>> {
+        let device = means.device();
+        let focal = camera.focal(img_size);
+        let (max_x, max_y) = (self.max_px / focal.x, self.max_px / focal.y);
+        let roll = self.max_px / (img_size.as_vec2().length() / 2.0);
+        let scales = [
+            max_y.atan(),
+            max_x.atan(),
+            roll,
+            max_x * scene_extent,
+            max_y * scene_extent,
+            max_x * scene_extent,
+        ];
+        let scales = Tensor::<TrainBack, 1>::from_floats(scales, &device);
+        let start = tanh(poses.clone().slice([0..6])) * scales.clone();
+        let end = tanh(poses.slice([6..12])) * scales;
+
+        // `x @ to_local` turns world directions into directions in the camera frame.
+        let rotation = glam::Mat3::from_quat(camera.rotation);
+        let to_local =
+            Tensor::<TrainBack, 2>::from_floats(rotation.to_cols_array_2d(), &device).transpose();
+        let to_world = to_local.clone().transpose();
+        let position = Tensor::<TrainBack, 1>::from_floats(camera.position.to_array(), &device)
+            .unsqueeze::<2>();
+        let local = (means - position.clone()).matmul(to_local);
+
+        (0..SAMPLE_COUNT)
+            .map(|i| {
+                let t = i as f32 / (SAMPLE_COUNT - 1) as f32;
+                let pose = start.clone() * (1.0 - t) + end.clone() * t;
+                let moved = local.clone() - pose.clone().slice([3..6]).unsqueeze::<2>();
+                // Turning the camera by a small rotation turns what it sees back by it.
+                let moved = moved.clone() - moved.matmul(cross_matrix(pose.slice([0..3])));
+                moved.matmul(to_world.clone()) + position.clone()
+            })
+            .collect()
+    }
+
+    /// The average of the renders along the motion.
+    pub(crate) fn blend(renders: Vec<Tensor<TrainBack, 3>>) -> Tensor<TrainBack, 3> {
+        let renders: Vec<_> = renders.into_iter().map(|r| r.unsqueeze_dim(0)).collect();
+        Tensor::cat(renders, 0).mean_dim(0).squeeze(0)
+    }
+
+    /// Index of the pose in the middle of the motion, see [`Self::moved_means`].
+    pub(crate) fn middle() -> usize {
+        SAMPLE_COUNT / 2
+    }
+
+    /// Take a step on the poses of a view.
+    pub(crate) fn step(
+        &mut self,
+        view: &str,
+        poses: &Tensor<TrainBack, 1>,
+        grads: &<TrainBack as AutodiffBackend>::Gradients,
+    ) {
+        self.poses.step(view, poses, grads);
+    }
+}
+
+/// The matrix `m` with `v @ m = w × v` for rows `v`.
+fn cross_matrix(w: Tensor<TrainBack, 1>) -> Tensor<TrainBack, 2> {
+    let c = |i: usize| w.clone().slice([i..i + 1]);
+    let zero = w.zeros_like().slice([0..1]);
+    Tensor::cat(
+        vec![
+            zero.clone(),
+            c(2),
+            -c(1),
+            -c(2),
+            zero.clone(),
+            c(0),
+            c(1),
+            -c(0),
+            zero,
+        ],
+        0,
+    )
+    .reshape([3, 3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MotionBlur;
+    use crate::train::TrainBack;
+    use brush_render::camera::Camera;
+    use burn::backend::wgpu::WgpuDevice;
+    use burn::tensor::Tensor;
+
+    fn moved_point(camera: &Camera, pose: [f32; 6], point: glam::Vec3) -> glam::Vec3 {
+        let device = WgpuDevice::DefaultDevice;
+        let size = glam::uvec2(200, 100);
+        // The start pose stays at the view, the end pose is saturated.
+        let poses = [[0.0; 6], pose.map(|v| v * 100.0)].concat();
+        let poses = Tensor::<TrainBack, 1>::from_floats(poses.as_slice(), &device);
+        let means = Tensor::<TrainBack, 2>::from_floats([point.to_array()], &device);
+        let moved = MotionBlur::new(4.0, 0.05).moved_means(poses, camera, size, 5.0, means);
+        let first = moved[0]
+            .clone()
+            .into_data()
+            .to_vec::<f32>()
+            .expect("Float data");
+        assert!(glam::Vec3::from_slice(&first).distance(point) < 1e-5);
+        let end = moved.last().expect("Poses").clone().into_data();
+        glam::Vec3::from_slice(&end.to_vec::<f32>().expect("Float data"))
+    }
+
+    #[test]
+    fn end_pose_blurs_by_max_px() {
+        let camera = Camera::new(
+            glam::vec3(1.0, 0.5, -2.0),
+            glam::Quat::from_rotation_y(0.3),
+            1.0,
+            0.8,
+            glam::vec2(0.5, 0.5),
+        );
+        let size = glam::uvec2(200, 100);
+        // A point in the middle of the view, as far as the scene extent.
+        let point = camera
+            .local_to_world()
+            .transform_point3(glam::vec3(0.0, 0.0, 5.0));
+        let center = camera.project(point, size).expect("In front of the camera");
+
+        for axis in 0..5 {
+            if axis == 2 {
+                // Rolling doesn't move the center of the image.
+                continue;
+            }
+            let mut pose = [0.0; 6];
+            pose[axis] = 1.0;
+            let moved = moved_point(&camera, pose, point);
+            let moved = camera.project(moved, size).expect("In front of the camera");
+            assert!((moved.distance(center) - 4.0).abs() < 1e-2, "axis {axis}");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use brush_render::{bounding_box::BoundingBox, camera::Camera};
-use glam::{Affine3A, Vec3, vec3};
+use glam::{Affine3A, Vec2, Vec3, vec3};
 use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -21,6 +21,29 @@ pub struct SceneView {
     pub camera: Camera,
     pub image: Arc<image::DynamicImage>,
     pub img_type: ViewImageType,
+    /// Views taken with the same camera share their intrinsics. Datasets without cameras put all
+    /// views in group 0.
+    pub camera_group: u32,
+    /// Points from structure from motion seen in this view, as where they were seen in uv
+    /// coordinates of the image, and their position in the world.
+    pub sparse_points: Arc<Vec<(Vec2, Vec3)>>,
+}
+
+impl SceneView {
+    /// Mean distance in pixels of the image between where the sparse points were seen, and
+    /// where `camera` projects them. None without sparse points.
+    pub fn reprojection_error(&self, camera: &Camera) -> Option<f32> {
+        let size = glam::uvec2(self.image.width(), self.image.height());
+        let errors: Vec<f32> = self
+            .sparse_points
+            .iter()
+            .filter_map(|&(uv, position)| {
+                let projected = camera.project(position, size)?;
+                Some(projected.distance(uv * size.as_vec2()))
+            })
+            .collect();
+        (!errors.is_empty()).then(|| errors.iter().sum::<f32>() / errors.len() as f32)
+    }
 }
 
 // Encapsulates a multi-view scene including cameras and the splats.
@@ -30,6 +53,11 @@ pub struct Scene {
     pub views: Arc<Vec<SceneView>>,
 }
 
+// Fraction of cameras on either side of each axis to ignore when finding the bounds of a scene.
+const CAMERA_OUTLIERS: f32 = 0.02;
+// How much to grow the bounds of the cameras when estimating the size of the scene.
+const EXTENT_INFLATION: f32 = 1.1;
+
 fn camera_distance_penalty(cam_local_to_world: Affine3A, reference: Affine3A) -> f32 {
     let mut penalty = 0.0;
     for off_x in [-1.0, 0.0, 1.0] {
@@ -62,18 +90,23 @@ impl Scene {
     }
 
     // Returns the extent of the cameras in the scene, taking into account
-    // the near and far plane of the cameras.
+    // the near and far plane of the cameras. A few cameras far away from the rest
+    // are ignored, so they don't blow up the bounds.
     pub fn adjusted_bounds(&self, cam_near: f32, cam_far: f32) -> BoundingBox {
-        let (min, max) = self.views.iter().fold(
-            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
-            |(min, max), view| {
+        let points: Vec<Vec3> = self
+            .views
+            .iter()
+            .flat_map(|view| {
                 let cam = &view.camera;
-                let pos1 = cam.position + cam.rotation * Vec3::Z * cam_near;
-                let pos2 = cam.position + cam.rotation * Vec3::Z * cam_far;
-                (min.min(pos1).min(pos2), max.max(pos1).max(pos2))
-            },
-        );
-        BoundingBox::from_min_max(min, max)
+                let forward = cam.rotation * Vec3::Z;
+                [
+                    cam.position + forward * cam_near,
+                    cam.position + forward * cam_far,
+                ]
+            })
+            .collect();
+        BoundingBox::from_points_robust(&points, CAMERA_OUTLIERS)
+            .unwrap_or(BoundingBox::from_min_max(Vec3::ZERO, Vec3::ZERO))
     }
 
     pub fn get_nearest_view(&self, reference: Affine3A) -> Option<usize> {
@@ -90,14 +123,59 @@ impl Scene {
             .map(|(index, _)| index) // We return the index instead of the camera
     }
 
+    /// Estimate the size of the scene from the cameras, ignoring outlier cameras. The bounds
+    /// are inflated a bit, as the scene usually extends a little past the cameras.
     pub fn estimate_extent(&self) -> Option<f32> {
         if self.views.len() < 5 {
             None
         } else {
-            // TODO: This is really sensitive to outliers.
-            let bounds = self.bounds();
+            let bounds = self.bounds().inflated(EXTENT_INFLATION);
             let smallest = find_two_smallest(bounds.extent * 2.0);
-            Some(smallest.0.hypot(smallest.1))
+            Some(smallest.0.hypot(smallest.1)).filter(|&extent| extent > 0.0)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reprojection_error() {
+        let camera = Camera::new(
+            Vec3::ZERO,
+            glam::Quat::IDENTITY,
+            1.0,
+            1.0,
+            glam::vec2(0.5, 0.5),
+        );
+        let size = glam::uvec2(100, 100);
+        let positions = [vec3(0.0, 0.0, 2.0), vec3(0.3, -0.2, 1.5)];
+        let sparse_points = positions
+            .iter()
+            .map(|&p| {
+                let px = camera.project(p, size).expect("In front of the camera");
+                (px / size.as_vec2(), p)
+            })
+            .collect();
+        let view = SceneView {
+            path: String::new(),
+            camera: camera.clone(),
+            image: Arc::new(image::DynamicImage::new_rgb8(size.x, size.y)),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(sparse_points),
+        };
+
+        let error = view.reprojection_error(&camera).expect("Has sparse points");
+        assert!(error < 1e-4);
+
+        // The point on the optical axis doesn't move, the other one does.
+        let mut distorted = camera;
+        distorted.distortion.k1 = 0.5;
+        let error = view
+            .reprojection_error(&distorted)
+            .expect("Has sparse points");
+        assert!(error > 0.1);
+    }
+}
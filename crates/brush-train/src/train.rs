@@ -1,5 +1,5 @@
 use anyhow::Result;
-use brush_render::gaussian_splats::{Splats, inverse_sigmoid};
+use brush_render::gaussian_splats::{SplatTracking, Splats, inverse_sigmoid};
 use brush_render::render::sh_coeffs_for_degree;
 use burn::backend::wgpu::WgpuDevice;
 use burn::backend::{Autodiff, Wgpu};
@@ -19,13 +19,35 @@ use tracing::trace_span;
 
 use crate::adam_scaled::{AdamScaled, AdamScaledConfig, AdamState};
 use crate::burn_glue::SplatForwardDiff;
+use crate::callback::{AuxLoss, TrainCallback, TrainHooks};
+use crate::defocus::Defocus;
+use crate::image::{LossSpace, srgb_to_linear};
+use crate::motion_blur::MotionBlur;
 use crate::scene::{SceneView, ViewImageType};
 use crate::ssim::Ssim;
 use crate::stats::RefineRecord;
-use clap::Args;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 const MIN_OPACITY: f32 = 0.99 / 255.0;
 
+/// Curated density control settings, for when the defaults don't fit a scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrainPreset {
+    /// Densify more and for longer, for the finest detail at the cost of memory and speed.
+    Quality,
+    /// The defaults.
+    Balanced,
+    /// Densify less and cap the number of splats, for small exports that render fast, eg. on
+    /// phones or the web.
+    Compact,
+}
+
+impl TrainPreset {
+    pub const ALL: [Self; 3] = [Self::Quality, Self::Balanced, Self::Compact];
+}
+
 #[derive(Config, Args)]
 pub struct TrainConfig {
     /// Total number of steps to train for.
@@ -33,11 +55,26 @@ pub struct TrainConfig {
     #[arg(long, help_heading = "Training options", default_value = "30000")]
     pub total_steps: u32,
 
+    /// Size of the scene, which scales the learning rate of the means and the refine thresholds.
+    /// By default estimated from the cameras.
+    #[arg(long, help_heading = "Training options")]
+    pub scene_scale: Option<f32>,
+
     /// Weight of SSIM loss (compared to l1 loss)
     #[config(default = 0.2)]
     #[clap(long, help_heading = "Training options", default_value = "0.2")]
     ssim_weight: f32,
 
+    /// Color space to compare renders to the images in.
+    #[config(default = "LossSpace::Srgb")]
+    #[arg(
+        long,
+        help_heading = "Training options",
+        value_enum,
+        default_value = "srgb"
+    )]
+    pub loss_space: LossSpace,
+
     /// SSIM window size
     #[config(default = 11)]
     #[clap(long, help_heading = "Training options", default_value = "11")]
@@ -116,7 +153,7 @@ pub struct TrainConfig {
     /// Period after which refinement stops.
     #[config(default = 15000)]
     #[arg(long, help_heading = "Refine options", default_value = "15000")]
-    refine_stop_iter: u32,
+    pub refine_stop_iter: u32,
 
     /// Every this many refinement steps, reset the alpha
     #[config(default = 30)]
@@ -128,10 +165,201 @@ pub struct TrainConfig {
     #[arg(long, help_heading = "Refine options", default_value = "100")]
     refine_every: u32,
 
+    /// Stop adding splats once there are this many.
+    #[arg(long, help_heading = "Refine options")]
+    max_splats: Option<u32>,
+
+    /// Prune splats that weren't visible in any training view during this many steps since they
+    /// were created. Tracks the splats, see `track_splats`.
+    #[arg(long, help_heading = "Refine options")]
+    pub prune_unseen_after: Option<u32>,
+
+    /// Start from the density control settings of a preset. Settings that are also passed
+    /// explicitly override the preset.
+    #[arg(long, help_heading = "Refine options", value_enum)]
+    pub preset: Option<TrainPreset>,
+
     /// Weight of l1 loss on alpha if input view has transparency.
     #[config(default = 0.1)]
     #[arg(long, help_heading = "Refine options", default_value = "0.1")]
     match_alpha_weight: f32,
+
+    /// Train on random square crops of at most this many pixels on a side, instead of whole
+    /// images. Needs much less memory for very large images, eg. 8K photos.
+    #[arg(long, help_heading = "Training options")]
+    pub crop_size: Option<u32>,
+
+    /// Model motion blur of up to this many pixels. Renders are averaged over camera poses
+    /// between a start and end pose learned per view, so blurry frames don't blur the splats.
+    /// Each step renders 5 times as much.
+    #[arg(long, help_heading = "Training options")]
+    pub motion_blur_px: Option<f32>,
+
+    /// Learning rate for the start and end poses of the views, see `motion_blur_px`.
+    #[config(default = 0.05)]
+    #[arg(long, help_heading = "Training options", default_value = "0.05")]
+    lr_motion_blur: f32,
+
+    /// Model defocus blur of up to this many pixels, for captures with a shallow depth of
+    /// field. Renders are blurred by a gaussian learned per view before comparing them, so out
+    /// of focus views don't blur the splats.
+    #[arg(long, help_heading = "Training options")]
+    pub defocus_px: Option<f32>,
+
+    /// Learning rate for how out of focus each view is, see `defocus_px`.
+    #[config(default = 0.05)]
+    #[arg(long, help_heading = "Training options", default_value = "0.05")]
+    lr_defocus: f32,
+
+    /// Track when each splat was created, in how many steps it was visible, and its summed
+    /// position gradient. These are exported as extra ply properties.
+    #[config(default = false)]
+    #[arg(long, help_heading = "Training options")]
+    pub track_splats: bool,
+
+    /// Read back where the splats pruned by each refine were, into
+    /// [`RefineStats::pruned_means`]. This waits on the GPU during every refine, so it's only
+    /// turned on while something shows them.
+    #[config(default = false)]
+    #[arg(skip)]
+    pub record_pruned: bool,
+
+    /// Calculate the gradient of the loss for each pixel of the render, into
+    /// [`TrainStepStats::pixel_grads`]. This costs an extra image sized gradient every step, so
+    /// it's only turned on while something shows them.
+    #[config(default = false)]
+    #[arg(skip)]
+    pub record_pixel_grads: bool,
+}
+
+/// The part of a [`TrainConfig`] that can be changed while training, see
+/// [`SplatTrainer::update_config`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LiveTrainConfig {
+    pub lr_mean: f64,
+    pub lr_mean_end: f64,
+    pub lr_coeffs_dc: f64,
+    pub lr_opac: f64,
+    pub lr_scale: f64,
+    pub lr_rotation: f64,
+    pub ssim_weight: f32,
+    pub refine_every: u32,
+    pub max_splats: Option<u32>,
+    pub record_pruned: bool,
+    pub record_pixel_grads: bool,
+}
+
+impl TrainConfig {
+    pub fn live(&self) -> LiveTrainConfig {
+        LiveTrainConfig {
+            lr_mean: self.lr_mean,
+            lr_mean_end: self.lr_mean_end,
+            lr_coeffs_dc: self.lr_coeffs_dc,
+            lr_opac: self.lr_opac,
+            lr_scale: self.lr_scale,
+            lr_rotation: self.lr_rotation,
+            ssim_weight: self.ssim_weight,
+            refine_every: self.refine_every,
+            max_splats: self.max_splats,
+            record_pruned: self.record_pruned,
+            record_pixel_grads: self.record_pixel_grads,
+        }
+    }
+
+    /// Change the density control settings to those of `preset`.
+    pub fn apply_preset(self, preset: TrainPreset) -> Self {
+        let defaults = Self::new();
+        match preset {
+            TrainPreset::Quality => Self {
+                densify_grad_thresh: 0.0004,
+                opac_refine_subtract: defaults.opac_refine_subtract,
+                refine_stop_iter: 20000,
+                max_splats: None,
+                ..self
+            },
+            TrainPreset::Balanced => Self {
+                densify_grad_thresh: defaults.densify_grad_thresh,
+                opac_refine_subtract: defaults.opac_refine_subtract,
+                refine_stop_iter: defaults.refine_stop_iter,
+                max_splats: defaults.max_splats,
+                ..self
+            },
+            TrainPreset::Compact => Self {
+                densify_grad_thresh: 0.0012,
+                opac_refine_subtract: 0.004,
+                refine_stop_iter: 12000,
+                max_splats: Some(1_000_000),
+                ..self
+            },
+        }
+    }
+
+    /// The preset the density control settings match, if any.
+    pub fn matching_preset(&self) -> Option<TrainPreset> {
+        TrainPreset::ALL.into_iter().find(|&preset| {
+            let other = self.clone().apply_preset(preset);
+            self.densify_grad_thresh.to_bits() == other.densify_grad_thresh.to_bits()
+                && self.opac_refine_subtract.to_bits() == other.opac_refine_subtract.to_bits()
+                && self.refine_stop_iter == other.refine_stop_iter
+                && self.max_splats == other.max_splats
+        })
+    }
+
+    /// Apply the requested [`Self::preset`] to the density control settings that are still at
+    /// their defaults, so settings that were set explicitly win.
+    pub fn with_requested_preset(self) -> Self {
+        let Some(preset) = self.preset else {
+            return self;
+        };
+        let defaults = Self::new();
+        let values = defaults.clone().apply_preset(preset);
+        Self {
+            densify_grad_thresh: if self.densify_grad_thresh.to_bits()
+                == defaults.densify_grad_thresh.to_bits()
+            {
+                values.densify_grad_thresh
+            } else {
+                self.densify_grad_thresh
+            },
+            opac_refine_subtract: if self.opac_refine_subtract.to_bits()
+                == defaults.opac_refine_subtract.to_bits()
+            {
+                values.opac_refine_subtract
+            } else {
+                self.opac_refine_subtract
+            },
+            refine_stop_iter: if self.refine_stop_iter == defaults.refine_stop_iter {
+                values.refine_stop_iter
+            } else {
+                self.refine_stop_iter
+            },
+            max_splats: self.max_splats.or(values.max_splats),
+            preset: None,
+            ..self
+        }
+    }
+
+    /// Whether the splats need [`SplatTracking`] while training.
+    pub fn tracks_splats(&self) -> bool {
+        self.track_splats || self.prune_unseen_after.is_some()
+    }
+
+    pub fn with_live(self, live: LiveTrainConfig) -> Self {
+        Self {
+            lr_mean: live.lr_mean,
+            lr_mean_end: live.lr_mean_end,
+            lr_coeffs_dc: live.lr_coeffs_dc,
+            lr_opac: live.lr_opac,
+            lr_scale: live.lr_scale,
+            lr_rotation: live.lr_rotation,
+            ssim_weight: live.ssim_weight,
+            refine_every: live.refine_every.max(1),
+            max_splats: live.max_splats,
+            record_pruned: live.record_pruned,
+            record_pixel_grads: live.record_pixel_grads,
+            ..self
+        }
+    }
 }
 
 pub type TrainBack = Autodiff<Wgpu>;
@@ -149,11 +377,54 @@ pub struct RefineStats {
     pub num_cloned: u32,
     pub num_transparent_pruned: u32,
     pub num_scale_pruned: u32,
+    /// Splats pruned for not being visible since they were created, see
+    /// [`TrainConfig::prune_unseen_after`].
+    pub num_unseen_pruned: u32,
+    /// Whether the opacity of all splats was reset.
+    pub opacity_reset: bool,
+    /// Number of splats after refining. The splats added by splitting and cloning are the last
+    /// [`RefineStats::num_added`] of them.
+    pub num_splats: u32,
+    /// Positions of the splats pruned for being transparent, too big or unseen, if any were and
+    /// [`TrainConfig::record_pruned`] is set.
+    pub pruned_means: Option<Tensor<<TrainBack as AutodiffBackend>::InnerBackend, 2>>,
+}
+
+impl RefineStats {
+    /// Number of splats added by this refine, each split splat being replaced by two.
+    pub fn num_added(&self) -> u32 {
+        2 * self.num_split + self.num_cloned
+    }
+}
+
+impl std::fmt::Display for RefineStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} split, {} cloned, {} pruned (transparent), {} pruned (too big), {} splats",
+            self.num_split,
+            self.num_cloned,
+            self.num_transparent_pruned,
+            self.num_scale_pruned,
+            self.num_splats,
+        )?;
+        if self.num_unseen_pruned > 0 {
+            write!(f, ", {} pruned (unseen)", self.num_unseen_pruned)?;
+        }
+        if self.opacity_reset {
+            write!(f, ", opacity reset")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
 pub struct TrainStepStats<B: Backend> {
     pub pred_image: Tensor<B, 3>,
+    /// Gradient of the loss with respect to each channel of each pixel of `pred_image`, eg. to
+    /// show where training pushes the render the hardest. Only calculated when
+    /// [`TrainConfig::record_pixel_grads`] is set.
+    pub pixel_grads: Option<Tensor<B, 3>>,
 
     pub gt_views: SceneView,
 
@@ -175,8 +446,16 @@ pub struct SplatTrainer {
     sched_mean: ExponentialLrScheduler,
     ssim: Ssim<TrainBack>,
 
+    // Number of steps taken with the current learning rate schedule.
+    lr_steps: u32,
     optim: Option<OptimizerType>,
     refine_record: Option<RefineRecord<<TrainBack as AutodiffBackend>::InnerBackend>>,
+
+    motion_blur: Option<MotionBlur>,
+    defocus: Option<Defocus>,
+
+    aux_losses: Vec<Box<dyn AuxLoss>>,
+    callbacks: Vec<Box<dyn TrainCallback>>,
 }
 
 fn quaternion_vec_multiply<B: Backend>(
@@ -247,12 +526,54 @@ impl SplatTrainer {
         Self {
             config: config.clone(),
             sched_mean: lr_mean.init().expect("Lr schedule must be valid."),
+            lr_steps: 0,
             optim: None,
             refine_record: None,
             ssim,
+            motion_blur: config
+                .motion_blur_px
+                .map(|px| MotionBlur::new(px, config.lr_motion_blur)),
+            defocus: config
+                .defocus_px
+                .map(|px| Defocus::new(px, config.lr_defocus)),
+            aux_losses: vec![],
+            callbacks: vec![],
         }
     }
 
+    /// Change part of the config while training, eg. to lower the learning rates of a run that
+    /// misbehaves.
+    pub fn update_config(&mut self, live: LiveTrainConfig) {
+        let config = self.config.clone().with_live(live);
+
+        if config.lr_mean != self.config.lr_mean || config.lr_mean_end != self.config.lr_mean_end {
+            // Continue the new schedule from where the old one was.
+            let decay = (config.lr_mean_end / config.lr_mean).powf(1.0 / config.total_steps as f64);
+            let lr_mean = config.lr_mean * decay.powi(self.lr_steps as i32);
+            self.sched_mean = ExponentialLrSchedulerConfig::new(lr_mean, decay)
+                .init()
+                .expect("Lr schedule must be valid.");
+        }
+
+        self.config = config;
+    }
+
+    /// Add an extra loss term to every following step.
+    pub fn add_aux_loss(&mut self, loss: impl AuxLoss + 'static) {
+        self.aux_losses.push(Box::new(loss));
+    }
+
+    /// Register a callback, called during every following step.
+    pub fn add_callback(&mut self, callback: impl TrainCallback + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Register all losses and callbacks of `hooks`.
+    pub fn add_hooks(&mut self, hooks: TrainHooks) {
+        self.aux_losses.extend(hooks.aux_losses);
+        self.callbacks.extend(hooks.callbacks);
+    }
+
     pub fn step(
         &mut self,
         scene_extent: f32,
@@ -261,16 +582,24 @@ impl SplatTrainer {
         splats: Splats<TrainBack>,
     ) -> (Splats<TrainBack>, TrainStepStats<TrainBack>) {
         let mut splats = splats;
+        if self.config.tracks_splats() && splats.tracking.is_none() {
+            splats.tracking = Some(SplatTracking::new(
+                splats.num_splats(),
+                iter,
+                &splats.device(),
+            ));
+        }
 
         let [img_h, img_w, _] = batch.gt_image.dims();
 
         let camera = &batch.gt_view.camera;
+        let img_size = glam::uvec2(img_w as u32, img_h as u32);
 
-        let (pred_image, aux, refine_weight_holder) = {
+        let render = |means: Tensor<TrainBack, 2>| {
             let diff_out = <TrainBack as SplatForwardDiff<TrainBack>>::render_splats(
                 camera,
-                glam::uvec2(img_w as u32, img_h as u32),
-                splats.means.val().into_primitive().tensor(),
+                img_size,
+                means.into_primitive().tensor(),
                 splats.log_scales.val().into_primitive().tensor(),
                 splats.rotation.val().into_primitive().tensor(),
                 splats.sh_coeffs.val().into_primitive().tensor(),
@@ -281,16 +610,66 @@ impl SplatTrainer {
             (img, wrapped_aux, diff_out.refine_weight_holder)
         };
 
+        let mut blur_poses = None;
+        let (mut pred_image, aux, refine_weight_holder) =
+            if let Some(motion_blur) = &mut self.motion_blur {
+                let poses = motion_blur.poses(&batch.gt_view.path, &splats.device());
+                let means = motion_blur.moved_means(
+                    poses.clone(),
+                    camera,
+                    img_size,
+                    scene_extent,
+                    splats.means.val(),
+                );
+                blur_poses = Some(poses);
+
+                // Refining only looks at the render from the middle of the motion.
+                let middle = MotionBlur::middle();
+                let (image, aux, refine_weight_holder) = render(means[middle].clone());
+                let renders = means
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, means)| {
+                        if i == middle {
+                            image.clone()
+                        } else {
+                            render(means).0
+                        }
+                    })
+                    .collect();
+                (MotionBlur::blend(renders), aux, refine_weight_holder)
+            } else {
+                render(splats.means.val())
+            };
+
+        let mut defocus_logit = None;
+        if let Some(defocus) = &mut self.defocus {
+            let logit = defocus.logit(&batch.gt_view.path, &splats.device());
+            pred_image = defocus.apply(logit.clone(), pred_image);
+            defocus_logit = Some(logit);
+        }
+
+        // Zeros added to the render, to read the gradient of the loss for each pixel from.
+        let pixel_grad_holder = self.config.record_pixel_grads.then(|| {
+            Tensor::<TrainBack, 3>::zeros(pred_image.shape(), &splats.device()).require_grad()
+        });
+        let pred_image = match &pixel_grad_holder {
+            Some(holder) => pred_image + holder.clone(),
+            None => pred_image,
+        };
+
         let _span = trace_span!("Calculate losses", sync_burn = true).entered();
 
-        let pred_rgb = pred_image.clone().slice([0..img_h, 0..img_w, 0..3]);
-        let gt_rgb = batch.gt_image.clone().slice([0..img_h, 0..img_w, 0..3]);
+        let mut pred_rgb = pred_image.clone().slice([0..img_h, 0..img_w, 0..3]);
+        let mut gt_rgb = batch.gt_image.clone().slice([0..img_h, 0..img_w, 0..3]);
+        if self.config.loss_space == LossSpace::Linear {
+            pred_rgb = srgb_to_linear(pred_rgb);
+            gt_rgb = srgb_to_linear(gt_rgb);
+        }
 
-        let l1_rgb = (pred_rgb.clone() - gt_rgb).abs();
+        let l1_rgb = (pred_rgb.clone() - gt_rgb.clone()).abs();
 
         let total_err = if self.config.ssim_weight > 0.0 {
-            let gt_rgb = batch.gt_image.clone().slice([0..img_h, 0..img_w, 0..3]);
-
             let ssim_err = -self.ssim.ssim(pred_rgb, gt_rgb);
             l1_rgb * (1.0 - self.config.ssim_weight) + ssim_err * self.config.ssim_weight
         } else {
@@ -320,8 +699,43 @@ impl SplatTrainer {
             loss = loss + opac_loss * self.config.opac_loss_weight;
         }
 
+        for aux_loss in &mut self.aux_losses {
+            loss = loss + aux_loss.loss(iter, &splats, &batch, pred_image.clone());
+        }
+
         let mut grads = trace_span!("Backward pass", sync_burn = true).in_scope(|| loss.backward());
+        let pixel_grads = pixel_grad_holder.map(|holder| {
+            Tensor::from_inner(
+                holder
+                    .grad_remove(&mut grads)
+                    .expect("Pixel gradients need to be calculated."),
+            )
+        });
+
+        if let Some(tracking) = splats.tracking.take() {
+            let seen = aux.radii.clone().inner().greater_elem(0.0).float();
+            let grad_norm = splats
+                .means
+                .val()
+                .grad(&grads)
+                .map(|grad| grad.powf_scalar(2.0).sum_dim(1).squeeze(1).sqrt())
+                .unwrap_or_else(|| seen.zeros_like());
+            splats.tracking =
+                Some(tracking.record_step(Tensor::from_inner(seen), Tensor::from_inner(grad_norm)));
+        }
+
+        for callback in &mut self.callbacks {
+            callback.on_gradients(iter, &splats, &grads);
+        }
+
+        if let (Some(motion_blur), Some(poses)) = (&mut self.motion_blur, &blur_poses) {
+            motion_blur.step(&batch.gt_view.path, poses, &grads);
+        }
+        if let (Some(defocus), Some(logit)) = (&mut self.defocus, &defocus_logit) {
+            defocus.step(&batch.gt_view.path, logit, &grads);
+        }
 
+        self.lr_steps += 1;
         let (lr_mean, lr_rotation, lr_scale, lr_coeffs, lr_opac) = (
             self.sched_mean.step() * scene_extent as f64,
             self.config.lr_rotation,
@@ -418,6 +832,7 @@ impl SplatTrainer {
 
         let stats = TrainStepStats {
             pred_image,
+            pixel_grads,
             gt_views: batch.gt_view,
             num_visible,
             num_intersections,
@@ -438,19 +853,29 @@ impl SplatTrainer {
         splats: Splats<TrainBack>,
         scene_extent: f32,
     ) -> (Splats<TrainBack>, Option<RefineStats>) {
-        if iter > 0 && iter % self.config.refine_every == 0 {
-            // Normalize rotations to prevent them from slowly drifting towards 0. When they
-            // get to 0 they are effectively killed off.
-            // This is slightly wrong wrt to adam gradients, but that's fine.
-            let splats = splats.with_normed_rotations();
-
-            // If not refining, update splat to step with gradients applied.
-            if iter >= self.config.refine_start_iter && iter < self.config.refine_stop_iter {
-                let (splats, refine) = self.refine_splats(iter, splats, scene_extent).await;
-                (splats, Some(refine))
-            } else {
-                (splats, None)
-            }
+        let is_refine_step = iter > 0 && iter % self.config.refine_every == 0;
+        let mut refine = is_refine_step
+            && iter >= self.config.refine_start_iter
+            && iter < self.config.refine_stop_iter;
+        for callback in &mut self.callbacks {
+            refine = callback.should_refine(iter, refine);
+        }
+        // Can't refine before any stats are gathered.
+        let refine = refine && self.refine_record.is_some();
+
+        if !is_refine_step && !refine {
+            return (splats, None);
+        }
+
+        // Normalize rotations to prevent them from slowly drifting towards 0. When they
+        // get to 0 they are effectively killed off.
+        // This is slightly wrong wrt to adam gradients, but that's fine.
+        let splats = splats.with_normed_rotations();
+
+        // If not refining, update splat to step with gradients applied.
+        if refine {
+            let (splats, refine) = self.refine_splats(iter, splats, scene_extent).await;
+            (splats, Some(refine))
         } else {
             (splats, None)
         }
@@ -480,7 +905,15 @@ impl SplatTrainer {
 
         let device = splats.means.device();
 
-        let is_grad_high = avg_grad.greater_equal_elem(self.config.densify_grad_thresh);
+        // Once there are enough splats, only prune them.
+        let can_grow = self
+            .config
+            .max_splats
+            .is_none_or(|max| splats.num_splats() < max);
+        let grow_threshold = |threshold: f32| if can_grow { threshold } else { f32::INFINITY };
+
+        let is_grad_high =
+            avg_grad.greater_equal_elem(grow_threshold(self.config.densify_grad_thresh));
         let split_clone_size_mask = splats
             .scales()
             .inner()
@@ -541,7 +974,7 @@ impl SplatTrainer {
 
         let radii_grow = refiner
             .max_radii
-            .greater_elem(self.config.densify_radius_threshold);
+            .greater_elem(grow_threshold(self.config.densify_radius_threshold));
 
         let split_mask = Tensor::stack::<2>(vec![split_mask, radii_grow], 1)
             .any_dim(1)
@@ -599,6 +1032,11 @@ impl SplatTrainer {
             .val()
             .inner()
             .lower_elem(inverse_sigmoid(MIN_OPACITY));
+        let record_pruned = self.config.record_pruned;
+        let mut pruned_means = vec![];
+        if record_pruned {
+            pruned_means.extend(masked_means(&splats, alpha_mask.clone()).await);
+        }
         let (splats, alpha_pruned) = prune_points(splats, &mut record, alpha_mask).await;
 
         // Delete Gaussians with too large of a radius in world-units.
@@ -609,8 +1047,37 @@ impl SplatTrainer {
             .greater_elem((self.config.cull_scale3d_percentage_threshold * scene_extent).ln());
 
         let scale_mask = Tensor::any_dim(scale_big, 1).squeeze(1);
+        if record_pruned {
+            pruned_means.extend(masked_means(&splats, scale_mask.clone()).await);
+        }
         let (mut splats, scale_pruned) = prune_points(splats, &mut record, scale_mask).await;
 
+        // Remove splats no view has seen for a while.
+        let unseen_mask = match (self.config.prune_unseen_after, splats.tracking.as_ref()) {
+            (Some(steps), Some(tracking)) => Some(
+                tracking
+                    .times_seen
+                    .clone()
+                    .inner()
+                    .equal_elem(0.0)
+                    .bool_and(
+                        tracking
+                            .created_step
+                            .clone()
+                            .inner()
+                            .lower_equal_elem(iter.saturating_sub(steps) as f32),
+                    ),
+            ),
+            _ => None,
+        };
+        let mut unseen_pruned = 0;
+        if let Some(unseen_mask) = unseen_mask {
+            if record_pruned {
+                pruned_means.extend(masked_means(&splats, unseen_mask.clone()).await);
+            }
+            (splats, unseen_pruned) = prune_points(splats, &mut record, unseen_mask).await;
+        }
+
         if !append_means.is_empty() {
             let append_means = Tensor::cat(append_means, 0);
             let append_rots = Tensor::cat(append_rots, 0);
@@ -621,6 +1088,7 @@ impl SplatTrainer {
             splats = concat_splats(
                 splats,
                 &mut record,
+                iter,
                 append_means,
                 append_rots,
                 append_scales,
@@ -630,7 +1098,8 @@ impl SplatTrainer {
         }
 
         let refine_step = iter / self.config.refine_every;
-        if refine_step % self.config.reset_alpha_every_refine == 0 {
+        let opacity_reset = refine_step % self.config.reset_alpha_every_refine == 0;
+        if opacity_reset {
             splats.raw_opacity = splats
                 .raw_opacity
                 .map(|op| op.clamp_max(inverse_sigmoid(0.01)));
@@ -663,6 +1132,10 @@ impl SplatTrainer {
             num_cloned: clone_count,
             num_transparent_pruned: alpha_pruned,
             num_scale_pruned: scale_pruned,
+            num_unseen_pruned: unseen_pruned,
+            opacity_reset,
+            num_splats: splats.num_splats(),
+            pruned_means: (!pruned_means.is_empty()).then(|| Tensor::cat(pruned_means, 0)),
         };
 
         (splats, stats)
@@ -706,6 +1179,7 @@ fn map_splats_and_opt<B: AutodiffBackend>(
         raw_opacity: splats
             .raw_opacity
             .map(|m| Tensor::from_inner(map_opac(m.inner())).require_grad()),
+        tracking: splats.tracking,
     }
 }
 
@@ -728,6 +1202,18 @@ fn map_opt<B: AutodiffBackend, const D: usize>(
     record.insert(param_id, AdaptorRecord::from_state(state));
 }
 
+// The means of the splats in `mask`, or None if it's empty.
+async fn masked_means<B: AutodiffBackend>(
+    splats: &Splats<B>,
+    mask: Tensor<B::InnerBackend, 1, Bool>,
+) -> Option<Tensor<B::InnerBackend, 2>> {
+    let inds = mask.argwhere_async().await;
+    if inds.dims()[0] == 0 {
+        return None;
+    }
+    Some(splats.means.val().inner().select(0, inds.squeeze(1)))
+}
+
 // Prunes points based on the given mask.
 //
 // Args:
@@ -762,6 +1248,9 @@ pub async fn prune_points<B: AutodiffBackend>(
 
     if new_points < start_splats {
         let valid_inds = valid_inds.squeeze(1);
+        splats.tracking = splats
+            .tracking
+            .map(|tracking| tracking.select(Tensor::from_inner(valid_inds.clone())));
         splats = map_splats_and_opt(
             splats,
             record,
@@ -781,9 +1270,11 @@ pub async fn prune_points<B: AutodiffBackend>(
     (splats, start_splats - new_points)
 }
 
+/// Append splats, counted as created at `step` if the splats are tracked.
 pub fn concat_splats<B: AutodiffBackend>(
-    splats: Splats<B>,
+    mut splats: Splats<B>,
     record: &mut HashMap<ParamId, AdaptorRecord<AdamScaled, B>>,
+    step: u32,
     means: Tensor<B::InnerBackend, 2>,
     rotations: Tensor<B::InnerBackend, 2>,
     log_scales: Tensor<B::InnerBackend, 2>,
@@ -795,6 +1286,9 @@ pub fn concat_splats<B: AutodiffBackend>(
     let cur_count = splats.means.dims()[0];
     let append_count = means.dims()[0];
     let sh_dim = splats.sh_coeffs.dims()[1];
+    splats.tracking = splats
+        .tracking
+        .map(|tracking| tracking.appended(append_count as u32, step));
 
     map_splats_and_opt(
         splats,
@@ -826,13 +1320,26 @@ pub fn concat_splats<B: AutodiffBackend>(
 
 #[cfg(test)]
 mod tests {
+    use brush_render::gaussian_splats::SplatTracking;
     use burn::{
         backend::{Wgpu, wgpu::WgpuDevice},
         tensor::Tensor,
     };
     use glam::Quat;
 
-    use super::quaternion_vec_multiply;
+    use burn::lr_scheduler::LrScheduler;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        callback::{AuxLoss, TrainCallback, TrainGradients, TrainHooks},
+        image::view_to_sample,
+        scene::{SceneView, ViewImageType},
+    };
+    use brush_render::{camera::Camera, gaussian_splats::Splats};
+
+    use super::{
+        SceneBatch, SplatTrainer, TrainBack, TrainConfig, TrainPreset, quaternion_vec_multiply,
+    };
 
     #[test]
     fn test_quat_multiply() {
@@ -849,4 +1356,151 @@ mod tests {
         let result = glam::vec3(result[0], result[1], result[2]);
         assert!((result_ref - result).length() < 1e-7);
     }
+
+    #[test]
+    fn requested_preset_keeps_explicit_settings() {
+        let config = TrainConfig::new()
+            .with_preset(Some(TrainPreset::Compact))
+            .with_refine_stop_iter(9000)
+            .with_requested_preset();
+        assert_eq!(config.refine_stop_iter, 9000);
+        assert_eq!(config.max_splats, Some(1_000_000));
+        assert_eq!(config.preset, None);
+
+        // The explicit setting doesn't match the preset anymore.
+        assert_eq!(config.matching_preset(), None);
+        let compact = config.apply_preset(TrainPreset::Compact);
+        assert_eq!(compact.matching_preset(), Some(TrainPreset::Compact));
+        assert_eq!(
+            TrainConfig::new().matching_preset(),
+            Some(TrainPreset::Balanced)
+        );
+    }
+
+    #[test]
+    fn tracking_follows_pruned_and_added_splats() {
+        let device = WgpuDevice::DefaultDevice;
+        let seen = Tensor::<Wgpu, 1>::from_floats([1.0, 0.0, 1.0], &device);
+        let grad = Tensor::<Wgpu, 1>::from_floats([0.5, 0.0, 2.0], &device);
+        let tracking = SplatTracking::<Wgpu>::new(3, 10, &device)
+            .record_step(seen.clone(), grad.clone())
+            .record_step(seen, grad)
+            .select(Tensor::from_ints([0, 2], &device))
+            .appended(1, 200);
+
+        let read = |t: Tensor<Wgpu, 1>| t.into_data().to_vec::<f32>().expect("Wrong type");
+        assert_eq!(read(tracking.created_step), [10.0, 10.0, 200.0]);
+        assert_eq!(read(tracking.times_seen), [2.0, 2.0, 0.0]);
+        assert_eq!(read(tracking.grad_accum), [1.0, 4.0, 0.0]);
+    }
+
+    #[test]
+    fn lr_schedule_continues_over_live_changes() {
+        let config = TrainConfig::new().with_total_steps(1000);
+        let mut trainer = SplatTrainer::new(&config, &WgpuDevice::DefaultDevice);
+        let run_steps = |trainer: &mut SplatTrainer, steps: u32| {
+            for _ in 0..steps {
+                trainer.lr_steps += 1;
+                trainer.sched_mean.step();
+            }
+        };
+
+        run_steps(&mut trainer, 100);
+        let mut live = config.live();
+        live.lr_mean *= 0.5;
+        trainer.update_config(live);
+        run_steps(&mut trainer, 100);
+        live.lr_mean_end *= 0.5;
+        trainer.update_config(live);
+
+        // The second change continues after all 200 steps, not just those since the first one.
+        let decay = (live.lr_mean_end / live.lr_mean).powf(1.0 / 1000.0);
+        let expected = live.lr_mean * decay.powi(200);
+        let lr = trainer.sched_mean.step();
+        // Allow for a step of decay, which is well under 1%.
+        assert!((lr - expected).abs() < expected * 0.01);
+    }
+
+    // Records the gradient of the opacities.
+    struct OpacityGrads(Arc<Mutex<Vec<f32>>>);
+
+    impl TrainCallback for OpacityGrads {
+        fn on_gradients(&mut self, _iter: u32, splats: &Splats<TrainBack>, grads: &TrainGradients) {
+            let grad = splats
+                .raw_opacity
+                .val()
+                .grad(grads)
+                .expect("Opacity has a gradient");
+            *self.0.lock().expect("Not poisoned") = grad.into_data().to_vec().expect("Wrong type");
+        }
+    }
+
+    // Pushes all splats to be more transparent.
+    struct Transparency;
+
+    impl AuxLoss for Transparency {
+        fn name(&self) -> &str {
+            "transparency"
+        }
+
+        fn loss(
+            &mut self,
+            _iter: u32,
+            splats: &Splats<TrainBack>,
+            _batch: &SceneBatch<TrainBack>,
+            _pred_image: Tensor<TrainBack, 3>,
+        ) -> Tensor<TrainBack, 1> {
+            splats.opacity().sum()
+        }
+    }
+
+    fn opacity_grads(hooks: TrainHooks) -> Vec<f32> {
+        let device = WgpuDevice::DefaultDevice;
+        let means = [
+            glam::vec3(0.0, 0.0, 0.0),
+            glam::vec3(0.2, 0.1, 0.3),
+            glam::vec3(-0.2, 0.1, -0.1),
+            glam::vec3(0.1, -0.2, 0.2),
+        ];
+        let splats = Splats::<TrainBack>::from_raw(&means, None, None, None, None, &device);
+        let camera = Camera::new(
+            glam::vec3(0.0, 0.0, -3.0),
+            Quat::IDENTITY,
+            0.8,
+            0.8,
+            glam::vec2(0.5, 0.5),
+        );
+        let image = image::RgbImage::from_pixel(32, 32, image::Rgb([128, 64, 32]));
+        let gt_view = SceneView {
+            path: "view.png".to_owned(),
+            camera,
+            image: Arc::new(image.into()),
+            img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![]),
+        };
+        let batch = SceneBatch {
+            gt_image: view_to_sample(&gt_view, &device),
+            gt_view,
+        };
+
+        let grads = Arc::new(Mutex::new(vec![]));
+        let mut trainer = SplatTrainer::new(&TrainConfig::new(), &device);
+        trainer.add_hooks(hooks.with_callback(OpacityGrads(grads.clone())));
+        let _ = trainer.step(1.0, 0, batch, splats);
+        grads.lock().expect("Not poisoned").clone()
+    }
+
+    #[test]
+    fn aux_loss_changes_the_gradient() {
+        let plain = opacity_grads(TrainHooks::default());
+        let with_aux = opacity_grads(TrainHooks::default().with_aux_loss(Transparency));
+        assert_eq!(plain.len(), 4);
+        assert_eq!(with_aux.len(), 4);
+        // The render doesn't change, so the aux loss adds its own gradient, which is positive
+        // for every splat.
+        for (plain, with_aux) in plain.iter().zip(&with_aux) {
+            assert!(with_aux - plain > 1e-3, "{plain} vs {with_aux}");
+        }
+    }
 }
@@ -0,0 +1,80 @@
+//! Small parameters learned for each view separately, like how blurry its image is. They're
+//! kept by the path of the view, and take a step every time their view is trained on.
+
+use burn::backend::wgpu::WgpuDevice;
+use burn::optim::SimpleOptimizer;
+use burn::tensor::{Tensor, backend::AutodiffBackend};
+use hashbrown::HashMap;
+
+use crate::adam_scaled::{AdamScaled, AdamScaledConfig, AdamState};
+use crate::train::TrainBack;
+
+type InnerBackend = <TrainBack as AutodiffBackend>::InnerBackend;
+
+pub(crate) struct ViewParams {
+    init: Vec<f32>,
+    lr: f32,
+    optim: AdamScaled,
+    values: HashMap<String, (Tensor<InnerBackend, 1>, Option<AdamState<InnerBackend, 1>>)>,
+}
+
+impl ViewParams {
+    /// Parameters of all views start out as `init`.
+    pub(crate) fn new(init: Vec<f32>, lr: f32) -> Self {
+        Self {
+            init,
+            lr,
+            optim: AdamScaledConfig::new().with_epsilon(1e-15).init_simple(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// The parameters of a view, tracked for gradients.
+    pub(crate) fn get(&mut self, view: &str, device: &WgpuDevice) -> Tensor<TrainBack, 1> {
+        if !self.values.contains_key(view) {
+            let init = Tensor::from_floats(self.init.as_slice(), device);
+            self.values.insert(view.to_owned(), (init, None));
+        }
+        Tensor::from_inner(self.values[view].0.clone()).require_grad()
+    }
+
+    /// Take an Adam step on the parameters of a view, as returned by [`Self::get`]. Each view is
+    /// only trained on now and then, so its gradients are noisy, and their size depends a lot on
+    /// the size of the images.
+    pub(crate) fn step(
+        &mut self,
+        view: &str,
+        params: &Tensor<TrainBack, 1>,
+        grads: &<TrainBack as AutodiffBackend>::Gradients,
+    ) {
+        let (Some(grad), Some((value, state))) = (params.grad(grads), self.values.get_mut(view))
+        else {
+            return;
+        };
+        let (stepped, stepped_state) =
+            self.optim
+                .step(self.lr.into(), value.clone(), grad, state.take());
+        *value = stepped;
+        *state = stepped_state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ViewParams;
+    use burn::backend::wgpu::WgpuDevice;
+
+    #[test]
+    fn single_param_converges() {
+        let device = WgpuDevice::DefaultDevice;
+        let mut params = ViewParams::new(vec![0.0], 0.05);
+        for _ in 0..500 {
+            let value = params.get("view", &device);
+            let loss = (value.clone() - 2.0).powf_scalar(2.0).sum();
+            params.step("view", &value, &loss.backward());
+        }
+        let value: f32 = params.get("view", &device).into_scalar();
+        // Normalizing the gradient by its own size would keep it jumping by the learning rate.
+        assert!((value - 2.0).abs() < 0.01, "{value}");
+    }
+}
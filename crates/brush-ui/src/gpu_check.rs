@@ -0,0 +1,100 @@
+//! Checks whether the GPU supports what Brush needs, to explain what is missing instead of failing
+//! to start.
+
+use wgpu::{AdapterInfo, Features, Limits};
+
+// The projection kernels bind this many storage buffers at once.
+const REQUIRED_STORAGE_BUFFERS: u32 = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct GpuReport {
+    /// The adapter that would be used, if any was found.
+    pub adapter: Option<AdapterInfo>,
+    pub features: Features,
+    pub limits: Limits,
+    /// Problems that prevent Brush from running.
+    pub errors: Vec<String>,
+    /// Missing features that disable or slow down some functionality.
+    pub warnings: Vec<String>,
+}
+
+impl GpuReport {
+    pub fn is_supported(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// A short human readable description of the adapter.
+    pub fn adapter_summary(&self) -> String {
+        self.adapter.as_ref().map_or_else(
+            || "No adapter found".to_owned(),
+            |info| {
+                let mut summary =
+                    format!("{} ({:?}, {:?})", info.name, info.backend, info.device_type);
+                if !info.driver.is_empty() {
+                    summary += &format!(", driver {} {}", info.driver, info.driver_info);
+                }
+                summary
+            },
+        )
+    }
+}
+
+/// Request an adapter the same way the app does, and check its features and limits.
+pub async fn check_gpu() -> GpuReport {
+    let mut report = GpuReport::default();
+
+    #[cfg(target_family = "wasm")]
+    if !wgpu::util::is_browser_webgpu_supported().await {
+        report.errors.push(
+            "This browser doesn't support WebGPU. Try a recent version of Chrome or Edge, or \
+             enable WebGPU in your browser's settings."
+                .to_owned(),
+        );
+        return report;
+    }
+
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        })
+        .await;
+
+    let Some(adapter) = adapter else {
+        report.errors.push(
+            "No GPU adapter is available. WebGPU might be disabled for this GPU or driver, or \
+             blocked by the browser."
+                .to_owned(),
+        );
+        return report;
+    };
+
+    report.adapter = Some(adapter.get_info());
+    report.features = adapter.features();
+    report.limits = adapter.limits();
+
+    if report.limits.max_storage_buffers_per_shader_stage < REQUIRED_STORAGE_BUFFERS {
+        report.errors.push(format!(
+            "The GPU supports {} storage buffers per shader stage, but at least {REQUIRED_STORAGE_BUFFERS} are needed.",
+            report.limits.max_storage_buffers_per_shader_stage
+        ));
+    }
+
+    if !report.features.contains(Features::SUBGROUP) {
+        report.warnings.push(
+            "The GPU doesn't support subgroup operations, training is unavailable but splats \
+             can still be viewed."
+                .to_owned(),
+        );
+    }
+
+    if !report.features.contains(Features::SHADER_F16) {
+        report
+            .warnings
+            .push("The GPU doesn't support f16 in shaders.".to_owned());
+    }
+
+    report
+}
@@ -6,6 +6,7 @@ use eframe::egui_wgpu::WgpuConfiguration;
 use wgpu::{Adapter, Features};
 
 pub mod burn_texture;
+pub mod gpu_check;
 
 pub fn create_egui_options() -> WgpuConfiguration {
     WgpuConfiguration {
@@ -0,0 +1,461 @@
+//! Embed Brush in other applications: load splats and datasets, train splats, render them and
+//! export them.
+//!
+//! This is the stable API of Brush. The other `brush-*` crates are the internals of the app,
+//! and change between versions as the app needs. The functions and types here only change with
+//! a new major version, and are converted to those of the internals when used, so none of the
+//! types of the internals (or of the libraries they use) show up in the API. Options of
+//! [`TrainOptions`] follow the command line flags of the same name.
+//!
+//! Everything runs on the GPU set up by [`init_device`], in a tokio runtime.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use brush_dataset::{splat_export, splat_import};
+use brush_process::process_loop::{ProcessArgs, ProcessMessage, start_process};
+use burn::tensor::backend::AutodiffBackend;
+use burn_wgpu::WgpuDevice;
+use tokio_stream::StreamExt;
+
+type Backend = <brush_train::train::TrainBack as AutodiffBackend>::InnerBackend;
+
+/// A GPU to run on, see [`init_device`].
+#[derive(Clone, Debug)]
+pub struct Device {
+    inner: WgpuDevice,
+}
+
+/// Gaussian splats on the GPU.
+#[derive(Clone)]
+pub struct Splats {
+    inner: brush_render::gaussian_splats::Splats<Backend>,
+}
+
+impl Splats {
+    pub fn num_splats(&self) -> u32 {
+        self.inner.num_splats()
+    }
+
+    /// Degree of the spherical harmonics of the splats, 0 for only base colors.
+    pub fn sh_degree(&self) -> u32 {
+        self.inner.sh_degree()
+    }
+
+    /// Serialize the splats to the contents of a file in `format`.
+    pub async fn export(&self, format: ExportFormat) -> anyhow::Result<Vec<u8>> {
+        splat_export::export_splats(self.inner.clone(), format.to_internal()).await
+    }
+}
+
+/// File formats splats can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ExportFormat {
+    /// Full precision ply, as used by the original 3DGS implementation.
+    Ply,
+    /// Quantized ply as used by `PlayCanvas` and `SuperSplat`, about 4x smaller.
+    CompressedPly,
+    /// The `.splat` format supported by many web viewers. Only stores base colors.
+    Splat,
+    /// Niantic's gzipped spz format, supports up to SH degree 3.
+    Spz,
+    /// The centers of the splats as a point cloud ply, with colors, normals, opacity and scale.
+    PointCloudPly,
+    /// The centers of the splats as a LAS point cloud, with colors, opacity and scale.
+    Las,
+}
+
+impl ExportFormat {
+    fn to_internal(self) -> splat_export::ExportFormat {
+        use splat_export::ExportFormat as Internal;
+        match self {
+            Self::Ply => Internal::Ply,
+            Self::CompressedPly => Internal::CompressedPly,
+            Self::Splat => Internal::Splat,
+            Self::Spz => Internal::Spz,
+            Self::PointCloudPly => Internal::PointCloudPly,
+            Self::Las => Internal::Las,
+        }
+    }
+
+    /// Extension of files in this format, without the leading dot, eg. `compressed.ply`.
+    pub fn extension(self) -> &'static str {
+        self.to_internal().extension()
+    }
+}
+
+/// A pinhole camera to render from, or that took a view of a dataset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Camera {
+    pub position: [f32; 3],
+    /// Rotation from the space of the camera to the space of the splats, as a quaternion
+    /// `[x, y, z, w]`. The camera looks along +z of its own space, with +y down.
+    pub rotation: [f32; 4],
+    /// Horizontal field of view, in radians.
+    pub fov_x: f64,
+    /// Vertical field of view, in radians.
+    pub fov_y: f64,
+    /// Principal point, as a fraction of the size of the image.
+    pub center: [f32; 2],
+}
+
+impl Camera {
+    /// A camera with its principal point in the middle of the image.
+    pub fn new(position: [f32; 3], rotation: [f32; 4], fov_x: f64, fov_y: f64) -> Self {
+        Self {
+            position,
+            rotation,
+            fov_x,
+            fov_y,
+            center: [0.5, 0.5],
+        }
+    }
+
+    fn from_internal(camera: &brush_render::camera::Camera) -> Self {
+        Self {
+            position: camera.position.to_array(),
+            rotation: camera.rotation.to_array(),
+            fov_x: camera.fov_x,
+            fov_y: camera.fov_y,
+            center: camera.center_uv.to_array(),
+        }
+    }
+
+    fn to_internal(self) -> brush_render::camera::Camera {
+        brush_render::camera::Camera::new(
+            glam::Vec3::from_array(self.position),
+            glam::Quat::from_array(self.rotation).normalize(),
+            self.fov_x,
+            self.fov_y,
+            glam::Vec2::from_array(self.center),
+        )
+    }
+}
+
+/// An 8 bit RGBA image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels row by row from the top, 4 bytes each.
+    pub rgba: Vec<u8>,
+}
+
+impl Image {
+    fn from_internal(image: &image::DynamicImage) -> Self {
+        let rgba = image.to_rgba8();
+        Self {
+            width: rgba.width(),
+            height: rgba.height(),
+            rgba: rgba.into_raw(),
+        }
+    }
+}
+
+/// Where to load splats or a dataset from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DataSource {
+    /// A ply file, a directory, or a zip, tar.gz or 7z archive.
+    Path(PathBuf),
+    /// A url to download, which can end with a `#sha256=<hex>` checksum the data has to match.
+    Url(String),
+}
+
+impl DataSource {
+    fn into_internal(self) -> brush_process::data_source::DataSource {
+        use brush_process::data_source::DataSource as Internal;
+        match self {
+            Self::Path(path) => Internal::Path(path.to_string_lossy().into_owned()),
+            Self::Url(url) => Internal::Url(url),
+        }
+    }
+}
+
+/// Options to train with. The defaults are those of the app and command line.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct TrainOptions {
+    /// Total number of steps to train for.
+    pub total_steps: u32,
+    /// Stop adding splats once there are this many.
+    pub max_splats: Option<u32>,
+    /// SH degree of the splats, 0 for only base colors.
+    pub sh_degree: u32,
+    /// Max resolution of the images to train on, larger images are downscaled.
+    pub max_resolution: u32,
+    /// Max number of frames of the dataset to load.
+    pub max_frames: Option<usize>,
+    /// Evaluate on every nth view of the dataset, instead of training on it.
+    pub eval_split_every: Option<usize>,
+    /// Evaluate every this many steps.
+    pub eval_every: u32,
+    /// Random seed.
+    pub seed: u64,
+}
+
+impl Default for TrainOptions {
+    fn default() -> Self {
+        let args = ProcessArgs::default();
+        Self {
+            total_steps: args.train_config.total_steps,
+            max_splats: args.train_config.live().max_splats,
+            sh_degree: args.model_config.sh_degree,
+            max_resolution: args.load_config.max_resolution,
+            max_frames: args.load_config.max_frames,
+            eval_split_every: args.load_config.eval_split_every,
+            eval_every: args.process_config.eval_every,
+            seed: args.process_config.seed,
+        }
+    }
+}
+
+impl TrainOptions {
+    fn to_internal(&self) -> ProcessArgs {
+        let mut args = ProcessArgs::default();
+        args.train_config.total_steps = self.total_steps;
+        let mut live = args.train_config.live();
+        live.max_splats = self.max_splats;
+        args.train_config = args.train_config.with_live(live);
+        args.model_config.sh_degree = self.sh_degree;
+        args.load_config.max_resolution = self.max_resolution;
+        args.load_config.max_frames = self.max_frames;
+        args.load_config.eval_split_every = self.eval_split_every;
+        args.process_config.eval_every = self.eval_every;
+        args.process_config.seed = self.seed;
+        args
+    }
+}
+
+/// A view of a dataset: an image, and the camera it was taken with.
+pub struct View<'a> {
+    inner: &'a brush_train::scene::SceneView,
+}
+
+impl View<'_> {
+    /// Path of the image in the dataset.
+    pub fn path(&self) -> &str {
+        &self.inner.path
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera::from_internal(&self.inner.camera)
+    }
+
+    /// The image of the view, converted to 8 bit RGBA.
+    pub fn image(&self) -> Image {
+        Image::from_internal(&self.inner.image)
+    }
+}
+
+/// The views of a dataset to train on, and those to evaluate on.
+#[derive(Clone)]
+pub struct Dataset {
+    inner: brush_dataset::Dataset,
+}
+
+impl Dataset {
+    fn views(scene: &brush_train::scene::Scene) -> impl Iterator<Item = View<'_>> {
+        scene.views.iter().map(|inner| View { inner })
+    }
+
+    /// The views to train on.
+    pub fn train_views(&self) -> impl Iterator<Item = View<'_>> {
+        Self::views(&self.inner.train)
+    }
+
+    /// The views held out to evaluate on, if any.
+    pub fn eval_views(&self) -> impl Iterator<Item = View<'_>> {
+        self.inner.eval.iter().flat_map(Self::views)
+    }
+
+    /// Hash of the views and their cameras, to tell whether two runs trained on the same data.
+    pub fn content_hash(&self) -> String {
+        self.inner.content_hash()
+    }
+}
+
+/// Comparison of two snapshots of splats, see [`diff`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct SnapshotDiff {
+    pub splats_before: u32,
+    pub splats_after: u32,
+    /// Summed opacity of the splats.
+    pub mass_before: f32,
+    pub mass_after: f32,
+    /// Part of the mass that is in other cells after than before, from 0 (spread the same way) to
+    /// 1 (nothing in common).
+    pub moved_mass: f32,
+    /// How far the center of mass moved.
+    pub center_shift: f32,
+    /// Corners of the grid, around most splats of both snapshots.
+    pub grid_min: [f32; 3],
+    pub grid_max: [f32; 3],
+    /// Number of cells of the grid along each axis.
+    pub resolution: usize,
+    /// Mass after minus mass before of every cell, with x changing fastest, then y, then z.
+    pub cell_change: Vec<f32>,
+}
+
+/// Progress of [`train`], passed to its callback.
+#[non_exhaustive]
+pub enum TrainEvent<'a> {
+    /// The dataset to train on is loaded.
+    Dataset(&'a Dataset),
+    /// Training reached step `iter`. Not every step is reported.
+    Step {
+        iter: u32,
+        total_steps: u32,
+        splats: &'a Splats,
+    },
+    /// The splats were evaluated on the eval views, at step `iter`.
+    Eval { iter: u32, psnr: f32, ssim: f32 },
+    /// Something is off that doesn't stop training, eg. a dataset likely to train poorly.
+    Warning(&'a str),
+}
+
+/// Set up the default GPU of the system to run on.
+pub async fn init_device() -> Device {
+    Device {
+        inner: brush_render::burn_init_setup().await,
+    }
+}
+
+/// Load the splats of a ply, .splat or .spz file, or of the first such file in a directory or
+/// zip. Animated splats are loaded at their first frame.
+pub async fn load_splats(source: DataSource, device: &Device) -> anyhow::Result<Splats> {
+    let mut vfs = source.into_internal().open().await?.vfs;
+    let path = vfs
+        .file_names()
+        .find(|p| {
+            p.extension().is_some_and(|e| {
+                ["ply", "splat", "spz"].contains(&&*e.to_string_lossy().to_lowercase())
+            })
+        })
+        .context("No ply, .splat or .spz file in the source")?;
+
+    let name = path.display().to_string();
+    let (message, _) =
+        splat_import::load_splat_file(vfs.open_path(&path).await?, &name, device.inner.clone())
+            .await?;
+    Ok(Splats {
+        inner: message.splats,
+    })
+}
+
+/// Load the views of a dataset (COLMAP or nerfstudio, in a directory or zip), using the load
+/// options of `options`.
+pub async fn load_dataset(
+    source: DataSource,
+    options: &TrainOptions,
+    device: &Device,
+) -> anyhow::Result<Dataset> {
+    let vfs = source.into_internal().open().await?.vfs;
+    let args = options.to_internal();
+    let (_, mut datasets) = brush_dataset::load_dataset::<Backend>(
+        vfs,
+        &args.load_config,
+        &args.model_config,
+        &device.inner,
+    )
+    .await?;
+    let mut dataset = None;
+    while let Some(loaded) = datasets.next().await {
+        dataset = Some(loaded?);
+    }
+    let inner = dataset.context("No views in the dataset")?;
+    Ok(Dataset { inner })
+}
+
+/// Train splats on the dataset of `source`, and return the splats of the last step.
+/// `on_event` is called as training progresses, and stops training early by returning
+/// [`ControlFlow::Break`], in which case the latest splats are returned.
+pub async fn train(
+    source: DataSource,
+    options: TrainOptions,
+    device: &Device,
+    mut on_event: impl FnMut(TrainEvent<'_>) -> ControlFlow<()>,
+) -> anyhow::Result<Splats> {
+    let total_steps = options.total_steps;
+    let mut process = start_process(
+        source.into_internal(),
+        options.to_internal(),
+        device.inner.clone(),
+    );
+
+    let mut latest = None;
+    while let Some(message) = process.messages.recv().await {
+        let flow = match message {
+            ProcessMessage::StartLoading { training: false } => {
+                anyhow::bail!("The source holds splats, not a dataset to train on");
+            }
+            ProcessMessage::Error(e) => anyhow::bail!("{e:#}"),
+            ProcessMessage::Warning(warning) => on_event(TrainEvent::Warning(&warning)),
+            ProcessMessage::Dataset { data } => {
+                on_event(TrainEvent::Dataset(&Dataset { inner: data }))
+            }
+            ProcessMessage::TrainStep { splats, iter, .. } => {
+                let splats = Splats { inner: *splats };
+                let flow = on_event(TrainEvent::Step {
+                    iter,
+                    total_steps,
+                    splats: &splats,
+                });
+                latest = Some(splats);
+                flow
+            }
+            ProcessMessage::EvalResult {
+                iter,
+                avg_psnr,
+                avg_ssim,
+                ..
+            } => on_event(TrainEvent::Eval {
+                iter,
+                psnr: avg_psnr,
+                ssim: avg_ssim,
+            }),
+            _ => ControlFlow::Continue(()),
+        };
+        if flow.is_break() {
+            break;
+        }
+    }
+    latest.context("Training stopped before the first step")
+}
+
+/// Render `splats` seen from `camera` to an image of `width` by `height` pixels, with an alpha
+/// channel.
+pub async fn render(splats: &Splats, camera: &Camera, width: u32, height: u32) -> Image {
+    let size = glam::uvec2(width, height);
+    let (img, _) = splats.inner.render(&camera.to_internal(), size, false);
+    Image::from_internal(&brush_train::image::tensor_into_image(
+        img.into_data_async().await,
+    ))
+}
+
+/// Compare two snapshots of splats, eg. of a run before and after changing a setting, by their
+/// counts and where their opacity moved.
+pub async fn diff(before: &Splats, after: &Splats) -> anyhow::Result<SnapshotDiff> {
+    let diff = brush_render::diff::diff_splats(&before.inner, &after.inner, 32)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read back the splats: {e:?}"))?;
+    Ok(SnapshotDiff {
+        splats_before: diff.splats_before,
+        splats_after: diff.splats_after,
+        mass_before: diff.mass_before,
+        mass_after: diff.mass_after,
+        moved_mass: diff.moved_mass,
+        center_shift: diff.center_shift,
+        grid_min: diff.bounds.min().to_array(),
+        grid_max: diff.bounds.max().to_array(),
+        resolution: diff.resolution,
+        cell_change: diff.cell_change,
+    })
+}
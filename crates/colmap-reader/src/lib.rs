@@ -24,6 +24,23 @@ pub enum CameraModel {
 }
 
 impl CameraModel {
+    /// The name COLMAP uses for this model.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SimplePinhole => "SIMPLE_PINHOLE",
+            Self::Pinhole => "PINHOLE",
+            Self::SimpleRadial => "SIMPLE_RADIAL",
+            Self::Radial => "RADIAL",
+            Self::OpenCV => "OPENCV",
+            Self::OpenCvFishEye => "OPENCV_FISHEYE",
+            Self::FullOpenCV => "FULL_OPENCV",
+            Self::Fov => "FOV",
+            Self::SimpleRadialFisheye => "SIMPLE_RADIAL_FISHEYE",
+            Self::RadialFisheye => "RADIAL_FISHEYE",
+            Self::ThinPrismFisheye => "THIN_PRISM_FISHEYE",
+        }
+    }
+
     fn from_id(id: i32) -> Option<Self> {
         match id {
             0 => Some(Self::SimplePinhole),
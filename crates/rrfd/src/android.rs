@@ -1,6 +1,6 @@
 use anyhow::{Context, Result, anyhow};
 use jni::JNIEnv;
-use jni::objects::{GlobalRef, JClass, JStaticMethodID};
+use jni::objects::{GlobalRef, JClass, JStaticMethodID, JValue};
 use jni::signature::Primitive;
 use jni::sys::jint;
 use lazy_static::lazy_static;
@@ -15,6 +15,7 @@ lazy_static! {
     static ref CHANNEL: RwLock<Option<Sender<Option<File>>>> = RwLock::new(None);
     static ref START_FILE_PICKER: RwLock<Option<JStaticMethodID>> = RwLock::new(None);
     static ref FILE_PICKER_CLASS: RwLock<Option<GlobalRef>> = RwLock::new(None);
+    static ref SHARE_SHEET_CLASS: RwLock<Option<GlobalRef>> = RwLock::new(None);
 }
 
 #[allow(unused)]
@@ -30,6 +31,10 @@ pub fn jni_initialize(vm: Arc<jni::JavaVM>) {
     *START_FILE_PICKER
         .write()
         .expect("Failed to write JNI data.") = Some(method);
+    let share_sheet = env.find_class("com/splats/app/ShareSheet").unwrap();
+    *SHARE_SHEET_CLASS
+        .write()
+        .expect("Failed to write JNI data.") = Some(env.new_global_ref(share_sheet).unwrap());
     *VM.write().unwrap() = Some(vm);
 }
 
@@ -82,6 +87,37 @@ pub(crate) async fn pick_file() -> Result<File> {
     file.context("No file selected")
 }
 
+/// Open the share sheet of the system to send `data` to another app, as a file named `name`.
+pub(crate) fn share_file(name: &str, data: &[u8]) -> Result<()> {
+    let java_vm = VM
+        .read()
+        .expect("Failed to read JNI data.")
+        .clone()
+        .context("Java VM isn't initialized")?;
+    let mut env = java_vm.attach_current_thread()?;
+    let class = SHARE_SHEET_CLASS
+        .read()
+        .expect("Failed to read JNI data.")
+        .clone()
+        .context("ShareSheet class isn't initialized")?;
+
+    let name = env.new_string(name)?;
+    let data = env.byte_array_from_slice(data)?;
+    let result = env.call_static_method(
+        <&JClass>::from(class.as_obj()),
+        "share",
+        "(Ljava/lang/String;[B)V",
+        &[JValue::Object(&name), JValue::Object(&data)],
+    );
+    if result.is_err() && env.exception_check()? {
+        // Leave the JVM in a usable state, the error is returned below.
+        env.exception_describe()?;
+        env.exception_clear()?;
+    }
+    result?;
+    Ok(())
+}
+
 #[no_mangle]
 extern "system" fn Java_com_splats_app_FilePicker_onFilePickerResult<'local>(
     _env: JNIEnv<'local>,
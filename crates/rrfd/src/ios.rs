@@ -0,0 +1,12 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// The documents directory of the app. With file sharing enabled in the Info.plist, this is
+/// where files shared with the app in the Files app end up.
+pub fn documents_dir() -> Result<PathBuf> {
+    // Apps are sandboxed, and HOME points to the app's container.
+    let home = std::env::var("HOME").context("No home directory")?;
+    let dir = PathBuf::from(home).join("Documents");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
@@ -1,50 +1,89 @@
 #[cfg(target_os = "android")]
 pub mod android;
+#[cfg(target_os = "ios")]
+mod ios;
+#[cfg(target_os = "ios")]
+pub use ios::documents_dir;
+#[cfg(target_family = "wasm")]
+mod web;
 
 #[allow(unused)]
 use anyhow::Context;
 use anyhow::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub enum FileHandle {
-    #[cfg(not(target_os = "android"))]
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
     Rfd(rfd::FileHandle),
     #[cfg(target_os = "android")]
     Android(tokio::fs::File),
+    #[cfg(target_os = "ios")]
+    Path(PathBuf),
 }
 
 impl FileHandle {
     pub async fn write(&self, data: &[u8]) -> std::io::Result<()> {
         match self {
-            #[cfg(not(target_os = "android"))]
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
             Self::Rfd(file_handle) => file_handle.write(data).await,
             #[cfg(target_os = "android")]
             Self::Android(_) => {
                 let _ = data;
                 unimplemented!("No saving on Android yet.")
             }
+            #[cfg(target_os = "ios")]
+            Self::Path(path) => std::fs::write(path, data),
         }
     }
 
-    pub async fn read(mut self) -> Vec<u8> {
+    pub fn file_name(&self) -> String {
+        match self {
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            Self::Rfd(file_handle) => file_handle.file_name(),
+            #[cfg(target_os = "android")]
+            Self::Android(_) => "file".to_owned(),
+            #[cfg(target_os = "ios")]
+            Self::Path(path) => path
+                .file_name()
+                .map_or_else(|| "file".to_owned(), |n| n.to_string_lossy().into_owned()),
+        }
+    }
+
+    /// Path of the file, on platforms where files have a path that can be opened again.
+    pub fn path(&self) -> Option<PathBuf> {
+        match self {
+            #[cfg(not(any(target_os = "android", target_os = "ios", target_family = "wasm")))]
+            Self::Rfd(file_handle) => Some(file_handle.path().to_path_buf()),
+            #[cfg(target_family = "wasm")]
+            Self::Rfd(_) => None,
+            #[cfg(target_os = "android")]
+            Self::Android(_) => None,
+            #[cfg(target_os = "ios")]
+            Self::Path(path) => Some(path.clone()),
+        }
+    }
+
+    pub async fn read(mut self) -> std::io::Result<Vec<u8>> {
         match &mut self {
-            #[cfg(not(target_os = "android"))]
-            Self::Rfd(file_handle) => file_handle.read().await,
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            Self::Rfd(file_handle) => Ok(file_handle.read().await),
             #[cfg(target_os = "android")]
             Self::Android(file) => {
                 use tokio::io::AsyncReadExt;
 
                 let mut buf = vec![];
-                file.read_to_end(&mut buf).await.unwrap();
-                buf
+                file.read_to_end(&mut buf).await?;
+                Ok(buf)
             }
+            #[cfg(target_os = "ios")]
+            Self::Path(path) => std::fs::read(path),
         }
     }
 }
 
 /// Pick a file and return the name & bytes of the file.
 pub async fn pick_file() -> Result<FileHandle> {
-    #[cfg(not(target_os = "android"))]
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
     {
         let file = rfd::AsyncFileDialog::new()
             .pick_file()
@@ -57,10 +96,17 @@ pub async fn pick_file() -> Result<FileHandle> {
     {
         android::pick_file().await.map(FileHandle::Android)
     }
+
+    #[cfg(target_os = "ios")]
+    {
+        anyhow::bail!(
+            "Picking files isn't supported on iOS yet, share them with Brush in the Files app instead."
+        )
+    }
 }
 
 pub async fn pick_directory() -> Result<PathBuf> {
-    #[cfg(all(not(target_os = "android"), not(target_family = "wasm")))]
+    #[cfg(not(any(target_os = "android", target_os = "ios", target_family = "wasm")))]
     {
         let dir = rfd::AsyncFileDialog::new()
             .pick_folder()
@@ -70,17 +116,18 @@ pub async fn pick_directory() -> Result<PathBuf> {
         Ok(dir.path().to_path_buf())
     }
 
-    #[cfg(any(target_os = "android", target_family = "wasm"))]
+    #[cfg(any(target_os = "android", target_os = "ios", target_family = "wasm"))]
     {
-        unimplemented!("No folder picking on Android yet.")
+        unimplemented!("No folder picking on this platform yet.")
     }
 }
 
 /// Saves data to a file and returns the filename the data was saved too.
 ///
-/// Nb: Does not work on Android currently.
+/// Nb: Does not work on Android currently. On iOS, files are saved to the app's documents, which
+/// are shared in the Files app.
 pub async fn save_file(default_name: &str) -> Result<FileHandle> {
-    #[cfg(not(target_os = "android"))]
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
     {
         let file = rfd::AsyncFileDialog::new()
             .set_file_name(default_name)
@@ -95,4 +142,118 @@ pub async fn save_file(default_name: &str) -> Result<FileHandle> {
         let _ = default_name;
         unimplemented!("No saving on Android yet.")
     }
+
+    #[cfg(target_os = "ios")]
+    {
+        Ok(FileHandle::Path(documents_dir()?.join(default_name)))
+    }
+}
+
+/// Whether [`share_file`] is supported, to only offer sharing where it works.
+pub fn can_share() -> bool {
+    #[cfg(target_family = "wasm")]
+    {
+        web::can_share()
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        cfg!(target_os = "android")
+    }
+}
+
+/// Open the share sheet of the system or browser to send `data` to another app, as a file named
+/// `name`. The returned future finishes once it's shared.
+///
+/// Browsers only open the share sheet shortly after the user clicked something, so this opens it
+/// right away rather than when the future is first polled. Call it from the click, with `data`
+/// ready by then.
+///
+/// Nb: Only works on Android and in browsers with the Web Share API.
+pub fn share_file(name: &str, data: &[u8]) -> impl Future<Output = Result<()>> + 'static {
+    #[cfg(target_family = "wasm")]
+    {
+        let share = web::share_file(name, data);
+        async move { web::shared(share?).await }
+    }
+
+    #[cfg(target_os = "android")]
+    {
+        let shared = android::share_file(name, data);
+        async move { shared }
+    }
+
+    #[cfg(not(any(target_os = "android", target_family = "wasm")))]
+    {
+        let _ = (name, data);
+        async move { Err(anyhow::anyhow!("Sharing isn't supported on this platform")) }
+    }
+}
+
+/// Whether [`reveal_file`] is supported.
+pub fn can_reveal() -> bool {
+    cfg!(not(any(
+        target_os = "android",
+        target_os = "ios",
+        target_family = "wasm"
+    )))
+}
+
+/// Show a file in the file manager of the system, with the file selected where possible.
+pub fn reveal_file(path: &Path) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .spawn()?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt as _;
+
+        // Explorer doesn't parse its arguments like other programs, so pass them as they are.
+        std::process::Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn()?;
+        Ok(())
+    }
+
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "windows",
+        target_os = "android",
+        target_os = "ios",
+        target_family = "wasm"
+    )))]
+    {
+        // Most Linux file managers implement the FileManager1 interface to select a file. If
+        // it's not there, open the folder instead.
+        let uri = format!("file://{}", path.display());
+        let selected = std::process::Command::new("dbus-send")
+            .args([
+                "--session",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{uri}"))
+            .arg("string:")
+            .status()
+            .is_ok_and(|status| status.success());
+        if !selected {
+            let folder = path.parent().context("File has no folder")?;
+            std::process::Command::new("xdg-open").arg(folder).spawn()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "android", target_os = "ios", target_family = "wasm"))]
+    {
+        let _ = path;
+        anyhow::bail!("Showing files isn't supported on this platform")
+    }
 }
@@ -0,0 +1,58 @@
+use anyhow::{Context, Result, anyhow};
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+fn js_error(value: &JsValue) -> anyhow::Error {
+    anyhow!("{value:?}")
+}
+
+/// A method of the navigator of the browser, if the browser supports it.
+fn navigator_method(name: &str) -> Option<(web_sys::Navigator, Function)> {
+    let navigator = web_sys::window()?.navigator();
+    let method = Reflect::get(&navigator, &name.into())
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    Some((navigator, method))
+}
+
+/// Whether the browser has a share sheet, usually only mobile browsers and Safari.
+pub(crate) fn can_share() -> bool {
+    navigator_method("share").is_some()
+}
+
+/// Open the share sheet. Browsers only allow this shortly after the user clicked something, so
+/// this opens it right away, and returns a future for when it's closed.
+pub(crate) fn share_file(name: &str, data: &[u8]) -> Result<JsFuture> {
+    let (navigator, share) =
+        navigator_method("share").context("Sharing isn't supported in this browser")?;
+
+    let parts = Array::of1(&Uint8Array::from(data));
+    let file = web_sys::File::new_with_u8_array_sequence(&parts, name).map_err(|e| js_error(&e))?;
+    let share_data = Object::new();
+    Reflect::set(&share_data, &"files".into(), &Array::of1(&file)).map_err(|e| js_error(&e))?;
+
+    // Some browsers can share links but not files.
+    if let Some((_, can_share)) = navigator_method("canShare") {
+        let can_share = can_share
+            .call1(&navigator, &share_data)
+            .map_err(|e| js_error(&e))?;
+        if !can_share.is_truthy() {
+            anyhow::bail!("This browser can't share files");
+        }
+    }
+
+    let promise: Promise = share
+        .call1(&navigator, &share_data)
+        .map_err(|e| js_error(&e))?
+        .dyn_into()
+        .map_err(|e| js_error(&e))?;
+    Ok(JsFuture::from(promise))
+}
+
+/// Wait for the share sheet opened by [`share_file`] to close.
+pub(crate) async fn shared(share: JsFuture) -> Result<()> {
+    share.await.map_err(|e| js_error(&e))?;
+    Ok(())
+}
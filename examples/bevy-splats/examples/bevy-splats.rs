@@ -0,0 +1,70 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_splats::{
+    SplatDevice, SplatMaterial, SplatPlugin, SplatScene, SplatView, load_ply, splat_quad,
+};
+
+fn setup(
+    mut commands: Commands,
+    device: Res<SplatDevice>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut splat_materials: ResMut<Assets<SplatMaterial>>,
+) {
+    let path = std::env::args()
+        .nth(1)
+        .expect("Pass the ply file to show as argument");
+    let splats = bevy::tasks::block_on(load_ply(&path, &device)).expect("Failed to load splats");
+
+    // Trained splats usually have y pointing down, turn them upright.
+    commands.spawn((
+        SplatScene(splats),
+        Transform::from_rotation(Quat::from_rotation_x(PI)),
+    ));
+
+    // The camera renders the splats, and the quad draws them into the scene at their depth.
+    let view = SplatView::new(&mut images);
+    commands.spawn(splat_quad(&view, &mut meshes, &mut splat_materials));
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_xyz(0.0, 1.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+        view,
+    ));
+
+    // Moves in and out of the splats, to show them hiding each other.
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(0.3, 0.3, 0.3))),
+        MeshMaterial3d(materials.add(Color::srgb(0.8, 0.3, 0.2))),
+        Transform::from_xyz(1.0, 0.0, 0.0),
+        Bob,
+    ));
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(3.0, 5.0, 2.0).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+#[derive(Component)]
+struct Bob;
+
+fn bob(time: Res<Time>, mut meshes: Query<&mut Transform, With<Bob>>) {
+    for mut transform in &mut meshes {
+        transform.translation.z = time.elapsed_secs().sin() * 1.5;
+    }
+}
+
+fn orbit(time: Res<Time>, mut cameras: Query<&mut Transform, With<SplatView>>) {
+    for mut transform in &mut cameras {
+        transform.rotate_around(Vec3::ZERO, Quat::from_rotation_y(time.delta_secs() * 0.3));
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins((DefaultPlugins, SplatPlugin))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (orbit, bob))
+        .run();
+}
@@ -0,0 +1,338 @@
+//! Render splats in a bevy scene. Brush renders the splats on the wgpu device of bevy, to an
+//! image and the depth of the splats in it. A quad covering the screen draws the image at that
+//! depth, so the splats are in front of or behind the meshes of the scene where they should be.
+//!
+//! The depth of a pixel is that of the last splat blended into it, so meshes inside of
+//! semi-transparent splats show through them or not as a whole.
+
+use std::f32::consts::PI;
+
+use bevy::asset::{RenderAssetUsages, embedded_asset};
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::{RenderAdapter, RenderDevice, RenderQueue};
+use bevy::render::texture::GpuImage;
+use bevy::render::view::NoFrustumCulling;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::transform::TransformSystem;
+use brush_dataset::splat_import::load_splat_from_ply;
+use brush_render::camera::Camera as SplatCamera;
+use brush_render::gaussian_splats::Splats;
+use brush_render::{BBase, RenderAux};
+use burn::tensor::{Tensor, TensorPrimitive};
+use burn_fusion::client::FusionClient;
+use burn_wgpu::{Wgpu, WgpuDevice};
+use tokio_stream::StreamExt;
+
+type Base = BBase<f32, i32, u32>;
+
+/// The burn device brush renders with, sharing the wgpu device of bevy.
+#[derive(Resource, Clone)]
+pub struct SplatDevice(pub WgpuDevice);
+
+/// Splats placed in the scene by the transform of this entity. Only one splat scene is
+/// rendered.
+#[derive(Component)]
+pub struct SplatScene(pub Splats<Wgpu>);
+
+/// Renders the splats, as seen by the camera of this entity, to `color`, with their depth in
+/// `depth`. Draw them in the scene with [`splat_quad`].
+#[derive(Component, Clone, ExtractComponent)]
+pub struct SplatView {
+    pub color: Handle<Image>,
+    /// Depth of the splats, as the depth buffer of a bevy camera holds it.
+    pub depth: Handle<Image>,
+    rendered: Option<(Tensor<Wgpu, 3>, Tensor<Wgpu, 3>)>,
+}
+
+impl SplatView {
+    pub fn new(images: &mut Assets<Image>) -> Self {
+        Self {
+            color: images.add(splat_target(UVec2::ONE, TextureFormat::Rgba8UnormSrgb)),
+            depth: images.add(splat_target(UVec2::ONE, TextureFormat::R32Float)),
+            rendered: None,
+        }
+    }
+}
+
+// An image to render splats to. It's resized to the camera rendering to it as needed.
+fn splat_target(size: UVec2, format: TextureFormat) -> Image {
+    // Both formats have 4 bytes per pixel, all zero is transparent, and infinitely far away.
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size.x.max(1),
+            height: size.y.max(1),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        format,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST;
+    image
+}
+
+/// Draws the render of a [`SplatView`] at the depth of the splats.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct SplatMaterial {
+    #[texture(0)]
+    color: Handle<Image>,
+    #[texture(1, sample_type = "float", filterable = false)]
+    depth: Handle<Image>,
+}
+
+impl Material for SplatMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+const SHADER_PATH: &str = "embedded://bevy_splats/splats.wgsl";
+
+/// A quad covering the screen, that draws the splats of `view` into the scene. Only spawn this
+/// for one camera, as every camera draws it.
+pub fn splat_quad(
+    view: &SplatView,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<SplatMaterial>,
+) -> impl Bundle {
+    (
+        Mesh3d(meshes.add(Rectangle::new(2.0, 2.0))),
+        MeshMaterial3d(materials.add(SplatMaterial {
+            color: view.color.clone(),
+            depth: view.depth.clone(),
+        })),
+        // The quad is placed by its shader, not by its transform.
+        NoFrustumCulling,
+        NotShadowCaster,
+    )
+}
+
+/// Load the splats of a ply file. Animated splats are loaded at their first frame.
+pub async fn load_ply(path: &str, device: &SplatDevice) -> anyhow::Result<Splats<Wgpu>> {
+    let data = std::fs::read(path)?;
+    let stream = load_splat_from_ply(std::io::Cursor::new(data), None, device.0.clone());
+    let mut stream = std::pin::pin!(stream);
+
+    let mut splats = None;
+    // The splats are sent as they load, the last message has all of them.
+    while let Some(message) = stream.next().await {
+        let message = message?;
+        if message.meta.current_frame == 0 {
+            splats = Some(message.splats);
+        }
+    }
+    splats.ok_or_else(|| anyhow::anyhow!("No splats in {path}"))
+}
+
+/// Renders [`SplatScene`] for every [`SplatView`].
+pub struct SplatPlugin;
+
+impl Plugin for SplatPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "splats.wgsl");
+        app.add_plugins((
+            ExtractComponentPlugin::<SplatView>::default(),
+            MaterialPlugin::<SplatMaterial>::default(),
+        ))
+        .add_systems(
+            PostUpdate,
+            render_splats.after(TransformSystem::TransformPropagate),
+        );
+    }
+
+    // The wgpu device of bevy is only created once all plugins are built.
+    fn finish(&self, app: &mut App) {
+        let device = {
+            let world = app.world();
+            let adapter = world.resource::<RenderAdapter>();
+            let device = world.resource::<RenderDevice>();
+            let queue = world.resource::<RenderQueue>();
+            brush_render::burn_init_device(
+                (**adapter.0).clone(),
+                device.wgpu_device().clone(),
+                (**queue.0).clone(),
+            )
+        };
+        app.insert_resource(SplatDevice(device));
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(Render, copy_to_targets.in_set(RenderSet::PrepareResources));
+        }
+    }
+}
+
+fn render_splats(
+    mut views: Query<(&mut SplatView, &Camera, &Projection, &GlobalTransform)>,
+    scenes: Query<(&SplatScene, &GlobalTransform)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some((scene, scene_transform)) = scenes.iter().next() else {
+        return;
+    };
+
+    for (mut view, camera, projection, transform) in &mut views {
+        view.rendered = None;
+        let (Some(size), Projection::Perspective(perspective)) =
+            (camera.physical_target_size(), projection)
+        else {
+            continue;
+        };
+        if size.x == 0 || size.y == 0 {
+            continue;
+        }
+        for (target, format) in [
+            (&view.color, TextureFormat::Rgba8UnormSrgb),
+            (&view.depth, TextureFormat::R32Float),
+        ] {
+            if let Some(image) = images.get_mut(target) {
+                if image.size() != size {
+                    *image = splat_target(size, format);
+                }
+            }
+        }
+
+        // The camera in the space of the splats. Bevy cameras look along -z with y up, brush
+        // cameras along +z with y down.
+        let local = scene_transform.affine().inverse() * transform.affine();
+        let (_, rotation, position) = local.to_scale_rotation_translation();
+        let rotation =
+            glam::Quat::from_array(rotation.to_array()) * glam::Quat::from_rotation_x(PI);
+        let fov_y = perspective.fov as f64;
+        let aspect = size.x as f64 / size.y as f64;
+        let fov_x = 2.0 * ((fov_y / 2.0).tan() * aspect).atan();
+        let camera = SplatCamera::new(
+            glam::Vec3::from_array(position.to_array()),
+            rotation,
+            fov_x,
+            fov_y,
+            glam::vec2(0.5, 0.5),
+        );
+
+        let (img, aux) = scene.0.render(&camera, glam::uvec2(size.x, size.y), true);
+        let depth = splat_depth(&scene.0, &camera, &aux, perspective.near);
+        view.rendered = Some((img, depth));
+    }
+}
+
+// The depth of the last splat blended into each pixel, as a bevy camera with a `near` plane
+// writes it to its depth buffer: reversed, near / distance.
+fn splat_depth(
+    splats: &Splats<Wgpu>,
+    camera: &SplatCamera,
+    aux: &RenderAux<Wgpu>,
+    near: f32,
+) -> Tensor<Wgpu, 3> {
+    let [h, w] = aux.final_index.dims();
+    let final_index = aux.final_index.clone().reshape([h * w]);
+    let empty = final_index.clone().lower_equal_elem(0);
+    let isect = (final_index - 1).clamp_min(0);
+    let compact_gid = aux.compact_gid_from_isect.clone().select(0, isect);
+    let global_gid = aux.global_from_compact_gid.clone().select(0, compact_gid);
+    let means = splats.means.val().select(0, global_gid);
+
+    let forward = camera.rotation * glam::Vec3::Z;
+    let forward_tensor =
+        Tensor::<Wgpu, 1>::from_floats(forward.to_array(), &means.device()).unsqueeze::<2>();
+    let distance =
+        (means * forward_tensor).sum_dim(1).reshape([h * w]) - forward.dot(camera.position);
+    let depth = distance.clamp_min(near).recip() * near;
+    depth.mask_fill(empty, 0.0).reshape([h, w, 1])
+}
+
+fn copy_to_targets(
+    views: Query<&SplatView>,
+    images: Res<RenderAssets<GpuImage>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    let mut encoder =
+        device
+            .wgpu_device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Splat copy encoder"),
+            });
+    for view in &views {
+        let Some((img, depth)) = view.rendered.clone() else {
+            continue;
+        };
+        for (tensor, target) in [(img, &view.color), (depth, &view.depth)] {
+            let Some(target) = images.get(target) else {
+                continue;
+            };
+            let [height, width, _] = tensor.dims();
+            // Skip renders until the resized image is on the GPU.
+            if target.texture.width() == width as u32 && target.texture.height() == height as u32 {
+                copy_to_texture(tensor, &target.texture, &mut encoder);
+            }
+        }
+    }
+    queue.submit([encoder.finish()]);
+}
+
+// Copy a render of 4 bytes per pixel (packed 8 bit colors, or a float) to a texture of the same
+// size.
+fn copy_to_texture(
+    img: Tensor<Wgpu, 3>,
+    texture: &wgpu::Texture,
+    encoder: &mut wgpu::CommandEncoder,
+) {
+    let [height, width, c] = img.dims();
+    let padded_shape = vec![height, width.div_ceil(64) * 64, c];
+
+    let img_prim = img.into_primitive().tensor();
+    let fusion_client = img_prim.client.clone();
+    let img = fusion_client.resolve_tensor_float::<Base>(img_prim);
+    let img: Tensor<Base, 3> = Tensor::from_primitive(TensorPrimitive::Float(img));
+
+    // The bytes_per_row needs to be divisible by 256 in WebGPU, so 4 bytes per pixel means
+    // width needs to be divisible by 64.
+    let img = if width % 64 != 0 {
+        let padded: Tensor<Base, 3> = Tensor::zeros(&padded_shape, &img.device());
+        padded.slice_assign([0..height, 0..width], img)
+    } else {
+        img
+    };
+    let img = img.into_primitive().tensor();
+
+    let client = &img.client;
+    let resource = client.get_resource(img.handle.clone().binding());
+    // Submit the render before copying its result.
+    client.flush();
+
+    encoder.copy_buffer_to_texture(
+        wgpu::TexelCopyBufferInfo {
+            buffer: &resource.resource().buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: resource.resource().offset(),
+                bytes_per_row: Some(4 * padded_shape[1] as u32),
+                rows_per_image: None,
+            },
+        },
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+    );
+}
@@ -0,0 +1,35 @@
+// Draws the render of the splats over the whole screen, at the depth of the splats, so the depth
+// test puts them in front of or behind the meshes of the scene.
+
+@group(2) @binding(0) var color: texture_2d<f32>;
+@group(2) @binding(1) var depth: texture_2d<f32>;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    // The quad spans -1 to 1, which covers the screen in clip space, whatever its transform.
+    var out: VertexOutput;
+    out.position = vec4(vertex.position.xy, 0.0, 1.0);
+    return out;
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> FragmentOutput {
+    let pixel = vec2<i32>(in.position.xy);
+    var out: FragmentOutput;
+    out.color = textureLoad(color, pixel, 0);
+    out.depth = textureLoad(depth, pixel, 0).r;
+    return out;
+}
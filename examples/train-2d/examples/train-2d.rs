@@ -123,6 +123,8 @@ impl App {
             camera,
             image: Arc::new(image),
             img_type: ViewImageType::Alpha,
+            camera_group: 0,
+            sparse_points: Arc::new(vec![]),
         };
 
         let (sender, receiver) = tokio::sync::mpsc::channel(32);